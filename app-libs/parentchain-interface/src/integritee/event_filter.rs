@@ -21,7 +21,8 @@ use itp_api_client_types::Events;
 
 use itp_types::{
 	parentchain::{
-		BalanceTransfer, ExtrinsicFailed, ExtrinsicStatus, ExtrinsicSuccess, FilterEvents,
+		BalanceTransfer, EnclaveUpgradeScheduled, ExtrinsicFailed, ExtrinsicStatus,
+		ExtrinsicSuccess, FilterEvents, StateMigrationScheduled,
 	},
 	H256,
 };
@@ -82,4 +83,38 @@ impl FilterEvents for FilterableEvents {
 			})
 			.collect())
 	}
+
+	fn get_scheduled_enclave_upgrades(
+		&self,
+	) -> core::result::Result<Vec<EnclaveUpgradeScheduled>, Self::Error> {
+		Ok(self
+			.to_events()
+			.iter()
+			.flatten()
+			.filter_map(|ev| match ev.as_event::<EnclaveUpgradeScheduled>() {
+				Ok(maybe_event) => maybe_event,
+				Err(e) => {
+					log::error!("Could not decode event: {:?}", e);
+					None
+				},
+			})
+			.collect())
+	}
+
+	fn get_scheduled_state_migrations(
+		&self,
+	) -> core::result::Result<Vec<StateMigrationScheduled>, Self::Error> {
+		Ok(self
+			.to_events()
+			.iter()
+			.flatten()
+			.filter_map(|ev| match ev.as_event::<StateMigrationScheduled>() {
+				Ok(maybe_event) => maybe_event,
+				Err(e) => {
+					log::error!("Could not decode event: {:?}", e);
+					None
+				},
+			})
+			.collect())
+	}
 }
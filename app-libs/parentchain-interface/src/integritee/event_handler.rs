@@ -20,12 +20,17 @@ use codec::Encode;
 pub use ita_sgx_runtime::{Balance, Index};
 use ita_stf::{Getter, TrustedCall, TrustedCallSigned};
 use itc_parentchain_indirect_calls_executor::error::Error;
+use itp_enclave_upgrade::{ScheduledUpgrade, GLOBAL_UPGRADE_COORDINATOR};
 use itp_stf_primitives::{traits::IndirectExecutor, types::TrustedOperation};
-use itp_types::parentchain::{
-	AccountId, FilterEvents, HandleParentchainEvents, ParentchainError, ParentchainId,
+use itp_types::{
+	parentchain::{
+		AccountId, FilterEvents, HandleParentchainEvents, ParentchainError, ParentchainId,
+	},
+	BlockNumber,
 };
 use itp_utils::hex::hex_encode;
 use log::*;
+use std::vec::Vec;
 
 pub struct ParentchainEventHandler {}
 
@@ -53,6 +58,27 @@ impl ParentchainEventHandler {
 
 		Ok(())
 	}
+
+	fn schedule_state_migration<Executor: IndirectExecutor<TrustedCallSigned, Error>>(
+		executor: &Executor,
+		migration: Vec<u8>,
+		activation_block: BlockNumber,
+	) -> Result<(), Error> {
+		let shard = executor.get_default_shard();
+		let trusted_call = TrustedCall::state_migration_schedule(
+			executor.get_enclave_account()?,
+			migration,
+			activation_block,
+		);
+		let signed_trusted_call = executor.sign_call_with_self(&trusted_call, &shard)?;
+		let trusted_operation =
+			TrustedOperation::<TrustedCallSigned, Getter>::indirect_call(signed_trusted_call);
+
+		let encrypted_trusted_call = executor.encrypt(&trusted_operation.encode())?;
+		executor.submit_trusted_call(shard, encrypted_trusted_call);
+
+		Ok(())
+	}
 }
 
 impl<Executor> HandleParentchainEvents<Executor, TrustedCallSigned, Error>
@@ -82,6 +108,40 @@ where
 				})
 				.map_err(|_| ParentchainError::ShieldFundsFailure)?;
 		}
+
+		let shard = executor.get_default_shard();
+		if let Ok(upgrades) = events.get_scheduled_enclave_upgrades() {
+			upgrades.iter().filter(|&event| event.shard == shard).try_for_each(|event| {
+				info!("found scheduled enclave upgrade: {}", event);
+				GLOBAL_UPGRADE_COORDINATOR
+					.schedule(ScheduledUpgrade {
+						shard: event.shard,
+						mrenclave: event.mrenclave,
+						activation_block: event.activation_block,
+					})
+					.map_err(|e| {
+						error!("Could not schedule enclave upgrade: {:?}", e);
+						ParentchainError::EnclaveUpgradeFailure
+					})
+			})?;
+		}
+		if let Ok(migrations) = events.get_scheduled_state_migrations() {
+			migrations
+				.into_iter()
+				.filter(|event| event.shard == shard)
+				.try_for_each(|event| {
+					info!("found scheduled state migration: {}", event);
+					Self::schedule_state_migration(
+						executor,
+						event.migration,
+						event.activation_block,
+					)
+					.map_err(|e| {
+						error!("Could not schedule state migration: {:?}", e);
+						ParentchainError::StateMigrationFailure
+					})
+				})?;
+		}
 		Ok(())
 	}
 }
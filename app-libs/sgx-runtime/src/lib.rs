@@ -30,6 +30,7 @@
 
 #[cfg(feature = "evm")]
 mod evm;
+pub mod order_book_events;
 
 #[cfg(feature = "evm")]
 pub use evm::{
@@ -71,7 +72,8 @@ pub use frame_support::{
 	StorageValue,
 };
 use itp_sgx_runtime_primitives::types::Moment;
-pub use pallet_balances::Call as BalancesCall;
+pub use order_book_events::Event as OrderBookEvent;
+pub use pallet_balances::{Call as BalancesCall, Event as BalancesEvent};
 pub use pallet_parentchain::Call as ParentchainPalletCall;
 pub use pallet_timestamp::Call as TimestampCall;
 #[cfg(any(feature = "std", test))]
@@ -281,6 +283,10 @@ impl pallet_parentchain::Config<crate::ParentchainInstanceTargetB> for Runtime {
 	type Moment = Moment;
 }
 
+impl order_book_events::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+}
+
 // The plain sgx-runtime without the `evm-pallet`
 #[cfg(not(feature = "evm"))]
 construct_runtime!(
@@ -298,6 +304,8 @@ construct_runtime!(
 		ParentchainIntegritee: pallet_parentchain::<Instance1>::{Pallet, Call, Event<T>} = 10,
 		ParentchainTargetA: pallet_parentchain::<Instance2>::{Pallet, Call, Event<T>} = 11,
 		ParentchainTargetB: pallet_parentchain::<Instance3>::{Pallet, Call, Event<T>} = 12,
+
+		OrderBookEvents: order_book_events::{Pallet, Event<T>} = 30,
 	}
 );
 
@@ -323,6 +331,8 @@ construct_runtime!(
 		ParentchainTargetB: pallet_parentchain::<Instance3>::{Pallet, Call, Event<T>} = 12,
 
 		Evm: pallet_evm::{Pallet, Call, Storage, Config, Event<T>} = 20,
+
+		OrderBookEvents: order_book_events::{Pallet, Event<T>} = 30,
 	}
 );
 
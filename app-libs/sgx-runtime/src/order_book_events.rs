@@ -0,0 +1,53 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Events of the confidential order book.
+//!
+//! The order book itself is a module of the STF. This pallet only adds its events to the
+//! runtime events, so they are recorded and disclosed like the events of any other pallet.
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use crate::Balance;
+	use frame_support::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+	}
+
+	#[pallet::event]
+	pub enum Event<T: Config> {
+		/// An order has been placed in a market.
+		OrderPlaced { market: u32, order_id: u64, owner: T::AccountId },
+		/// An order has been cancelled by its owner, its funds have been released.
+		OrderCancelled { market: u32, order_id: u64, owner: T::AccountId },
+		/// An order has been (partially) filled in a clearing round at the given price.
+		OrderSettled {
+			market: u32,
+			order_id: u64,
+			owner: T::AccountId,
+			amount: Balance,
+			price: Balance,
+		},
+	}
+}
@@ -0,0 +1,251 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Differential privacy for the public getters of shard-wide aggregates.
+//!
+//! Root can configure Laplace noise and a query budget per shard. The noise of an aggregate is
+//! derived from a secret seed and the current window of sidechain blocks, so repeating a query
+//! within a window, by another querier or on another validateer, returns the same answer and
+//! averaging tells nothing. Every querier is answered only `budget` distinct aggregates per
+//! window, which limits differencing of e.g. holder counts at neighbouring thresholds, without
+//! one querier using up the budget of all others. A querier opens an aggregate for the window
+//! with a trusted call, which counts it against the budget of the querier in the state, so
+//! neither a restart nor another validateer grants a fresh budget. The aggregate is then answered
+//! by a trusted getter of the querier.
+
+use crate::helpers::{
+	get_storage_map, get_storage_value, kill_storage_value, put_storage_map, put_storage_value,
+};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::{Balance, Balances, Runtime, System};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use itp_types::{BlockNumber, H256};
+use log::*;
+use sp_io::hashing::blake2_256;
+use std::{format, vec::Vec};
+
+pub(crate) const AGGREGATE_PRIVACY: &str = "AggregatePrivacy";
+const CONFIG: &str = "Config";
+const QUERY_LEDGERS: &str = "QueryLedgers";
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+	TotalIssuance,
+	AccountCount,
+	/// Number of accounts with at least the given free balance.
+	AccountsHoldingAtLeast(Balance),
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct AggregatePrivacy {
+	/// Scale of the Laplace noise added to balance aggregates.
+	pub balance_noise: Balance,
+	/// Scale of the Laplace noise added to account counts.
+	pub count_noise: u32,
+	/// Number of distinct aggregates answered per querier and window.
+	pub budget: u32,
+	/// Length of a window in sidechain blocks.
+	pub window: BlockNumber,
+}
+
+/// Replaces the privacy configuration and its seed, `None` answers aggregates exactly. The
+/// budgets start over, as the ledgers of the previous seed are stale. `entropy` must be unknown
+/// to root and the querying parties, i.e. the randomness of the enclave.
+pub fn configure(config: Option<AggregatePrivacy>, entropy: H256) -> StfResult<()> {
+	match config {
+		Some(config) => {
+			if config.window == 0 {
+				return Err(StfError::Dispatch("aggregate privacy window must not be zero".into()))
+			}
+			let previous_seed = privacy().map(|(_, seed)| seed).unwrap_or_default();
+			let seed: H256 =
+				blake2_256(&(previous_seed, entropy, System::block_number()).encode()).into();
+			put_storage_value(AGGREGATE_PRIVACY, CONFIG, &(config, seed));
+		},
+		None => kill_storage_value(AGGREGATE_PRIVACY, CONFIG),
+	}
+	Ok(())
+}
+
+/// Opens the aggregate for the getter of the querier in the current window. Fails if the query
+/// budget of the querier is used up for the window.
+pub fn open(querier: &AccountId, aggregate: Aggregate) -> StfResult<()> {
+	let (config, seed) = match privacy() {
+		Some(privacy) => privacy,
+		// Exact aggregates are always answered.
+		None => return Ok(()),
+	};
+	let window = System::block_number() / config.window;
+	let mut ledger = ledger_of(querier);
+	if !ledger.admit(seed, window, &aggregate, config.budget) {
+		return Err(StfError::Dispatch(format!(
+			"aggregate query budget of window {} is used up, rejecting {:?}",
+			window, aggregate
+		)))
+	}
+	put_storage_map(
+		AGGREGATE_PRIVACY,
+		QUERY_LEDGERS,
+		querier,
+		&StorageHasher::Blake2_128Concat,
+		&ledger,
+	);
+	Ok(())
+}
+
+/// The value of the aggregate with the configured noise, `None` if the querier has not opened the
+/// aggregate in the current window.
+pub fn query(querier: &AccountId, aggregate: Aggregate) -> Option<u128> {
+	let exact = exact_value(&aggregate);
+	let (config, seed) = match privacy() {
+		Some(privacy) => privacy,
+		None => return Some(exact),
+	};
+	let window = System::block_number() / config.window;
+	if !ledger_of(querier).is_open(seed, window, &aggregate) {
+		debug!("Aggregate {:?} has not been opened in window {}", aggregate, window);
+		return None
+	}
+	let scale = match aggregate {
+		Aggregate::TotalIssuance => config.balance_noise,
+		Aggregate::AccountCount | Aggregate::AccountsHoldingAtLeast(_) =>
+			config.count_noise as u128,
+	};
+	Some(add_laplace_noise(exact, scale, blake2_256(&(seed, window, &aggregate).encode())))
+}
+
+fn exact_value(aggregate: &Aggregate) -> u128 {
+	match aggregate {
+		Aggregate::TotalIssuance => Balances::total_issuance(),
+		Aggregate::AccountCount => frame_system::Account::<Runtime>::iter().count() as u128,
+		Aggregate::AccountsHoldingAtLeast(threshold) => frame_system::Account::<Runtime>::iter()
+			.filter(|(_, info)| info.data.free >= *threshold)
+			.count() as u128,
+	}
+}
+
+fn privacy() -> Option<(AggregatePrivacy, H256)> {
+	get_storage_value(AGGREGATE_PRIVACY, CONFIG)
+}
+
+fn ledger_of(querier: &AccountId) -> QueryLedger {
+	get_storage_map(AGGREGATE_PRIVACY, QUERY_LEDGERS, querier, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+/// Adds Laplace noise of the given scale by inverse transform of the uniform `sample`.
+fn add_laplace_noise(value: u128, scale: u128, sample: [u8; 32]) -> u128 {
+	if scale == 0 {
+		return value
+	}
+	let mut bits = [0u8; 8];
+	bits.copy_from_slice(&sample[..8]);
+	// Uniform in the open interval (-0.5, 0.5), using the 53 bits an f64 represents exactly.
+	let uniform = ((u64::from_le_bytes(bits) >> 11) as f64 + 0.5) / (1u64 << 53) as f64 - 0.5;
+	let noise = -(scale as f64) * uniform.signum() * (1.0 - 2.0 * uniform.abs()).ln();
+	let noisy = value as f64 + noise;
+	if noisy <= 0.0 {
+		0
+	} else {
+		noisy.round() as u128
+	}
+}
+
+/// The aggregates a querier opened in a window under the seed of the privacy configuration.
+#[derive(Encode, Decode, Default, Clone)]
+struct QueryLedger {
+	seed: H256,
+	window: BlockNumber,
+	opened: Vec<Aggregate>,
+}
+
+impl QueryLedger {
+	/// Opening an aggregate again is always admitted, it returns the same noisy value.
+	fn admit(
+		&mut self,
+		seed: H256,
+		window: BlockNumber,
+		aggregate: &Aggregate,
+		budget: u32,
+	) -> bool {
+		if self.seed != seed || self.window != window {
+			self.seed = seed;
+			self.window = window;
+			self.opened.clear();
+		}
+		if self.opened.contains(aggregate) {
+			return true
+		}
+		if self.opened.len() >= budget as usize {
+			return false
+		}
+		self.opened.push(aggregate.clone());
+		true
+	}
+
+	fn is_open(&self, seed: H256, window: BlockNumber, aggregate: &Aggregate) -> bool {
+		self.seed == seed && self.window == window && self.opened.contains(aggregate)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn laplace_noise_is_zero_at_the_median_and_clamped_at_zero() {
+		let mut median = [0u8; 32];
+		median[7] = 0x80;
+
+		assert_eq!(add_laplace_noise(1000, 0, [0u8; 32]), 1000);
+		assert_eq!(add_laplace_noise(1000, 50, median), 1000);
+		assert_eq!(add_laplace_noise(1000, 50, [0u8; 32]), 0);
+		assert!(add_laplace_noise(1000, 50, [0xffu8; 32]) > 1000);
+	}
+
+	#[test]
+	fn ledger_admits_distinct_aggregates_up_to_the_budget_per_window() {
+		let mut ledger = QueryLedger::default();
+		let seed = H256::repeat_byte(1);
+
+		assert!(ledger.admit(seed, 0, &Aggregate::AccountsHoldingAtLeast(10), 2));
+		assert!(ledger.admit(seed, 0, &Aggregate::AccountsHoldingAtLeast(11), 2));
+		assert!(!ledger.admit(seed, 0, &Aggregate::AccountsHoldingAtLeast(12), 2));
+		assert!(ledger.admit(seed, 0, &Aggregate::AccountsHoldingAtLeast(10), 2));
+		assert!(ledger.is_open(seed, 0, &Aggregate::AccountsHoldingAtLeast(11)));
+		assert!(!ledger.is_open(seed, 0, &Aggregate::AccountsHoldingAtLeast(12)));
+
+		assert!(ledger.admit(seed, 1, &Aggregate::AccountsHoldingAtLeast(12), 2));
+		assert!(!ledger.is_open(seed, 1, &Aggregate::AccountsHoldingAtLeast(10)));
+	}
+
+	#[test]
+	fn ledger_of_a_previous_seed_is_stale() {
+		let mut ledger = QueryLedger::default();
+		let seed = H256::repeat_byte(1);
+		let next_seed = H256::repeat_byte(2);
+
+		assert!(ledger.admit(seed, 0, &Aggregate::AccountCount, 1));
+		assert!(!ledger.is_open(next_seed, 0, &Aggregate::AccountCount));
+		assert!(ledger.admit(next_seed, 0, &Aggregate::TotalIssuance, 1));
+		assert!(ledger.is_open(next_seed, 0, &Aggregate::TotalIssuance));
+	}
+}
@@ -0,0 +1,282 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Sealed-bid, first-price auctions.
+//!
+//! Bids are reserved from the bidder's balance and stay confidential until the auction closes.
+//! The auction is settled at the start of the first sidechain block after the close block, or
+//! by anyone with a call if that did not happen: the highest bid at or above the reserve price
+//! wins (ties go to the earlier bid), the winner pays its bid to the seller, all other bids are
+//! released, and the outcome is published to the parentchain.
+//!
+//! Bids of bidders that are not compliant with the seller for their amount at settlement are
+//! released without taking part.
+
+use crate::{
+	compliance,
+	helpers::{
+		get_storage_map, get_storage_value, kill_storage_map, put_storage_map, put_storage_value,
+	},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::{BalanceStatus, ReservableCurrency};
+use ita_sgx_runtime::{Balance, Balances, System};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use itp_types::{BlockNumber, H256};
+use itp_utils::stringify::account_id_to_string;
+use log::*;
+use sp_io::hashing::blake2_256;
+use std::{format, vec::Vec};
+
+pub(crate) const AUCTION: &str = "Auction";
+const NEXT_AUCTION_ID: &str = "NextAuctionId";
+const AUCTIONS: &str = "Auctions";
+const BIDS: &str = "Bids";
+const OUTCOMES: &str = "Outcomes";
+const OPEN_AUCTIONS: &str = "OpenAuctions";
+
+/// Maximum length of the opaque item description of an auction.
+pub const MAX_ITEM_LEN: usize = 256;
+
+pub type AuctionId = u32;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Auction {
+	pub seller: AccountId,
+	/// Opaque description of the auctioned item, e.g. an NFT or tender identifier.
+	pub item: Vec<u8>,
+	pub reserve_price: Balance,
+	/// Last sidechain block at which bids are accepted.
+	pub close: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Bid {
+	pub bidder: AccountId,
+	pub amount: Balance,
+}
+
+/// Outcome of a settled auction, as published to the parentchain.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct AuctionOutcome {
+	pub auction_id: AuctionId,
+	pub item: Vec<u8>,
+	/// Winning bidder and price, `None` if no bid reached the reserve price.
+	pub winner: Option<(AccountId, Balance)>,
+	pub number_of_bids: u32,
+}
+
+pub fn auction(auction_id: AuctionId) -> Option<Auction> {
+	get_storage_map(AUCTION, AUCTIONS, &auction_id, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn outcome(auction_id: AuctionId) -> Option<AuctionOutcome> {
+	get_storage_map(AUCTION, OUTCOMES, &auction_id, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn bid_of(auction_id: AuctionId, who: &AccountId) -> Option<Balance> {
+	bids(auction_id).into_iter().find(|b| &b.bidder == who).map(|b| b.amount)
+}
+
+pub fn create_auction(
+	seller: AccountId,
+	item: Vec<u8>,
+	reserve_price: Balance,
+	close: BlockNumber,
+) -> StfResult<AuctionId> {
+	if item.len() > MAX_ITEM_LEN {
+		return Err(StfError::Dispatch(format!("item exceeds {} bytes", MAX_ITEM_LEN)))
+	}
+	if close <= System::block_number() {
+		return Err(StfError::Dispatch("auction close must be in the future".into()))
+	}
+	let auction_id: AuctionId = get_storage_value(AUCTION, NEXT_AUCTION_ID).unwrap_or_default();
+	put_storage_value(AUCTION, NEXT_AUCTION_ID, &(auction_id + 1));
+	let mut open_auctions = open_auctions();
+	open_auctions.push(auction_id);
+	put_storage_value(AUCTION, OPEN_AUCTIONS, &open_auctions);
+	put_storage_map(
+		AUCTION,
+		AUCTIONS,
+		&auction_id,
+		&StorageHasher::Blake2_128Concat,
+		&Auction { seller, item, reserve_price, close },
+	);
+	Ok(auction_id)
+}
+
+/// Places a bid or replaces the bidder's previous bid, which then loses its time priority.
+pub fn place_bid(bidder: AccountId, auction_id: AuctionId, amount: Balance) -> StfResult<()> {
+	let auction = auction(auction_id).ok_or_else(|| unknown_auction(auction_id))?;
+	if System::block_number() > auction.close {
+		return Err(StfError::Dispatch(format!("auction {} is closed", auction_id)))
+	}
+	if bidder == auction.seller {
+		return Err(StfError::Dispatch("seller cannot bid on own auction".into()))
+	}
+	compliance::ensure_compliant(&[&bidder, &auction.seller], amount)?;
+
+	let mut bids = bids(auction_id);
+	let previous = bids.iter().position(|b| b.bidder == bidder).map(|i| bids.remove(i));
+	if let Some(ref previous) = previous {
+		Balances::unreserve(&bidder, previous.amount);
+	}
+	if let Err(e) = Balances::reserve(&bidder, amount) {
+		if let Some(previous) = previous {
+			// Re-reserving the amount that has just been released cannot fail.
+			let _ = Balances::reserve(&bidder, previous.amount);
+		}
+		debug!("could not reserve bid of {}: {:?}", account_id_to_string(&bidder), e);
+		return Err(StfError::MissingFunds)
+	}
+	bids.push(Bid { bidder, amount });
+	put_storage_map(AUCTION, BIDS, &auction_id, &StorageHasher::Blake2_128Concat, &bids);
+	Ok(())
+}
+
+/// Settles a closed auction and returns the outcome to be published.
+pub fn settle(auction_id: AuctionId) -> StfResult<AuctionOutcome> {
+	let auction = auction(auction_id).ok_or_else(|| unknown_auction(auction_id))?;
+	if System::block_number() <= auction.close {
+		return Err(StfError::Dispatch(format!("auction {} is still open", auction_id)))
+	}
+	if outcome(auction_id).is_some() {
+		return Err(StfError::Dispatch(format!("auction {} is already settled", auction_id)))
+	}
+
+	let bids = bids(auction_id);
+	let eligible_bids: Vec<Bid> = bids
+		.iter()
+		.filter(|b| compliance::ensure_compliant(&[&b.bidder, &auction.seller], b.amount).is_ok())
+		.cloned()
+		.collect();
+	let winner = determine_winner(&eligible_bids, auction.reserve_price).cloned();
+	for bid in bids.iter() {
+		match winner {
+			Some(ref w) if w.bidder == bid.bidder => {
+				Balances::repatriate_reserved(
+					&bid.bidder,
+					&auction.seller,
+					bid.amount,
+					BalanceStatus::Free,
+				)
+				.map_err(|e| StfError::Dispatch(format!("Auction payment error: {:?}", e)))?;
+			},
+			_ => {
+				Balances::unreserve(&bid.bidder, bid.amount);
+			},
+		}
+	}
+
+	let outcome = AuctionOutcome {
+		auction_id,
+		item: auction.item,
+		winner: winner.map(|w| (w.bidder, w.amount)),
+		number_of_bids: bids.len() as u32,
+	};
+	put_storage_map(AUCTION, OUTCOMES, &auction_id, &StorageHasher::Blake2_128Concat, &outcome);
+	kill_storage_map(AUCTION, BIDS, &auction_id, &StorageHasher::Blake2_128Concat);
+	remove_open_auction(auction_id);
+	Ok(outcome)
+}
+
+/// Settles all auctions that closed before the current block and returns their outcomes to be
+/// published. Called once at the start of every sidechain block.
+pub fn on_initialize() -> Vec<AuctionOutcome> {
+	let now = System::block_number();
+	let mut outcomes = Vec::new();
+	for auction_id in open_auctions() {
+		match auction(auction_id) {
+			Some(auction) if auction.close >= now => continue,
+			_ => {},
+		}
+		match settle(auction_id) {
+			Ok(outcome) => outcomes.push(outcome),
+			Err(e) => {
+				// Not retried in later blocks, the auction can still be settled with a call.
+				warn!("could not settle auction {}: {:?}", auction_id, e);
+				remove_open_auction(auction_id);
+			},
+		}
+	}
+	outcomes
+}
+
+/// Topic under which the outcome of an auction is published on the parentchain.
+pub fn outcome_topic(auction_id: AuctionId) -> H256 {
+	blake2_256(&(AUCTION, auction_id).encode()).into()
+}
+
+/// Highest bid at or above the reserve price. Among equal bids, the earliest one wins.
+pub fn determine_winner(bids: &[Bid], reserve_price: Balance) -> Option<&Bid> {
+	bids.iter()
+		.filter(|b| b.amount >= reserve_price)
+		.fold(None, |best: Option<&Bid>, bid| match best {
+			Some(b) if b.amount >= bid.amount => Some(b),
+			_ => Some(bid),
+		})
+}
+
+fn bids(auction_id: AuctionId) -> Vec<Bid> {
+	get_storage_map(AUCTION, BIDS, &auction_id, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+fn open_auctions() -> Vec<AuctionId> {
+	get_storage_value(AUCTION, OPEN_AUCTIONS).unwrap_or_default()
+}
+
+fn remove_open_auction(auction_id: AuctionId) {
+	let mut open_auctions = open_auctions();
+	open_auctions.retain(|id| *id != auction_id);
+	put_storage_value(AUCTION, OPEN_AUCTIONS, &open_auctions);
+}
+
+fn unknown_auction(auction_id: AuctionId) -> StfError {
+	StfError::Dispatch(format!("unknown auction {}", auction_id))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bid(id: u8, amount: Balance) -> Bid {
+		Bid { bidder: AccountId::new([id; 32]), amount }
+	}
+
+	#[test]
+	fn highest_bid_wins() {
+		let bids = vec![bid(1, 10), bid(2, 30), bid(3, 20)];
+		assert_eq!(Some(&bids[1]), determine_winner(&bids, 0));
+	}
+
+	#[test]
+	fn earlier_bid_wins_tie() {
+		let bids = vec![bid(1, 10), bid(2, 30), bid(3, 30)];
+		assert_eq!(Some(&bids[1]), determine_winner(&bids, 0));
+	}
+
+	#[test]
+	fn no_winner_below_reserve_price() {
+		let bids = vec![bid(1, 10), bid(2, 30)];
+		assert_eq!(None, determine_winner(&bids, 31));
+	}
+}
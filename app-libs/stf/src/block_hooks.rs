@@ -0,0 +1,129 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Hooks executed at sidechain block boundaries.
+//!
+//! The block author runs these hooks on the state before executing the trusted calls of a new
+//! block. Other validateers receive the resulting changes as part of the block's state diff,
+//! so the hooks do not need to be re-run on import. Parentchain calls of the hooks are sent
+//! along with those of the block's trusted calls.
+
+use crate::{
+	auction::{self, AuctionOutcome},
+	event_disclosure,
+	feature_flags::{self, Feature},
+	order_book, payment_channel,
+	voting::{self, PollResult},
+};
+use codec::Encode;
+use itp_node_api::metadata::{provider::AccessNodeMetadata, NodeMetadataTrait};
+use itp_node_api_metadata::pallet_enclave_bridge::EnclaveBridgeCallIndexes;
+use itp_types::{parentchain::ParentchainCall, OpaqueCall, H256};
+use log::*;
+use sp_io::hashing::blake2_256;
+use std::{sync::Arc, vec, vec::Vec};
+
+/// Must be called within the externalities of the state a new sidechain block is proposed on,
+/// after the new block number has been set. The hooks of disabled features are skipped, except
+/// for the auction settlement, which releases the bids.
+pub fn on_initialize<NodeMetadataRepository>(
+	calls: &mut Vec<ParentchainCall>,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) where
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	event_disclosure::on_initialize();
+	settle_ended_auctions(calls, node_metadata_repo.clone());
+	if feature_flags::is_enabled(Feature::OrderBook) {
+		order_book::on_initialize();
+	}
+	if feature_flags::is_enabled(Feature::PaymentChannel) {
+		payment_channel::on_initialize();
+	}
+	if feature_flags::is_enabled(Feature::Voting) {
+		reveal_poll_results(calls, node_metadata_repo);
+	}
+}
+
+/// Settles the auctions that have closed and publishes their outcomes.
+fn settle_ended_auctions<NodeMetadataRepository>(
+	calls: &mut Vec<ParentchainCall>,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) where
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	// Auctions are only settled once their outcome can be published.
+	let call_indexes = match node_metadata_repo.get_from_metadata(|m| m.publish_hash_call_indexes())
+	{
+		Ok(Ok(call_indexes)) => call_indexes,
+		e => {
+			warn!("Not settling auctions, publish_hash call is unavailable: {:?}", e);
+			return
+		},
+	};
+	for outcome in auction::on_initialize() {
+		std::println!("⣿STF⣿ 🔨 settled auction {}: {:?}", outcome.auction_id, outcome.winner);
+		calls.push(publish_auction_outcome(call_indexes, &outcome));
+	}
+}
+
+/// Publishes the signed results and ballot commitments of the polls that have closed.
+fn reveal_poll_results<NodeMetadataRepository>(
+	calls: &mut Vec<ParentchainCall>,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) where
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	// Polls are only revealed once their result can be published.
+	let call_indexes = match node_metadata_repo.get_from_metadata(|m| m.publish_hash_call_indexes())
+	{
+		Ok(Ok(call_indexes)) => call_indexes,
+		e => {
+			warn!("Not revealing poll results, publish_hash call is unavailable: {:?}", e);
+			return
+		},
+	};
+	for result in voting::on_initialize() {
+		std::println!("⣿STF⣿ 🗳 revealing result of poll {}: {:?}", result.poll_id, result.tally);
+		calls.push(publish_poll_result(call_indexes, &result));
+	}
+}
+
+/// Parentchain call publishing the outcome of a settled auction.
+pub(crate) fn publish_auction_outcome(
+	call_indexes: [u8; 2],
+	outcome: &AuctionOutcome,
+) -> ParentchainCall {
+	ParentchainCall::Integritee(OpaqueCall::from_tuple(&(
+		call_indexes,
+		H256::from(blake2_256(&outcome.encode())),
+		vec![auction::outcome_topic(outcome.auction_id)],
+		outcome.encode(),
+	)))
+}
+
+fn publish_poll_result(call_indexes: [u8; 2], result: &PollResult) -> ParentchainCall {
+	ParentchainCall::Integritee(OpaqueCall::from_tuple(&(
+		call_indexes,
+		result.ballots_root,
+		vec![voting::result_topic(result.poll_id)],
+		result.encode(),
+	)))
+}
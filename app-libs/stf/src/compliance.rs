@@ -0,0 +1,132 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Optional compliance (KYC/allowlist) gate for regulated shards.
+//!
+//! The gate is disabled as long as no registrar is configured. Once root sets a registrar,
+//! every balance movement above the configured threshold requires both the sending and the
+//! receiving account to be attested by that registrar.
+
+use crate::helpers::{
+	get_storage_map, get_storage_value, kill_storage_map, kill_storage_value, put_storage_map,
+	put_storage_value,
+};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::{Balance, System};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use itp_types::BlockNumber;
+use itp_utils::stringify::account_id_to_string;
+use log::*;
+
+pub(crate) const COMPLIANCE: &str = "Compliance";
+const REGISTRAR: &str = "Registrar";
+const THRESHOLD: &str = "Threshold";
+const ATTESTATIONS: &str = "Attestations";
+
+/// Compliance status of an account, as returned by the `compliance_status` getter.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum ComplianceStatus {
+	/// No registrar is configured on this shard, the gate is not enforced.
+	Disabled,
+	/// The account has not been attested by the registrar.
+	Unattested,
+	/// The account has been attested at the given sidechain block number.
+	Attested(BlockNumber),
+}
+
+pub fn registrar() -> Option<AccountId> {
+	get_storage_value(COMPLIANCE, REGISTRAR)
+}
+
+/// Amount above which balance movements require attested accounts.
+pub fn threshold() -> Balance {
+	get_storage_value(COMPLIANCE, THRESHOLD).unwrap_or_default()
+}
+
+/// Sets the registrar and the threshold. `None` disables the gate again.
+pub fn set_registrar(maybe_registrar: Option<AccountId>, threshold: Balance) {
+	match maybe_registrar {
+		Some(registrar) => {
+			put_storage_value(COMPLIANCE, REGISTRAR, &registrar);
+			put_storage_value(COMPLIANCE, THRESHOLD, &threshold);
+		},
+		None => {
+			kill_storage_value(COMPLIANCE, REGISTRAR);
+			kill_storage_value(COMPLIANCE, THRESHOLD);
+		},
+	}
+}
+
+/// Ensures an account is the configured compliance registrar.
+pub fn ensure_registrar(account: &AccountId) -> StfResult<()> {
+	match registrar() {
+		Some(ref registrar) if registrar == account => Ok(()),
+		_ => {
+			error!("Account {} is not the compliance registrar", account_id_to_string(account));
+			Err(StfError::RequireComplianceRegistrar)
+		},
+	}
+}
+
+pub fn attest(who: &AccountId) {
+	put_storage_map(
+		COMPLIANCE,
+		ATTESTATIONS,
+		who,
+		&StorageHasher::Blake2_128Concat,
+		&System::block_number(),
+	);
+}
+
+pub fn revoke(who: &AccountId) {
+	kill_storage_map(COMPLIANCE, ATTESTATIONS, who, &StorageHasher::Blake2_128Concat);
+}
+
+pub fn status(who: &AccountId) -> ComplianceStatus {
+	if registrar().is_none() {
+		return ComplianceStatus::Disabled
+	}
+	get_storage_map::<AccountId, BlockNumber>(
+		COMPLIANCE,
+		ATTESTATIONS,
+		who,
+		&StorageHasher::Blake2_128Concat,
+	)
+	.map_or(ComplianceStatus::Unattested, ComplianceStatus::Attested)
+}
+
+/// Ensures all the given accounts may take part in a balance movement of `amount`.
+pub fn ensure_compliant(accounts: &[&AccountId], amount: Balance) -> StfResult<()> {
+	if registrar().is_none() || amount <= threshold() {
+		return Ok(())
+	}
+	for account in accounts {
+		if status(account) == ComplianceStatus::Unattested {
+			warn!(
+				"Rejecting movement of {} involving unattested account {}",
+				amount,
+				account_id_to_string(*account)
+			);
+			return Err(StfError::MissingComplianceAttestation((*account).clone()))
+		}
+	}
+	Ok(())
+}
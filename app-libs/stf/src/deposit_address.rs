@@ -0,0 +1,149 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Deposit addresses of custodial integrators.
+//!
+//! A parent account derives fresh deposit addresses on request, one per customer for instance.
+//! Each address is the hash of the parent account and a running index, so nobody holds a key for
+//! it: funds sent to a deposit address can only leave it by a sweep of its parent account to
+//! itself. Clients can compute the address of an index in advance, but it is only linked to the
+//! parent once it has been derived in the state.
+
+use crate::{
+	compliance,
+	helpers::{get_storage_map, put_storage_map},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::{Currency, ExistenceRequirement};
+use ita_sgx_runtime::{Balance, Balances};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use itp_utils::stringify::account_id_to_string;
+use log::*;
+use sp_io::hashing::blake2_256;
+use std::{format, vec::Vec};
+
+pub(crate) const DEPOSIT_ADDRESS: &str = "DepositAddress";
+const NEXT_INDEX: &str = "NextIndex";
+const PARENTS: &str = "Parents";
+
+/// Maximum number of deposit addresses swept by one call.
+pub const MAX_SWEPT_ADDRESSES: usize = 64;
+
+pub type DepositIndex = u32;
+
+/// Address of the deposit account of the parent account with the given index.
+pub fn derive(parent: &AccountId, index: DepositIndex) -> AccountId {
+	AccountId::new(blake2_256(&(b"deposit_address", parent, index).encode()))
+}
+
+/// Deposit addresses derived by the parent account so far, in the order of their indices.
+pub fn addresses(parent: &AccountId) -> Vec<AccountId> {
+	(0..next_index(parent)).map(|index| derive(parent, index)).collect()
+}
+
+/// Parent account and index of a derived deposit address.
+pub fn parent_of(address: &AccountId) -> Option<(AccountId, DepositIndex)> {
+	get_storage_map(DEPOSIT_ADDRESS, PARENTS, address, &StorageHasher::Blake2_128Concat)
+}
+
+/// Derives the next deposit address of the parent account and links it to the parent.
+pub fn derive_next(parent: &AccountId) -> StfResult<AccountId> {
+	if parent_of(parent).is_some() {
+		return Err(StfError::Dispatch("a deposit address cannot derive addresses itself".into()))
+	}
+	let index = next_index(parent);
+	let next = index
+		.checked_add(1)
+		.ok_or_else(|| StfError::Dispatch("no deposit addresses left to derive".into()))?;
+	let address = derive(parent, index);
+	put_storage_map(
+		DEPOSIT_ADDRESS,
+		PARENTS,
+		&address,
+		&StorageHasher::Blake2_128Concat,
+		&(parent.clone(), index),
+	);
+	put_storage_map(DEPOSIT_ADDRESS, NEXT_INDEX, parent, &StorageHasher::Blake2_128Concat, &next);
+	info!(
+		"derived deposit address #{} {} of {}",
+		index,
+		account_id_to_string(&address),
+		account_id_to_string(parent)
+	);
+	Ok(address)
+}
+
+/// Moves the free balance of the deposit addresses to their parent account, returns the total.
+pub fn sweep(parent: &AccountId, addresses: &[AccountId]) -> StfResult<Balance> {
+	if addresses.len() > MAX_SWEPT_ADDRESSES {
+		return Err(StfError::Dispatch(format!(
+			"at most {} deposit addresses can be swept at once",
+			MAX_SWEPT_ADDRESSES
+		)))
+	}
+	let mut total: Balance = 0;
+	for address in addresses {
+		match parent_of(address) {
+			Some((address_parent, _)) if &address_parent == parent => {},
+			_ =>
+				return Err(StfError::Dispatch(format!(
+					"{} is not a deposit address of the sender",
+					account_id_to_string(address)
+				))),
+		}
+		let free = Balances::free_balance(address);
+		if free == 0 {
+			continue
+		}
+		compliance::ensure_compliant(&[parent], free)?;
+		<Balances as Currency<AccountId>>::transfer(
+			address,
+			parent,
+			free,
+			ExistenceRequirement::AllowDeath,
+		)
+		.map_err(|e| StfError::Dispatch(format!("Deposit sweep error: {:?}", e)))?;
+		total = total.saturating_add(free);
+	}
+	debug!("swept {} from {} deposit addresses", total, addresses.len());
+	Ok(total)
+}
+
+fn next_index(parent: &AccountId) -> DepositIndex {
+	get_storage_map(DEPOSIT_ADDRESS, NEXT_INDEX, parent, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn deposit_addresses_are_distinct_per_parent_and_index() {
+		let alice = AccountId::new([1u8; 32]);
+		let bob = AccountId::new([2u8; 32]);
+
+		assert_eq!(derive(&alice, 0), derive(&alice, 0));
+		assert_ne!(derive(&alice, 0), derive(&alice, 1));
+		assert_ne!(derive(&alice, 0), derive(&bob, 0));
+		assert_ne!(derive(&alice, 0), alice);
+	}
+}
@@ -0,0 +1,134 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Selective disclosure of the events emitted by trusted calls.
+//!
+//! The events of every successfully executed trusted call are attributed to its sender and are
+//! only available to that sender through a trusted getter. A call wrapped in
+//! `TrustedCall::disclose_events` can additionally mark all or some of its events as public,
+//! which exports them through the `disclosed_events` public getter. Events are kept for the
+//! current sidechain block only.
+
+use crate::helpers::{get_storage_value, kill_storage_value, put_storage_value};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::{Hash, RuntimeEvent, System};
+use itp_stf_primitives::types::AccountId;
+use log::*;
+use std::vec::Vec;
+
+pub(crate) const EVENT_DISCLOSURE: &str = "EventDisclosure";
+const CALL_EVENTS: &str = "CallEvents";
+
+pub type EventRecord = frame_system::EventRecord<RuntimeEvent, Hash>;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum DisclosurePolicy {
+	/// Events are only available to the sender of the call.
+	Private,
+	/// All events of the call are public.
+	Public,
+	/// Only events of the pallets with the given indices are public.
+	Pallets(Vec<u8>),
+}
+
+impl DisclosurePolicy {
+	pub fn discloses(&self, event: &RuntimeEvent) -> bool {
+		match self {
+			DisclosurePolicy::Private => false,
+			DisclosurePolicy::Public => true,
+			// The first byte of an encoded runtime event is the index of the emitting pallet.
+			DisclosurePolicy::Pallets(pallets) =>
+				event.encode().first().map_or(false, |index| pallets.contains(index)),
+		}
+	}
+}
+
+/// Range of event indices emitted by one trusted call.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct CallEvents {
+	pub sender: AccountId,
+	pub first: u32,
+	pub end: u32,
+	pub policy: DisclosurePolicy,
+}
+
+impl CallEvents {
+	fn contains(&self, index: u32) -> bool {
+		self.first <= index && index < self.end
+	}
+
+	fn overlaps(&self, first: u32, end: u32) -> bool {
+		self.first < end && first < self.end
+	}
+}
+
+/// Attributes the events emitted since `first` to the sender of the call that has just been
+/// executed.
+///
+/// The recorded ranges never overlap, so an event is never attributed to more than one call.
+/// A recorded range that overlaps the new one is stale, e.g. because the events have been reset
+/// without resetting the ranges, and is rejected.
+pub fn record_call_events(sender: AccountId, first: u32, policy: DisclosurePolicy) {
+	let end = System::event_count();
+	if end <= first {
+		return
+	}
+	let mut call_events = call_events();
+	let recorded_ranges = call_events.len();
+	call_events.retain(|c| !c.overlaps(first, end));
+	if call_events.len() < recorded_ranges {
+		error!(
+			"Rejected {} stale event ranges overlapping the events {}..{}",
+			recorded_ranges - call_events.len(),
+			first,
+			end
+		);
+	}
+	call_events.push(CallEvents { sender, first, end, policy });
+	put_storage_value(EVENT_DISCLOSURE, CALL_EVENTS, &call_events);
+}
+
+/// Events of the current block that have been disclosed by their calls.
+pub fn disclosed_events() -> Vec<EventRecord> {
+	let call_events = call_events();
+	filter_events(|index, event| {
+		call_events.iter().any(|c| c.contains(index) && c.policy.discloses(event))
+	})
+}
+
+/// Events of the current block emitted by calls of `who`.
+pub fn events_of(who: &AccountId) -> Vec<EventRecord> {
+	let call_events = call_events();
+	filter_events(|index, _| call_events.iter().any(|c| &c.sender == who && c.contains(index)))
+}
+
+/// Must be called at the start of every sidechain block, after the events have been reset.
+pub fn on_initialize() {
+	kill_storage_value(EVENT_DISCLOSURE, CALL_EVENTS);
+}
+
+fn call_events() -> Vec<CallEvents> {
+	get_storage_value(EVENT_DISCLOSURE, CALL_EVENTS).unwrap_or_default()
+}
+
+fn filter_events(filter: impl Fn(u32, &RuntimeEvent) -> bool) -> Vec<EventRecord> {
+	System::read_events_no_consensus()
+		.enumerate()
+		.filter(|(index, record)| filter(*index as u32, &record.event))
+		.map(|(_, record)| *record)
+		.collect()
+}
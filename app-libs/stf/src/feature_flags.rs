@@ -0,0 +1,182 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Feature flags that enable or disable optional STF modules per shard.
+//!
+//! Every module is enabled unless root has disabled it. Root schedules a flag change at a
+//! sidechain block number, so all validateers switch at the same block. Calls of a disabled
+//! module fail, except for the exit calls that release funds, and its block hooks are not run.
+
+use crate::helpers::{get_storage_map, kill_storage_map, put_storage_map};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::System;
+use itp_stf_primitives::error::{StfError, StfResult};
+use itp_storage::StorageHasher;
+use itp_types::BlockNumber;
+use log::*;
+use std::{format, vec::Vec};
+
+pub(crate) const FEATURE_FLAGS: &str = "FeatureFlags";
+const TOGGLES: &str = "Toggles";
+
+/// Optional STF modules that can be toggled at runtime.
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Feature {
+	OrderBook,
+	Voting,
+	Auction,
+	PaymentChannel,
+	Recovery,
+	Multisig,
+	Proxy,
+	EventDisclosure,
+	DepositAddress,
+	/// Only has an effect in enclaves built with the `evm` feature.
+	Evm,
+}
+
+impl Feature {
+	pub const ALL: [Feature; 10] = [
+		Feature::OrderBook,
+		Feature::Voting,
+		Feature::Auction,
+		Feature::PaymentChannel,
+		Feature::Recovery,
+		Feature::Multisig,
+		Feature::Proxy,
+		Feature::EventDisclosure,
+		Feature::DepositAddress,
+		Feature::Evm,
+	];
+}
+
+/// A flag change taking effect at the given sidechain block: `(activation block, enabled)`.
+pub type Toggle = (BlockNumber, bool);
+
+/// State of a feature flag, as returned by the `feature_flags` getter.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct FeatureFlagStatus {
+	pub feature: Feature,
+	/// Whether the feature is enabled at the latest sidechain block.
+	pub enabled: bool,
+	/// Flag changes that take effect at a later block, in order.
+	pub scheduled: Vec<Toggle>,
+}
+
+pub fn is_enabled(feature: Feature) -> bool {
+	enabled_at(&toggles(feature), System::block_number())
+}
+
+pub fn ensure_enabled(feature: Feature) -> StfResult<()> {
+	if is_enabled(feature) {
+		Ok(())
+	} else {
+		warn!("Rejecting call of disabled feature {:?}", feature);
+		Err(StfError::FeatureDisabled(format!("{:?}", feature)))
+	}
+}
+
+/// Schedules the flag change at `activation_block`, which replaces all changes of the feature
+/// scheduled at that block or later.
+pub fn schedule(feature: Feature, enabled: bool, activation_block: BlockNumber) -> StfResult<()> {
+	let now = System::block_number();
+	if activation_block < now {
+		return Err(StfError::Dispatch(
+			"feature flags cannot be changed at a past sidechain block".into(),
+		))
+	}
+	let toggles = reschedule(toggles(feature), now, (activation_block, enabled));
+	put_storage_map(FEATURE_FLAGS, TOGGLES, &feature, &StorageHasher::Blake2_128Concat, &toggles);
+	Ok(())
+}
+
+pub fn status() -> Vec<FeatureFlagStatus> {
+	let now = System::block_number();
+	Feature::ALL
+		.iter()
+		.map(|feature| {
+			let toggles = toggles(*feature);
+			FeatureFlagStatus {
+				feature: *feature,
+				enabled: enabled_at(&toggles, now),
+				scheduled: toggles.into_iter().filter(|(at, _)| *at > now).collect(),
+			}
+		})
+		.collect()
+}
+
+/// Drops all flag changes, which enables every feature again.
+pub fn reset() {
+	for feature in Feature::ALL.iter() {
+		kill_storage_map(FEATURE_FLAGS, TOGGLES, feature, &StorageHasher::Blake2_128Concat);
+	}
+}
+
+fn toggles(feature: Feature) -> Vec<Toggle> {
+	get_storage_map(FEATURE_FLAGS, TOGGLES, &feature, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+fn enabled_at(toggles: &[Toggle], block_number: BlockNumber) -> bool {
+	toggles
+		.iter()
+		.rev()
+		.find(|(at, _)| *at <= block_number)
+		.map_or(true, |(_, enabled)| *enabled)
+}
+
+/// Only the latest change that already took effect is kept, together with the scheduled ones.
+fn reschedule(toggles: Vec<Toggle>, now: BlockNumber, toggle: Toggle) -> Vec<Toggle> {
+	let (past, future): (Vec<Toggle>, Vec<Toggle>) =
+		toggles.into_iter().partition(|(at, _)| *at <= now);
+	let mut toggles: Vec<Toggle> = past
+		.last()
+		.copied()
+		.into_iter()
+		.chain(future)
+		.filter(|(at, _)| *at < toggle.0)
+		.collect();
+	toggles.push(toggle);
+	toggles
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn feature_is_enabled_until_a_toggle_takes_effect() {
+		let toggles = vec![(5, false), (9, true)];
+
+		assert!(enabled_at(&[], 3));
+		assert!(enabled_at(&toggles, 4));
+		assert!(!enabled_at(&toggles, 5));
+		assert!(!enabled_at(&toggles, 8));
+		assert!(enabled_at(&toggles, 9));
+	}
+
+	#[test]
+	fn reschedule_prunes_past_and_replaces_later_toggles() {
+		let toggles = vec![(2, false), (4, true), (8, false), (12, true)];
+
+		assert_eq!(
+			reschedule(toggles.clone(), 6, (10, true)),
+			vec![(4, true), (8, false), (10, true)]
+		);
+		assert_eq!(reschedule(toggles, 4, (4, false)), vec![(4, false)]);
+	}
+}
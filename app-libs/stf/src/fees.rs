@@ -0,0 +1,106 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Fees of trusted calls and their estimation before submission.
+//!
+//! Only transfers and unshields charge a fee so far, both flat. The weight class tells wallets
+//! how expensive a call is to execute, e.g. to warn before an EVM call.
+
+use crate::{TrustedCall, STF_TX_FEE};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::{Balance, Balances};
+use itp_stf_primitives::types::AccountId;
+
+/// Fee of a balance transfer, paid to the enclave account.
+pub const TRANSFER_FEE: Balance = STF_TX_FEE;
+
+/// Fee of an unshield, has to cover at least two transfers on the parentchain.
+pub const UNSHIELD_FEE: Balance = STF_TX_FEE * 3;
+
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WeightClass {
+	/// Does not touch the state.
+	Light,
+	Normal,
+	/// Executes a contract or another call whose cost depends on its input.
+	Heavy,
+}
+
+/// Value returned by the `author_estimateFee` RPC.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct FeeEstimate {
+	pub fee: Balance,
+	pub weight_class: WeightClass,
+	/// Account the fee is charged to, the sender of the innermost call.
+	pub payer: AccountId,
+	/// Whether the free balance of the payer covers the fee and the transferred amount.
+	pub affordable: bool,
+}
+
+/// Estimates the fee against the current state, without executing the call.
+pub fn estimate(call: &TrustedCall) -> FeeEstimate {
+	let (payer, fee, spent) = charges(call);
+	let affordable = Balances::free_balance(&payer) >= fee.saturating_add(spent);
+	FeeEstimate { fee, weight_class: weight_class(call), payer, affordable }
+}
+
+pub fn weight_class(call: &TrustedCall) -> WeightClass {
+	match call {
+		TrustedCall::noop(..) => WeightClass::Light,
+		TrustedCall::proxy_call(.., inner)
+		| TrustedCall::disclose_events(.., inner)
+		| TrustedCall::multisig_propose(.., inner) => weight_class(inner).max(WeightClass::Normal),
+		#[cfg(feature = "evm")]
+		TrustedCall::evm_withdraw(..)
+		| TrustedCall::evm_call(..)
+		| TrustedCall::evm_create(..)
+		| TrustedCall::evm_create2(..) => WeightClass::Heavy,
+		_ => WeightClass::Normal,
+	}
+}
+
+/// Payer, fee and transferred amount of the call. Wrapped calls are executed on behalf of their
+/// sender, who pays for them.
+fn charges(call: &TrustedCall) -> (AccountId, Balance, Balance) {
+	match call {
+		TrustedCall::balance_transfer(from, _, value) => (from.clone(), TRANSFER_FEE, *value),
+		TrustedCall::balance_unshield(from, _, value, _) => (from.clone(), UNSHIELD_FEE, *value),
+		TrustedCall::proxy_call(.., inner)
+		| TrustedCall::disclose_events(.., inner)
+		| TrustedCall::multisig_propose(.., inner) => charges(inner),
+		_ => (call.sender_account().clone(), 0, 0),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn wrapped_calls_are_charged_to_the_sender_of_the_inner_call() {
+		let delegate = AccountId::new([1u8; 32]);
+		let delegator = AccountId::new([2u8; 32]);
+		let transfer = TrustedCall::balance_transfer(delegator.clone(), delegate.clone(), 10);
+		let proxy_call =
+			TrustedCall::proxy_call(delegate.clone(), delegator.clone(), Box::new(transfer));
+
+		assert_eq!(charges(&proxy_call), (delegator, TRANSFER_FEE, 10));
+		assert_eq!(weight_class(&proxy_call), WeightClass::Normal);
+		assert_eq!(weight_class(&TrustedCall::noop(delegate.clone())), WeightClass::Light);
+		assert_eq!(charges(&TrustedCall::noop(delegate.clone())), (delegate, 0, 0));
+	}
+}
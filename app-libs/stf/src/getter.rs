@@ -15,6 +15,15 @@
 
 */
 
+use crate::{
+	aggregate_privacy::{self, Aggregate},
+	auction::{self, AuctionId},
+	compliance, deposit_address, event_disclosure, feature_flags, multisig,
+	order_book::{self, MarketId},
+	payment_channel::{self, ChannelId},
+	proxy, recovery, shard_configuration, state_hash, state_migration,
+	voting::{self, PollId},
+};
 use codec::{Decode, Encode};
 use ita_sgx_runtime::System;
 use itp_stf_interface::ExecuteGetter;
@@ -94,6 +103,12 @@ impl PoolTransactionValidation for Getter {
 #[allow(non_camel_case_types)]
 pub enum PublicGetter {
 	some_value,
+	order_book_clearing_price(MarketId),
+	disclosed_events,
+	feature_flags,
+	state_migrations,
+	state_hash_algorithm,
+	shard_configuration,
 }
 
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
@@ -102,6 +117,23 @@ pub enum TrustedGetter {
 	free_balance(AccountId),
 	reserved_balance(AccountId),
 	nonce(AccountId),
+	compliance_status(AccountId),
+	order_book_orders(AccountId, MarketId),
+	order_book_base_balance(AccountId, MarketId),
+	voting_poll(AccountId, PollId),
+	voting_ballot(AccountId, PollId),
+	voting_inclusion_proof(AccountId, PollId),
+	auction_info(AccountId, AuctionId),
+	auction_bid(AccountId, AuctionId),
+	auction_outcome(AccountId, AuctionId),
+	payment_channel(AccountId, ChannelId),
+	recovery_config(AccountId),
+	recovery_attempt(AccountId, AccountId), // (Rescuer, Lost account)
+	multisig_pending_proposals(AccountId, AccountId), // (Signatory, Multisig)
+	proxy_proxies(AccountId),
+	events(AccountId),
+	deposit_addresses(AccountId),
+	aggregate(AccountId, Aggregate), // (Querier, Aggregate opened by the querier)
 	#[cfg(feature = "evm")]
 	evm_nonce(AccountId),
 	#[cfg(feature = "evm")]
@@ -116,6 +148,23 @@ impl TrustedGetter {
 			TrustedGetter::free_balance(sender_account) => sender_account,
 			TrustedGetter::reserved_balance(sender_account) => sender_account,
 			TrustedGetter::nonce(sender_account) => sender_account,
+			TrustedGetter::compliance_status(sender_account) => sender_account,
+			TrustedGetter::order_book_orders(sender_account, _) => sender_account,
+			TrustedGetter::order_book_base_balance(sender_account, _) => sender_account,
+			TrustedGetter::voting_poll(sender_account, _) => sender_account,
+			TrustedGetter::voting_ballot(sender_account, _) => sender_account,
+			TrustedGetter::voting_inclusion_proof(sender_account, _) => sender_account,
+			TrustedGetter::auction_info(sender_account, _) => sender_account,
+			TrustedGetter::auction_bid(sender_account, _) => sender_account,
+			TrustedGetter::auction_outcome(sender_account, _) => sender_account,
+			TrustedGetter::payment_channel(sender_account, _) => sender_account,
+			TrustedGetter::recovery_config(sender_account) => sender_account,
+			TrustedGetter::recovery_attempt(sender_account, _) => sender_account,
+			TrustedGetter::multisig_pending_proposals(sender_account, _) => sender_account,
+			TrustedGetter::proxy_proxies(sender_account) => sender_account,
+			TrustedGetter::events(sender_account) => sender_account,
+			TrustedGetter::deposit_addresses(sender_account) => sender_account,
+			TrustedGetter::aggregate(sender_account, _) => sender_account,
 			#[cfg(feature = "evm")]
 			TrustedGetter::evm_nonce(sender_account) => sender_account,
 			#[cfg(feature = "evm")]
@@ -187,6 +236,76 @@ impl ExecuteGetter for TrustedGetterSigned {
 				debug!("Account nonce is {}", nonce);
 				Some(nonce.encode())
 			},
+			TrustedGetter::compliance_status(who) => {
+				let status = compliance::status(&who);
+				debug!("TrustedGetter compliance_status");
+				debug!("Compliance status for {} is {:?}", account_id_to_string(&who), status);
+				Some(status.encode())
+			},
+			TrustedGetter::order_book_orders(who, market) => {
+				debug!("TrustedGetter order_book_orders");
+				Some(order_book::orders_of(market, &who).encode())
+			},
+			TrustedGetter::order_book_base_balance(who, market) => {
+				debug!("TrustedGetter order_book_base_balance");
+				Some(order_book::base_balance(market, &who).encode())
+			},
+			TrustedGetter::voting_poll(_who, poll_id) => {
+				debug!("TrustedGetter voting_poll");
+				Some(voting::poll(poll_id).encode())
+			},
+			TrustedGetter::voting_ballot(who, poll_id) => {
+				debug!("TrustedGetter voting_ballot");
+				Some(voting::ballot(poll_id, &who).encode())
+			},
+			TrustedGetter::voting_inclusion_proof(who, poll_id) => {
+				debug!("TrustedGetter voting_inclusion_proof");
+				Some(voting::inclusion_proof(poll_id, &who).encode())
+			},
+			TrustedGetter::auction_info(_who, auction_id) => {
+				debug!("TrustedGetter auction_info");
+				Some(auction::auction(auction_id).encode())
+			},
+			TrustedGetter::auction_bid(who, auction_id) => {
+				debug!("TrustedGetter auction_bid");
+				Some(auction::bid_of(auction_id, &who).encode())
+			},
+			TrustedGetter::auction_outcome(_who, auction_id) => {
+				debug!("TrustedGetter auction_outcome");
+				Some(auction::outcome(auction_id).encode())
+			},
+			TrustedGetter::payment_channel(who, channel_id) => {
+				debug!("TrustedGetter payment_channel");
+				Some(payment_channel::channel_of(channel_id, &who).encode())
+			},
+			TrustedGetter::recovery_config(who) => {
+				debug!("TrustedGetter recovery_config");
+				Some(recovery::config(&who).encode())
+			},
+			TrustedGetter::recovery_attempt(rescuer, lost) => {
+				debug!("TrustedGetter recovery_attempt");
+				Some(recovery::attempt(&lost, &rescuer).encode())
+			},
+			TrustedGetter::multisig_pending_proposals(who, multisig) => {
+				debug!("TrustedGetter multisig_pending_proposals");
+				Some(multisig::proposals_for(&multisig, &who).encode())
+			},
+			TrustedGetter::proxy_proxies(who) => {
+				debug!("TrustedGetter proxy_proxies");
+				Some(proxy::proxies(&who).encode())
+			},
+			TrustedGetter::events(who) => {
+				debug!("TrustedGetter events");
+				Some(event_disclosure::events_of(&who).encode())
+			},
+			TrustedGetter::deposit_addresses(who) => {
+				debug!("TrustedGetter deposit_addresses");
+				Some(deposit_address::addresses(&who).encode())
+			},
+			TrustedGetter::aggregate(who, aggregate) => {
+				debug!("TrustedGetter aggregate");
+				Some(aggregate_privacy::query(&who, aggregate).encode())
+			},
 			#[cfg(feature = "evm")]
 			TrustedGetter::evm_nonce(who) => {
 				let evm_account = get_evm_account(&who);
@@ -228,6 +347,13 @@ impl ExecuteGetter for PublicGetter {
 	fn execute(self) -> Option<Vec<u8>> {
 		match self {
 			PublicGetter::some_value => Some(42u32.encode()),
+			PublicGetter::order_book_clearing_price(market) =>
+				Some(order_book::clearing_price(market).encode()),
+			PublicGetter::disclosed_events => Some(event_disclosure::disclosed_events().encode()),
+			PublicGetter::feature_flags => Some(feature_flags::status().encode()),
+			PublicGetter::state_migrations => Some(state_migration::status().encode()),
+			PublicGetter::state_hash_algorithm => Some(state_hash::algorithm().encode()),
+			PublicGetter::shard_configuration => Some(shard_configuration::current().encode()),
 		}
 	}
 
@@ -65,6 +65,39 @@ pub fn get_storage_double_map<K: Encode, Q: Encode, V: Decode + Clone>(
 	get_storage_by_key_hash(key)
 }
 
+pub fn put_storage_value<V: Encode>(
+	storage_prefix: &'static str,
+	storage_key_name: &'static str,
+	value: &V,
+) {
+	sp_io::storage::set(&storage_value_key(storage_prefix, storage_key_name), &value.encode());
+}
+
+pub fn kill_storage_value(storage_prefix: &'static str, storage_key_name: &'static str) {
+	sp_io::storage::clear(&storage_value_key(storage_prefix, storage_key_name));
+}
+
+pub fn put_storage_map<K: Encode, V: Encode>(
+	storage_prefix: &'static str,
+	storage_key_name: &'static str,
+	map_key: &K,
+	hasher: &StorageHasher,
+	value: &V,
+) {
+	let key = storage_map_key::<K>(storage_prefix, storage_key_name, map_key, hasher);
+	sp_io::storage::set(&key, &value.encode());
+}
+
+pub fn kill_storage_map<K: Encode>(
+	storage_prefix: &'static str,
+	storage_key_name: &'static str,
+	map_key: &K,
+	hasher: &StorageHasher,
+) {
+	let key = storage_map_key::<K>(storage_prefix, storage_key_name, map_key, hasher);
+	sp_io::storage::clear(&key);
+}
+
 /// Get value in storage.
 pub fn get_storage_by_key_hash<V: Decode>(key: Vec<u8>) -> Option<V> {
 	if let Some(value_encoded) = sp_io::storage::get(&key) {
@@ -106,6 +139,25 @@ pub fn ensure_enclave_signer_account<AccountId: Encode + Decode + PartialEq>(
 	}
 }
 
+/// Random bytes of the enclave, which nobody outside of it can predict, not even root.
+#[cfg(feature = "sgx")]
+pub fn enclave_randomness() -> StfResult<[u8; 32]> {
+	use sgx_rand::{Rng, StdRng};
+	use std::format;
+
+	let mut bytes = [0u8; 32];
+	let mut rand = StdRng::new()
+		.map_err(|e| StfError::Dispatch(format!("enclave randomness is not available: {:?}", e)))?;
+	rand.fill_bytes(&mut bytes);
+	Ok(bytes)
+}
+
+/// Outside of the enclave, there is no randomness that is secret.
+#[cfg(not(feature = "sgx"))]
+pub fn enclave_randomness() -> StfResult<[u8; 32]> {
+	Err(StfError::Dispatch("enclave randomness is only available inside the enclave".into()))
+}
+
 pub fn set_block_number(block_number: u32) {
 	sp_io::storage::set(&storage_value_key("System", "Number"), &block_number.encode());
 }
@@ -30,11 +30,28 @@ pub use getter::*;
 pub use stf_sgx_primitives::{types::*, Stf};
 pub use trusted_call::*;
 
+pub mod aggregate_privacy;
+pub mod auction;
+pub mod block_hooks;
+pub mod compliance;
+pub mod deposit_address;
+pub mod event_disclosure;
 #[cfg(feature = "evm")]
 pub mod evm_helpers;
+pub mod feature_flags;
+pub mod fees;
 pub mod getter;
 pub mod hash;
 pub mod helpers;
+pub mod multisig;
+pub mod order_book;
+pub mod payment_channel;
+pub mod proxy;
+pub mod recovery;
+pub mod shard_configuration;
+pub mod state_hash;
+pub mod state_migration;
+pub mod storage_namespace;
 pub mod stf_sgx;
 pub mod stf_sgx_primitives;
 #[cfg(all(feature = "test", feature = "sgx"))]
@@ -42,6 +59,7 @@ pub mod stf_sgx_tests;
 #[cfg(all(feature = "test", feature = "sgx"))]
 pub mod test_genesis;
 pub mod trusted_call;
+pub mod voting;
 
 pub(crate) const ENCLAVE_ACCOUNT_KEY: &str = "Enclave_Account_Key";
 
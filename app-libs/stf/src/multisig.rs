@@ -0,0 +1,231 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! M-of-n multi-signature accounts.
+//!
+//! A multisig account is derived deterministically from its signatories and threshold, similar
+//! to Substrate's multisig pallet. Any signatory can propose a trusted call with the multisig as
+//! sender; the call is executed once `threshold` signatories have approved it.
+
+use crate::{
+	helpers::{get_storage_map, kill_storage_map, put_storage_map},
+	TrustedCall,
+};
+use codec::{Decode, Encode};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use itp_types::H256;
+use sp_io::hashing::blake2_256;
+use std::{format, vec, vec::Vec};
+
+pub(crate) const MULTISIG: &str = "Multisig";
+const MULTISIGS: &str = "Multisigs";
+const PROPOSALS: &str = "Proposals";
+
+pub const MAX_SIGNATORIES: usize = 16;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct MultisigInfo {
+	/// Sorted, without duplicates.
+	pub signatories: Vec<AccountId>,
+	pub threshold: u16,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Proposal {
+	pub call_hash: H256,
+	pub call: TrustedCall,
+	pub proposer: AccountId,
+	/// Sorted, without duplicates.
+	pub approvals: Vec<AccountId>,
+}
+
+/// Derives the multisig account of a set of signatories and a threshold.
+pub fn multi_account_id(signatories: &[AccountId], threshold: u16) -> AccountId {
+	let mut signatories = signatories.to_vec();
+	signatories.sort();
+	signatories.dedup();
+	blake2_256(&(MULTISIG, signatories, threshold).encode()).into()
+}
+
+pub fn info(multisig: &AccountId) -> Option<MultisigInfo> {
+	get_storage_map(MULTISIG, MULTISIGS, multisig, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn proposals(multisig: &AccountId) -> Vec<Proposal> {
+	get_storage_map(MULTISIG, PROPOSALS, multisig, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+/// Pending proposals of a multisig, if `who` is one of its signatories.
+pub fn proposals_for(multisig: &AccountId, who: &AccountId) -> Option<Vec<Proposal>> {
+	info(multisig).filter(|i| i.signatories.binary_search(who).is_ok())?;
+	Some(proposals(multisig))
+}
+
+/// Stores a new proposal approved by its proposer. Returns the call if it can be executed
+/// right away, i.e. if the threshold is one.
+pub fn propose(
+	proposer: &AccountId,
+	other_signatories: Vec<AccountId>,
+	threshold: u16,
+	call: TrustedCall,
+) -> StfResult<Option<TrustedCall>> {
+	let info = ensure_signatory(proposer, other_signatories, threshold)?;
+	let multisig = multi_account_id(&info.signatories, threshold);
+	if call.sender_account() != &multisig {
+		return Err(StfError::Dispatch("proposed call must be sent by the multisig".into()))
+	}
+	if is_multisig_call(&call) {
+		return Err(StfError::Dispatch("nested multisig calls are not supported".into()))
+	}
+
+	let call_hash: H256 = blake2_256(&call.encode()).into();
+	let mut proposals = proposals(&multisig);
+	if proposals.iter().any(|p| p.call_hash == call_hash) {
+		return Err(StfError::Dispatch(format!("call {:?} has already been proposed", call_hash)))
+	}
+	if threshold == 1 {
+		return Ok(Some(call))
+	}
+	proposals.push(Proposal {
+		call_hash,
+		call,
+		proposer: proposer.clone(),
+		approvals: vec![proposer.clone()],
+	});
+	put_storage_map(MULTISIG, MULTISIGS, &multisig, &StorageHasher::Blake2_128Concat, &info);
+	put_proposals(&multisig, proposals);
+	Ok(None)
+}
+
+/// Adds an approval to a proposal. Returns the call once the threshold has been reached, the
+/// proposal is removed in that case.
+pub fn approve(
+	signer: &AccountId,
+	other_signatories: Vec<AccountId>,
+	threshold: u16,
+	call_hash: H256,
+) -> StfResult<Option<TrustedCall>> {
+	let info = ensure_signatory(signer, other_signatories, threshold)?;
+	let multisig = multi_account_id(&info.signatories, threshold);
+	let mut proposals = proposals(&multisig);
+	let position = proposals
+		.iter()
+		.position(|p| p.call_hash == call_hash)
+		.ok_or_else(|| unknown_proposal(call_hash))?;
+
+	let proposal = &mut proposals[position];
+	if let Err(index) = proposal.approvals.binary_search(signer) {
+		proposal.approvals.insert(index, signer.clone());
+	}
+	if proposal.approvals.len() >= threshold as usize {
+		let proposal = proposals.remove(position);
+		put_proposals(&multisig, proposals);
+		return Ok(Some(proposal.call))
+	}
+	put_proposals(&multisig, proposals);
+	Ok(None)
+}
+
+/// Removes a pending proposal. Only its proposer can cancel it.
+pub fn cancel(
+	proposer: &AccountId,
+	other_signatories: Vec<AccountId>,
+	threshold: u16,
+	call_hash: H256,
+) -> StfResult<()> {
+	let info = ensure_signatory(proposer, other_signatories, threshold)?;
+	let multisig = multi_account_id(&info.signatories, threshold);
+	let mut proposals = proposals(&multisig);
+	let position = proposals
+		.iter()
+		.position(|p| p.call_hash == call_hash && &p.proposer == proposer)
+		.ok_or_else(|| unknown_proposal(call_hash))?;
+	proposals.remove(position);
+	put_proposals(&multisig, proposals);
+	Ok(())
+}
+
+fn ensure_signatory(
+	who: &AccountId,
+	mut other_signatories: Vec<AccountId>,
+	threshold: u16,
+) -> StfResult<MultisigInfo> {
+	if other_signatories.contains(who) {
+		return Err(StfError::Dispatch("sender must not be in other signatories".into()))
+	}
+	other_signatories.push(who.clone());
+	other_signatories.sort();
+	other_signatories.dedup();
+	if other_signatories.len() < 2 || other_signatories.len() > MAX_SIGNATORIES {
+		return Err(StfError::Dispatch(format!(
+			"between 2 and {} signatories needed",
+			MAX_SIGNATORIES
+		)))
+	}
+	if threshold == 0 || threshold as usize > other_signatories.len() {
+		return Err(StfError::Dispatch(format!("invalid threshold {}", threshold)))
+	}
+	Ok(MultisigInfo { signatories: other_signatories, threshold })
+}
+
+fn is_multisig_call(call: &TrustedCall) -> bool {
+	matches!(
+		call,
+		TrustedCall::multisig_propose(..)
+			| TrustedCall::multisig_approve(..)
+			| TrustedCall::multisig_cancel(..)
+	)
+}
+
+fn put_proposals(multisig: &AccountId, proposals: Vec<Proposal>) {
+	if proposals.is_empty() {
+		kill_storage_map(MULTISIG, PROPOSALS, multisig, &StorageHasher::Blake2_128Concat);
+	} else {
+		put_storage_map(
+			MULTISIG,
+			PROPOSALS,
+			multisig,
+			&StorageHasher::Blake2_128Concat,
+			&proposals,
+		);
+	}
+}
+
+fn unknown_proposal(call_hash: H256) -> StfError {
+	StfError::Dispatch(format!("no pending proposal {:?}", call_hash))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn multi_account_id_does_not_depend_on_signatory_order() {
+		let a = AccountId::new([1u8; 32]);
+		let b = AccountId::new([2u8; 32]);
+		assert_eq!(
+			multi_account_id(&[a.clone(), b.clone()], 2),
+			multi_account_id(&[b.clone(), a.clone()], 2)
+		);
+		assert_ne!(multi_account_id(&[a.clone(), b.clone()], 2), multi_account_id(&[a, b], 1));
+	}
+}
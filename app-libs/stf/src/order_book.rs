@@ -0,0 +1,434 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Confidential order book with periodic uniform-price matching.
+//!
+//! Every market trades a market-specific base asset, held in this module's ledger, against the
+//! native shard balance as quote asset. Orders stay confidential in the shard state. At the start
+//! of every sidechain block, all markets with open orders are cleared at the single price that
+//! maximizes the matched volume, so no participant can front-run another one inside a block.
+//!
+//! For the compliance gate, an order is a balance movement of its quote volume at the limit
+//! price. Orders whose owner is not compliant for that volume are left out of clearing rounds.
+//!
+//! Placing, cancelling and settling an order emits an `OrderBookEvent`, which is only
+//! available to the owner of the order.
+
+use crate::{
+	compliance,
+	event_disclosure::{self, DisclosurePolicy},
+	helpers::{
+		get_storage_double_map, get_storage_map, get_storage_value, kill_storage_map,
+		put_storage_map, put_storage_value,
+	},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::{Currency, ReservableCurrency};
+use ita_sgx_runtime::{Balance, Balances, OrderBookEvent, Runtime, System};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::{storage_double_map_key, StorageHasher};
+use itp_utils::stringify::account_id_to_string;
+use log::*;
+use std::{format, vec::Vec};
+
+pub(crate) const ORDER_BOOK: &str = "OrderBook";
+const NEXT_ORDER_ID: &str = "NextOrderId";
+const ACTIVE_MARKETS: &str = "ActiveMarkets";
+const ORDERS: &str = "Orders";
+const BASE_BALANCES: &str = "BaseBalances";
+const CLEARING_PRICES: &str = "ClearingPrices";
+
+pub type MarketId = u32;
+pub type OrderId = u64;
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderSide {
+	Buy,
+	Sell,
+}
+
+/// An open limit order. `limit_price` is denominated in quote per unit of base asset.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+	pub id: OrderId,
+	pub owner: AccountId,
+	pub side: OrderSide,
+	pub amount: Balance,
+	pub limit_price: Balance,
+}
+
+/// Base asset holdings of an account in a market.
+#[derive(Encode, Decode, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BaseBalance {
+	pub free: Balance,
+	pub reserved: Balance,
+}
+
+/// Base asset amount matched for a single order in a clearing round.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Fill {
+	pub order_id: OrderId,
+	pub amount: Balance,
+}
+
+pub fn orders(market: MarketId) -> Vec<Order> {
+	get_storage_map(ORDER_BOOK, ORDERS, &market, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+pub fn orders_of(market: MarketId, who: &AccountId) -> Vec<Order> {
+	orders(market).into_iter().filter(|o| &o.owner == who).collect()
+}
+
+pub fn base_balance(market: MarketId, who: &AccountId) -> BaseBalance {
+	get_storage_double_map(
+		ORDER_BOOK,
+		BASE_BALANCES,
+		&market,
+		&StorageHasher::Blake2_128Concat,
+		who,
+		&StorageHasher::Blake2_128Concat,
+	)
+	.unwrap_or_default()
+}
+
+/// Price of the last clearing round in the given market, if any trade happened yet.
+pub fn clearing_price(market: MarketId) -> Option<Balance> {
+	get_storage_map(ORDER_BOOK, CLEARING_PRICES, &market, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn set_base_balance(market: MarketId, who: &AccountId, free: Balance) {
+	let mut balance = base_balance(market, who);
+	balance.free = free;
+	put_base_balance(market, who, &balance);
+}
+
+pub fn place_order(
+	who: AccountId,
+	market: MarketId,
+	side: OrderSide,
+	amount: Balance,
+	limit_price: Balance,
+) -> StfResult<OrderId> {
+	if amount == 0 || limit_price == 0 {
+		return Err(StfError::Dispatch("order amount and price must be non-zero".into()))
+	}
+	let volume = quote_amount(amount, limit_price)?;
+	compliance::ensure_compliant(&[&who], volume)?;
+	match side {
+		OrderSide::Buy => {
+			Balances::reserve(&who, volume).map_err(|_| StfError::MissingFunds)?;
+		},
+		OrderSide::Sell => {
+			let mut balance = base_balance(market, &who);
+			if balance.free < amount {
+				return Err(StfError::MissingFunds)
+			}
+			balance.free -= amount;
+			balance.reserved += amount;
+			put_base_balance(market, &who, &balance);
+		},
+	}
+
+	let id: OrderId = get_storage_value(ORDER_BOOK, NEXT_ORDER_ID).unwrap_or_default();
+	put_storage_value(ORDER_BOOK, NEXT_ORDER_ID, &(id + 1));
+
+	let mut market_orders = orders(market);
+	market_orders.push(Order { id, owner: who.clone(), side, amount, limit_price });
+	put_orders(market, market_orders);
+	System::deposit_event(OrderBookEvent::<Runtime>::OrderPlaced {
+		market,
+		order_id: id,
+		owner: who,
+	});
+
+	let mut markets: Vec<MarketId> =
+		get_storage_value(ORDER_BOOK, ACTIVE_MARKETS).unwrap_or_default();
+	if !markets.contains(&market) {
+		markets.push(market);
+		put_storage_value(ORDER_BOOK, ACTIVE_MARKETS, &markets);
+	}
+	Ok(id)
+}
+
+pub fn cancel_order(who: &AccountId, market: MarketId, order_id: OrderId) -> StfResult<()> {
+	let mut market_orders = orders(market);
+	let position = market_orders
+		.iter()
+		.position(|o| o.id == order_id && &o.owner == who)
+		.ok_or_else(|| StfError::Dispatch(format!("no order {} owned by caller", order_id)))?;
+	let order = market_orders.remove(position);
+	release(market, &order, order.amount);
+	put_orders(market, market_orders);
+	System::deposit_event(OrderBookEvent::<Runtime>::OrderCancelled {
+		market,
+		order_id,
+		owner: order.owner,
+	});
+	Ok(())
+}
+
+/// Clears all markets with open orders. Called once at the start of every sidechain block.
+pub fn on_initialize() {
+	let markets: Vec<MarketId> = get_storage_value(ORDER_BOOK, ACTIVE_MARKETS).unwrap_or_default();
+	for market in markets.iter() {
+		clear_market(*market);
+	}
+}
+
+fn clear_market(market: MarketId) {
+	let mut market_orders = orders(market);
+	let eligible_orders: Vec<Order> =
+		market_orders.iter().filter(|o| is_compliant(o)).cloned().collect();
+	let (price, fills) = match uniform_price_match(&eligible_orders) {
+		Some(outcome) => outcome,
+		None => return,
+	};
+	debug!("clearing market {} at price {} with {} fills", market, price, fills.len());
+
+	for fill in fills.iter() {
+		let order = match market_orders.iter_mut().find(|o| o.id == fill.order_id) {
+			Some(o) => o,
+			None => continue,
+		};
+		if let Err(e) = settle(market, order, fill.amount, price) {
+			// Matching only selects orders whose funds have been reserved upfront,
+			// so this can only happen if the reservation invariant has been violated.
+			error!(
+				"failed to settle order {} of {}: {:?}",
+				order.id,
+				account_id_to_string(&order.owner),
+				e
+			);
+			continue
+		}
+		order.amount -= fill.amount;
+
+		// Clearing runs outside of any trusted call, so the event is attributed to the owner
+		// of the order here.
+		let first_event = System::event_count();
+		System::deposit_event(OrderBookEvent::<Runtime>::OrderSettled {
+			market,
+			order_id: order.id,
+			owner: order.owner.clone(),
+			amount: fill.amount,
+			price,
+		});
+		event_disclosure::record_call_events(
+			order.owner.clone(),
+			first_event,
+			DisclosurePolicy::Private,
+		);
+	}
+
+	market_orders.retain(|o| o.amount > 0);
+	put_orders(market, market_orders);
+	put_storage_map(ORDER_BOOK, CLEARING_PRICES, &market, &StorageHasher::Blake2_128Concat, &price);
+}
+
+fn settle(market: MarketId, order: &Order, amount: Balance, price: Balance) -> StfResult<()> {
+	let mut balance = base_balance(market, &order.owner);
+	match order.side {
+		OrderSide::Buy => {
+			let reserved = quote_amount(amount, order.limit_price)?;
+			let cost = quote_amount(amount, price)?;
+			// The quote payment is burnt here and minted again for the sellers below.
+			// Both sides of a clearing round sum up to the same volume at the same price.
+			let _ = Balances::slash_reserved(&order.owner, cost);
+			Balances::unreserve(&order.owner, reserved - cost);
+			balance.free += amount;
+		},
+		OrderSide::Sell => {
+			balance.reserved -= amount;
+			let _ = Balances::deposit_creating(&order.owner, quote_amount(amount, price)?);
+		},
+	}
+	put_base_balance(market, &order.owner, &balance);
+	Ok(())
+}
+
+fn is_compliant(order: &Order) -> bool {
+	quote_amount(order.amount, order.limit_price)
+		.and_then(|volume| compliance::ensure_compliant(&[&order.owner], volume))
+		.is_ok()
+}
+
+fn release(market: MarketId, order: &Order, amount: Balance) {
+	match order.side {
+		OrderSide::Buy => {
+			// Cannot overflow, the same product has been computed when placing the order.
+			Balances::unreserve(&order.owner, amount.saturating_mul(order.limit_price));
+		},
+		OrderSide::Sell => {
+			let mut balance = base_balance(market, &order.owner);
+			balance.reserved -= amount;
+			balance.free += amount;
+			put_base_balance(market, &order.owner, &balance);
+		},
+	}
+}
+
+/// Determines the uniform clearing price and the resulting fills for a set of orders.
+///
+/// The clearing price is the limit price that maximizes the matched volume, ties are broken
+/// in favour of the lower price. Within each side, orders are filled by price priority
+/// first and time priority (order id) second. Returns `None` if no order can be matched.
+pub fn uniform_price_match(orders: &[Order]) -> Option<(Balance, Vec<Fill>)> {
+	let mut bids: Vec<&Order> = orders.iter().filter(|o| o.side == OrderSide::Buy).collect();
+	let mut asks: Vec<&Order> = orders.iter().filter(|o| o.side == OrderSide::Sell).collect();
+	bids.sort_by(|a, b| b.limit_price.cmp(&a.limit_price).then(a.id.cmp(&b.id)));
+	asks.sort_by(|a, b| a.limit_price.cmp(&b.limit_price).then(a.id.cmp(&b.id)));
+
+	let mut best: Option<(Balance, Balance)> = None;
+	for price in orders.iter().map(|o| o.limit_price) {
+		let demand = total_amount(bids.iter().filter(|o| o.limit_price >= price));
+		let supply = total_amount(asks.iter().filter(|o| o.limit_price <= price));
+		let volume = demand.min(supply);
+		if volume == 0 {
+			continue
+		}
+		match best {
+			Some((best_price, best_volume))
+				if best_volume > volume || (best_volume == volume && best_price <= price) => {},
+			_ => best = Some((price, volume)),
+		}
+	}
+
+	let (price, volume) = best?;
+	let mut fills = Vec::new();
+	fill_side(bids.iter().filter(|o| o.limit_price >= price), volume, &mut fills);
+	fill_side(asks.iter().filter(|o| o.limit_price <= price), volume, &mut fills);
+	Some((price, fills))
+}
+
+fn total_amount<'a>(orders: impl Iterator<Item = &'a &'a Order>) -> Balance {
+	orders.fold(0, |acc, o| acc.saturating_add(o.amount))
+}
+
+fn fill_side<'a>(
+	orders: impl Iterator<Item = &'a &'a Order>,
+	volume: Balance,
+	fills: &mut Vec<Fill>,
+) {
+	let mut remaining = volume;
+	for order in orders {
+		if remaining == 0 {
+			break
+		}
+		let amount = remaining.min(order.amount);
+		fills.push(Fill { order_id: order.id, amount });
+		remaining -= amount;
+	}
+}
+
+pub(crate) fn quote_amount(amount: Balance, price: Balance) -> StfResult<Balance> {
+	amount
+		.checked_mul(price)
+		.ok_or_else(|| StfError::Dispatch("order volume overflows".into()))
+}
+
+fn put_orders(market: MarketId, market_orders: Vec<Order>) {
+	if market_orders.is_empty() {
+		kill_storage_map(ORDER_BOOK, ORDERS, &market, &StorageHasher::Blake2_128Concat);
+		let mut markets: Vec<MarketId> =
+			get_storage_value(ORDER_BOOK, ACTIVE_MARKETS).unwrap_or_default();
+		markets.retain(|m| m != &market);
+		put_storage_value(ORDER_BOOK, ACTIVE_MARKETS, &markets);
+	} else {
+		put_storage_map(
+			ORDER_BOOK,
+			ORDERS,
+			&market,
+			&StorageHasher::Blake2_128Concat,
+			&market_orders,
+		);
+	}
+}
+
+fn put_base_balance(market: MarketId, who: &AccountId, balance: &BaseBalance) {
+	let key = storage_double_map_key(
+		ORDER_BOOK,
+		BASE_BALANCES,
+		&market,
+		&StorageHasher::Blake2_128Concat,
+		who,
+		&StorageHasher::Blake2_128Concat,
+	);
+	sp_io::storage::set(&key, &balance.encode());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn order(id: OrderId, side: OrderSide, amount: Balance, limit_price: Balance) -> Order {
+		Order { id, owner: AccountId::new([id as u8; 32]), side, amount, limit_price }
+	}
+
+	#[test]
+	fn no_match_if_all_bids_are_below_all_asks() {
+		let orders = vec![order(0, OrderSide::Buy, 10, 5), order(1, OrderSide::Sell, 10, 6)];
+		assert!(uniform_price_match(&orders).is_none());
+	}
+
+	#[test]
+	fn clearing_price_maximizes_matched_volume() {
+		let orders = vec![
+			order(0, OrderSide::Buy, 10, 12),
+			order(1, OrderSide::Buy, 10, 10),
+			order(2, OrderSide::Sell, 5, 9),
+			order(3, OrderSide::Sell, 20, 11),
+		];
+
+		let (price, fills) = uniform_price_match(&orders).unwrap();
+
+		// At 10, demand is 20 but supply only 5. At 11, demand is 10 and supply 25.
+		assert_eq!(price, 11);
+		assert_eq!(
+			fills,
+			vec![
+				Fill { order_id: 0, amount: 10 },
+				Fill { order_id: 2, amount: 5 },
+				Fill { order_id: 3, amount: 5 },
+			]
+		);
+	}
+
+	#[test]
+	fn equal_prices_are_filled_in_time_priority() {
+		let orders = vec![
+			order(0, OrderSide::Sell, 10, 5),
+			order(1, OrderSide::Sell, 10, 5),
+			order(2, OrderSide::Buy, 15, 5),
+		];
+
+		let (price, fills) = uniform_price_match(&orders).unwrap();
+
+		assert_eq!(price, 5);
+		assert_eq!(
+			fills,
+			vec![
+				Fill { order_id: 2, amount: 15 },
+				Fill { order_id: 0, amount: 10 },
+				Fill { order_id: 1, amount: 5 },
+			]
+		);
+	}
+}
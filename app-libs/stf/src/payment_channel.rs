@@ -0,0 +1,229 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Streaming payment channels between shard accounts.
+//!
+//! The payer reserves a deposit when opening a channel. At the start of every sidechain
+//! block, the channel's rate is streamed from that deposit to the payee until the deposit is
+//! used up or either party closes the channel, which returns the unstreamed rest to the payer.
+//!
+//! A channel is one balance movement for the compliance gate, of its total deposit. Streaming
+//! pauses while either party is not compliant for that volume.
+
+use crate::{
+	compliance,
+	helpers::{
+		get_storage_map, get_storage_value, kill_storage_map, put_storage_map, put_storage_value,
+	},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::{BalanceStatus, ReservableCurrency};
+use ita_sgx_runtime::{Balance, Balances, System};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use itp_types::BlockNumber;
+use itp_utils::stringify::account_id_to_string;
+use log::*;
+use std::{format, vec::Vec};
+
+pub(crate) const PAYMENT_CHANNELS: &str = "PaymentChannels";
+const NEXT_CHANNEL_ID: &str = "NextChannelId";
+const CHANNELS: &str = "Channels";
+const OPEN_CHANNELS: &str = "OpenChannels";
+
+pub type ChannelId = u32;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Channel {
+	pub payer: AccountId,
+	pub payee: AccountId,
+	/// Amount streamed to the payee per sidechain block.
+	pub rate: Balance,
+	/// Reserved deposit of the payer that has not been streamed yet.
+	pub remaining: Balance,
+	/// Total amount streamed to the payee so far.
+	pub streamed: Balance,
+	pub opened_at: BlockNumber,
+}
+
+impl Channel {
+	/// Total deposit of the channel, streamed or not.
+	pub fn volume(&self) -> Balance {
+		self.remaining.saturating_add(self.streamed)
+	}
+}
+
+pub fn channel(channel_id: ChannelId) -> Option<Channel> {
+	get_storage_map(PAYMENT_CHANNELS, CHANNELS, &channel_id, &StorageHasher::Blake2_128Concat)
+}
+
+/// The channel, if `who` is one of its parties.
+pub fn channel_of(channel_id: ChannelId, who: &AccountId) -> Option<Channel> {
+	channel(channel_id).filter(|c| &c.payer == who || &c.payee == who)
+}
+
+pub fn open(
+	payer: AccountId,
+	payee: AccountId,
+	rate: Balance,
+	deposit: Balance,
+) -> StfResult<ChannelId> {
+	if rate == 0 {
+		return Err(StfError::Dispatch("channel rate must not be zero".into()))
+	}
+	if payer == payee {
+		return Err(StfError::Dispatch("cannot open a channel to self".into()))
+	}
+	compliance::ensure_compliant(&[&payer, &payee], deposit)?;
+	reserve(&payer, deposit)?;
+
+	let channel_id: ChannelId =
+		get_storage_value(PAYMENT_CHANNELS, NEXT_CHANNEL_ID).unwrap_or_default();
+	put_storage_value(PAYMENT_CHANNELS, NEXT_CHANNEL_ID, &(channel_id + 1));
+	let mut open_channels = open_channels();
+	open_channels.push(channel_id);
+	put_storage_value(PAYMENT_CHANNELS, OPEN_CHANNELS, &open_channels);
+	put_channel(
+		channel_id,
+		&Channel {
+			payer,
+			payee,
+			rate,
+			remaining: deposit,
+			streamed: 0,
+			opened_at: System::block_number(),
+		},
+	);
+	Ok(channel_id)
+}
+
+/// Adds to the deposit of a channel. Only the payer can top up.
+pub fn top_up(who: &AccountId, channel_id: ChannelId, amount: Balance) -> StfResult<()> {
+	let mut channel = channel(channel_id)
+		.filter(|c| &c.payer == who)
+		.ok_or_else(|| StfError::Dispatch(format!("no channel {} paid by caller", channel_id)))?;
+	compliance::ensure_compliant(
+		&[&channel.payer, &channel.payee],
+		channel.volume().saturating_add(amount),
+	)?;
+	reserve(who, amount)?;
+	channel.remaining += amount;
+	put_channel(channel_id, &channel);
+	Ok(())
+}
+
+/// Closes a channel and returns the unstreamed deposit to the payer. Either party can close.
+pub fn close(who: &AccountId, channel_id: ChannelId) -> StfResult<()> {
+	let channel = channel_of(channel_id, who)
+		.ok_or_else(|| StfError::Dispatch(format!("no channel {} of caller", channel_id)))?;
+	Balances::unreserve(&channel.payer, channel.remaining);
+	remove_channel(channel_id);
+	Ok(())
+}
+
+/// Streams one block's worth of payments on all open channels. Called once at the start of
+/// every sidechain block.
+pub fn on_initialize() {
+	for channel_id in open_channels() {
+		let mut channel = match channel(channel_id) {
+			Some(c) => c,
+			None => continue,
+		};
+		if let Err(e) =
+			compliance::ensure_compliant(&[&channel.payer, &channel.payee], channel.volume())
+		{
+			warn!("not streaming on payment channel {}: {:?}", channel_id, e);
+			continue
+		}
+		let amount = channel.rate.min(channel.remaining);
+		match Balances::repatriate_reserved(
+			&channel.payer,
+			&channel.payee,
+			amount,
+			BalanceStatus::Free,
+		) {
+			Ok(unmoved) => {
+				let moved = amount.saturating_sub(unmoved);
+				channel.remaining -= moved;
+				channel.streamed += moved;
+				if unmoved > 0 {
+					// The payer's reserved balance does not cover the deposit anymore. Streaming
+					// cannot continue, so the channel is closed and the rest is released.
+					error!(
+						"payment channel {} is short of {} reserved by {}, closing it",
+						channel_id,
+						unmoved,
+						account_id_to_string(&channel.payer)
+					);
+					Balances::unreserve(&channel.payer, channel.remaining);
+					remove_channel(channel_id);
+					continue
+				}
+			},
+			Err(e) => {
+				// E.g. the payment would not bring a new payee account above the existential
+				// deposit. The payment is retried in the next block.
+				warn!(
+					"could not stream {} to {} on channel {}: {:?}",
+					amount,
+					account_id_to_string(&channel.payee),
+					channel_id,
+					e
+				);
+				continue
+			},
+		}
+
+		if channel.remaining == 0 {
+			debug!("payment channel {} is exhausted", channel_id);
+			remove_channel(channel_id);
+		} else {
+			put_channel(channel_id, &channel);
+		}
+	}
+}
+
+fn reserve(who: &AccountId, amount: Balance) -> StfResult<()> {
+	Balances::reserve(who, amount).map_err(|e| {
+		debug!("could not reserve deposit of {}: {:?}", account_id_to_string(who), e);
+		StfError::MissingFunds
+	})
+}
+
+fn open_channels() -> Vec<ChannelId> {
+	get_storage_value(PAYMENT_CHANNELS, OPEN_CHANNELS).unwrap_or_default()
+}
+
+fn put_channel(channel_id: ChannelId, channel: &Channel) {
+	put_storage_map(
+		PAYMENT_CHANNELS,
+		CHANNELS,
+		&channel_id,
+		&StorageHasher::Blake2_128Concat,
+		channel,
+	);
+}
+
+fn remove_channel(channel_id: ChannelId) {
+	kill_storage_map(PAYMENT_CHANNELS, CHANNELS, &channel_id, &StorageHasher::Blake2_128Concat);
+	let mut open_channels = open_channels();
+	open_channels.retain(|id| *id != channel_id);
+	put_storage_value(PAYMENT_CHANNELS, OPEN_CHANNELS, &open_channels);
+}
@@ -0,0 +1,165 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Proxy accounts with scoped permissions, similar to Substrate's proxy pallet.
+//!
+//! An account can authorize a delegate to send trusted calls on its behalf. Each
+//! authorization is scoped by a [`ProxyType`], which is checked against the wrapped call
+//! before it is executed.
+
+use crate::{
+	helpers::{get_storage_map, kill_storage_map, put_storage_map},
+	TrustedCall,
+};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::Balance;
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::StorageHasher;
+use std::vec::Vec;
+
+pub(crate) const PROXY: &str = "Proxy";
+const PROXIES: &str = "Proxies";
+
+pub const MAX_PROXIES: usize = 32;
+
+/// Class of calls a delegate may send on behalf of the delegator.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum ProxyType {
+	/// All calls, except for further proxy calls.
+	Any,
+	/// Balance transfers and unshields of at most the given amount per call.
+	Transfer(Balance),
+	/// Placing and cancelling orders, bidding on auctions.
+	Trading,
+	/// Casting ballots.
+	Voting,
+}
+
+impl ProxyType {
+	pub fn allows(&self, call: &TrustedCall) -> bool {
+		if matches!(call, TrustedCall::proxy_call(..)) {
+			return false
+		}
+		match self {
+			ProxyType::Any => true,
+			ProxyType::Transfer(limit) => match call {
+				TrustedCall::balance_transfer(_, _, amount)
+				| TrustedCall::balance_unshield(_, _, amount, _) => amount <= limit,
+				_ => false,
+			},
+			ProxyType::Trading => matches!(
+				call,
+				TrustedCall::order_book_place_order(..)
+					| TrustedCall::order_book_cancel_order(..)
+					| TrustedCall::auction_bid(..)
+			),
+			ProxyType::Voting => matches!(call, TrustedCall::voting_cast_ballot(..)),
+		}
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ProxyDefinition {
+	pub delegate: AccountId,
+	pub proxy_type: ProxyType,
+}
+
+pub fn proxies(delegator: &AccountId) -> Vec<ProxyDefinition> {
+	get_storage_map(PROXY, PROXIES, delegator, &StorageHasher::Blake2_128Concat).unwrap_or_default()
+}
+
+/// Authorizes a delegate, replacing any previous authorization of the same delegate.
+pub fn add_proxy(
+	delegator: &AccountId,
+	delegate: AccountId,
+	proxy_type: ProxyType,
+) -> StfResult<()> {
+	if delegator == &delegate {
+		return Err(StfError::Dispatch("an account cannot proxy for itself".into()))
+	}
+	let mut proxies = proxies(delegator);
+	proxies.retain(|p| p.delegate != delegate);
+	if proxies.len() >= MAX_PROXIES {
+		return Err(StfError::Dispatch("too many proxies".into()))
+	}
+	proxies.push(ProxyDefinition { delegate, proxy_type });
+	put_proxies(delegator, proxies);
+	Ok(())
+}
+
+pub fn remove_proxy(delegator: &AccountId, delegate: &AccountId) -> StfResult<()> {
+	let mut proxies = proxies(delegator);
+	let len = proxies.len();
+	proxies.retain(|p| &p.delegate != delegate);
+	if proxies.len() == len {
+		return Err(StfError::Dispatch("no such proxy".into()))
+	}
+	put_proxies(delegator, proxies);
+	Ok(())
+}
+
+/// Ensures `delegate` may send `call`, whose sender is the delegator.
+pub fn ensure_allowed(delegate: &AccountId, call: &TrustedCall) -> StfResult<()> {
+	let allowed = proxies(call.sender_account())
+		.iter()
+		.any(|p| &p.delegate == delegate && p.proxy_type.allows(call));
+	if !allowed {
+		return Err(StfError::MissingPrivileges(delegate.clone()))
+	}
+	Ok(())
+}
+
+fn put_proxies(delegator: &AccountId, proxies: Vec<ProxyDefinition>) {
+	if proxies.is_empty() {
+		kill_storage_map(PROXY, PROXIES, delegator, &StorageHasher::Blake2_128Concat);
+	} else {
+		put_storage_map(PROXY, PROXIES, delegator, &StorageHasher::Blake2_128Concat, &proxies);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_stf_primitives::types::ShardIdentifier;
+	use sp_core::H256;
+
+	#[test]
+	fn transfer_proxy_is_limited_to_transfers_up_to_limit() {
+		let who = AccountId::new([1u8; 32]);
+		let proxy_type = ProxyType::Transfer(100);
+		assert!(proxy_type.allows(&TrustedCall::balance_transfer(who.clone(), who.clone(), 100)));
+		assert!(!proxy_type.allows(&TrustedCall::balance_transfer(who.clone(), who.clone(), 101)));
+		assert!(!proxy_type.allows(&TrustedCall::balance_unshield(
+			who.clone(),
+			who.clone(),
+			101,
+			ShardIdentifier::default()
+		)));
+		assert!(!proxy_type.allows(&TrustedCall::voting_cast_ballot(who, 0, 0, H256::default())));
+	}
+
+	#[test]
+	fn proxy_calls_cannot_be_nested() {
+		let who = AccountId::new([1u8; 32]);
+		let call =
+			TrustedCall::proxy_call(who.clone(), who.clone(), Box::new(TrustedCall::noop(who)));
+		assert!(!ProxyType::Any.allows(&call));
+	}
+}
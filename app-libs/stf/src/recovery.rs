@@ -0,0 +1,213 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Social recovery of shard accounts.
+//!
+//! An account registers a set of guardians, an approval threshold and a delay. If its key is
+//! lost, a rescuer initiates a recovery, which the guardians approve. Once the threshold is met
+//! and the delay has passed since initiation, the rescuer can claim the account's free balance.
+//! As long as the original key is still available, the account can cancel any recovery attempt.
+
+use crate::{
+	compliance,
+	helpers::{get_storage_double_map, get_storage_map, kill_storage_map, put_storage_map},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::{Currency, ExistenceRequirement};
+use ita_sgx_runtime::{Balances, System};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::{storage_double_map_key, StorageHasher};
+use itp_types::BlockNumber;
+use itp_utils::stringify::account_id_to_string;
+use log::*;
+use std::{format, vec::Vec};
+
+pub(crate) const RECOVERY: &str = "Recovery";
+const CONFIGS: &str = "Configs";
+const ATTEMPTS: &str = "Attempts";
+const RECOVERED: &str = "Recovered";
+
+pub const MAX_GUARDIANS: usize = 16;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryConfig {
+	/// Sorted, without duplicates.
+	pub guardians: Vec<AccountId>,
+	pub threshold: u16,
+	/// Number of sidechain blocks between initiation and a possible claim.
+	pub delay: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryAttempt {
+	pub initiated_at: BlockNumber,
+	pub approvals: Vec<AccountId>,
+}
+
+pub fn config(who: &AccountId) -> Option<RecoveryConfig> {
+	get_storage_map(RECOVERY, CONFIGS, who, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn attempt(lost: &AccountId, rescuer: &AccountId) -> Option<RecoveryAttempt> {
+	get_storage_double_map(
+		RECOVERY,
+		ATTEMPTS,
+		lost,
+		&StorageHasher::Blake2_128Concat,
+		rescuer,
+		&StorageHasher::Blake2_128Concat,
+	)
+}
+
+/// Account that has successfully recovered `lost`, if any.
+pub fn recovered_by(lost: &AccountId) -> Option<AccountId> {
+	get_storage_map(RECOVERY, RECOVERED, lost, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn create_config(
+	who: &AccountId,
+	mut guardians: Vec<AccountId>,
+	threshold: u16,
+	delay: BlockNumber,
+) -> StfResult<()> {
+	guardians.sort();
+	guardians.dedup();
+	if guardians.is_empty() || guardians.len() > MAX_GUARDIANS {
+		return Err(StfError::Dispatch(format!("between 1 and {} guardians needed", MAX_GUARDIANS)))
+	}
+	if threshold == 0 || threshold as usize > guardians.len() {
+		return Err(StfError::Dispatch(format!("invalid threshold {}", threshold)))
+	}
+	if guardians.contains(who) {
+		return Err(StfError::Dispatch("an account cannot guard itself".into()))
+	}
+	put_storage_map(
+		RECOVERY,
+		CONFIGS,
+		who,
+		&StorageHasher::Blake2_128Concat,
+		&RecoveryConfig { guardians, threshold, delay },
+	);
+	Ok(())
+}
+
+pub fn remove_config(who: &AccountId) -> StfResult<()> {
+	config(who).ok_or_else(|| not_recoverable(who))?;
+	kill_storage_map(RECOVERY, CONFIGS, who, &StorageHasher::Blake2_128Concat);
+	Ok(())
+}
+
+pub fn initiate(rescuer: &AccountId, lost: &AccountId) -> StfResult<()> {
+	config(lost).ok_or_else(|| not_recoverable(lost))?;
+	if attempt(lost, rescuer).is_some() {
+		return Err(StfError::Dispatch("recovery has already been initiated".into()))
+	}
+	put_attempt(
+		lost,
+		rescuer,
+		&RecoveryAttempt { initiated_at: System::block_number(), approvals: Vec::new() },
+	);
+	Ok(())
+}
+
+pub fn approve(guardian: &AccountId, lost: &AccountId, rescuer: &AccountId) -> StfResult<()> {
+	let config = config(lost).ok_or_else(|| not_recoverable(lost))?;
+	if config.guardians.binary_search(guardian).is_err() {
+		return Err(StfError::MissingPrivileges(guardian.clone()))
+	}
+	let mut attempt = attempt(lost, rescuer).ok_or_else(no_attempt)?;
+	if let Err(index) = attempt.approvals.binary_search(guardian) {
+		attempt.approvals.insert(index, guardian.clone());
+		put_attempt(lost, rescuer, &attempt);
+	}
+	Ok(())
+}
+
+/// Called by the lost account itself to abort a recovery it did not ask for.
+pub fn cancel(lost: &AccountId, rescuer: &AccountId) -> StfResult<()> {
+	attempt(lost, rescuer).ok_or_else(no_attempt)?;
+	kill_attempt(lost, rescuer);
+	Ok(())
+}
+
+/// Completes the recovery and moves the free balance of `lost` to the rescuer. Once an
+/// account has been recovered, its rescuer can claim again to sweep funds that became free
+/// later on, e.g. when reservations have been released.
+pub fn claim(rescuer: &AccountId, lost: &AccountId) -> StfResult<()> {
+	let free = Balances::free_balance(lost);
+	compliance::ensure_compliant(&[rescuer, lost], free)?;
+	match recovered_by(lost) {
+		Some(ref r) if r == rescuer => {},
+		Some(_) => return Err(StfError::Dispatch("account has already been recovered".into())),
+		None => {
+			let config = config(lost).ok_or_else(|| not_recoverable(lost))?;
+			let attempt = attempt(lost, rescuer).ok_or_else(no_attempt)?;
+			if attempt.approvals.len() < config.threshold as usize {
+				return Err(StfError::Dispatch("not enough guardian approvals".into()))
+			}
+			if System::block_number() < attempt.initiated_at + config.delay {
+				return Err(StfError::Dispatch("recovery delay has not passed yet".into()))
+			}
+			info!(
+				"account {} has been recovered by {}",
+				account_id_to_string(lost),
+				account_id_to_string(rescuer)
+			);
+			kill_attempt(lost, rescuer);
+			kill_storage_map(RECOVERY, CONFIGS, lost, &StorageHasher::Blake2_128Concat);
+			put_storage_map(RECOVERY, RECOVERED, lost, &StorageHasher::Blake2_128Concat, rescuer);
+		},
+	}
+
+	<Balances as Currency<AccountId>>::transfer(
+		lost,
+		rescuer,
+		free,
+		ExistenceRequirement::AllowDeath,
+	)
+	.map_err(|e| StfError::Dispatch(format!("Recovery transfer error: {:?}", e)))
+}
+
+fn put_attempt(lost: &AccountId, rescuer: &AccountId, attempt: &RecoveryAttempt) {
+	sp_io::storage::set(&attempt_key(lost, rescuer), &attempt.encode());
+}
+
+fn kill_attempt(lost: &AccountId, rescuer: &AccountId) {
+	sp_io::storage::clear(&attempt_key(lost, rescuer));
+}
+
+fn attempt_key(lost: &AccountId, rescuer: &AccountId) -> Vec<u8> {
+	storage_double_map_key(
+		RECOVERY,
+		ATTEMPTS,
+		lost,
+		&StorageHasher::Blake2_128Concat,
+		rescuer,
+		&StorageHasher::Blake2_128Concat,
+	)
+}
+
+fn not_recoverable(who: &AccountId) -> StfError {
+	StfError::Dispatch(format!("account {} is not recoverable", account_id_to_string(who)))
+}
+
+fn no_attempt() -> StfError {
+	StfError::Dispatch("no such recovery attempt".into())
+}
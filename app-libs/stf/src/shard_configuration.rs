@@ -0,0 +1,77 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Effective runtime configuration of the shard, as returned by the `shard_configuration` getter.
+//!
+//! Validateers publish the hash of their configuration on the Integritee parentchain together
+//! with every sidechain block import confirmation. A client detects a validateer running with a
+//! divergent configuration by comparing the published hashes with the hash of the getter result.
+
+use crate::{
+	feature_flags::{self, FeatureFlagStatus},
+	fees::{TRANSFER_FEE, UNSHIELD_FEE},
+	state_hash,
+};
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use ita_sgx_runtime::{Balance, ExistentialDeposit};
+use itp_hashing::hash_function::StateHashAlgorithm;
+use itp_settings::{
+	files::{SIDECHAIN_PURGE_LIMIT, STATE_SNAPSHOTS_CACHE_SIZE},
+	sidechain::SLOT_DURATION,
+	worker::BLOCK_NUMBER_FINALIZATION_DIFF,
+};
+use itp_types::H256;
+use sp_core::blake2_256;
+use std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ShardConfiguration {
+	pub transfer_fee: Balance,
+	pub unshield_fee: Balance,
+	pub existential_deposit: Balance,
+	/// Duration of a sidechain slot in milliseconds, which bounds the execution time of a block.
+	pub slot_duration_ms: u64,
+	/// Number of sidechain blocks between two block import confirmations on the parentchain.
+	pub finalization_block_diff: u64,
+	pub feature_flags: Vec<FeatureFlagStatus>,
+	pub state_hash_algorithm: StateHashAlgorithm,
+	/// Number of state snapshots kept per shard.
+	pub state_snapshots_retained: u64,
+	/// Number of sidechain blocks kept when the sidechain database is purged.
+	pub sidechain_blocks_retained: u64,
+}
+
+impl ShardConfiguration {
+	pub fn hash(&self) -> H256 {
+		blake2_256(&self.encode()).into()
+	}
+}
+
+pub fn current() -> ShardConfiguration {
+	ShardConfiguration {
+		transfer_fee: TRANSFER_FEE,
+		unshield_fee: UNSHIELD_FEE,
+		existential_deposit: ExistentialDeposit::get(),
+		slot_duration_ms: SLOT_DURATION.as_millis() as u64,
+		finalization_block_diff: BLOCK_NUMBER_FINALIZATION_DIFF,
+		feature_flags: feature_flags::status(),
+		state_hash_algorithm: state_hash::algorithm(),
+		state_snapshots_retained: STATE_SNAPSHOTS_CACHE_SIZE as u64,
+		sidechain_blocks_retained: SIDECHAIN_PURGE_LIMIT,
+	}
+}
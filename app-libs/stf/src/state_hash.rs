@@ -0,0 +1,32 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Hash function of the state hash of the shard, chosen by root.
+//!
+//! Switching the algorithm changes the state hash from the block that executes the call on, so
+//! verifiers of the state commitments have to know of the switch.
+
+use crate::helpers::{get_storage_value, put_storage_value};
+use itp_hashing::hash_function::{StateHashAlgorithm, ALGORITHM, STATE_HASH};
+
+pub fn algorithm() -> StateHashAlgorithm {
+	get_storage_value(STATE_HASH, ALGORITHM).unwrap_or_default()
+}
+
+pub fn set_algorithm(algorithm: StateHashAlgorithm) {
+	put_storage_value(STATE_HASH, ALGORITHM, &algorithm);
+}
@@ -0,0 +1,149 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! State migrations scheduled by the governance of the Integritee parentchain.
+//!
+//! The parentchain announces a migration for a shard with a `StateMigrationScheduled` event,
+//! which the bridge pallet only emits for its governance origin. The enclave schedules it with
+//! an indirect call signed by the enclave account. The migration routine runs once, before the
+//! block hooks of the first sidechain block proposed on top of the activation parentchain block,
+//! and is recorded together with the hash of the migrated state.
+
+use crate::{
+	feature_flags,
+	helpers::{get_storage_value, put_storage_value},
+};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::{ParentchainIntegritee, System};
+use itp_sgx_externalities::{SgxExternalitiesTrait, StateHash};
+use itp_stf_primitives::error::{StfError, StfResult};
+use itp_types::{BlockNumber, H256};
+use log::*;
+use std::{format, string::String, vec::Vec};
+
+pub(crate) const STATE_MIGRATIONS: &str = "StateMigrations";
+const SCHEDULED: &str = "Scheduled";
+const RECORDS: &str = "Records";
+
+/// Registered migration routines by name. A routine must only depend on the state, so it yields
+/// the same migrated state in every enclave.
+const MIGRATIONS: &[(&str, fn())] = &[("reset_feature_flags", feature_flags::reset)];
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledMigration {
+	pub migration: Vec<u8>,
+	/// Integritee parentchain block from which on the migration is run.
+	pub activation_block: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct MigrationRecord {
+	pub migration: Vec<u8>,
+	pub parentchain_block: BlockNumber,
+	/// Sidechain block proposed on the migrated state.
+	pub sidechain_block: BlockNumber,
+	/// Hash of the state right after the migration routine has run.
+	pub state_hash: H256,
+}
+
+/// Scheduled and conducted migrations, as returned by the `state_migrations` getter.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateMigrations {
+	/// Ordered by activation block.
+	pub scheduled: Vec<ScheduledMigration>,
+	pub records: Vec<MigrationRecord>,
+}
+
+pub fn status() -> StateMigrations {
+	StateMigrations { scheduled: scheduled(), records: records() }
+}
+
+/// Schedules a registered migration, each migration runs at most once.
+pub fn schedule(migration: Vec<u8>, activation_block: BlockNumber) -> StfResult<()> {
+	let name = String::from_utf8_lossy(&migration).into_owned();
+	if routine(&migration).is_none() {
+		return Err(StfError::Dispatch(format!("unknown state migration {}", name)))
+	}
+	let mut scheduled = scheduled();
+	if scheduled.iter().any(|s| s.migration == migration)
+		|| records().iter().any(|r| r.migration == migration)
+	{
+		return Err(StfError::Dispatch(format!("state migration {} is already scheduled", name)))
+	}
+	let index = scheduled.partition_point(|s| s.activation_block <= activation_block);
+	scheduled.insert(index, ScheduledMigration { migration, activation_block });
+	put_storage_value(STATE_MIGRATIONS, SCHEDULED, &scheduled);
+	Ok(())
+}
+
+/// Runs the migrations that are due at the latest imported Integritee parentchain block and
+/// records them. Must be called on the state a new sidechain block is proposed on, after the
+/// new block number has been set.
+pub fn run_due_migrations<State: SgxExternalitiesTrait + StateHash>(state: &mut State) {
+	let (parentchain_block, due) = state.execute_with(|| {
+		let parentchain_block = ParentchainIntegritee::block_number().unwrap_or_default();
+		let (due, pending): (Vec<_>, Vec<_>) =
+			scheduled().into_iter().partition(|s| s.activation_block <= parentchain_block);
+		if !due.is_empty() {
+			put_storage_value(STATE_MIGRATIONS, SCHEDULED, &pending);
+		}
+		(parentchain_block, due)
+	});
+
+	for scheduled in due {
+		let name = String::from_utf8_lossy(&scheduled.migration).into_owned();
+		// Routines are checked when scheduling, but an enclave upgrade may have dropped one.
+		let routine = match routine(&scheduled.migration) {
+			Some(routine) => routine,
+			None => {
+				error!("Skipping state migration {}, it is not registered", name);
+				continue
+			},
+		};
+		state.execute_with(routine);
+		let state_hash = state.hash();
+		state.execute_with(|| {
+			let mut records = records();
+			records.push(MigrationRecord {
+				migration: scheduled.migration,
+				parentchain_block,
+				sidechain_block: System::block_number(),
+				state_hash,
+			});
+			put_storage_value(STATE_MIGRATIONS, RECORDS, &records);
+		});
+		info!(
+			"Ran state migration {} at parentchain block {}: {:?}",
+			name, parentchain_block, state_hash
+		);
+	}
+}
+
+fn routine(migration: &[u8]) -> Option<fn()> {
+	MIGRATIONS
+		.iter()
+		.find(|(name, _)| name.as_bytes() == migration)
+		.map(|(_, routine)| *routine)
+}
+
+fn scheduled() -> Vec<ScheduledMigration> {
+	get_storage_value(STATE_MIGRATIONS, SCHEDULED).unwrap_or_default()
+}
+
+fn records() -> Vec<MigrationRecord> {
+	get_storage_value(STATE_MIGRATIONS, RECORDS).unwrap_or_default()
+}
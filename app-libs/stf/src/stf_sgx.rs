@@ -33,7 +33,7 @@ use itp_stf_interface::{
 	sudo_pallet::SudoPalletInterface,
 	system_pallet::{SystemPalletAccountInterface, SystemPalletEventInterface},
 	ExecuteCall, ExecuteGetter, InitState, ShardCreationInfo, ShardCreationQuery, ShardVaultQuery,
-	StateCallInterface, StateGetterInterface, UpdateState,
+	StateCallInterface, StateCallValidation, StateGetterInterface, UpdateState, ValidateCall,
 };
 use itp_stf_primitives::{error::StfError, traits::TrustedCallVerification};
 use itp_storage::storage_value_key;
@@ -157,6 +157,19 @@ where
 	}
 }
 
+impl<TCS, G, State, Runtime> StateCallValidation<TCS, State> for Stf<TCS, G, State, Runtime>
+where
+	TCS: ValidateCall,
+	TCS::Error: Debug,
+	State: SgxExternalitiesTrait + Debug,
+{
+	type Error = TCS::Error;
+
+	fn validate_call(state: &mut State, call: &TCS) -> Result<(), Self::Error> {
+		state.execute_with(|| call.validate())
+	}
+}
+
 impl<TCS, G, State, Runtime> StateGetterInterface<G, State> for Stf<TCS, G, State, Runtime>
 where
 	G: PartialEq + ExecuteGetter,
@@ -15,20 +15,46 @@
 
 */
 
-use crate::{Getter, State, Stf, TrustedCall, TrustedCallSigned};
-use ita_sgx_runtime::Runtime;
+use crate::{
+	aggregate_privacy::{self, Aggregate, AggregatePrivacy},
+	auction, block_hooks,
+	compliance::{self, ComplianceStatus},
+	deposit_address,
+	event_disclosure::{self, DisclosurePolicy},
+	feature_flags::{self, Feature},
+	helpers::set_block_number,
+	multisig,
+	order_book::{self, BaseBalance, OrderSide},
+	payment_channel,
+	proxy::ProxyType,
+	recovery, shard_configuration, state_hash, state_migration,
+	test_genesis::{endowed_account, second_endowed_account, unendowed_account},
+	voting, Getter, State, Stf, TrustedCall, TrustedCallSigned,
+};
+use codec::Encode;
+use frame_support::traits::ReservableCurrency;
+use ita_sgx_runtime::{Balances, OrderBookEvent, Runtime, RuntimeEvent, System};
+use itp_hashing::hash_function::StateHashAlgorithm;
 use itp_node_api::metadata::{metadata_mocks::NodeMetadataMock, provider::NodeMetadataRepository};
+use itp_sgx_externalities::{SgxExternalitiesTrait, StateHash};
 use itp_stf_interface::{
-	parentchain_pallet::ParentchainPalletInstancesInterface, sudo_pallet::SudoPalletInterface,
-	system_pallet::SystemPalletAccountInterface, InitState, StateCallInterface,
+	parentchain_pallet::ParentchainPalletInstancesInterface,
+	sudo_pallet::SudoPalletInterface,
+	system_pallet::{SystemPalletAccountInterface, SystemPalletEventInterface},
+	InitState, StateCallInterface, StateCallValidation,
 };
 use itp_stf_primitives::types::{AccountId, Signature};
-use itp_types::parentchain::ParentchainId;
+use itp_types::{
+	parentchain::{Header, ParentchainId},
+	H256,
+};
 use sp_core::{
 	ed25519::{Pair as Ed25519Pair, Signature as Ed25519Signature},
 	Pair,
 };
-use std::{sync::Arc, vec::Vec};
+use sp_io::hashing::{blake2_256, sha2_256};
+use sp_runtime::traits::Header as HeaderT;
+use std::{boxed::Box, sync::Arc, vec, vec::Vec};
 
 pub type StfState = Stf<TrustedCallSigned, Getter, State, Runtime>;
 
@@ -74,3 +100,721 @@ pub fn test_root_account_exists_after_initialization() {
 	let account_data = StfState::get_account_data(&mut state, &root_account);
 	assert!(account_data.free > 0);
 }
+
+pub fn transfer_above_compliance_threshold_requires_attestation() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let registrar = AccountId::new([3u8; 32]);
+	let sender: AccountId = endowed_account().public().into();
+	let receiver: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let set_registrar = unsigned_call(
+		TrustedCall::compliance_set_registrar(root, Some(registrar.clone()), 1000),
+		0,
+	);
+	StfState::execute_call(&mut state, set_registrar, &mut Vec::new(), repo.clone()).unwrap();
+
+	let transfer =
+		unsigned_call(TrustedCall::balance_transfer(sender.clone(), receiver.clone(), 2000), 0);
+	assert!(StfState::execute_call(&mut state, transfer, &mut Vec::new(), repo.clone()).is_err());
+
+	for (nonce, who) in [sender.clone(), receiver.clone()].into_iter().enumerate() {
+		let attest =
+			unsigned_call(TrustedCall::compliance_attest(registrar.clone(), who), nonce as u32);
+		StfState::execute_call(&mut state, attest, &mut Vec::new(), repo.clone()).unwrap();
+	}
+	assert!(matches!(
+		state.execute_with(|| compliance::status(&receiver)),
+		ComplianceStatus::Attested(_)
+	));
+
+	let transfer = unsigned_call(TrustedCall::balance_transfer(sender, receiver, 2000), 1);
+	StfState::execute_call(&mut state, transfer, &mut Vec::new(), repo).unwrap();
+}
+
+pub fn module_balance_movements_are_gated_on_compliance_when_validating() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let registrar = AccountId::new([3u8; 32]);
+	let payer: AccountId = endowed_account().public().into();
+	let payee: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let set_registrar =
+		unsigned_call(TrustedCall::compliance_set_registrar(root, Some(registrar), 1000), 0);
+	StfState::execute_call(&mut state, set_registrar, &mut Vec::new(), repo.clone()).unwrap();
+
+	let small_channel =
+		unsigned_call(TrustedCall::payment_channel_open(payer.clone(), payee.clone(), 10, 500), 0);
+	assert!(StfState::validate_call(&mut state, &small_channel).is_ok());
+
+	let large_channel =
+		unsigned_call(TrustedCall::payment_channel_open(payer.clone(), payee.clone(), 10, 2000), 0);
+	assert!(StfState::validate_call(&mut state, &large_channel).is_err());
+	assert!(
+		StfState::execute_call(&mut state, large_channel, &mut Vec::new(), repo.clone()).is_err()
+	);
+
+	let add_proxy = unsigned_call(
+		TrustedCall::proxy_add(payer.clone(), payee.clone(), ProxyType::Transfer(5000)),
+		1,
+	);
+	StfState::execute_call(&mut state, add_proxy, &mut Vec::new(), repo).unwrap();
+	let proxied_transfer = unsigned_call(
+		TrustedCall::proxy_call(
+			payee.clone(),
+			payer.clone(),
+			Box::new(TrustedCall::balance_transfer(payer, payee, 2000)),
+		),
+		0,
+	);
+	assert!(StfState::validate_call(&mut state, &proxied_transfer).is_err());
+
+	// Validation computes the volume like the execution, an overflow rejects the call.
+	let overflowing_order = unsigned_call(
+		TrustedCall::order_book_place_order(
+			AccountId::new([5u8; 32]),
+			0,
+			OrderSide::Bid,
+			u128::MAX,
+			2,
+		),
+		0,
+	);
+	assert!(StfState::validate_call(&mut state, &overflowing_order).is_err());
+}
+
+pub fn disabled_feature_rejects_calls_from_its_activation_block() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let payer: AccountId = endowed_account().public().into();
+	let payee: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+
+	let disable =
+		unsigned_call(TrustedCall::feature_flag_set(root, Feature::PaymentChannel, false, 3), 0);
+	StfState::execute_call(&mut state, disable, &mut Vec::new(), repo.clone()).unwrap();
+
+	let open =
+		unsigned_call(TrustedCall::payment_channel_open(payer.clone(), payee.clone(), 100, 250), 0);
+	StfState::execute_call(&mut state, open, &mut Vec::new(), repo.clone()).unwrap();
+
+	state.execute_with(|| set_block_number(3));
+	assert!(!state.execute_with(|| feature_flags::is_enabled(Feature::PaymentChannel)));
+	let top_up = unsigned_call(TrustedCall::payment_channel_top_up(payer.clone(), 0, 750), 1);
+	assert!(StfState::execute_call(&mut state, top_up, &mut Vec::new(), repo.clone()).is_err());
+
+	// Exit calls are not gated, the payer can still get the reserved funds back.
+	let close = unsigned_call(TrustedCall::payment_channel_close(payer.clone(), 0), 2);
+	StfState::execute_call(&mut state, close, &mut Vec::new(), repo).unwrap();
+	assert_eq!(state.execute_with(|| Balances::reserved_balance(&payer)), 0);
+}
+
+pub fn scheduled_state_migration_runs_once_at_its_activation_block() {
+	let enclave_account = AccountId::new([2u8; 32]);
+	let mut state = StfState::init_state(enclave_account.clone());
+	let root = StfState::get_root(&mut state);
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+	let parentchain_header = |number| {
+		Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			H256::default(),
+			Default::default(),
+		)
+	};
+
+	let disable = unsigned_call(TrustedCall::feature_flag_set(root, Feature::Voting, false, 1), 0);
+	StfState::execute_call(&mut state, disable, &mut Vec::new(), repo.clone()).unwrap();
+	let schedule = unsigned_call(
+		TrustedCall::state_migration_schedule(
+			enclave_account.clone(),
+			b"reset_feature_flags".to_vec(),
+			5,
+		),
+		0,
+	);
+	StfState::execute_call(&mut state, schedule, &mut Vec::new(), repo.clone()).unwrap();
+	let unknown = unsigned_call(
+		TrustedCall::state_migration_schedule(enclave_account, b"unknown".to_vec(), 5),
+		1,
+	);
+	assert!(StfState::execute_call(&mut state, unknown, &mut Vec::new(), repo).is_err());
+
+	StfState::update_parentchain_integritee_block(&mut state, parentchain_header(4)).unwrap();
+	state_migration::run_due_migrations(&mut state);
+	assert!(!state.execute_with(|| feature_flags::is_enabled(Feature::Voting)));
+
+	StfState::update_parentchain_integritee_block(&mut state, parentchain_header(5)).unwrap();
+	state.execute_with(|| set_block_number(2));
+	state_migration::run_due_migrations(&mut state);
+	assert!(state.execute_with(|| feature_flags::is_enabled(Feature::Voting)));
+
+	let migrations = state.execute_with(state_migration::status);
+	assert!(migrations.scheduled.is_empty());
+	assert_eq!(1, migrations.records.len());
+	assert_eq!(5, migrations.records[0].parentchain_block);
+	assert_eq!(2, migrations.records[0].sidechain_block);
+}
+
+pub fn aggregate_queries_are_noisy_and_limited_by_the_privacy_budget() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let who: AccountId = endowed_account().public().into();
+	let other: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+	let query = |state: &mut State, querier: &AccountId, aggregate| {
+		state.execute_with(|| aggregate_privacy::query(querier, aggregate))
+	};
+	let open = |querier: &AccountId, aggregate, nonce| {
+		unsigned_call(TrustedCall::aggregate_open(querier.clone(), aggregate), nonce)
+	};
+
+	let privacy = AggregatePrivacy { balance_noise: 1000, count_noise: 2, budget: 1, window: 10 };
+	let configure = unsigned_call(TrustedCall::aggregate_privacy_set(root, Some(privacy)), 0);
+	StfState::execute_call(&mut state, configure, &mut Vec::new(), repo.clone()).unwrap();
+
+	assert_eq!(None, query(&mut state, &who, Aggregate::AccountCount));
+	StfState::execute_call(
+		&mut state,
+		open(&who, Aggregate::AccountCount, 0),
+		&mut Vec::new(),
+		repo.clone(),
+	)
+	.unwrap();
+	let account_count = query(&mut state, &who, Aggregate::AccountCount);
+	assert!(account_count.is_some());
+	assert_eq!(account_count, query(&mut state, &who, Aggregate::AccountCount));
+
+	// The budget is kept in the state, it is used up for this window.
+	let over_budget = open(&who, Aggregate::AccountsHoldingAtLeast(1), 1);
+	assert!(StfState::execute_call(&mut state, over_budget, &mut Vec::new(), repo.clone()).is_err());
+	assert_eq!(None, query(&mut state, &who, Aggregate::AccountsHoldingAtLeast(1)));
+
+	// Other queriers have their own budget, but are answered the same noise.
+	assert_eq!(None, query(&mut state, &other, Aggregate::AccountCount));
+	let other_open = open(&other, Aggregate::AccountCount, 0);
+	StfState::execute_call(&mut state, other_open, &mut Vec::new(), repo.clone()).unwrap();
+	assert_eq!(account_count, query(&mut state, &other, Aggregate::AccountCount));
+
+	state.execute_with(|| set_block_number(10));
+	let next_window = open(&who, Aggregate::AccountsHoldingAtLeast(1), 2);
+	StfState::execute_call(&mut state, next_window, &mut Vec::new(), repo).unwrap();
+	assert!(query(&mut state, &who, Aggregate::AccountsHoldingAtLeast(1)).is_some());
+	assert_eq!(None, query(&mut state, &who, Aggregate::AccountCount));
+}
+
+pub fn state_is_hashed_with_the_algorithm_set_by_root() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let set_algorithm =
+		unsigned_call(TrustedCall::state_hash_algorithm_set(root, StateHashAlgorithm::Sha256), 0);
+	StfState::execute_call(&mut state, set_algorithm, &mut Vec::new(), repo).unwrap();
+
+	assert_eq!(StateHashAlgorithm::Sha256, state.execute_with(state_hash::algorithm));
+	assert_eq!(StateHash::hash(&state), state.state().using_encoded(sha2_256).into());
+}
+
+pub fn shard_configuration_hash_changes_with_the_configuration() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	let configuration = state.execute_with(shard_configuration::current);
+	assert_eq!(StateHashAlgorithm::Blake2b256, configuration.state_hash_algorithm);
+
+	let set_algorithm = unsigned_call(
+		TrustedCall::state_hash_algorithm_set(root, StateHashAlgorithm::Keccak256),
+		0,
+	);
+	StfState::execute_call(&mut state, set_algorithm, &mut Vec::new(), repo).unwrap();
+
+	let updated_configuration = state.execute_with(shard_configuration::current);
+	assert_eq!(StateHashAlgorithm::Keccak256, updated_configuration.state_hash_algorithm);
+	assert_ne!(configuration.hash(), updated_configuration.hash());
+}
+
+pub fn order_book_matches_orders_at_block_initialization() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let buyer: AccountId = endowed_account().public().into();
+	let seller: AccountId = second_endowed_account().public().into();
+	let market = 1;
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let calls = [
+		unsigned_call(
+			TrustedCall::order_book_set_base_balance(root.clone(), market, seller.clone(), 100),
+			0,
+		),
+		unsigned_call(
+			TrustedCall::order_book_place_order(buyer.clone(), market, OrderSide::Buy, 10, 50),
+			0,
+		),
+		unsigned_call(
+			TrustedCall::order_book_place_order(seller.clone(), market, OrderSide::Sell, 10, 40),
+			0,
+		),
+	];
+	for call in calls {
+		StfState::execute_call(&mut state, call, &mut Vec::new(), repo.clone()).unwrap();
+	}
+	let buyer_free_before = StfState::get_account_data(&mut state, &buyer).free;
+	let seller_free_before = StfState::get_account_data(&mut state, &seller).free;
+
+	state.execute_with(|| block_hooks::on_initialize(&mut Vec::new(), repo));
+
+	// The clearing price is the lowest price maximizing the volume, the ask price here.
+	assert_eq!(Some(40), state.execute_with(|| order_book::clearing_price(market)));
+	assert!(state.execute_with(|| order_book::orders(market)).is_empty());
+	assert_eq!(100, state.execute_with(|| order_book::base_balance(market, &buyer)).free);
+	assert_eq!(
+		BaseBalance { free: 90, reserved: 0 },
+		state.execute_with(|| order_book::base_balance(market, &seller))
+	);
+	// The difference between the limit and the clearing price is returned to the buyer.
+	assert_eq!(buyer_free_before + 100, StfState::get_account_data(&mut state, &buyer).free);
+	assert_eq!(seller_free_before + 400, StfState::get_account_data(&mut state, &seller).free);
+}
+
+pub fn order_book_events_are_only_available_to_the_order_owner() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let buyer: AccountId = endowed_account().public().into();
+	let seller: AccountId = second_endowed_account().public().into();
+	let market = 1;
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+
+	let calls = [
+		unsigned_call(
+			TrustedCall::order_book_set_base_balance(root, market, seller.clone(), 100),
+			0,
+		),
+		unsigned_call(
+			TrustedCall::order_book_place_order(buyer.clone(), market, OrderSide::Buy, 10, 50),
+			0,
+		),
+		unsigned_call(
+			TrustedCall::order_book_place_order(seller.clone(), market, OrderSide::Sell, 10, 40),
+			0,
+		),
+	];
+	for call in calls {
+		StfState::execute_call(&mut state, call, &mut Vec::new(), repo.clone()).unwrap();
+	}
+	let placed =
+		OrderBookEvent::<Runtime>::OrderPlaced { market, order_id: 0, owner: buyer.clone() };
+	assert!(has_order_book_event(
+		&state.execute_with(|| event_disclosure::events_of(&buyer)),
+		&placed
+	));
+	assert!(!has_order_book_event(
+		&state.execute_with(|| event_disclosure::events_of(&seller)),
+		&placed
+	));
+
+	StfState::reset_events(&mut state);
+	state.execute_with(|| block_hooks::on_initialize(&mut Vec::new(), repo));
+
+	let settled = OrderBookEvent::<Runtime>::OrderSettled {
+		market,
+		order_id: 1,
+		owner: seller.clone(),
+		amount: 10,
+		price: 40,
+	};
+	assert!(has_order_book_event(
+		&state.execute_with(|| event_disclosure::events_of(&seller)),
+		&settled
+	));
+	assert!(!has_order_book_event(
+		&state.execute_with(|| event_disclosure::events_of(&buyer)),
+		&settled
+	));
+}
+
+pub fn voting_publishes_result_to_parentchain_after_deadline() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let creator: AccountId = endowed_account().public().into();
+	let voter: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+
+	let create_poll = unsigned_call(TrustedCall::voting_create_poll(creator.clone(), 2, 5), 0);
+	StfState::execute_call(&mut state, create_poll, &mut Vec::new(), repo.clone()).unwrap();
+	for (who, nonce) in [(creator.clone(), 1), (voter.clone(), 0)] {
+		let cast_ballot =
+			unsigned_call(TrustedCall::voting_cast_ballot(who, 0, 1, H256::repeat_byte(7)), nonce);
+		StfState::execute_call(&mut state, cast_ballot, &mut Vec::new(), repo.clone()).unwrap();
+	}
+
+	let mut parentchain_calls = Vec::new();
+	state.execute_with(|| {
+		set_block_number(5);
+		block_hooks::on_initialize(&mut parentchain_calls, repo.clone());
+	});
+	assert!(parentchain_calls.is_empty());
+	assert!(!state.execute_with(|| voting::poll(0)).unwrap().revealed);
+
+	// The result is published once, in the first block after the deadline.
+	for block_number in 6..8 {
+		state.execute_with(|| {
+			set_block_number(block_number);
+			block_hooks::on_initialize(&mut parentchain_calls, repo.clone());
+		});
+	}
+	assert_eq!(1, parentchain_calls.len());
+	assert!(state.execute_with(|| voting::poll(0)).unwrap().revealed);
+
+	let proof = state.execute_with(|| voting::inclusion_proof(0, &voter)).unwrap();
+	assert_eq!(proof.leaf, voting::ballot_commitment(0, &voter, 1, &H256::repeat_byte(7)));
+	assert_eq!(2, proof.number_of_leaves);
+}
+
+pub fn auction_pays_highest_sealed_bid_to_seller_after_close() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let seller: AccountId = unendowed_account().public().into();
+	let low_bidder: AccountId = endowed_account().public().into();
+	let high_bidder: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+	let high_bidder_free_before = StfState::get_account_data(&mut state, &high_bidder).free;
+
+	let calls = [
+		unsigned_call(TrustedCall::auction_create(seller.clone(), b"nft".to_vec(), 1000, 5), 0),
+		unsigned_call(TrustedCall::auction_bid(low_bidder.clone(), 0, 1500), 0),
+		unsigned_call(TrustedCall::auction_bid(high_bidder.clone(), 0, 2000), 0),
+	];
+	for call in calls {
+		StfState::execute_call(&mut state, call, &mut Vec::new(), repo.clone()).unwrap();
+	}
+	assert_eq!(1500, StfState::get_account_data(&mut state, &low_bidder).reserved);
+
+	let settle = unsigned_call(TrustedCall::auction_settle(seller.clone(), 0), 1);
+	assert!(StfState::execute_call(&mut state, settle, &mut Vec::new(), repo.clone()).is_err());
+
+	state.execute_with(|| set_block_number(6));
+	let mut parentchain_calls = Vec::new();
+	let settle = unsigned_call(TrustedCall::auction_settle(seller.clone(), 0), 2);
+	StfState::execute_call(&mut state, settle, &mut parentchain_calls, repo).unwrap();
+	assert_eq!(1, parentchain_calls.len());
+
+	let outcome = state.execute_with(|| auction::outcome(0)).unwrap();
+	assert_eq!(Some((high_bidder.clone(), 2000)), outcome.winner);
+	assert_eq!(2, outcome.number_of_bids);
+	assert_eq!(2000, StfState::get_account_data(&mut state, &seller).free);
+	assert_eq!(0, StfState::get_account_data(&mut state, &low_bidder).reserved);
+	assert_eq!(
+		high_bidder_free_before - 2000,
+		StfState::get_account_data(&mut state, &high_bidder).free
+	);
+}
+
+pub fn ended_auction_is_settled_by_the_block_hook() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let seller: AccountId = unendowed_account().public().into();
+	let bidder: AccountId = endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+
+	let calls = [
+		unsigned_call(TrustedCall::auction_create(seller.clone(), b"nft".to_vec(), 1000, 5), 0),
+		unsigned_call(TrustedCall::auction_bid(bidder.clone(), 0, 1500), 0),
+	];
+	for call in calls {
+		StfState::execute_call(&mut state, call, &mut Vec::new(), repo.clone()).unwrap();
+	}
+
+	let mut parentchain_calls = Vec::new();
+	state.execute_with(|| {
+		set_block_number(5);
+		block_hooks::on_initialize(&mut parentchain_calls, repo.clone());
+	});
+	assert!(parentchain_calls.is_empty());
+	assert!(state.execute_with(|| auction::outcome(0)).is_none());
+
+	state.execute_with(|| {
+		set_block_number(6);
+		block_hooks::on_initialize(&mut parentchain_calls, repo.clone());
+	});
+	assert_eq!(1, parentchain_calls.len());
+	let outcome = state.execute_with(|| auction::outcome(0)).unwrap();
+	assert_eq!(Some((bidder.clone(), 1500)), outcome.winner);
+	assert_eq!(1500, StfState::get_account_data(&mut state, &seller).free);
+
+	// Settled auctions are not settled again.
+	state.execute_with(|| {
+		set_block_number(7);
+		block_hooks::on_initialize(&mut parentchain_calls, repo);
+	});
+	assert_eq!(1, parentchain_calls.len());
+}
+
+pub fn payment_channel_streams_rate_per_block_until_closed() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let payer: AccountId = endowed_account().public().into();
+	let payee: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	let payer_free_before = StfState::get_account_data(&mut state, &payer).free;
+	let payee_free_before = StfState::get_account_data(&mut state, &payee).free;
+
+	let open =
+		unsigned_call(TrustedCall::payment_channel_open(payer.clone(), payee.clone(), 100, 250), 0);
+	StfState::execute_call(&mut state, open, &mut Vec::new(), repo.clone()).unwrap();
+	let top_up = unsigned_call(TrustedCall::payment_channel_top_up(payer.clone(), 0, 750), 1);
+	StfState::execute_call(&mut state, top_up, &mut Vec::new(), repo.clone()).unwrap();
+
+	for block_number in 2..4 {
+		state.execute_with(|| {
+			set_block_number(block_number);
+			block_hooks::on_initialize(&mut Vec::new(), repo.clone());
+		});
+	}
+	let channel = state.execute_with(|| payment_channel::channel_of(0, &payee)).unwrap();
+	assert_eq!(200, channel.streamed);
+	assert_eq!(800, channel.remaining);
+
+	let close = unsigned_call(TrustedCall::payment_channel_close(payee.clone(), 0), 0);
+	StfState::execute_call(&mut state, close, &mut Vec::new(), repo).unwrap();
+	assert!(state.execute_with(|| payment_channel::channel(0)).is_none());
+	assert_eq!(payer_free_before - 200, StfState::get_account_data(&mut state, &payer).free);
+	assert_eq!(0, StfState::get_account_data(&mut state, &payer).reserved);
+	assert_eq!(payee_free_before + 200, StfState::get_account_data(&mut state, &payee).free);
+}
+
+pub fn payment_channel_is_closed_if_the_reserved_deposit_runs_short() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let payer: AccountId = endowed_account().public().into();
+	let payee: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let open =
+		unsigned_call(TrustedCall::payment_channel_open(payer.clone(), payee.clone(), 100, 250), 0);
+	StfState::execute_call(&mut state, open, &mut Vec::new(), repo.clone()).unwrap();
+	let payee_free_before = StfState::get_account_data(&mut state, &payee).free;
+	// E.g. a bug in another module releasing part of the deposit.
+	state.execute_with(|| Balances::unreserve(&payer, 210));
+
+	state.execute_with(|| {
+		set_block_number(2);
+		block_hooks::on_initialize(&mut Vec::new(), repo);
+	});
+
+	// Only the reserved rest has been streamed, the channel cannot continue.
+	assert!(state.execute_with(|| payment_channel::channel(0)).is_none());
+	assert_eq!(payee_free_before + 40, StfState::get_account_data(&mut state, &payee).free);
+	assert_eq!(0, StfState::get_account_data(&mut state, &payer).reserved);
+}
+
+pub fn recovery_moves_funds_to_rescuer_after_approvals_and_delay() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let lost: AccountId = endowed_account().public().into();
+	let rescuer: AccountId = second_endowed_account().public().into();
+	let guardians = vec![AccountId::new([7u8; 32]), AccountId::new([8u8; 32])];
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+	let lost_free = StfState::get_account_data(&mut state, &lost).free;
+	let rescuer_free_before = StfState::get_account_data(&mut state, &rescuer).free;
+
+	let mut calls = vec![
+		unsigned_call(TrustedCall::recovery_create(lost.clone(), guardians.clone(), 2, 3), 0),
+		unsigned_call(TrustedCall::recovery_initiate(rescuer.clone(), lost.clone()), 0),
+	];
+	for guardian in guardians {
+		calls.push(unsigned_call(
+			TrustedCall::recovery_approve(guardian, lost.clone(), rescuer.clone()),
+			0,
+		));
+	}
+	for call in calls {
+		StfState::execute_call(&mut state, call, &mut Vec::new(), repo.clone()).unwrap();
+	}
+
+	let claim = unsigned_call(TrustedCall::recovery_claim(rescuer.clone(), lost.clone()), 1);
+	assert!(StfState::execute_call(&mut state, claim, &mut Vec::new(), repo.clone()).is_err());
+
+	state.execute_with(|| set_block_number(4));
+	let claim = unsigned_call(TrustedCall::recovery_claim(rescuer.clone(), lost.clone()), 2);
+	StfState::execute_call(&mut state, claim, &mut Vec::new(), repo).unwrap();
+	assert_eq!(Some(rescuer.clone()), state.execute_with(|| recovery::recovered_by(&lost)));
+	assert_eq!(
+		rescuer_free_before + lost_free,
+		StfState::get_account_data(&mut state, &rescuer).free
+	);
+}
+
+pub fn multisig_executes_proposal_once_threshold_is_reached() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let root = StfState::get_root(&mut state);
+	let alice: AccountId = endowed_account().public().into();
+	let bob: AccountId = second_endowed_account().public().into();
+	let dest = AccountId::new([9u8; 32]);
+	let multisig = multisig::multi_account_id(&[alice.clone(), bob.clone()], 2);
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let transfer = TrustedCall::balance_transfer(multisig.clone(), dest.clone(), 1000);
+	let call_hash = H256::from(blake2_256(&transfer.encode()));
+	let calls = [
+		unsigned_call(
+			TrustedCall::balance_set_balance(root, multisig.clone(), 1_000_000_000, 0),
+			0,
+		),
+		unsigned_call(
+			TrustedCall::multisig_propose(alice.clone(), vec![bob.clone()], 2, Box::new(transfer)),
+			0,
+		),
+	];
+	for call in calls {
+		StfState::execute_call(&mut state, call, &mut Vec::new(), repo.clone()).unwrap();
+	}
+	assert_eq!(0, StfState::get_account_data(&mut state, &dest).free);
+	let pending = state.execute_with(|| multisig::proposals_for(&multisig, &bob)).unwrap();
+	assert_eq!(vec![alice.clone()], pending[0].approvals);
+
+	let approve =
+		unsigned_call(TrustedCall::multisig_approve(bob.clone(), vec![alice], 2, call_hash), 0);
+	StfState::execute_call(&mut state, approve, &mut Vec::new(), repo).unwrap();
+	assert_eq!(1000, StfState::get_account_data(&mut state, &dest).free);
+	assert_eq!(1, StfState::get_account_nonce(&mut state, &multisig));
+	assert!(state.execute_with(|| multisig::proposals(&multisig)).is_empty());
+}
+
+pub fn proxy_can_only_send_calls_within_its_scope() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let delegator: AccountId = endowed_account().public().into();
+	let delegate: AccountId = second_endowed_account().public().into();
+	let dest = AccountId::new([9u8; 32]);
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	let add_proxy = unsigned_call(
+		TrustedCall::proxy_add(delegator.clone(), delegate.clone(), ProxyType::Transfer(1000)),
+		0,
+	);
+	StfState::execute_call(&mut state, add_proxy, &mut Vec::new(), repo.clone()).unwrap();
+
+	let proxy_call = |amount, nonce| {
+		let transfer = TrustedCall::balance_transfer(delegator.clone(), dest.clone(), amount);
+		unsigned_call(
+			TrustedCall::proxy_call(delegate.clone(), delegator.clone(), Box::new(transfer)),
+			nonce,
+		)
+	};
+	assert!(StfState::validate_call(&mut state, &proxy_call(1001, 0)).is_err());
+	assert!(StfState::validate_call(&mut state, &proxy_call(1000, 0)).is_ok());
+	assert!(StfState::execute_call(&mut state, proxy_call(1001, 0), &mut Vec::new(), repo.clone())
+		.is_err());
+	StfState::execute_call(&mut state, proxy_call(1000, 1), &mut Vec::new(), repo).unwrap();
+	assert_eq!(1000, StfState::get_account_data(&mut state, &dest).free);
+	assert_eq!(2, StfState::get_account_nonce(&mut state, &delegator));
+}
+
+pub fn only_events_of_disclosing_calls_are_public() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let alice: AccountId = endowed_account().public().into();
+	let bob: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	// Events are not deposited in the genesis block.
+	state.execute_with(|| set_block_number(1));
+
+	let transfer = TrustedCall::balance_transfer(alice.clone(), bob.clone(), 1000);
+	StfState::execute_call(
+		&mut state,
+		unsigned_call(transfer.clone(), 0),
+		&mut Vec::new(),
+		repo.clone(),
+	)
+	.unwrap();
+	let first_public_event = StfState::get_event_count(&mut state);
+	let disclosed_transfer =
+		TrustedCall::disclose_events(alice.clone(), DisclosurePolicy::Public, Box::new(transfer));
+	StfState::execute_call(&mut state, unsigned_call(disclosed_transfer, 1), &mut Vec::new(), repo)
+		.unwrap();
+	let event_count = StfState::get_event_count(&mut state);
+
+	let disclosed = state.execute_with(event_disclosure::disclosed_events);
+	assert_eq!((event_count - first_public_event) as usize, disclosed.len());
+	assert_eq!(
+		event_count as usize,
+		state.execute_with(|| event_disclosure::events_of(&alice)).len()
+	);
+	assert!(state.execute_with(|| event_disclosure::events_of(&bob)).is_empty());
+}
+
+pub fn events_are_never_attributed_to_more_than_one_call() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let alice: AccountId = endowed_account().public().into();
+	let bob: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	state.execute_with(|| set_block_number(1));
+
+	let transfer = TrustedCall::balance_transfer(alice.clone(), bob.clone(), 1000);
+	StfState::execute_call(&mut state, unsigned_call(transfer, 0), &mut Vec::new(), repo.clone())
+		.unwrap();
+	assert!(!state.execute_with(|| event_disclosure::events_of(&alice)).is_empty());
+
+	// The events are reset without resetting the recorded ranges, which are stale then.
+	state.execute_with(System::reset_events);
+	let transfer = TrustedCall::balance_transfer(bob.clone(), alice.clone(), 1000);
+	StfState::execute_call(&mut state, unsigned_call(transfer, 0), &mut Vec::new(), repo).unwrap();
+
+	assert!(state.execute_with(|| event_disclosure::events_of(&alice)).is_empty());
+	assert_eq!(
+		StfState::get_event_count(&mut state) as usize,
+		state.execute_with(|| event_disclosure::events_of(&bob)).len()
+	);
+}
+
+pub fn deposit_addresses_can_only_be_swept_by_their_parent() {
+	let mut state = StfState::init_state(AccountId::new([2u8; 32]));
+	let parent: AccountId = endowed_account().public().into();
+	let customer: AccountId = second_endowed_account().public().into();
+	let repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+
+	for nonce in 0..2 {
+		let derive = unsigned_call(TrustedCall::deposit_address_derive(parent.clone()), nonce);
+		StfState::execute_call(&mut state, derive, &mut Vec::new(), repo.clone()).unwrap();
+	}
+	let addresses = state.execute_with(|| deposit_address::addresses(&parent));
+	assert_eq!(
+		addresses,
+		vec![deposit_address::derive(&parent, 0), deposit_address::derive(&parent, 1)]
+	);
+
+	let deposit = unsigned_call(
+		TrustedCall::balance_transfer(customer.clone(), addresses[1].clone(), 1000),
+		0,
+	);
+	StfState::execute_call(&mut state, deposit, &mut Vec::new(), repo.clone()).unwrap();
+
+	let foreign_sweep =
+		unsigned_call(TrustedCall::deposit_address_sweep(customer, addresses.clone()), 1);
+	assert!(
+		StfState::execute_call(&mut state, foreign_sweep, &mut Vec::new(), repo.clone()).is_err()
+	);
+
+	let parent_balance = StfState::get_account_data(&mut state, &parent).free;
+	let sweep =
+		unsigned_call(TrustedCall::deposit_address_sweep(parent.clone(), addresses.clone()), 2);
+	StfState::execute_call(&mut state, sweep, &mut Vec::new(), repo).unwrap();
+	assert_eq!(parent_balance + 1000, StfState::get_account_data(&mut state, &parent).free);
+	assert_eq!(0, StfState::get_account_data(&mut state, &addresses[1]).free);
+}
+
+/// Signatures are verified by the executor, not by the STF, so a dummy signature suffices here.
+fn unsigned_call(call: TrustedCall, nonce: u32) -> TrustedCallSigned {
+	TrustedCallSigned::new(call, nonce, Signature::Ed25519(Ed25519Signature([0u8; 64])))
+}
+
+fn has_order_book_event(
+	events: &[event_disclosure::EventRecord],
+	event: &OrderBookEvent<Runtime>,
+) -> bool {
+	events.iter().any(|r| r.event == RuntimeEvent::OrderBookEvents(event.clone()))
+}
@@ -0,0 +1,167 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Isolates the storage of the STF modules from each other.
+//!
+//! A call of a module may only access the storage of the other modules it has an explicit
+//! capability for, so a bug in one module cannot silently corrupt the confidential data of another.
+
+use crate::{
+	aggregate_privacy::AGGREGATE_PRIVACY, auction::AUCTION, compliance::COMPLIANCE,
+	deposit_address::DEPOSIT_ADDRESS, event_disclosure::EVENT_DISCLOSURE,
+	feature_flags::FEATURE_FLAGS, multisig::MULTISIG, order_book::ORDER_BOOK,
+	payment_channel::PAYMENT_CHANNELS, proxy::PROXY, recovery::RECOVERY,
+	state_migration::STATE_MIGRATIONS, voting::VOTING,
+};
+use itp_sgx_externalities::{execute_in_namespace, NamespaceGuard};
+use itp_stf_primitives::error::StfError;
+use itp_utils::hex::hex_encode;
+use log::*;
+use sp_core::twox_128;
+use std::{format, vec::Vec};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageNamespace {
+	AggregatePrivacy,
+	Auction,
+	Compliance,
+	DepositAddress,
+	EventDisclosure,
+	FeatureFlags,
+	Multisig,
+	OrderBook,
+	PaymentChannels,
+	Proxy,
+	Recovery,
+	StateMigrations,
+	Voting,
+}
+
+impl StorageNamespace {
+	pub const ALL: [StorageNamespace; 13] = [
+		StorageNamespace::AggregatePrivacy,
+		StorageNamespace::Auction,
+		StorageNamespace::Compliance,
+		StorageNamespace::DepositAddress,
+		StorageNamespace::EventDisclosure,
+		StorageNamespace::FeatureFlags,
+		StorageNamespace::Multisig,
+		StorageNamespace::OrderBook,
+		StorageNamespace::PaymentChannels,
+		StorageNamespace::Proxy,
+		StorageNamespace::Recovery,
+		StorageNamespace::StateMigrations,
+		StorageNamespace::Voting,
+	];
+
+	/// Storage prefix of the module.
+	pub fn prefix(&self) -> &'static str {
+		match self {
+			StorageNamespace::AggregatePrivacy => AGGREGATE_PRIVACY,
+			StorageNamespace::Auction => AUCTION,
+			StorageNamespace::Compliance => COMPLIANCE,
+			StorageNamespace::DepositAddress => DEPOSIT_ADDRESS,
+			StorageNamespace::EventDisclosure => EVENT_DISCLOSURE,
+			StorageNamespace::FeatureFlags => FEATURE_FLAGS,
+			StorageNamespace::Multisig => MULTISIG,
+			StorageNamespace::OrderBook => ORDER_BOOK,
+			StorageNamespace::PaymentChannels => PAYMENT_CHANNELS,
+			StorageNamespace::Proxy => PROXY,
+			StorageNamespace::Recovery => RECOVERY,
+			StorageNamespace::StateMigrations => STATE_MIGRATIONS,
+			StorageNamespace::Voting => VOTING,
+		}
+	}
+
+	/// Namespaces of other modules the module may access.
+	///
+	/// Multisig and proxy calls execute calls on behalf of other accounts, which checks the
+	/// feature flags and records the events of the call. Modules that move balances check the
+	/// compliance gate.
+	pub fn capabilities(&self) -> &'static [StorageNamespace] {
+		match self {
+			StorageNamespace::Multisig | StorageNamespace::Proxy =>
+				&[StorageNamespace::FeatureFlags, StorageNamespace::EventDisclosure],
+			StorageNamespace::Auction
+			| StorageNamespace::DepositAddress
+			| StorageNamespace::OrderBook
+			| StorageNamespace::PaymentChannels
+			| StorageNamespace::Recovery => &[StorageNamespace::Compliance],
+			_ => &[],
+		}
+	}
+
+	/// Guard that denies access to all module namespaces but this one and its capabilities.
+	pub fn guard(&self) -> NamespaceGuard {
+		let accessible: Vec<Vec<u8>> = core::iter::once(self)
+			.chain(self.capabilities())
+			.map(|namespace| key_prefix(namespace.prefix()))
+			.collect();
+		let protected = StorageNamespace::ALL
+			.iter()
+			.map(|namespace| key_prefix(namespace.prefix()))
+			.collect();
+		NamespaceGuard::new(protected, &accessible)
+	}
+}
+
+/// Executes the call of the module within its namespace. Calls that don't belong to a module
+/// are executed without any restriction.
+pub fn execute_in<F: FnOnce() -> Result<(), StfError>>(
+	namespace: Option<StorageNamespace>,
+	f: F,
+) -> Result<(), StfError> {
+	let mut guard = namespace.map(|namespace| namespace.guard()).unwrap_or_default();
+	let result = execute_in_namespace(&mut guard, f);
+	if let Some(key) = guard.violations().first() {
+		error!(
+			"Call of the {:?} module accessed the storage key {} of another module",
+			namespace,
+			hex_encode(key)
+		);
+		return Err(StfError::StorageNamespaceViolation(format!("{:?}", namespace)))
+	}
+	result
+}
+
+fn key_prefix(prefix: &str) -> Vec<u8> {
+	twox_128(prefix.as_bytes()).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_sgx_externalities::{SgxExternalities, SgxExternalitiesTrait};
+	use itp_storage::storage_value_key;
+
+	#[test]
+	fn module_only_accesses_its_own_namespace_and_capabilities() {
+		let mut ext = SgxExternalities::default();
+		let mut guard = StorageNamespace::Multisig.guard();
+
+		execute_in_namespace(&mut guard, || {
+			ext.insert(storage_value_key(MULTISIG, "Proposals"), vec![1]);
+			ext.insert(storage_value_key(FEATURE_FLAGS, "Toggles"), vec![2]);
+			ext.insert(storage_value_key(VOTING, "Polls"), vec![3]);
+		});
+
+		assert!(ext.contains_key(&storage_value_key(MULTISIG, "Proposals")));
+		assert!(ext.contains_key(&storage_value_key(FEATURE_FLAGS, "Toggles")));
+		assert!(!ext.contains_key(&storage_value_key(VOTING, "Polls")));
+		assert_eq!(guard.violations(), &[storage_value_key(VOTING, "Polls")]);
+	}
+}
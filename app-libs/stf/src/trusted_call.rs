@@ -16,7 +16,7 @@
 */
 
 #[cfg(feature = "evm")]
-use sp_core::{H160, H256, U256};
+use sp_core::{H160, U256};
 
 #[cfg(feature = "evm")]
 use std::vec::Vec;
@@ -24,7 +24,21 @@ use std::vec::Vec;
 #[cfg(feature = "evm")]
 use crate::evm_helpers::{create_code_hash, evm_create2_address, evm_create_address};
 use crate::{
-	helpers::{enclave_signer_account, ensure_enclave_signer_account, shard_vault},
+	aggregate_privacy::{self, Aggregate, AggregatePrivacy},
+	auction::{self, AuctionId},
+	block_hooks, compliance, deposit_address,
+	event_disclosure::{self, DisclosurePolicy},
+	feature_flags::{self, Feature},
+	fees,
+	helpers::{
+		enclave_randomness, enclave_signer_account, ensure_enclave_signer_account, shard_vault,
+	},
+	multisig,
+	order_book::{self, MarketId, OrderId, OrderSide},
+	payment_channel::{self, ChannelId},
+	proxy, recovery, state_hash, state_migration,
+	storage_namespace::{self, StorageNamespace},
+	voting::{self, PollId},
 	Getter,
 };
 use codec::{Compact, Decode, Encode};
@@ -36,12 +50,13 @@ use ita_sgx_runtime::{
 	ParentchainInstanceIntegritee, ParentchainInstanceTargetA, ParentchainInstanceTargetB,
 	ParentchainIntegritee, Runtime, System,
 };
+use itp_hashing::hash_function::StateHashAlgorithm;
 use itp_node_api::metadata::{provider::AccessNodeMetadata, NodeMetadataTrait};
 use itp_node_api_metadata::{
 	pallet_balances::BalancesCallIndexes, pallet_enclave_bridge::EnclaveBridgeCallIndexes,
 	pallet_proxy::ProxyCallIndexes,
 };
-use itp_stf_interface::ExecuteCall;
+use itp_stf_interface::{ExecuteCall, ValidateCall};
 use itp_stf_primitives::{
 	error::StfError,
 	traits::{TrustedCallSigning, TrustedCallVerification},
@@ -49,7 +64,7 @@ use itp_stf_primitives::{
 };
 use itp_types::{
 	parentchain::{ParentchainCall, ParentchainId, ProxyType},
-	Address, Moment, OpaqueCall,
+	Address, BlockNumber, Moment, OpaqueCall, H256,
 };
 use itp_utils::stringify::account_id_to_string;
 use log::*;
@@ -58,8 +73,8 @@ use sp_core::{
 	ed25519,
 };
 use sp_io::hashing::blake2_256;
-use sp_runtime::{traits::Verify, MultiAddress, MultiSignature};
-use std::{format, prelude::v1::*, sync::Arc};
+use sp_runtime::{traits::Verify, MultiAddress};
+use std::{format, prelude::v1::*, sync::Arc, vec};
 
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
@@ -70,6 +85,40 @@ pub enum TrustedCall {
 	balance_unshield(AccountId, AccountId, Balance, ShardIdentifier), // (AccountIncognito, BeneficiaryPublicAccount, Amount, Shard)
 	balance_shield(AccountId, AccountId, Balance, ParentchainId), // (Root, AccountIncognito, Amount, origin parentchain)
 	timestamp_set(AccountId, Moment, ParentchainId),              // (Root, now)
+	compliance_set_registrar(AccountId, Option<AccountId>, Balance), // (Root, Registrar, Threshold)
+	compliance_attest(AccountId, AccountId),                      // (Registrar, Account)
+	compliance_revoke(AccountId, AccountId),                      // (Registrar, Account)
+	feature_flag_set(AccountId, Feature, bool, BlockNumber), // (Root, Feature, Enabled, Activation block)
+	state_migration_schedule(AccountId, Vec<u8>, BlockNumber), // (Enclave account, Migration, Activation parentchain block)
+	aggregate_privacy_set(AccountId, Option<AggregatePrivacy>), // (Root, Noise and budget of the aggregate getters)
+	aggregate_open(AccountId, Aggregate), // (Querier, Aggregate answered by its getter in the current window)
+	state_hash_algorithm_set(AccountId, StateHashAlgorithm), // (Root, Hash function of the state hash)
+	order_book_place_order(AccountId, MarketId, OrderSide, Balance, Balance), // (Origin, Market, Side, Amount, Limit price)
+	order_book_cancel_order(AccountId, MarketId, OrderId),
+	order_book_set_base_balance(AccountId, MarketId, AccountId, Balance), // (Root, Market, Account, Amount)
+	voting_create_poll(AccountId, u8, BlockNumber), // (Creator, Number of options, Deadline)
+	voting_cast_ballot(AccountId, PollId, u8, H256), // (Voter, Poll, Option, Salt)
+	auction_create(AccountId, Vec<u8>, Balance, BlockNumber), // (Seller, Item, Reserve price, Close)
+	auction_bid(AccountId, AuctionId, Balance),
+	auction_settle(AccountId, AuctionId),
+	payment_channel_open(AccountId, AccountId, Balance, Balance), // (Payer, Payee, Rate per block, Deposit)
+	payment_channel_top_up(AccountId, ChannelId, Balance),
+	payment_channel_close(AccountId, ChannelId),
+	recovery_create(AccountId, Vec<AccountId>, u16, BlockNumber), // (Account, Guardians, Threshold, Delay)
+	recovery_remove(AccountId),
+	recovery_initiate(AccountId, AccountId), // (Rescuer, Lost account)
+	recovery_approve(AccountId, AccountId, AccountId), // (Guardian, Lost account, Rescuer)
+	recovery_cancel(AccountId, AccountId),   // (Lost account, Rescuer)
+	recovery_claim(AccountId, AccountId),    // (Rescuer, Lost account)
+	multisig_propose(AccountId, Vec<AccountId>, u16, Box<TrustedCall>), // (Proposer, Other signatories, Threshold, Call)
+	multisig_approve(AccountId, Vec<AccountId>, u16, H256), // (Signatory, Other signatories, Threshold, Call hash)
+	multisig_cancel(AccountId, Vec<AccountId>, u16, H256), // (Proposer, Other signatories, Threshold, Call hash)
+	proxy_add(AccountId, AccountId, proxy::ProxyType),     // (Delegator, Delegate, Scope)
+	proxy_remove(AccountId, AccountId),                    // (Delegator, Delegate)
+	proxy_call(AccountId, AccountId, Box<TrustedCall>),    // (Delegate, Delegator, Call)
+	disclose_events(AccountId, DisclosurePolicy, Box<TrustedCall>), // (Origin, Disclosed events, Call)
+	deposit_address_derive(AccountId),                     // (Parent)
+	deposit_address_sweep(AccountId, Vec<AccountId>),      // (Parent, Deposit addresses)
 	#[cfg(feature = "evm")]
 	evm_withdraw(AccountId, H160, Balance), // (Origin, Address EVM Account, Value)
 	// (Origin, Source, Target, Input, Value, Gas limit, Max fee per gas, Max priority fee per gas, Nonce, Access list)
@@ -124,6 +173,40 @@ impl TrustedCall {
 			Self::balance_unshield(sender_account, ..) => sender_account,
 			Self::balance_shield(sender_account, ..) => sender_account,
 			Self::timestamp_set(sender_account, ..) => sender_account,
+			Self::compliance_set_registrar(sender_account, ..) => sender_account,
+			Self::compliance_attest(sender_account, ..) => sender_account,
+			Self::compliance_revoke(sender_account, ..) => sender_account,
+			Self::feature_flag_set(sender_account, ..) => sender_account,
+			Self::state_migration_schedule(sender_account, ..) => sender_account,
+			Self::aggregate_privacy_set(sender_account, ..) => sender_account,
+			Self::aggregate_open(sender_account, ..) => sender_account,
+			Self::state_hash_algorithm_set(sender_account, ..) => sender_account,
+			Self::order_book_place_order(sender_account, ..) => sender_account,
+			Self::order_book_cancel_order(sender_account, ..) => sender_account,
+			Self::order_book_set_base_balance(sender_account, ..) => sender_account,
+			Self::voting_create_poll(sender_account, ..) => sender_account,
+			Self::voting_cast_ballot(sender_account, ..) => sender_account,
+			Self::auction_create(sender_account, ..) => sender_account,
+			Self::auction_bid(sender_account, ..) => sender_account,
+			Self::auction_settle(sender_account, ..) => sender_account,
+			Self::payment_channel_open(sender_account, ..) => sender_account,
+			Self::payment_channel_top_up(sender_account, ..) => sender_account,
+			Self::payment_channel_close(sender_account, ..) => sender_account,
+			Self::recovery_create(sender_account, ..) => sender_account,
+			Self::recovery_remove(sender_account) => sender_account,
+			Self::recovery_initiate(sender_account, ..) => sender_account,
+			Self::recovery_approve(sender_account, ..) => sender_account,
+			Self::recovery_cancel(sender_account, ..) => sender_account,
+			Self::recovery_claim(sender_account, ..) => sender_account,
+			Self::multisig_propose(sender_account, ..) => sender_account,
+			Self::multisig_approve(sender_account, ..) => sender_account,
+			Self::multisig_cancel(sender_account, ..) => sender_account,
+			Self::proxy_add(sender_account, ..) => sender_account,
+			Self::proxy_remove(sender_account, ..) => sender_account,
+			Self::proxy_call(sender_account, ..) => sender_account,
+			Self::disclose_events(sender_account, ..) => sender_account,
+			Self::deposit_address_derive(sender_account) => sender_account,
+			Self::deposit_address_sweep(sender_account, ..) => sender_account,
 			#[cfg(feature = "evm")]
 			Self::evm_withdraw(sender_account, ..) => sender_account,
 			#[cfg(feature = "evm")]
@@ -134,6 +217,133 @@ impl TrustedCall {
 			Self::evm_create2(sender_account, ..) => sender_account,
 		}
 	}
+
+	/// Name of the call variant, e.g. to configure the handling of a call type.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::noop(..) => "noop",
+			Self::balance_set_balance(..) => "balance_set_balance",
+			Self::balance_transfer(..) => "balance_transfer",
+			Self::balance_unshield(..) => "balance_unshield",
+			Self::balance_shield(..) => "balance_shield",
+			Self::timestamp_set(..) => "timestamp_set",
+			Self::compliance_set_registrar(..) => "compliance_set_registrar",
+			Self::compliance_attest(..) => "compliance_attest",
+			Self::compliance_revoke(..) => "compliance_revoke",
+			Self::feature_flag_set(..) => "feature_flag_set",
+			Self::state_migration_schedule(..) => "state_migration_schedule",
+			Self::aggregate_privacy_set(..) => "aggregate_privacy_set",
+			Self::aggregate_open(..) => "aggregate_open",
+			Self::state_hash_algorithm_set(..) => "state_hash_algorithm_set",
+			Self::order_book_place_order(..) => "order_book_place_order",
+			Self::order_book_cancel_order(..) => "order_book_cancel_order",
+			Self::order_book_set_base_balance(..) => "order_book_set_base_balance",
+			Self::voting_create_poll(..) => "voting_create_poll",
+			Self::voting_cast_ballot(..) => "voting_cast_ballot",
+			Self::auction_create(..) => "auction_create",
+			Self::auction_bid(..) => "auction_bid",
+			Self::auction_settle(..) => "auction_settle",
+			Self::payment_channel_open(..) => "payment_channel_open",
+			Self::payment_channel_top_up(..) => "payment_channel_top_up",
+			Self::payment_channel_close(..) => "payment_channel_close",
+			Self::recovery_create(..) => "recovery_create",
+			Self::recovery_remove(..) => "recovery_remove",
+			Self::recovery_initiate(..) => "recovery_initiate",
+			Self::recovery_approve(..) => "recovery_approve",
+			Self::recovery_cancel(..) => "recovery_cancel",
+			Self::recovery_claim(..) => "recovery_claim",
+			Self::multisig_propose(..) => "multisig_propose",
+			Self::multisig_approve(..) => "multisig_approve",
+			Self::multisig_cancel(..) => "multisig_cancel",
+			Self::proxy_add(..) => "proxy_add",
+			Self::proxy_remove(..) => "proxy_remove",
+			Self::proxy_call(..) => "proxy_call",
+			Self::disclose_events(..) => "disclose_events",
+			Self::deposit_address_derive(..) => "deposit_address_derive",
+			Self::deposit_address_sweep(..) => "deposit_address_sweep",
+			#[cfg(feature = "evm")]
+			Self::evm_withdraw(..) => "evm_withdraw",
+			#[cfg(feature = "evm")]
+			Self::evm_call(..) => "evm_call",
+			#[cfg(feature = "evm")]
+			Self::evm_create(..) => "evm_create",
+			#[cfg(feature = "evm")]
+			Self::evm_create2(..) => "evm_create2",
+		}
+	}
+
+	/// The optional module the call belongs to, if any, i.e. the feature the call is gated by.
+	///
+	/// Exit calls, which release reserved or deposited funds, are not gated, so users can always
+	/// withdraw from a module that has been disabled.
+	pub fn feature(&self) -> Option<Feature> {
+		match self {
+			Self::order_book_cancel_order(..)
+			| Self::auction_settle(..)
+			| Self::payment_channel_close(..)
+			| Self::recovery_cancel(..)
+			| Self::deposit_address_sweep(..) => None,
+			Self::order_book_place_order(..) | Self::order_book_set_base_balance(..) =>
+				Some(Feature::OrderBook),
+			Self::voting_create_poll(..) | Self::voting_cast_ballot(..) => Some(Feature::Voting),
+			Self::auction_create(..) | Self::auction_bid(..) => Some(Feature::Auction),
+			Self::payment_channel_open(..) | Self::payment_channel_top_up(..) =>
+				Some(Feature::PaymentChannel),
+			Self::recovery_create(..)
+			| Self::recovery_remove(..)
+			| Self::recovery_initiate(..)
+			| Self::recovery_approve(..)
+			| Self::recovery_claim(..) => Some(Feature::Recovery),
+			Self::multisig_propose(..) | Self::multisig_approve(..) | Self::multisig_cancel(..) =>
+				Some(Feature::Multisig),
+			Self::proxy_add(..) | Self::proxy_remove(..) | Self::proxy_call(..) =>
+				Some(Feature::Proxy),
+			Self::disclose_events(..) => Some(Feature::EventDisclosure),
+			Self::deposit_address_derive(..) => Some(Feature::DepositAddress),
+			#[cfg(feature = "evm")]
+			Self::evm_withdraw(..)
+			| Self::evm_call(..)
+			| Self::evm_create(..)
+			| Self::evm_create2(..) => Some(Feature::Evm),
+			_ => None,
+		}
+	}
+
+	/// The module namespace the call may access the storage of, if any.
+	pub fn storage_namespace(&self) -> Option<StorageNamespace> {
+		match self {
+			Self::compliance_set_registrar(..)
+			| Self::compliance_attest(..)
+			| Self::compliance_revoke(..) => Some(StorageNamespace::Compliance),
+			Self::feature_flag_set(..) => Some(StorageNamespace::FeatureFlags),
+			Self::state_migration_schedule(..) => Some(StorageNamespace::StateMigrations),
+			Self::aggregate_privacy_set(..) | Self::aggregate_open(..) =>
+				Some(StorageNamespace::AggregatePrivacy),
+			Self::order_book_place_order(..)
+			| Self::order_book_cancel_order(..)
+			| Self::order_book_set_base_balance(..) => Some(StorageNamespace::OrderBook),
+			Self::voting_create_poll(..) | Self::voting_cast_ballot(..) =>
+				Some(StorageNamespace::Voting),
+			Self::auction_create(..) | Self::auction_bid(..) | Self::auction_settle(..) =>
+				Some(StorageNamespace::Auction),
+			Self::payment_channel_open(..)
+			| Self::payment_channel_top_up(..)
+			| Self::payment_channel_close(..) => Some(StorageNamespace::PaymentChannels),
+			Self::recovery_create(..)
+			| Self::recovery_remove(..)
+			| Self::recovery_initiate(..)
+			| Self::recovery_approve(..)
+			| Self::recovery_cancel(..)
+			| Self::recovery_claim(..) => Some(StorageNamespace::Recovery),
+			Self::multisig_propose(..) | Self::multisig_approve(..) | Self::multisig_cancel(..) =>
+				Some(StorageNamespace::Multisig),
+			Self::proxy_add(..) | Self::proxy_remove(..) | Self::proxy_call(..) =>
+				Some(StorageNamespace::Proxy),
+			Self::deposit_address_derive(..) | Self::deposit_address_sweep(..) =>
+				Some(StorageNamespace::DepositAddress),
+			_ => None,
+		}
+	}
 }
 
 impl TrustedCallSigning<TrustedCallSigned> for TrustedCall {
@@ -181,7 +391,7 @@ impl Default for TrustedCallSigned {
 		Self {
 			call: TrustedCall::noop(AccountId32::unchecked_from([0u8; 32].into())),
 			nonce: 0,
-			signature: MultiSignature::Ed25519(ed25519::Signature::unchecked_from([0u8; 64])),
+			signature: Signature::Ed25519(ed25519::Signature::unchecked_from([0u8; 64])),
 		}
 	}
 }
@@ -201,6 +411,74 @@ impl TrustedCallVerification for TrustedCallSigned {
 		payload.append(&mut shard.encode());
 		self.signature.verify(payload.as_slice(), self.call.sender_account())
 	}
+
+	fn call_names(&self) -> Vec<&'static str> {
+		let mut names = Vec::new();
+		let mut call = &self.call;
+		loop {
+			names.push(call.name());
+			call = match call {
+				TrustedCall::proxy_call(.., inner)
+				| TrustedCall::disclose_events(.., inner)
+				| TrustedCall::multisig_propose(.., inner) => inner,
+				_ => return names,
+			};
+		}
+	}
+}
+
+impl ValidateCall for TrustedCallSigned {
+	type Error = StfError;
+
+	fn validate(&self) -> Result<(), Self::Error> {
+		validate_call(&self.call)
+	}
+}
+
+/// Checks the balance movements of a call against the compliance gate and proxied calls against
+/// the scope of the proxy, so a call that is bound to fail does not enter the pool.
+fn validate_call(call: &TrustedCall) -> Result<(), StfError> {
+	match call {
+		TrustedCall::balance_transfer(from, to, value) =>
+			compliance::ensure_compliant(&[from, to], *value),
+		TrustedCall::balance_unshield(from, beneficiary, value, _) =>
+			compliance::ensure_compliant(&[from, beneficiary], *value),
+		TrustedCall::balance_shield(_, who, value, _) =>
+			compliance::ensure_compliant(&[who], *value),
+		TrustedCall::order_book_place_order(who, _, _, amount, limit_price) =>
+			compliance::ensure_compliant(&[who], order_book::quote_amount(*amount, *limit_price)?),
+		TrustedCall::auction_bid(bidder, auction_id, amount) => match auction::auction(*auction_id)
+		{
+			Some(auction) => compliance::ensure_compliant(&[bidder, &auction.seller], *amount),
+			None => Ok(()),
+		},
+		TrustedCall::payment_channel_open(payer, payee, _, deposit) =>
+			compliance::ensure_compliant(&[payer, payee], *deposit),
+		TrustedCall::payment_channel_top_up(_, channel_id, amount) =>
+			match payment_channel::channel(*channel_id) {
+				Some(channel) => compliance::ensure_compliant(
+					&[&channel.payer, &channel.payee],
+					channel.volume().saturating_add(*amount),
+				),
+				None => Ok(()),
+			},
+		TrustedCall::recovery_claim(rescuer, lost) =>
+			compliance::ensure_compliant(&[rescuer, lost], System::account(lost).data.free),
+		TrustedCall::deposit_address_sweep(parent, addresses) =>
+			addresses.iter().try_for_each(|address| {
+				compliance::ensure_compliant(&[parent], System::account(address).data.free)
+			}),
+		TrustedCall::proxy_call(delegate, delegator, inner) => {
+			ensure!(
+				inner.sender_account() == delegator,
+				StfError::Dispatch("proxied call must be sent by the delegator".into())
+			);
+			proxy::ensure_allowed(delegate, inner)?;
+			validate_call(inner)
+		},
+		TrustedCall::disclose_events(.., inner) => validate_call(inner),
+		_ => Ok(()),
+	}
 }
 
 // TODO: #91 signed return value
@@ -235,7 +513,27 @@ where
 		// so it should be considered as valid
 		System::inc_account_nonce(&sender);
 
-		match self.call {
+		if let Some(feature) = self.call.feature() {
+			feature_flags::ensure_enabled(feature)?;
+		}
+
+		let (call, disclosure) = match self.call {
+			TrustedCall::disclose_events(who, disclosure, call) => {
+				ensure!(
+					call.sender_account() == &who,
+					Self::Error::Dispatch("wrapped call must be sent by the same account".into())
+				);
+				if let Some(feature) = call.feature() {
+					feature_flags::ensure_enabled(feature)?;
+				}
+				(*call, disclosure)
+			},
+			call => (call, DisclosurePolicy::Private),
+		};
+		let first_event = System::event_count();
+
+		let namespace = call.storage_namespace();
+		storage_namespace::execute_in(namespace, || match call {
 			TrustedCall::noop(who) => {
 				debug!("noop called by {}", account_id_to_string(&who),);
 				Ok::<(), Self::Error>(())
@@ -266,12 +564,13 @@ where
 				Ok::<(), Self::Error>(())
 			},
 			TrustedCall::balance_transfer(from, to, value) => {
+				compliance::ensure_compliant(&[&from, &to], value)?;
 				let origin = ita_sgx_runtime::RuntimeOrigin::signed(from.clone());
 				std::println!("⣿STF⣿ 🔄 balance_transfer from ⣿⣿⣿ to ⣿⣿⣿ amount ⣿⣿⣿");
 				// endow fee to enclave (self)
 				let fee_recipient: AccountId = enclave_signer_account();
 				// fixme: apply fees through standard frame process and tune it
-				let fee = crate::STF_TX_FEE;
+				let fee = fees::TRANSFER_FEE;
 				info!(
 					"from {}, to {}, amount {}, fee {}",
 					account_id_to_string(&from),
@@ -303,10 +602,11 @@ where
 					account_id_to_string(&beneficiary),
 					value
 				);
+				compliance::ensure_compliant(&[&account_incognito, &beneficiary], value)?;
 				// endow fee to enclave (self)
 				let fee_recipient: AccountId = enclave_signer_account();
-				// fixme: apply fees through standard frame process and tune it. has to be at least two L1 transfer's fees
-				let fee = crate::STF_TX_FEE * 3;
+				// fixme: apply fees through standard frame process and tune it
+				let fee = fees::UNSHIELD_FEE;
 
 				info!(
 					"balance_unshield(from (L2): {}, to (L1): {}, amount {} (+fee: {}), shard {})",
@@ -371,6 +671,7 @@ where
 					parentchain_id == vault_parentchain_id,
 					StfError::WrongParentchainIdForShardVault
 				);
+				compliance::ensure_compliant(&[&who], value)?;
 				std::println!("⣿STF⣿ 🛡 will shield to {}", account_id_to_string(&who));
 				shield_funds(who, value)?;
 
@@ -441,6 +742,265 @@ where
 				};
 				Ok(())
 			},
+			TrustedCall::compliance_set_registrar(root, maybe_registrar, threshold) => {
+				ensure!(is_root::<Runtime, AccountId>(&root), Self::Error::MissingPrivileges(root));
+				debug!(
+					"compliance_set_registrar({:?}, {})",
+					maybe_registrar.as_ref().map(account_id_to_string),
+					threshold
+				);
+				compliance::set_registrar(maybe_registrar, threshold);
+				Ok(())
+			},
+			TrustedCall::compliance_attest(registrar, who) => {
+				compliance::ensure_registrar(&registrar)?;
+				debug!("compliance_attest({})", account_id_to_string(&who));
+				compliance::attest(&who);
+				Ok(())
+			},
+			TrustedCall::compliance_revoke(registrar, who) => {
+				compliance::ensure_registrar(&registrar)?;
+				debug!("compliance_revoke({})", account_id_to_string(&who));
+				compliance::revoke(&who);
+				Ok(())
+			},
+			TrustedCall::feature_flag_set(root, feature, enabled, activation_block) => {
+				ensure!(is_root::<Runtime, AccountId>(&root), Self::Error::MissingPrivileges(root));
+				debug!("feature_flag_set({:?}, {}, {})", feature, enabled, activation_block);
+				feature_flags::schedule(feature, enabled, activation_block)
+			},
+			TrustedCall::state_migration_schedule(enclave_account, migration, activation_block) => {
+				ensure_enclave_signer_account(&enclave_account)?;
+				debug!(
+					"state_migration_schedule({}, {})",
+					String::from_utf8_lossy(&migration),
+					activation_block
+				);
+				state_migration::schedule(migration, activation_block)
+			},
+			TrustedCall::aggregate_privacy_set(root, config) => {
+				ensure!(is_root::<Runtime, AccountId>(&root), Self::Error::MissingPrivileges(root));
+				debug!("aggregate_privacy_set({:?})", config);
+				aggregate_privacy::configure(config, enclave_randomness()?.into())
+			},
+			TrustedCall::aggregate_open(who, aggregate) => {
+				debug!("aggregate_open({}, {:?})", account_id_to_string(&who), aggregate);
+				aggregate_privacy::open(&who, aggregate)
+			},
+			TrustedCall::state_hash_algorithm_set(root, algorithm) => {
+				ensure!(is_root::<Runtime, AccountId>(&root), Self::Error::MissingPrivileges(root));
+				debug!("state_hash_algorithm_set({:?})", algorithm);
+				state_hash::set_algorithm(algorithm);
+				Ok(())
+			},
+			TrustedCall::order_book_place_order(who, market, side, amount, limit_price) => {
+				debug!(
+					"order_book_place_order({}, {}, {:?})",
+					account_id_to_string(&who),
+					market,
+					side
+				);
+				let order_id = order_book::place_order(who, market, side, amount, limit_price)?;
+				debug!("placed order {} in market {}", order_id, market);
+				Ok(())
+			},
+			TrustedCall::order_book_cancel_order(who, market, order_id) => {
+				debug!(
+					"order_book_cancel_order({}, {}, {})",
+					account_id_to_string(&who),
+					market,
+					order_id
+				);
+				order_book::cancel_order(&who, market, order_id)
+			},
+			TrustedCall::order_book_set_base_balance(root, market, who, amount) => {
+				ensure!(is_root::<Runtime, AccountId>(&root), Self::Error::MissingPrivileges(root));
+				debug!(
+					"order_book_set_base_balance({}, {}, {})",
+					market,
+					account_id_to_string(&who),
+					amount
+				);
+				order_book::set_base_balance(market, &who, amount);
+				Ok(())
+			},
+			TrustedCall::voting_create_poll(creator, options, deadline) => {
+				debug!(
+					"voting_create_poll({}, {}, {})",
+					account_id_to_string(&creator),
+					options,
+					deadline
+				);
+				let poll_id = voting::create_poll(creator, options, deadline)?;
+				debug!("created poll {}", poll_id);
+				Ok(())
+			},
+			TrustedCall::voting_cast_ballot(voter, poll_id, option, salt) => {
+				debug!("voting_cast_ballot({}, {})", account_id_to_string(&voter), poll_id);
+				voting::cast_ballot(voter, poll_id, option, salt)
+			},
+			TrustedCall::auction_create(seller, item, reserve_price, close) => {
+				debug!(
+					"auction_create({}, {}, {})",
+					account_id_to_string(&seller),
+					reserve_price,
+					close
+				);
+				let auction_id = auction::create_auction(seller, item, reserve_price, close)?;
+				debug!("created auction {}", auction_id);
+				Ok(())
+			},
+			TrustedCall::auction_bid(bidder, auction_id, amount) => {
+				debug!("auction_bid({}, {})", account_id_to_string(&bidder), auction_id);
+				auction::place_bid(bidder, auction_id, amount)
+			},
+			TrustedCall::auction_settle(who, auction_id) => {
+				debug!("auction_settle({}, {})", account_id_to_string(&who), auction_id);
+				let outcome = auction::settle(auction_id)?;
+				std::println!("⣿STF⣿ 🔨 settled auction {}: {:?}", auction_id, outcome.winner);
+
+				// Publish the signed outcome on chain.
+				let call_indexes = node_metadata_repo
+					.get_from_metadata(|m| m.publish_hash_call_indexes())
+					.map_err(|_| StfError::InvalidMetadata)?
+					.map_err(|_| StfError::InvalidMetadata)?;
+				calls.push(block_hooks::publish_auction_outcome(call_indexes, &outcome));
+				Ok(())
+			},
+			TrustedCall::payment_channel_open(payer, payee, rate, deposit) => {
+				debug!(
+					"payment_channel_open({}, {}, {}, {})",
+					account_id_to_string(&payer),
+					account_id_to_string(&payee),
+					rate,
+					deposit
+				);
+				let channel_id = payment_channel::open(payer, payee, rate, deposit)?;
+				debug!("opened payment channel {}", channel_id);
+				Ok(())
+			},
+			TrustedCall::payment_channel_top_up(payer, channel_id, amount) => {
+				debug!(
+					"payment_channel_top_up({}, {}, {})",
+					account_id_to_string(&payer),
+					channel_id,
+					amount
+				);
+				payment_channel::top_up(&payer, channel_id, amount)
+			},
+			TrustedCall::payment_channel_close(who, channel_id) => {
+				debug!("payment_channel_close({}, {})", account_id_to_string(&who), channel_id);
+				payment_channel::close(&who, channel_id)
+			},
+			TrustedCall::recovery_create(who, guardians, threshold, delay) => {
+				debug!(
+					"recovery_create({}, {} guardians, {}, {})",
+					account_id_to_string(&who),
+					guardians.len(),
+					threshold,
+					delay
+				);
+				recovery::create_config(&who, guardians, threshold, delay)
+			},
+			TrustedCall::recovery_remove(who) => {
+				debug!("recovery_remove({})", account_id_to_string(&who));
+				recovery::remove_config(&who)
+			},
+			TrustedCall::recovery_initiate(rescuer, lost) => {
+				debug!(
+					"recovery_initiate({}, {})",
+					account_id_to_string(&rescuer),
+					account_id_to_string(&lost)
+				);
+				recovery::initiate(&rescuer, &lost)
+			},
+			TrustedCall::recovery_approve(guardian, lost, rescuer) => {
+				debug!(
+					"recovery_approve({}, {}, {})",
+					account_id_to_string(&guardian),
+					account_id_to_string(&lost),
+					account_id_to_string(&rescuer)
+				);
+				recovery::approve(&guardian, &lost, &rescuer)
+			},
+			TrustedCall::recovery_cancel(lost, rescuer) => {
+				debug!(
+					"recovery_cancel({}, {})",
+					account_id_to_string(&lost),
+					account_id_to_string(&rescuer)
+				);
+				recovery::cancel(&lost, &rescuer)
+			},
+			TrustedCall::recovery_claim(rescuer, lost) => {
+				debug!(
+					"recovery_claim({}, {})",
+					account_id_to_string(&rescuer),
+					account_id_to_string(&lost)
+				);
+				recovery::claim(&rescuer, &lost)
+			},
+			TrustedCall::multisig_propose(proposer, other_signatories, threshold, call) => {
+				debug!("multisig_propose({}, {})", account_id_to_string(&proposer), threshold);
+				match multisig::propose(&proposer, other_signatories, threshold, *call)? {
+					Some(call) => execute_on_behalf(call, calls, node_metadata_repo),
+					None => Ok(()),
+				}
+			},
+			TrustedCall::multisig_approve(signer, other_signatories, threshold, call_hash) => {
+				debug!("multisig_approve({}, {:?})", account_id_to_string(&signer), call_hash);
+				match multisig::approve(&signer, other_signatories, threshold, call_hash)? {
+					Some(call) => execute_on_behalf(call, calls, node_metadata_repo),
+					None => Ok(()),
+				}
+			},
+			TrustedCall::multisig_cancel(proposer, other_signatories, threshold, call_hash) => {
+				debug!("multisig_cancel({}, {:?})", account_id_to_string(&proposer), call_hash);
+				multisig::cancel(&proposer, other_signatories, threshold, call_hash)
+			},
+			TrustedCall::proxy_add(delegator, delegate, proxy_type) => {
+				debug!(
+					"proxy_add({}, {}, {:?})",
+					account_id_to_string(&delegator),
+					account_id_to_string(&delegate),
+					proxy_type
+				);
+				proxy::add_proxy(&delegator, delegate, proxy_type)
+			},
+			TrustedCall::proxy_remove(delegator, delegate) => {
+				debug!(
+					"proxy_remove({}, {})",
+					account_id_to_string(&delegator),
+					account_id_to_string(&delegate)
+				);
+				proxy::remove_proxy(&delegator, &delegate)
+			},
+			TrustedCall::proxy_call(delegate, delegator, call) => {
+				debug!(
+					"proxy_call({}, {})",
+					account_id_to_string(&delegate),
+					account_id_to_string(&delegator)
+				);
+				ensure!(
+					call.sender_account() == &delegator,
+					Self::Error::Dispatch("proxied call must be sent by the delegator".into())
+				);
+				proxy::ensure_allowed(&delegate, &call)?;
+				execute_on_behalf(*call, calls, node_metadata_repo)
+			},
+			TrustedCall::disclose_events(..) =>
+				Err(Self::Error::Dispatch("nested disclose_events calls are not supported".into())),
+			TrustedCall::deposit_address_derive(parent) => {
+				debug!("deposit_address_derive({})", account_id_to_string(&parent));
+				deposit_address::derive_next(&parent).map(|_| ())
+			},
+			TrustedCall::deposit_address_sweep(parent, addresses) => {
+				debug!(
+					"deposit_address_sweep({}, {} addresses)",
+					account_id_to_string(&parent),
+					addresses.len()
+				);
+				deposit_address::sweep(&parent, &addresses).map(|_| ())
+			},
 
 			#[cfg(feature = "evm")]
 			TrustedCall::evm_withdraw(from, address, value) => {
@@ -559,7 +1119,8 @@ where
 				info!("Trying to create evm contract with address {:?}", contract_address);
 				Ok(())
 			},
-		}?;
+		})?;
+		event_disclosure::record_call_events(sender, first_event, disclosure);
 		Ok(())
 	}
 
@@ -572,6 +1133,40 @@ where
 			TrustedCall::balance_unshield(..) => debug!("No storage updates needed..."),
 			TrustedCall::balance_shield(..) => debug!("No storage updates needed..."),
 			TrustedCall::timestamp_set(..) => debug!("No storage updates needed..."),
+			TrustedCall::compliance_set_registrar(..)
+			| TrustedCall::compliance_attest(..)
+			| TrustedCall::compliance_revoke(..) => debug!("No storage updates needed..."),
+			TrustedCall::feature_flag_set(..) => debug!("No storage updates needed..."),
+			TrustedCall::state_migration_schedule(..) => debug!("No storage updates needed..."),
+			TrustedCall::aggregate_privacy_set(..) | TrustedCall::aggregate_open(..) =>
+				debug!("No storage updates needed..."),
+			TrustedCall::state_hash_algorithm_set(..) => debug!("No storage updates needed..."),
+			TrustedCall::order_book_place_order(..)
+			| TrustedCall::order_book_cancel_order(..)
+			| TrustedCall::order_book_set_base_balance(..) => debug!("No storage updates needed..."),
+			TrustedCall::voting_create_poll(..) | TrustedCall::voting_cast_ballot(..) =>
+				debug!("No storage updates needed..."),
+			TrustedCall::auction_create(..)
+			| TrustedCall::auction_bid(..)
+			| TrustedCall::auction_settle(..) => debug!("No storage updates needed..."),
+			TrustedCall::payment_channel_open(..)
+			| TrustedCall::payment_channel_top_up(..)
+			| TrustedCall::payment_channel_close(..) => debug!("No storage updates needed..."),
+			TrustedCall::recovery_create(..)
+			| TrustedCall::recovery_remove(..)
+			| TrustedCall::recovery_initiate(..)
+			| TrustedCall::recovery_approve(..)
+			| TrustedCall::recovery_cancel(..)
+			| TrustedCall::recovery_claim(..) => debug!("No storage updates needed..."),
+			TrustedCall::multisig_propose(..)
+			| TrustedCall::multisig_approve(..)
+			| TrustedCall::multisig_cancel(..) => debug!("No storage updates needed..."),
+			TrustedCall::proxy_add(..)
+			| TrustedCall::proxy_remove(..)
+			| TrustedCall::proxy_call(..) => debug!("No storage updates needed..."),
+			TrustedCall::disclose_events(..) => debug!("No storage updates needed..."),
+			TrustedCall::deposit_address_derive(..) | TrustedCall::deposit_address_sweep(..) =>
+				debug!("No storage updates needed..."),
 			#[cfg(feature = "evm")]
 			_ => debug!("No storage updates needed..."),
 		};
@@ -579,6 +1174,24 @@ where
 	}
 }
 
+/// Executes a call on behalf of its sender, e.g. once enough multisig signatories have approved
+/// it or a proxy has been checked. That authorization takes the place of the signature, the
+/// call still consumes the sender's nonce.
+fn execute_on_behalf<NodeMetadataRepository>(
+	call: TrustedCall,
+	calls: &mut Vec<ParentchainCall>,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) -> Result<(), StfError>
+where
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	info!("executing call on behalf of {}", account_id_to_string(call.sender_account()));
+	let nonce = System::account_nonce(call.sender_account());
+	let signature = Signature::Ed25519(ed25519::Signature::unchecked_from([0u8; 64]));
+	TrustedCallSigned::new(call, nonce, signature).execute(calls, node_metadata_repo)
+}
+
 fn burn_funds(account: AccountId, amount: u128) -> Result<(), StfError> {
 	let account_info = System::account(&account);
 	if account_info.data.free < amount {
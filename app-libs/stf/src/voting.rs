@@ -0,0 +1,216 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Private voting with result revelation.
+//!
+//! Ballots and running tallies stay confidential in the shard state. In the first sidechain
+//! block after the deadline, the block hook reveals the result and the enclave publishes the
+//! final tally together with a Merkle root over all ballot commitments to the parentchain.
+//! Each voter can obtain an inclusion proof for their own ballot through a getter and check it
+//! against that root.
+
+use crate::helpers::{
+	get_storage_double_map, get_storage_map, get_storage_value, put_storage_map, put_storage_value,
+};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::System;
+use itp_binary_merkle_tree::{merkle_proof, merkle_root, MerkleProofWithCodec};
+use itp_stf_primitives::{
+	error::{StfError, StfResult},
+	types::AccountId,
+};
+use itp_storage::{storage_double_map_key, StorageHasher};
+use itp_types::{BlockNumber, H256};
+use sp_io::hashing::blake2_256;
+use sp_runtime::traits::BlakeTwo256;
+use std::{format, vec, vec::Vec};
+
+pub(crate) const VOTING: &str = "Voting";
+const NEXT_POLL_ID: &str = "NextPollId";
+const POLLS: &str = "Polls";
+const OPEN_POLLS: &str = "OpenPolls";
+const TALLIES: &str = "Tallies";
+const BALLOTS: &str = "Ballots";
+const COMMITMENTS: &str = "Commitments";
+
+pub type PollId = u32;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Poll {
+	pub creator: AccountId,
+	pub options: u8,
+	/// Last sidechain block at which ballots are accepted.
+	pub deadline: BlockNumber,
+	pub revealed: bool,
+}
+
+/// Result of a poll as published to the parentchain.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct PollResult {
+	pub poll_id: PollId,
+	pub tally: Vec<u64>,
+	/// Merkle root over the commitments of all cast ballots.
+	pub ballots_root: H256,
+}
+
+/// A voter's own ballot record, including the index of its commitment in the ballots tree.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Ballot {
+	pub option: u8,
+	pub salt: H256,
+	pub commitment_index: u32,
+}
+
+pub fn poll(poll_id: PollId) -> Option<Poll> {
+	get_storage_map(VOTING, POLLS, &poll_id, &StorageHasher::Blake2_128Concat)
+}
+
+pub fn ballot(poll_id: PollId, who: &AccountId) -> Option<Ballot> {
+	get_storage_double_map(
+		VOTING,
+		BALLOTS,
+		&poll_id,
+		&StorageHasher::Blake2_128Concat,
+		who,
+		&StorageHasher::Blake2_128Concat,
+	)
+}
+
+pub fn create_poll(creator: AccountId, options: u8, deadline: BlockNumber) -> StfResult<PollId> {
+	if options < 2 {
+		return Err(StfError::Dispatch("a poll needs at least two options".into()))
+	}
+	if deadline <= System::block_number() {
+		return Err(StfError::Dispatch("poll deadline must be in the future".into()))
+	}
+	let poll_id: PollId = get_storage_value(VOTING, NEXT_POLL_ID).unwrap_or_default();
+	put_storage_value(VOTING, NEXT_POLL_ID, &(poll_id + 1));
+	put_poll(poll_id, &Poll { creator, options, deadline, revealed: false });
+	let mut open_polls = open_polls();
+	open_polls.push(poll_id);
+	put_storage_value(VOTING, OPEN_POLLS, &open_polls);
+	put_storage_map(
+		VOTING,
+		TALLIES,
+		&poll_id,
+		&StorageHasher::Blake2_128Concat,
+		&vec![0u64; options as usize],
+	);
+	Ok(poll_id)
+}
+
+/// Casts a ballot. Every account can vote once per poll; the salt is chosen by the voter
+/// and blinds the ballot commitment that ends up in the published Merkle root.
+pub fn cast_ballot(who: AccountId, poll_id: PollId, option: u8, salt: H256) -> StfResult<()> {
+	let poll = poll(poll_id).ok_or_else(|| unknown_poll(poll_id))?;
+	if System::block_number() > poll.deadline {
+		return Err(StfError::Dispatch(format!("poll {} is closed", poll_id)))
+	}
+	if option >= poll.options {
+		return Err(StfError::Dispatch(format!("invalid option {}", option)))
+	}
+	if ballot(poll_id, &who).is_some() {
+		return Err(StfError::Dispatch(format!("already voted in poll {}", poll_id)))
+	}
+
+	let mut tally = tally(poll_id);
+	tally[option as usize] += 1;
+	put_storage_map(VOTING, TALLIES, &poll_id, &StorageHasher::Blake2_128Concat, &tally);
+
+	let mut commitments = commitments(poll_id);
+	let commitment_index = commitments.len() as u32;
+	commitments.push(ballot_commitment(poll_id, &who, option, &salt));
+	put_storage_map(VOTING, COMMITMENTS, &poll_id, &StorageHasher::Blake2_128Concat, &commitments);
+
+	let key = storage_double_map_key(
+		VOTING,
+		BALLOTS,
+		&poll_id,
+		&StorageHasher::Blake2_128Concat,
+		&who,
+		&StorageHasher::Blake2_128Concat,
+	);
+	sp_io::storage::set(&key, &Ballot { option, salt, commitment_index }.encode());
+	Ok(())
+}
+
+/// Closes all polls whose deadline has passed and returns their results to be published.
+/// Called once at the start of every sidechain block.
+pub fn on_initialize() -> Vec<PollResult> {
+	let (due, open): (Vec<PollId>, Vec<PollId>) = open_polls()
+		.into_iter()
+		.partition(|poll_id| poll(*poll_id).map_or(true, |p| System::block_number() > p.deadline));
+	if due.is_empty() {
+		return Vec::new()
+	}
+	put_storage_value(VOTING, OPEN_POLLS, &open);
+	due.into_iter().filter_map(reveal_result).collect()
+}
+
+fn reveal_result(poll_id: PollId) -> Option<PollResult> {
+	let mut poll = poll(poll_id)?;
+	poll.revealed = true;
+	put_poll(poll_id, &poll);
+
+	Some(PollResult {
+		poll_id,
+		tally: tally(poll_id),
+		ballots_root: merkle_root::<BlakeTwo256, _>(commitments(poll_id)),
+	})
+}
+
+/// Proof that the caller's ballot is included in the poll's ballots root.
+pub fn inclusion_proof(
+	poll_id: PollId,
+	who: &AccountId,
+) -> Option<MerkleProofWithCodec<H256, H256>> {
+	let ballot = ballot(poll_id, who)?;
+	Some(
+		merkle_proof::<BlakeTwo256, _, _>(commitments(poll_id), ballot.commitment_index as usize)
+			.into(),
+	)
+}
+
+/// Topic under which the result of a poll is published on the parentchain.
+pub fn result_topic(poll_id: PollId) -> H256 {
+	blake2_256(&(VOTING, poll_id).encode()).into()
+}
+
+pub fn ballot_commitment(poll_id: PollId, who: &AccountId, option: u8, salt: &H256) -> H256 {
+	blake2_256(&(poll_id, who, option, salt).encode()).into()
+}
+
+fn tally(poll_id: PollId) -> Vec<u64> {
+	get_storage_map(VOTING, TALLIES, &poll_id, &StorageHasher::Blake2_128Concat).unwrap_or_default()
+}
+
+fn open_polls() -> Vec<PollId> {
+	get_storage_value(VOTING, OPEN_POLLS).unwrap_or_default()
+}
+
+fn commitments(poll_id: PollId) -> Vec<H256> {
+	get_storage_map(VOTING, COMMITMENTS, &poll_id, &StorageHasher::Blake2_128Concat)
+		.unwrap_or_default()
+}
+
+fn put_poll(poll_id: PollId, poll: &Poll) {
+	put_storage_map(VOTING, POLLS, &poll_id, &StorageHasher::Blake2_128Concat, poll);
+}
+
+fn unknown_poll(poll_id: PollId) -> StfError {
+	StfError::Dispatch(format!("unknown poll {}", poll_id))
+}
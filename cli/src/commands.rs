@@ -24,6 +24,9 @@ use crate::oracle::OracleCommand;
 
 use crate::attesteer::AttesteerCommand;
 
+#[cfg(not(feature = "production"))]
+use crate::state_repl::StateReplCommand;
+
 #[derive(Subcommand)]
 pub enum Commands {
 	#[clap(flatten)]
@@ -41,6 +44,10 @@ pub enum Commands {
 	/// Subcommand for the attesteer.
 	#[clap(subcommand)]
 	Attesteer(AttesteerCommand),
+
+	/// Run the STF natively on a shard state for debugging, no worker needed
+	#[cfg(not(feature = "production"))]
+	StateRepl(StateReplCommand),
 }
 
 pub fn match_command(cli: &Cli) -> CliResult {
@@ -56,5 +63,7 @@ pub fn match_command(cli: &Cli) -> CliResult {
 			cmd.run(cli);
 			Ok(CliResultOk::None)
 		},
+		#[cfg(not(feature = "production"))]
+		Commands::StateRepl(cmd) => cmd.run(),
 	}
 }
@@ -36,6 +36,8 @@ mod command_utils;
 mod evm;
 #[cfg(feature = "teeracle")]
 mod oracle;
+#[cfg(not(feature = "production"))]
+mod state_repl;
 mod trusted_base_cli;
 mod trusted_cli;
 mod trusted_command_utils;
@@ -120,6 +122,8 @@ pub enum CliError {
 	EvmRead { msg: String },
 	#[error("worker rpc api error: {:?}", msg)]
 	WorkerRpcApi { msg: String },
+	#[error("state repl error: {:?}", msg)]
+	StateRepl { msg: String },
 }
 
 pub type CliResult = Result<CliResultOk, CliError>;
@@ -0,0 +1,263 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Developer REPL running the STF natively on a shard state, no enclave needed.
+//!
+//! Trusted calls are executed like by the STF executor, which trusts the author to have verified
+//! their signature. Hence any account can be used as sender. Every call prints the storage it
+//! changed and the parentchain calls it created.
+
+use crate::{CliError, CliResult, CliResultOk};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::{Runtime, System};
+use ita_stf::{
+	helpers::put_storage_value, Getter, Stf, TrustedCall, TrustedCallSigned, TrustedGetter,
+	TrustedGetterSigned,
+};
+use itp_node_api::metadata::{metadata_mocks::NodeMetadataMock, provider::NodeMetadataRepository};
+use itp_sgx_externalities::{
+	SgxExternalities, SgxExternalitiesDiffType, SgxExternalitiesTrait, SgxExternalitiesType,
+};
+use itp_stf_interface::{InitState, StateCallInterface, StateGetterInterface};
+use itp_stf_primitives::types::{AccountId, Signature};
+use itp_types::Balance;
+use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+use std::{
+	fs,
+	io::{self, BufRead, Write},
+	path::PathBuf,
+	sync::Arc,
+};
+
+type ReplStf = Stf<TrustedCallSigned, Getter, SgxExternalities, Runtime>;
+
+const HELP: &str = "\
+get <hex key>                  value at a storage key
+keys [<hex prefix>]            storage keys, optionally only those with the prefix
+balance <account>              free balance of an account
+nonce <account>                nonce of an account
+transfer <from> <to> <amount>  execute a balance transfer
+set-balance <who> <amount>     set the free balance of an account as the root of the state
+call <hex call>                execute a SCALE encoded trusted call
+getter <hex getter>            execute a SCALE encoded trusted getter
+diff                           storage changed by the last call
+dump <file>                    write the state to a file, it can be loaded again with --state
+quit                           leave the REPL
+Accounts are given as //Seed or in ss58check format.";
+
+#[derive(Parser)]
+pub struct StateReplCommand {
+	/// SCALE encoded plaintext state to load, e.g. written by `dump` before. Starts from a fresh
+	/// state with //Alice as root otherwise.
+	#[clap(long)]
+	state: Option<PathBuf>,
+}
+
+impl StateReplCommand {
+	pub(crate) fn run(&self) -> CliResult {
+		let mut repl =
+			StateRepl::new(self.load_state().map_err(|msg| CliError::StateRepl { msg })?);
+		println!("Type help for the list of commands.");
+
+		let stdin = io::stdin();
+		loop {
+			print!("> ");
+			let _ = io::stdout().flush();
+			let mut line = String::new();
+			match stdin.lock().read_line(&mut line) {
+				Ok(0) => break,
+				Ok(_) => {},
+				Err(e) => return Err(CliError::StateRepl { msg: format!("{:?}", e) }),
+			}
+			let words: Vec<&str> = line.split_whitespace().collect();
+			match words.as_slice() {
+				[] => continue,
+				["quit"] | ["exit"] => break,
+				words => match repl.eval(words) {
+					Ok(output) => println!("{}", output),
+					Err(e) => println!("error: {}", e),
+				},
+			}
+		}
+		Ok(CliResultOk::None)
+	}
+
+	fn load_state(&self) -> Result<SgxExternalities, String> {
+		let mut state = match &self.state {
+			Some(path) => {
+				let encoded = fs::read(path).map_err(|e| format!("{:?}", e))?;
+				let state = SgxExternalitiesType::decode(&mut encoded.as_slice())
+					.map_err(|e| format!("invalid state file: {:?}", e))?;
+				SgxExternalities::new(state)
+			},
+			None => {
+				let root = parse_account("//Alice")?;
+				let mut state = ReplStf::init_state(root.clone());
+				state.execute_with(|| put_storage_value("Sudo", "Key", &root));
+				state
+			},
+		};
+		state.prune_state_diff();
+		Ok(state)
+	}
+}
+
+struct StateRepl {
+	state: SgxExternalities,
+	node_metadata_repo: Arc<NodeMetadataRepository<NodeMetadataMock>>,
+	last_diff: SgxExternalitiesDiffType,
+}
+
+impl StateRepl {
+	fn new(state: SgxExternalities) -> Self {
+		StateRepl {
+			state,
+			node_metadata_repo: Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new())),
+			last_diff: Default::default(),
+		}
+	}
+
+	fn eval(&mut self, words: &[&str]) -> Result<String, String> {
+		match words {
+			["get", key] =>
+				Ok(self.state.get(&decode_hex(key)?).map_or("none".into(), |v| encode_hex(v))),
+			["keys"] => Ok(self.keys(&[])),
+			["keys", prefix] => Ok(self.keys(&decode_hex(prefix)?)),
+			["balance", who] => {
+				let who = parse_account(who)?;
+				Ok(self.state.execute_with(|| System::account(&who).data.free).to_string())
+			},
+			["nonce", who] => {
+				let who = parse_account(who)?;
+				Ok(self.state.execute_with(|| System::account_nonce(&who)).to_string())
+			},
+			["transfer", from, to, amount] => self.apply(TrustedCall::balance_transfer(
+				parse_account(from)?,
+				parse_account(to)?,
+				parse_amount(amount)?,
+			)),
+			["set-balance", who, amount] => {
+				let root = self
+					.state
+					.execute_with(ita_sgx_runtime::Sudo::key)
+					.ok_or("the state has no root")?;
+				self.apply(TrustedCall::balance_set_balance(
+					root,
+					parse_account(who)?,
+					parse_amount(amount)?,
+					0,
+				))
+			},
+			["call", call] => {
+				let call = TrustedCall::decode(&mut decode_hex(call)?.as_slice())
+					.map_err(|e| format!("invalid trusted call: {:?}", e))?;
+				self.apply(call)
+			},
+			["getter", getter] => {
+				let getter = TrustedGetter::decode(&mut decode_hex(getter)?.as_slice())
+					.map_err(|e| format!("invalid trusted getter: {:?}", e))?;
+				let getter =
+					Getter::trusted(TrustedGetterSigned::new(getter, unchecked_signature()));
+				Ok(ReplStf::execute_getter(&mut self.state, getter)
+					.map_or("none".into(), |v| encode_hex(&v)))
+			},
+			["diff"] => Ok(format_diff(&self.last_diff)),
+			["dump", path] => {
+				fs::write(path, self.state.state().encode()).map_err(|e| format!("{:?}", e))?;
+				Ok(format!("state written to {}", path))
+			},
+			["help"] => Ok(HELP.into()),
+			_ => Err("unknown command, type help for the list of commands".into()),
+		}
+	}
+
+	fn keys(&self, prefix: &[u8]) -> String {
+		let keys: Vec<String> = self
+			.state
+			.state()
+			.keys()
+			.filter(|k| k.starts_with(prefix))
+			.map(|k| encode_hex(k))
+			.collect();
+		format!("{} keys\n{}", keys.len(), keys.join("\n"))
+	}
+
+	/// Executes the call with the next nonce of its sender, the diff is pruned afterwards.
+	fn apply(&mut self, call: TrustedCall) -> Result<String, String> {
+		let sender = call.sender_account().clone();
+		let nonce = self.state.execute_with(|| System::account_nonce(&sender));
+		let call = TrustedCallSigned::new(call, nonce, unchecked_signature());
+
+		let mut parentchain_calls = Vec::new();
+		let result = ReplStf::execute_call(
+			&mut self.state,
+			call,
+			&mut parentchain_calls,
+			self.node_metadata_repo.clone(),
+		);
+		self.last_diff = self.state.state_diff().clone();
+		self.state.prune_state_diff();
+
+		let mut output = match result {
+			Ok(()) => "executed".to_string(),
+			Err(e) => format!("failed: {:?}", e),
+		};
+		for call in parentchain_calls {
+			output.push_str(&format!("\nparentchain call: {:?}", call));
+		}
+		output.push('\n');
+		output.push_str(&format_diff(&self.last_diff));
+		Ok(output)
+	}
+}
+
+fn format_diff(diff: &SgxExternalitiesDiffType) -> String {
+	let changes: Vec<String> = diff
+		.iter()
+		.map(|(key, value)| match value {
+			Some(value) => format!("{} = {}", encode_hex(key), encode_hex(value)),
+			None => format!("{} removed", encode_hex(key)),
+		})
+		.collect();
+	format!("{} keys changed\n{}", changes.len(), changes.join("\n"))
+}
+
+/// The STF does not verify signatures, the author does before a call enters the pool.
+fn unchecked_signature() -> Signature {
+	Signature::Sr25519(sr25519::Signature::from_raw([0u8; 64]))
+}
+
+fn parse_account(account: &str) -> Result<AccountId, String> {
+	if account.starts_with("//") {
+		return sr25519::Pair::from_string(account, None)
+			.map(|pair| pair.public().into())
+			.map_err(|e| format!("invalid seed {}: {:?}", account, e))
+	}
+	AccountId::from_ss58check(account).map_err(|e| format!("invalid account {}: {:?}", account, e))
+}
+
+fn parse_amount(amount: &str) -> Result<Balance, String> {
+	amount.parse().map_err(|e| format!("invalid amount {}: {:?}", amount, e))
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+	hex::decode(hex.trim_start_matches("0x")).map_err(|e| format!("invalid hex {}: {:?}", hex, e))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
@@ -31,6 +31,7 @@ use itp_sgx_crypto::ShieldingCryptoEncrypt;
 use itp_stf_primitives::types::{ShardIdentifier, TrustedOperation};
 use itp_types::{
 	parentchain::{BlockHash, BlockNumber, ProcessedParentchainBlock},
+	shard_routing::ShardRoute,
 	DirectRequestStatus, TrustedOperationStatus,
 };
 use itp_utils::{FromHexPrefixed, ToHexPrefixed};
@@ -132,6 +133,9 @@ pub(crate) fn get_state<T: Decode + Debug>(
 			msg: "[Error] DirectRequestStatus::Error".to_string(),
 		})
 	}
+	if rpc_return_value.status == DirectRequestStatus::Redirect {
+		return Err(redirect_error(&rpc_return_value.value))
+	}
 
 	let maybe_state: Option<Vec<u8>> = Option::decode(&mut rpc_return_value.value.as_slice())
 		// Replace with `inspect_err` once it's stable.
@@ -324,6 +328,10 @@ fn send_direct_request<T: Decode + Debug>(
 							let value = decode_response_value(&mut return_value.value.as_slice())?;
 							return Ok(value)
 						},
+						DirectRequestStatus::Redirect => {
+							direct_api.close().unwrap();
+							return Err(redirect_error(&return_value.value))
+						},
 					}
 					if !return_value.do_watch {
 						debug!("do watch is false, closing connection");
@@ -408,6 +416,10 @@ pub(crate) fn wait_until(
 								debug!("request status is ignored");
 								return None
 							},
+							DirectRequestStatus::Redirect => {
+								error!("{}", redirect_error(&return_value.value));
+								return None
+							},
 						}
 					};
 				} else {
@@ -422,6 +434,19 @@ pub(crate) fn wait_until(
 	}
 }
 
+/// The shard is served by another worker, whose url is given in the response.
+fn redirect_error(mut value: &[u8]) -> TrustedOperationError {
+	let msg = match ShardRoute::decode(&mut value) {
+		Ok(route) => format!(
+			"Shard {} is served by the worker at {}",
+			route.shard.encode().to_base58(),
+			String::from_utf8_lossy(&route.url)
+		),
+		Err(e) => format!("Could not decode the redirect: {:?}", e),
+	};
+	TrustedOperationError::Default { msg }
+}
+
 fn connection_can_be_closed(top_status: TrustedOperationStatus) -> bool {
 	!matches!(
 		top_status,
@@ -430,5 +455,6 @@ fn connection_can_be_closed(top_status: TrustedOperationStatus) -> bool {
 			| TrustedOperationStatus::Ready
 			| TrustedOperationStatus::Broadcast
 			| TrustedOperationStatus::Invalid
+			| TrustedOperationStatus::Deferred
 	)
 }
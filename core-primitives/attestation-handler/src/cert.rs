@@ -52,6 +52,16 @@ static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
 ];
 
 pub const CERTEXPIRYDAYS: i64 = 90i64;
+
+/// Measurement of a peer enclave that is accepted in a mutual remote attestation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PeerMeasurementPolicy {
+	/// The peer runs the same enclave as we do.
+	SameMrEnclave,
+	/// The peer runs the same enclave or the enclave with the given MRENCLAVE, which has been
+	/// announced on chain as the upgrade of our enclave for the shard in question.
+	SameOrUpgradeMrEnclave([u8; 32]),
+}
 pub const IAS_REPORT_CA: &[u8] = include_bytes!("../AttestationReportSigningCACert.pem");
 
 #[cfg(feature = "sgx")]
@@ -259,18 +269,12 @@ pub fn parse_cert_issuer(cert_der: &[u8]) -> SgxResult<Vec<u8>> {
 	Ok(pub_k)
 }
 
-// FIXME: This code is redundant with the host call of the integritee-node
-pub fn verify_mra_cert<A>(
+/// Public key and the (decoded) Netscape Comment payload of a mutual remote attestation
+/// certificate.
+fn parse_mra_cert(
 	cert_der: &[u8],
 	is_payload_base64_encoded: bool,
-	is_dcap: bool,
-	attestation_ocall: &A,
-) -> SgxResult<()>
-where
-	A: EnclaveAttestationOCallApi,
-{
-	// Before we reach here, Webpki already verified the cert is properly signed
-
+) -> SgxResult<(Vec<u8>, Vec<u8>)> {
 	// Search for Public Key prime256v1 OID
 	let prime256v1_oid = &[0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
 	let mut offset = cert_der
@@ -313,6 +317,46 @@ where
 		payload = base64::decode(&payload[..]).or(Err(sgx_status_t::SGX_ERROR_UNEXPECTED))?;
 	}
 	trace!("payload in mra cert verifier is: {:?}", &payload);
+	Ok((pub_k, payload))
+}
+
+/// MRENCLAVE in the attestation report of an EPID mutual remote attestation certificate.
+///
+/// Doesn't verify the certificate, which must have been verified with [`verify_mra_cert`].
+pub fn mrenclave_of_mra_cert(
+	cert_der: &[u8],
+	is_payload_base64_encoded: bool,
+) -> SgxResult<[u8; 32]> {
+	let (_, payload) = parse_mra_cert(cert_der, is_payload_base64_encoded)?;
+	let attn_report_raw =
+		payload.split(|x| *x == b'|').next().ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+	let attn_report: Value =
+		serde_json::from_slice(attn_report_raw).map_err(|e| EnclaveError::Other(e.into()))?;
+	let quote = match &attn_report["isvEnclaveQuoteBody"] {
+		Value::String(quote_raw) =>
+			base64::decode(quote_raw).map_err(|e| EnclaveError::Other(e.into()))?,
+		_ => return Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+	};
+	// The MRENCLAVE follows the 48 bytes of the quote header and the first 64 bytes of the
+	// report body.
+	let mut mrenclave = [0u8; 32];
+	mrenclave.copy_from_slice(quote.get(112..144).ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?);
+	Ok(mrenclave)
+}
+
+// FIXME: This code is redundant with the host call of the integritee-node
+pub fn verify_mra_cert<A>(
+	cert_der: &[u8],
+	is_payload_base64_encoded: bool,
+	is_dcap: bool,
+	policy: PeerMeasurementPolicy,
+	attestation_ocall: &A,
+) -> SgxResult<()>
+where
+	A: EnclaveAttestationOCallApi,
+{
+	// Before we reach here, Webpki already verified the cert is properly signed
+	let (pub_k, payload) = parse_mra_cert(cert_der, is_payload_base64_encoded)?;
 	if !is_dcap {
 		// Extract each field
 		let mut iter = payload.split(|x| *x == b'|');
@@ -368,7 +412,7 @@ where
 			},
 		}
 
-		verify_attn_report(attn_report_raw, pub_k, attestation_ocall)
+		verify_attn_report(attn_report_raw, pub_k, policy, attestation_ocall)
 	} else {
 		// TODO Refactor state provisioning to not use MURA #1385
 		// TODO DCAP is currently just passed through! SECURITY!!!
@@ -379,6 +423,7 @@ where
 pub fn verify_attn_report<A>(
 	report_raw: &[u8],
 	pub_k: Vec<u8>,
+	policy: PeerMeasurementPolicy,
 	attestation_ocall: &A,
 ) -> SgxResult<()>
 where
@@ -457,14 +502,7 @@ where
 		// TODO: lack security check here
 		let sgx_quote: sgx_quote_t = unsafe { ptr::read(quote.as_ptr() as *const _) };
 
-		let ti = attestation_ocall.get_mrenclave_of_self()?;
-		if sgx_quote.report_body.mr_enclave.m != ti.m {
-			error!(
-				"mr_enclave is not equal to self {:?} != {:?}",
-				sgx_quote.report_body.mr_enclave.m, ti.m
-			);
-			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-		}
+		verify_peer_measurement(&sgx_quote, policy, attestation_ocall)?;
 
 		// ATTENTION
 		// DO SECURITY CHECK ON DEMAND
@@ -495,3 +533,27 @@ where
 
 	Ok(())
 }
+
+fn verify_peer_measurement<A>(
+	sgx_quote: &sgx_quote_t,
+	policy: PeerMeasurementPolicy,
+	attestation_ocall: &A,
+) -> SgxResult<()>
+where
+	A: EnclaveAttestationOCallApi,
+{
+	let peer_mrenclave = sgx_quote.report_body.mr_enclave.m;
+	match policy {
+		PeerMeasurementPolicy::SameOrUpgradeMrEnclave(upgrade_mrenclave)
+			if peer_mrenclave == upgrade_mrenclave =>
+			info!("peer runs the enclave we are upgrading to"),
+		PeerMeasurementPolicy::SameMrEnclave | PeerMeasurementPolicy::SameOrUpgradeMrEnclave(_) => {
+			let ti = attestation_ocall.get_mrenclave_of_self()?;
+			if peer_mrenclave != ti.m {
+				error!("mr_enclave is not equal to self {:?} != {:?}", peer_mrenclave, ti.m);
+				return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+			}
+		},
+	}
+	Ok(())
+}
@@ -91,6 +91,13 @@ extern "C" {
 
 	pub fn execute_trusted_calls(eid: sgx_enclave_id_t, retval: *mut sgx_status_t) -> sgx_status_t;
 
+	pub fn report_epc_usage(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		used_bytes: u64,
+		total_bytes: u64,
+	) -> sgx_status_t;
+
 	pub fn sync_parentchain(
 		eid: sgx_enclave_id_t,
 		retval: *mut sgx_status_t,
@@ -152,6 +159,137 @@ extern "C" {
 		mrenclave_size: u32,
 	) -> sgx_status_t;
 
+	pub fn enable_payload_quarantine(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		max_bytes: u64,
+	) -> sgx_status_t;
+
+	pub fn export_payload_quarantine(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		request: *const u8,
+		request_size: u32,
+		export: *mut u8,
+		export_size: u32,
+	) -> sgx_status_t;
+
+	pub fn set_maintenance_windows(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		shard: *const u8,
+		shard_size: u32,
+		windows: *const u8,
+		windows_size: u32,
+	) -> sgx_status_t;
+
+	pub fn set_load_shedding_policy(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		policy: *const u8,
+		policy_size: u32,
+	) -> sgx_status_t;
+
+	pub fn set_stale_shard_threshold(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		threshold: u64,
+	) -> sgx_status_t;
+
+	pub fn set_panic_policy(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		policy: *const u8,
+		policy_size: u32,
+	) -> sgx_status_t;
+
+	pub fn set_never_persist_calls(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		call_names: *const u8,
+		call_names_size: u32,
+	) -> sgx_status_t;
+
+	pub fn set_shard_routes(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		routes: *const u8,
+		routes_size: u32,
+	) -> sgx_status_t;
+
+	pub fn conduct_key_ceremony(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		ceremony: *const u8,
+		ceremony_size: u32,
+		outcome: *mut u8,
+		outcome_size: u32,
+	) -> sgx_status_t;
+
+	pub fn export_key_ceremony_trail(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		trail: *mut u8,
+		trail_size: u32,
+	) -> sgx_status_t;
+
+	pub fn issue_key_ceremony_nonce(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		nonce: *mut u8,
+		nonce_size: u32,
+	) -> sgx_status_t;
+
+	pub fn run_smoke_test(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		report: *mut u8,
+		report_size: u32,
+	) -> sgx_status_t;
+
+	pub fn run_canary_round(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		shard: *const u8,
+		shard_size: u32,
+		report: *mut u8,
+		report_size: u32,
+	) -> sgx_status_t;
+
+	pub fn export_state_backup(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		shard: *const u8,
+		shard_size: u32,
+		path: *const u8,
+		path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn import_state_backup(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		path: *const u8,
+		path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn export_shard_state(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		request: *const u8,
+		request_size: u32,
+		path: *const u8,
+		path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn diff_state_snapshots(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		request: *const u8,
+		request_size: u32,
+		diff: *mut u8,
+		diff_size: u32,
+	) -> sgx_status_t;
+
 	pub fn generate_ias_ra_extrinsic(
 		eid: sgx_enclave_id_t,
 		retval: *mut sgx_status_t,
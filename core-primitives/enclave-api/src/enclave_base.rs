@@ -21,7 +21,20 @@ use codec::Decode;
 use core::fmt::Debug;
 use itc_parentchain::primitives::{ParentchainId, ParentchainInitParams};
 use itp_stf_interface::ShardCreationInfo;
-use itp_types::{parentchain::Header, Balance, ShardIdentifier};
+use itp_types::{
+	canary::CanaryReport,
+	key_ceremony::{ApprovedKeyCeremony, EncryptedStateKeyEscrow, KeyCeremonyTrail},
+	load_shedding::LoadSheddingPolicy,
+	maintenance_window::MaintenanceWindow,
+	panic_policy::PanicPolicy,
+	parentchain::Header,
+	payload_quarantine::{EncryptedQuarantineExport, SignedQuarantineExportRequest},
+	shard_export::SignedShardExportRequest,
+	shard_routing::ShardRoute,
+	smoke_test::SignedSmokeTestReport,
+	state_diff::StateDiff,
+	Balance, ShardIdentifier,
+};
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
 use sp_core::ed25519;
 use teerex_primitives::EnclaveFingerprint;
@@ -85,6 +98,81 @@ pub trait EnclaveBase: Send + Sync + 'static {
 	fn get_ecc_vault_pubkey(&self, shard: &ShardIdentifier) -> EnclaveResult<ed25519::Public>;
 
 	fn get_fingerprint(&self) -> EnclaveResult<EnclaveFingerprint>;
+
+	/// Start quarantining rejected shielded payloads, up to `max_bytes` in total.
+	fn enable_payload_quarantine(&self, max_bytes: u64) -> EnclaveResult<()>;
+
+	/// Export the quarantined payloads of a shard, the request must be signed by its root.
+	fn export_payload_quarantine(
+		&self,
+		request: &SignedQuarantineExportRequest,
+	) -> EnclaveResult<EncryptedQuarantineExport>;
+
+	/// Replace the maintenance windows of a shard, trusted calls are deferred during a window.
+	fn set_maintenance_windows(
+		&self,
+		shard: &ShardIdentifier,
+		windows: &[MaintenanceWindow],
+	) -> EnclaveResult<()>;
+
+	/// Enable the load shedding under sustained overload.
+	fn set_load_shedding_policy(&self, policy: &LoadSheddingPolicy) -> EnclaveResult<()>;
+
+	/// Stop authoring for a shard whose last sidechain block lags the last block confirmed on the
+	/// parentchain by more than `threshold` blocks, until it caught up with a peer.
+	fn set_stale_shard_threshold(&self, threshold: u64) -> EnclaveResult<()>;
+
+	/// Choose whether an unrecoverable error on a shard fails the enclave or only isolates the
+	/// shard.
+	fn set_panic_policy(&self, policy: PanicPolicy) -> EnclaveResult<()>;
+
+	/// Keep these trusted call types, by name, out of the payload quarantine and the logs.
+	fn set_never_persist_calls(&self, call_names: &[String]) -> EnclaveResult<()>;
+
+	/// Replace the routes of the shards served by other worker instances.
+	fn set_shard_routes(&self, routes: &[ShardRoute]) -> EnclaveResult<()>;
+
+	/// Conduct a key ceremony approved by the operators, returns the escrow of an escrow ceremony.
+	fn conduct_key_ceremony(
+		&self,
+		ceremony: &ApprovedKeyCeremony,
+	) -> EnclaveResult<Option<EncryptedStateKeyEscrow>>;
+
+	/// Export the operator quorums and the trail of the conducted key ceremonies.
+	fn export_key_ceremony_trail(&self) -> EnclaveResult<KeyCeremonyTrail>;
+
+	/// Issue the nonce the next key ceremony has to be signed over.
+	fn issue_key_ceremony_nonce(&self) -> EnclaveResult<[u8; 32]>;
+
+	/// Run an init, transfer, getter and purge flow against a throwaway shard, returns the report
+	/// signed by the enclave.
+	fn run_smoke_test(&self) -> EnclaveResult<SignedSmokeTestReport>;
+
+	/// Run a round of the canary on the shard: a transfer and a getter on its own state, which is
+	/// kept apart from the served shard.
+	fn run_canary_round(&self, shard: &ShardIdentifier) -> EnclaveResult<CanaryReport>;
+
+	/// Stream an encrypted backup of the state of the shard to the existing, empty file at `path`.
+	fn export_state_backup(&self, shard: &ShardIdentifier, path: &str) -> EnclaveResult<()>;
+
+	/// Replace the state of the shard of the backup at `path` with the state of the backup.
+	fn import_state_backup(&self, path: &str) -> EnclaveResult<()>;
+
+	/// Stream the state of the shard, encrypted to the key of its owner, to the existing, empty
+	/// file at `path`.
+	fn export_shard_state(
+		&self,
+		request: &SignedShardExportRequest,
+		path: &str,
+	) -> EnclaveResult<()>;
+
+	/// Compare two state snapshots of the shard, identified by the ids in their file names.
+	fn diff_state_snapshots(
+		&self,
+		shard: &ShardIdentifier,
+		from: u128,
+		to: u128,
+	) -> EnclaveResult<StateDiff>;
 }
 
 /// EnclaveApi implementation for Enclave struct
@@ -98,11 +186,23 @@ mod impl_ffi {
 	use itc_parentchain::primitives::{ParentchainId, ParentchainInitParams};
 	use itp_enclave_api_ffi as ffi;
 	use itp_settings::worker::{
-		HEADER_MAX_SIZE, MR_ENCLAVE_SIZE, SHIELDING_KEY_SIZE, SIGNING_KEY_SIZE,
+		CANARY_REPORT_SIZE, HEADER_MAX_SIZE, KEY_CEREMONY_OUTCOME_SIZE,
+		KEY_CEREMONY_TRAIL_MAX_SIZE, MR_ENCLAVE_SIZE, PAYLOAD_QUARANTINE_MAX_SIZE,
+		SHIELDING_KEY_SIZE, SIGNING_KEY_SIZE, SMOKE_TEST_REPORT_SIZE, STATE_DIFF_SIZE,
 	};
 	use itp_stf_interface::ShardCreationInfo;
 	use itp_types::{
+		canary::CanaryReport,
+		key_ceremony::{ApprovedKeyCeremony, EncryptedStateKeyEscrow, KeyCeremonyTrail},
+		load_shedding::LoadSheddingPolicy,
+		maintenance_window::MaintenanceWindow,
+		panic_policy::PanicPolicy,
 		parentchain::{Balance, Header},
+		payload_quarantine::{EncryptedQuarantineExport, SignedQuarantineExportRequest},
+		shard_export::SignedShardExportRequest,
+		shard_routing::ShardRoute,
+		smoke_test::SignedSmokeTestReport,
+		state_diff::{StateDiff, StateDiffRequest},
 		ShardIdentifier,
 	};
 	use log::*;
@@ -411,6 +511,333 @@ mod impl_ffi {
 
 			Ok(mr_enclave.into())
 		}
+
+		fn enable_payload_quarantine(&self, max_bytes: u64) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+
+			let result =
+				unsafe { ffi::enable_payload_quarantine(self.eid, &mut retval, max_bytes) };
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn export_payload_quarantine(
+			&self,
+			request: &SignedQuarantineExportRequest,
+		) -> EnclaveResult<EncryptedQuarantineExport> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let request = request.encode();
+			// The enclave public key and the length prefixes are encoded along the archive.
+			let mut export = vec![0u8; PAYLOAD_QUARANTINE_MAX_SIZE + 64];
+
+			let result = unsafe {
+				ffi::export_payload_quarantine(
+					self.eid,
+					&mut retval,
+					request.as_ptr(),
+					request.len() as u32,
+					export.as_mut_ptr(),
+					export.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Decode::decode(&mut export.as_slice()).map_err(|e| Error::Codec(e.into()))
+		}
+
+		fn set_maintenance_windows(
+			&self,
+			shard: &ShardIdentifier,
+			windows: &[MaintenanceWindow],
+		) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let shard = shard.encode();
+			let windows = windows.encode();
+
+			let result = unsafe {
+				ffi::set_maintenance_windows(
+					self.eid,
+					&mut retval,
+					shard.as_ptr(),
+					shard.len() as u32,
+					windows.as_ptr(),
+					windows.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn set_load_shedding_policy(&self, policy: &LoadSheddingPolicy) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let policy = policy.encode();
+
+			let result = unsafe {
+				ffi::set_load_shedding_policy(
+					self.eid,
+					&mut retval,
+					policy.as_ptr(),
+					policy.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn set_stale_shard_threshold(&self, threshold: u64) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+
+			let result =
+				unsafe { ffi::set_stale_shard_threshold(self.eid, &mut retval, threshold) };
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn set_panic_policy(&self, policy: PanicPolicy) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let policy = policy.encode();
+
+			let result = unsafe {
+				ffi::set_panic_policy(self.eid, &mut retval, policy.as_ptr(), policy.len() as u32)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn set_never_persist_calls(&self, call_names: &[String]) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let call_names = call_names.encode();
+
+			let result = unsafe {
+				ffi::set_never_persist_calls(
+					self.eid,
+					&mut retval,
+					call_names.as_ptr(),
+					call_names.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn set_shard_routes(&self, routes: &[ShardRoute]) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let routes = routes.encode();
+
+			let result = unsafe {
+				ffi::set_shard_routes(self.eid, &mut retval, routes.as_ptr(), routes.len() as u32)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn conduct_key_ceremony(
+			&self,
+			ceremony: &ApprovedKeyCeremony,
+		) -> EnclaveResult<Option<EncryptedStateKeyEscrow>> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let ceremony = ceremony.encode();
+			let mut outcome = vec![0u8; KEY_CEREMONY_OUTCOME_SIZE];
+
+			let result = unsafe {
+				ffi::conduct_key_ceremony(
+					self.eid,
+					&mut retval,
+					ceremony.as_ptr(),
+					ceremony.len() as u32,
+					outcome.as_mut_ptr(),
+					outcome.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Decode::decode(&mut outcome.as_slice()).map_err(|e| Error::Codec(e.into()))
+		}
+
+		fn export_key_ceremony_trail(&self) -> EnclaveResult<KeyCeremonyTrail> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let mut trail = vec![0u8; KEY_CEREMONY_TRAIL_MAX_SIZE];
+
+			let result = unsafe {
+				ffi::export_key_ceremony_trail(
+					self.eid,
+					&mut retval,
+					trail.as_mut_ptr(),
+					trail.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Decode::decode(&mut trail.as_slice()).map_err(|e| Error::Codec(e.into()))
+		}
+
+		fn issue_key_ceremony_nonce(&self) -> EnclaveResult<[u8; 32]> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let mut nonce = [0u8; 32];
+
+			let result = unsafe {
+				ffi::issue_key_ceremony_nonce(
+					self.eid,
+					&mut retval,
+					nonce.as_mut_ptr(),
+					nonce.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(nonce)
+		}
+
+		fn run_smoke_test(&self) -> EnclaveResult<SignedSmokeTestReport> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let mut report = vec![0u8; SMOKE_TEST_REPORT_SIZE];
+
+			let result = unsafe {
+				ffi::run_smoke_test(self.eid, &mut retval, report.as_mut_ptr(), report.len() as u32)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Decode::decode(&mut report.as_slice()).map_err(|e| Error::Codec(e.into()))
+		}
+
+		fn run_canary_round(&self, shard: &ShardIdentifier) -> EnclaveResult<CanaryReport> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let shard = shard.encode();
+			let mut report = vec![0u8; CANARY_REPORT_SIZE];
+
+			let result = unsafe {
+				ffi::run_canary_round(
+					self.eid,
+					&mut retval,
+					shard.as_ptr(),
+					shard.len() as u32,
+					report.as_mut_ptr(),
+					report.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Decode::decode(&mut report.as_slice()).map_err(|e| Error::Codec(e.into()))
+		}
+
+		fn export_state_backup(&self, shard: &ShardIdentifier, path: &str) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let shard = shard.encode();
+
+			let result = unsafe {
+				ffi::export_state_backup(
+					self.eid,
+					&mut retval,
+					shard.as_ptr(),
+					shard.len() as u32,
+					path.as_ptr(),
+					path.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn import_state_backup(&self, path: &str) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+
+			let result = unsafe {
+				ffi::import_state_backup(self.eid, &mut retval, path.as_ptr(), path.len() as u32)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn export_shard_state(
+			&self,
+			request: &SignedShardExportRequest,
+			path: &str,
+		) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let request = request.encode();
+
+			let result = unsafe {
+				ffi::export_shard_state(
+					self.eid,
+					&mut retval,
+					request.as_ptr(),
+					request.len() as u32,
+					path.as_ptr(),
+					path.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
+
+		fn diff_state_snapshots(
+			&self,
+			shard: &ShardIdentifier,
+			from: u128,
+			to: u128,
+		) -> EnclaveResult<StateDiff> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+			let request = StateDiffRequest { shard: *shard, from, to }.encode();
+			let mut diff = vec![0u8; STATE_DIFF_SIZE];
+
+			let result = unsafe {
+				ffi::diff_state_snapshots(
+					self.eid,
+					&mut retval,
+					request.as_ptr(),
+					request.len() as u32,
+					diff.as_mut_ptr(),
+					diff.len() as u32,
+				)
+			};
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Decode::decode(&mut diff.as_slice()).map_err(|e| Error::Codec(e.into()))
+		}
 	}
 
 	fn init_parentchain_components_ffi(
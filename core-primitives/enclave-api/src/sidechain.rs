@@ -36,6 +36,9 @@ pub trait Sidechain: Send + Sync + 'static {
 	) -> EnclaveResult<()>;
 
 	fn execute_trusted_calls(&self) -> EnclaveResult<()>;
+
+	/// Report the EPC usage of the enclave, high usage counts as overload when shedding load.
+	fn report_epc_usage(&self, used_bytes: u64, total_bytes: u64) -> EnclaveResult<()>;
 }
 
 #[cfg(feature = "implement-ffi")]
@@ -97,5 +100,17 @@ mod impl_ffi {
 
 			Ok(())
 		}
+
+		fn report_epc_usage(&self, used_bytes: u64, total_bytes: u64) -> EnclaveResult<()> {
+			let mut retval = sgx_status_t::SGX_SUCCESS;
+
+			let result =
+				unsafe { ffi::report_epc_usage(self.eid, &mut retval, used_bytes, total_bytes) };
+
+			ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+			ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+			Ok(())
+		}
 	}
 }
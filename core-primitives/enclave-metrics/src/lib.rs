@@ -38,6 +38,11 @@ pub enum EnclaveMetric {
 	TopPoolSizeDecrement,
 	ExchangeRateOracle(ExchangeRateOracleMetric),
 	// OracleMetric(OracleMetric<MetricsInfo>),
+	/// Number of active load shedding actions, sent on every transition.
+	LoadSheddingLevelSet(u64),
+	/// Number of blocks a stale shard lags the parentchain, sent on every check of a stale shard
+	/// and once with 0 when it has caught up.
+	StaleShardLagSet(u64),
 }
 
 #[derive(Encode, Decode, Debug)]
@@ -0,0 +1,205 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Coordination of a scheduled upgrade to an enclave with a new MRENCLAVE.
+//!
+//! Governance announces the MRENCLAVE of the new enclave for a shard and the parentchain block at
+//! which it takes over. Until then, the old enclave provisions its secrets and the state of that
+//! shard to enclaves with the announced MRENCLAVE. At the activation block, the old enclave stops
+//! authoring sidechain blocks and the new one starts.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(feature = "std", feature = "sgx"))]
+compile_error!("feature \"std\" and feature \"sgx\" cannot be enabled at the same time");
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+extern crate sgx_tstd as std;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use itp_sgx_io::{read as unseal, write as seal};
+#[cfg(feature = "sgx")]
+use itp_sgx_io::{seal, unseal};
+
+use codec::{Decode, Encode};
+use itp_types::ShardIdentifier;
+use lazy_static::lazy_static;
+use log::*;
+use std::{io::ErrorKind, path::PathBuf};
+
+pub type MrEnclave = [u8; 32];
+pub type BlockNumber = u32;
+
+lazy_static! {
+	/// Global instance of the upgrade coordinator.
+	///
+	/// Upgrades are scheduled by the parentchain event handler and by the state provisioning.
+	pub static ref GLOBAL_UPGRADE_COORDINATOR: UpgradeCoordinator = Default::default();
+}
+
+#[derive(Encode, Decode, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ScheduledUpgrade {
+	/// Shard the upgrade has been announced for.
+	pub shard: ShardIdentifier,
+	pub mrenclave: MrEnclave,
+	/// First parentchain block at which the new enclave authors sidechain blocks.
+	pub activation_block: BlockNumber,
+}
+
+#[derive(Debug)]
+pub enum Error {
+	LockPoisoning,
+	Io(std::io::Error),
+	Codec(codec::Error),
+}
+
+impl From<std::io::Error> for Error {
+	fn from(e: std::io::Error) -> Self {
+		Self::Io(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Self::Codec(e)
+	}
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Default)]
+pub struct UpgradeCoordinator {
+	scheduled: RwLock<Option<ScheduledUpgrade>>,
+	/// File the schedule is sealed to, so it survives a restart of the enclave.
+	sealed_file: RwLock<Option<PathBuf>>,
+}
+
+impl UpgradeCoordinator {
+	/// Loads the schedule sealed to `path`, if any, and seals all later schedules to it.
+	pub fn use_sealed_file(&self, path: PathBuf) -> Result<()> {
+		match unseal(&path) {
+			Ok(bytes) => {
+				let upgrade = ScheduledUpgrade::decode(&mut bytes.as_slice())?;
+				info!("Loaded sealed enclave upgrade schedule: {:?}", upgrade);
+				*self.scheduled.write().map_err(|_| Error::LockPoisoning)? = Some(upgrade);
+			},
+			Err(e) if e.kind() == ErrorKind::NotFound => {},
+			Err(e) => return Err(e.into()),
+		}
+		*self.sealed_file.write().map_err(|_| Error::LockPoisoning)? = Some(path);
+		Ok(())
+	}
+
+	/// Replaces a previously scheduled upgrade.
+	pub fn schedule(&self, upgrade: ScheduledUpgrade) -> Result<()> {
+		if let Some(path) = self.sealed_file.read().map_err(|_| Error::LockPoisoning)?.as_ref() {
+			seal(&upgrade.encode(), path)?;
+		}
+		info!(
+			"Scheduled enclave upgrade of shard {:?} to MRENCLAVE {:?} at parentchain block {}",
+			upgrade.shard, upgrade.mrenclave, upgrade.activation_block
+		);
+		*self.scheduled.write().map_err(|_| Error::LockPoisoning)? = Some(upgrade);
+		Ok(())
+	}
+
+	pub fn scheduled(&self) -> Result<Option<ScheduledUpgrade>> {
+		Ok(*self.scheduled.read().map_err(|_| Error::LockPoisoning)?)
+	}
+
+	/// The scheduled upgrade, if it has been announced for `shard`.
+	pub fn scheduled_for(&self, shard: &ShardIdentifier) -> Result<Option<ScheduledUpgrade>> {
+		Ok(self.scheduled()?.filter(|upgrade| upgrade.shard == *shard))
+	}
+
+	/// Whether `mrenclave` is the announced new enclave, which may be provisioned with our secrets.
+	pub fn is_upgrade_target(&self, mrenclave: &MrEnclave) -> Result<bool> {
+		Ok(self.scheduled()?.map_or(false, |upgrade| upgrade.mrenclave == *mrenclave))
+	}
+
+	/// Whether the enclave with `own_mrenclave` may author sidechain blocks on top of the given
+	/// parentchain block. The old enclave authors before the activation block, the new one from
+	/// the activation block on.
+	pub fn may_author(&self, own_mrenclave: &MrEnclave, block_number: BlockNumber) -> Result<bool> {
+		Ok(match self.scheduled()? {
+			None => true,
+			Some(upgrade) if upgrade.mrenclave == *own_mrenclave =>
+				block_number >= upgrade.activation_block,
+			Some(upgrade) => block_number < upgrade.activation_block,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const OLD_MRENCLAVE: MrEnclave = [1u8; 32];
+	const NEW_MRENCLAVE: MrEnclave = [2u8; 32];
+
+	fn shard() -> ShardIdentifier {
+		ShardIdentifier::repeat_byte(3)
+	}
+
+	fn upgrade() -> ScheduledUpgrade {
+		ScheduledUpgrade { shard: shard(), mrenclave: NEW_MRENCLAVE, activation_block: 100 }
+	}
+
+	#[test]
+	fn authoring_switches_at_the_activation_block() {
+		let coordinator = UpgradeCoordinator::default();
+		assert!(coordinator.may_author(&OLD_MRENCLAVE, 99).unwrap());
+
+		coordinator.schedule(upgrade()).unwrap();
+
+		assert!(coordinator.may_author(&OLD_MRENCLAVE, 99).unwrap());
+		assert!(!coordinator.may_author(&NEW_MRENCLAVE, 99).unwrap());
+		assert!(!coordinator.may_author(&OLD_MRENCLAVE, 100).unwrap());
+		assert!(coordinator.may_author(&NEW_MRENCLAVE, 100).unwrap());
+		assert!(coordinator.is_upgrade_target(&NEW_MRENCLAVE).unwrap());
+		assert!(!coordinator.is_upgrade_target(&OLD_MRENCLAVE).unwrap());
+	}
+
+	#[test]
+	fn upgrade_is_only_scheduled_for_its_shard() {
+		let coordinator = UpgradeCoordinator::default();
+		coordinator.schedule(upgrade()).unwrap();
+
+		assert_eq!(coordinator.scheduled_for(&shard()).unwrap(), Some(upgrade()));
+		assert_eq!(coordinator.scheduled_for(&ShardIdentifier::repeat_byte(4)).unwrap(), None);
+	}
+
+	#[test]
+	fn sealed_schedule_survives_a_restart() {
+		let path = std::env::temp_dir().join("sealed_schedule_survives_a_restart.bin");
+		let _ = std::fs::remove_file(&path);
+		let coordinator = UpgradeCoordinator::default();
+		coordinator.use_sealed_file(path.clone()).unwrap();
+		coordinator.schedule(upgrade()).unwrap();
+
+		let restarted_coordinator = UpgradeCoordinator::default();
+		restarted_coordinator.use_sealed_file(path.clone()).unwrap();
+
+		assert_eq!(restarted_coordinator.scheduled().unwrap(), Some(upgrade()));
+		std::fs::remove_file(path).unwrap();
+	}
+}
@@ -0,0 +1,107 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Hash functions the state hash can be computed with.
+//!
+//! Chains verifying commitments to a shard state, e.g. through a bridge, may only support a
+//! particular hash function, so it is configured per shard. The algorithm is stored in the state
+//! itself, under the storage value `StateHash::Algorithm`.
+
+use codec::{Decode, Encode};
+use sp_core::{
+	hashing::{blake2_256, keccak_256, sha2_256, twox_128},
+	H256,
+};
+
+pub const STATE_HASH: &str = "StateHash";
+pub const ALGORITHM: &str = "Algorithm";
+
+/// Hash function with a 32 byte digest.
+pub trait HashFunction {
+	fn hash_bytes(&self, data: &[u8]) -> H256;
+}
+
+pub struct Blake2b256;
+
+impl HashFunction for Blake2b256 {
+	fn hash_bytes(&self, data: &[u8]) -> H256 {
+		blake2_256(data).into()
+	}
+}
+
+pub struct Sha256;
+
+impl HashFunction for Sha256 {
+	fn hash_bytes(&self, data: &[u8]) -> H256 {
+		sha2_256(data).into()
+	}
+}
+
+pub struct Keccak256;
+
+impl HashFunction for Keccak256 {
+	fn hash_bytes(&self, data: &[u8]) -> H256 {
+		keccak_256(data).into()
+	}
+}
+
+/// Hash function of the state hash of a shard.
+#[derive(Encode, Decode, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StateHashAlgorithm {
+	#[default]
+	Blake2b256,
+	Sha256,
+	Keccak256,
+}
+
+impl StateHashAlgorithm {
+	pub fn hash_function(&self) -> &'static dyn HashFunction {
+		match self {
+			StateHashAlgorithm::Blake2b256 => &Blake2b256,
+			StateHashAlgorithm::Sha256 => &Sha256,
+			StateHashAlgorithm::Keccak256 => &Keccak256,
+		}
+	}
+}
+
+impl HashFunction for StateHashAlgorithm {
+	fn hash_bytes(&self, data: &[u8]) -> H256 {
+		self.hash_function().hash_bytes(data)
+	}
+}
+
+/// Storage key of the algorithm in the state, the key of the storage value `StateHash::Algorithm`.
+pub fn state_hash_algorithm_key() -> [u8; 32] {
+	let mut key = [0u8; 32];
+	key[..16].copy_from_slice(&twox_128(STATE_HASH.as_bytes()));
+	key[16..].copy_from_slice(&twox_128(ALGORITHM.as_bytes()));
+	key
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn algorithms_hash_like_their_hash_function() {
+		let data = b"state";
+
+		assert_eq!(StateHashAlgorithm::default().hash_bytes(data), H256::from(blake2_256(data)));
+		assert_eq!(StateHashAlgorithm::Sha256.hash_bytes(data), H256::from(sha2_256(data)));
+		assert_eq!(StateHashAlgorithm::Keccak256.hash_bytes(data), H256::from(keccak_256(data)));
+	}
+}
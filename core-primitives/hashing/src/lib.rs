@@ -20,6 +20,7 @@
 
 use sp_core::H256;
 
+pub mod hash_function;
 #[cfg(feature = "std")]
 pub mod std_hash;
 
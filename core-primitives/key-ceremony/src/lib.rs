@@ -0,0 +1,368 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Approval of the key ceremonies by the operator quorums of the shards, see
+//! [`itp_types::key_ceremony`].
+//!
+//! The quorums and the trail of the conducted ceremonies are sealed, so they survive a restart of
+//! the enclave. The trail is bounded in size, the oldest records are dropped first.
+//!
+//! A ceremony has to be signed over the nonce the coordinator issued last, which is sealed with
+//! the trail and consumed once the ceremony is approved.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(feature = "std", feature = "sgx"))]
+compile_error!("feature \"std\" and feature \"sgx\" cannot be enabled at the same time");
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+extern crate sgx_tstd as std;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use itp_sgx_io::{read as unseal, write as seal};
+#[cfg(feature = "sgx")]
+use itp_sgx_io::{seal, unseal};
+
+use codec::{Decode, Encode};
+use itp_types::{
+	key_ceremony::{
+		ApprovedKeyCeremony, KeyCeremonyOperation, KeyCeremonyRecord, KeyCeremonyTrail,
+		OperatorQuorum,
+	},
+	AccountId, ShardIdentifier,
+};
+use log::*;
+use std::{io::ErrorKind, path::PathBuf, string::String, vec, vec::Vec};
+
+#[derive(Debug)]
+pub enum Error {
+	LockPoisoning,
+	Io(std::io::Error),
+	Codec(codec::Error),
+	/// The ceremony id does not exceed the one of the last conducted ceremony.
+	Replayed(u64),
+	/// The ceremony is meant for the enclave with this signing account.
+	OtherEnclave(AccountId),
+	/// The ceremony is not signed over the pending nonce.
+	UnknownNonce,
+	InvalidQuorum,
+	/// The approvals don't satisfy the quorum of the shard.
+	NotApproved(ShardIdentifier),
+	/// There is no shard whose operators could approve the ceremony.
+	NoShards,
+	/// The operation itself failed.
+	Failed(String),
+}
+
+impl From<std::io::Error> for Error {
+	fn from(e: std::io::Error) -> Self {
+		Self::Io(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Self::Codec(e)
+	}
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+pub struct KeyCeremonyCoordinator {
+	/// Signing account of the enclave, the ceremonies have to be meant for.
+	enclave: AccountId,
+	trail: RwLock<KeyCeremonyTrail>,
+	/// Upper bound of the encoded trail size.
+	max_trail_size: usize,
+	/// File the trail is sealed to.
+	sealed_file: RwLock<Option<PathBuf>>,
+}
+
+impl KeyCeremonyCoordinator {
+	pub fn new(max_trail_size: usize, enclave: AccountId) -> Self {
+		KeyCeremonyCoordinator {
+			enclave,
+			trail: Default::default(),
+			max_trail_size,
+			sealed_file: Default::default(),
+		}
+	}
+
+	/// Loads the trail sealed to `path`, if any, and seals all later changes to it.
+	pub fn use_sealed_file(&self, path: PathBuf) -> Result<()> {
+		match unseal(&path) {
+			Ok(bytes) => {
+				let trail = KeyCeremonyTrail::decode(&mut bytes.as_slice())?;
+				debug!(
+					"Loaded the key ceremony trail with {} records and {} operator quorums",
+					trail.records.len(),
+					trail.quorums.len()
+				);
+				*self.trail.write().map_err(|_| Error::LockPoisoning)? = trail;
+			},
+			Err(e) if e.kind() == ErrorKind::NotFound => {},
+			Err(e) => return Err(e.into()),
+		}
+		*self.sealed_file.write().map_err(|_| Error::LockPoisoning)? = Some(path);
+		Ok(())
+	}
+
+	pub fn trail(&self) -> Result<KeyCeremonyTrail> {
+		Ok(self.trail.read().map_err(|_| Error::LockPoisoning)?.clone())
+	}
+
+	/// Makes `nonce` the one the next ceremony has to be signed over. `nonce` has to be drawn
+	/// from a secure random source.
+	pub fn issue_nonce(&self, nonce: [u8; 32]) -> Result<()> {
+		let mut trail = self.trail.write().map_err(|_| Error::LockPoisoning)?;
+		trail.pending_nonce = Some(nonce);
+		self.seal(&trail)
+	}
+
+	/// Performs the operation of the ceremony if its approvals satisfy the quorums of all
+	/// concerned shards, and records it. `shard_root` gives the root account of a shard, which
+	/// approves alone if the shard has no quorum.
+	pub fn conduct<R>(
+		&self,
+		ceremony: &ApprovedKeyCeremony,
+		shards: &[ShardIdentifier],
+		shard_root: impl Fn(&ShardIdentifier) -> Option<AccountId>,
+		conducted_at: u64,
+		perform: impl FnOnce(&KeyCeremonyOperation) -> core::result::Result<R, String>,
+	) -> Result<R> {
+		let mut trail = self.trail.write().map_err(|_| Error::LockPoisoning)?;
+		let request = &ceremony.request;
+		if request.ceremony_id <= trail.last_ceremony_id {
+			return Err(Error::Replayed(request.ceremony_id))
+		}
+		if request.enclave != self.enclave {
+			return Err(Error::OtherEnclave(request.enclave.clone()))
+		}
+		if trail.pending_nonce != Some(request.nonce) {
+			return Err(Error::UnknownNonce)
+		}
+
+		let concerned_shards = match &request.operation {
+			KeyCeremonyOperation::SetOperatorQuorum { shard, quorum } => {
+				if !quorum.is_valid() {
+					return Err(Error::InvalidQuorum)
+				}
+				vec![*shard]
+			},
+			KeyCeremonyOperation::RotateStateKey | KeyCeremonyOperation::EscrowStateKey { .. } => {
+				let mut concerned_shards: Vec<ShardIdentifier> =
+					trail.quorums.keys().copied().collect();
+				concerned_shards.extend(shards.iter().filter(|s| !trail.quorums.contains_key(s)));
+				concerned_shards
+			},
+		};
+		if concerned_shards.is_empty() {
+			return Err(Error::NoShards)
+		}
+		for shard in concerned_shards.iter() {
+			let quorum = match trail.quorums.get(shard) {
+				Some(quorum) => quorum.clone(),
+				None => OperatorQuorum {
+					operators: shard_root(shard).into_iter().collect(),
+					threshold: 1,
+				},
+			};
+			if !ceremony.is_approved_by(&quorum) {
+				return Err(Error::NotApproved(*shard))
+			}
+		}
+
+		// Consume the nonce before performing the operation, so a ceremony that fails half-way
+		// can't be repeated without new approvals.
+		trail.pending_nonce = None;
+		self.seal(&trail)?;
+
+		let result = perform(&request.operation).map_err(Error::Failed)?;
+		info!("Conducted key ceremony #{}: {:?}", request.ceremony_id, request.operation);
+
+		if let KeyCeremonyOperation::SetOperatorQuorum { shard, quorum } = &request.operation {
+			trail.quorums.insert(*shard, quorum.clone());
+		}
+		trail.last_ceremony_id = request.ceremony_id;
+		trail
+			.records
+			.push_back(KeyCeremonyRecord { ceremony: ceremony.clone(), conducted_at });
+		while trail.encoded_size() > self.max_trail_size && trail.records.pop_front().is_some() {}
+		self.seal(&trail)?;
+		Ok(result)
+	}
+
+	fn seal(&self, trail: &KeyCeremonyTrail) -> Result<()> {
+		if let Some(path) = self.sealed_file.read().map_err(|_| Error::LockPoisoning)?.as_ref() {
+			seal(&trail.encode(), path)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_types::key_ceremony::{KeyCeremonyRequest, OperatorApproval};
+	use sp_core::{ed25519, Pair};
+
+	const SHARD: ShardIdentifier = ShardIdentifier::repeat_byte(1);
+	const ENCLAVE_SEED: u8 = 9;
+	const NONCE: [u8; 32] = [5u8; 32];
+
+	fn operator(seed: u8) -> ed25519::Pair {
+		ed25519::Pair::from_seed(&[seed; 32])
+	}
+
+	fn account(seed: u8) -> AccountId {
+		operator(seed).public().into()
+	}
+
+	fn ceremony(
+		operation: KeyCeremonyOperation,
+		ceremony_id: u64,
+		approvers: &[u8],
+	) -> ApprovedKeyCeremony {
+		let request = KeyCeremonyRequest {
+			operation,
+			ceremony_id,
+			enclave: account(ENCLAVE_SEED),
+			nonce: NONCE,
+		};
+		let approvals = approvers
+			.iter()
+			.map(|seed| OperatorApproval {
+				operator: account(*seed),
+				signature: operator(*seed).sign(&request.encode()).into(),
+			})
+			.collect();
+		ApprovedKeyCeremony { request, approvals }
+	}
+
+	fn set_quorum(ceremony_id: u64, approvers: &[u8]) -> ApprovedKeyCeremony {
+		let quorum =
+			OperatorQuorum { operators: vec![account(2), account(3), account(4)], threshold: 2 };
+		ceremony(
+			KeyCeremonyOperation::SetOperatorQuorum { shard: SHARD, quorum },
+			ceremony_id,
+			approvers,
+		)
+	}
+
+	fn test_coordinator() -> KeyCeremonyCoordinator {
+		KeyCeremonyCoordinator::new(1024 * 1024, account(ENCLAVE_SEED))
+	}
+
+	/// Issues the nonce the test ceremonies are signed over and conducts the ceremony.
+	fn conduct(coordinator: &KeyCeremonyCoordinator, ceremony: &ApprovedKeyCeremony) -> Result<()> {
+		coordinator.issue_nonce(NONCE).unwrap();
+		conduct_without_nonce(coordinator, ceremony)
+	}
+
+	fn conduct_without_nonce(
+		coordinator: &KeyCeremonyCoordinator,
+		ceremony: &ApprovedKeyCeremony,
+	) -> Result<()> {
+		// The root account of the shard is the operator with seed 1.
+		coordinator.conduct(ceremony, &[SHARD], |_| Some(account(1)), 0, |_| Ok(()))
+	}
+
+	#[test]
+	fn quorum_of_a_shard_replaces_its_root() {
+		let coordinator = test_coordinator();
+		let rotate =
+			|id, approvers: &[u8]| ceremony(KeyCeremonyOperation::RotateStateKey, id, approvers);
+		conduct(&coordinator, &rotate(1, &[1])).unwrap();
+		conduct(&coordinator, &set_quorum(2, &[1])).unwrap();
+
+		assert!(matches!(conduct(&coordinator, &rotate(3, &[1, 2])), Err(Error::NotApproved(_))));
+		conduct(&coordinator, &rotate(3, &[2, 4])).unwrap();
+		assert_eq!(coordinator.trail().unwrap().records.len(), 3);
+	}
+
+	#[test]
+	fn replayed_ceremony_is_rejected() {
+		let coordinator = test_coordinator();
+		let ceremony = set_quorum(1, &[1]);
+		conduct(&coordinator, &ceremony).unwrap();
+
+		assert!(matches!(conduct(&coordinator, &ceremony), Err(Error::Replayed(1))));
+	}
+
+	#[test]
+	fn ceremony_needs_the_pending_nonce() {
+		let coordinator = test_coordinator();
+		let ceremony = set_quorum(1, &[1]);
+		assert!(matches!(conduct_without_nonce(&coordinator, &ceremony), Err(Error::UnknownNonce)));
+
+		coordinator.issue_nonce([6u8; 32]).unwrap();
+		assert!(matches!(conduct_without_nonce(&coordinator, &ceremony), Err(Error::UnknownNonce)));
+
+		coordinator.issue_nonce(NONCE).unwrap();
+		conduct_without_nonce(&coordinator, &ceremony).unwrap();
+		assert_eq!(coordinator.trail().unwrap().pending_nonce, None);
+	}
+
+	#[test]
+	fn nonce_is_consumed_by_a_failing_ceremony() {
+		let coordinator = test_coordinator();
+		coordinator.issue_nonce(NONCE).unwrap();
+		let ceremony = set_quorum(1, &[1]);
+
+		let result = coordinator.conduct(
+			&ceremony,
+			&[SHARD],
+			|_| Some(account(1)),
+			0,
+			|_| -> core::result::Result<(), _> { Err("interrupted".into()) },
+		);
+
+		assert!(matches!(result, Err(Error::Failed(_))));
+		assert!(matches!(conduct_without_nonce(&coordinator, &ceremony), Err(Error::UnknownNonce)));
+	}
+
+	#[test]
+	fn ceremony_for_another_enclave_is_rejected() {
+		let coordinator = KeyCeremonyCoordinator::new(1024 * 1024, account(8));
+
+		assert!(matches!(conduct(&coordinator, &set_quorum(1, &[1])), Err(Error::OtherEnclave(_))));
+	}
+
+	#[test]
+	fn sealed_trail_survives_a_restart() {
+		let path = std::env::temp_dir().join("sealed_key_ceremony_trail_survives_a_restart.bin");
+		let _ = std::fs::remove_file(&path);
+		let coordinator = test_coordinator();
+		coordinator.use_sealed_file(path.clone()).unwrap();
+		conduct(&coordinator, &set_quorum(1, &[1])).unwrap();
+
+		let restarted_coordinator = test_coordinator();
+		restarted_coordinator.use_sealed_file(path.clone()).unwrap();
+
+		assert_eq!(restarted_coordinator.trail().unwrap(), coordinator.trail().unwrap());
+		assert!(matches!(
+			conduct(&restarted_coordinator, &set_quorum(1, &[2, 3])),
+			Err(Error::Replayed(1))
+		));
+		std::fs::remove_file(path).unwrap();
+	}
+}
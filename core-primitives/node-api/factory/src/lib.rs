@@ -17,7 +17,18 @@
 */
 
 use itp_api_client_types::{ParentchainApi, TungsteniteRpcClient};
+use log::*;
 use sp_core::sr25519;
+use std::{
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+/// An endpoint that failed is only retried after this duration, unless all endpoints failed.
+const FAILED_ENDPOINT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// While connected to a fallback endpoint, the primary endpoint is checked at this interval.
+const PRIMARY_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Trait to create a node API, based on a node URL and signer.
 pub trait CreateNodeApi {
@@ -31,6 +42,8 @@ pub enum NodeApiFactoryError {
 	FailedToCreateRpcClient(itp_api_client_types::RpcClientError),
 	#[error("Failed to create a node API")]
 	FailedToCreateNodeApi(itp_api_client_types::ApiClientError),
+	#[error("No node endpoint configured")]
+	NoEndpointConfigured,
 	#[error(transparent)]
 	Other(#[from] Box<dyn std::error::Error + Sync + Send + 'static>),
 }
@@ -50,20 +63,126 @@ impl From<itp_api_client_types::ApiClientError> for NodeApiFactoryError {
 pub type Result<T> = std::result::Result<T, NodeApiFactoryError>;
 
 /// Node API factory implementation.
+///
+/// Connects to the first healthy node out of a list of endpoints. An endpoint counts as
+/// healthy if the api could be created on it, which fetches the metadata and runtime version.
+/// Endpoints that failed recently are tried last, so a failing primary node doesn't delay
+/// every connection attempt. Long-lived connections should keep a [`Failback`] and call
+/// [`NodeApiFactory::should_fail_back`] regularly to switch back once the primary recovered.
 pub struct NodeApiFactory {
-	node_url: String,
+	node_urls: Vec<String>,
 	signer: sr25519::Pair,
+	last_failures: Mutex<Vec<Option<Instant>>>,
+}
+
+/// Failback state of a single client of the factory, i.e. of one long-lived connection.
+#[derive(Default)]
+pub struct Failback {
+	/// Index of the endpoint the client is connected to, `None` if unknown.
+	endpoint: Option<usize>,
+	last_health_check: Option<Instant>,
 }
 
 impl NodeApiFactory {
 	pub fn new(url: String, signer: sr25519::Pair) -> Self {
-		NodeApiFactory { node_url: url, signer }
+		Self::new_with_fallbacks(vec![url], signer)
 	}
-}
 
-impl CreateNodeApi for NodeApiFactory {
-	fn create_api(&self) -> Result<ParentchainApi> {
-		let rpc_client = TungsteniteRpcClient::new(self.node_url.as_str(), 5)
+	/// Factory with a primary node URL followed by fallback URLs, in order of preference.
+	pub fn new_with_fallbacks(urls: Vec<String>, signer: sr25519::Pair) -> Self {
+		let last_failures = Mutex::new(vec![None; urls.len()]);
+		NodeApiFactory { node_urls: urls, signer, last_failures }
+	}
+
+	/// Like [`CreateNodeApi::create_api`], but records the endpoint in the failback state of
+	/// the client.
+	pub fn create_api_with_failback(&self, failback: &mut Failback) -> Result<ParentchainApi> {
+		let (api, endpoint) = self.connect()?;
+		failback.endpoint = Some(endpoint);
+		Ok(api)
+	}
+
+	/// Whether the client may be connected to a fallback endpoint while the primary endpoint is
+	/// available again. The primary is checked at most every [`PRIMARY_HEALTH_CHECK_INTERVAL`].
+	///
+	/// If true, the client should create a new api, which will connect to the primary.
+	pub fn should_fail_back(&self, failback: &mut Failback) -> bool {
+		if !self.primary_health_check_due(failback, Instant::now()) {
+			return false
+		}
+		match self.create_api_for(&self.node_urls[0]) {
+			Ok(_) => {
+				info!("Primary parentchain node {} is available", self.node_urls[0]);
+				self.set_last_failure(0, None);
+				true
+			},
+			Err(e) => {
+				debug!(
+					"Primary parentchain node {} is still not available: {:?}",
+					self.node_urls[0], e
+				);
+				self.set_last_failure(0, Some(Instant::now()));
+				false
+			},
+		}
+	}
+
+	/// Whether the client is not known to be on the primary and the primary has not been
+	/// checked recently for it. Marks the check as done if it is due.
+	fn primary_health_check_due(&self, failback: &mut Failback, now: Instant) -> bool {
+		if self.node_urls.len() < 2 || failback.endpoint == Some(0) {
+			return false
+		}
+		let due = failback.last_health_check.map_or(true, |checked_at| {
+			now.duration_since(checked_at) >= PRIMARY_HEALTH_CHECK_INTERVAL
+		});
+		if due {
+			failback.last_health_check = Some(now);
+		}
+		due
+	}
+
+	/// Api of the first available endpoint and the index of that endpoint.
+	fn connect(&self) -> Result<(ParentchainApi, usize)> {
+		let mut last_error = NodeApiFactoryError::NoEndpointConfigured;
+		for index in self.endpoint_order(Instant::now()) {
+			let url = &self.node_urls[index];
+			match self.create_api_for(url) {
+				Ok(api) => {
+					if index > 0 {
+						warn!("Using fallback parentchain node {}", url);
+					}
+					self.set_last_failure(index, None);
+					return Ok((api, index))
+				},
+				Err(e) => {
+					warn!("Parentchain node {} is not available: {:?}", url, e);
+					self.set_last_failure(index, Some(Instant::now()));
+					last_error = e;
+				},
+			}
+		}
+		Err(last_error)
+	}
+
+	/// Indices of the endpoints in the order they should be tried.
+	fn endpoint_order(&self, now: Instant) -> Vec<usize> {
+		let last_failures = self.last_failures.lock().expect("lock poisoning");
+		let is_cooling_down = |index: &usize| {
+			last_failures[*index]
+				.map_or(false, |failed_at| now.duration_since(failed_at) < FAILED_ENDPOINT_COOLDOWN)
+		};
+		let (cooling_down, healthy): (Vec<usize>, Vec<usize>) =
+			(0..self.node_urls.len()).partition(is_cooling_down);
+		healthy.into_iter().chain(cooling_down).collect()
+	}
+
+	fn set_last_failure(&self, index: usize, failure: Option<Instant>) {
+		self.last_failures.lock().expect("lock poisoning")[index] = failure;
+	}
+
+	fn create_api_for(&self, url: &str) -> Result<ParentchainApi> {
+		let rpc_client = TungsteniteRpcClient::new(url, 5)
 			.map_err(NodeApiFactoryError::FailedToCreateRpcClient)?;
 		let mut api =
 			ParentchainApi::new(rpc_client).map_err(NodeApiFactoryError::FailedToCreateNodeApi)?;
@@ -71,3 +190,57 @@ impl CreateNodeApi for NodeApiFactory {
 		Ok(api)
 	}
 }
+
+impl CreateNodeApi for NodeApiFactory {
+	fn create_api(&self) -> Result<ParentchainApi> {
+		self.connect().map(|(api, _)| api)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::Pair;
+
+	fn factory(number_of_urls: usize) -> NodeApiFactory {
+		let urls = (0..number_of_urls).map(|i| format!("ws://node{}:9944", i)).collect();
+		NodeApiFactory::new_with_fallbacks(urls, sr25519::Pair::from_seed(&[1u8; 32]))
+	}
+
+	#[test]
+	fn endpoints_are_tried_in_configured_order() {
+		assert_eq!(factory(3).endpoint_order(Instant::now()), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn recently_failed_endpoints_are_tried_last() {
+		let factory = factory(3);
+		let now = Instant::now();
+		factory.set_last_failure(0, Some(now));
+
+		assert_eq!(factory.endpoint_order(now), vec![1, 2, 0]);
+		assert_eq!(factory.endpoint_order(now + FAILED_ENDPOINT_COOLDOWN), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn primary_is_only_checked_periodically_for_clients_not_on_the_primary() {
+		let factory = factory(2);
+		let now = Instant::now();
+		let mut on_primary = Failback { endpoint: Some(0), last_health_check: None };
+		let mut on_fallback = Failback { endpoint: Some(1), last_health_check: None };
+		let mut unknown = Failback::default();
+
+		assert!(!factory.primary_health_check_due(&mut on_primary, now));
+		assert!(factory.primary_health_check_due(&mut on_fallback, now));
+		assert!(!factory.primary_health_check_due(&mut on_fallback, now + Duration::from_secs(1)));
+		assert!(factory.primary_health_check_due(&mut unknown, now + Duration::from_secs(1)));
+		assert!(
+			factory.primary_health_check_due(&mut on_fallback, now + PRIMARY_HEALTH_CHECK_INTERVAL)
+		);
+	}
+
+	#[test]
+	fn single_endpoint_is_never_checked() {
+		assert!(!factory(1).primary_health_check_due(&mut Failback::default(), Instant::now()));
+	}
+}
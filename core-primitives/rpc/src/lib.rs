@@ -28,6 +28,30 @@ use itp_types::DirectRequestStatus;
 use serde::{Deserialize, Serialize};
 use std::{borrow::ToOwned, string::String, vec::Vec};
 
+/// Stable codes of the RPC errors of the enclave, one per error domain. Clients may rely on them.
+pub mod error_code {
+	const BASE_ERROR: i64 = 2000;
+	/// Uninitialized components, poisoned locks and everything else.
+	pub const INTERNAL_ERROR: i64 = BASE_ERROR;
+	/// Loading, decrypting or persisting the state of a shard, or executing the STF on it.
+	pub const STATE_ERROR: i64 = BASE_ERROR + 1;
+	/// Keys, encryption and signatures.
+	pub const CRYPTO_ERROR: i64 = BASE_ERROR + 2;
+	/// The pool of trusted operations.
+	pub const POOL_ERROR: i64 = BASE_ERROR + 3;
+	/// Malformed, unauthorized or rejected requests.
+	pub const RPC_ERROR: i64 = BASE_ERROR + 4;
+	/// Remote attestation and quotes.
+	pub const ATTESTATION_ERROR: i64 = BASE_ERROR + 5;
+	/// Light clients, block import and storage proofs of the parentchains.
+	pub const PARENTCHAIN_ERROR: i64 = BASE_ERROR + 6;
+	/// Sidechain consensus and block import.
+	pub const SIDECHAIN_ERROR: i64 = BASE_ERROR + 7;
+	/// The worker can't serve the request right now, e.g. because it is overloaded. The request
+	/// may be retried later.
+	pub const UNAVAILABLE_ERROR: i64 = BASE_ERROR + 8;
+}
+
 #[derive(Encode, Decode, Debug)]
 pub struct RpcReturnValue {
 	pub value: Vec<u8>,
@@ -62,6 +86,23 @@ pub struct RpcResponse {
 	pub id: Id,
 }
 
+/// Error object of a failed JSON-RPC request, with one of the [`error_code`]s.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RpcError {
+	pub code: i64,
+	pub message: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub data: Option<serde_json::Value>,
+}
+
+/// Response to a failed JSON-RPC request, which replaces the result of [`RpcResponse`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RpcErrorResponse {
+	pub jsonrpc: String,
+	pub error: RpcError,
+	pub id: Id,
+}
+
 #[derive(Clone, Encode, Decode, Serialize, Deserialize)]
 pub struct RpcRequest {
 	pub jsonrpc: String,
@@ -86,7 +127,7 @@ impl RpcRequest {
 
 #[cfg(test)]
 pub mod tests {
-	use crate::Id;
+	use crate::{error_code, Id, RpcErrorResponse};
 
 	#[test]
 	pub fn deserialize_string_id() {
@@ -113,4 +154,14 @@ pub mod tests {
 		let serialized = serde_json::to_string(&id).unwrap();
 		assert_eq!(serialized, r#"1"#)
 	}
+
+	#[test]
+	pub fn deserialize_error_response() {
+		let response: RpcErrorResponse = serde_json::from_str(
+			r#"{"jsonrpc":"2.0","error":{"code":2001,"message":"State error","data":"InvalidShard"},"id":1}"#,
+		)
+		.unwrap();
+		assert_eq!(response.error.code, error_code::STATE_ERROR);
+		assert_eq!(response.error.data, Some("InvalidShard".into()));
+	}
 }
@@ -53,6 +53,26 @@ pub mod files {
 
 	pub const RA_DUMP_CERT_DER_FILE: &str = "ra_dump_cert.der";
 
+	/// Sealed schedule of an announced upgrade to a new enclave.
+	pub const ENCLAVE_UPGRADE_SCHEDULE_FILE: &str = "enclave_upgrade_schedule.bin";
+
+	/// Sealed archive of the rejected shielded payloads.
+	pub const PAYLOAD_QUARANTINE_FILE: &str = "payload_quarantine.bin";
+
+	/// Sealed operator quorums and trail of the conducted key ceremonies.
+	pub const KEY_CEREMONY_TRAIL_FILE: &str = "key_ceremony_trail.bin";
+
+	/// Directory of the state of the canary shard, apart from the shards the worker serves.
+	pub const CANARY_PATH: &str = "canary";
+
+	/// Directory of partially provisioned states, which replace the state of their shard only
+	/// once the provisioning is complete.
+	pub const STAGED_STATES_PATH: &str = "staged_states";
+
+	/// Directory of the new state key while a key rotation replaces the states, the key in it
+	/// replaces the state key once all states are encrypted with it.
+	pub const PENDING_STATE_KEY_PATH: &str = "pending_state_key";
+
 	// used by worker and enclave
 	pub const SHARDS_PATH: &str = "shards";
 
@@ -82,6 +102,25 @@ pub mod worker {
 	pub const SIGNING_KEY_SIZE: usize = 32;
 	// size of the MR enclave
 	pub const MR_ENCLAVE_SIZE: usize = 32;
+	// maximum size of the encoded payload quarantine, bounds the size of its export
+	pub const PAYLOAD_QUARANTINE_MAX_SIZE: usize = 4 * 1024 * 1024;
+	// maximum size of the encoded key ceremony trail, bounds the size of its export
+	pub const KEY_CEREMONY_TRAIL_MAX_SIZE: usize = 1024 * 1024;
+	// size of the buffer for the encoded outcome of a key ceremony
+	pub const KEY_CEREMONY_OUTCOME_SIZE: usize = 256;
+	// size of the buffer for the encoded and signed report of a smoke test
+	pub const SMOKE_TEST_REPORT_SIZE: usize = 4096;
+	// size of the buffer for the encoded report of a canary round
+	pub const CANARY_REPORT_SIZE: usize = 4096;
+	// maximum size of the chunks the enclave writes and reads when streaming a state backup to and
+	// from disk, every chunk is copied to the untrusted stack
+	pub const STATE_BACKUP_CHUNK_SIZE: usize = 256 * 1024;
+	// size of the chunks the worker writes when exporting the sidechain blocks of a shard
+	pub const BLOCK_ARCHIVE_CHUNK_SIZE: usize = 1024 * 1024;
+	// maximum number of changed keys listed in a state diff, the remaining ones are only counted
+	pub const STATE_DIFF_MAX_CHANGES: usize = 1000;
+	// size of the buffer for the encoded state diff, a listed key is encoded in at most 42 bytes
+	pub const STATE_DIFF_SIZE: usize = STATE_DIFF_MAX_CHANGES * 42 + 64;
 
 	// Should be set to a value that ensures that the enclave can register itself
 	// and that the worker can start.
@@ -95,6 +134,21 @@ pub mod sidechain {
 	use core::time::Duration;
 
 	pub static SLOT_DURATION: Duration = Duration::from_millis(1000);
+
+	// number of consecutive overloaded slots after which the next load shedding action is activated
+	pub const LOAD_SHEDDING_ESCALATION_SLOTS: u32 = 5;
+	// number of consecutive healthy slots after which the last active action is deactivated again
+	pub const LOAD_SHEDDING_RECOVERY_SLOTS: u32 = 30;
+	// share of the EPC in percent from which on the enclave counts as overloaded
+	pub const LOAD_SHEDDING_EPC_PRESSURE_PERCENT: u8 = 90;
+	// trusted operations accepted per slot while submissions are rate limited, unless configured
+	pub const LOAD_SHEDDING_MAX_SUBMISSIONS_PER_SLOT: u32 = 100;
+	// number of sidechain blocks the last imported block of a shard may lag the last block
+	// confirmed on the parentchain, before the shard is considered stale, unless configured
+	pub const STALE_SHARD_THRESHOLD: u64 = 40;
+	// number of parentchain blocks after which the enclave closes and signs a digest of its SLA
+	// metrics, an hour with 6 second blocks
+	pub const SLA_METRICS_PERIOD_BLOCKS: u64 = 600;
 }
 
 /// Settings concerning the enclave
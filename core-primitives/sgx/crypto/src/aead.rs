@@ -0,0 +1,130 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Authenticated encryption with AES-128-GCM.
+//!
+//! Every message is encrypted under a fresh random nonce, which is stored with the ciphertext,
+//! so a key may encrypt many messages. Unlike [`crate::Aes`], a modified ciphertext or associated
+//! data fails to decrypt.
+
+use crate::{
+	aes::Aes,
+	error::{Error, Result},
+};
+use aes_gcm::{
+	aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+	Aes128Gcm,
+};
+use codec::{Decode, Encode};
+use std::vec::Vec;
+
+pub type Nonce = [u8; 12];
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AeadKey(pub [u8; 16]);
+
+/// Ciphertext, including the authentication tag, and the nonce it has been encrypted with.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct AeadCiphertext {
+	pub nonce: Nonce,
+	pub ciphertext: Vec<u8>,
+}
+
+impl AeadKey {
+	/// Encrypts with the given nonce, which must never be reused with this key.
+	pub fn encrypt(&self, nonce: Nonce, plaintext: &[u8], aad: &[u8]) -> Result<AeadCiphertext> {
+		let ciphertext = self
+			.cipher()
+			.encrypt(GenericArray::from_slice(&nonce), Payload { msg: plaintext, aad })
+			.map_err(|_| Error::Aead)?;
+		Ok(AeadCiphertext { nonce, ciphertext })
+	}
+
+	pub fn decrypt(&self, ciphertext: &AeadCiphertext, aad: &[u8]) -> Result<Vec<u8>> {
+		self.cipher()
+			.decrypt(
+				GenericArray::from_slice(&ciphertext.nonce),
+				Payload { msg: &ciphertext.ciphertext, aad },
+			)
+			.map_err(|_| Error::Aead)
+	}
+
+	fn cipher(&self) -> Aes128Gcm {
+		Aes128Gcm::new(GenericArray::from_slice(&self.0))
+	}
+}
+
+impl From<Aes> for AeadKey {
+	fn from(aes: Aes) -> Self {
+		AeadKey(aes.key)
+	}
+}
+
+#[cfg(feature = "sgx")]
+pub use sgx::*;
+
+#[cfg(feature = "sgx")]
+pub mod sgx {
+	use super::*;
+	use sgx_rand::{Rng, StdRng};
+
+	impl AeadKey {
+		/// Encrypts with a nonce from the enclave's randomness.
+		pub fn encrypt_with_random_nonce(
+			&self,
+			plaintext: &[u8],
+			aad: &[u8],
+		) -> Result<AeadCiphertext> {
+			let mut nonce = Nonce::default();
+			let mut rand = StdRng::new()?;
+			rand.fill_bytes(&mut nonce);
+			self.encrypt(nonce, plaintext, aad)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const KEY: AeadKey = AeadKey([1u8; 16]);
+
+	#[test]
+	fn ciphertext_decrypts_with_the_same_associated_data() {
+		let ciphertext = KEY.encrypt([2u8; 12], b"plaintext", b"context").unwrap();
+
+		assert_ne!(ciphertext.ciphertext[..9], b"plaintext"[..]);
+		assert_eq!(KEY.decrypt(&ciphertext, b"context").unwrap(), b"plaintext".to_vec());
+		assert!(matches!(KEY.decrypt(&ciphertext, b"another context"), Err(Error::Aead)));
+	}
+
+	#[test]
+	fn modified_ciphertext_fails_to_decrypt() {
+		let mut ciphertext = KEY.encrypt([2u8; 12], b"plaintext", b"context").unwrap();
+		ciphertext.ciphertext[0] ^= 1;
+
+		assert!(matches!(KEY.decrypt(&ciphertext, b"context"), Err(Error::Aead)));
+	}
+
+	#[test]
+	fn nonce_changes_the_ciphertext() {
+		let ciphertext = KEY.encrypt([2u8; 12], b"plaintext", b"context").unwrap();
+		let other_ciphertext = KEY.encrypt([3u8; 12], b"plaintext", b"context").unwrap();
+
+		assert_ne!(ciphertext.ciphertext, other_ciphertext.ciphertext);
+	}
+}
@@ -25,6 +25,7 @@ use ofb::{
 	cipher::{NewStreamCipher, SyncStreamCipher},
 	Ofb,
 };
+use sp_core::hashing::blake2_256;
 use std::{
 	convert::{TryFrom, TryInto},
 	path::PathBuf,
@@ -45,6 +46,17 @@ impl Aes {
 	pub fn new(key: [u8; 16], init_vec: [u8; 16]) -> Self {
 		Self { key, init_vec }
 	}
+
+	/// Derives an independent key for the given context. Revealing the derived key does not
+	/// reveal this key, nor the keys derived for other contexts.
+	pub fn derive(&self, context: &[u8]) -> Self {
+		let hash = blake2_256(&(self.key, self.init_vec, context).encode());
+		let mut key = [0u8; 16];
+		let mut init_vec = [0u8; 16];
+		key.copy_from_slice(&hash[..16]);
+		init_vec.copy_from_slice(&hash[16..]);
+		Self { key, init_vec }
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -82,6 +94,21 @@ impl TryFrom<&Aes> for AesOfb {
 	}
 }
 
+/// Keystream that continues across calls, so data encrypted in consecutive pieces can be
+/// decrypted at once and vice versa.
+pub struct AesStream(AesOfb);
+
+impl AesStream {
+	pub fn new(aes: &Aes) -> Result<Self> {
+		aes.try_into().map(AesStream)
+	}
+
+	/// Encrypts encrypted data and decrypts plaintext, like [`de_or_encrypt`].
+	pub fn apply(&mut self, data: &mut [u8]) {
+		self.0.apply_keystream(data)
+	}
+}
+
 /// If AES acts on the encrypted data it decrypts and vice versa
 pub fn de_or_encrypt(aes: &Aes, data: &mut [u8]) -> Result<()> {
 	aes.try_into().map(|mut ofb: AesOfb| ofb.apply_keystream(data))
@@ -201,3 +228,40 @@ pub mod sgx_tests {
 		assert_ne!(key_different, key);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn derived_keys_differ_per_context_and_decrypt_their_own_ciphertext() {
+		let aes = Aes::new([1u8; 16], [2u8; 16]);
+		let derived = aes.derive(b"context");
+		assert_eq!(derived, aes.derive(b"context"));
+		assert_ne!(derived, aes.derive(b"other context"));
+		assert_ne!(derived, aes);
+
+		let mut data = vec![7u8; 21];
+		derived.encrypt(&mut data).unwrap();
+		assert_ne!(data, vec![7u8; 21]);
+		derived.decrypt(&mut data).unwrap();
+		assert_eq!(data, vec![7u8; 21]);
+	}
+
+	#[test]
+	fn stream_encrypted_in_pieces_decrypts_at_once() {
+		let aes = Aes::new([1u8; 16], [2u8; 16]);
+		let plaintext: Vec<u8> = (0..100).collect();
+		let mut stream = AesStream::new(&aes).unwrap();
+		let mut ciphertext = Vec::new();
+		for piece in plaintext.chunks(7) {
+			let mut piece = piece.to_vec();
+			stream.apply(&mut piece);
+			ciphertext.extend(piece);
+		}
+
+		aes.decrypt(&mut ciphertext).unwrap();
+
+		assert_eq!(ciphertext, plaintext);
+	}
+}
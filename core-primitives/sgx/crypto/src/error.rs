@@ -26,6 +26,8 @@ use std::prelude::v1::Box;
 pub enum Error {
 	IO(std::io::Error),
 	InvalidNonceKeyLength,
+	InvalidPublicKey,
+	Aead,
 	Codec(codec::Error),
 	Serialization(serde_json::Error),
 	LockPoisoning,
@@ -32,12 +32,14 @@ pub mod sgx_reexport_prelude {
 	pub use serde_json_sgx as serde_json;
 }
 
+pub mod aead;
 pub mod aes;
 pub mod ed25519;
 pub mod ed25519_derivation;
 pub mod error;
 pub mod key_repository;
 pub mod rsa3072;
+pub mod session_key;
 pub mod traits;
 
 pub use self::{aes::*, ed25519::*, rsa3072::*};
@@ -0,0 +1,276 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Ephemeral X25519 key agreement for per-connection session keys.
+//!
+//! Both ends of a connection generate an ephemeral key pair and derive the session key from the
+//! shared secret. The ephemeral secrets are dropped right after the agreement, so a leaked
+//! long-term key does not decrypt the messages of past sessions.
+//!
+//! The enclave signs its ephemeral public key with its signing key, which is registered on the
+//! parentchain together with its attestation report. The client verifies that signature before
+//! the agreement, so the host can't replace the key with its own. The messages of a session are
+//! encrypted with AES-GCM, so a modified, replayed or reordered message fails to decrypt.
+
+use crate::{
+	aead::{AeadCiphertext, AeadKey, Nonce},
+	aes::Aes,
+	error::{Error, Result},
+};
+use codec::{Decode, Encode};
+use curve25519_dalek::{constants::X25519_BASEPOINT, montgomery::MontgomeryPoint, scalar::Scalar};
+use sp_core::{ed25519, hashing::blake2_256, Pair};
+use std::vec::Vec;
+
+pub type SessionPublicKey = [u8; 32];
+
+/// Ephemeral X25519 key pair, consumed by the key agreement.
+pub struct EphemeralKey {
+	secret: Scalar,
+	public: SessionPublicKey,
+}
+
+impl EphemeralKey {
+	pub fn from_seed(seed: [u8; 32]) -> Self {
+		let secret = Scalar::from_bits(clamp(seed));
+		let public = (X25519_BASEPOINT * secret).to_bytes();
+		EphemeralKey { secret, public }
+	}
+
+	pub fn public(&self) -> SessionPublicKey {
+		self.public
+	}
+
+	/// Agrees on the session key with the other end, `client_public` and `server_public` are
+	/// the ephemeral public keys of the two ends.
+	pub fn agree(
+		self,
+		peer_public: &SessionPublicKey,
+		client_public: &SessionPublicKey,
+		server_public: &SessionPublicKey,
+	) -> Result<SessionKey> {
+		let shared_secret = (MontgomeryPoint(*peer_public) * self.secret).to_bytes();
+		// A low order point of the peer results in a predictable shared secret.
+		if shared_secret == [0u8; 32] {
+			return Err(Error::InvalidPublicKey)
+		}
+		let hash = blake2_256(
+			&(SESSION_KEY_CONTEXT, shared_secret, client_public, server_public).encode(),
+		);
+		let mut key = [0u8; 16];
+		let mut init_vec = [0u8; 16];
+		key.copy_from_slice(&hash[..16]);
+		init_vec.copy_from_slice(&hash[16..]);
+		Ok(SessionKey { root: Aes::new(key, init_vec) })
+	}
+}
+
+const SESSION_KEY_CONTEXT: &[u8] = b"integritee_direct_rpc_session";
+
+const SESSION_PUBLIC_KEY_CONTEXT: &[u8] = b"integritee_direct_rpc_session_public_key";
+
+/// Ephemeral public key of the enclave, signed by the enclave for the session opened with the
+/// ephemeral public key of the client.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedSessionPublicKey {
+	pub server_public: SessionPublicKey,
+	pub signature: ed25519::Signature,
+}
+
+impl SignedSessionPublicKey {
+	pub fn new(
+		server_public: SessionPublicKey,
+		client_public: &SessionPublicKey,
+		signer: &ed25519::Pair,
+	) -> Self {
+		let signature = signer.sign(&signed_payload(client_public, &server_public));
+		SignedSessionPublicKey { server_public, signature }
+	}
+
+	/// Whether the key has been signed by `enclave` for the session of `client_public`.
+	pub fn verify(&self, client_public: &SessionPublicKey, enclave: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(
+			&self.signature,
+			signed_payload(client_public, &self.server_public),
+			enclave,
+		)
+	}
+}
+
+fn signed_payload(client_public: &SessionPublicKey, server_public: &SessionPublicKey) -> Vec<u8> {
+	(SESSION_PUBLIC_KEY_CONTEXT, client_public, server_public).encode()
+}
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageDirection {
+	Request,
+	Response,
+}
+
+/// Key of a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionKey {
+	root: Aes,
+}
+
+impl SessionKey {
+	/// Encrypts a message of the session. The nonce is derived from the direction and the
+	/// sequence number, which therefore must never be reused within a session.
+	pub fn encrypt_message(
+		&self,
+		direction: MessageDirection,
+		sequence: u64,
+		plaintext: &[u8],
+	) -> Result<Vec<u8>> {
+		let ciphertext =
+			self.message_aead_key()
+				.encrypt(message_nonce(direction, sequence), plaintext, &[])?;
+		Ok(ciphertext.ciphertext)
+	}
+
+	/// Decrypts a message of the session, fails if it has been modified or has been encrypted
+	/// for another direction or sequence number.
+	pub fn decrypt_message(
+		&self,
+		direction: MessageDirection,
+		sequence: u64,
+		ciphertext: &[u8],
+	) -> Result<Vec<u8>> {
+		let ciphertext = AeadCiphertext {
+			nonce: message_nonce(direction, sequence),
+			ciphertext: ciphertext.to_vec(),
+		};
+		self.message_aead_key().decrypt(&ciphertext, &[])
+	}
+
+	/// Key of the message with the given sequence number, which must never be reused within a
+	/// session because the key stream would repeat.
+	pub fn message_key(&self, direction: MessageDirection, sequence: u64) -> Aes {
+		self.root.derive(&(direction, sequence).encode())
+	}
+
+	fn message_aead_key(&self) -> AeadKey {
+		self.root.derive(b"session_messages").into()
+	}
+}
+
+fn message_nonce(direction: MessageDirection, sequence: u64) -> Nonce {
+	let mut nonce = [0u8; 12];
+	nonce[0] = direction as u8;
+	nonce[4..].copy_from_slice(&sequence.to_be_bytes());
+	nonce
+}
+
+/// Generates the ephemeral keys of new sessions.
+pub trait GenerateEphemeralKey: Send + Sync {
+	fn generate(&self) -> Result<EphemeralKey>;
+}
+
+fn clamp(mut bytes: [u8; 32]) -> [u8; 32] {
+	bytes[0] &= 248;
+	bytes[31] &= 127;
+	bytes[31] |= 64;
+	bytes
+}
+
+#[cfg(feature = "sgx")]
+pub use sgx::*;
+
+#[cfg(feature = "sgx")]
+pub mod sgx {
+	use super::*;
+	use sgx_rand::{Rng, StdRng};
+
+	/// Generates ephemeral keys from the enclave's randomness.
+	#[derive(Default)]
+	pub struct EphemeralKeyGenerator;
+
+	impl GenerateEphemeralKey for EphemeralKeyGenerator {
+		fn generate(&self) -> Result<EphemeralKey> {
+			let mut seed = [0u8; 32];
+			let mut rand = StdRng::new()?;
+			rand.fill_bytes(&mut seed);
+			Ok(EphemeralKey::from_seed(seed))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn both_ends_agree_on_the_same_message_keys() {
+		let client = EphemeralKey::from_seed([1u8; 32]);
+		let server = EphemeralKey::from_seed([2u8; 32]);
+		let (client_public, server_public) = (client.public(), server.public());
+
+		let client_key = client.agree(&server_public, &client_public, &server_public).unwrap();
+		let server_key = server.agree(&client_public, &client_public, &server_public).unwrap();
+		assert_eq!(client_key, server_key);
+
+		let ciphertext =
+			client_key.encrypt_message(MessageDirection::Request, 0, b"request").unwrap();
+		assert_eq!(
+			server_key.decrypt_message(MessageDirection::Request, 0, &ciphertext).unwrap(),
+			b"request".to_vec()
+		);
+		assert_ne!(
+			server_key.message_key(MessageDirection::Request, 0),
+			server_key.message_key(MessageDirection::Response, 0)
+		);
+	}
+
+	#[test]
+	fn modified_replayed_or_reflected_messages_fail_to_decrypt() {
+		let client = EphemeralKey::from_seed([1u8; 32]);
+		let server_public = EphemeralKey::from_seed([2u8; 32]).public();
+		let client_public = client.public();
+		let key = client.agree(&server_public, &client_public, &server_public).unwrap();
+		let mut ciphertext = key.encrypt_message(MessageDirection::Request, 3, b"request").unwrap();
+
+		assert!(key.decrypt_message(MessageDirection::Request, 4, &ciphertext).is_err());
+		assert!(key.decrypt_message(MessageDirection::Response, 3, &ciphertext).is_err());
+		ciphertext[0] ^= 1;
+		assert!(key.decrypt_message(MessageDirection::Request, 3, &ciphertext).is_err());
+	}
+
+	#[test]
+	fn signed_session_public_key_is_bound_to_the_enclave_and_the_client_key() {
+		let enclave = ed25519::Pair::from_seed(&[7u8; 32]);
+		let client_public = EphemeralKey::from_seed([1u8; 32]).public();
+		let server_public = EphemeralKey::from_seed([2u8; 32]).public();
+
+		let signed = SignedSessionPublicKey::new(server_public, &client_public, &enclave);
+
+		assert!(signed.verify(&client_public, &enclave.public()));
+		assert!(!signed.verify(&[3u8; 32], &enclave.public()));
+		assert!(!signed.verify(&client_public, &ed25519::Pair::from_seed(&[8u8; 32]).public()));
+		let replaced = SignedSessionPublicKey { server_public: [4u8; 32], ..signed };
+		assert!(!replaced.verify(&client_public, &enclave.public()));
+	}
+
+	#[test]
+	fn low_order_public_key_is_rejected() {
+		let server = EphemeralKey::from_seed([2u8; 32]);
+		let server_public = server.public();
+
+		let result = server.agree(&[0u8; 32], &[0u8; 32], &server_public);
+
+		assert!(matches!(result, Err(Error::InvalidPublicKey)));
+	}
+}
@@ -0,0 +1,104 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Allowlist of the path prefixes the io functions may access.
+//!
+//! Paths containing `..` are always rejected. Once the allowed prefixes have been set, all paths
+//! outside of them are rejected as well. Until then, every other path is accessible.
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+use lazy_static::lazy_static;
+use std::{
+	format,
+	io::{Error, ErrorKind, Result as IOResult},
+	path::{Component, Path, PathBuf},
+	vec::Vec,
+};
+
+lazy_static! {
+	static ref ALLOWED_PATH_PREFIXES: RwLock<Option<Vec<PathBuf>>> = RwLock::new(None);
+}
+
+/// Restricts the io functions to the given path prefixes. Replaces previously set prefixes.
+pub fn set_allowed_path_prefixes(prefixes: Vec<PathBuf>) -> IOResult<()> {
+	replace_allowed_path_prefixes(Some(prefixes)).map(|_| ())
+}
+
+/// Replaces the allowed path prefixes, `None` lifts the restriction. Returns the previous ones,
+/// so a temporary policy can be reverted.
+pub fn replace_allowed_path_prefixes(
+	prefixes: Option<Vec<PathBuf>>,
+) -> IOResult<Option<Vec<PathBuf>>> {
+	let prefixes = prefixes.map(|prefixes| prefixes.iter().map(|p| normalize(p)).collect());
+	let mut allowed_prefixes = ALLOWED_PATH_PREFIXES.write().map_err(|_| poisoned_lock())?;
+	Ok(core::mem::replace(&mut *allowed_prefixes, prefixes))
+}
+
+/// Fails with `PermissionDenied` if the path may not be accessed.
+pub fn check_path_access(path: &Path) -> IOResult<()> {
+	let allowed_prefixes = ALLOWED_PATH_PREFIXES.read().map_err(|_| poisoned_lock())?;
+	if is_allowed(path, allowed_prefixes.as_deref()) {
+		Ok(())
+	} else {
+		Err(Error::new(ErrorKind::PermissionDenied, format!("access to {:?} is not allowed", path)))
+	}
+}
+
+fn is_allowed(path: &Path, allowed_prefixes: Option<&[PathBuf]>) -> bool {
+	if path.components().any(|c| c == Component::ParentDir) {
+		return false
+	}
+	let path = normalize(path);
+	allowed_prefixes.map_or(true, |prefixes| prefixes.iter().any(|p| path.starts_with(p)))
+}
+
+/// Removes `.` components, so `./shards` and `shards` are the same prefix.
+fn normalize(path: &Path) -> PathBuf {
+	path.components().filter(|c| *c != Component::CurDir).collect()
+}
+
+fn poisoned_lock() -> Error {
+	Error::new(ErrorKind::Other, "path access policy lock is poisoned")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn paths_below_allowed_prefixes_are_allowed() {
+		let prefixes = [PathBuf::from("./base/shards"), PathBuf::from("key.bin")];
+
+		assert!(is_allowed(Path::new("base/shards/abc/state.bin"), Some(&prefixes)));
+		assert!(is_allowed(Path::new("./key.bin"), Some(&prefixes)));
+		assert!(!is_allowed(Path::new("base/shardsx/state.bin"), Some(&prefixes)));
+		assert!(!is_allowed(Path::new("/etc/passwd"), Some(&prefixes)));
+	}
+
+	#[test]
+	fn path_traversal_is_rejected() {
+		let prefixes = [PathBuf::from("base/shards")];
+
+		assert!(!is_allowed(Path::new("base/shards/../../etc/passwd"), Some(&prefixes)));
+		assert!(!is_allowed(Path::new("../outside"), None));
+		assert!(is_allowed(Path::new("anywhere"), None));
+	}
+}
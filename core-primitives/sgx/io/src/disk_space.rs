@@ -0,0 +1,91 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Check of the free disk space before files are written.
+//!
+//! Running out of disk space in the middle of a write leaves a truncated file behind. The check
+//! is registered by the environment the io functions run in, e.g. the enclave asks the untrusted
+//! worker service. Until a check has been registered, writes are not checked.
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+use lazy_static::lazy_static;
+use std::{
+	fmt,
+	io::{Error, ErrorKind, Result as IOResult},
+	path::{Path, PathBuf},
+};
+
+/// Fails with [`insufficient_disk_space`] if `bytes_to_write` may not be written to the path.
+pub type DiskSpaceCheck = fn(&Path, usize) -> IOResult<()>;
+
+lazy_static! {
+	static ref DISK_SPACE_CHECK: RwLock<Option<DiskSpaceCheck>> = RwLock::new(None);
+}
+
+/// Checks the free disk space with `check` before every write. Replaces a previously set check.
+pub fn set_disk_space_check(check: DiskSpaceCheck) -> IOResult<()> {
+	*DISK_SPACE_CHECK.write().map_err(|_| poisoned_lock())? = Some(check);
+	Ok(())
+}
+
+pub fn check_disk_space(path: &Path, bytes_to_write: usize) -> IOResult<()> {
+	let check = *DISK_SPACE_CHECK.read().map_err(|_| poisoned_lock())?;
+	check.map_or(Ok(()), |check| check(path, bytes_to_write))
+}
+
+/// Error of a write that has been refused because of insufficient free disk space.
+#[derive(Debug)]
+pub struct InsufficientDiskSpace {
+	pub path: PathBuf,
+}
+
+impl fmt::Display for InsufficientDiskSpace {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "insufficient disk space to write {:?}", self.path)
+	}
+}
+
+impl std::error::Error for InsufficientDiskSpace {}
+
+pub fn insufficient_disk_space(path: &Path) -> Error {
+	Error::new(ErrorKind::Other, InsufficientDiskSpace { path: path.into() })
+}
+
+pub fn is_insufficient_disk_space(error: &Error) -> bool {
+	error.get_ref().map_or(false, |e| e.is::<InsufficientDiskSpace>())
+}
+
+fn poisoned_lock() -> Error {
+	Error::new(ErrorKind::Other, "disk space check lock is poisoned")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn insufficient_disk_space_error_is_recognized() {
+		let error = insufficient_disk_space(Path::new("shards/state.bin"));
+
+		assert!(is_insufficient_disk_space(&error));
+		assert!(!is_insufficient_disk_space(&Error::new(ErrorKind::Other, "other")));
+	}
+}
@@ -17,9 +17,14 @@ use std::{
 	vec::Vec,
 };
 
+pub use access_policy::{check_path_access, set_allowed_path_prefixes};
+pub use disk_space::{check_disk_space, set_disk_space_check};
 #[cfg(feature = "sgx")]
 pub use sgx::*;
 
+pub mod access_policy;
+pub mod disk_space;
+
 /// Abstraction around IO that is supposed to use the `std::io::File`
 pub trait IO: Sized {
 	type Error: From<std::io::Error> + std::fmt::Debug + 'static;
@@ -57,16 +62,31 @@ pub trait SealedIO: Sized {
 }
 
 pub fn read<P: AsRef<Path>>(path: P) -> IOResult<Vec<u8>> {
+	check_path_access(path.as_ref())?;
 	let mut buf = Vec::new();
 	fs::File::open(path).map(|mut f| f.read_to_end(&mut buf))??;
 	Ok(buf)
 }
 
 pub fn write<P: AsRef<Path>>(bytes: &[u8], path: P) -> IOResult<()> {
+	check_path_access(path.as_ref())?;
+	check_disk_space(path.as_ref(), bytes.len())?;
 	fs::File::create(path).map(|mut f| f.write_all(bytes))?
 }
 
+/// Appends the bytes to the file, which is created if it does not exist.
+pub fn append<P: AsRef<Path>>(bytes: &[u8], path: P) -> IOResult<()> {
+	check_path_access(path.as_ref())?;
+	check_disk_space(path.as_ref(), bytes.len())?;
+	fs::OpenOptions::new()
+		.append(true)
+		.create(true)
+		.open(path)
+		.map(|mut f| f.write_all(bytes))?
+}
+
 pub fn read_to_string<P: AsRef<Path>>(filepath: P) -> IOResult<String> {
+	check_path_access(filepath.as_ref())?;
 	let mut contents = String::new();
 	fs::File::open(filepath).map(|mut f| f.read_to_string(&mut contents))??;
 	Ok(contents)
@@ -74,21 +94,32 @@ pub fn read_to_string<P: AsRef<Path>>(filepath: P) -> IOResult<String> {
 
 #[cfg(feature = "sgx")]
 mod sgx {
+	use crate::{check_disk_space, check_path_access};
 	use std::{
 		convert::AsRef,
 		io::{Read, Result, Write},
 		path::Path,
-		sgxfs::SgxFile,
+		sgxfs::{OpenOptions, SgxFile},
 		vec::Vec,
 	};
 
 	pub fn unseal<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+		check_path_access(path.as_ref())?;
 		let mut buf = Vec::new();
 		SgxFile::open(path).map(|mut f| f.read_to_end(&mut buf))??;
 		Ok(buf)
 	}
 
 	pub fn seal<P: AsRef<Path>>(bytes: &[u8], path: P) -> Result<()> {
+		check_path_access(path.as_ref())?;
+		check_disk_space(path.as_ref(), bytes.len())?;
 		SgxFile::create(path).map(|mut f| f.write_all(bytes))?
 	}
+
+	/// Appends the bytes to the sealed file, which is created if it does not exist.
+	pub fn seal_append<P: AsRef<Path>>(bytes: &[u8], path: P) -> Result<()> {
+		check_path_access(path.as_ref())?;
+		check_disk_space(path.as_ref(), bytes.len())?;
+		OpenOptions::new().append(true).open(path).map(|mut f| f.write_all(bytes))?
+	}
 }
@@ -0,0 +1,79 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Call validator uses the state observer to validate trusted calls on the most recent state of
+//! their shard, before they enter the TOP pool.
+
+use itp_stf_interface::StateCallValidation;
+use itp_stf_state_observer::traits::ObserveState;
+use itp_top_pool_author::call_validation::ValidateTrustedCall;
+use itp_types::ShardIdentifier;
+use std::{format, marker::PhantomData, string::String, sync::Arc};
+
+pub struct CallValidator<StateObserver, Stf> {
+	state_observer: Arc<StateObserver>,
+	_phantom: PhantomData<Stf>,
+}
+
+impl<StateObserver, Stf> CallValidator<StateObserver, Stf> {
+	pub fn new(state_observer: Arc<StateObserver>) -> Self {
+		Self { state_observer, _phantom: Default::default() }
+	}
+}
+
+impl<StateObserver, Stf, TCS> ValidateTrustedCall<TCS> for CallValidator<StateObserver, Stf>
+where
+	StateObserver: ObserveState,
+	Stf: StateCallValidation<TCS, StateObserver::StateType>,
+{
+	fn validate(&self, shard: &ShardIdentifier, call: &TCS) -> Result<(), String> {
+		self.state_observer
+			.observe_state(shard, |state| Stf::validate_call(state, call))
+			.map_err(|e| format!("{:?}", e))?
+			.map_err(|e| format!("{:?}", e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_stf_state_observer::mock::ObserveStateMock;
+
+	type TestState = u64;
+
+	/// Accepts calls up to the amount in the state.
+	struct TestStf;
+	impl StateCallValidation<u64, TestState> for TestStf {
+		type Error = String;
+
+		fn validate_call(state: &mut TestState, call: &u64) -> Result<(), Self::Error> {
+			match call <= state {
+				true => Ok(()),
+				false => Err("exceeds the state".into()),
+			}
+		}
+	}
+
+	#[test]
+	fn calls_are_validated_on_the_observed_state() {
+		let state_observer = Arc::new(ObserveStateMock::new(10u64));
+		let call_validator = CallValidator::<_, TestStf>::new(state_observer);
+
+		assert!(call_validator.validate(&ShardIdentifier::default(), &10u64).is_ok());
+		assert!(call_validator.validate(&ShardIdentifier::default(), &11u64).is_err());
+	}
+}
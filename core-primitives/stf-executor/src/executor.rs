@@ -31,7 +31,10 @@ use itp_stf_primitives::{
 	traits::TrustedCallVerification,
 	types::{ShardIdentifier, TrustedOperation, TrustedOperationOrHash},
 };
-use itp_stf_state_handler::{handle_state::HandleState, query_shard_state::QueryShardState};
+use itp_stf_state_handler::{
+	handle_state::{mutate_unpoisoned, HandleState},
+	query_shard_state::QueryShardState,
+};
 use itp_time_utils::duration_now;
 use itp_types::{
 	parentchain::{Header as ParentchainHeader, ParentchainCall, ParentchainId},
@@ -197,7 +200,10 @@ where
 		let shards = self.state_handler.list_shards()?;
 		for shard_id in shards {
 			let (state_lock, mut state) = self.state_handler.load_for_mutation(&shard_id)?;
-			match Stf::update_parentchain_integritee_block(&mut state, header.clone()) {
+			let (state_lock, update_result) = mutate_unpoisoned(state_lock, || {
+				Stf::update_parentchain_integritee_block(&mut state, header.clone())
+			});
+			match update_result {
 				Ok(_) => {
 					self.state_handler.write_after_mutation(state, state_lock, &shard_id)?;
 				},
@@ -263,9 +269,12 @@ where
 				)
 				.map(into_map)?;
 
-			Stf::apply_state_diff(&mut state, per_shard_update.into());
-			Stf::apply_state_diff(&mut state, state_diff_update.clone().into());
-			if let Err(e) = Stf::update_parentchain_integritee_block(&mut state, header.clone()) {
+			let (state_lock, update_result) = mutate_unpoisoned(state_lock, || {
+				Stf::apply_state_diff(&mut state, per_shard_update.into());
+				Stf::apply_state_diff(&mut state, state_diff_update.clone().into());
+				Stf::update_parentchain_integritee_block(&mut state, header.clone())
+			});
+			if let Err(e) = update_result {
 				error!("Could not update parentchain block. {:?}: {:?}", shard_id, e)
 			}
 
@@ -33,6 +33,13 @@ pub trait ExecuteGetter {
 		shard: &ShardIdentifier,
 		encoded_signed_getter: Vec<u8>,
 	) -> Result<Option<Vec<u8>>>;
+
+	/// Executes a getter the caller has authorized otherwise, e.g. with a getter session token.
+	fn execute_authorized_getter(
+		&self,
+		shard: &ShardIdentifier,
+		encoded_getter: Vec<u8>,
+	) -> Result<Option<Vec<u8>>>;
 }
 
 pub struct GetterExecutor<StateObserver, StateGetter, G>
@@ -76,6 +83,16 @@ where
 
 		Ok(state_result)
 	}
+
+	fn execute_authorized_getter(
+		&self,
+		shard: &ShardIdentifier,
+		encoded_getter: Vec<u8>,
+	) -> Result<Option<Vec<u8>>> {
+		let getter = G::decode(&mut encoded_getter.as_slice())?;
+		self.state_observer
+			.observe_state(shard, |state| StateGetter::get_authorized_state(getter, state))?
+	}
 }
 
 #[cfg(test)]
@@ -96,6 +113,13 @@ mod tests {
 		fn get_state(_getter: GetterMock, state: &mut TestState) -> Result<Option<Vec<u8>>> {
 			Ok(Some(state.encode()))
 		}
+
+		fn get_authorized_state(
+			_getter: GetterMock,
+			state: &mut TestState,
+		) -> Result<Option<Vec<u8>>> {
+			Ok(Some(state.encode()))
+		}
 	}
 
 	type TestGetterExecutor = GetterExecutor<TestStateObserver, TestStateGetter, GetterMock>;
@@ -37,6 +37,7 @@ pub mod sgx_reexport_prelude {
 	pub use thiserror_sgx as thiserror;
 }
 
+pub mod call_validator;
 pub mod error;
 pub mod getter_executor;
 pub mod state_getter;
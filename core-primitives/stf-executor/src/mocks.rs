@@ -158,4 +158,8 @@ where
 	fn get_state(_getter: G, state: &mut StateType) -> Result<Option<Vec<u8>>> {
 		Ok(Some(state.encode()))
 	}
+
+	fn get_authorized_state(_getter: G, state: &mut StateType) -> Result<Option<Vec<u8>>> {
+		Ok(Some(state.encode()))
+	}
 }
@@ -89,6 +89,14 @@ pub trait StateGetterInterface<G, S> {
 	fn execute_getter(state: &mut S, getter: G) -> Option<Vec<u8>>;
 }
 
+/// Interface to validate calls on a state before they enter the pool.
+pub trait StateCallValidation<TCS, State> {
+	type Error: Debug;
+
+	/// Validate a call on a specific state, without changing the state.
+	fn validate_call(state: &mut State, call: &TCS) -> Result<(), Self::Error>;
+}
+
 /// Trait used to abstract the call execution.
 pub trait ExecuteCall<NodeMetadataRepository>
 where
@@ -108,6 +116,15 @@ where
 	fn get_storage_hashes_to_update(self) -> Vec<Vec<u8>>;
 }
 
+/// Trait used to abstract the call validation.
+pub trait ValidateCall {
+	type Error;
+
+	/// Validate a call against the current state. The execution checks again, since the state
+	/// may change until the call is executed.
+	fn validate(&self) -> Result<(), Self::Error>;
+}
+
 /// Trait used to abstract the getter execution.
 pub trait ExecuteGetter {
 	/// Execute a getter.
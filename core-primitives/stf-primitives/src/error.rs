@@ -40,4 +40,12 @@ pub enum StfError {
 	ChangingShardVaultAccountNotAllowed,
 	WrongParentchainIdForShardVault,
 	NoShardVaultAssigned,
+	#[display(fmt = "Account {:?} is not attested by the compliance registrar", _0)]
+	MissingComplianceAttestation(AccountId),
+	#[display(fmt = "Valid compliance registrar account is required")]
+	RequireComplianceRegistrar,
+	#[display(fmt = "Feature {} is disabled on this shard", _0)]
+	FeatureDisabled(String),
+	#[display(fmt = "Call of the {} module accessed the storage of another module", _0)]
+	StorageNamespaceViolation(String),
 }
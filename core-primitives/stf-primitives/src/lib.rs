@@ -18,5 +18,6 @@
 extern crate alloc;
 
 pub mod error;
+pub mod signature;
 pub mod traits;
 pub mod types;
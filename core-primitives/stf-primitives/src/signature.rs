@@ -0,0 +1,221 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Signature of a trusted operation.
+//!
+//! The SCALE index of the variant selects the signature scheme. The first three schemes are
+//! encoded like a `MultiSignature`, so signatures of substrate keys remain valid. Accounts of
+//! Ethereum and Neo keys are mapped from the address of the respective chain.
+
+use alloc::format;
+use codec::{Decode, Encode};
+use p256::ecdsa::{signature::Verifier, Signature as P256Signature, VerifyingKey};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use sp_core::{blake2_256, crypto::AccountId32, ecdsa, ed25519, keccak_256, sr25519};
+use sp_runtime::{
+	traits::{Lazy, Verify},
+	MultiSignature, MultiSigner,
+};
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum Signature {
+	Ed25519(ed25519::Signature),
+	Sr25519(sr25519::Signature),
+	/// secp256k1 signature of the blake2 hash of the payload, the account is the blake2 hash of
+	/// the compressed public key.
+	Ecdsa(ecdsa::Signature),
+	/// secp256k1 signature of an Ethereum wallet (`personal_sign`) of the payload, the account is
+	/// mapped from the Ethereum address.
+	Ethereum(ecdsa::Signature),
+	/// secp256r1 signature of a Neo wallet of the payload, the account is mapped from the script
+	/// hash of the public key.
+	Neo(NeoSignature),
+}
+
+/// secp256r1 signature of the SHA-256 hash of the payload, as verified by Neo's `CheckSig`.
+///
+/// Contains the compressed public key, which can't be recovered from a secp256r1 signature.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct NeoSignature {
+	pub public: [u8; 33],
+	pub signature: [u8; 64],
+}
+
+impl NeoSignature {
+	fn verify(&self, payload: &[u8]) -> bool {
+		match (
+			VerifyingKey::from_sec1_bytes(&self.public),
+			P256Signature::from_slice(&self.signature),
+		) {
+			(Ok(public), Ok(signature)) => public.verify(payload, &signature).is_ok(),
+			_ => false,
+		}
+	}
+}
+
+impl Verify for Signature {
+	type Signer = MultiSigner;
+
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &AccountId32) -> bool {
+		let signature = match self {
+			Self::Ed25519(signature) => MultiSignature::Ed25519(signature.clone()),
+			Self::Sr25519(signature) => MultiSignature::Sr25519(signature.clone()),
+			Self::Ecdsa(signature) => MultiSignature::Ecdsa(signature.clone()),
+			Self::Ethereum(signature) => {
+				let message_hash = ethereum_message_hash(msg.get());
+				return match sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &message_hash) {
+					Ok(public) => ethereum_account(&ethereum_address(&public)) == *signer,
+					Err(_) => false,
+				}
+			},
+			Self::Neo(signature) =>
+				return signature.verify(msg.get())
+					&& neo_account(&neo_script_hash(&signature.public)) == *signer,
+		};
+		signature.verify(msg, signer)
+	}
+}
+
+impl From<MultiSignature> for Signature {
+	fn from(signature: MultiSignature) -> Self {
+		match signature {
+			MultiSignature::Ed25519(signature) => Self::Ed25519(signature),
+			MultiSignature::Sr25519(signature) => Self::Sr25519(signature),
+			MultiSignature::Ecdsa(signature) => Self::Ecdsa(signature),
+		}
+	}
+}
+
+impl From<ed25519::Signature> for Signature {
+	fn from(signature: ed25519::Signature) -> Self {
+		Self::Ed25519(signature)
+	}
+}
+
+impl From<sr25519::Signature> for Signature {
+	fn from(signature: sr25519::Signature) -> Self {
+		Self::Sr25519(signature)
+	}
+}
+
+/// Hash an Ethereum wallet signs for `personal_sign`.
+pub fn ethereum_message_hash(payload: &[u8]) -> [u8; 32] {
+	let mut message = format!("\x19Ethereum Signed Message:\n{}", payload.len()).into_bytes();
+	message.extend_from_slice(payload);
+	keccak_256(&message)
+}
+
+/// Ethereum address of an uncompressed secp256k1 public key.
+pub fn ethereum_address(public: &[u8; 64]) -> [u8; 20] {
+	let mut address = [0u8; 20];
+	address.copy_from_slice(&keccak_256(public)[12..]);
+	address
+}
+
+/// Account of an Ethereum address, the same the EVM of the shard maps the address to.
+pub fn ethereum_account(address: &[u8; 20]) -> AccountId32 {
+	let mut data: Vec<u8> = b"evm:".to_vec();
+	data.extend_from_slice(address);
+	blake2_256(&data).into()
+}
+
+/// Script hash of the single signature verification script of a compressed secp256r1 public key,
+/// from which Neo derives the address.
+pub fn neo_script_hash(public: &[u8; 33]) -> [u8; 20] {
+	// PUSHDATA1 33 <public key> SYSCALL System.Crypto.CheckSig
+	let mut script = Vec::with_capacity(40);
+	script.extend_from_slice(&[0x0c, 0x21]);
+	script.extend_from_slice(public);
+	script.extend_from_slice(&[0x41, 0x56, 0xe7, 0xb3, 0x27]);
+	Ripemd160::digest(Sha256::digest(&script)).into()
+}
+
+/// Account of a Neo script hash.
+pub fn neo_account(script_hash: &[u8; 20]) -> AccountId32 {
+	let mut data: Vec<u8> = b"neo:".to_vec();
+	data.extend_from_slice(script_hash);
+	blake2_256(&data).into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::Pair;
+
+	#[test]
+	fn substrate_signatures_are_encoded_like_a_multi_signature() {
+		let signature = ed25519::Signature::from_raw([1u8; 64]);
+
+		assert_eq!(
+			Signature::Ed25519(signature.clone()).encode(),
+			MultiSignature::Ed25519(signature).encode()
+		);
+	}
+
+	#[test]
+	fn ethereum_signature_verifies_with_the_account_mapped_from_the_address() {
+		let pair = ecdsa::Pair::from_seed(&[0x46u8; 32]);
+		let address = [
+			0x9d, 0x8a, 0x62, 0xf6, 0x56, 0xa8, 0xd1, 0x61, 0x5c, 0x12, 0x94, 0xfd, 0x71, 0xe9,
+			0xcf, 0xb3, 0xe4, 0x85, 0x5a, 0x4f,
+		];
+		let signature =
+			Signature::Ethereum(pair.sign_prehashed(&ethereum_message_hash(b"trusted call")));
+
+		assert!(signature.verify(&b"trusted call"[..], &ethereum_account(&address)));
+		assert!(!signature.verify(&b"another call"[..], &ethereum_account(&address)));
+		let substrate_account: AccountId32 = blake2_256(pair.public().as_ref()).into();
+		assert!(!signature.verify(&b"trusted call"[..], &substrate_account));
+	}
+
+	#[test]
+	fn neo_signature_verifies_with_the_account_mapped_from_the_script_hash() {
+		use p256::ecdsa::{signature::Signer, SigningKey};
+
+		let signing_key = SigningKey::from_bytes(&[0x46u8; 32].into()).unwrap();
+		let mut public = [0u8; 33];
+		public.copy_from_slice(signing_key.verifying_key().to_encoded_point(true).as_bytes());
+		let signed: P256Signature = signing_key.sign(b"trusted call");
+		let mut signature = [0u8; 64];
+		signature.copy_from_slice(&signed.to_bytes());
+		let signature = Signature::Neo(NeoSignature { public, signature });
+		let account = neo_account(&neo_script_hash(&public));
+
+		assert!(signature.verify(&b"trusted call"[..], &account));
+		assert!(!signature.verify(&b"another call"[..], &account));
+		let other_account = neo_account(&neo_script_hash(&[2u8; 33]));
+		assert!(!signature.verify(&b"trusted call"[..], &other_account));
+	}
+
+	#[test]
+	fn neo_signature_with_another_public_key_is_rejected() {
+		use p256::ecdsa::{signature::Signer, SigningKey};
+
+		let signing_key = SigningKey::from_bytes(&[0x46u8; 32].into()).unwrap();
+		let other_key = SigningKey::from_bytes(&[0x47u8; 32].into()).unwrap();
+		let mut public = [0u8; 33];
+		public.copy_from_slice(other_key.verifying_key().to_encoded_point(true).as_bytes());
+		let signed: P256Signature = signing_key.sign(b"trusted call");
+		let mut signature = [0u8; 64];
+		signature.copy_from_slice(&signed.to_bytes());
+		let signature = Signature::Neo(NeoSignature { public, signature });
+
+		assert!(!signature.verify(&b"trusted call"[..], &neo_account(&neo_script_hash(&public))));
+	}
+}
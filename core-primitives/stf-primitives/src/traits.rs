@@ -43,6 +43,9 @@ pub trait TrustedCallVerification {
 	fn nonce(&self) -> Index;
 
 	fn verify_signature(&self, mrenclave: &[u8; 32], shard: &ShardIdentifier) -> bool;
+
+	/// Names of the call and of the calls it wraps, outermost first.
+	fn call_names(&self) -> Vec<&'static str>;
 }
 
 /// validation for top pool
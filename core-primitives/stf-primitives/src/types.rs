@@ -15,6 +15,7 @@
 
 */
 extern crate alloc;
+pub use crate::signature::Signature;
 use crate::traits::{PoolTransactionValidation, TrustedCallVerification};
 use alloc::boxed::Box;
 use codec::{Compact, Decode, Encode};
@@ -23,10 +24,8 @@ use sp_core::{blake2_256, crypto::AccountId32, ed25519, sr25519, Pair, H256};
 use sp_runtime::{
 	traits::Verify,
 	transaction_validity::{TransactionValidityError, ValidTransaction},
-	MultiSignature,
 };
 use sp_std::{vec, vec::Vec};
-pub type Signature = MultiSignature;
 pub type AuthorityId = <Signature as Verify>::Signer;
 pub type AccountId = AccountId32;
 pub type Nonce = u32;
@@ -23,6 +23,7 @@ use std::sync::RwLockWriteGuard;
 
 use crate::error::Result;
 use itp_types::ShardIdentifier;
+use std::panic::{self, AssertUnwindSafe};
 
 /// Facade for handling STF state loading and storing (e.g. from file).
 pub trait HandleState {
@@ -73,3 +74,17 @@ pub trait HandleState {
 	/// Use in cases where the previous state is of no interest. Otherwise use `load_for_mutation` and `write_after_mutation`.
 	fn reset(&self, state: Self::StateT, shard: &ShardIdentifier) -> Result<Self::HashType>;
 }
+
+/// Runs the mutation of a state loaded with [`HandleState::load_for_mutation`]. If the mutation
+/// panics, the write lock is released before the panic unwinds further, so the lock of all shards
+/// isn't poisoned. That's sound because the mutation works on a copy of the state, which is only
+/// stored by [`HandleState::write_after_mutation`].
+pub fn mutate_unpoisoned<Lock, R>(state_lock: Lock, mutation: impl FnOnce() -> R) -> (Lock, R) {
+	match panic::catch_unwind(AssertUnwindSafe(mutation)) {
+		Ok(result) => (state_lock, result),
+		Err(panic) => {
+			drop(state_lock);
+			panic::resume_unwind(panic)
+		},
+	}
+}
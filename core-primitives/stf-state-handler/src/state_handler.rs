@@ -251,15 +251,23 @@ where
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::test::mocks::{
-		initialize_state_mock::InitializeStateMock,
-		versioned_state_access_mock::VersionedStateAccessMock,
+	use crate::{
+		handle_state::mutate_unpoisoned,
+		test::mocks::{
+			initialize_state_mock::InitializeStateMock,
+			versioned_state_access_mock::VersionedStateAccessMock,
+		},
 	};
 	use codec::Encode;
 	use itp_sgx_externalities::{SgxExternalities, SgxExternalitiesType};
 	use itp_stf_state_observer::mock::UpdateStateMock;
 	use itp_types::H256;
-	use std::{collections::VecDeque, sync::Arc, thread};
+	use std::{
+		collections::VecDeque,
+		panic::{self, AssertUnwindSafe},
+		sync::Arc,
+		thread,
+	};
 
 	type TestState = SgxExternalities;
 	type TestHash = H256;
@@ -285,6 +293,21 @@ mod tests {
 		state
 	}
 
+	#[test]
+	fn panicking_mutation_does_not_poison_the_state_lock() {
+		let shard_id = ShardIdentifier::random();
+		let state_handler = default_state_handler();
+		state_handler.initialize_shard(shard_id).unwrap();
+
+		let result = panic::catch_unwind(AssertUnwindSafe(|| {
+			let (lock, _state) = state_handler.load_for_mutation(&shard_id).unwrap();
+			mutate_unpoisoned(lock, || panic!("corrupt state"))
+		}));
+
+		assert!(result.is_err());
+		assert!(state_handler.load_for_mutation(&shard_id).is_ok());
+	}
+
 	#[test]
 	fn load_for_mutation_blocks_any_concurrent_access() {
 		let shard_id = ShardIdentifier::random();
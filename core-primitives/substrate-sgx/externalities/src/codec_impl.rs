@@ -19,7 +19,8 @@
 //!
 //! This is necessary workaround, as `Encode` and `Decode` can't directly be implemented on `HashMap` or `BTreeMap`.
 
-use codec::{Decode, Encode, Input};
+use codec::{Decode, Encode, Input, Output};
+use postcard::flavors::SerFlavor;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{vec, vec::Vec};
 
@@ -29,6 +30,10 @@ impl Encode for SgxExternalitiesType {
 	fn encode(&self) -> Vec<u8> {
 		encode_with_serialize(&self)
 	}
+
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		encode_to_with_serialize(&self, dest)
+	}
 }
 
 impl Decode for SgxExternalitiesType {
@@ -41,6 +46,10 @@ impl Encode for SgxExternalitiesDiffType {
 	fn encode(&self) -> Vec<u8> {
 		encode_with_serialize(&self)
 	}
+
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		encode_to_with_serialize(&self, dest)
+	}
 }
 
 impl Decode for SgxExternalitiesDiffType {
@@ -56,6 +65,33 @@ fn encode_with_serialize<T: Serialize>(source: &T) -> Vec<u8> {
 	postcard::to_allocvec(source).unwrap()
 }
 
+/// Same bytes as [`encode_with_serialize`], but written to `dest` as they are serialized, so a
+/// streaming output doesn't need the whole encoding in memory.
+fn encode_to_with_serialize<T: Serialize, O: Output + ?Sized>(source: &T, dest: &mut O) {
+	postcard::serialize_with_flavor(source, OutputFlavor(dest)).unwrap()
+}
+
+/// Postcard serialization flavor writing to a SCALE [`Output`].
+struct OutputFlavor<'a, O: Output + ?Sized>(&'a mut O);
+
+impl<'a, O: Output + ?Sized> SerFlavor for OutputFlavor<'a, O> {
+	type Output = ();
+
+	fn try_extend(&mut self, data: &[u8]) -> Result<(), ()> {
+		self.0.write(data);
+		Ok(())
+	}
+
+	fn try_push(&mut self, data: u8) -> Result<(), ()> {
+		self.0.push_byte(data);
+		Ok(())
+	}
+
+	fn release(self) -> Result<Self::Output, ()> {
+		Ok(())
+	}
+}
+
 fn decode_with_deserialize<I: Input, T: DeserializeOwned>(
 	input: &mut I,
 ) -> Result<T, codec::Error> {
@@ -116,6 +152,20 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn encoding_to_output_equals_encoding() {
+		let state = create_default_state();
+		let state_diff = create_default_state_diff();
+		let mut state_output = Vec::new();
+		let mut state_diff_output = Vec::new();
+
+		state.encode_to(&mut state_output);
+		state_diff.encode_to(&mut state_diff_output);
+
+		assert_eq!(state_output, state.encode());
+		assert_eq!(state_diff_output, state_diff.encode());
+	}
+
 	fn create_default_state_diff() -> SgxExternalitiesDiffType {
 		let mut map = InternalMap::<Option<Vec<u8>>>::new();
 		map.insert(Encode::encode("dings"), Some(Encode::encode("other")));
@@ -24,11 +24,15 @@ extern crate sgx_tstd as std;
 use codec::{Decode, Encode, EncodeAppend};
 use core::ops::Bound;
 use derive_more::{Deref, DerefMut, From, IntoIterator};
-use itp_hashing::Hash;
+use itp_hashing::{
+	hash_function::{state_hash_algorithm_key, HashFunction, StateHashAlgorithm},
+	Hash,
+};
 use serde::{Deserialize, Serialize};
-use sp_core::{hashing::blake2_256, H256};
+use sp_core::H256;
 use std::{collections::BTreeMap, vec, vec::Vec};
 
+pub use namespace::{execute_in_namespace, NamespaceGuard};
 pub use scope_limited::{set_and_run_with_externalities, with_externalities};
 
 // Unfortunately we cannot use `serde_with::serde_as` to serialize our map (which would be very convenient)
@@ -38,6 +42,7 @@ pub use scope_limited::{set_and_run_with_externalities, with_externalities};
 //use serde_with::serde_as;
 
 mod codec_impl;
+mod namespace;
 mod scope_limited;
 // These are used to serialize a map with keys that are not string.
 mod bypass;
@@ -74,8 +79,14 @@ pub trait StateHash {
 }
 
 impl StateHash for SgxExternalities {
+	/// Hashes the state with the algorithm configured in the state, blake2b-256 by default.
 	fn hash(&self) -> H256 {
-		self.state.using_encoded(blake2_256).into()
+		let algorithm = self
+			.state
+			.get(state_hash_algorithm_key().as_slice())
+			.and_then(|encoded| StateHashAlgorithm::decode(&mut encoded.as_slice()).ok())
+			.unwrap_or_default();
+		self.state.using_encoded(|encoded| algorithm.hash_bytes(encoded))
 	}
 }
 
@@ -143,27 +154,39 @@ where
 	}
 
 	fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
+		if !namespace::permits(&key) {
+			return None
+		}
 		self.state_diff.insert(key.clone(), Some(value.clone()));
 		self.state.insert(key, value)
 	}
 
 	fn append(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		if !namespace::permits(&key) {
+			return
+		}
 		let current = self.state.entry(key.clone()).or_default();
 		let updated_value = StorageAppend::new(current).append(value);
 		self.state_diff.insert(key, Some(updated_value));
 	}
 
 	fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+		if !namespace::permits(key) {
+			return None
+		}
 		self.state_diff.insert(key.to_vec(), None);
 		self.state.remove(key)
 	}
 
 	fn get(&self, key: &[u8]) -> Option<&Vec<u8>> {
+		if !namespace::permits(key) {
+			return None
+		}
 		self.state.get(key)
 	}
 
 	fn contains_key(&self, key: &[u8]) -> bool {
-		self.state.contains_key(key)
+		namespace::permits(key) && self.state.contains_key(key)
 	}
 
 	fn next_storage_key(&self, key: &[u8]) -> Option<Vec<u8>> {
@@ -185,9 +208,12 @@ where
 			.cloned()
 			.collect::<Vec<_>>();
 
-		let count = to_remove.len() as u32;
+		let mut count = 0;
 		for key in to_remove {
-			self.remove(&key);
+			if namespace::permits(&key) {
+				self.remove(&key);
+				count += 1;
+			}
 		}
 		count
 	}
@@ -282,6 +308,21 @@ pub mod tests {
 		assert_eq!(2, state_len);
 	}
 
+	#[test]
+	fn state_is_hashed_with_the_configured_algorithm() {
+		let mut ext = SgxExternalities::default();
+		ext.insert(b"key".to_vec(), b"value".to_vec());
+		let blake2_hash = StateHash::hash(&ext);
+		assert_eq!(blake2_hash, ext.state.using_encoded(sp_core::hashing::blake2_256).into());
+
+		ext.insert(state_hash_algorithm_key().to_vec(), StateHashAlgorithm::Keccak256.encode());
+
+		assert_eq!(
+			StateHash::hash(&ext),
+			ext.state.using_encoded(sp_core::hashing::keccak_256).into()
+		);
+	}
+
 	#[test]
 	fn basic_externalities_is_empty() {
 		let ext = SgxExternalities::default();
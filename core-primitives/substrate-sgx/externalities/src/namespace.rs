@@ -0,0 +1,88 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Restricts the keys the code executed in a namespace guard may access.
+//!
+//! Keys with one of the denied prefixes are neither read nor written while the guard is set, the
+//! attempted accesses are recorded as violations instead.
+
+use std::vec::Vec;
+
+environmental::environmental!(namespace_guard: NamespaceGuard);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceGuard {
+	denied: Vec<Vec<u8>>,
+	violations: Vec<Vec<u8>>,
+}
+
+impl NamespaceGuard {
+	/// Denies access to all `protected` key prefixes that are not `accessible`.
+	pub fn new(protected: Vec<Vec<u8>>, accessible: &[Vec<u8>]) -> Self {
+		let denied = protected.into_iter().filter(|prefix| !accessible.contains(prefix)).collect();
+		NamespaceGuard { denied, violations: Vec::new() }
+	}
+
+	/// Keys that have been accessed despite being denied.
+	pub fn violations(&self) -> &[Vec<u8>] {
+		&self.violations
+	}
+
+	fn permits(&mut self, key: &[u8]) -> bool {
+		if self.denied.iter().any(|prefix| key.starts_with(prefix)) {
+			self.violations.push(key.to_vec());
+			return false
+		}
+		true
+	}
+}
+
+/// Executes the closure with the guard set. Nested guards replace the outer one until they return.
+pub fn execute_in_namespace<F: FnOnce() -> R, R>(guard: &mut NamespaceGuard, f: F) -> R {
+	namespace_guard::using(guard, f)
+}
+
+/// Whether the key may be accessed, always true if no guard is set.
+pub(crate) fn permits(key: &[u8]) -> bool {
+	namespace_guard::with(|guard| guard.permits(key)).unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{SgxExternalities, SgxExternalitiesTrait};
+
+	#[test]
+	fn denied_keys_are_neither_read_nor_written() {
+		let mut ext = SgxExternalities::default();
+		ext.insert(b"bank_balance".to_vec(), vec![1]);
+		let mut guard =
+			NamespaceGuard::new(vec![b"bank".to_vec(), b"vote".to_vec()], &[b"vote".to_vec()]);
+
+		execute_in_namespace(&mut guard, || {
+			assert_eq!(ext.get(b"bank_balance"), None);
+			assert_eq!(ext.insert(b"bank_balance".to_vec(), vec![2]), None);
+			ext.insert(b"vote_tally".to_vec(), vec![3]);
+			ext.insert(b"other".to_vec(), vec![4]);
+		});
+
+		assert_eq!(ext.get(b"bank_balance"), Some(&vec![1]));
+		assert_eq!(ext.get(b"vote_tally"), Some(&vec![3]));
+		assert_eq!(ext.get(b"other"), Some(&vec![4]));
+		assert_eq!(guard.violations(), &[b"bank_balance".to_vec(), b"bank_balance".to_vec()]);
+	}
+}
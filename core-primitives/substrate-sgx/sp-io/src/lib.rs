@@ -652,12 +652,7 @@ pub mod crypto {
 		sig: &[u8; 65],
 		msg: &[u8; 32],
 	) -> Result<[u8; 64], EcdsaVerifyError> {
-		let rs = libsecp256k1::Signature::parse_standard_slice(&sig[0..64])
-			.map_err(|_| EcdsaVerifyError::BadRS)?;
-		let v = libsecp256k1::RecoveryId::parse(if sig[64] > 26 { sig[64] - 27 } else { sig[64] })
-			.map_err(|_| EcdsaVerifyError::BadV)?;
-		let pubkey = libsecp256k1::recover(&libsecp256k1::Message::parse(msg), &rs, &v)
-			.map_err(|_| EcdsaVerifyError::BadSignature)?;
+		let pubkey = secp256k1_recover(sig, msg)?;
 		let mut res = [0u8; 64];
 		res.copy_from_slice(&pubkey.serialize()[1..65]);
 
@@ -668,8 +663,19 @@ pub mod crypto {
 		sig: &[u8; 65],
 		msg: &[u8; 32],
 	) -> Result<[u8; 33], EcdsaVerifyError> {
-		warn!("crypto::secp256k1_ecdsa_recover unimplemented");
-		Ok([0; 33])
+		Ok(secp256k1_recover(sig, msg)?.serialize_compressed())
+	}
+
+	fn secp256k1_recover(
+		sig: &[u8; 65],
+		msg: &[u8; 32],
+	) -> Result<libsecp256k1::PublicKey, EcdsaVerifyError> {
+		let rs = libsecp256k1::Signature::parse_standard_slice(&sig[0..64])
+			.map_err(|_| EcdsaVerifyError::BadRS)?;
+		let v = libsecp256k1::RecoveryId::parse(if sig[64] > 26 { sig[64] - 27 } else { sig[64] })
+			.map_err(|_| EcdsaVerifyError::BadV)?;
+		libsecp256k1::recover(&libsecp256k1::Message::parse(msg), &rs, &v)
+			.map_err(|_| EcdsaVerifyError::BadSignature)
 	}
 }
 
@@ -1004,4 +1010,20 @@ mod tests {
 	fn storage_next_key_without_externalities_panics() {
 		storage::next_key(b"d".to_vec().as_slice());
 	}
+
+	#[test]
+	fn secp256k1_ecdsa_recover_compressed_returns_the_public_key_of_the_signer() {
+		let pair = ecdsa::Pair::from_seed(&[1u8; 32]);
+		let message = hashing::blake2_256(b"trusted call");
+		let signature = pair.sign_prehashed(&message);
+
+		assert_eq!(
+			crypto::secp256k1_ecdsa_recover_compressed(&signature.0, &message).ok(),
+			Some(pair.public().0)
+		);
+		assert_ne!(
+			crypto::secp256k1_ecdsa_recover_compressed(&signature.0, &[0u8; 32]).ok(),
+			Some(pair.public().0)
+		);
+	}
 }
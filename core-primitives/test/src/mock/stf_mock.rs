@@ -199,6 +199,15 @@ impl TrustedCallVerification for TrustedCallSignedMock {
 	fn verify_signature(&self, _mrenclave: &[u8; 32], _shard: &ShardIdentifier) -> bool {
 		true
 	}
+
+	fn call_names(&self) -> Vec<&'static str> {
+		let name = match self.call {
+			TrustedCallMock::noop(_) => "noop",
+			TrustedCallMock::balance_transfer(..) => "balance_transfer",
+			TrustedCallMock::waste_time_ms(..) => "waste_time_ms",
+		};
+		vec![name]
+	}
 }
 
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
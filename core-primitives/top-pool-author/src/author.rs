@@ -20,8 +20,12 @@ use crate::sgx_reexport_prelude::*;
 use core::fmt::Debug;
 
 use crate::{
+	call_validation::ValidateTrustedCall,
 	client_error::Error as ClientError,
 	error::{Error as StateRpcError, Result},
+	load_shedding::LoadShedder,
+	persistence::PersistenceExclusions,
+	quarantine::PayloadQuarantine,
 	top_filter::Filter,
 	traits::{AuthorApi, OnBlockImported},
 };
@@ -41,7 +45,9 @@ use itp_top_pool::{
 		TrustedOperationSource, TxHash,
 	},
 };
-use itp_types::{BlockHash as SidechainBlockHash, ShardIdentifier};
+use itp_types::{
+	payload_quarantine::QuarantineReason, BlockHash as SidechainBlockHash, ShardIdentifier,
+};
 use jsonrpc_core::{
 	futures::future::{ready, TryFutureExt},
 	Error as RpcError,
@@ -86,6 +92,10 @@ where
 	state_facade: Arc<StateFacade>,
 	shielding_key_repo: Arc<ShieldingKeyRepository>,
 	ocall_api: Arc<OCallApi>,
+	quarantine: Arc<PayloadQuarantine>,
+	load_shedder: Arc<LoadShedder>,
+	persistence_exclusions: Arc<PersistenceExclusions>,
+	call_validator: Arc<dyn ValidateTrustedCall<TCS> + Send + Sync>,
 }
 
 impl<TopPool, TopFilter, StateFacade, ShieldingKeyRepository, OCallApi, TCS, G>
@@ -107,8 +117,22 @@ where
 		state_facade: Arc<StateFacade>,
 		encryption_key: Arc<ShieldingKeyRepository>,
 		ocall_api: Arc<OCallApi>,
+		quarantine: Arc<PayloadQuarantine>,
+		load_shedder: Arc<LoadShedder>,
+		persistence_exclusions: Arc<PersistenceExclusions>,
+		call_validator: Arc<dyn ValidateTrustedCall<TCS> + Send + Sync>,
 	) -> Self {
-		Author { top_pool, top_filter, state_facade, shielding_key_repo: encryption_key, ocall_api }
+		Author {
+			top_pool,
+			top_filter,
+			state_facade,
+			shielding_key_repo: encryption_key,
+			ocall_api,
+			quarantine,
+			load_shedder,
+			persistence_exclusions,
+			call_validator,
+		}
 	}
 }
 
@@ -167,24 +191,47 @@ where
 		};
 		let request_vec = match shielding_key.decrypt(ext.as_slice()) {
 			Ok(req) => req,
-			Err(_) => return Box::pin(ready(Err(ClientError::BadFormatDecipher.into()))),
+			Err(_) => {
+				self.quarantine(shard, QuarantineReason::Undecryptable, &ext);
+				return Box::pin(ready(Err(ClientError::BadFormatDecipher.into())))
+			},
 		};
 		// decode call
 		let trusted_operation =
 			match StfTrustedOperation::<TCS, G>::decode(&mut request_vec.as_slice()) {
 				Ok(op) => op,
-				Err(_) => return Box::pin(ready(Err(ClientError::BadFormat.into()))),
+				Err(_) => {
+					self.quarantine(shard, QuarantineReason::Undecodable, &ext);
+					return Box::pin(ready(Err(ClientError::BadFormat.into())))
+				},
 			};
 
-		trace!("decrypted indirect invocation: {:?}", trusted_operation);
+		// the contents of a never persisted call must not leave the enclave memory
+		let never_persist = trusted_operation
+			.to_call()
+			.map_or(false, |call| self.persistence_exclusions.is_excluded(&call.call_names()));
+		if !never_persist {
+			trace!("decrypted indirect invocation: {:?}", trusted_operation);
+		}
 
 		// apply top filter - return error if this specific type of trusted operation
 		// is not allowed by the filter
 		if !self.top_filter.filter(&trusted_operation) {
 			warn!("unsupported operation");
+			if !never_persist {
+				self.quarantine(shard, QuarantineReason::Unsupported, &ext);
+			}
 			return Box::pin(ready(Err(ClientError::UnsupportedOperation.into())))
 		}
 
+		// validate the call against the current state of the shard
+		if let Some(trusted_call_signed) = trusted_operation.to_call() {
+			if let Err(reason) = self.call_validator.validate(&shard, trusted_call_signed) {
+				warn!("invalid trusted call: {}", reason);
+				return Box::pin(ready(Err(ClientError::InvalidCall(reason).into())))
+			}
+		}
+
 		//let best_block_hash = self.client.info().best_hash;
 		// dummy block hash
 		let best_block_hash = Default::default();
@@ -194,7 +241,12 @@ where
 			warn!("Failed to update metric for top pool size: {:?}", e);
 		}
 
-		if let Some(trusted_call_signed) = trusted_operation.to_call() {
+		if never_persist {
+			debug!(
+				"Submitting never persisted trusted call to TOP pool, TOP hash: {:?}",
+				self.hash_of(&trusted_operation)
+			);
+		} else if let Some(trusted_call_signed) = trusted_operation.to_call() {
 			debug!(
 				"Submitting trusted call to TOP pool: {:?}, TOP hash: {:?}",
 				trusted_call_signed,
@@ -233,6 +285,13 @@ where
 		}
 	}
 
+	/// Archives the still shielded payload, never its plaintext.
+	fn quarantine(&self, shard: ShardIdentifier, reason: QuarantineReason, payload: &[u8]) {
+		if let Err(e) = self.quarantine.quarantine(shard, reason, payload) {
+			warn!("Failed to quarantine rejected payload: {:?}", e);
+		}
+	}
+
 	fn remove_top(
 		&self,
 		bytes_or_hash: TrustedOperationOrHash<TCS, G>,
@@ -394,7 +453,16 @@ where
 		failed_to_remove
 	}
 
+	fn on_calls_deferred(&self, hashes: &[TxHash]) {
+		self.top_pool.on_deferred(hashes)
+	}
+
+	/// Only the direct submissions are rate limited when shedding load, the indirect invocations
+	/// have been paid for on the parentchain already.
 	fn watch_top(&self, ext: Vec<u8>, shard: ShardIdentifier) -> PoolFuture<TxHash, RpcError> {
+		if !self.load_shedder.admit_submission() {
+			return Box::pin(ready(Err(ClientError::Overloaded.into())))
+		}
 		self.process_top(ext, shard, TopSubmissionMode::SubmitWatch)
 	}
 }
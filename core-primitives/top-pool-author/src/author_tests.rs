@@ -17,6 +17,10 @@
 
 use crate::{
 	author::Author,
+	call_validation::{AcceptAllCalls, ValidateTrustedCall},
+	load_shedding::LoadShedder,
+	persistence::PersistenceExclusions,
+	quarantine::PayloadQuarantine,
 	test_fixtures::shard_id,
 	test_utils::submit_operation_to_top_pool,
 	top_filter::{AllowAllTopsFilter, Filter, GettersOnlyFilter},
@@ -36,6 +40,11 @@ use itp_test::mock::{
 	},
 };
 use itp_top_pool::mocks::trusted_operation_pool_mock::TrustedOperationPoolMock;
+use itp_types::{
+	load_shedding::{LoadSheddingPolicy, SheddingAction},
+	payload_quarantine::QuarantineReason,
+	ShardIdentifier,
+};
 
 use sgx_crypto_helper::{rsa3072::Rsa3072KeyPair, RsaKeyPair};
 use sp_core::H256;
@@ -125,8 +134,134 @@ fn submitting_indirect_call_works() {
 	assert_eq!(1, author.get_pending_trusted_calls(shard_id()).len());
 }
 
+#[test]
+fn filtered_call_is_quarantined_still_shielded() {
+	let quarantine = Arc::new(PayloadQuarantine::default());
+	quarantine.enable(1024).unwrap();
+	let (author, _, shielding_key) =
+		create_author_with_filter_and_quarantine(GettersOnlyFilter::new(), quarantine.clone());
+	let top_call = mock_top_direct_trusted_call_signed();
+
+	let _ = submit_operation_to_top_pool(&author, &top_call, &shielding_key, shard_id());
+
+	let quarantined = quarantine.payloads(&shard_id()).unwrap();
+	assert_eq!(1, quarantined.len());
+	assert_eq!(QuarantineReason::Unsupported, quarantined[0].reason);
+	assert_eq!(
+		top_call,
+		TrustedOperationMock::decode(
+			&mut shielding_key.decrypt(&quarantined[0].payload).unwrap().as_slice()
+		)
+		.unwrap()
+	);
+}
+
+#[test]
+fn never_persisted_call_is_not_quarantined() {
+	let quarantine = Arc::new(PayloadQuarantine::default());
+	quarantine.enable(1024).unwrap();
+	let persistence_exclusions = Arc::new(PersistenceExclusions::default());
+	persistence_exclusions.set_excluded(vec!["balance_transfer".into()]);
+	let (author, _, shielding_key) = create_author(
+		GettersOnlyFilter::new(),
+		quarantine.clone(),
+		Arc::new(LoadShedder::default()),
+		persistence_exclusions,
+		Arc::new(AcceptAllCalls),
+	);
+	let top_call = mock_top_direct_trusted_call_signed();
+
+	let submit_response =
+		submit_operation_to_top_pool(&author, &top_call, &shielding_key, shard_id());
+
+	assert!(submit_response.is_err());
+	assert!(quarantine.payloads(&shard_id()).unwrap().is_empty());
+}
+
+#[test]
+fn direct_submissions_are_rate_limited_while_shedding_load() {
+	let load_shedder = Arc::new(LoadShedder::default());
+	load_shedder.set_policy(Some(LoadSheddingPolicy {
+		actions: vec![SheddingAction::RateLimitSubmissions],
+		escalate_after: 1,
+		recover_after: 1,
+		epc_pressure_percent: 100,
+		max_submissions_per_slot: 1,
+		low_priority_shards: Vec::new(),
+	}));
+	load_shedder.on_slot_end(true);
+	let (author, _top_pool, shielding_key) = create_author(
+		AllowAllTopsFilter::new(),
+		Arc::new(PayloadQuarantine::default()),
+		load_shedder,
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
+	);
+
+	let first_call = mock_top_direct_trusted_call_signed();
+	let second_call = mock_top_indirect_trusted_call_signed();
+	assert!(submit_operation_to_top_pool(&author, &first_call, &shielding_key, shard_id()).is_ok());
+	assert!(
+		submit_operation_to_top_pool(&author, &second_call, &shielding_key, shard_id()).is_err()
+	);
+	assert_eq!(vec![first_call], author.get_pending_trusted_calls(shard_id()));
+}
+
+#[test]
+fn call_rejected_by_the_validator_does_not_enter_the_pool() {
+	let (author, top_pool, shielding_key) = create_author(
+		AllowAllTopsFilter::new(),
+		Arc::new(PayloadQuarantine::default()),
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(RejectAllCalls),
+	);
+	let top_call = mock_top_direct_trusted_call_signed();
+
+	let submit_response =
+		submit_operation_to_top_pool(&author, &top_call, &shielding_key, shard_id());
+
+	assert!(submit_response.is_err());
+	assert!(top_pool.get_last_submitted_transactions().is_empty());
+}
+
+struct RejectAllCalls;
+
+impl ValidateTrustedCall<TrustedCallSignedMock> for RejectAllCalls {
+	fn validate(
+		&self,
+		_shard: &ShardIdentifier,
+		_call: &TrustedCallSignedMock,
+	) -> Result<(), String> {
+		Err("missing compliance attestation".into())
+	}
+}
+
 fn create_author_with_filter<F: Filter<Value = TrustedOperationMock>>(
 	filter: F,
+) -> (TestAuthor<F>, Arc<TrustedOperationPoolMock<TrustedOperationMock>>, ShieldingCryptoMock) {
+	create_author_with_filter_and_quarantine(filter, Arc::new(PayloadQuarantine::default()))
+}
+
+fn create_author_with_filter_and_quarantine<F: Filter<Value = TrustedOperationMock>>(
+	filter: F,
+	quarantine: Arc<PayloadQuarantine>,
+) -> (TestAuthor<F>, Arc<TrustedOperationPoolMock<TrustedOperationMock>>, ShieldingCryptoMock) {
+	create_author(
+		filter,
+		quarantine,
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
+	)
+}
+
+fn create_author<F: Filter<Value = TrustedOperationMock>>(
+	filter: F,
+	quarantine: Arc<PayloadQuarantine>,
+	load_shedder: Arc<LoadShedder>,
+	persistence_exclusions: Arc<PersistenceExclusions>,
+	call_validator: Arc<dyn ValidateTrustedCall<TrustedCallSignedMock> + Send + Sync>,
 ) -> (TestAuthor<F>, Arc<TrustedOperationPoolMock<TrustedOperationMock>>, ShieldingCryptoMock) {
 	let top_pool = Arc::new(TrustedOperationPoolMock::default());
 
@@ -146,6 +281,10 @@ fn create_author_with_filter<F: Filter<Value = TrustedOperationMock>>(
 			Arc::new(state_facade),
 			shielding_key_repo,
 			ocall_mock,
+			quarantine,
+			load_shedder,
+			persistence_exclusions,
+			call_validator,
 		),
 		top_pool,
 		encryption_key,
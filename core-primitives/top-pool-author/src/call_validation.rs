@@ -0,0 +1,39 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Validation of trusted calls against the state of their shard, before they enter the pool.
+//!
+//! The STF decides what a call is checked for, e.g. the compliance gate of regulated shards.
+//! A call is checked again when it is executed, since the state may change in between.
+
+use itp_stf_primitives::types::ShardIdentifier;
+use std::string::String;
+
+pub trait ValidateTrustedCall<TCS> {
+	/// Returns the reason if the call must not enter the pool.
+	fn validate(&self, shard: &ShardIdentifier, call: &TCS) -> Result<(), String>;
+}
+
+/// Accepts all calls, e.g. for authors without access to the state.
+#[derive(Default)]
+pub struct AcceptAllCalls;
+
+impl<TCS> ValidateTrustedCall<TCS> for AcceptAllCalls {
+	fn validate(&self, _shard: &ShardIdentifier, _call: &TCS) -> Result<(), String> {
+		Ok(())
+	}
+}
@@ -22,7 +22,7 @@ use crate::sgx_reexport_prelude::*;
 
 use derive_more::{Display, From};
 use jsonrpc_core as rpc_core;
-use std::{boxed::Box, format};
+use std::{boxed::Box, format, string::String};
 
 /// Author RPC Result type.
 pub type Result<T> = core::result::Result<T, Error>;
@@ -68,6 +68,13 @@ pub enum Error {
 	/// Unsupported trusted operation (in case we allow only certain types of operations, using filters)
 	#[display(fmt = "Unsupported operation type")]
 	UnsupportedOperation,
+	/// The trusted call is invalid on the current state of the shard.
+	#[display(fmt = "Invalid trusted call: {}", _0)]
+	#[from(ignore)]
+	InvalidCall(String),
+	/// The worker sheds load and refuses the operation for now.
+	#[display(fmt = "Worker is overloaded")]
+	Overloaded,
 }
 
 impl std::error::Error for Error {
@@ -104,6 +111,8 @@ const POOL_CYCLE_DETECTED: i64 = POOL_INVALID_TX + 5;
 const POOL_IMMEDIATELY_DROPPED: i64 = POOL_INVALID_TX + 6;
 /// The key type crypto is not known.
 const UNSUPPORTED_KEY_TYPE: i64 = POOL_INVALID_TX + 7;
+/// The worker is overloaded and rate limits the submissions.
+const OVERLOADED: i64 = BASE_ERROR + 20;
 
 impl From<Error> for rpc_core::Error {
 	fn from(e: Error) -> Self {
@@ -130,6 +139,11 @@ impl From<Error> for rpc_core::Error {
 				message: "Shard does not exist".into(),
 				data: Some(format!("{:?}", e).into()),
 			},
+			Error::InvalidCall(reason) => rpc_core::Error {
+				code: rpc_core::ErrorCode::ServerError(VERIFICATION_ERROR),
+				message: "Trusted call is invalid".into(),
+				data: Some(reason.into()),
+			},
 			Error::Pool(PoolError::InvalidTrustedOperation) => rpc_core::Error {
 				code: rpc_core::ErrorCode::ServerError(POOL_INVALID_TX),
 				message: "Invalid Trusted Operation".into(),
@@ -165,6 +179,11 @@ impl From<Error> for rpc_core::Error {
 				message: "Immediately Dropped".into(),
 				data: Some("The Trusted Operation couldn't enter the pool because of the limit".into()),
 			},
+			Error::Overloaded => rpc_core::Error {
+				code: rpc_core::ErrorCode::ServerError(OVERLOADED),
+				message: "Worker is overloaded".into(),
+				data: Some("The submission limit of the current slot has been reached, please try again later.".into()),
+			},
 			Error::UnsupportedKeyType => rpc_core::Error {
 				code: rpc_core::ErrorCode::ServerError(UNSUPPORTED_KEY_TYPE),
 				message: "Unknown key type crypto" .into(),
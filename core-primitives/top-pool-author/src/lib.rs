@@ -33,8 +33,13 @@ pub mod sgx_reexport_prelude {
 
 pub mod api;
 pub mod author;
+pub mod call_validation;
 pub mod client_error;
 pub mod error;
+pub mod load_shedding;
+pub mod maintenance;
+pub mod persistence;
+pub mod quarantine;
 pub mod top_filter;
 pub mod traits;
 
@@ -0,0 +1,239 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Graceful degradation under sustained overload.
+//!
+//! Every slot is either overloaded, if it missed its deadline or the EPC is under pressure, or
+//! healthy. The shedding level rises by one after `escalate_after` overloaded slots in a row, and
+//! drops by one after `recover_after` healthy slots in a row. The first `level` actions of the
+//! policy are active.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "sgx")]
+use std::sync::SgxMutex as Mutex;
+
+use itp_types::{
+	load_shedding::{LoadSheddingPolicy, SheddingAction},
+	ShardIdentifier,
+};
+use log::*;
+
+#[derive(Default)]
+struct SheddingState {
+	policy: Option<LoadSheddingPolicy>,
+	level: usize,
+	overloaded_slots: u32,
+	healthy_slots: u32,
+	epc_usage_percent: u8,
+	submissions: u32,
+}
+
+impl SheddingState {
+	fn is_active(&self, action: SheddingAction) -> bool {
+		self.policy
+			.as_ref()
+			.map_or(false, |policy| policy.actions.iter().take(self.level).any(|a| *a == action))
+	}
+}
+
+/// Disabled, until a policy is set.
+#[derive(Default)]
+pub struct LoadShedder {
+	state: Mutex<SheddingState>,
+}
+
+impl LoadShedder {
+	/// Replaces the policy and starts over without any active action.
+	pub fn set_policy(&self, policy: Option<LoadSheddingPolicy>) {
+		info!("Load shedding policy: {:?}", policy);
+		match self.state.lock() {
+			Ok(mut state) => *state = SheddingState { policy, ..Default::default() },
+			Err(_) => error!("Load shedding lock is poisoned"),
+		}
+	}
+
+	/// Records the EPC usage, taken into account at the end of the current slot.
+	pub fn report_epc_usage(&self, used: u64, total: u64) {
+		if total == 0 {
+			return
+		}
+		if let Ok(mut state) = self.state.lock() {
+			state.epc_usage_percent = (used.saturating_mul(100) / total).min(100) as u8;
+		}
+	}
+
+	/// Ends a slot, returns the new shedding level if it has changed.
+	pub fn on_slot_end(&self, deadline_missed: bool) -> Option<usize> {
+		let mut state = match self.state.lock() {
+			Ok(state) => state,
+			Err(_) => {
+				error!("Load shedding lock is poisoned");
+				return None
+			},
+		};
+		state.submissions = 0;
+		let (escalate_after, recover_after, max_level, epc_pressure_percent) =
+			match state.policy.as_ref() {
+				Some(policy) => (
+					policy.escalate_after,
+					policy.recover_after,
+					policy.actions.len(),
+					policy.epc_pressure_percent,
+				),
+				None => return None,
+			};
+
+		let previous_level = state.level;
+		if deadline_missed || state.epc_usage_percent >= epc_pressure_percent {
+			state.healthy_slots = 0;
+			state.overloaded_slots += 1;
+			if state.overloaded_slots >= escalate_after && state.level < max_level {
+				state.level += 1;
+				state.overloaded_slots = 0;
+			}
+		} else {
+			state.overloaded_slots = 0;
+			state.healthy_slots += 1;
+			if state.healthy_slots >= recover_after && state.level > 0 {
+				state.level -= 1;
+				state.healthy_slots = 0;
+			}
+		}
+
+		if state.level == previous_level {
+			return None
+		}
+		if state.level > previous_level {
+			warn!(
+				"Overloaded for {} slots, load shedding level {} activates {:?}",
+				escalate_after,
+				state.level,
+				state.policy.as_ref().map(|policy| policy.actions[state.level - 1])
+			);
+		} else {
+			info!(
+				"Healthy for {} slots, load shedding level {} deactivates {:?}",
+				recover_after,
+				state.level,
+				state.policy.as_ref().map(|policy| policy.actions[state.level])
+			);
+		}
+		Some(state.level)
+	}
+
+	pub fn level(&self) -> usize {
+		self.state.lock().map(|state| state.level).unwrap_or_default()
+	}
+
+	pub fn sheds_anonymous_getters(&self) -> bool {
+		self.state
+			.lock()
+			.map_or(false, |state| state.is_active(SheddingAction::ShedAnonymousGetters))
+	}
+
+	/// Counts the submission, returns false if the limit of the slot has been reached.
+	pub fn admit_submission(&self) -> bool {
+		let mut state = match self.state.lock() {
+			Ok(state) => state,
+			Err(_) => return true,
+		};
+		if !state.is_active(SheddingAction::RateLimitSubmissions) {
+			return true
+		}
+		let max_submissions = state.policy.as_ref().map_or(0, |p| p.max_submissions_per_slot);
+		if state.submissions >= max_submissions {
+			return false
+		}
+		state.submissions += 1;
+		true
+	}
+
+	pub fn is_paused(&self, shard: &ShardIdentifier) -> bool {
+		self.state.lock().map_or(false, |state| {
+			state.is_active(SheddingAction::PauseLowPriorityShards)
+				&& state
+					.policy
+					.as_ref()
+					.map_or(false, |policy| policy.low_priority_shards.contains(shard))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn policy() -> LoadSheddingPolicy {
+		LoadSheddingPolicy {
+			actions: vec![
+				SheddingAction::ShedAnonymousGetters,
+				SheddingAction::RateLimitSubmissions,
+				SheddingAction::PauseLowPriorityShards,
+			],
+			escalate_after: 2,
+			recover_after: 3,
+			epc_pressure_percent: 90,
+			max_submissions_per_slot: 1,
+			low_priority_shards: vec![ShardIdentifier::repeat_byte(1)],
+		}
+	}
+
+	#[test]
+	fn actions_are_activated_in_order_and_deactivated_in_reverse() {
+		let shedder = LoadShedder::default();
+		shedder.set_policy(Some(policy()));
+		let low_priority_shard = ShardIdentifier::repeat_byte(1);
+
+		assert_eq!(shedder.on_slot_end(true), None);
+		assert_eq!(shedder.on_slot_end(true), Some(1));
+		assert!(shedder.sheds_anonymous_getters());
+		assert!(shedder.admit_submission());
+		assert!(shedder.admit_submission());
+
+		shedder.on_slot_end(true);
+		assert_eq!(shedder.on_slot_end(true), Some(2));
+		assert!(shedder.admit_submission());
+		assert!(!shedder.admit_submission());
+		assert!(!shedder.is_paused(&low_priority_shard));
+
+		shedder.report_epc_usage(95, 100);
+		shedder.on_slot_end(false);
+		assert_eq!(shedder.on_slot_end(false), Some(3));
+		assert!(shedder.is_paused(&low_priority_shard));
+		assert!(!shedder.is_paused(&ShardIdentifier::default()));
+
+		shedder.report_epc_usage(50, 100);
+		shedder.on_slot_end(false);
+		shedder.on_slot_end(false);
+		assert_eq!(shedder.on_slot_end(false), Some(2));
+		assert!(!shedder.is_paused(&low_priority_shard));
+		assert!(shedder.sheds_anonymous_getters());
+	}
+
+	#[test]
+	fn nothing_is_shed_without_a_policy() {
+		let shedder = LoadShedder::default();
+
+		for _ in 0..10 {
+			assert_eq!(shedder.on_slot_end(true), None);
+		}
+		assert!(!shedder.sheds_anonymous_getters());
+		assert!(shedder.admit_submission());
+		assert_eq!(shedder.level(), 0);
+	}
+}
@@ -0,0 +1,86 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Maintenance windows declared by the operator, per shard.
+//!
+//! The author keeps accepting trusted calls during a window, and they count for the nonces of
+//! their senders. The block proposer does not execute them, they are executed in the first block
+//! after the window has ended.
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+use itp_types::{maintenance_window::MaintenanceWindow, ShardIdentifier};
+use log::*;
+use std::{collections::HashMap, vec::Vec};
+
+#[derive(Default)]
+pub struct MaintenanceSchedule {
+	windows: RwLock<HashMap<ShardIdentifier, Vec<MaintenanceWindow>>>,
+}
+
+impl MaintenanceSchedule {
+	/// Replaces the maintenance windows of the shard, an empty list removes them.
+	pub fn set_windows(&self, shard: ShardIdentifier, windows: Vec<MaintenanceWindow>) {
+		info!("Maintenance windows of shard {:?}: {:?}", shard, windows);
+		match self.windows.write() {
+			Ok(mut all_windows) =>
+				if windows.is_empty() {
+					all_windows.remove(&shard);
+				} else {
+					all_windows.insert(shard, windows);
+				},
+			Err(_) => error!("Maintenance schedule lock is poisoned"),
+		}
+	}
+
+	/// Whether `now`, in unix milliseconds, is within a maintenance window of the shard.
+	pub fn is_under_maintenance(&self, shard: &ShardIdentifier, now: u64) -> bool {
+		match self.windows.read() {
+			Ok(all_windows) => all_windows
+				.get(shard)
+				.map_or(false, |windows| windows.iter().any(|window| window.contains(now))),
+			Err(_) => {
+				error!("Maintenance schedule lock is poisoned");
+				false
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn shard_is_only_under_maintenance_within_its_windows() {
+		let shard = ShardIdentifier::default();
+		let other_shard = ShardIdentifier::repeat_byte(1);
+		let schedule = MaintenanceSchedule::default();
+		schedule.set_windows(shard, vec![MaintenanceWindow { start: 10, end: 20 }]);
+
+		assert!(!schedule.is_under_maintenance(&shard, 9));
+		assert!(schedule.is_under_maintenance(&shard, 10));
+		assert!(!schedule.is_under_maintenance(&shard, 20));
+		assert!(!schedule.is_under_maintenance(&other_shard, 15));
+
+		schedule.set_windows(shard, Vec::new());
+		assert!(!schedule.is_under_maintenance(&shard, 15));
+	}
+}
@@ -220,6 +220,8 @@ where
 		failed_to_remove
 	}
 
+	fn on_calls_deferred(&self, _hashes: &[H256]) {}
+
 	fn watch_top(&self, _ext: Vec<u8>, _shard: ShardIdentifier) -> PoolFuture<H256, RpcError> {
 		todo!()
 	}
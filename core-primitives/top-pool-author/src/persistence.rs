@@ -0,0 +1,77 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Trusted call types the operator marked as never persist.
+//!
+//! The pool itself is only held in enclave memory. The author additionally keeps excluded calls
+//! out of the payload quarantine and logs only their hash, so their contents never leave the
+//! enclave memory. Wrapped calls are excluded if any of the calls they wrap is.
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+use log::*;
+use std::{collections::BTreeSet, string::String, vec::Vec};
+
+#[derive(Default)]
+pub struct PersistenceExclusions {
+	call_names: RwLock<BTreeSet<String>>,
+}
+
+impl PersistenceExclusions {
+	/// Replaces the excluded call names, an empty list persists all calls again.
+	pub fn set_excluded(&self, call_names: Vec<String>) {
+		info!("Trusted calls that are never persisted: {:?}", call_names);
+		match self.call_names.write() {
+			Ok(mut excluded) => *excluded = call_names.into_iter().collect(),
+			Err(_) => error!("Persistence exclusions lock is poisoned"),
+		}
+	}
+
+	/// Whether any of the names of a call and the calls it wraps is excluded.
+	pub fn is_excluded(&self, call_names: &[&str]) -> bool {
+		match self.call_names.read() {
+			Ok(excluded) => call_names.iter().any(|name| excluded.contains(*name)),
+			Err(_) => {
+				error!("Persistence exclusions lock is poisoned");
+				// Rather keep a call out of the quarantine than leak it.
+				true
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn wrapped_calls_are_excluded_with_their_inner_call() {
+		let exclusions = PersistenceExclusions::default();
+		assert!(!exclusions.is_excluded(&["proxy_call", "balance_transfer"]));
+
+		exclusions.set_excluded(vec!["balance_transfer".into()]);
+		assert!(exclusions.is_excluded(&["balance_transfer"]));
+		assert!(exclusions.is_excluded(&["proxy_call", "balance_transfer"]));
+		assert!(!exclusions.is_excluded(&["balance_unshield"]));
+
+		exclusions.set_excluded(Vec::new());
+		assert!(!exclusions.is_excluded(&["balance_transfer"]));
+	}
+}
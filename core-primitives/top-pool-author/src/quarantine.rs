@@ -0,0 +1,238 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Size-bounded, sealed archive of the shielded payloads the author rejects.
+//!
+//! The quarantine is disabled until a size limit is set. Once enabled, the oldest payloads are
+//! dropped when a new one would exceed the limit. See [`itp_types::payload_quarantine`] for how
+//! the archive is exported.
+//!
+//! Every payload is appended to the sealed file, so quarantining doesn't reseal the whole
+//! archive. Dropped payloads stay in the file until it grows to twice the limit, then the file
+//! is rewritten with the archive. They are dropped again when the archive is loaded, as the
+//! oldest payloads are dropped first.
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use itp_sgx_io::{append as seal_append, read as unseal, write as seal};
+#[cfg(feature = "sgx")]
+use itp_sgx_io::{seal, seal_append, unseal};
+
+use codec::{Decode, Encode};
+use core::sync::atomic::{AtomicU64, Ordering};
+use itp_types::{
+	payload_quarantine::{QuarantineReason, QuarantinedPayload},
+	ShardIdentifier,
+};
+use log::*;
+use std::{collections::VecDeque, io::ErrorKind, path::PathBuf, vec::Vec};
+
+#[derive(Debug)]
+pub enum Error {
+	LockPoisoning,
+	Io(std::io::Error),
+	Codec(codec::Error),
+}
+
+impl From<std::io::Error> for Error {
+	fn from(e: std::io::Error) -> Self {
+		Self::Io(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Self::Codec(e)
+	}
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Default)]
+pub struct PayloadQuarantine {
+	archive: RwLock<VecDeque<QuarantinedPayload>>,
+	/// Upper bound of the encoded archive size, 0 disables the quarantine.
+	max_bytes: AtomicU64,
+	/// File the archive is sealed to, so it survives a restart of the enclave.
+	sealed_file: RwLock<Option<SealedFile>>,
+}
+
+struct SealedFile {
+	path: PathBuf,
+	/// Size of the payloads in the file, including the ones dropped from the archive.
+	size: u64,
+}
+
+impl PayloadQuarantine {
+	/// Loads the archive sealed to `path`, if any, and seals all later changes to it.
+	pub fn use_sealed_file(&self, path: PathBuf) -> Result<()> {
+		let bytes = match unseal(&path) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+			Err(e) => return Err(e.into()),
+		};
+		let mut input = bytes.as_slice();
+		let mut archive = VecDeque::new();
+		while !input.is_empty() {
+			archive.push_back(QuarantinedPayload::decode(&mut input)?);
+		}
+		debug!("Loaded {} quarantined payloads", archive.len());
+		*self.archive.write().map_err(|_| Error::LockPoisoning)? = archive;
+		*self.sealed_file.write().map_err(|_| Error::LockPoisoning)? =
+			Some(SealedFile { path, size: bytes.len() as u64 });
+		Ok(())
+	}
+
+	/// Enables the quarantine, dropping the oldest loaded payloads beyond the limit.
+	pub fn enable(&self, max_bytes: u64) -> Result<()> {
+		info!("Quarantining rejected payloads, up to {} bytes", max_bytes);
+		self.max_bytes.store(max_bytes, Ordering::SeqCst);
+		let mut archive = self.archive.write().map_err(|_| Error::LockPoisoning)?;
+		drop_oldest(&mut archive, max_bytes);
+		Ok(())
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.max_bytes.load(Ordering::SeqCst) > 0
+	}
+
+	/// Adds the payload to the archive, dropping the oldest payloads if necessary.
+	pub fn quarantine(
+		&self,
+		shard: ShardIdentifier,
+		reason: QuarantineReason,
+		payload: &[u8],
+	) -> Result<()> {
+		let max_bytes = self.max_bytes.load(Ordering::SeqCst);
+		let quarantined = QuarantinedPayload { shard, reason, payload: payload.to_vec() };
+		let quarantined_size = quarantined.encoded_size() as u64;
+		if quarantined_size > max_bytes {
+			return Ok(())
+		}
+		let mut archive = self.archive.write().map_err(|_| Error::LockPoisoning)?;
+		drop_oldest(&mut archive, max_bytes - quarantined_size);
+		let encoded = quarantined.encode();
+		archive.push_back(quarantined);
+		if let Some(file) = self.sealed_file.write().map_err(|_| Error::LockPoisoning)?.as_mut() {
+			if file.size + quarantined_size > 2 * max_bytes {
+				let bytes: Vec<u8> = archive.iter().flat_map(Encode::encode).collect();
+				seal(&bytes, &file.path)?;
+				file.size = bytes.len() as u64;
+			} else {
+				seal_append(&encoded, &file.path)?;
+				file.size += quarantined_size;
+			}
+		}
+		Ok(())
+	}
+
+	/// Quarantined payloads of the shard, oldest first.
+	pub fn payloads(&self, shard: &ShardIdentifier) -> Result<Vec<QuarantinedPayload>> {
+		Ok(self
+			.archive
+			.read()
+			.map_err(|_| Error::LockPoisoning)?
+			.iter()
+			.filter(|p| p.shard == *shard)
+			.cloned()
+			.collect())
+	}
+}
+
+/// Drops the oldest payloads until the archive is at most `max_bytes` in size.
+fn drop_oldest(archive: &mut VecDeque<QuarantinedPayload>, max_bytes: u64) {
+	let mut size: u64 = archive.iter().map(|p| p.encoded_size() as u64).sum();
+	while size > max_bytes {
+		match archive.pop_front() {
+			Some(dropped) => size -= dropped.encoded_size() as u64,
+			None => break,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn oldest_payloads_are_dropped_beyond_the_size_limit() {
+		let shard = ShardIdentifier::default();
+		let quarantine = PayloadQuarantine::default();
+		quarantine
+			.quarantine(shard, QuarantineReason::Undecryptable, &[1u8; 4])
+			.unwrap();
+		assert!(quarantine.payloads(&shard).unwrap().is_empty());
+
+		// A payload of 4 bytes is encoded together with its shard and reason in 38 bytes.
+		quarantine.enable(80).unwrap();
+		quarantine
+			.quarantine(shard, QuarantineReason::Undecryptable, &[1u8; 4])
+			.unwrap();
+		quarantine.quarantine(shard, QuarantineReason::Undecodable, &[2u8; 4]).unwrap();
+		quarantine.quarantine(shard, QuarantineReason::Unsupported, &[3u8; 4]).unwrap();
+		quarantine.quarantine(shard, QuarantineReason::Unsupported, &[4u8; 50]).unwrap();
+
+		let payloads = quarantine.payloads(&shard).unwrap();
+		assert_eq!(payloads.iter().map(|p| p.payload[0]).collect::<Vec<_>>(), vec![2u8, 3u8]);
+	}
+
+	#[test]
+	fn sealed_archive_survives_a_restart() {
+		let path = std::env::temp_dir().join("sealed_payload_quarantine_survives_a_restart.bin");
+		let _ = std::fs::remove_file(&path);
+		let shard = ShardIdentifier::default();
+		let quarantine = PayloadQuarantine::default();
+		quarantine.use_sealed_file(path.clone()).unwrap();
+		quarantine.enable(40).unwrap();
+		quarantine
+			.quarantine(shard, QuarantineReason::Undecryptable, &[1u8; 4])
+			.unwrap();
+
+		let restarted_quarantine = PayloadQuarantine::default();
+		restarted_quarantine.use_sealed_file(path.clone()).unwrap();
+
+		assert_eq!(restarted_quarantine.payloads(&shard).unwrap().len(), 1);
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn dropped_payloads_are_not_restored_after_a_restart() {
+		let path = std::env::temp_dir().join("sealed_payload_quarantine_drops_payloads.bin");
+		let _ = std::fs::remove_file(&path);
+		let shard = ShardIdentifier::default();
+		let quarantine = PayloadQuarantine::default();
+		quarantine.use_sealed_file(path.clone()).unwrap();
+		quarantine.enable(80).unwrap();
+		for byte in 1u8..=6 {
+			quarantine.quarantine(shard, QuarantineReason::Undecodable, &[byte; 4]).unwrap();
+		}
+		// The file has been rewritten once it exceeded twice the limit.
+		assert!(std::fs::metadata(&path).unwrap().len() <= 160);
+
+		let restarted_quarantine = PayloadQuarantine::default();
+		restarted_quarantine.use_sealed_file(path.clone()).unwrap();
+		restarted_quarantine.enable(80).unwrap();
+
+		let payloads = restarted_quarantine.payloads(&shard).unwrap();
+		assert_eq!(payloads.iter().map(|p| p.payload[0]).collect::<Vec<_>>(), vec![5u8, 6u8]);
+		std::fs::remove_file(path).unwrap();
+	}
+}
@@ -81,6 +81,10 @@ where
 		executed_calls: Vec<(TrustedOperationOrHash<TCS, G>, bool)>,
 	) -> Vec<TrustedOperationOrHash<TCS, G>>;
 
+	/// Notifies the watchers of the trusted calls that their execution has been deferred, they
+	/// stay in the pool.
+	fn on_calls_deferred(&self, hashes: &[Hash]);
+
 	/// Submit an extrinsic to watch.
 	///
 	/// See [`TrustedOperationStatus`](sp_transaction_pool::TrustedOperationStatus) for details on transaction
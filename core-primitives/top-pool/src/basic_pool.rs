@@ -232,6 +232,10 @@ where
 		self.pool.validated_pool().on_broadcasted(propagations)
 	}
 
+	fn on_deferred(&self, hashes: &[TxHash]) {
+		self.pool.validated_pool().on_deferred(hashes)
+	}
+
 	fn hash_of(&self, xt: &TOP) -> TxHash {
 		self.pool.hash_of(xt)
 	}
@@ -108,6 +108,12 @@ where
 		})
 	}
 
+	/// Execution of the TrustedOperation was deferred.
+	pub fn deferred(&mut self, tx: &TxHash) {
+		trace!(target: "txpool", "[{:?}] Deferred", tx);
+		self.fire(tx, |watcher| watcher.deferred());
+	}
+
 	/// TrustedOperation was removed as invalid.
 	pub fn invalid(&mut self, tx: &TxHash) {
 		self.fire(tx, |watcher| watcher.invalid());
@@ -52,4 +52,8 @@ where
 	fn send_state(&self, _hash: Self::Hash, _state_encoded: Vec<u8>) -> DirectRpcResult<()> {
 		Ok(())
 	}
+
+	fn send_notification(&self, _hash: Self::Hash, _value_encoded: Vec<u8>) -> DirectRpcResult<()> {
+		Ok(())
+	}
 }
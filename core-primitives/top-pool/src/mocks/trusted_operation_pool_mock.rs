@@ -199,6 +199,8 @@ where
 		unimplemented!()
 	}
 
+	fn on_deferred(&self, _hashes: &[TxHash]) {}
+
 	fn hash_of(&self, xt: &TOP) -> TxHash {
 		hash_of_top(xt)
 	}
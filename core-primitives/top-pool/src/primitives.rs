@@ -240,6 +240,9 @@ pub trait TrustedOperationPool<TOP>: Send + Sync {
 	/// Notify the pool about operations broadcast.
 	fn on_broadcasted(&self, propagations: HashMap<TxHash, Vec<String>>);
 
+	/// Notify the pool that the execution of operations is deferred.
+	fn on_deferred(&self, hashes: &[TxHash]);
+
 	/// Returns operation hash
 	fn hash_of(&self, xt: &TOP) -> TxHash;
 
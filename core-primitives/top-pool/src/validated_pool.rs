@@ -613,6 +613,13 @@ where
 		}
 	}
 
+	pub fn on_deferred(&self, hashes: &[TxHash]) {
+		let mut listener = self.listener.write().unwrap();
+		for hash in hashes {
+			listener.deferred(hash);
+		}
+	}
+
 	/// Remove a subtree of operations from the pool and mark them invalid.
 	///
 	/// The operations passed as an argument will be additionally banned
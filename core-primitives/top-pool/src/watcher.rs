@@ -34,6 +34,7 @@ pub struct Watcher<S> {
 	//receiver: TracingUnboundedReceiver<TrustedOperationStatus<H, BH>>,
 	hash: TxHash,
 	is_in_block: bool,
+	is_deferred: bool,
 	rpc_response_sender: Arc<S>,
 }
 
@@ -47,7 +48,7 @@ where
 	}
 
 	pub fn new_watcher(hash: TxHash, rpc_response_sender: Arc<S>) -> Self {
-		Watcher { hash, is_in_block: false, rpc_response_sender }
+		Watcher { hash, is_in_block: false, is_deferred: false, rpc_response_sender }
 	}
 
 	/// TrustedOperation became ready.
@@ -106,6 +107,15 @@ where
 		self.is_in_block = true;
 	}
 
+	/// The execution of the extrinsic is deferred until a maintenance window has ended. Only
+	/// the first deferral is sent, the extrinsic is deferred again in every block of the window.
+	pub fn deferred(&mut self) {
+		if !self.is_deferred {
+			self.is_deferred = true;
+			self.send(TrustedOperationStatus::Deferred);
+		}
+	}
+
 	/// The extrinsic has been broadcast to the given peers.
 	pub fn broadcast(&mut self, _peers: Vec<String>) {
 		//self.send(TrustedOperationStatus::Broadcast(peers))
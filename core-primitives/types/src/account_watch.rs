@@ -0,0 +1,113 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Watch-only registrations of exchange deposit accounts.
+//!
+//! An exchange registers a set of its deposit accounts on an open RPC connection, proving
+//! ownership with a signature of every account over the request. The worker then pushes a
+//! notification signed by the enclave for every incoming transfer to one of the accounts, so
+//! the exchange doesn't have to poll the balance of each account.
+
+use crate::{AccountId, Balance, ShardIdentifier, Signature};
+use codec::{Decode, Encode};
+use sp_core::{ed25519, Pair};
+use sp_runtime::traits::Verify;
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct AccountWatchRequest {
+	pub shard: ShardIdentifier,
+	pub accounts: Vec<AccountId>,
+	/// Unix time in milliseconds after which the request is no longer accepted, so a leaked
+	/// request can't be used to watch the accounts forever.
+	pub valid_until: u64,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedAccountWatchRequest {
+	pub request: AccountWatchRequest,
+	/// Signatures of the encoded request, one per watched account in the same order.
+	pub proofs: Vec<Signature>,
+}
+
+impl SignedAccountWatchRequest {
+	/// True if every watched account has signed the request.
+	pub fn verify_ownership(&self) -> bool {
+		let payload = self.request.encode();
+		self.proofs.len() == self.request.accounts.len()
+			&& self
+				.request
+				.accounts
+				.iter()
+				.zip(self.proofs.iter())
+				.all(|(account, proof)| proof.verify(payload.as_slice(), account))
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct IncomingTransfer {
+	pub shard: ShardIdentifier,
+	/// Sidechain block the transfer has been executed in.
+	pub sidechain_block: u64,
+	pub from: AccountId,
+	pub to: AccountId,
+	pub amount: Balance,
+}
+
+/// Value pushed to the watching connection, signed with the enclave signing key.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedTransferNotification {
+	pub transfer: IncomingTransfer,
+	pub signature: ed25519::Signature,
+}
+
+impl SignedTransferNotification {
+	pub fn new(transfer: IncomingTransfer, signer: &ed25519::Pair) -> Self {
+		let signature = signer.sign(&transfer.encode());
+		SignedTransferNotification { transfer, signature }
+	}
+
+	pub fn verify_signature(&self, signer: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(&self.signature, self.transfer.encode(), signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::sr25519;
+
+	#[test]
+	fn ownership_requires_a_signature_of_every_account() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let bob = sr25519::Pair::from_seed(&[2u8; 32]);
+		let request = AccountWatchRequest {
+			shard: ShardIdentifier::repeat_byte(1),
+			accounts: vec![alice.public().into(), bob.public().into()],
+			valid_until: 1_000,
+		};
+		let alice_proof: Signature = alice.sign(&request.encode()).into();
+		let bob_proof: Signature = bob.sign(&request.encode()).into();
+
+		let mut signed_request =
+			SignedAccountWatchRequest { request, proofs: vec![alice_proof.clone(), bob_proof] };
+		assert!(signed_request.verify_ownership());
+
+		signed_request.proofs = vec![alice_proof.clone(), alice_proof];
+		assert!(!signed_request.verify_ownership());
+	}
+}
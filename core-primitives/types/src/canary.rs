@@ -0,0 +1,104 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Report of a round of the canary an operator runs on a designated shard in production.
+//!
+//! Every round the enclave unseals the state of the canary shard, executes a transfer between
+//! two canary accounts, checks the outcome with a getter and seals the state again. The funds go
+//! back and forth, so the canary runs forever without being topped up. The state of the canary
+//! shard is kept apart from the served shard.
+//!
+//! The canary executes its calls directly on the STF. It does not cover the TOP pool, the block
+//! production and the sidechain block import.
+
+use codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanaryStep {
+	/// Unseal the state of the canary shard, initialize it in the first round.
+	LoadState,
+	/// Sign, verify and execute a balance transfer between the canary accounts.
+	Transfer,
+	/// Sign, verify and execute a balance getter of the receiver.
+	Getter,
+	/// Seal the updated state.
+	SealState,
+}
+
+impl CanaryStep {
+	pub const ALL: [CanaryStep; 4] =
+		[CanaryStep::LoadState, CanaryStep::Transfer, CanaryStep::Getter, CanaryStep::SealState];
+
+	/// Name of the step, e.g. to label metrics.
+	pub fn name(&self) -> &'static str {
+		match self {
+			CanaryStep::LoadState => "load_state",
+			CanaryStep::Transfer => "transfer",
+			CanaryStep::Getter => "getter",
+			CanaryStep::SealState => "seal_state",
+		}
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct CanaryReport {
+	/// Number of the round, counted in the state of the canary shard.
+	pub round: u64,
+	/// Unix time in milliseconds at the start of the round.
+	pub timestamp: u64,
+	/// Outcome of every step that was run, with the UTF-8 encoded error of a failed step. The
+	/// steps after a failure are skipped.
+	pub outcomes: Vec<(CanaryStep, Result<(), Vec<u8>>)>,
+}
+
+impl CanaryReport {
+	/// Whether all steps have been run and passed.
+	pub fn passed(&self) -> bool {
+		self.outcomes.len() == CanaryStep::ALL.len()
+			&& self.outcomes.iter().all(|(_, outcome)| outcome.is_ok())
+	}
+
+	/// The first failed step with its error.
+	pub fn failure(&self) -> Option<(CanaryStep, &[u8])> {
+		self.outcomes.iter().find_map(|(step, outcome)| match outcome {
+			Err(e) => Some((*step, e.as_slice())),
+			Ok(()) => None,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn report_only_passes_if_every_step_passed() {
+		let mut report = CanaryReport {
+			round: 3,
+			timestamp: 1_000,
+			outcomes: CanaryStep::ALL.iter().map(|step| (*step, Ok(()))).collect(),
+		};
+		assert!(report.passed());
+		assert!(report.failure().is_none());
+
+		report.outcomes[1].1 = Err(b"invalid nonce".to_vec());
+		report.outcomes.truncate(2);
+		assert!(!report.passed());
+		assert_eq!(report.failure(), Some((CanaryStep::Transfer, &b"invalid nonce"[..])));
+	}
+}
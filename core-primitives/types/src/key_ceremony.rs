@@ -0,0 +1,191 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Key ceremonies: operations on the state encryption key that need the approval of operators.
+//!
+//! A shard can be given a quorum of operators, of which `threshold` have to sign a ceremony
+//! before it proceeds. Shards without a quorum are approved by their root account alone. The
+//! state key encrypts the states of all shards of the worker, so operations on the key need the
+//! approval of every shard, a quorum change only that of the shard concerned.
+//!
+//! A request is bound to the enclave that conducts it and to a nonce that enclave issued for it,
+//! so approvals can neither be used with another enclave nor replayed once the nonce was used.
+
+use crate::{AccountId, ShardIdentifier, Signature};
+use codec::{Decode, Encode};
+use sp_runtime::traits::Verify;
+use sp_std::{
+	collections::{btree_map::BTreeMap, vec_deque::VecDeque},
+	vec::Vec,
+};
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct OperatorQuorum {
+	pub operators: Vec<AccountId>,
+	/// Number of distinct operators that have to approve a ceremony.
+	pub threshold: u32,
+}
+
+impl OperatorQuorum {
+	pub fn is_valid(&self) -> bool {
+		let distinct_operators =
+			self.operators.iter().enumerate().all(|(i, o)| !self.operators[..i].contains(o));
+		self.threshold > 0 && self.threshold as usize <= self.operators.len() && distinct_operators
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum KeyCeremonyOperation {
+	/// Replace the quorum that approves the ceremonies of the shard.
+	SetOperatorQuorum { shard: ShardIdentifier, quorum: OperatorQuorum },
+	/// Replace the state key and re-encrypt all states with it. The peer workers of the shards
+	/// have to be provisioned with the new key again.
+	RotateStateKey,
+	/// Export the state key, encrypted to the ephemeral X25519 public key of the recipient.
+	EscrowStateKey { recipient: [u8; 32] },
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct KeyCeremonyRequest {
+	pub operation: KeyCeremonyOperation,
+	/// Has to exceed the id of the last ceremony the enclave conducted, so a ceremony can't be
+	/// replayed.
+	pub ceremony_id: u64,
+	/// Signing account of the enclave that conducts the ceremony.
+	pub enclave: AccountId,
+	/// Nonce the enclave issued for the ceremony, it is invalidated by the next issued nonce and
+	/// once the ceremony is conducted.
+	pub nonce: [u8; 32],
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct OperatorApproval {
+	pub operator: AccountId,
+	/// Signature of the encoded [`KeyCeremonyRequest`].
+	pub signature: Signature,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ApprovedKeyCeremony {
+	pub request: KeyCeremonyRequest,
+	pub approvals: Vec<OperatorApproval>,
+}
+
+impl ApprovedKeyCeremony {
+	/// Whether the threshold of the quorum is reached by distinct operators with valid signatures.
+	pub fn is_approved_by(&self, quorum: &OperatorQuorum) -> bool {
+		let request = self.request.encode();
+		let mut approvers: Vec<&AccountId> = Vec::new();
+		for approval in self.approvals.iter() {
+			if quorum.operators.contains(&approval.operator)
+				&& !approvers.contains(&&approval.operator)
+				&& approval.signature.verify(request.as_slice(), &approval.operator)
+			{
+				approvers.push(&approval.operator);
+			}
+		}
+		approvers.len() >= quorum.threshold as usize
+	}
+}
+
+/// Entry of the sealed audit trail of the conducted ceremonies.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct KeyCeremonyRecord {
+	/// The ceremony with all its approvals, so auditors can verify the signatures.
+	pub ceremony: ApprovedKeyCeremony,
+	/// Unix timestamp in milliseconds.
+	pub conducted_at: u64,
+}
+
+/// Operator quorums of the shards and the ceremonies conducted so far.
+#[derive(Encode, Decode, Default, Clone, Debug, PartialEq, Eq)]
+pub struct KeyCeremonyTrail {
+	pub quorums: BTreeMap<ShardIdentifier, OperatorQuorum>,
+	pub last_ceremony_id: u64,
+	/// Nonce the next ceremony has to be signed over, if one was issued.
+	pub pending_nonce: Option<[u8; 32]>,
+	/// Conducted ceremonies, oldest first.
+	pub records: VecDeque<KeyCeremonyRecord>,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedStateKeyEscrow {
+	/// Ephemeral X25519 public key of the enclave.
+	pub enclave_public: [u8; 32],
+	/// Encoded state key, encrypted with the response key of sequence 0 of the session key
+	/// agreed on with the recipient.
+	pub ciphertext: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{ed25519, Pair};
+
+	fn operator(seed: u8) -> ed25519::Pair {
+		ed25519::Pair::from_seed(&[seed; 32])
+	}
+
+	fn approve(request: &KeyCeremonyRequest, operator: &ed25519::Pair) -> OperatorApproval {
+		OperatorApproval {
+			operator: operator.public().into(),
+			signature: operator.sign(&request.encode()).into(),
+		}
+	}
+
+	#[test]
+	fn ceremony_needs_threshold_of_distinct_operators() {
+		let quorum = OperatorQuorum {
+			operators: (1..=3).map(|seed| operator(seed).public().into()).collect(),
+			threshold: 2,
+		};
+		let request = KeyCeremonyRequest {
+			operation: KeyCeremonyOperation::RotateStateKey,
+			ceremony_id: 1,
+			enclave: operator(9).public().into(),
+			nonce: [7u8; 32],
+		};
+		let approval = approve(&request, &operator(1));
+		let foreign_approval = approve(&request, &operator(4));
+		let mut ceremony = ApprovedKeyCeremony {
+			request: request.clone(),
+			approvals: vec![approval.clone(), approval, foreign_approval],
+		};
+		assert!(!ceremony.is_approved_by(&quorum));
+
+		ceremony.approvals.push(approve(&request, &operator(3)));
+		assert!(ceremony.is_approved_by(&quorum));
+
+		ceremony.request.ceremony_id = 2;
+		assert!(!ceremony.is_approved_by(&quorum));
+
+		ceremony.request.ceremony_id = 1;
+		ceremony.request.nonce = [8u8; 32];
+		assert!(!ceremony.is_approved_by(&quorum));
+	}
+
+	#[test]
+	fn quorum_validity_works() {
+		let operators: Vec<AccountId> =
+			(1..=2).map(|seed| operator(seed).public().into()).collect();
+		assert!(OperatorQuorum { operators: operators.clone(), threshold: 2 }.is_valid());
+		assert!(!OperatorQuorum { operators: operators.clone(), threshold: 3 }.is_valid());
+		assert!(!OperatorQuorum { operators: operators.clone(), threshold: 0 }.is_valid());
+		let duplicated = vec![operators[0].clone(), operators[0].clone()];
+		assert!(!OperatorQuorum { operators: duplicated, threshold: 2 }.is_valid());
+	}
+}
@@ -22,8 +22,24 @@ use crate::storage::StorageEntry;
 use codec::{Decode, Encode};
 use sp_std::vec::Vec;
 
+pub mod account_watch;
+pub mod canary;
+pub mod key_ceremony;
+pub mod load_shedding;
+pub mod maintenance_window;
+pub mod panic_policy;
 pub mod parentchain;
+pub mod payload_quarantine;
+pub mod rpc_authorization;
+pub mod shard_export;
+pub mod shard_routing;
+pub mod sla_metrics;
+pub mod smoke_test;
+pub mod state_backup;
+pub mod state_diff;
 pub mod storage;
+pub mod time_lock;
+pub mod worker_command;
 
 /// Substrate runtimes provide no string type. Hence, for arbitrary data of varying length the
 /// `Vec<u8>` is used. In the polkadot-js the typedef `Text` is used to automatically
@@ -46,9 +62,12 @@ pub type ConfirmCallFn = ([u8; 2], ShardIdentifier, H256, Vec<u8>);
 pub type ShieldFundsFn = ([u8; 2], ShardIdentifier, Vec<u8>, Balance);
 pub type CallWorkerFn = ([u8; 2], Request);
 
-use enclave_bridge_primitives::ShardSignerStatus as ShardSignerStatusGen;
+use enclave_bridge_primitives::{
+	ShardSignerStatus as ShardSignerStatusGen, UpgradableShardConfig as UpgradableShardConfigGen,
+};
 pub type ShardSignerStatus = ShardSignerStatusGen<AccountId, BlockNumber>;
 pub type ShardStatus = Vec<ShardSignerStatus>;
+pub type UpgradableShardConfig = UpgradableShardConfigGen<AccountId, BlockNumber>;
 pub use enclave_bridge_primitives::Request;
 pub use teerex_primitives::{
 	EnclaveFingerprint, MultiEnclave, SgxBuildMode, SgxEnclave, SgxReportData, SgxStatus,
@@ -80,6 +99,8 @@ pub enum DirectRequestStatus {
 	TrustedOperationStatus(TrustedOperationStatus),
 	/// Direct request could not be executed
 	Error,
+	/// The shard is served by another worker, the value is its [`shard_routing::ShardRoute`]
+	Redirect,
 }
 
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
@@ -108,6 +129,9 @@ pub enum TrustedOperationStatus {
 	Dropped,
 	/// TrustedOperation is no longer valid in the current state.
 	Invalid,
+	/// TrustedOperation stays in the pool, but is only executed after the maintenance window
+	/// of the shard.
+	Deferred,
 }
 
 #[derive(Encode, Decode, Clone, Debug, PartialEq)]
@@ -0,0 +1,51 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Policy of the load shedding under sustained overload.
+//!
+//! The actions are activated one after the other in the configured order while the worker stays
+//! overloaded, and deactivated in reverse order once it has recovered.
+
+use crate::ShardIdentifier;
+use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SheddingAction {
+	/// Refuse public getters, which are not signed by any account.
+	ShedAnonymousGetters,
+	/// Limit the number of trusted operations submitted over the direct RPC per slot.
+	RateLimitSubmissions,
+	/// Defer the execution of the trusted calls of the low priority shards.
+	PauseLowPriorityShards,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadSheddingPolicy {
+	/// Actions in the order they are activated.
+	pub actions: Vec<SheddingAction>,
+	/// Number of consecutive overloaded slots after which the next action is activated.
+	pub escalate_after: u32,
+	/// Number of consecutive healthy slots after which the last active action is deactivated.
+	pub recover_after: u32,
+	/// Share of the EPC in percent from which on the enclave counts as overloaded.
+	pub epc_pressure_percent: u8,
+	/// Trusted operations accepted per slot while submissions are rate limited.
+	pub max_submissions_per_slot: u32,
+	pub low_priority_shards: Vec<ShardIdentifier>,
+}
@@ -0,0 +1,37 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Maintenance windows of a shard.
+//!
+//! During a window, trusted calls are still accepted into the pool, but their execution is
+//! deferred until the window has ended.
+
+use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Period in unix milliseconds, the start is inclusive and the end exclusive.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+	pub start: u64,
+	pub end: u64,
+}
+
+impl MaintenanceWindow {
+	pub fn contains(&self, now: u64) -> bool {
+		self.start <= now && now < self.end
+	}
+}
@@ -0,0 +1,32 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+//! Policy of the enclave when the processing of a shard fails with an unrecoverable error.
+//!
+//! Unrecoverable are the errors that would recur in every slot, e.g. a state that cannot be
+//! decoded or decrypted, and panics.
+
+use codec::{Decode, Encode};
+
+#[derive(Encode, Decode, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+	/// Fail the whole enclave, a panic leaves it unusable until the worker is restarted.
+	#[default]
+	Halt,
+	/// Disable only the failing shard until the worker is restarted, the other shards are still
+	/// served.
+	IsolateShard,
+}
@@ -91,6 +91,20 @@ pub trait FilterEvents {
 	fn get_extrinsic_statuses(&self) -> core::result::Result<Vec<ExtrinsicStatus>, Self::Error>;
 
 	fn get_transfer_events(&self) -> core::result::Result<Vec<BalanceTransfer>, Self::Error>;
+
+	/// Only the Integritee parentchain announces enclave upgrades.
+	fn get_scheduled_enclave_upgrades(
+		&self,
+	) -> core::result::Result<Vec<EnclaveUpgradeScheduled>, Self::Error> {
+		Ok(Vec::new())
+	}
+
+	/// Only the Integritee parentchain schedules state migrations.
+	fn get_scheduled_state_migrations(
+		&self,
+	) -> core::result::Result<Vec<StateMigrationScheduled>, Self::Error> {
+		Ok(Vec::new())
+	}
 }
 
 #[derive(Encode, Decode, Debug)]
@@ -231,6 +245,56 @@ impl StaticEvent for crate::parentchain::ExchangeRateUpdated {
 	const EVENT: &'static str = "ExchangeRateUpdated";
 }
 
+#[derive(Encode, Decode, Debug)]
+pub struct EnclaveUpgradeScheduled {
+	pub shard: ShardIdentifier,
+	pub mrenclave: [u8; 32],
+	/// First block at which the enclave with the new MRENCLAVE authors sidechain blocks.
+	pub activation_block: BlockNumber,
+}
+
+impl core::fmt::Display for crate::parentchain::EnclaveUpgradeScheduled {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let message = format!(
+			"EnclaveUpgradeScheduled :: shard: {}, mrenclave: {:?}, activation block: {}",
+			self.shard, self.mrenclave, self.activation_block
+		);
+		write!(f, "{}", message)
+	}
+}
+
+impl StaticEvent for crate::parentchain::EnclaveUpgradeScheduled {
+	const PALLET: &'static str = "EnclaveBridge";
+	const EVENT: &'static str = "EnclaveUpgradeScheduled";
+}
+
+/// Emitted by the bridge pallet for its governance origin only.
+#[derive(Encode, Decode, Debug)]
+pub struct StateMigrationScheduled {
+	pub shard: ShardIdentifier,
+	/// Name of the migration routine registered in the STF.
+	pub migration: Vec<u8>,
+	/// First Integritee parentchain block at which the migration is run.
+	pub activation_block: BlockNumber,
+}
+
+impl core::fmt::Display for crate::parentchain::StateMigrationScheduled {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let message = format!(
+			"StateMigrationScheduled :: shard: {}, migration: {}, activation block: {}",
+			self.shard,
+			alloc::string::String::from_utf8_lossy(&self.migration),
+			self.activation_block
+		);
+		write!(f, "{}", message)
+	}
+}
+
+impl StaticEvent for crate::parentchain::StateMigrationScheduled {
+	const PALLET: &'static str = "EnclaveBridge";
+	const EVENT: &'static str = "StateMigrationScheduled";
+}
+
 pub trait HandleParentchainEvents<Executor, TCS, Error>
 where
 	Executor: IndirectExecutor<TCS, Error>,
@@ -247,6 +311,8 @@ where
 pub enum ParentchainError {
 	ShieldFundsFailure,
 	FunctionalityDisabled,
+	EnclaveUpgradeFailure,
+	StateMigrationFailure,
 }
 
 impl core::fmt::Display for ParentchainError {
@@ -254,6 +320,8 @@ impl core::fmt::Display for ParentchainError {
 		let message = match &self {
 			ParentchainError::ShieldFundsFailure => "Parentchain Error: ShieldFundsFailure",
 			ParentchainError::FunctionalityDisabled => "Parentchain Error: FunctionalityDisabled",
+			ParentchainError::EnclaveUpgradeFailure => "Parentchain Error: EnclaveUpgradeFailure",
+			ParentchainError::StateMigrationFailure => "Parentchain Error: StateMigrationFailure",
 		};
 		write!(f, "{}", message)
 	}
@@ -0,0 +1,75 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Archive of the raw shielded payloads that the enclave rejected, for forensics.
+//!
+//! The enclave keeps the payloads sealed and bounded in size, dropping the oldest ones first.
+//! The archive can only be exported with a request signed by an owner of the shard on the
+//! parentchain, and is encrypted to an ephemeral key of the requester. A replayed request is
+//! harmless, because only the holder of the ephemeral secret can decrypt the export.
+
+use crate::{AccountId, ShardIdentifier, Signature};
+use codec::{Decode, Encode};
+use sp_runtime::traits::Verify;
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuarantineReason {
+	/// The payload could not be decrypted with the shielding key.
+	Undecryptable,
+	/// The decrypted payload is not a valid trusted operation.
+	Undecodable,
+	/// The trusted operation is not supported by this worker.
+	Unsupported,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct QuarantinedPayload {
+	pub shard: ShardIdentifier,
+	pub reason: QuarantineReason,
+	/// The payload as it was received, still shielded.
+	pub payload: Vec<u8>,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct QuarantineExportRequest {
+	pub shard: ShardIdentifier,
+	/// Ephemeral X25519 public key of the requester, the export is encrypted to it.
+	pub recipient: [u8; 32],
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedQuarantineExportRequest {
+	pub request: QuarantineExportRequest,
+	pub signer: AccountId,
+	pub signature: Signature,
+}
+
+impl SignedQuarantineExportRequest {
+	pub fn verify_signature(&self) -> bool {
+		self.signature.verify(self.request.encode().as_slice(), &self.signer)
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedQuarantineExport {
+	/// Ephemeral X25519 public key of the enclave.
+	pub enclave_public: [u8; 32],
+	/// Encoded `Vec<QuarantinedPayload>`, encrypted with the response key of sequence 0 of
+	/// the session key agreed on with the requester.
+	pub ciphertext: Vec<u8>,
+}
@@ -0,0 +1,82 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Signed authorizations of RPCs that grant an account more than a single request, like a
+//! getter session or a nonce reservation.
+//!
+//! An authorization names the RPC it is for, carries a random nonce and expires at a sidechain
+//! block of its shard, because the enclave can't trust the time of the host. The enclave accepts
+//! every authorization only once, so a replayed one doesn't grant anything.
+
+use crate::{AccountId, ShardIdentifier, Signature};
+use codec::{Decode, Encode};
+use sp_runtime::traits::Verify;
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthorizedRpc {
+	OpenGetterSession,
+	ReserveNonce,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct RpcAuthorization {
+	pub rpc: AuthorizedRpc,
+	pub shard: ShardIdentifier,
+	pub account: AccountId,
+	/// Random, an authorization with the same nonce is accepted only once.
+	pub nonce: [u8; 32],
+	/// Sidechain block of the shard at which the authorization and what it granted expire.
+	pub expires_at_block: u64,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedRpcAuthorization {
+	pub authorization: RpcAuthorization,
+	pub signature: Signature,
+}
+
+impl SignedRpcAuthorization {
+	/// True if the account of the authorization has signed it.
+	pub fn verify_signature(&self) -> bool {
+		self.signature
+			.verify(self.authorization.encode().as_slice(), &self.authorization.account)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{sr25519, Pair};
+
+	#[test]
+	fn authorization_is_only_valid_as_signed() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let authorization = RpcAuthorization {
+			rpc: AuthorizedRpc::OpenGetterSession,
+			shard: ShardIdentifier::repeat_byte(1),
+			account: alice.public().into(),
+			nonce: [7u8; 32],
+			expires_at_block: 100,
+		};
+		let signature = alice.sign(&authorization.encode()).into();
+		let mut signed = SignedRpcAuthorization { authorization, signature };
+		assert!(signed.verify_signature());
+
+		signed.authorization.rpc = AuthorizedRpc::ReserveNonce;
+		assert!(!signed.verify_signature());
+	}
+}
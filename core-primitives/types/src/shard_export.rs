@@ -0,0 +1,122 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+//! Export of the state of a shard to its owner, the exit path of a tenant off the worker.
+//!
+//! The owners of a shard are the authorities of its active shard config in the enclave bridge
+//! pallet of the Integritee parentchain. The export must be requested with a request signed by one
+//! of them and is encrypted to an X25519 key of the owner's choice, so neither the operator of the
+//! worker nor a replayed request can read the state.
+//!
+//! # Container format
+//!
+//! An export file is the concatenation of
+//! 1. the plaintext SCALE encoded [`SignedShardExportHeader`], signed with the enclave signing
+//!    key,
+//! 2. the encoded state of the shard, encrypted with AES-128-OFB.
+//!
+//! The state is encoded as the postcard serialization of its raw storage entries: the number of
+//! entries, followed by every key and value ordered by key, each prefixed with its length. All
+//! numbers are unsigned LEB128 varints.
+//!
+//! The key and the initialization vector of the cipher are the response key of sequence 0 of the
+//! session key agreed on between [`ShardExportHeader::enclave_public`] and the recipient key of
+//! the request, see `itp_sgx_crypto::session_key`. The owner verifies the decrypted state by
+//! hashing it with the state hash algorithm of the shard, blake2b-256 by default, and comparing
+//! the result with [`ShardExportHeader::state_hash`].
+
+use crate::{AccountId, ShardIdentifier, Signature, H256};
+use codec::{Decode, Encode};
+use sp_core::{ed25519, Pair};
+use sp_runtime::traits::Verify;
+
+/// Version of the container format, increased with every incompatible change.
+pub const SHARD_EXPORT_VERSION: u16 = 1;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ShardExportRequest {
+	pub shard: ShardIdentifier,
+	/// X25519 public key of the owner, the export is encrypted to it.
+	pub recipient: [u8; 32],
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedShardExportRequest {
+	pub request: ShardExportRequest,
+	pub signer: AccountId,
+	pub signature: Signature,
+}
+
+impl SignedShardExportRequest {
+	pub fn verify_signature(&self) -> bool {
+		self.signature.verify(self.request.encode().as_slice(), &self.signer)
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ShardExportHeader {
+	pub version: u16,
+	pub shard: ShardIdentifier,
+	/// Root account of the shard that requested the export.
+	pub owner: AccountId,
+	/// Unix time in milliseconds at the start of the export.
+	pub timestamp: u64,
+	/// Ephemeral X25519 public key of the enclave.
+	pub enclave_public: [u8; 32],
+	/// Hash of the exported state, as committed to in the sidechain blocks.
+	pub state_hash: H256,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedShardExportHeader {
+	pub header: ShardExportHeader,
+	pub signature: ed25519::Signature,
+}
+
+impl SignedShardExportHeader {
+	pub fn new(header: ShardExportHeader, signer: &ed25519::Pair) -> Self {
+		let signature = signer.sign(&header.encode());
+		SignedShardExportHeader { header, signature }
+	}
+
+	pub fn verify_signature(&self, signer: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(&self.signature, self.header.encode(), signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn signed_header_verifies_only_with_unmodified_header() {
+		let signer = ed25519::Pair::from_seed(&[1u8; 32]);
+		let header = ShardExportHeader {
+			version: SHARD_EXPORT_VERSION,
+			shard: ShardIdentifier::repeat_byte(2),
+			owner: AccountId::new([3u8; 32]),
+			timestamp: 4,
+			enclave_public: [5u8; 32],
+			state_hash: H256::repeat_byte(6),
+		};
+
+		let mut signed_header = SignedShardExportHeader::new(header, &signer);
+		assert!(signed_header.verify_signature(&signer.public()));
+
+		signed_header.header.state_hash = H256::repeat_byte(7);
+		assert!(!signed_header.verify_signature(&signer.public()));
+	}
+}
@@ -0,0 +1,30 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Routes to the worker instances serving the shards this worker does not serve.
+
+use crate::ShardIdentifier;
+use codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+/// Value of a [`DirectRequestStatus::Redirect`](crate::DirectRequestStatus::Redirect) response.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ShardRoute {
+	pub shard: ShardIdentifier,
+	/// Trusted RPC url of the worker serving the shard, utf8 encoded.
+	pub url: Vec<u8>,
+}
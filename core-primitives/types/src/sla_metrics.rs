@@ -0,0 +1,101 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Metrics digest a validateer operator hands to the shard owner, or an SLA contract, to prove
+//! the service level of its enclave.
+//!
+//! The enclave closes a digest at the end of every period and signs it with its signing key,
+//! which is registered on the parentchain, so the metrics can be verified without trusting the
+//! operator. Time is measured in the numbers of the parentchain blocks the enclave has imported,
+//! because the host controls the clock of the enclave, but can't forge parentchain blocks.
+
+use crate::ShardIdentifier;
+use codec::{Decode, Encode};
+use sp_core::{ed25519, Pair};
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SlaMetrics {
+	pub mrenclave: [u8; 32],
+	pub shard: ShardIdentifier,
+	/// Increases with every digest since the enclave has been started, a gap reveals a dropped
+	/// digest.
+	pub sequence: u64,
+	/// Parentchain block of the first slot after the enclave has been started.
+	pub enclave_started_at_block: u64,
+	/// Parentchain block of the first slot of the period.
+	pub period_start_block: u64,
+	/// Parentchain block at which the period has been closed.
+	pub period_end_block: u64,
+	/// Number of slots the enclave has run in the period. The slots the host hasn't run at all
+	/// show up as fewer slots than fit into the parentchain blocks of the period.
+	pub slots: u64,
+	pub blocks_authored: u64,
+	/// Number of slots without a produced block, because the deadline has been missed, the
+	/// shard was stale or authoring failed.
+	pub missed_slots: u64,
+}
+
+impl SlaMetrics {
+	/// Parentchain blocks the enclave has been running at the end of the period.
+	pub fn uptime_blocks(&self) -> u64 {
+		self.period_end_block.saturating_sub(self.enclave_started_at_block)
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedSlaMetrics {
+	pub metrics: SlaMetrics,
+	pub signature: ed25519::Signature,
+}
+
+impl SignedSlaMetrics {
+	pub fn new(metrics: SlaMetrics, signer: &ed25519::Pair) -> Self {
+		let signature = signer.sign(&metrics.encode());
+		SignedSlaMetrics { metrics, signature }
+	}
+
+	pub fn verify_signature(&self, signer: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(&self.signature, self.metrics.encode(), signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn signed_metrics_verify_only_with_unmodified_metrics() {
+		let signer = ed25519::Pair::from_seed(&[1u8; 32]);
+		let metrics = SlaMetrics {
+			mrenclave: [2u8; 32],
+			shard: ShardIdentifier::repeat_byte(3),
+			sequence: 0,
+			enclave_started_at_block: 100,
+			period_start_block: 100,
+			period_end_block: 500,
+			slots: 4,
+			blocks_authored: 2,
+			missed_slots: 1,
+		};
+		assert_eq!(metrics.uptime_blocks(), 400);
+
+		let mut signed_metrics = SignedSlaMetrics::new(metrics, &signer);
+		assert!(signed_metrics.verify_signature(&signer.public()));
+		signed_metrics.metrics.missed_slots = 0;
+		assert!(!signed_metrics.verify_signature(&signer.public()));
+	}
+}
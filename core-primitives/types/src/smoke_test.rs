@@ -0,0 +1,111 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Report of the smoke test an operator runs against a fresh deployment before registering it.
+//!
+//! The enclave runs a miniature end-to-end flow against a throwaway shard and signs the outcome
+//! of every step with its signing key.
+
+use codec::{Decode, Encode};
+use sp_core::{ed25519, Pair};
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmokeTestStep {
+	/// Initialize a state and seal it to a new shard.
+	InitShard,
+	/// Sign, verify and execute a balance transfer, then seal the updated state.
+	Transfer,
+	/// Sign, verify and execute a balance getter on the unsealed state.
+	Getter,
+	/// Remove the shard again.
+	PurgeShard,
+}
+
+impl SmokeTestStep {
+	pub const ALL: [SmokeTestStep; 4] = [
+		SmokeTestStep::InitShard,
+		SmokeTestStep::Transfer,
+		SmokeTestStep::Getter,
+		SmokeTestStep::PurgeShard,
+	];
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SmokeTestReport {
+	pub mrenclave: [u8; 32],
+	/// Unix time in milliseconds at the start of the test.
+	pub timestamp: u64,
+	/// Outcome of every step that was run, with the UTF-8 encoded error of a failed step. The
+	/// steps after a failure are skipped, except for the purge.
+	pub outcomes: Vec<(SmokeTestStep, Result<(), Vec<u8>>)>,
+}
+
+impl SmokeTestReport {
+	/// Whether all steps have been run and passed.
+	pub fn passed(&self) -> bool {
+		self.outcomes.len() == SmokeTestStep::ALL.len()
+			&& self
+				.outcomes
+				.iter()
+				.zip(SmokeTestStep::ALL.iter())
+				.all(|((step, outcome), expected)| step == expected && outcome.is_ok())
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedSmokeTestReport {
+	pub report: SmokeTestReport,
+	pub signature: ed25519::Signature,
+}
+
+impl SignedSmokeTestReport {
+	pub fn new(report: SmokeTestReport, signer: &ed25519::Pair) -> Self {
+		let signature = signer.sign(&report.encode());
+		SignedSmokeTestReport { report, signature }
+	}
+
+	pub fn verify_signature(&self, signer: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(&self.signature, self.report.encode(), signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn report_only_passes_if_every_step_passed() {
+		let signer = ed25519::Pair::from_seed(&[1u8; 32]);
+		let mut report = SmokeTestReport {
+			mrenclave: [2u8; 32],
+			timestamp: 1_000,
+			outcomes: SmokeTestStep::ALL.iter().map(|step| (*step, Ok(()))).collect(),
+		};
+		assert!(report.passed());
+
+		report.outcomes[1].1 = Err(b"invalid nonce".to_vec());
+		assert!(!report.passed());
+		report.outcomes.truncate(1);
+		assert!(!report.passed());
+
+		let mut signed_report = SignedSmokeTestReport::new(report, &signer);
+		assert!(signed_report.verify_signature(&signer.public()));
+		signed_report.report.timestamp = 1_001;
+		assert!(!signed_report.verify_signature(&signer.public()));
+	}
+}
@@ -0,0 +1,48 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Backup of the state of a shard, written by the enclave in bounded chunks.
+//!
+//! A backup file starts with the plaintext SCALE encoded [`StateBackupHeader`], followed by the
+//! SCALE encoded state, split into chunks. Every chunk is SCALE encoded as a `bool` that is only
+//! true for the last chunk and an `itp_sgx_crypto::aead::AeadCiphertext`, encrypted under a
+//! random nonce with the state key derived for [`StateBackupHeader::key_context`] and
+//! [`StateBackupHeader::chunk_aad`] as associated data. The last chunk is empty, so a modified,
+//! reordered or truncated backup fails to restore.
+
+use crate::ShardIdentifier;
+use codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct StateBackupHeader {
+	pub shard: ShardIdentifier,
+	/// Random, every backup is encrypted with its own key.
+	pub backup_id: [u8; 32],
+}
+
+impl StateBackupHeader {
+	/// Context of the key derivation.
+	pub fn key_context(&self) -> Vec<u8> {
+		(b"state_backup", self.shard, self.backup_id).encode()
+	}
+
+	/// Associated data of the chunk at `index`, which binds it to the backup and its position.
+	pub fn chunk_aad(&self, index: u64, is_last: bool) -> Vec<u8> {
+		(self, index, is_last).encode()
+	}
+}
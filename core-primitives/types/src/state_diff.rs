@@ -0,0 +1,168 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Difference between two state snapshots of a shard, for operators investigating anomalies.
+//!
+//! The enclave compares the snapshots and reveals only the hashes of the changed keys and the
+//! sizes of their values, never the keys or values themselves. The keys are hashed with a salt
+//! only known to the enclave, otherwise the hashes of keys containing an account id could be
+//! matched against the hashes of the keys of known accounts.
+
+use crate::ShardIdentifier;
+use codec::{Decode, Encode};
+use sp_core::{hashing::blake2_256, H256};
+use sp_std::{cmp::Reverse, collections::btree_map::BTreeMap, vec::Vec};
+
+/// Salt of the key hashes, known only to the enclave.
+pub type KeyHashSalt = [u8; 32];
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateDiffRequest {
+	pub shard: ShardIdentifier,
+	/// Id of the older snapshot, as in the name of its state file.
+	pub from: u128,
+	/// Id of the newer snapshot.
+	pub to: u128,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct KeyChange {
+	/// Blake2b-256 hash of the salt followed by the storage key.
+	pub key_hash: H256,
+	/// Size of the value in the older snapshot, none if the key was added.
+	pub from_size: Option<u32>,
+	/// Size of the value in the newer snapshot, none if the key was removed.
+	pub to_size: Option<u32>,
+}
+
+impl KeyChange {
+	pub fn size_delta(&self) -> i64 {
+		i64::from(self.to_size.unwrap_or_default()) - i64::from(self.from_size.unwrap_or_default())
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiff {
+	pub added: u32,
+	pub removed: u32,
+	pub modified: u32,
+	/// Total size of the values of the newer snapshot minus the one of the older snapshot.
+	pub size_delta: i64,
+	/// Changed keys with the largest size change first, at most the number requested.
+	pub changes: Vec<KeyChange>,
+}
+
+impl StateDiff {
+	/// Compares the key-value pairs of two snapshots, keeping the `max_changes` largest changes.
+	pub fn between(
+		from: &BTreeMap<Vec<u8>, Vec<u8>>,
+		to: &BTreeMap<Vec<u8>, Vec<u8>>,
+		max_changes: usize,
+		salt: &KeyHashSalt,
+	) -> Self {
+		let mut diff = StateDiff::default();
+		let mut changes = Vec::new();
+		for (key, value) in from {
+			match to.get(key) {
+				Some(new_value) if new_value == value => continue,
+				Some(_) => diff.modified += 1,
+				None => diff.removed += 1,
+			}
+			changes.push(key_change(salt, key, Some(value), to.get(key)));
+		}
+		for (key, value) in to.iter().filter(|(key, _)| !from.contains_key(*key)) {
+			diff.added += 1;
+			changes.push(key_change(salt, key, None, Some(value)));
+		}
+
+		diff.size_delta = changes.iter().map(KeyChange::size_delta).sum();
+		changes.sort_by_key(|change| Reverse(change.size_delta().unsigned_abs()));
+		changes.truncate(max_changes);
+		diff.changes = changes;
+		diff
+	}
+}
+
+fn key_change(
+	salt: &KeyHashSalt,
+	key: &[u8],
+	from: Option<&Vec<u8>>,
+	to: Option<&Vec<u8>>,
+) -> KeyChange {
+	KeyChange {
+		key_hash: key_hash(salt, key),
+		from_size: from.map(|value| value.len() as u32),
+		to_size: to.map(|value| value.len() as u32),
+	}
+}
+
+fn key_hash(salt: &KeyHashSalt, key: &[u8]) -> H256 {
+	blake2_256(&[&salt[..], key].concat()).into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SALT: KeyHashSalt = [7u8; 32];
+
+	#[test]
+	fn diff_counts_all_changes_but_keeps_only_the_largest() {
+		let from = BTreeMap::from([
+			(b"unchanged".to_vec(), vec![1u8; 4]),
+			(b"modified".to_vec(), vec![1u8; 4]),
+			(b"removed".to_vec(), vec![1u8; 10]),
+		]);
+		let to = BTreeMap::from([
+			(b"unchanged".to_vec(), vec![1u8; 4]),
+			(b"modified".to_vec(), vec![2u8; 5]),
+			(b"added".to_vec(), vec![1u8; 3]),
+		]);
+
+		let diff = StateDiff::between(&from, &to, 2, &SALT);
+
+		assert_eq!((diff.added, diff.removed, diff.modified), (1, 1, 1));
+		assert_eq!(diff.size_delta, 1 - 10 + 3);
+		assert_eq!(
+			diff.changes,
+			vec![
+				KeyChange {
+					key_hash: key_hash(&SALT, b"removed"),
+					from_size: Some(10),
+					to_size: None
+				},
+				KeyChange {
+					key_hash: key_hash(&SALT, b"added"),
+					from_size: None,
+					to_size: Some(3)
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn key_hashes_depend_on_the_salt() {
+		let from = BTreeMap::new();
+		let to = BTreeMap::from([(b"account".to_vec(), vec![1u8; 4])]);
+
+		let diff = StateDiff::between(&from, &to, 1, &SALT);
+		let other_diff = StateDiff::between(&from, &to, 1, &[8u8; 32]);
+
+		assert_ne!(diff.changes[0].key_hash, other_diff.changes[0].key_hash);
+		assert_ne!(diff.changes[0].key_hash, H256::from(blake2_256(b"account")));
+	}
+}
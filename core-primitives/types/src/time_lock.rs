@@ -0,0 +1,45 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Getter results that are time-locked until a sidechain block.
+//!
+//! The enclave encrypts the result with AES-128-GCM under a random nonce and a key it derives
+//! from the shard's state key and the release block. The key context is the associated data, so
+//! a result doesn't decrypt as the one of another shard or release block. The enclave only hands
+//! out the key once the shard's state has reached the release block, so a result can be published before its content may be known, e.g. for commit-reveal
+//! schemes or embargoed reports. A rotation of the state key also rotates the time lock keys,
+//! so results should be released before the state key is rotated.
+
+use crate::ShardIdentifier;
+use codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+/// Context of the key derivation of the time lock key of a sidechain block.
+pub fn time_lock_key_context(shard: &ShardIdentifier, release_block: u64) -> Vec<u8> {
+	(b"time_lock", shard, release_block).encode()
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct TimeLockedValue {
+	/// Sidechain block number from which on the key is released.
+	pub release_block: u64,
+	/// Nonce the result has been encrypted with.
+	pub nonce: [u8; 12],
+	/// Encoded getter result and authentication tag, encrypted with the time lock key of the
+	/// release block.
+	pub ciphertext: Vec<u8>,
+}
@@ -0,0 +1,92 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Commands the enclave asks the untrusted worker service to execute.
+//!
+//! Every command is signed with the enclave signing key together with a sequence number, so the
+//! service can verify that it originates from its enclave and has not been replayed.
+
+use crate::parentchain::ParentchainId;
+use codec::{Decode, Encode};
+use sp_core::{ed25519, Pair};
+use sp_runtime::OpaqueExtrinsic;
+use sp_std::vec::Vec;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum WorkerCommand<SignedSidechainBlock> {
+	/// Submit extrinsics to a parentchain.
+	SubmitExtrinsics {
+		parentchain_id: ParentchainId,
+		extrinsics: Vec<OpaqueExtrinsic>,
+		await_each_inclusion: bool,
+	},
+	/// Gossip sidechain blocks to the peer workers.
+	ProposeSidechainBlocks(Vec<SignedSidechainBlock>),
+	/// Write a file to IPFS, responds with the IPFS content id.
+	WriteIpfs(Vec<u8>),
+}
+
+impl<SignedSidechainBlock> WorkerCommand<SignedSidechainBlock> {
+	/// Name of the command, e.g. to label logs and metrics.
+	pub fn name(&self) -> &'static str {
+		match self {
+			WorkerCommand::SubmitExtrinsics { .. } => "submit_extrinsics",
+			WorkerCommand::ProposeSidechainBlocks(_) => "propose_sidechain_blocks",
+			WorkerCommand::WriteIpfs(_) => "write_ipfs",
+		}
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedWorkerCommand<SignedSidechainBlock> {
+	pub command: WorkerCommand<SignedSidechainBlock>,
+	/// Increases with every command issued by the enclave.
+	pub sequence: u64,
+	pub signature: ed25519::Signature,
+}
+
+impl<SignedSidechainBlock: Encode> SignedWorkerCommand<SignedSidechainBlock> {
+	pub fn new(
+		command: WorkerCommand<SignedSidechainBlock>,
+		sequence: u64,
+		signer: &ed25519::Pair,
+	) -> Self {
+		let signature = signer.sign(&(&command, sequence).encode());
+		SignedWorkerCommand { command, sequence, signature }
+	}
+
+	pub fn verify_signature(&self, signer: &ed25519::Public) -> bool {
+		ed25519::Pair::verify(&self.signature, (&self.command, self.sequence).encode(), signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn signed_command_verifies_only_with_unmodified_payload() {
+		let signer = ed25519::Pair::from_seed(&[1u8; 32]);
+		let command = WorkerCommand::<()>::WriteIpfs(vec![1, 2, 3]);
+
+		let mut signed_command = SignedWorkerCommand::new(command, 7, &signer);
+		assert!(signed_command.verify_signature(&signer.public()));
+
+		signed_command.sequence = 8;
+		assert!(!signed_command.verify_signature(&signer.public()));
+	}
+}
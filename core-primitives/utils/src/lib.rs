@@ -25,6 +25,7 @@ pub mod buffer;
 pub mod error;
 pub mod hex;
 pub mod hex_display;
+pub mod stream_encoder;
 pub mod stringify;
 
 // Public re-exports.
@@ -0,0 +1,148 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! SCALE encoding into bounded chunks.
+//!
+//! Large values, e.g. the state of a shard, are encoded into a buffer of fixed size that is
+//! handed to a [`WriteChunk`] whenever it is full, so the encoding is never materialized in one
+//! piece.
+
+use alloc::vec::Vec;
+use codec::{Encode, Output};
+
+/// Receives the encoded bytes one chunk at a time.
+pub trait WriteChunk {
+	type Error;
+
+	fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// [`Output`] that buffers at most `chunk_size` bytes before writing them to the chunk writer.
+///
+/// [`Output`] cannot fail, so the first error of the chunk writer is kept and returned by
+/// [`StreamEncoder::finish`], everything written afterwards is dropped.
+pub struct StreamEncoder<W: WriteChunk> {
+	writer: W,
+	chunk_size: usize,
+	buffer: Vec<u8>,
+	bytes_written: usize,
+	error: Option<W::Error>,
+}
+
+impl<W: WriteChunk> StreamEncoder<W> {
+	pub fn new(writer: W, chunk_size: usize) -> Self {
+		let chunk_size = chunk_size.max(1);
+		StreamEncoder {
+			writer,
+			chunk_size,
+			buffer: Vec::with_capacity(chunk_size),
+			bytes_written: 0,
+			error: None,
+		}
+	}
+
+	/// Encodes the value and writes the remaining buffered bytes, see [`StreamEncoder::finish`].
+	pub fn encode<E: Encode + ?Sized>(
+		writer: W,
+		chunk_size: usize,
+		value: &E,
+	) -> Result<usize, W::Error> {
+		let mut encoder = Self::new(writer, chunk_size);
+		value.encode_to(&mut encoder);
+		encoder.finish()
+	}
+
+	/// Writes the buffered bytes and returns the total number of written bytes.
+	pub fn finish(mut self) -> Result<usize, W::Error> {
+		self.flush();
+		match self.error {
+			Some(e) => Err(e),
+			None => Ok(self.bytes_written),
+		}
+	}
+
+	fn flush(&mut self) {
+		if self.error.is_some() || self.buffer.is_empty() {
+			return
+		}
+		match self.writer.write_chunk(&self.buffer) {
+			Ok(()) => self.bytes_written += self.buffer.len(),
+			Err(e) => self.error = Some(e),
+		}
+		self.buffer.clear();
+	}
+}
+
+impl<W: WriteChunk> Output for StreamEncoder<W> {
+	fn write(&mut self, mut bytes: &[u8]) {
+		while !bytes.is_empty() && self.error.is_none() {
+			let free = self.chunk_size - self.buffer.len();
+			let (head, tail) = bytes.split_at(free.min(bytes.len()));
+			self.buffer.extend_from_slice(head);
+			bytes = tail;
+			if self.buffer.len() == self.chunk_size {
+				self.flush();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use alloc::{vec, vec::Vec};
+
+	#[derive(Default)]
+	struct ChunkCollector {
+		chunks: Vec<Vec<u8>>,
+		fail_after: Option<usize>,
+	}
+
+	impl WriteChunk for &mut ChunkCollector {
+		type Error = ();
+
+		fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), ()> {
+			if self.fail_after == Some(self.chunks.len()) {
+				return Err(())
+			}
+			self.chunks.push(chunk.to_vec());
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn chunks_are_bounded_and_concatenate_to_the_encoding() {
+		let value = (vec![7u8; 1000], vec![1u64, 2, 3], 42u32);
+		let mut collector = ChunkCollector::default();
+
+		let bytes_written = StreamEncoder::encode(&mut collector, 64, &value).unwrap();
+
+		assert_eq!(bytes_written, value.encode().len());
+		assert!(collector.chunks.iter().all(|chunk| chunk.len() <= 64));
+		assert_eq!(collector.chunks.concat(), value.encode());
+	}
+
+	#[test]
+	fn first_write_error_is_returned() {
+		let mut collector = ChunkCollector { fail_after: Some(2), ..Default::default() };
+
+		let result = StreamEncoder::encode(&mut collector, 16, &vec![1u8; 100]);
+
+		assert_eq!(result, Err(()));
+		assert_eq!(collector.chunks.len(), 2);
+	}
+}
@@ -53,6 +53,7 @@ mod builders;
 pub mod response_channel;
 pub mod rpc_connection_registry;
 pub mod rpc_responder;
+pub mod rpc_session;
 pub mod rpc_watch_extractor;
 pub mod rpc_ws_handler;
 
@@ -61,6 +62,8 @@ pub mod rpc_ws_handler;
 pub enum DirectRpcError {
 	#[error("Invalid connection hash")]
 	InvalidConnectionHash,
+	#[error("No session has been opened on the connection")]
+	NoSession,
 	#[error("RPC serialization error: {0}")]
 	SerializationError(SerdeJsonError),
 	#[error("Web socket error: {0}")]
@@ -80,11 +83,45 @@ impl<T: std::hash::Hash + traits::Member + Encode> RpcHash for T {}
 /// Registry for RPC connections (i.e. connections that are kept alive to send updates).
 pub trait RpcConnectionRegistry: Send + Sync {
 	type Hash: RpcHash;
-	type Connection: Copy + Debug;
+	type Connection: Copy + Debug + PartialEq;
 
 	fn store(&self, hash: Self::Hash, connection: Self::Connection, rpc_response: RpcResponse);
 
 	fn withdraw(&self, hash: &Self::Hash) -> Option<(Self::Connection, RpcResponse)>;
+
+	fn is_watched(&self, hash: &Self::Hash) -> bool;
+
+	/// Number of hashes watched on the connection.
+	fn watch_count(&self, connection: &Self::Connection) -> usize;
+
+	/// Number of hashes watched on the connection for which `matches` holds.
+	fn watch_count_where<F: Fn(&Self::Hash) -> bool>(
+		&self,
+		connection: &Self::Connection,
+		matches: F,
+	) -> usize;
+
+	/// Removes all hashes watched on the connection, e.g. once it has been closed. Returns the
+	/// number of removed hashes.
+	fn withdraw_connection(&self, connection: &Self::Connection) -> usize;
+
+	/// Removes the hashes that have not been stored since `idle_since`, in milliseconds since the
+	/// unix epoch, unless `keep` holds for them. Returns the number of removed hashes.
+	fn withdraw_idle<F: Fn(&Self::Hash) -> bool>(&self, idle_since: u64, keep: F) -> usize;
+}
+
+/// Long-lived watches that are not tied to a single request, e.g. the account watches. They are
+/// kept when idle and are limited per connection on their own.
+pub trait RpcSubscriptions: Send + Sync {
+	type Hash: RpcHash;
+
+	/// Maximum number of subscriptions watched on one connection at the same time.
+	const MAX_PER_CONNECTION: usize;
+
+	fn is_subscription(&self, hash: &Self::Hash) -> bool;
+
+	/// Cancels a subscription whose hash is not going to be watched.
+	fn cancel(&self, hash: &Self::Hash);
 }
 
 /// Sends an RPC response back to the client.
@@ -98,6 +135,9 @@ pub trait SendRpcResponse: Send + Sync {
 	) -> DirectRpcResult<()>;
 
 	fn send_state(&self, hash: Self::Hash, state_encoded: Vec<u8>) -> DirectRpcResult<()>;
+
+	/// Pushes a value to the client and keeps watching the connection.
+	fn send_notification(&self, hash: Self::Hash, value_encoded: Vec<u8>) -> DirectRpcResult<()>;
 }
 
 /// Determines if a given connection must be watched (i.e. kept alive),
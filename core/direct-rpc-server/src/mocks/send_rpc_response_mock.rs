@@ -50,4 +50,8 @@ where
 		states_lock.push((hash, state_encoded));
 		Ok(())
 	}
+
+	fn send_notification(&self, _hash: Self::Hash, _value_encoded: Vec<u8>) -> DirectRpcResult<()> {
+		unimplemented!()
+	}
 }
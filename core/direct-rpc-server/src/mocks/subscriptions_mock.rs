@@ -0,0 +1,56 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::{RpcHash, RpcSubscriptions};
+use std::vec::Vec;
+
+/// Subscriptions mock, every hash given on construction is a subscription.
+#[derive(Default)]
+pub struct SubscriptionsMock<Hash> {
+	subscriptions: Vec<Hash>,
+	pub cancelled: RwLock<Vec<Hash>>,
+}
+
+impl<Hash> SubscriptionsMock<Hash> {
+	#[allow(unused)]
+	pub fn new(subscriptions: Vec<Hash>) -> Self {
+		SubscriptionsMock { subscriptions, cancelled: RwLock::new(Vec::new()) }
+	}
+}
+
+impl<Hash> RpcSubscriptions for SubscriptionsMock<Hash>
+where
+	Hash: RpcHash,
+{
+	type Hash = Hash;
+
+	const MAX_PER_CONNECTION: usize = 2;
+
+	fn is_subscription(&self, hash: &Self::Hash) -> bool {
+		self.subscriptions.contains(hash)
+	}
+
+	fn cancel(&self, hash: &Self::Hash) {
+		self.cancelled.write().unwrap().push(hash.clone());
+	}
+}
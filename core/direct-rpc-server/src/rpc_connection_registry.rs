@@ -23,22 +23,36 @@ use std::sync::RwLock;
 
 use crate::{RpcConnectionRegistry, RpcHash};
 use itp_rpc::RpcResponse;
+use itp_time_utils::now_as_millis;
 use std::{collections::HashMap, fmt::Debug};
 
 type HashMapLock<K, V> = RwLock<HashMap<K, V>>;
 
+/// Maximum number of hashes watched on one connection at the same time.
+pub const MAX_WATCHES_PER_CONNECTION: usize = 64;
+
+/// Time after which a watched hash without any update is removed.
+pub const WATCH_IDLE_TIMEOUT_MILLIS: u64 = 30 * 60 * 1000;
+
+struct WatchedConnection<Token> {
+	token: Token,
+	rpc_response: RpcResponse,
+	/// Time of the last update, in milliseconds since the unix epoch.
+	stored_at: u64,
+}
+
 pub struct ConnectionRegistry<Hash, Token>
 where
 	Hash: RpcHash,
-	Token: Copy + Send + Sync + Debug,
+	Token: Copy + Send + Sync + Debug + PartialEq,
 {
-	connection_map: HashMapLock<<Self as RpcConnectionRegistry>::Hash, (Token, RpcResponse)>,
+	connection_map: HashMapLock<<Self as RpcConnectionRegistry>::Hash, WatchedConnection<Token>>,
 }
 
 impl<Hash, Token> ConnectionRegistry<Hash, Token>
 where
 	Hash: RpcHash,
-	Token: Copy + Send + Sync + Debug,
+	Token: Copy + Send + Sync + Debug + PartialEq,
 {
 	pub fn new() -> Self {
 		Self::default()
@@ -53,7 +67,7 @@ where
 impl<Hash, Token> Default for ConnectionRegistry<Hash, Token>
 where
 	Hash: RpcHash,
-	Token: Copy + Send + Sync + Debug,
+	Token: Copy + Send + Sync + Debug + PartialEq,
 {
 	fn default() -> Self {
 		ConnectionRegistry { connection_map: RwLock::new(HashMap::default()) }
@@ -63,19 +77,55 @@ where
 impl<Hash, Token> RpcConnectionRegistry for ConnectionRegistry<Hash, Token>
 where
 	Hash: RpcHash,
-	Token: Copy + Send + Sync + Debug,
+	Token: Copy + Send + Sync + Debug + PartialEq,
 {
 	type Hash = Hash;
 	type Connection = Token;
 
 	fn store(&self, hash: Self::Hash, connection: Self::Connection, rpc_response: RpcResponse) {
 		let mut map = self.connection_map.write().expect("Lock poisoning");
-		map.insert(hash, (connection, rpc_response));
+		map.insert(
+			hash,
+			WatchedConnection { token: connection, rpc_response, stored_at: now_as_millis() },
+		);
 	}
 
 	fn withdraw(&self, hash: &Self::Hash) -> Option<(Self::Connection, RpcResponse)> {
 		let mut map = self.connection_map.write().expect("Lock poisoning");
-		map.remove(hash)
+		map.remove(hash).map(|watched| (watched.token, watched.rpc_response))
+	}
+
+	fn is_watched(&self, hash: &Self::Hash) -> bool {
+		self.connection_map.read().expect("Lock poisoning").contains_key(hash)
+	}
+
+	fn watch_count(&self, connection: &Self::Connection) -> usize {
+		self.watch_count_where(connection, |_| true)
+	}
+
+	fn watch_count_where<F: Fn(&Self::Hash) -> bool>(
+		&self,
+		connection: &Self::Connection,
+		matches: F,
+	) -> usize {
+		let map = self.connection_map.read().expect("Lock poisoning");
+		map.iter()
+			.filter(|(hash, watched)| watched.token == *connection && matches(hash))
+			.count()
+	}
+
+	fn withdraw_connection(&self, connection: &Self::Connection) -> usize {
+		let mut map = self.connection_map.write().expect("Lock poisoning");
+		let watch_count = map.len();
+		map.retain(|_, watched| watched.token != *connection);
+		watch_count - map.len()
+	}
+
+	fn withdraw_idle<F: Fn(&Self::Hash) -> bool>(&self, idle_since: u64, keep: F) -> usize {
+		let mut map = self.connection_map.write().expect("Lock poisoning");
+		let watch_count = map.len();
+		map.retain(|hash, watched| watched.stored_at >= idle_since || keep(hash));
+		watch_count - map.len()
 	}
 }
 
@@ -119,6 +169,33 @@ pub mod tests {
 		assert!(registry.is_empty());
 	}
 
+	#[test]
+	pub fn withdrawing_connection_removes_only_its_hashes() {
+		let registry = TestRegistry::new();
+
+		registry.store("first".to_string(), 1, dummy_rpc_response());
+		registry.store("second".to_string(), 1, dummy_rpc_response());
+		registry.store("third".to_string(), 2, dummy_rpc_response());
+
+		assert_eq!(registry.watch_count(&1), 2);
+		assert_eq!(registry.withdraw_connection(&1), 2);
+		assert_eq!(registry.watch_count(&1), 0);
+		assert!(registry.is_watched(&"third".to_string()));
+	}
+
+	#[test]
+	pub fn withdrawing_idle_hashes_spares_the_kept_ones() {
+		let registry = TestRegistry::new();
+
+		registry.store("idle".to_string(), 1, dummy_rpc_response());
+		registry.store("subscription".to_string(), 1, dummy_rpc_response());
+
+		assert_eq!(registry.withdraw_idle(0, |_| false), 0);
+		assert_eq!(registry.withdraw_idle(u64::MAX, |hash| hash == "subscription"), 1);
+		assert!(registry.is_watched(&"subscription".to_string()));
+		assert!(!registry.is_watched(&"idle".to_string()));
+	}
+
 	fn dummy_rpc_response() -> RpcResponse {
 		RpcResponse { jsonrpc: String::new(), result: Default::default(), id: Id::Number(1u32) }
 	}
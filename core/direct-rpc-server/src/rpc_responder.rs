@@ -128,6 +128,24 @@ where
 		debug!("sending state successful");
 		Ok(())
 	}
+
+	fn send_notification(&self, hash: Hash, value_encoded: Vec<u8>) -> DirectRpcResult<()> {
+		debug!("sending notification");
+
+		let (connection_token, mut response) = self
+			.connection_registry
+			.withdraw(&hash)
+			.ok_or(DirectRpcError::InvalidConnectionHash)?;
+
+		response.result =
+			RpcReturnValue::new(value_encoded, true, DirectRequestStatus::Ok).to_hex();
+
+		self.encode_and_send_response(connection_token, &response)?;
+		self.connection_registry.store(hash, connection_token, response);
+
+		debug!("sending notification successful");
+		Ok(())
+	}
 }
 
 fn continue_watching(status: &TrustedOperationStatus) -> bool {
@@ -237,6 +255,22 @@ pub mod tests {
 		assert_eq!(1, websocket_responder.number_of_updates());
 	}
 
+	#[test]
+	fn sending_notification_keeps_connection() {
+		let connection_hash = String::from("conn_hash");
+		let connection_registry = create_registry_with_single_connection(connection_hash.clone());
+
+		let websocket_responder = Arc::new(TestResponseChannel::default());
+		let rpc_responder =
+			RpcResponder::new(connection_registry.clone(), websocket_responder.clone());
+
+		assert!(rpc_responder.send_notification(connection_hash.clone(), 1u8.encode()).is_ok());
+		assert!(rpc_responder.send_notification(connection_hash.clone(), 2u8.encode()).is_ok());
+
+		verify_open_connection(&connection_hash, connection_registry);
+		assert_eq!(2, websocket_responder.number_of_updates());
+	}
+
 	#[test]
 	fn test_continue_watching() {
 		assert!(!continue_watching(&TrustedOperationStatus::Invalid));
@@ -0,0 +1,156 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Per-connection session keys, negotiated with an ephemeral key agreement.
+//!
+//! A client opens a session with [`OPEN_SESSION_METHOD`], passing its ephemeral public key, and
+//! receives the ephemeral public key of the enclave, signed with the enclave's signing key. The
+//! client must verify that signature against the enclave's registered signing key, otherwise the
+//! host can open the session in place of the enclave. It then wraps its JSON-RPC requests with
+//! [`SESSION_REQUEST_METHOD`], encrypted for the request's sequence number (starting at 0). The
+//! response is encrypted for the same sequence number. A request that fails to decrypt doesn't
+//! use up its sequence number. The session ends with the connection, or once it has been idle for
+//! [`SESSION_IDLE_TIMEOUT_MILLIS`], after which the client has to open a new one.
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::{DirectRpcError, DirectRpcResult};
+use alloc::format;
+use itc_tls_websocket_server::ConnectionToken;
+use itp_sgx_crypto::session_key::{
+	GenerateEphemeralKey, MessageDirection, SessionKey, SessionPublicKey, SignedSessionPublicKey,
+};
+use itp_time_utils::now_as_millis;
+use sp_core::ed25519;
+use std::{collections::HashMap, string::String, sync::Arc, vec::Vec};
+
+pub const OPEN_SESSION_METHOD: &str = "session_open";
+pub const SESSION_REQUEST_METHOD: &str = "session_request";
+
+/// Time after which a session without any request is closed.
+pub const SESSION_IDLE_TIMEOUT_MILLIS: u64 = 30 * 60 * 1000;
+
+struct Session {
+	key: SessionKey,
+	next_request: u64,
+	/// Time of the last request, in milliseconds since the unix epoch.
+	last_used: u64,
+}
+
+pub struct RpcSessions<KeyGenerator> {
+	key_generator: Arc<KeyGenerator>,
+	/// Signing key of the enclave, which signs the ephemeral keys.
+	signer: ed25519::Pair,
+	sessions: RwLock<HashMap<ConnectionToken, Session>>,
+}
+
+impl<KeyGenerator> RpcSessions<KeyGenerator>
+where
+	KeyGenerator: GenerateEphemeralKey,
+{
+	pub fn new(key_generator: Arc<KeyGenerator>, signer: ed25519::Pair) -> Self {
+		RpcSessions { key_generator, signer, sessions: RwLock::new(HashMap::default()) }
+	}
+
+	/// Opens a new session on the connection, replacing a previous one, and returns the signed
+	/// ephemeral public key of the enclave.
+	pub fn open(
+		&self,
+		connection: ConnectionToken,
+		client_public: &SessionPublicKey,
+	) -> DirectRpcResult<SignedSessionPublicKey> {
+		let ephemeral_key = self.key_generator.generate().map_err(other_error)?;
+		let server_public = ephemeral_key.public();
+		let key = ephemeral_key
+			.agree(client_public, client_public, &server_public)
+			.map_err(other_error)?;
+		self.sessions
+			.write()
+			.map_err(|_| lock_poisoning())?
+			.insert(connection, Session { key, next_request: 0, last_used: now_as_millis() });
+		Ok(SignedSessionPublicKey::new(server_public, client_public, &self.signer))
+	}
+
+	/// Decrypts the next request of the session, returns it together with its sequence number.
+	pub fn decrypt_request(
+		&self,
+		connection: ConnectionToken,
+		ciphertext: Vec<u8>,
+	) -> DirectRpcResult<(String, u64)> {
+		let mut sessions = self.sessions.write().map_err(|_| lock_poisoning())?;
+		let session = sessions.get_mut(&connection).ok_or(DirectRpcError::NoSession)?;
+		let sequence = session.next_request;
+		let plaintext = session
+			.key
+			.decrypt_message(MessageDirection::Request, sequence, &ciphertext)
+			.map_err(other_error)?;
+		session.next_request += 1;
+		session.last_used = now_as_millis();
+		let request = String::from_utf8(plaintext).map_err(other_error)?;
+		Ok((request, sequence))
+	}
+
+	pub fn encrypt_response(
+		&self,
+		connection: ConnectionToken,
+		sequence: u64,
+		response: String,
+	) -> DirectRpcResult<Vec<u8>> {
+		let key = self.read_key(connection)?;
+		key.encrypt_message(MessageDirection::Response, sequence, response.as_bytes())
+			.map_err(other_error)
+	}
+
+	pub fn close(&self, connection: ConnectionToken) {
+		if let Ok(mut sessions) = self.sessions.write() {
+			sessions.remove(&connection);
+		}
+	}
+
+	/// Closes the sessions without a request since `idle_since`, returns their number.
+	pub fn close_idle(&self, idle_since: u64) -> usize {
+		match self.sessions.write() {
+			Ok(mut sessions) => {
+				let session_count = sessions.len();
+				sessions.retain(|_, session| session.last_used >= idle_since);
+				session_count - sessions.len()
+			},
+			Err(_) => 0,
+		}
+	}
+
+	fn read_key(&self, connection: ConnectionToken) -> DirectRpcResult<SessionKey> {
+		self.sessions
+			.read()
+			.map_err(|_| lock_poisoning())?
+			.get(&connection)
+			.map(|session| session.key)
+			.ok_or(DirectRpcError::NoSession)
+	}
+}
+
+fn other_error<E: core::fmt::Debug>(e: E) -> DirectRpcError {
+	DirectRpcError::Other(format!("{:?}", e).into())
+}
+
+fn lock_poisoning() -> DirectRpcError {
+	DirectRpcError::Other("Session lock is poisoned".into())
+}
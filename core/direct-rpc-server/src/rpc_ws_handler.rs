@@ -18,43 +18,180 @@
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use crate::sgx_reexport_prelude::*;
 
-use crate::{DetermineWatch, RpcConnectionRegistry, RpcHash};
+use crate::{
+	rpc_connection_registry::{MAX_WATCHES_PER_CONNECTION, WATCH_IDLE_TIMEOUT_MILLIS},
+	rpc_session::{
+		RpcSessions, OPEN_SESSION_METHOD, SESSION_IDLE_TIMEOUT_MILLIS, SESSION_REQUEST_METHOD,
+	},
+	DetermineWatch, DirectRpcError, DirectRpcResult, RpcConnectionRegistry, RpcHash,
+	RpcSubscriptions,
+};
+use alloc::format;
+use codec::Encode;
 use itc_tls_websocket_server::{error::WebSocketResult, ConnectionToken, WebSocketMessageHandler};
+use itp_rpc::{Id, RpcRequest, RpcResponse, RpcReturnValue};
+use itp_sgx_crypto::session_key::{GenerateEphemeralKey, SessionPublicKey};
+use itp_types::DirectRequestStatus;
+use itp_utils::{FromHexPrefixed, ToHexPrefixed};
 use jsonrpc_core::IoHandler;
 use log::*;
-use std::{string::String, sync::Arc};
-
-pub struct RpcWsHandler<Watcher, Registry, Hash>
+use sp_core::ed25519;
+use std::{
+	string::{String, ToString},
+	sync::Arc,
+	vec::Vec,
+};
+
+pub struct RpcWsHandler<Watcher, Registry, Subscriptions, KeyGenerator, Hash>
 where
 	Watcher: DetermineWatch<Hash = Hash>,
 	Registry: RpcConnectionRegistry<Hash = Hash>,
+	Subscriptions: RpcSubscriptions<Hash = Hash>,
+	KeyGenerator: GenerateEphemeralKey,
 	Hash: RpcHash,
 {
 	rpc_io_handler: IoHandler,
 	connection_watcher: Arc<Watcher>,
 	connection_registry: Arc<Registry>,
+	subscriptions: Arc<Subscriptions>,
+	sessions: RpcSessions<KeyGenerator>,
 }
 
-impl<Watcher, Registry, Hash> RpcWsHandler<Watcher, Registry, Hash>
+impl<Watcher, Registry, Subscriptions, KeyGenerator, Hash>
+	RpcWsHandler<Watcher, Registry, Subscriptions, KeyGenerator, Hash>
 where
 	Watcher: DetermineWatch<Hash = Hash>,
 	Registry: RpcConnectionRegistry<Hash = Hash>,
+	Registry::Connection: From<ConnectionToken>,
+	Subscriptions: RpcSubscriptions<Hash = Hash>,
+	KeyGenerator: GenerateEphemeralKey,
 	Hash: RpcHash,
 {
 	pub fn new(
 		rpc_io_handler: IoHandler,
 		connection_watcher: Arc<Watcher>,
 		connection_registry: Arc<Registry>,
+		subscriptions: Arc<Subscriptions>,
+		session_key_generator: Arc<KeyGenerator>,
+		session_signer: ed25519::Pair,
 	) -> Self {
-		RpcWsHandler { rpc_io_handler, connection_watcher, connection_registry }
+		RpcWsHandler {
+			rpc_io_handler,
+			connection_watcher,
+			connection_registry,
+			subscriptions,
+			sessions: RpcSessions::new(session_key_generator, session_signer),
+		}
+	}
+
+	fn handle_rpc_request(
+		&self,
+		connection_token: ConnectionToken,
+		message: &str,
+	) -> Option<String> {
+		let maybe_rpc_response = self.rpc_io_handler.handle_request_sync(message);
+
+		debug!("RPC response string: {:?}", maybe_rpc_response);
+
+		if let Ok(rpc_response) =
+			serde_json::from_str(maybe_rpc_response.clone().unwrap_or_default().as_str())
+		{
+			if let Ok(Some(connection_hash)) =
+				self.connection_watcher.must_be_watched(&rpc_response)
+			{
+				let connection = connection_token.into();
+				if self.connection_registry.watch_count(&connection) >= MAX_WATCHES_PER_CONNECTION {
+					warn!(
+						"Connection {:?} watches {} hashes already, not watching another one",
+						connection_token, MAX_WATCHES_PER_CONNECTION
+					);
+					return self.unwatched_response(&connection_hash, rpc_response)
+				}
+				if self.subscriptions.is_subscription(&connection_hash)
+					&& self.connection_registry.watch_count_where(&connection, |hash| {
+						self.subscriptions.is_subscription(hash)
+					}) >= Subscriptions::MAX_PER_CONNECTION
+				{
+					warn!(
+						"Connection {:?} has {} subscriptions already, not subscribing again",
+						connection_token,
+						Subscriptions::MAX_PER_CONNECTION
+					);
+					return self.unwatched_response(&connection_hash, rpc_response)
+				}
+				self.connection_registry.store(connection_hash, connection, rpc_response);
+			}
+		}
+
+		maybe_rpc_response
+	}
+
+	/// Tells the client that the response will not be followed by any updates, and cancels the
+	/// subscription the response may belong to.
+	fn unwatched_response(&self, hash: &Hash, rpc_response: RpcResponse) -> Option<String> {
+		if self.subscriptions.is_subscription(hash) {
+			self.subscriptions.cancel(hash);
+		}
+		unwatched_response(rpc_response)
+	}
+
+	/// Closes the idle sessions and removes the idle watched hashes, unless they belong to a
+	/// long-lived subscription.
+	pub fn sweep_idle(&self, now_millis: u64) {
+		let closed_sessions =
+			self.sessions.close_idle(now_millis.saturating_sub(SESSION_IDLE_TIMEOUT_MILLIS));
+		let withdrawn_watches = self
+			.connection_registry
+			.withdraw_idle(now_millis.saturating_sub(WATCH_IDLE_TIMEOUT_MILLIS), |hash| {
+				self.subscriptions.is_subscription(hash)
+			});
+		if closed_sessions > 0 || withdrawn_watches > 0 {
+			info!(
+				"Closed {} idle RPC sessions and removed {} idle watched hashes",
+				closed_sessions, withdrawn_watches
+			);
+		}
+	}
+
+	pub fn is_watched(&self, hash: &Hash) -> bool {
+		self.connection_registry.is_watched(hash)
+	}
+
+	fn open_session(
+		&self,
+		connection_token: ConnectionToken,
+		request: &RpcRequest,
+	) -> DirectRpcResult<Vec<u8>> {
+		let client_public = SessionPublicKey::from_hex(first_param(request)?)
+			.map_err(|e| DirectRpcError::Other(format!("{:?}", e).into()))?;
+		let signed_server_public = self.sessions.open(connection_token, &client_public)?;
+		Ok(signed_server_public.encode())
+	}
+
+	/// Decrypts the wrapped request, handles it and returns the encrypted response.
+	fn handle_session_request(
+		&self,
+		connection_token: ConnectionToken,
+		request: &RpcRequest,
+	) -> DirectRpcResult<Vec<u8>> {
+		let ciphertext = Vec::<u8>::from_hex(first_param(request)?)
+			.map_err(|e| DirectRpcError::Other(format!("{:?}", e).into()))?;
+		let (inner_request, sequence) =
+			self.sessions.decrypt_request(connection_token, ciphertext)?;
+		let inner_response =
+			self.handle_rpc_request(connection_token, &inner_request).unwrap_or_default();
+		self.sessions.encrypt_response(connection_token, sequence, inner_response)
 	}
 }
 
-impl<Watcher, Registry, Hash> WebSocketMessageHandler for RpcWsHandler<Watcher, Registry, Hash>
+impl<Watcher, Registry, Subscriptions, KeyGenerator, Hash> WebSocketMessageHandler
+	for RpcWsHandler<Watcher, Registry, Subscriptions, KeyGenerator, Hash>
 where
 	Watcher: DetermineWatch<Hash = Hash>,
 	Registry: RpcConnectionRegistry<Hash = Hash>,
 	Registry::Connection: From<ConnectionToken>,
+	Subscriptions: RpcSubscriptions<Hash = Hash>,
+	KeyGenerator: GenerateEphemeralKey,
 	Hash: RpcHash,
 {
 	fn handle_message(
@@ -62,47 +199,102 @@ where
 		connection_token: ConnectionToken,
 		message: String,
 	) -> WebSocketResult<Option<String>> {
-		let maybe_rpc_response = self.rpc_io_handler.handle_request_sync(message.as_str());
-
-		debug!("RPC response string: {:?}", maybe_rpc_response);
+		let session_result = match serde_json::from_str::<RpcRequest>(message.as_str()) {
+			Ok(request) if request.method == OPEN_SESSION_METHOD =>
+				Some((self.open_session(connection_token, &request), request.id)),
+			Ok(request) if request.method == SESSION_REQUEST_METHOD =>
+				Some((self.handle_session_request(connection_token, &request), request.id)),
+			_ => None,
+		};
 
-		if let Ok(rpc_response) =
-			serde_json::from_str(maybe_rpc_response.clone().unwrap_or_default().as_str())
-		{
-			if let Ok(Some(connection_hash)) =
-				self.connection_watcher.must_be_watched(&rpc_response)
-			{
-				self.connection_registry.store(
-					connection_hash,
-					connection_token.into(),
-					rpc_response,
-				);
-			}
+		match session_result {
+			Some((result, id)) => Ok(session_response(result, id)),
+			None => Ok(self.handle_rpc_request(connection_token, message.as_str())),
 		}
+	}
 
-		Ok(maybe_rpc_response)
+	fn on_connection_closed(&self, connection_token: ConnectionToken) {
+		self.sessions.close(connection_token);
+		let withdrawn_watches =
+			self.connection_registry.withdraw_connection(&connection_token.into());
+		if withdrawn_watches > 0 {
+			debug!(
+				"Removed {} watched hashes of the closed connection {:?}",
+				withdrawn_watches, connection_token
+			);
+		}
 	}
 }
 
+/// Tells the client that the response will not be followed by any updates.
+fn unwatched_response(mut rpc_response: RpcResponse) -> Option<String> {
+	let mut return_value = RpcReturnValue::from_hex(&rpc_response.result).ok()?;
+	return_value.do_watch = false;
+	rpc_response.result = return_value.to_hex();
+	serde_json::to_string(&rpc_response).ok()
+}
+
+fn first_param(request: &RpcRequest) -> DirectRpcResult<&String> {
+	request.params.first().ok_or_else(|| {
+		DirectRpcError::Other(format!("{} requires a parameter", request.method).into())
+	})
+}
+
+fn session_response(result: DirectRpcResult<Vec<u8>>, id: Id) -> Option<String> {
+	let return_value = match result {
+		Ok(value) => RpcReturnValue::new(value, false, DirectRequestStatus::Ok),
+		Err(e) => {
+			warn!("Session request failed: {:?}", e);
+			RpcReturnValue::from_error_message(&e.to_string())
+		},
+	};
+	serde_json::to_string(&RpcResponse {
+		jsonrpc: "2.0".to_string(),
+		result: return_value.to_hex(),
+		id,
+	})
+	.ok()
+}
+
 #[cfg(test)]
 pub mod tests {
 
 	use super::*;
 	use crate::{
-		mocks::determine_watch_mock::DetermineWatchMock,
+		builders::rpc_response_builder::RpcResponseBuilder,
+		mocks::{determine_watch_mock::DetermineWatchMock, subscriptions_mock::SubscriptionsMock},
 		rpc_connection_registry::ConnectionRegistry,
 	};
 	use codec::Encode;
 	use itc_tls_websocket_server::ConnectionToken;
 	use itp_rpc::RpcReturnValue;
+	use itp_sgx_crypto::{
+		session_key::{EphemeralKey, MessageDirection, SignedSessionPublicKey},
+		Result as CryptoResult,
+	};
 	use itp_types::DirectRequestStatus;
-	use itp_utils::ToHexPrefixed;
 	use jsonrpc_core::Params;
 	use serde_json::json;
+	use sp_core::Pair;
 
 	type TestConnectionRegistry = ConnectionRegistry<String, ConnectionToken>;
 	type TestConnectionWatcher = DetermineWatchMock<String>;
-	type TestWsHandler = RpcWsHandler<TestConnectionWatcher, TestConnectionRegistry, String>;
+	type TestSubscriptions = SubscriptionsMock<String>;
+	type TestWsHandler = RpcWsHandler<
+		TestConnectionWatcher,
+		TestConnectionRegistry,
+		TestSubscriptions,
+		SeededKeyGenerator,
+		String,
+	>;
+
+	struct SeededKeyGenerator;
+
+	impl GenerateEphemeralKey for SeededKeyGenerator {
+		fn generate(&self) -> CryptoResult<EphemeralKey> {
+			Ok(EphemeralKey::from_seed([9u8; 32]))
+		}
+	}
 
 	const RPC_METHOD_NAME: &str = "test_call";
 
@@ -136,6 +328,91 @@ pub mod tests {
 		assert!(connection_registry.withdraw(&connection_hash).is_some());
 	}
 
+	#[test]
+	fn watch_beyond_the_limit_of_the_connection_is_not_stored() {
+		let io_handler = create_io_handler(
+			RPC_METHOD_NAME,
+			RpcReturnValue {
+				do_watch: true,
+				value: String::from("value").encode(),
+				status: DirectRequestStatus::Ok,
+			},
+		);
+
+		let connection_hash = String::from("connection_hash");
+		let (connection_token, message) = create_message_to_handle(RPC_METHOD_NAME);
+
+		let (ws_handler, connection_registry) =
+			create_ws_handler(io_handler, Some(connection_hash.clone()));
+		for i in 0..MAX_WATCHES_PER_CONNECTION {
+			connection_registry.store(
+				i.to_string(),
+				connection_token,
+				RpcResponseBuilder::new().build(),
+			);
+		}
+
+		let response = ws_handler.handle_message(connection_token, message).unwrap().unwrap();
+
+		let response: RpcResponse = serde_json::from_str(&response).unwrap();
+		assert!(!RpcReturnValue::from_hex(&response.result).unwrap().do_watch);
+		assert!(!connection_registry.is_watched(&connection_hash));
+	}
+
+	#[test]
+	fn subscription_beyond_the_limit_of_the_connection_is_cancelled() {
+		let io_handler = create_io_handler(
+			RPC_METHOD_NAME,
+			RpcReturnValue {
+				do_watch: true,
+				value: String::from("value").encode(),
+				status: DirectRequestStatus::Ok,
+			},
+		);
+
+		let subscription_hashes: Vec<String> = (0..=TestSubscriptions::MAX_PER_CONNECTION)
+			.map(|i| format!("subscription_{}", i))
+			.collect();
+		let connection_hash = subscription_hashes[TestSubscriptions::MAX_PER_CONNECTION].clone();
+		let (connection_token, message) = create_message_to_handle(RPC_METHOD_NAME);
+
+		let (ws_handler, connection_registry, subscriptions) = create_ws_handler_with_subscriptions(
+			io_handler,
+			Some(connection_hash.clone()),
+			subscription_hashes.clone(),
+		);
+		for hash in &subscription_hashes[..TestSubscriptions::MAX_PER_CONNECTION] {
+			connection_registry.store(
+				hash.clone(),
+				connection_token,
+				RpcResponseBuilder::new().build(),
+			);
+		}
+
+		let response = ws_handler.handle_message(connection_token, message).unwrap().unwrap();
+
+		let response: RpcResponse = serde_json::from_str(&response).unwrap();
+		assert!(!RpcReturnValue::from_hex(&response.result).unwrap().do_watch);
+		assert!(!connection_registry.is_watched(&connection_hash));
+		assert_eq!(*subscriptions.cancelled.read().unwrap(), vec![connection_hash]);
+	}
+
+	#[test]
+	fn closing_the_connection_removes_its_watches() {
+		let io_handler = create_io_handler_with_method(RPC_METHOD_NAME);
+
+		let connection_hash = String::from("connection_hash");
+		let (connection_token, message) = create_message_to_handle(RPC_METHOD_NAME);
+
+		let (ws_handler, connection_registry) =
+			create_ws_handler(io_handler, Some(connection_hash.clone()));
+		ws_handler.handle_message(connection_token, message).unwrap();
+
+		ws_handler.on_connection_closed(connection_token);
+
+		assert!(connection_registry.is_empty());
+	}
+
 	#[test]
 	fn when_rpc_returns_error_then_return_ok_but_status_is_set_to_error() {
 		let io_handler = create_io_handler_with_error(RPC_METHOD_NAME);
@@ -165,6 +442,108 @@ pub mod tests {
 		assert!(connection_registry.is_empty());
 	}
 
+	#[test]
+	fn session_request_is_decrypted_and_answered_encrypted() {
+		let io_handler = create_io_handler_with_method(RPC_METHOD_NAME);
+		let (ws_handler, _) = create_ws_handler(io_handler, None);
+		let connection_token = ConnectionToken(23);
+
+		let client = EphemeralKey::from_seed([1u8; 32]);
+		let client_public = client.public();
+		let open_request = RpcRequest::compose_jsonrpc_call(
+			OPEN_SESSION_METHOD.to_string(),
+			vec![client_public.to_hex()],
+		)
+		.unwrap();
+		let signed_server_public: SignedSessionPublicKey = decode_return_value(
+			ws_handler.handle_message(connection_token, open_request).unwrap().unwrap(),
+		);
+		assert!(signed_server_public.verify(&client_public, &session_signer().public()));
+		let server_public = signed_server_public.server_public;
+		let session_key = client.agree(&server_public, &client_public, &server_public).unwrap();
+
+		let (_, inner_request) = create_message_to_handle(RPC_METHOD_NAME);
+		let ciphertext = session_key
+			.encrypt_message(MessageDirection::Request, 0, inner_request.as_bytes())
+			.unwrap();
+		let session_request = RpcRequest::compose_jsonrpc_call(
+			SESSION_REQUEST_METHOD.to_string(),
+			vec![ciphertext.to_hex()],
+		)
+		.unwrap();
+		let response: Vec<u8> = decode_return_value(
+			ws_handler.handle_message(connection_token, session_request).unwrap().unwrap(),
+		);
+		let response =
+			session_key.decrypt_message(MessageDirection::Response, 0, &response).unwrap();
+
+		let inner_response: RpcResponse =
+			serde_json::from_str(&String::from_utf8(response).unwrap()).unwrap();
+		let inner_value = RpcReturnValue::from_hex(&inner_response.result).unwrap();
+		assert_eq!(inner_value.value, String::from("value").encode());
+	}
+
+	#[test]
+	fn modified_session_request_is_rejected() {
+		let io_handler = create_io_handler_with_method(RPC_METHOD_NAME);
+		let (ws_handler, _) = create_ws_handler(io_handler, None);
+		let connection_token = ConnectionToken(23);
+
+		let client = EphemeralKey::from_seed([1u8; 32]);
+		let client_public = client.public();
+		let open_request = RpcRequest::compose_jsonrpc_call(
+			OPEN_SESSION_METHOD.to_string(),
+			vec![client_public.to_hex()],
+		)
+		.unwrap();
+		let signed_server_public: SignedSessionPublicKey = decode_return_value(
+			ws_handler.handle_message(connection_token, open_request).unwrap().unwrap(),
+		);
+		let server_public = signed_server_public.server_public;
+		let session_key = client.agree(&server_public, &client_public, &server_public).unwrap();
+
+		let (_, inner_request) = create_message_to_handle(RPC_METHOD_NAME);
+		let mut ciphertext = session_key
+			.encrypt_message(MessageDirection::Request, 0, inner_request.as_bytes())
+			.unwrap();
+		ciphertext[0] ^= 1;
+		let session_request = RpcRequest::compose_jsonrpc_call(
+			SESSION_REQUEST_METHOD.to_string(),
+			vec![ciphertext.to_hex()],
+		)
+		.unwrap();
+
+		let response = ws_handler.handle_message(connection_token, session_request).unwrap();
+
+		let response: RpcResponse = serde_json::from_str(&response.unwrap()).unwrap();
+		let return_value = RpcReturnValue::from_hex(&response.result).unwrap();
+		assert_eq!(return_value.status, DirectRequestStatus::Error);
+	}
+
+	#[test]
+	fn session_request_without_session_returns_error_status() {
+		let io_handler = create_io_handler_with_method(RPC_METHOD_NAME);
+		let (ws_handler, _) = create_ws_handler(io_handler, None);
+		let session_request = RpcRequest::compose_jsonrpc_call(
+			SESSION_REQUEST_METHOD.to_string(),
+			vec![vec![1u8, 2, 3].to_hex()],
+		)
+		.unwrap();
+
+		let response = ws_handler.handle_message(ConnectionToken(23), session_request).unwrap();
+
+		let response: RpcResponse = serde_json::from_str(&response.unwrap()).unwrap();
+		let return_value = RpcReturnValue::from_hex(&response.result).unwrap();
+		assert_eq!(return_value.status, DirectRequestStatus::Error);
+	}
+
+	fn decode_return_value<T: codec::Decode>(response: String) -> T {
+		let response: RpcResponse = serde_json::from_str(&response).unwrap();
+		let return_value = RpcReturnValue::from_hex(&response.result).unwrap();
+		assert_eq!(return_value.status, DirectRequestStatus::Ok);
+		T::decode(&mut return_value.value.as_slice()).unwrap()
+	}
+
 	fn create_message_to_handle(method_name: &str) -> (ConnectionToken, String) {
 		let json_rpc_pre_method = r#"{"jsonrpc": "2.0", "method": ""#;
 		let json_rpc_post_method = r#"", "params": {}, "id": 1}"#;
@@ -175,20 +554,43 @@ pub mod tests {
 		(ConnectionToken(23), json_string)
 	}
 
+	fn session_signer() -> ed25519::Pair {
+		ed25519::Pair::from_seed(&[5u8; 32])
+	}
+
 	fn create_ws_handler(
 		io_handler: IoHandler,
 		watch_connection: Option<String>,
 	) -> (TestWsHandler, Arc<TestConnectionRegistry>) {
+		let (ws_handler, connection_registry, _) =
+			create_ws_handler_with_subscriptions(io_handler, watch_connection, Vec::new());
+		(ws_handler, connection_registry)
+	}
+
+	fn create_ws_handler_with_subscriptions(
+		io_handler: IoHandler,
+		watch_connection: Option<String>,
+		subscriptions: Vec<String>,
+	) -> (TestWsHandler, Arc<TestConnectionRegistry>, Arc<TestSubscriptions>) {
 		let watcher = match watch_connection {
 			Some(hash) => TestConnectionWatcher::do_watch(hash),
 			None => TestConnectionWatcher::no_watch(),
 		};
 
 		let connection_registry = Arc::new(TestConnectionRegistry::new());
+		let subscriptions = Arc::new(TestSubscriptions::new(subscriptions));
 
 		(
-			TestWsHandler::new(io_handler, Arc::new(watcher), connection_registry.clone()),
+			TestWsHandler::new(
+				io_handler,
+				Arc::new(watcher),
+				connection_registry.clone(),
+				subscriptions.clone(),
+				Arc::new(SeededKeyGenerator),
+				session_signer(),
+			),
 			connection_registry,
+			subscriptions,
 		)
 	}
 
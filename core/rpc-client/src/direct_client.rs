@@ -22,7 +22,7 @@ use codec::Decode;
 use frame_metadata::RuntimeMetadataPrefixed;
 use itp_api_client_types::Metadata;
 use itp_rpc::{RpcRequest, RpcResponse, RpcReturnValue};
-use itp_types::DirectRequestStatus;
+use itp_types::{DirectRequestStatus, H256};
 use itp_utils::FromHexPrefixed;
 use log::*;
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
@@ -51,6 +51,8 @@ pub trait DirectApi {
 	fn get_mu_ra_url(&self) -> Result<String>;
 	fn get_untrusted_worker_url(&self) -> Result<String>;
 	fn get_state_metadata(&self) -> Result<Metadata>;
+	/// Number of the last sidechain block applied to the state of the shard, and the state hash.
+	fn get_state_hash(&self) -> Result<(Option<u64>, H256)>;
 
 	fn send(&self, request: &str) -> Result<()>;
 	/// Close any open websocket connection.
@@ -155,6 +157,23 @@ impl DirectApi for DirectClient {
 		Metadata::try_from(metadata).map_err(|e| e.into())
 	}
 
+	fn get_state_hash(&self) -> Result<(Option<u64>, H256)> {
+		let jsonrpc_call: String =
+			RpcRequest::compose_jsonrpc_call("state_getStateHash".to_string(), Default::default())?;
+
+		// Send json rpc call to ws server.
+		let response_str = self.get(&jsonrpc_call)?;
+
+		// Decode rpc response.
+		let rpc_response: RpcResponse = serde_json::from_str(&response_str)?;
+		let rpc_return_value = RpcReturnValue::from_hex(&rpc_response.result)
+			.map_err(|e| Error::Custom(format!("{:?}", e).into()))?;
+		if rpc_return_value.status != DirectRequestStatus::Ok {
+			return Err(Error::Status(String::decode(&mut rpc_return_value.value.as_slice())?))
+		}
+		Ok(Decode::decode(&mut rpc_return_value.value.as_slice())?)
+	}
+
 	fn send(&self, request: &str) -> Result<()> {
 		self.web_socket_control.send(request)
 	}
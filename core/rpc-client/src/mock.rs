@@ -21,6 +21,7 @@ use crate::{direct_client::DirectApi, error::Result};
 use codec::Decode;
 use frame_metadata::RuntimeMetadataPrefixed;
 use itp_api_client_types::Metadata;
+use itp_types::H256;
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
 use std::{sync::mpsc::Sender as MpscSender, thread::JoinHandle};
 
@@ -30,6 +31,7 @@ pub struct DirectClientMock {
 	mu_ra_url: String,
 	untrusted_worker_url: String,
 	metadata: Vec<u8>,
+	state_hash: (Option<u64>, H256),
 }
 
 impl DirectClientMock {
@@ -39,7 +41,13 @@ impl DirectClientMock {
 		untrusted_worker_url: String,
 		metadata: Vec<u8>,
 	) -> Self {
-		Self { rsa_pubkey, mu_ra_url, untrusted_worker_url, metadata }
+		Self {
+			rsa_pubkey,
+			mu_ra_url,
+			untrusted_worker_url,
+			metadata,
+			state_hash: Default::default(),
+		}
 	}
 
 	pub fn with_rsa_pubkey(mut self, key: Rsa3072PubKey) -> Self {
@@ -61,6 +69,11 @@ impl DirectClientMock {
 		self.metadata = metadata;
 		self
 	}
+
+	pub fn with_state_hash(mut self, block_number: Option<u64>, state_hash: H256) -> Self {
+		self.state_hash = (block_number, state_hash);
+		self
+	}
 }
 
 impl DirectApi for DirectClientMock {
@@ -89,6 +102,10 @@ impl DirectApi for DirectClientMock {
 		Metadata::try_from(metadata).map_err(|e| e.into())
 	}
 
+	fn get_state_hash(&self) -> Result<(Option<u64>, H256)> {
+		Ok(self.state_hash)
+	}
+
 	fn send(&self, _request: &str) -> Result<()> {
 		unimplemented!()
 	}
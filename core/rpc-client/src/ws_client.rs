@@ -20,6 +20,8 @@
 /// This should be replaced with the `jsonrpsee::WsClient`as soon as available in no-std:
 /// https://github.com/paritytech/jsonrpsee/issues/1
 use crate::error::{Error, Result as RpcClientResult};
+use itp_rpc::{RpcErrorResponse, RpcResponse, RpcReturnValue};
+use itp_utils::ToHexPrefixed;
 use log::*;
 use openssl::ssl::{SslConnector, SslMethod, SslStream, SslVerifyMode};
 use parking_lot::Mutex;
@@ -125,7 +127,9 @@ impl Handler for WsClient {
 		trace!("got message");
 		trace!("{}", msg);
 		trace!("sending result to MpscSender..");
-		self.result.send(msg.to_string()).expect("Failed to send");
+		self.result
+			.send(with_error_as_return_value(msg.to_string()))
+			.expect("Failed to send");
 		if !self.do_watch {
 			debug!("do_watch is false, closing connection");
 			self.web_socket.close(CloseCode::Normal).expect("Failed to close connection");
@@ -166,3 +170,51 @@ impl Handler for WsClient {
 			.map_err(From::from)
 	}
 }
+
+/// The clients handle failures as responses with the error status. Error responses, which carry
+/// the stable error code of the enclave, are passed on in that format, with the code in the
+/// error message.
+fn with_error_as_return_value(message: String) -> String {
+	let error_response = match serde_json::from_str::<RpcErrorResponse>(&message) {
+		Ok(error_response) => error_response,
+		Err(_) => return message,
+	};
+	let RpcErrorResponse { jsonrpc, error, id } = error_response;
+	let error_message = format!(
+		"{} ({}): {}",
+		error.message,
+		error.code,
+		error.data.map(|data| data.to_string()).unwrap_or_default()
+	);
+	let result = RpcReturnValue::from_error_message(&error_message).to_hex();
+	serde_json::to_string(&RpcResponse { jsonrpc, result, id }).unwrap_or(message)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use itp_types::DirectRequestStatus;
+	use itp_utils::FromHexPrefixed;
+
+	#[test]
+	fn error_response_is_passed_on_as_return_value_with_the_error_code() {
+		let message = with_error_as_return_value(
+			r#"{"jsonrpc":"2.0","error":{"code":2008,"message":"Unavailable error","data":"Overloaded"},"id":1}"#
+				.to_string(),
+		);
+
+		let response: RpcResponse = serde_json::from_str(&message).unwrap();
+		let return_value = RpcReturnValue::from_hex(&response.result).unwrap();
+		assert_eq!(return_value.status, DirectRequestStatus::Error);
+		assert_eq!(
+			String::decode(&mut return_value.value.as_slice()).unwrap(),
+			r#"Unavailable error (2008): "Overloaded""#
+		);
+	}
+
+	#[test]
+	fn other_messages_are_passed_on_unchanged() {
+		assert_eq!(with_error_as_return_value("End of service.".to_string()), "End of service.");
+	}
+}
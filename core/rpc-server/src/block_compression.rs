@@ -0,0 +1,65 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Compression of the sidechain blocks broadcast to peer workers.
+//!
+//! Blocks are SCALE encoded and compressed with zstd. The peers decompress them before they are
+//! forwarded to the enclave, which imports them unchanged.
+
+use its_primitives::types::block::SignedBlock;
+use parity_scale_codec::{Decode, Encode};
+use std::io;
+
+/// Upper bound of decompressed blocks, to not allocate arbitrary amounts of memory.
+pub const MAX_DECOMPRESSED_BLOCKS_SIZE: usize = 64 * 1024 * 1024;
+
+/// zstd's default level, which compresses well at a fraction of the block time.
+const COMPRESSION_LEVEL: i32 = 3;
+
+pub fn compress_blocks(blocks: &[SignedBlock]) -> io::Result<Vec<u8>> {
+	zstd::bulk::compress(&blocks.encode(), COMPRESSION_LEVEL)
+}
+
+pub fn decompress_blocks(compressed: &[u8]) -> io::Result<Vec<SignedBlock>> {
+	let encoded = zstd::bulk::decompress(compressed, MAX_DECOMPRESSED_BLOCKS_SIZE)?;
+	Vec::<SignedBlock>::decode(&mut encoded.as_slice())
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use its_test::sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait};
+
+	#[test]
+	fn compressed_blocks_decompress_to_the_original_blocks() {
+		let blocks = vec![
+			SidechainBlockBuilder::default().build_signed(),
+			SidechainBlockBuilder::default().build_signed(),
+		];
+
+		let compressed = compress_blocks(&blocks).unwrap();
+
+		assert!(compressed.len() < blocks.encode().len());
+		assert_eq!(decompress_blocks(&compressed).unwrap(), blocks);
+	}
+
+	#[test]
+	fn decompressing_garbage_fails() {
+		assert!(decompress_blocks(&[1, 2, 3]).is_err());
+	}
+}
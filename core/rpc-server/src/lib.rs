@@ -17,10 +17,12 @@
 
 use itp_enclave_api::direct_request::DirectRequest;
 use itp_rpc::RpcRequest;
-use itp_utils::ToHexPrefixed;
+use itp_utils::{hex::decode_hex, ToHexPrefixed};
 use its_peer_fetch::block_fetch_server::BlockFetchServerModuleBuilder;
 use its_primitives::types::block::SignedBlock;
-use its_rpc_handler::constants::RPC_METHOD_NAME_IMPORT_BLOCKS;
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_IMPORT_BLOCKS, RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS,
+};
 use its_storage::interface::FetchBlocks;
 use jsonrpsee::{
 	types::error::CallError,
@@ -30,6 +32,8 @@ use log::debug;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::net::ToSocketAddrs;
 
+pub mod block_compression;
+
 #[cfg(test)]
 mod mock;
 #[cfg(test)]
@@ -64,6 +68,26 @@ where
 				.map_err(|e| CallError::Failed(e.into()))
 		},
 	)?;
+	import_sidechain_block_module.register_method(
+		RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS,
+		|params, enclave| {
+			debug!("{} params: {:?}", RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS, params);
+
+			let compressed = decode_hex(params.one::<String>()?)
+				.map_err(|e| CallError::Failed(format!("{:?}", e).into()))?;
+			let blocks = block_compression::decompress_blocks(&compressed)
+				.map_err(|e| CallError::Failed(e.into()))?;
+			let enclave_req = RpcRequest::compose_jsonrpc_call(
+				RPC_METHOD_NAME_IMPORT_BLOCKS.into(),
+				vec![blocks.to_hex()],
+			)
+			.unwrap();
+
+			enclave
+				.rpc(enclave_req.as_bytes().to_vec())
+				.map_err(|e| CallError::Failed(e.into()))
+		},
+	)?;
 	server.register_module(import_sidechain_block_module).unwrap();
 
 	let fetch_sidechain_blocks_module = BlockFetchServerModuleBuilder::new(sidechain_block_fetcher)
@@ -18,7 +18,10 @@
 use super::*;
 use crate::mock::MockSidechainBlockFetcher;
 use itp_rpc::RpcResponse;
-use its_rpc_handler::constants::RPC_METHOD_NAME_IMPORT_BLOCKS;
+use itp_utils::hex::hex_encode;
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_IMPORT_BLOCKS, RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS,
+};
 use its_test::sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait};
 use jsonrpsee::{
 	types::{to_json_value, traits::Client},
@@ -54,3 +57,27 @@ async fn test_client_calls() {
 
 	assert!(RpcResponse::decode(&mut response.as_slice()).is_ok());
 }
+
+#[tokio::test]
+async fn compressed_blocks_are_imported() {
+	init();
+	let addr =
+		run_server("127.0.0.1:0", Arc::new(TestEnclave), Arc::new(MockSidechainBlockFetcher))
+			.await
+			.unwrap();
+
+	let url = format!("ws://{}", addr);
+	let client = WsClientBuilder::default().build(&url).await.unwrap();
+	let compressed =
+		block_compression::compress_blocks(&[SidechainBlockBuilder::default().build_signed()])
+			.unwrap();
+	let response: Vec<u8> = client
+		.request(
+			RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS,
+			vec![to_json_value(hex_encode(&compressed)).unwrap()].into(),
+		)
+		.await
+		.unwrap();
+
+	assert!(RpcResponse::decode(&mut response.as_slice()).is_ok());
+}
@@ -124,6 +124,7 @@ where
 			if connection.is_closed() {
 				trace!("Connection {:?} is closed, removing", token);
 				connections_lock.remove(&token);
+				self.connection_handler.on_connection_closed(token.into());
 				trace!(
 					"Closed {:?}, {} active connections remaining",
 					token,
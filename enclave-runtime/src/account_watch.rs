@@ -0,0 +1,93 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Pushes the incoming transfers to watched accounts to the watching RPC connections.
+//!
+//! The transfers are read from the events of the latest sidechain block in the state. If several
+//! blocks are imported at once, e.g. during a peer sync, only the transfers of the last one are
+//! notified, so exchanges should still reconcile the balances of their accounts now and then.
+
+use crate::{
+	error::Result as EnclaveResult,
+	initialization::global_components::{
+		GLOBAL_ACCOUNT_WATCHES_COMPONENT, GLOBAL_RPC_RESPONDER_COMPONENT,
+		GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
+	},
+};
+use codec::Encode;
+use ita_sgx_runtime::{BalancesEvent, RuntimeEvent, System};
+use itc_direct_rpc_server::SendRpcResponse;
+use itp_component_container::ComponentGetter;
+use itp_sgx_crypto::key_repository::AccessKey;
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_stf_state_handler::handle_state::HandleState;
+use itp_types::{
+	account_watch::{IncomingTransfer, SignedTransferNotification},
+	ShardIdentifier,
+};
+use its_sidechain::state::SidechainSystemExt;
+use log::*;
+use std::vec::Vec;
+
+/// Notifies the watchers of the accounts that received a transfer in the latest sidechain block
+/// of the shard, unless that block has been notified before.
+pub(crate) fn notify_incoming_transfers(shard: &ShardIdentifier) -> EnclaveResult<()> {
+	let account_watches = GLOBAL_ACCOUNT_WATCHES_COMPONENT.get()?;
+	if account_watches.is_empty() {
+		return Ok(())
+	}
+
+	let (mut state, _) = GLOBAL_STATE_HANDLER_COMPONENT.get()?.load_cloned(shard)?;
+	let sidechain_block = match state.get_block_number() {
+		Some(block_number) => block_number,
+		None => return Ok(()),
+	};
+	if !account_watches.mark_notified(*shard, sidechain_block) {
+		return Ok(())
+	}
+
+	let transfers: Vec<IncomingTransfer> = state.execute_with(|| {
+		System::read_events_no_consensus()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::Balances(BalancesEvent::Transfer { from, to, amount }) =>
+					Some(IncomingTransfer { shard: *shard, sidechain_block, from, to, amount }),
+				_ => None,
+			})
+			.collect()
+	});
+	if transfers.is_empty() {
+		return Ok(())
+	}
+
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let rpc_responder = GLOBAL_RPC_RESPONDER_COMPONENT.get()?;
+	for transfer in transfers {
+		let watchers = account_watches.watchers_of(shard, &transfer.to);
+		if watchers.is_empty() {
+			continue
+		}
+		let notification = SignedTransferNotification::new(transfer, &signer).encode();
+		for watch_hash in watchers {
+			// The connection has been closed, the watch is dropped with it.
+			if let Err(e) = rpc_responder.send_notification(watch_hash, notification.clone()) {
+				debug!("Dropping account watch {:?}: {:?}", watch_hash, e);
+				account_watches.unregister(&watch_hash);
+			}
+		}
+	}
+	Ok(())
+}
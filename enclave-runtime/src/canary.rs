@@ -0,0 +1,286 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Rounds of the canary on a designated shard, see [`itp_types::canary`].
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	get_base_path,
+	initialization::global_components::{
+		EnclaveNodeMetadataRepository, EnclaveStateFileIo, EnclaveStateInitializer, EnclaveStf,
+		GLOBAL_OCALL_API_COMPONENT, GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT,
+		GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
+	},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::UnfilteredDispatchable;
+use ita_sgx_runtime::{Runtime, System};
+use ita_stf::{Getter, State as StfState, TrustedCall, TrustedGetter};
+use itp_component_container::ComponentGetter;
+use itp_ocall_api::EnclaveAttestationOCallApi;
+use itp_settings::files::CANARY_PATH;
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_stf_interface::{StateCallInterface, StateGetterInterface};
+use itp_stf_primitives::{
+	traits::{TrustedCallSigning, TrustedCallVerification},
+	types::{AccountId, KeyPair},
+};
+use itp_stf_state_handler::{
+	file_io::{StateDir, StateFileIo},
+	state_initializer::InitializeState,
+};
+use itp_time_utils::now_as_millis;
+use itp_types::{
+	canary::{CanaryReport, CanaryStep},
+	Balance, ShardIdentifier,
+};
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use sgx_types::sgx_status_t;
+use sp_core::{blake2_256, ed25519, Pair};
+use sp_runtime::MultiAddress;
+use std::{boxed::Box, format, slice, string::String, sync::Arc, vec::Vec};
+
+/// Free balance of each canary account when the canary shard is initialized.
+const CANARY_ENDOWMENT: Balance = 1_000_000_000_000;
+
+/// Amount transferred every round, above the existential deposit.
+const CANARY_TRANSFER: Balance = 1_000_000;
+
+type StepOutcomes = Vec<(CanaryStep, Result<(), Vec<u8>>)>;
+
+#[no_mangle]
+pub unsafe extern "C" fn run_canary_round(
+	shard: *const u8,
+	shard_size: u32,
+	report: *mut u8,
+	report_size: u32,
+) -> sgx_status_t {
+	let mut shard_slice = slice::from_raw_parts(shard, shard_size as usize);
+	let shard = match ShardIdentifier::decode(&mut shard_slice) {
+		Ok(shard) => shard,
+		Err(e) => {
+			error!("Could not decode the canary shard: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	let canary_report = match run_canary_round_internal(shard) {
+		Ok(canary_report) => canary_report,
+		Err(e) => {
+			error!("Could not run a canary round: {:?}", e);
+			return e.into()
+		},
+	};
+
+	let report_slice = slice::from_raw_parts_mut(report, report_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(report_slice, canary_report.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Errors of the components the canary depends on abort the round, failed steps are reported.
+pub(crate) fn run_canary_round_internal(shard: ShardIdentifier) -> EnclaveResult<CanaryReport> {
+	let canary = Canary {
+		state_file_io: EnclaveStateFileIo::new(
+			GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?,
+			StateDir::new(get_base_path()?.join(CANARY_PATH)),
+		),
+		state_initializer: EnclaveStateInitializer::new(
+			GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?,
+		),
+		state_dir: StateDir::new(get_base_path()?.join(CANARY_PATH)),
+		shard,
+		mrenclave: GLOBAL_OCALL_API_COMPONENT.get()?.get_mrenclave_of_self()?.m,
+		accounts: [b"canary_a", b"canary_b"]
+			.map(|name| ed25519::Pair::from_seed(&blake2_256(&(name, shard).encode()))),
+	};
+	Ok(canary.run(now_as_millis()))
+}
+
+/// Works on its own state files, so neither the state handler nor the served shard are touched.
+struct Canary {
+	state_file_io: EnclaveStateFileIo,
+	state_initializer: EnclaveStateInitializer,
+	state_dir: StateDir,
+	shard: ShardIdentifier,
+	mrenclave: [u8; 32],
+	accounts: [ed25519::Pair; 2],
+}
+
+impl Canary {
+	fn run(&self, timestamp: u64) -> CanaryReport {
+		let mut outcomes = Vec::new();
+		let mut round = 0;
+		if let Some((state_id, mut state)) =
+			record(&mut outcomes, CanaryStep::LoadState, self.load_state(timestamp))
+		{
+			round = self.round(&mut state);
+			// The funds go back and forth between the accounts.
+			let (sender, receiver) = match round % 2 {
+				0 => (&self.accounts[0], &self.accounts[1]),
+				_ => (&self.accounts[1], &self.accounts[0]),
+			};
+			if let Some(expected_balance) = record(
+				&mut outcomes,
+				CanaryStep::Transfer,
+				self.transfer(&mut state, sender, receiver),
+			) {
+				if record(
+					&mut outcomes,
+					CanaryStep::Getter,
+					self.getter(&mut state, receiver, expected_balance),
+				)
+				.is_some()
+				{
+					record(&mut outcomes, CanaryStep::SealState, self.seal_state(state_id, &state));
+				}
+			}
+		}
+		CanaryReport { round, timestamp, outcomes }
+	}
+
+	/// Unseals the latest state of the shard, or seals a fresh one with endowed accounts.
+	fn load_state(&self, timestamp: u64) -> Result<(u128, StfState), String> {
+		let state_ids = if self.state_dir.shard_exists(&self.shard) {
+			self.state_file_io
+				.list_state_ids_for_shard(&self.shard)
+				.map_err(|e| format!("{:?}", e))?
+		} else {
+			Vec::new()
+		};
+		if let Some(state_id) = state_ids.into_iter().max() {
+			let state =
+				self.state_file_io.load(&self.shard, state_id).map_err(|e| format!("{:?}", e))?;
+			return Ok((state_id, state))
+		}
+
+		info!("Initializing the canary shard {:?}", self.shard);
+		let mut state = self.state_initializer.initialize().map_err(|e| format!("{:?}", e))?;
+		for account in &self.accounts {
+			let account: AccountId = account.public().into();
+			state.execute_with(|| {
+				ita_sgx_runtime::BalancesCall::<Runtime>::force_set_balance {
+					who: MultiAddress::Id(account),
+					new_free: CANARY_ENDOWMENT,
+				}
+				.dispatch_bypass_filter(ita_sgx_runtime::RuntimeOrigin::root())
+				.map_err(|e| format!("could not endow the canary account: {:?}", e.error))
+			})?;
+		}
+		state.prune_state_diff();
+
+		let state_id = timestamp as u128;
+		self.state_file_io
+			.initialize_shard(&self.shard, state_id, &state)
+			.map_err(|e| format!("{:?}", e))?;
+		Ok((state_id, state))
+	}
+
+	/// Every round increments the nonce of one of the accounts.
+	fn round(&self, state: &mut StfState) -> u64 {
+		let accounts: Vec<AccountId> =
+			self.accounts.iter().map(|account| account.public().into()).collect();
+		state.execute_with(|| {
+			accounts.iter().map(|account| u64::from(System::account_nonce(account))).sum()
+		})
+	}
+
+	/// Executes a signed transfer, returns the balance the receiver is expected to have.
+	fn transfer(
+		&self,
+		state: &mut StfState,
+		sender: &ed25519::Pair,
+		receiver: &ed25519::Pair,
+	) -> Result<Balance, String> {
+		let sender_account: AccountId = sender.public().into();
+		let receiver_account: AccountId = receiver.public().into();
+		let (nonce, receiver_balance) = state.execute_with(|| {
+			(System::account_nonce(&sender_account), System::account(&receiver_account).data.free)
+		});
+		let call = TrustedCall::balance_transfer(sender_account, receiver_account, CANARY_TRANSFER)
+			.sign(&KeyPair::Ed25519(Box::new(sender.clone())), nonce, &self.mrenclave, &self.shard);
+		if !call.verify_signature(&self.mrenclave, &self.shard) {
+			return Err("invalid signature of the trusted call".into())
+		}
+
+		EnclaveStf::execute_call(
+			state,
+			call,
+			&mut Vec::new(),
+			Arc::new(EnclaveNodeMetadataRepository::default()),
+		)
+		.map_err(|e| format!("{:?}", e))?;
+		state.prune_state_diff();
+		Ok(receiver_balance + CANARY_TRANSFER)
+	}
+
+	/// Queries the balance of the receiver after the transfer.
+	fn getter(
+		&self,
+		state: &mut StfState,
+		receiver: &ed25519::Pair,
+		expected_balance: Balance,
+	) -> Result<(), String> {
+		let getter = TrustedGetter::free_balance(receiver.public().into())
+			.sign(&KeyPair::Ed25519(Box::new(receiver.clone())));
+		if !getter.verify_signature() {
+			return Err("invalid signature of the trusted getter".into())
+		}
+
+		let balance = EnclaveStf::execute_getter(state, Getter::trusted(getter))
+			.and_then(|encoded| Balance::decode(&mut encoded.as_slice()).ok());
+		match balance {
+			Some(balance) if balance == expected_balance => Ok(()),
+			balance => Err(format!(
+				"unexpected balance of the receiver: {:?} instead of {}",
+				balance, expected_balance
+			)),
+		}
+	}
+
+	/// Seals the state as a new state file and removes the previous one, so the canary never
+	/// accumulates state files.
+	fn seal_state(&self, state_id: u128, state: &StfState) -> Result<(), String> {
+		let next_state_id = state_id + 1;
+		self.state_file_io
+			.write(&self.shard, next_state_id, state)
+			.map_err(|e| format!("{:?}", e))?;
+		std::fs::remove_file(self.state_dir.state_file_path(&self.shard, state_id))
+			.map_err(|e| format!("could not remove the previous state file: {:?}", e))
+	}
+}
+
+/// Records the outcome of the step, returns the value of a passed step.
+fn record<T>(
+	outcomes: &mut StepOutcomes,
+	step: CanaryStep,
+	result: Result<T, String>,
+) -> Option<T> {
+	match result {
+		Ok(value) => {
+			outcomes.push((step, Ok(())));
+			Some(value)
+		},
+		Err(e) => {
+			warn!("Canary step {:?} failed: {}", step, e);
+			outcomes.push((step, Err(e.into_bytes())));
+			None
+		},
+	}
+}
@@ -15,9 +15,18 @@
 
 */
 
+//! Errors of the enclave runtime.
+//!
+//! Every error belongs to an [`ErrorDomain`], which determines the status returned by the ECALLs
+//! and the stable error code every RPC method of the enclave fails with, see
+//! [`itp_rpc::error_code`]. Context added with [`ErrorContext`] is kept around the original error
+//! instead of replacing it with a string.
+
 use derive_more::From;
+use itp_rpc::error_code::*;
+use jsonrpc_core as rpc_core;
 use sgx_types::{sgx_quote3_error_t, sgx_status_t};
-use std::{boxed::Box, result::Result as StdResult, string::String};
+use std::{boxed::Box, format, result::Result as StdResult, string::String};
 
 pub type Result<T> = StdResult<T, Error>;
 
@@ -37,6 +46,7 @@ pub enum Error {
 	Consensus(its_sidechain::consensus_common::Error),
 	Stf(String),
 	StfStateHandler(itp_stf_state_handler::error::Error),
+	StateObserver(itp_stf_state_observer::error::Error),
 	StfExecution(itp_stf_executor::error::Error),
 	ParentchainBlockImportDispatch(itc_parentchain::block_import_dispatcher::error::Error),
 	ExpectedTriggeredImportDispatcher,
@@ -53,27 +63,156 @@ pub enum Error {
 	Attestation(itp_attestation_handler::error::Error),
 	Metadata(itp_node_api_metadata::error::Error),
 	BufferError(itp_utils::buffer::BufferError),
+	/// Malformed, unauthorized or otherwise rejected RPC request.
+	#[from(ignore)]
+	InvalidRequest(String),
+	/// The request can't be served right now, but may be retried later.
+	#[from(ignore)]
+	Unavailable(String),
+	/// What was done when the `source` error occurred.
+	#[from(ignore)]
+	Context {
+		context: String,
+		source: Box<Error>,
+	},
 	Other(Box<dyn std::error::Error>),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorDomain {
+	/// Loading, decrypting or persisting the state of a shard, or executing the STF on it.
+	State,
+	/// Keys, encryption and signatures.
+	Crypto,
+	/// The pool of trusted operations.
+	Pool,
+	/// Malformed, unauthorized or rejected requests and responses.
+	Rpc,
+	/// Remote attestation and quotes.
+	Attestation,
+	/// Light clients, block import and storage proofs of the parentchains.
+	Parentchain,
+	/// Sidechain consensus and block import.
+	Sidechain,
+	/// Overload, block authoring and isolated shards, which keep requests from being served.
+	Unavailable,
+	/// Uninitialized components, poisoned locks and everything else.
+	Internal,
+}
+
+impl ErrorDomain {
+	pub fn rpc_error_code(&self) -> i64 {
+		match self {
+			ErrorDomain::State => STATE_ERROR,
+			ErrorDomain::Crypto => CRYPTO_ERROR,
+			ErrorDomain::Pool => POOL_ERROR,
+			ErrorDomain::Rpc => RPC_ERROR,
+			ErrorDomain::Attestation => ATTESTATION_ERROR,
+			ErrorDomain::Parentchain => PARENTCHAIN_ERROR,
+			ErrorDomain::Sidechain => SIDECHAIN_ERROR,
+			ErrorDomain::Unavailable => UNAVAILABLE_ERROR,
+			ErrorDomain::Internal => INTERNAL_ERROR,
+		}
+	}
+
+	fn sgx_status(&self) -> sgx_status_t {
+		match self {
+			ErrorDomain::State => sgx_status_t::SGX_ERROR_INVALID_STATE,
+			ErrorDomain::Rpc => sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+			_ => sgx_status_t::SGX_ERROR_UNEXPECTED,
+		}
+	}
+}
+
+impl Error {
+	pub fn domain(&self) -> ErrorDomain {
+		match self {
+			Error::Codec(_) | Error::BufferError(_) | Error::InvalidRequest(_) => ErrorDomain::Rpc,
+			Error::Crypto(_) => ErrorDomain::Crypto,
+			Error::TopPoolAuthor(_) => ErrorDomain::Pool,
+			Error::Stf(_)
+			| Error::StfStateHandler(_)
+			| Error::StateObserver(_)
+			| Error::StfExecution(_) => ErrorDomain::State,
+			Error::SgxQuote(_) | Error::Attestation(_) => ErrorDomain::Attestation,
+			Error::ChainStorage(_)
+			| Error::ExtrinsicsFactory(_)
+			| Error::LightClient(_)
+			| Error::NodeMetadataProvider(_)
+			| Error::ParentchainBlockImportDispatch(_)
+			| Error::ExpectedTriggeredImportDispatcher
+			| Error::CouldNotDispatchBlockImport
+			| Error::NoIntegriteeParentchainAssigned
+			| Error::NoTargetAParentchainAssigned
+			| Error::NoTargetBParentchainAssigned
+			| Error::ParentChainValidation(_)
+			| Error::ParentChainSync
+			| Error::Metadata(_) => ErrorDomain::Parentchain,
+			Error::Consensus(_) | Error::NoShardAssigned | Error::TooManyShardsAssigned =>
+				ErrorDomain::Sidechain,
+			Error::Unavailable(_) => ErrorDomain::Unavailable,
+			Error::Context { source, .. } => source.domain(),
+			Error::Sgx(_)
+			| Error::ComponentContainer(_)
+			| Error::IO(_)
+			| Error::PrimitivesAccess(_)
+			| Error::MutexAccess
+			| Error::Other(_) => ErrorDomain::Internal,
+		}
+	}
+
+	/// The original error, without the context added to it.
+	pub fn root_cause(&self) -> &Error {
+		match self {
+			Error::Context { source, .. } => source.root_cause(),
+			error => error,
+		}
+	}
+
+	/// The status of the SGX operation that failed, if the error is caused by one.
+	fn sgx_cause(&self) -> Option<sgx_status_t> {
+		match self.root_cause() {
+			Error::Sgx(status) => Some(*status),
+			Error::ChainStorage(itp_ocall_api::Error::Sgx(status)) => Some(*status),
+			Error::StfStateHandler(itp_stf_state_handler::error::Error::SgxError(status)) =>
+				Some(*status),
+			Error::Attestation(itp_attestation_handler::error::Error::Sgx(status)) => Some(*status),
+			_ => None,
+		}
+	}
+}
+
+/// Adds context to the error of a result, e.g. `load_state().context("loading the state")?`.
+pub trait ErrorContext<T> {
+	fn context(self, context: &str) -> Result<T>;
+}
+
+impl<T, E> ErrorContext<T> for StdResult<T, E>
+where
+	Error: From<E>,
+{
+	fn context(self, context: &str) -> Result<T> {
+		self.map_err(|e| Error::Context { context: context.into(), source: Box::new(e.into()) })
+	}
+}
+
 impl From<Error> for sgx_status_t {
 	/// return sgx_status for top level enclave functions
 	fn from(error: Error) -> sgx_status_t {
-		match error {
-			Error::Sgx(status) => status,
-			_ => {
-				log::error!("Returning error {:?} as sgx unexpected.", error);
-				sgx_status_t::SGX_ERROR_UNEXPECTED
-			},
+		if let Some(status) = error.sgx_cause() {
+			return status
 		}
+		let domain = error.domain();
+		log::error!("Returning {:?} error {:?} as {:?}.", domain, error, domain.sgx_status());
+		domain.sgx_status()
 	}
 }
 
 impl From<Error> for sgx_quote3_error_t {
 	/// return sgx_quote error
 	fn from(error: Error) -> sgx_quote3_error_t {
-		match error {
-			Error::SgxQuote(status) => status,
+		match error.root_cause() {
+			Error::SgxQuote(status) => *status,
 			_ => {
 				log::error!("Returning error {:?} as sgx unexpected.", error);
 				sgx_quote3_error_t::SGX_QL_ERROR_UNEXPECTED
@@ -82,8 +221,51 @@ impl From<Error> for sgx_quote3_error_t {
 	}
 }
 
+impl From<Error> for rpc_core::Error {
+	fn from(error: Error) -> Self {
+		let domain = error.domain();
+		rpc_core::Error {
+			code: rpc_core::ErrorCode::ServerError(domain.rpc_error_code()),
+			message: format!("{:?} error", domain),
+			data: Some(format!("{:?}", error).into()),
+		}
+	}
+}
+
 impl<T> From<Error> for StdResult<T, Error> {
 	fn from(error: Error) -> StdResult<T, Error> {
 		Err(error)
 	}
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+
+	pub fn context_keeps_the_domain_and_the_sgx_status_of_the_error() {
+		let result: StdResult<(), _> =
+			Err(itp_stf_state_handler::error::Error::InvalidShard(Default::default()));
+		let error = result.context("loading the state").unwrap_err();
+
+		assert_eq!(error.domain(), ErrorDomain::State);
+		assert!(matches!(error.root_cause(), Error::StfStateHandler(_)));
+		assert_eq!(sgx_status_t::from(error), sgx_status_t::SGX_ERROR_INVALID_STATE);
+
+		let error = Err::<(), _>(Error::Sgx(sgx_status_t::SGX_ERROR_OUT_OF_EPC))
+			.context("sealing the state")
+			.unwrap_err();
+		assert_eq!(sgx_status_t::from(error), sgx_status_t::SGX_ERROR_OUT_OF_EPC);
+	}
+
+	pub fn rpc_error_carries_the_code_of_the_domain() {
+		let error = Err::<(), _>(Error::Unavailable("Worker is overloaded".into()))
+			.context("executing the getter")
+			.unwrap_err();
+		let rpc_error = rpc_core::Error::from(error);
+		assert_eq!(rpc_error.code, rpc_core::ErrorCode::ServerError(UNAVAILABLE_ERROR));
+		assert_eq!(rpc_error.message, "Unavailable error");
+
+		let rpc_error = rpc_core::Error::from(Error::InvalidRequest("Missing param".into()));
+		assert_eq!(rpc_error.code, rpc_core::ErrorCode::ServerError(RPC_ERROR));
+	}
+}
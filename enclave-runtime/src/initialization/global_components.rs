@@ -27,7 +27,12 @@ use crate::{
 		target_b_parachain::TargetBParachainHandler, target_b_solochain::TargetBSolochainHandler,
 	},
 	ocall::OcallApi,
-	rpc::rpc_response_channel::RpcResponseChannel,
+	rpc::{
+		account_watch_subscriptions::AccountWatchSubscriptions,
+		rpc_response_channel::RpcResponseChannel,
+	},
+	shard_configuration::ShardConfigurationHasher,
+	shard_isolation::RejectIsolatedShards,
 	tls_ra::seal_handler::SealHandler,
 };
 use ita_parentchain_interface::{integritee, target_a, target_b};
@@ -56,15 +61,19 @@ use itp_attestation_handler::IntelAttestationHandler;
 use itp_component_container::ComponentContainer;
 use itp_extrinsics_factory::ExtrinsicsFactory;
 use itp_import_queue::ImportQueue;
+use itp_key_ceremony::KeyCeremonyCoordinator;
 use itp_node_api::{
 	api_client::PairSignature,
 	metadata::{provider::NodeMetadataRepository, NodeMetadata},
 };
 use itp_nonce_cache::NonceCache;
-use itp_sgx_crypto::{key_repository::KeyRepository, Aes, AesSeal, Ed25519Seal, Rsa3072Seal};
+use itp_sgx_crypto::{
+	key_repository::KeyRepository, session_key::EphemeralKeyGenerator, Aes, AesSeal, Ed25519Seal,
+	Rsa3072Seal,
+};
 use itp_stf_executor::{
-	enclave_signer::StfEnclaveSigner, executor::StfExecutor, getter_executor::GetterExecutor,
-	state_getter::StfStateGetter,
+	call_validator::CallValidator, enclave_signer::StfEnclaveSigner, executor::StfExecutor,
+	getter_executor::GetterExecutor, state_getter::StfStateGetter,
 };
 use itp_stf_primitives::types::{Hash, TrustedOperation};
 use itp_stf_state_handler::{
@@ -76,6 +85,10 @@ use itp_top_pool::basic_pool::BasicPool;
 use itp_top_pool_author::{
 	api::SidechainApi,
 	author::{Author, AuthorTopFilter},
+	load_shedding::LoadShedder,
+	maintenance::MaintenanceSchedule,
+	persistence::PersistenceExclusions,
+	quarantine::PayloadQuarantine,
 };
 use itp_types::{Block as ParentchainBlock, SignedBlock as SignedParentchainBlock};
 use its_primitives::{
@@ -85,7 +98,11 @@ use its_primitives::{
 use its_sidechain::{
 	aura::block_importer::BlockImporter as SidechainBlockImporter,
 	block_composer::BlockComposer,
-	consensus_common::{BlockImportConfirmationHandler, BlockImportQueueWorker, PeerBlockSync},
+	consensus_common::{
+		BlockImportConfirmationHandler, BlockImportQueueWorker, GetterAdmission, PeerBlockSync,
+		ShardIsolation, SlaMetricsRecorder, StaleShardDetector,
+	},
+	rpc_handler::{account_watch::AccountWatches, shard_routing::ShardRoutes},
 };
 use lazy_static::lazy_static;
 use sgx_crypto_helper::rsa3072::Rsa3072KeyPair;
@@ -111,6 +128,8 @@ pub type EnclaveStateHandler =
 	StateHandler<EnclaveStateSnapshotRepository, EnclaveStateObserver, EnclaveStateInitializer>;
 pub type EnclaveGetterExecutor =
 	GetterExecutor<EnclaveStateObserver, StfStateGetter<EnclaveStf>, Getter>;
+pub type EnclaveCallValidator =
+	RejectIsolatedShards<CallValidator<EnclaveStateObserver, EnclaveStf>>;
 pub type EnclaveOCallApi = OcallApi;
 pub type EnclaveNodeMetadataRepository = NodeMetadataRepository<NodeMetadata>;
 pub type EnclaveStfExecutor = StfExecutor<
@@ -134,8 +153,13 @@ pub type EnclaveAttestationHandler =
 	IntelAttestationHandler<EnclaveOCallApi, EnclaveSigningKeyRepository>;
 
 pub type EnclaveRpcConnectionRegistry = ConnectionRegistry<Hash, ConnectionToken>;
-pub type EnclaveRpcWsHandler =
-	RpcWsHandler<RpcWatchExtractor<Hash>, EnclaveRpcConnectionRegistry, Hash>;
+pub type EnclaveRpcWsHandler = RpcWsHandler<
+	RpcWatchExtractor<Hash>,
+	EnclaveRpcConnectionRegistry,
+	AccountWatchSubscriptions,
+	EphemeralKeyGenerator,
+	Hash,
+>;
 pub type EnclaveWebSocketServer = TungsteniteWsServer<EnclaveRpcWsHandler, FromFileConfigProvider>;
 pub type EnclaveRpcResponder = RpcResponder<EnclaveRpcConnectionRegistry, Hash, RpcResponseChannel>;
 pub type EnclaveSidechainApi = SidechainApi<ParentchainBlock, EnclaveTrustedCallSigned>;
@@ -322,6 +346,7 @@ pub type EnclaveBlockImportConfirmationHandler = BlockImportConfirmationHandler<
 	EnclaveNodeMetadataRepository,
 	EnclaveExtrinsicsFactory,
 	EnclaveValidatorAccessor,
+	ShardConfigurationHasher,
 >;
 pub type EnclaveSidechainBlockSyncer = PeerBlockSync<
 	ParentchainBlock,
@@ -406,6 +431,58 @@ pub static GLOBAL_STATE_OBSERVER_COMPONENT: ComponentContainer<EnclaveStateObser
 pub static GLOBAL_TOP_POOL_AUTHOR_COMPONENT: ComponentContainer<EnclaveTopPoolAuthor> =
 	ComponentContainer::new("top_pool_author");
 
+/// Sealed archive of the shielded payloads the TOP pool author rejected.
+pub static GLOBAL_PAYLOAD_QUARANTINE_COMPONENT: ComponentContainer<PayloadQuarantine> =
+	ComponentContainer::new("payload quarantine");
+
+/// Maintenance windows during which the TOP pool author defers the execution of trusted calls.
+pub static GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT: ComponentContainer<MaintenanceSchedule> =
+	ComponentContainer::new("maintenance schedule");
+
+/// Degrades the service of the worker under sustained overload.
+pub static GLOBAL_LOAD_SHEDDER_COMPONENT: ComponentContainer<LoadShedder> =
+	ComponentContainer::new("load shedder");
+
+/// Shards lagging the sidechain blocks confirmed on the parentchain, which are not authored for.
+pub static GLOBAL_STALE_SHARD_DETECTOR_COMPONENT: ComponentContainer<StaleShardDetector> =
+	ComponentContainer::new("stale shard detector");
+
+/// Shards disabled after an unrecoverable error, which are neither imported nor authored for.
+pub static GLOBAL_SHARD_ISOLATION_COMPONENT: ComponentContainer<ShardIsolation> =
+	ComponentContainer::new("shard isolation");
+
+/// Defers the non-urgent getters while a block is authored.
+pub static GLOBAL_GETTER_ADMISSION_COMPONENT: ComponentContainer<GetterAdmission> =
+	ComponentContainer::new("getter admission");
+
+/// Slots, authored blocks and missed slots of the current SLA period, and the last signed digest.
+pub static GLOBAL_SLA_METRICS_RECORDER_COMPONENT: ComponentContainer<SlaMetricsRecorder> =
+	ComponentContainer::new("SLA metrics recorder");
+
+/// Trusted call types the TOP pool author keeps out of the payload quarantine and the logs.
+pub static GLOBAL_PERSISTENCE_EXCLUSIONS_COMPONENT: ComponentContainer<PersistenceExclusions> =
+	ComponentContainer::new("persistence exclusions");
+
+/// Routes of the shards served by other worker instances, requests for them are redirected.
+pub static GLOBAL_SHARD_ROUTES_COMPONENT: ComponentContainer<ShardRoutes> =
+	ComponentContainer::new("shard routes");
+
+/// Watch-only registrations of exchange deposit accounts.
+pub static GLOBAL_ACCOUNT_WATCHES_COMPONENT: ComponentContainer<AccountWatches> =
+	ComponentContainer::new("account watches");
+
+/// Pushes notifications to the watching RPC connections.
+pub static GLOBAL_RPC_RESPONDER_COMPONENT: ComponentContainer<EnclaveRpcResponder> =
+	ComponentContainer::new("rpc responder");
+
+/// Operator quorums and sealed trail of the key ceremonies.
+pub static GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT: ComponentContainer<KeyCeremonyCoordinator> =
+	ComponentContainer::new("key ceremony coordinator");
+
+/// State file I/O, re-encrypts the states when the state key is rotated.
+pub static GLOBAL_STATE_FILE_IO_COMPONENT: ComponentContainer<EnclaveStateFileIo> =
+	ComponentContainer::new("state file io");
+
 /// attestation handler
 pub static GLOBAL_ATTESTATION_HANDLER_COMPONENT: ComponentContainer<EnclaveAttestationHandler> =
 	ComponentContainer::new("Attestation handler");
@@ -20,25 +20,37 @@ pub mod parentchain;
 use crate::{
 	error::{Error, Result as EnclaveResult},
 	initialization::global_components::{
-		EnclaveBlockImportConfirmationHandler, EnclaveGetterExecutor, EnclaveLightClientSeal,
-		EnclaveOCallApi, EnclaveRpcConnectionRegistry, EnclaveRpcResponder,
+		EnclaveBlockImportConfirmationHandler, EnclaveCallValidator, EnclaveGetterExecutor,
+		EnclaveLightClientSeal, EnclaveOCallApi, EnclaveRpcConnectionRegistry, EnclaveRpcResponder,
 		EnclaveShieldingKeyRepository, EnclaveSidechainApi, EnclaveSidechainBlockImportQueue,
 		EnclaveSidechainBlockImportQueueWorker, EnclaveSidechainBlockImporter,
 		EnclaveSidechainBlockSyncer, EnclaveStateFileIo, EnclaveStateHandler,
 		EnclaveStateInitializer, EnclaveStateObserver, EnclaveStateSnapshotRepository,
 		EnclaveStfEnclaveSigner, EnclaveTopPool, EnclaveTopPoolAuthor,
-		GLOBAL_ATTESTATION_HANDLER_COMPONENT, GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL,
-		GLOBAL_OCALL_API_COMPONENT, GLOBAL_RPC_WS_HANDLER_COMPONENT,
+		GLOBAL_ACCOUNT_WATCHES_COMPONENT, GLOBAL_ATTESTATION_HANDLER_COMPONENT,
+		GLOBAL_GETTER_ADMISSION_COMPONENT, GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL,
+		GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT, GLOBAL_LOAD_SHEDDER_COMPONENT,
+		GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_PAYLOAD_QUARANTINE_COMPONENT, GLOBAL_PERSISTENCE_EXCLUSIONS_COMPONENT,
+		GLOBAL_RPC_RESPONDER_COMPONENT, GLOBAL_RPC_WS_HANDLER_COMPONENT,
+		GLOBAL_SHARD_ISOLATION_COMPONENT, GLOBAL_SHARD_ROUTES_COMPONENT,
 		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT,
 		GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT, GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT,
 		GLOBAL_SIDECHAIN_IMPORT_QUEUE_WORKER_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
-		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
-		GLOBAL_STATE_OBSERVER_COMPONENT, GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL,
+		GLOBAL_SLA_METRICS_RECORDER_COMPONENT, GLOBAL_STALE_SHARD_DETECTOR_COMPONENT,
+		GLOBAL_STATE_FILE_IO_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
+		GLOBAL_STATE_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_OBSERVER_COMPONENT,
+		GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL,
 		GLOBAL_TARGET_B_PARENTCHAIN_LIGHT_CLIENT_SEAL, GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
 		GLOBAL_WEB_SOCKET_SERVER_COMPONENT,
 	},
-	ocall::OcallApi,
-	rpc::{rpc_response_channel::RpcResponseChannel, worker_api_direct::public_api_rpc_handler},
+	key_ceremony::recover_state_key_rotation,
+	ocall::{check_disk_space, OcallApi},
+	rpc::{
+		account_watch_subscriptions::AccountWatchSubscriptions,
+		rpc_response_channel::RpcResponseChannel, worker_api_direct::public_api_rpc_handler,
+	},
+	shard_configuration::ShardConfigurationHasher,
 	utils::{
 		get_extrinsic_factory_from_integritee_solo_or_parachain,
 		get_node_metadata_repository_from_integritee_solo_or_parachain,
@@ -60,31 +72,88 @@ use itc_tls_websocket_server::{
 };
 use itp_attestation_handler::IntelAttestationHandler;
 use itp_component_container::{ComponentGetter, ComponentInitializer};
+use itp_enclave_upgrade::GLOBAL_UPGRADE_COORDINATOR;
+use itp_key_ceremony::KeyCeremonyCoordinator;
 use itp_primitives_cache::GLOBAL_PRIMITIVES_CACHE;
-use itp_settings::files::{
-	INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, STATE_SNAPSHOTS_CACHE_SIZE,
-	TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+use itp_settings::{
+	files::{
+		CANARY_PATH, ENCLAVE_UPGRADE_SCHEDULE_FILE, INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+		KEY_CEREMONY_TRAIL_FILE, PAYLOAD_QUARANTINE_FILE, PENDING_STATE_KEY_PATH, RA_API_KEY_FILE,
+		RA_DUMP_CERT_DER_FILE, RA_SPID_FILE, SHARDS_PATH, STAGED_STATES_PATH,
+		STATE_SNAPSHOTS_CACHE_SIZE, TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+		TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+	},
+	sidechain::SLA_METRICS_PERIOD_BLOCKS,
+	worker::KEY_CEREMONY_TRAIL_MAX_SIZE,
 };
 use itp_sgx_crypto::{
 	get_aes_repository, get_ed25519_repository, get_rsa3072_repository, key_repository::AccessKey,
+	session_key::EphemeralKeyGenerator, AES_KEY_FILE_AND_INIT_V, RSA3072_SEALED_KEY_FILE,
+	SEALED_SIGNER_SEED_FILE,
 };
+use itp_stf_executor::call_validator::CallValidator;
 use itp_stf_state_handler::{
 	file_io::StateDir, handle_state::HandleState, query_shard_state::QueryShardState,
 	state_snapshot_repository::VersionedStateAccess,
 	state_snapshot_repository_loader::StateSnapshotRepositoryLoader, StateHandler,
 };
 use itp_top_pool::pool::Options as PoolOptions;
-use itp_top_pool_author::author::AuthorTopFilter;
+use itp_top_pool_author::{
+	author::AuthorTopFilter, load_shedding::LoadShedder, maintenance::MaintenanceSchedule,
+	persistence::PersistenceExclusions, quarantine::PayloadQuarantine,
+};
 use itp_types::{parentchain::ParentchainId, ShardIdentifier};
-use its_sidechain::block_composer::BlockComposer;
+use its_sidechain::{
+	block_composer::BlockComposer,
+	consensus_common::{GetterAdmission, ShardIsolation, SlaMetricsRecorder, StaleShardDetector},
+	rpc_handler::{account_watch::AccountWatches, shard_routing::ShardRoutes},
+};
 use log::*;
 use sp_core::crypto::Pair;
-use std::{collections::HashMap, path::PathBuf, string::String, sync::Arc};
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	string::String,
+	sync::Arc,
+};
+
+/// Restricts the file access of the enclave to the files it manages itself.
+pub(crate) fn restrict_file_access(base_dir: &Path) -> EnclaveResult<()> {
+	let allowed_path_prefixes = vec![
+		base_dir.join(SHARDS_PATH),
+		base_dir.join(CANARY_PATH),
+		base_dir.join(STAGED_STATES_PATH),
+		base_dir.join(SEALED_SIGNER_SEED_FILE),
+		base_dir.join(RSA3072_SEALED_KEY_FILE),
+		base_dir.join(AES_KEY_FILE_AND_INIT_V),
+		base_dir.join(PENDING_STATE_KEY_PATH),
+		base_dir.join(ENCLAVE_UPGRADE_SCHEDULE_FILE),
+		base_dir.join(PAYLOAD_QUARANTINE_FILE),
+		base_dir.join(KEY_CEREMONY_TRAIL_FILE),
+		// The light-client db directories also contain the db backups.
+		base_dir.join(INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
+		base_dir.join(TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
+		base_dir.join(TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
+		// The attestation files are accessed relative to the working directory.
+		PathBuf::from(RA_DUMP_CERT_DER_FILE),
+		PathBuf::from(RA_SPID_FILE),
+		PathBuf::from(RA_API_KEY_FILE),
+	];
+	Ok(itp_sgx_io::set_allowed_path_prefixes(allowed_path_prefixes)?)
+}
+
 pub(crate) fn init_enclave(
 	mu_ra_url: String,
 	untrusted_worker_url: String,
 	base_dir: PathBuf,
 ) -> EnclaveResult<()> {
+	restrict_file_access(&base_dir)?;
+	itp_sgx_io::set_disk_space_check(check_disk_space)?;
+
+	GLOBAL_UPGRADE_COORDINATOR
+		.use_sealed_file(base_dir.join(ENCLAVE_UPGRADE_SCHEDULE_FILE))
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+
 	let signing_key_repository = Arc::new(get_ed25519_repository(base_dir.clone())?);
 	GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.initialize(signing_key_repository.clone());
 	let signer = signing_key_repository.retrieve_key()?;
@@ -96,8 +165,40 @@ pub(crate) fn init_enclave(
 	// Create the aes key that is used for state encryption such that a key is always present in tests.
 	// It will be overwritten anyway if mutual remote attestation is performed with the primary worker.
 	let state_key_repository = Arc::new(get_aes_repository(base_dir.clone())?);
+	recover_state_key_rotation(&base_dir, state_key_repository.as_ref())?;
 	GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.initialize(state_key_repository.clone());
 
+	let payload_quarantine = Arc::new(PayloadQuarantine::default());
+	payload_quarantine
+		.use_sealed_file(base_dir.join(PAYLOAD_QUARANTINE_FILE))
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+	GLOBAL_PAYLOAD_QUARANTINE_COMPONENT.initialize(payload_quarantine.clone());
+
+	let key_ceremony_coordinator =
+		Arc::new(KeyCeremonyCoordinator::new(KEY_CEREMONY_TRAIL_MAX_SIZE, signer.public().into()));
+	key_ceremony_coordinator
+		.use_sealed_file(base_dir.join(KEY_CEREMONY_TRAIL_FILE))
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+	GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT.initialize(key_ceremony_coordinator);
+
+	GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT.initialize(Arc::new(MaintenanceSchedule::default()));
+
+	let load_shedder = Arc::new(LoadShedder::default());
+	GLOBAL_LOAD_SHEDDER_COMPONENT.initialize(load_shedder.clone());
+
+	GLOBAL_STALE_SHARD_DETECTOR_COMPONENT.initialize(Arc::new(StaleShardDetector::default()));
+
+	let shard_isolation = Arc::new(ShardIsolation::default());
+	GLOBAL_SHARD_ISOLATION_COMPONENT.initialize(shard_isolation.clone());
+
+	GLOBAL_GETTER_ADMISSION_COMPONENT.initialize(Arc::new(GetterAdmission::default()));
+
+	GLOBAL_SLA_METRICS_RECORDER_COMPONENT
+		.initialize(Arc::new(SlaMetricsRecorder::new(SLA_METRICS_PERIOD_BLOCKS)));
+
+	let persistence_exclusions = Arc::new(PersistenceExclusions::default());
+	GLOBAL_PERSISTENCE_EXCLUSIONS_COMPONENT.initialize(persistence_exclusions.clone());
+
 	let integritee_light_client_seal = Arc::new(EnclaveLightClientSeal::new(
 		base_dir.join(INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
 		ParentchainId::Integritee,
@@ -118,6 +219,7 @@ pub(crate) fn init_enclave(
 
 	let state_file_io =
 		Arc::new(EnclaveStateFileIo::new(state_key_repository, StateDir::new(base_dir)));
+	GLOBAL_STATE_FILE_IO_COMPONENT.initialize(state_file_io.clone());
 	let state_initializer =
 		Arc::new(EnclaveStateInitializer::new(shielding_key_repository.clone()));
 	let state_snapshot_repository_loader = StateSnapshotRepositoryLoader::<
@@ -171,13 +273,42 @@ pub(crate) fn init_enclave(
 		state_handler,
 		ocall_api.clone(),
 		shielding_key_repository.clone(),
+		payload_quarantine,
+		load_shedder,
+		persistence_exclusions,
+		Arc::new(EnclaveCallValidator::new(
+			shard_isolation,
+			CallValidator::new(state_observer.clone()),
+		)),
 	);
 	GLOBAL_TOP_POOL_AUTHOR_COMPONENT.initialize(top_pool_author.clone());
 
+	let shard_routes = Arc::new(ShardRoutes::default());
+	GLOBAL_SHARD_ROUTES_COMPONENT.initialize(shard_routes.clone());
+
+	let account_watches = Arc::new(AccountWatches::default());
+	GLOBAL_ACCOUNT_WATCHES_COMPONENT.initialize(account_watches.clone());
+	GLOBAL_RPC_RESPONDER_COMPONENT.initialize(Arc::new(EnclaveRpcResponder::new(
+		connection_registry.clone(),
+		Arc::new(RpcResponseChannel::default()),
+	)));
+
 	let getter_executor = Arc::new(EnclaveGetterExecutor::new(state_observer));
-	let io_handler =
-		public_api_rpc_handler(top_pool_author, getter_executor, shielding_key_repository);
-	let rpc_handler = Arc::new(RpcWsHandler::new(io_handler, watch_extractor, connection_registry));
+	let io_handler = public_api_rpc_handler(
+		top_pool_author,
+		getter_executor,
+		shielding_key_repository,
+		shard_routes,
+		account_watches.clone(),
+	);
+	let rpc_handler = Arc::new(RpcWsHandler::new(
+		io_handler,
+		watch_extractor,
+		connection_registry,
+		Arc::new(AccountWatchSubscriptions::new(account_watches)),
+		Arc::new(EphemeralKeyGenerator),
+		signer,
+	));
 	GLOBAL_RPC_WS_HANDLER_COMPONENT.initialize(rpc_handler);
 
 	let sidechain_block_import_queue = Arc::new(EnclaveSidechainBlockImportQueue::default());
@@ -234,6 +365,7 @@ pub(crate) fn init_enclave_sidechain_components() -> EnclaveResult<()> {
 			metadata_repository,
 			extrinsics_factory,
 			validator_accessor,
+			Arc::new(ShardConfigurationHasher::new(GLOBAL_STATE_HANDLER_COMPONENT.get()?)),
 		));
 
 	let sidechain_block_syncer = Arc::new(EnclaveSidechainBlockSyncer::new(
@@ -295,6 +427,10 @@ pub fn create_top_pool_author(
 	state_handler: Arc<EnclaveStateHandler>,
 	ocall_api: Arc<EnclaveOCallApi>,
 	shielding_key_repository: Arc<EnclaveShieldingKeyRepository>,
+	payload_quarantine: Arc<PayloadQuarantine>,
+	load_shedder: Arc<LoadShedder>,
+	persistence_exclusions: Arc<PersistenceExclusions>,
+	call_validator: Arc<EnclaveCallValidator>,
 ) -> Arc<EnclaveTopPoolAuthor> {
 	let response_channel = Arc::new(RpcResponseChannel::default());
 	let rpc_responder = Arc::new(EnclaveRpcResponder::new(connection_registry, response_channel));
@@ -309,5 +445,9 @@ pub fn create_top_pool_author(
 		state_handler,
 		shielding_key_repository,
 		ocall_api,
+		payload_quarantine,
+		load_shedder,
+		persistence_exclusions,
+		call_validator,
 	))
 }
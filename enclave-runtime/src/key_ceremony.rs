@@ -0,0 +1,336 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Operations on the state key that need the approval of the operators of the shards, see
+//! [`itp_types::key_ceremony`].
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	get_base_path,
+	initialization::global_components::{
+		GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT, GLOBAL_STATE_FILE_IO_COMPONENT,
+		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
+	},
+};
+use codec::{Decode, Encode};
+use ita_sgx_runtime::Sudo;
+use itp_component_container::ComponentGetter;
+use itp_settings::files::PENDING_STATE_KEY_PATH;
+use itp_sgx_crypto::{
+	key_repository::{AccessKey, MutateKey},
+	session_key::{EphemeralKeyGenerator, GenerateEphemeralKey, MessageDirection},
+	Aes, AesSeal, AesSealing, StateCrypto,
+};
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_sgx_io::{write as io_write, SealedIO};
+use itp_stf_state_handler::{
+	file_io::{StateDir, StateFileIo},
+	handle_state::HandleState,
+	query_shard_state::QueryShardState,
+};
+use itp_time_utils::now_as_millis;
+use itp_types::{
+	key_ceremony::{ApprovedKeyCeremony, EncryptedStateKeyEscrow, KeyCeremonyOperation},
+	ShardIdentifier,
+};
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use sgx_rand::{Rng, StdRng};
+use sgx_types::sgx_status_t;
+use std::{
+	format, fs,
+	path::{Path, PathBuf},
+	slice,
+	vec::Vec,
+};
+
+/// Extension appended to a state file re-encrypted with the new key of a rotation.
+const ROTATED_STATE_EXTENSION: &str = "rotated";
+
+#[no_mangle]
+pub unsafe extern "C" fn conduct_key_ceremony(
+	ceremony: *const u8,
+	ceremony_size: u32,
+	outcome: *mut u8,
+	outcome_size: u32,
+) -> sgx_status_t {
+	let mut ceremony_slice = slice::from_raw_parts(ceremony, ceremony_size as usize);
+	let ceremony = match ApprovedKeyCeremony::decode(&mut ceremony_slice) {
+		Ok(ceremony) => ceremony,
+		Err(e) => {
+			error!("Could not decode the key ceremony: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	let escrow = match conduct_key_ceremony_internal(&ceremony) {
+		Ok(escrow) => escrow,
+		Err(e) => {
+			warn!("Key ceremony #{} failed: {:?}", ceremony.request.ceremony_id, e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+
+	let outcome_slice = slice::from_raw_parts_mut(outcome, outcome_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(outcome_slice, escrow.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn export_key_ceremony_trail(
+	trail: *mut u8,
+	trail_size: u32,
+) -> sgx_status_t {
+	let key_ceremony_trail = match GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT.get() {
+		Ok(coordinator) => match coordinator.trail() {
+			Ok(trail) => trail,
+			Err(e) => {
+				error!("Could not read the key ceremony trail: {:?}", e);
+				return sgx_status_t::SGX_ERROR_UNEXPECTED
+			},
+		},
+		Err(e) => return Error::ComponentContainer(e).into(),
+	};
+
+	let trail_slice = slice::from_raw_parts_mut(trail, trail_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(trail_slice, key_ceremony_trail.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn issue_key_ceremony_nonce(nonce: *mut u8, nonce_size: u32) -> sgx_status_t {
+	let issued_nonce = match issue_key_ceremony_nonce_internal() {
+		Ok(nonce) => nonce,
+		Err(e) => {
+			error!("Could not issue a key ceremony nonce: {:?}", e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+
+	let nonce_slice = slice::from_raw_parts_mut(nonce, nonce_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(nonce_slice, issued_nonce.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Draws the nonce the next ceremony has to be signed over.
+fn issue_key_ceremony_nonce_internal() -> EnclaveResult<[u8; 32]> {
+	let mut nonce = [0u8; 32];
+	StdRng::new()?.fill_bytes(&mut nonce);
+	GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT
+		.get()?
+		.issue_nonce(nonce)
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+	Ok(nonce)
+}
+
+/// Conducts the ceremony if it is approved, returns the escrowed state key for an escrow.
+fn conduct_key_ceremony_internal(
+	ceremony: &ApprovedKeyCeremony,
+) -> EnclaveResult<Option<EncryptedStateKeyEscrow>> {
+	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+	let shards = state_handler.list_shards()?;
+	let shard_root = |shard: &ShardIdentifier| {
+		let (mut state, _) = state_handler.load_cloned(shard).ok()?;
+		state.execute_with(Sudo::key)
+	};
+
+	GLOBAL_KEY_CEREMONY_COORDINATOR_COMPONENT
+		.get()?
+		.conduct(ceremony, &shards, shard_root, now_as_millis(), |operation| {
+			match operation {
+				KeyCeremonyOperation::SetOperatorQuorum { .. } => Ok(None),
+				KeyCeremonyOperation::RotateStateKey => rotate_state_key().map(|_| None),
+				KeyCeremonyOperation::EscrowStateKey { recipient } =>
+					escrow_state_key(recipient).map(Some),
+			}
+			.map_err(|e| format!("{:?}", e))
+		})
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))
+}
+
+/// Replaces the state key and re-encrypts all states with it.
+///
+/// The re-encrypted states are written next to the originals, which stay readable with the old
+/// key. Sealing the new key as the pending key commits the rotation: only then the re-encrypted
+/// states replace the originals and the pending key replaces the state key. A rotation that is
+/// interrupted is rolled back or completed when the enclave starts, see
+/// [`recover_state_key_rotation`].
+fn rotate_state_key() -> EnclaveResult<()> {
+	let base_dir = get_base_path()?;
+	let state_dir = StateDir::new(base_dir.clone());
+	let state_file_io = GLOBAL_STATE_FILE_IO_COMPONENT.get()?;
+
+	let mut key = [0u8; 16];
+	let mut init_vec = [0u8; 16];
+	let mut rand = StdRng::new()?;
+	rand.fill_bytes(&mut key);
+	rand.fill_bytes(&mut init_vec);
+	let key = Aes::new(key, init_vec);
+
+	let mut rotated_states = 0;
+	for shard in state_file_io.list_shards()? {
+		for state_id in state_file_io.list_state_ids_for_shard(&shard)? {
+			let mut ciphertext = state_file_io.load(&shard, state_id)?.state().encode();
+			key.encrypt(&mut ciphertext)?;
+			io_write(
+				&ciphertext,
+				rotated_state_path(&state_dir.state_file_path(&shard, state_id)),
+			)?;
+			rotated_states += 1;
+		}
+	}
+
+	let pending_key_seal = pending_state_key_seal(&base_dir)?;
+	pending_key_seal.seal(&key)?;
+	complete_state_key_rotation(
+		&state_dir,
+		&pending_key_seal,
+		GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?.as_ref(),
+	)?;
+	info!("Rotated the state key and re-encrypted {} states", rotated_states);
+	Ok(())
+}
+
+/// Completes a state key rotation that was interrupted once it was committed, or discards the
+/// states re-encrypted by a rotation that was interrupted before. Has to run before any state is
+/// loaded.
+pub(crate) fn recover_state_key_rotation<KeyRepository: MutateKey<Aes>>(
+	base_dir: &Path,
+	key_repository: &KeyRepository,
+) -> EnclaveResult<()> {
+	let state_dir = StateDir::new(base_dir.to_path_buf());
+	let pending_key_seal = pending_state_key_seal(base_dir)?;
+	if pending_key_seal.exists() {
+		warn!("Completing an interrupted state key rotation");
+		return complete_state_key_rotation(&state_dir, &pending_key_seal, key_repository)
+	}
+	for rotated_state in rotated_state_files(&state_dir)? {
+		warn!("Discarding {:?} of an interrupted state key rotation", rotated_state);
+		fs::remove_file(rotated_state)?;
+	}
+	Ok(())
+}
+
+/// Replaces the states with the re-encrypted ones and the state key with the pending key. Can be
+/// repeated if it is interrupted.
+fn complete_state_key_rotation<KeyRepository: MutateKey<Aes>>(
+	state_dir: &StateDir,
+	pending_key_seal: &AesSeal,
+	key_repository: &KeyRepository,
+) -> EnclaveResult<()> {
+	let key = pending_key_seal.unseal()?;
+	for rotated_state in rotated_state_files(state_dir)? {
+		fs::rename(&rotated_state, rotated_state.with_extension(""))?;
+	}
+	key_repository.update_key(key)?;
+	fs::remove_file(pending_key_seal.path())?;
+	Ok(())
+}
+
+fn pending_state_key_seal(base_dir: &Path) -> EnclaveResult<AesSeal> {
+	let path = base_dir.join(PENDING_STATE_KEY_PATH);
+	fs::create_dir_all(&path)?;
+	Ok(AesSeal::new(path))
+}
+
+fn rotated_state_path(state_path: &Path) -> PathBuf {
+	let mut path = state_path.as_os_str().to_owned();
+	path.push(".");
+	path.push(ROTATED_STATE_EXTENSION);
+	PathBuf::from(path)
+}
+
+fn rotated_state_files(state_dir: &StateDir) -> EnclaveResult<Vec<PathBuf>> {
+	let mut rotated_states = Vec::new();
+	for shard in state_dir.list_shards()? {
+		for entry in fs::read_dir(state_dir.shard_path(&shard))? {
+			let path = entry?.path();
+			if path.extension().map_or(false, |extension| extension == ROTATED_STATE_EXTENSION) {
+				rotated_states.push(path);
+			}
+		}
+	}
+	Ok(rotated_states)
+}
+
+/// Encrypts the state key to the ephemeral key of the recipient.
+fn escrow_state_key(recipient: &[u8; 32]) -> EnclaveResult<EncryptedStateKeyEscrow> {
+	let state_key = GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let ephemeral_key = EphemeralKeyGenerator.generate()?;
+	let enclave_public = ephemeral_key.public();
+	let session_key = ephemeral_key.agree(recipient, recipient, &enclave_public)?;
+	let mut ciphertext = state_key.encode();
+	session_key
+		.message_key(MessageDirection::Response, 0)
+		.encrypt(&mut ciphertext)?;
+
+	Ok(EncryptedStateKeyEscrow { enclave_public, ciphertext })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+	use itp_sgx_crypto::mocks::KeyRepositoryMock;
+	use itp_sgx_io::read as io_read;
+	use itp_sgx_temp_dir::TempDir;
+
+	const OLD_STATE: [u8; 3] = [1, 2, 3];
+	const ROTATED_STATE: [u8; 3] = [4, 5, 6];
+
+	/// A shard with a state file and its re-encrypted copy of an interrupted rotation.
+	fn interrupted_rotation(base_dir: &Path) -> (PathBuf, KeyRepositoryMock<Aes>) {
+		let state_dir = StateDir::new(base_dir.to_path_buf());
+		let shard = ShardIdentifier::repeat_byte(1);
+		state_dir.create_shard(&shard).unwrap();
+		let state_path = state_dir.state_file_path(&shard, 1);
+		io_write(&OLD_STATE, &state_path).unwrap();
+		io_write(&ROTATED_STATE, rotated_state_path(&state_path)).unwrap();
+		(state_path, KeyRepositoryMock::new(Aes::new([1u8; 16], [0u8; 16])))
+	}
+
+	pub fn committed_state_key_rotation_is_completed_on_recovery() {
+		let temp_dir = TempDir::with_prefix("committed_state_key_rotation_is_completed").unwrap();
+		let (state_path, key_repository) = interrupted_rotation(temp_dir.path());
+		let new_key = Aes::new([2u8; 16], [0u8; 16]);
+		pending_state_key_seal(temp_dir.path()).unwrap().seal(&new_key).unwrap();
+
+		recover_state_key_rotation(temp_dir.path(), &key_repository).unwrap();
+
+		assert_eq!(io_read(&state_path).unwrap(), ROTATED_STATE.to_vec());
+		assert!(!rotated_state_path(&state_path).exists());
+		assert_eq!(key_repository.retrieve_key().unwrap(), new_key);
+		assert!(!pending_state_key_seal(temp_dir.path()).unwrap().exists());
+	}
+
+	pub fn uncommitted_state_key_rotation_is_discarded_on_recovery() {
+		let temp_dir = TempDir::with_prefix("uncommitted_state_key_rotation_is_discarded").unwrap();
+		let (state_path, key_repository) = interrupted_rotation(temp_dir.path());
+		let old_key = key_repository.retrieve_key().unwrap();
+
+		recover_state_key_rotation(temp_dir.path(), &key_repository).unwrap();
+
+		assert_eq!(io_read(&state_path).unwrap(), OLD_STATE.to_vec());
+		assert!(!rotated_state_path(&state_path).exists());
+		assert_eq!(key_repository.retrieve_key().unwrap(), old_key);
+	}
+}
@@ -41,6 +41,7 @@ use crate::{
 		GLOBAL_TARGET_B_SOLOCHAIN_HANDLER_COMPONENT,
 	},
 	rpc::worker_api_direct::sidechain_io_handler,
+	shard_isolation::process_unless_isolated,
 	utils::{
 		get_node_metadata_repository_from_integritee_solo_or_parachain,
 		get_node_metadata_repository_from_target_a_solo_or_parachain,
@@ -58,6 +59,7 @@ use itp_nonce_cache::{MutateNonce, Nonce};
 
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
 use itp_sgx_crypto::key_repository::AccessPubkey;
+use itp_stf_state_handler::query_shard_state::QueryShardState;
 use itp_storage::{StorageProof, StorageProofChecker};
 use itp_types::{ShardIdentifier, SignedBlock};
 use itp_utils::write_slice_and_whitespace_pad;
@@ -72,14 +74,30 @@ use std::{
 	vec::Vec,
 };
 
+mod account_watch;
 mod attestation;
+mod canary;
 mod empty_impls;
 mod initialization;
 mod ipfs;
+mod key_ceremony;
+mod load_shedding;
+mod maintenance;
 mod ocall;
+mod payload_quarantine;
+mod persistence_exclusions;
 mod shard_config;
+mod shard_configuration;
 mod shard_creation_info;
+mod shard_export;
+mod shard_isolation;
+mod shard_routing;
 mod shard_vault;
+mod sla_metrics;
+mod smoke_test;
+mod stale_shard;
+mod state_backup;
+mod state_diff;
 mod utils;
 
 pub mod error;
@@ -487,12 +505,21 @@ unsafe fn sync_parentchain_internal(
 		}
 	}
 
-	dispatch_parentchain_blocks_for_import::<WorkerModeProvider>(
-		blocks_to_sync,
-		events_to_sync,
-		&parentchain_id,
-		immediate_import,
-	)
+	// The indirect calls of the blocks are executed on the state of the enclave's shard, so the
+	// import is skipped for an isolated shard. With several shards, the calls can't be attributed
+	// to a single shard, and none is isolated.
+	let import = || {
+		dispatch_parentchain_blocks_for_import::<WorkerModeProvider>(
+			blocks_to_sync,
+			events_to_sync,
+			&parentchain_id,
+			immediate_import,
+		)
+	};
+	match GLOBAL_STATE_HANDLER_COMPONENT.get()?.list_shards()?.as_slice() {
+		[shard] => process_unless_isolated(shard, import).map(|_| ()),
+		_ => import(),
+	}
 }
 
 /// Dispatch the parentchain blocks for import.
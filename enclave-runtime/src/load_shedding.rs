@@ -0,0 +1,72 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::GLOBAL_LOAD_SHEDDER_COMPONENT,
+};
+use codec::Decode;
+use itp_component_container::ComponentGetter;
+use itp_enclave_metrics::EnclaveMetric;
+use itp_ocall_api::EnclaveMetricsOCallApi;
+use itp_types::load_shedding::LoadSheddingPolicy;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::slice;
+
+#[no_mangle]
+pub unsafe extern "C" fn set_load_shedding_policy(
+	policy: *const u8,
+	policy_size: u32,
+) -> sgx_status_t {
+	let mut policy_slice = slice::from_raw_parts(policy, policy_size as usize);
+	let policy = match LoadSheddingPolicy::decode(&mut policy_slice) {
+		Ok(policy) => policy,
+		Err(e) => {
+			error!("Could not decode the load shedding policy: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	match GLOBAL_LOAD_SHEDDER_COMPONENT.get() {
+		Ok(load_shedder) => load_shedder.set_policy(Some(policy)),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn report_epc_usage(used_bytes: u64, total_bytes: u64) -> sgx_status_t {
+	match GLOBAL_LOAD_SHEDDER_COMPONENT.get() {
+		Ok(load_shedder) => load_shedder.report_epc_usage(used_bytes, total_bytes),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Feeds the outcome of the slot into the load shedder and publishes the level on a transition.
+pub(crate) fn end_load_shedding_slot<OCallApi: EnclaveMetricsOCallApi>(
+	deadline_missed: bool,
+	ocall_api: &OCallApi,
+) -> EnclaveResult<()> {
+	if let Some(level) = GLOBAL_LOAD_SHEDDER_COMPONENT.get()?.on_slot_end(deadline_missed) {
+		if let Err(e) = ocall_api.update_metric(EnclaveMetric::LoadSheddingLevelSet(level as u64)) {
+			warn!("Failed to update metric for the load shedding level: {:?}", e);
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,58 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	error::Error, initialization::global_components::GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT,
+};
+use codec::Decode;
+use itp_component_container::ComponentGetter;
+use itp_types::{maintenance_window::MaintenanceWindow, ShardIdentifier};
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, vec::Vec};
+
+#[no_mangle]
+pub unsafe extern "C" fn set_maintenance_windows(
+	shard: *const u8,
+	shard_size: u32,
+	windows: *const u8,
+	windows_size: u32,
+) -> sgx_status_t {
+	let mut shard_slice = slice::from_raw_parts(shard, shard_size as usize);
+	let shard = match ShardIdentifier::decode(&mut shard_slice) {
+		Ok(shard) => shard,
+		Err(e) => {
+			error!("Could not decode shard: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	let mut windows_slice = slice::from_raw_parts(windows, windows_size as usize);
+	let windows = match Vec::<MaintenanceWindow>::decode(&mut windows_slice) {
+		Ok(windows) => windows,
+		Err(e) => {
+			error!("Could not decode maintenance windows: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	match GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT.get() {
+		Ok(schedule) => schedule.set_windows(shard, windows),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
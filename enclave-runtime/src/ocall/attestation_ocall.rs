@@ -243,7 +243,7 @@ impl EnclaveAttestationOCallApi for OcallApi {
 	}
 }
 
-trait GetSgxReport {
+pub(crate) trait GetSgxReport {
 	fn get_report_of_self(&self) -> SgxResult<sgx_report_body_t>;
 }
 
@@ -0,0 +1,65 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::ocall::{ffi, OcallApi};
+use frame_support::ensure;
+use itp_sgx_io::disk_space::insufficient_disk_space;
+use log::*;
+use sgx_types::{sgx_status_t, SgxResult};
+use std::{io::Result as IOResult, path::Path};
+
+impl OcallApi {
+	/// Asks the worker service whether `bytes_to_write` may be written to the path.
+	pub(crate) fn has_free_disk_space(
+		&self,
+		path: &Path,
+		bytes_to_write: usize,
+	) -> SgxResult<bool> {
+		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+		let path = path.to_string_lossy();
+		let mut has_free_space: u8 = 0;
+
+		let res = unsafe {
+			ffi::ocall_check_disk_space(
+				&mut rt as *mut sgx_status_t,
+				path.as_ptr(),
+				path.len() as u32,
+				bytes_to_write as u64,
+				&mut has_free_space as *mut u8,
+			)
+		};
+
+		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
+		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
+
+		Ok(has_free_space != 0)
+	}
+}
+
+/// Disk space check of the enclave file io, see [`itp_sgx_io::set_disk_space_check`].
+///
+/// Writes are not refused if the worker service cannot be asked.
+pub(crate) fn check_disk_space(path: &Path, bytes_to_write: usize) -> IOResult<()> {
+	match OcallApi.has_free_disk_space(path, bytes_to_write) {
+		Ok(true) => Ok(()),
+		Ok(false) => Err(insufficient_disk_space(path)),
+		Err(e) => {
+			warn!("Could not check the free disk space before writing {:?}: {:?}", path, e);
+			Ok(())
+		},
+	}
+}
@@ -83,12 +83,6 @@ extern "C" {
 		metric_size: u32,
 	) -> sgx_status_t;
 
-	pub fn ocall_propose_sidechain_blocks(
-		ret_val: *mut sgx_status_t,
-		signed_blocks: *const u8,
-		signed_blocks_size: u32,
-	) -> sgx_status_t;
-
 	pub fn ocall_store_sidechain_blocks(
 		ret_val: *mut sgx_status_t,
 		signed_blocks: *const u8,
@@ -107,26 +101,43 @@ extern "C" {
 		sidechain_blocks_size: u32,
 	) -> sgx_status_t;
 
-	pub fn ocall_send_to_parentchain(
-		ret_val: *mut sgx_status_t,
-		extrinsics: *const u8,
-		extrinsics_size: u32,
-		parentchain_id: *const u8,
-		parentchain_id_size: u32,
-		await_each_inclusion: c_int,
-	) -> sgx_status_t;
-
 	pub fn ocall_read_ipfs(
 		ret_val: *mut sgx_status_t,
 		cid: *const u8,
 		cid_size: u32,
 	) -> sgx_status_t;
 
-	pub fn ocall_write_ipfs(
+	pub fn ocall_execute_worker_command(
 		ret_val: *mut sgx_status_t,
-		enc_state: *const u8,
-		enc_state_size: u32,
-		cid: *mut u8,
-		cid_size: u32,
+		signed_command: *const u8,
+		signed_command_size: u32,
+		response: *mut u8,
+		response_size: u32,
+	) -> sgx_status_t;
+
+	pub fn ocall_check_disk_space(
+		ret_val: *mut sgx_status_t,
+		path: *const u8,
+		path_size: u32,
+		bytes_to_write: u64,
+		has_free_space: *mut u8,
+	) -> sgx_status_t;
+
+	pub fn ocall_write_state_backup_chunk(
+		ret_val: *mut sgx_status_t,
+		path: *const u8,
+		path_size: u32,
+		chunk: *const u8,
+		chunk_size: u32,
+	) -> sgx_status_t;
+
+	pub fn ocall_read_state_backup_chunk(
+		ret_val: *mut sgx_status_t,
+		path: *const u8,
+		path_size: u32,
+		offset: u64,
+		chunk: *mut u8,
+		chunk_size: u32,
+		bytes_read: *mut u32,
 	) -> sgx_status_t;
 }
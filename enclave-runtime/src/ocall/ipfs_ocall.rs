@@ -19,27 +19,14 @@
 use crate::ocall::{ffi, OcallApi};
 use frame_support::ensure;
 use itp_ocall_api::{EnclaveIpfsOCallApi, IpfsCid};
+use itp_types::worker_command::WorkerCommand;
 use sgx_types::{sgx_status_t, SgxResult};
 
 impl EnclaveIpfsOCallApi for OcallApi {
 	fn write_ipfs(&self, encoded_state: &[u8]) -> SgxResult<IpfsCid> {
-		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
-		let mut cid_buf = IpfsCid([0u8; 46]);
-
-		let res = unsafe {
-			ffi::ocall_write_ipfs(
-				&mut rt as *mut sgx_status_t,
-				encoded_state.as_ptr(),
-				encoded_state.len() as u32,
-				cid_buf.0.as_mut_ptr(),
-				cid_buf.0.len() as u32,
-			)
-		};
-
-		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
-		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
-
-		Ok(cid_buf)
+		let cid =
+			self.execute_worker_command(WorkerCommand::<()>::WriteIpfs(encoded_state.to_vec()))?;
+		Ok(IpfsCid(cid))
 	}
 
 	fn read_ipfs(&self, cid: &IpfsCid) -> SgxResult<()> {
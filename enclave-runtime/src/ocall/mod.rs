@@ -16,11 +16,17 @@
 */
 
 mod attestation_ocall;
+mod disk_space_ocall;
 mod ffi;
 mod ipfs_ocall;
 mod metrics_ocall;
 mod on_chain_ocall;
 mod sidechain_ocall;
+mod state_backup_ocall;
+mod worker_command_ocall;
+
+pub(crate) use attestation_ocall::GetSgxReport;
+pub(crate) use disk_space_ocall::check_disk_space;
 
 #[derive(Clone, Debug, Default)]
 pub struct OcallApi;
@@ -22,7 +22,10 @@ use frame_support::ensure;
 use itc_parentchain::primitives::ParentchainId;
 use itp_ocall_api::{EnclaveOnChainOCallApi, Result};
 use itp_storage::{verify_storage_entries, Error as StorageError};
-use itp_types::{storage::StorageEntryVerified, WorkerRequest, WorkerResponse, H256};
+use itp_types::{
+	storage::StorageEntryVerified, worker_command::WorkerCommand, WorkerRequest, WorkerResponse,
+	H256,
+};
 use log::*;
 use sgx_types::*;
 use sp_runtime::{traits::Header, OpaqueExtrinsic};
@@ -35,25 +38,11 @@ impl EnclaveOnChainOCallApi for OcallApi {
 		parentchain_id: &ParentchainId,
 		await_each_inclusion: bool,
 	) -> SgxResult<()> {
-		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
-		let extrinsics_encoded = extrinsics.encode();
-		let parentchain_id_encoded = parentchain_id.encode();
-
-		let res = unsafe {
-			ffi::ocall_send_to_parentchain(
-				&mut rt as *mut sgx_status_t,
-				extrinsics_encoded.as_ptr(),
-				extrinsics_encoded.len() as u32,
-				parentchain_id_encoded.as_ptr(),
-				parentchain_id_encoded.len() as u32,
-				await_each_inclusion.into(),
-			)
-		};
-
-		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
-		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
-
-		Ok(())
+		self.execute_worker_command(WorkerCommand::<()>::SubmitExtrinsics {
+			parentchain_id: *parentchain_id,
+			extrinsics,
+			await_each_inclusion,
+		})
 	}
 
 	fn worker_request<V: Encode + Decode>(
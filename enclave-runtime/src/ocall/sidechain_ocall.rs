@@ -20,7 +20,7 @@ use crate::ocall::{ffi, OcallApi};
 use codec::{Decode, Encode};
 use frame_support::ensure;
 use itp_ocall_api::EnclaveSidechainOCallApi;
-use itp_types::{BlockHash, ShardIdentifier};
+use itp_types::{worker_command::WorkerCommand, BlockHash, ShardIdentifier};
 use log::*;
 use sgx_types::{sgx_status_t, SgxResult};
 use std::vec::Vec;
@@ -30,21 +30,7 @@ impl EnclaveSidechainOCallApi for OcallApi {
 		&self,
 		signed_blocks: Vec<SignedSidechainBlock>,
 	) -> SgxResult<()> {
-		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
-		let signed_blocks_encoded = signed_blocks.encode();
-
-		let res = unsafe {
-			ffi::ocall_propose_sidechain_blocks(
-				&mut rt as *mut sgx_status_t,
-				signed_blocks_encoded.as_ptr(),
-				signed_blocks_encoded.len() as u32,
-			)
-		};
-
-		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
-		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
-
-		Ok(())
+		self.execute_worker_command(WorkerCommand::ProposeSidechainBlocks(signed_blocks))
 	}
 
 	fn store_sidechain_blocks<SignedSidechainBlock: Encode>(
@@ -0,0 +1,75 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::ocall::{ffi, OcallApi};
+use frame_support::ensure;
+use sgx_types::{sgx_status_t, SgxResult};
+use std::{vec, vec::Vec};
+
+impl OcallApi {
+	/// Has the worker service append the chunk to the state backup file at `path`.
+	pub(crate) fn write_state_backup_chunk(&self, path: &[u8], chunk: &[u8]) -> SgxResult<()> {
+		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+
+		let res = unsafe {
+			ffi::ocall_write_state_backup_chunk(
+				&mut rt as *mut sgx_status_t,
+				path.as_ptr(),
+				path.len() as u32,
+				chunk.as_ptr(),
+				chunk.len() as u32,
+			)
+		};
+
+		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
+		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
+
+		Ok(())
+	}
+
+	/// Has the worker service read at most `max_size` bytes of the state backup file at `path`,
+	/// from `offset` on. Returns an empty chunk at the end of the file.
+	pub(crate) fn read_state_backup_chunk(
+		&self,
+		path: &[u8],
+		offset: u64,
+		max_size: usize,
+	) -> SgxResult<Vec<u8>> {
+		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+		let mut chunk = vec![0u8; max_size];
+		let mut bytes_read: u32 = 0;
+
+		let res = unsafe {
+			ffi::ocall_read_state_backup_chunk(
+				&mut rt as *mut sgx_status_t,
+				path.as_ptr(),
+				path.len() as u32,
+				offset,
+				chunk.as_mut_ptr(),
+				chunk.len() as u32,
+				&mut bytes_read as *mut u32,
+			)
+		};
+
+		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
+		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
+		ensure!(bytes_read as usize <= max_size, sgx_status_t::SGX_ERROR_UNEXPECTED);
+
+		chunk.truncate(bytes_read as usize);
+		Ok(chunk)
+	}
+}
@@ -0,0 +1,78 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	error::Result as EnclaveResult,
+	initialization::global_components::GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+	ocall::{ffi, OcallApi},
+};
+use codec::{Decode, Encode};
+use core::sync::atomic::{AtomicU64, Ordering};
+use frame_support::ensure;
+use itp_component_container::ComponentGetter;
+use itp_sgx_crypto::key_repository::AccessKey;
+use itp_types::worker_command::{SignedWorkerCommand, WorkerCommand};
+use log::*;
+use sgx_types::{sgx_status_t, SgxResult};
+use sp_core::ed25519;
+use std::vec::Vec;
+
+/// Size of the buffer for the encoded response of the worker service.
+const COMMAND_RESPONSE_BUFFER_SIZE: usize = 256;
+
+/// Sequence number of the next command, the service rejects commands it has seen before.
+static COMMAND_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+impl OcallApi {
+	/// Signs the command with the enclave signing key and has the worker service execute it.
+	pub(crate) fn execute_worker_command<SignedSidechainBlock: Encode, Response: Decode>(
+		&self,
+		command: WorkerCommand<SignedSidechainBlock>,
+	) -> SgxResult<Response> {
+		let signer = signing_key().map_err(|e| {
+			error!("Could not retrieve the signing key for a worker command: {:?}", e);
+			sgx_status_t::SGX_ERROR_UNEXPECTED
+		})?;
+		let sequence = COMMAND_SEQUENCE.fetch_add(1, Ordering::SeqCst);
+		let signed_command = SignedWorkerCommand::new(command, sequence, &signer).encode();
+
+		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+		let mut response: Vec<u8> = vec![0; COMMAND_RESPONSE_BUFFER_SIZE];
+
+		let res = unsafe {
+			ffi::ocall_execute_worker_command(
+				&mut rt as *mut sgx_status_t,
+				signed_command.as_ptr(),
+				signed_command.len() as u32,
+				response.as_mut_ptr(),
+				response.len() as u32,
+			)
+		};
+
+		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
+		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
+
+		Decode::decode(&mut response.as_slice()).map_err(|e| {
+			error!("Failed to decode worker command response: {:?}", e);
+			sgx_status_t::SGX_ERROR_UNEXPECTED
+		})
+	}
+}
+
+fn signing_key() -> EnclaveResult<ed25519::Pair> {
+	Ok(GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?)
+}
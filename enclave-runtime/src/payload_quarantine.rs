@@ -0,0 +1,114 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::GLOBAL_PAYLOAD_QUARANTINE_COMPONENT,
+	shard_config::ensure_shard_owner,
+};
+use codec::{Decode, Encode};
+use itp_component_container::ComponentGetter;
+use itp_settings::worker::PAYLOAD_QUARANTINE_MAX_SIZE;
+use itp_sgx_crypto::{
+	session_key::{EphemeralKeyGenerator, GenerateEphemeralKey, MessageDirection},
+	StateCrypto,
+};
+use itp_types::payload_quarantine::{EncryptedQuarantineExport, SignedQuarantineExportRequest};
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::slice;
+
+#[no_mangle]
+pub unsafe extern "C" fn enable_payload_quarantine(max_bytes: u64) -> sgx_status_t {
+	if max_bytes > PAYLOAD_QUARANTINE_MAX_SIZE as u64 {
+		error!(
+			"Payload quarantine size of {} bytes exceeds the maximum of {} bytes",
+			max_bytes, PAYLOAD_QUARANTINE_MAX_SIZE
+		);
+		return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+	}
+	let quarantine = match GLOBAL_PAYLOAD_QUARANTINE_COMPONENT.get() {
+		Ok(quarantine) => quarantine,
+		Err(e) => return Error::ComponentContainer(e).into(),
+	};
+	if let Err(e) = quarantine.enable(max_bytes) {
+		error!("Failed to enable the payload quarantine: {:?}", e);
+		return sgx_status_t::SGX_ERROR_UNEXPECTED
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn export_payload_quarantine(
+	request: *const u8,
+	request_size: u32,
+	export: *mut u8,
+	export_size: u32,
+) -> sgx_status_t {
+	let mut request_slice = slice::from_raw_parts(request, request_size as usize);
+	let request = match SignedQuarantineExportRequest::decode(&mut request_slice) {
+		Ok(request) => request,
+		Err(e) => {
+			error!("Could not decode the payload quarantine export request: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	let quarantine_export = match export_payload_quarantine_internal(&request) {
+		Ok(quarantine_export) => quarantine_export,
+		Err(e) => {
+			warn!("Failed to export the payload quarantine: {:?}", e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+
+	let export_slice = slice::from_raw_parts_mut(export, export_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(export_slice, quarantine_export.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Encrypts the quarantined payloads of the shard to the ephemeral key of the requester, if the
+/// request is signed by an owner of the shard on the Integritee parentchain.
+fn export_payload_quarantine_internal(
+	request: &SignedQuarantineExportRequest,
+) -> EnclaveResult<EncryptedQuarantineExport> {
+	if !request.verify_signature() {
+		return Err(Error::Other("invalid signature of the export request".into()))
+	}
+	let shard = request.request.shard;
+	ensure_shard_owner(&shard, &request.signer)?;
+
+	let payloads = GLOBAL_PAYLOAD_QUARANTINE_COMPONENT
+		.get()?
+		.payloads(&shard)
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+	info!("Exporting {} quarantined payloads of shard {:?}", payloads.len(), shard);
+
+	let recipient = request.request.recipient;
+	let ephemeral_key = EphemeralKeyGenerator.generate()?;
+	let enclave_public = ephemeral_key.public();
+	let session_key = ephemeral_key.agree(&recipient, &recipient, &enclave_public)?;
+	let mut ciphertext = payloads.encode();
+	session_key
+		.message_key(MessageDirection::Response, 0)
+		.encrypt(&mut ciphertext)?;
+
+	Ok(EncryptedQuarantineExport { enclave_public, ciphertext })
+}
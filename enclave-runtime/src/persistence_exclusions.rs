@@ -0,0 +1,46 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	error::Error, initialization::global_components::GLOBAL_PERSISTENCE_EXCLUSIONS_COMPONENT,
+};
+use codec::Decode;
+use itp_component_container::ComponentGetter;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, string::String, vec::Vec};
+
+#[no_mangle]
+pub unsafe extern "C" fn set_never_persist_calls(
+	call_names: *const u8,
+	call_names_size: u32,
+) -> sgx_status_t {
+	let mut call_names_slice = slice::from_raw_parts(call_names, call_names_size as usize);
+	let call_names = match Vec::<String>::decode(&mut call_names_slice) {
+		Ok(call_names) => call_names,
+		Err(e) => {
+			error!("Could not decode the never persisted call names: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	match GLOBAL_PERSISTENCE_EXCLUSIONS_COMPONENT.get() {
+		Ok(exclusions) => exclusions.set_excluded(call_names),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
@@ -0,0 +1,50 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! The account watches as subscriptions of the RPC connections, so they are limited per
+//! connection and don't expire while their connection is open.
+
+use crate::Hash;
+use itc_direct_rpc_server::RpcSubscriptions;
+use its_sidechain::rpc_handler::account_watch::{
+	AccountWatches, MAX_ACCOUNT_WATCHES_PER_CONNECTION,
+};
+use std::sync::Arc;
+
+pub struct AccountWatchSubscriptions {
+	account_watches: Arc<AccountWatches>,
+}
+
+impl AccountWatchSubscriptions {
+	pub fn new(account_watches: Arc<AccountWatches>) -> Self {
+		AccountWatchSubscriptions { account_watches }
+	}
+}
+
+impl RpcSubscriptions for AccountWatchSubscriptions {
+	type Hash = Hash;
+
+	const MAX_PER_CONNECTION: usize = MAX_ACCOUNT_WATCHES_PER_CONNECTION;
+
+	fn is_subscription(&self, hash: &Self::Hash) -> bool {
+		self.account_watches.is_registered(hash)
+	}
+
+	fn cancel(&self, hash: &Self::Hash) {
+		self.account_watches.unregister(hash)
+	}
+}
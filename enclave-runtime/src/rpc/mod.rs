@@ -15,5 +15,7 @@
 
 */
 
+pub mod account_watch_subscriptions;
+pub mod rpc_resource_sweep;
 pub mod rpc_response_channel;
 pub mod worker_api_direct;
@@ -0,0 +1,68 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Periodic sweep of the RPC resources a long-running worker would otherwise accumulate.
+//!
+//! Closed connections remove their sessions and watched hashes right away. The sweep closes the
+//! sessions and watches that have been idle for too long, e.g. of connections that broke without
+//! being closed, and drops the account watches whose connection is gone. Account watches are
+//! long-lived subscriptions and don't expire while their connection is open.
+
+use crate::{
+	error::Result as EnclaveResult,
+	initialization::global_components::{
+		GLOBAL_ACCOUNT_WATCHES_COMPONENT, GLOBAL_RPC_WS_HANDLER_COMPONENT,
+	},
+};
+use core::sync::atomic::{AtomicU64, Ordering};
+use itp_component_container::ComponentGetter;
+use itp_time_utils::now_as_millis;
+use log::*;
+
+/// Minimum time between two sweeps.
+const SWEEP_INTERVAL_MILLIS: u64 = 60 * 1000;
+
+static LAST_SWEEP: AtomicU64 = AtomicU64::new(0);
+
+/// Sweeps the idle RPC resources, unless they have been swept within the sweep interval.
+/// Failing sweeps must not stop the block production.
+pub(crate) fn sweep_idle_rpc_resources() {
+	let now = now_as_millis();
+	let last_sweep = LAST_SWEEP.load(Ordering::SeqCst);
+	if now.saturating_sub(last_sweep) < SWEEP_INTERVAL_MILLIS
+		|| LAST_SWEEP
+			.compare_exchange(last_sweep, now, Ordering::SeqCst, Ordering::SeqCst)
+			.is_err()
+	{
+		return
+	}
+	if let Err(e) = sweep(now) {
+		warn!("Failed to sweep the idle RPC resources: {:?}", e);
+	}
+}
+
+fn sweep(now: u64) -> EnclaveResult<()> {
+	let rpc_ws_handler = GLOBAL_RPC_WS_HANDLER_COMPONENT.get()?;
+	let account_watches = GLOBAL_ACCOUNT_WATCHES_COMPONENT.get()?;
+
+	rpc_ws_handler.sweep_idle(now);
+	let orphaned_watches = account_watches.retain(|hash| rpc_ws_handler.is_watched(hash));
+	if orphaned_watches > 0 {
+		info!("Removed {} account watches without a connection", orphaned_watches);
+	}
+	Ok(())
+}
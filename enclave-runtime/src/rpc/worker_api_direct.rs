@@ -20,33 +20,76 @@ use crate::{
 		generate_dcap_ra_extrinsic_from_quote_internal,
 		generate_ias_ra_extrinsic_from_der_cert_internal,
 	},
+	error::{Error as EnclaveError, ErrorContext, Result as EnclaveResult},
+	initialization::global_components::{
+		GLOBAL_GETTER_ADMISSION_COMPONENT, GLOBAL_LOAD_SHEDDER_COMPONENT,
+		GLOBAL_OCALL_API_COMPONENT, GLOBAL_SHARD_ISOLATION_COMPONENT,
+		GLOBAL_SLA_METRICS_RECORDER_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
+		GLOBAL_STATE_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_OBSERVER_COMPONENT,
+	},
 	utils::{
 		get_stf_enclave_signer_from_solo_or_parachain,
 		get_validator_accessor_from_integritee_solo_or_parachain,
 	},
 };
-use codec::Encode;
+use codec::{Decode, Encode};
 use core::result::Result;
 use ita_sgx_runtime::Runtime;
-use ita_stf::{Getter, TrustedCallSigned};
+use ita_stf::{
+	deposit_address::{self, DepositIndex},
+	fees::{self, FeeEstimate},
+	Getter, TrustedCallSigned, TrustedGetter, TrustedGetterSigned,
+};
 use itc_parentchain::light_client::{concurrent_access::ValidatorAccess, ExtrinsicSender};
+use itp_component_container::ComponentGetter;
+use itp_ocall_api::EnclaveAttestationOCallApi;
 use itp_primitives_cache::{GetPrimitives, GLOBAL_PRIMITIVES_CACHE};
 use itp_rpc::RpcReturnValue;
-use itp_sgx_crypto::key_repository::AccessPubkey;
+use itp_sgx_crypto::{
+	aead::{AeadCiphertext, AeadKey},
+	key_repository::{AccessKey, AccessPubkey},
+};
+use itp_sgx_externalities::SgxExternalitiesTrait;
 use itp_stf_executor::{getter_executor::ExecuteGetter, traits::StfShardVaultQuery};
+use itp_stf_primitives::{traits::TrustedCallVerification, types::Signature};
+use itp_stf_state_handler::handle_state::HandleState;
+use itp_stf_state_observer::traits::ObserveState;
+use itp_time_utils::{duration_now, now_as_millis};
 use itp_top_pool_author::traits::AuthorApi;
-use itp_types::{DirectRequestStatus, Request, ShardIdentifier, H256};
+use itp_types::{
+	account_watch::SignedAccountWatchRequest,
+	rpc_authorization::SignedRpcAuthorization,
+	sla_metrics::SignedSlaMetrics,
+	time_lock::{time_lock_key_context, TimeLockedValue},
+	AccountId, DirectRequestStatus, Index, Request, ShardIdentifier, TrustedOperationStatus, H256,
+};
 use itp_utils::{FromHexPrefixed, ToHexPrefixed};
 use its_primitives::types::block::SignedBlock;
-use its_sidechain::rpc_handler::{direct_top_pool_api, import_block_api};
+use its_sidechain::{
+	primitives::types::BlockNumber,
+	rpc_handler::{
+		account_watch::AccountWatches,
+		direct_top_pool_api,
+		getter_session::{GetterSession, GetterSessionToken, GetterSessions},
+		import_block_api,
+		nonce_reservation::NonceReservations,
+		shard_routing::{compute_hex_encoded_redirect, Routed, ShardRoutes},
+	},
+	state::SidechainSystemExt,
+};
 use jsonrpc_core::{serde_json::json, IoHandler, Params, Value};
 use log::debug;
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
+use sgx_rand::{Rng, StdRng};
+use sp_core::ed25519;
 use sp_runtime::OpaqueExtrinsic;
-use std::{borrow::ToOwned, format, str, string::String, sync::Arc, vec::Vec};
+use std::{
+	borrow::ToOwned, boxed::Box, fmt::Debug, format, str, string::String, sync::Arc, vec::Vec,
+};
 
-fn compute_hex_encoded_return_error(error_msg: &str) -> String {
-	RpcReturnValue::from_error_message(error_msg).to_hex()
+/// Malformed parameters of a request.
+fn invalid_request<E: Debug>(error: E) -> EnclaveError {
+	EnclaveError::InvalidRequest(format!("{:?}", error))
 }
 
 fn get_all_rpc_methods_string(io_handler: &IoHandler) -> String {
@@ -59,10 +102,14 @@ fn get_all_rpc_methods_string(io_handler: &IoHandler) -> String {
 	format!("methods: [{}]", method_string)
 }
 
+/// Every method fails with the stable error code of the failing domain, e.g. 2001 for the state,
+/// see [`itp_rpc::error_code`].
 pub fn public_api_rpc_handler<Author, GetterExecutor, AccessShieldingKey>(
 	top_pool_author: Arc<Author>,
 	getter_executor: Arc<GetterExecutor>,
 	shielding_key: Arc<AccessShieldingKey>,
+	shard_routes: Arc<ShardRoutes>,
+	account_watches: Arc<AccountWatches>,
 ) -> IoHandler
 where
 	Author: AuthorApi<H256, H256, TrustedCallSigned, Getter> + Send + Sync + 'static,
@@ -71,27 +118,17 @@ where
 {
 	let mut io = direct_top_pool_api::add_top_pool_direct_rpc_methods(
 		top_pool_author.clone(),
+		shard_routes.clone(),
 		IoHandler::new(),
 	);
 
 	io.add_sync_method("author_getShieldingKey", move |_: Params| {
 		debug!("worker_api_direct rpc was called: author_getShieldingKey");
-		let rsa_pubkey = match shielding_key.retrieve_pubkey() {
-			Ok(key) => key,
-			Err(status) => {
-				let error_msg: String = format!("Could not get rsa pubkey due to: {}", status);
-				return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-			},
-		};
+		let rsa_pubkey = shielding_key.retrieve_pubkey().context("getting the rsa pubkey")?;
+		let rsa_pubkey_json = serde_json::to_string(&rsa_pubkey)
+			.map_err(|e| EnclaveError::Other(Box::new(e)))
+			.context("serializing the rsa pubkey")?;
 
-		let rsa_pubkey_json = match serde_json::to_string(&rsa_pubkey) {
-			Ok(k) => k,
-			Err(x) => {
-				let error_msg: String =
-					format!("[Enclave] can't serialize rsa_pubkey {:?} {}", rsa_pubkey, x);
-				return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-			},
-		};
 		let json_value =
 			RpcReturnValue::new(rsa_pubkey_json.encode(), false, DirectRequestStatus::Ok);
 		Ok(json!(json_value.to_hex()))
@@ -102,20 +139,25 @@ where
 		debug!("worker_api_direct rpc was called: author_getShardVault");
 		let shard =
 			local_top_pool_author.list_handled_shards().first().copied().unwrap_or_default();
-		if let Ok(stf_enclave_signer) = get_stf_enclave_signer_from_solo_or_parachain() {
-			if let Ok(vault) = stf_enclave_signer.get_shard_vault(&shard) {
-				let json_value =
-					RpcReturnValue::new(vault.encode(), false, DirectRequestStatus::Ok);
-				Ok(json!(json_value.to_hex()))
-			} else {
-				Ok(json!(compute_hex_encoded_return_error("failed to get shard vault").to_hex()))
-			}
-		} else {
-			Ok(json!(compute_hex_encoded_return_error(
-				"failed to get stf_enclave_signer to get shard vault"
-			)
-			.to_hex()))
-		}
+		let vault = get_stf_enclave_signer_from_solo_or_parachain()
+			.context("getting the stf enclave signer to get the shard vault")?
+			.get_shard_vault(&shard)
+			.context("getting the shard vault")?;
+
+		let json_value = RpcReturnValue::new(vault.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	let local_top_pool_author = top_pool_author.clone();
+	io.add_sync_method("state_getStateHash", move |_: Params| {
+		debug!("worker_api_direct rpc was called: state_getStateHash");
+		let shard =
+			local_top_pool_author.list_handled_shards().first().copied().unwrap_or_default();
+		let block_number_and_hash = get_state_hash_inner(&shard)?;
+
+		let json_value =
+			RpcReturnValue::new(block_number_and_hash.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
 	});
 
 	io.add_sync_method("author_getShard", move |_: Params| {
@@ -127,13 +169,7 @@ where
 
 	io.add_sync_method("author_getMuRaUrl", move |_: Params| {
 		debug!("worker_api_direct rpc was called: author_getMuRaUrl");
-		let url = match GLOBAL_PRIMITIVES_CACHE.get_mu_ra_url() {
-			Ok(url) => url,
-			Err(status) => {
-				let error_msg: String = format!("Could not get mu ra url due to: {}", status);
-				return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-			},
-		};
+		let url = GLOBAL_PRIMITIVES_CACHE.get_mu_ra_url().context("getting the mu ra url")?;
 
 		let json_value = RpcReturnValue::new(url.encode(), false, DirectRequestStatus::Ok);
 		Ok(json!(json_value.to_hex()))
@@ -141,13 +177,9 @@ where
 
 	io.add_sync_method("author_getUntrustedUrl", move |_: Params| {
 		debug!("worker_api_direct rpc was called: author_getUntrustedUrl");
-		let url = match GLOBAL_PRIMITIVES_CACHE.get_untrusted_worker_url() {
-			Ok(url) => url,
-			Err(status) => {
-				let error_msg: String = format!("Could not get untrusted url due to: {}", status);
-				return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-			},
-		};
+		let url = GLOBAL_PRIMITIVES_CACHE
+			.get_untrusted_worker_url()
+			.context("getting the untrusted url")?;
 
 		let json_value = RpcReturnValue::new(url.encode(), false, DirectRequestStatus::Ok);
 		Ok(json!(json_value.to_hex()))
@@ -172,48 +204,149 @@ where
 		Ok(Value::String(format!("hello, {}", parsed)))
 	});
 
+	let time_locked_getter_executor = getter_executor.clone();
+	let nonce_getter_executor = getter_executor.clone();
+	let nonce_author = top_pool_author.clone();
+	let nonce_reservations = Arc::new(NonceReservations::default());
+	let session_shard_routes = shard_routes.clone();
+	let session_getter_executor = getter_executor.clone();
+	let getter_sessions = Arc::new(GetterSessions::default());
+	let opened_getter_sessions = getter_sessions.clone();
 	io.add_sync_method("state_executeGetter", move |params: Params| {
 		debug!("worker_api_direct rpc was called: state_executeGetter");
-		let json_value = match execute_getter_inner(getter_executor.as_ref(), params) {
-			Ok(state_getter_value) => RpcReturnValue {
-				do_watch: false,
-				value: state_getter_value.encode(),
-				status: DirectRequestStatus::Ok,
-			}
-			.to_hex(),
-			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+		let json_value =
+			match execute_getter_inner(getter_executor.as_ref(), shard_routes.as_ref(), params)? {
+				Routed::Served(state_getter_value) => RpcReturnValue {
+					do_watch: false,
+					value: state_getter_value.encode(),
+					status: DirectRequestStatus::Ok,
+				}
+				.to_hex(),
+				Routed::Redirected(route) => compute_hex_encoded_redirect(&route),
+			};
+		Ok(json!(json_value))
+	});
+
+	io.add_sync_method("author_reserveNonce", move |params: Params| {
+		debug!("worker_api_direct rpc was called: author_reserveNonce");
+		let reservation = reserve_nonce_inner(
+			nonce_author.as_ref(),
+			nonce_getter_executor.as_ref(),
+			nonce_reservations.as_ref(),
+			params,
+		)?;
+		let json_value = RpcReturnValue::new(reservation.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	io.add_sync_method("state_openGetterSession", move |params: Params| {
+		debug!("worker_api_direct rpc was called: state_openGetterSession");
+		let json_value = match open_getter_session_inner(
+			opened_getter_sessions.as_ref(),
+			session_shard_routes.as_ref(),
+			params,
+		)? {
+			Routed::Served(session) =>
+				RpcReturnValue::new(session.encode(), false, DirectRequestStatus::Ok).to_hex(),
+			Routed::Redirected(route) => compute_hex_encoded_redirect(&route),
+		};
+		Ok(json!(json_value))
+	});
+
+	io.add_sync_method("state_executeSessionGetter", move |params: Params| {
+		debug!("worker_api_direct rpc was called: state_executeSessionGetter");
+		let state_getter_value = execute_session_getter_inner(
+			session_getter_executor.as_ref(),
+			getter_sessions.as_ref(),
+			params,
+		)?;
+		let json_value =
+			RpcReturnValue::new(state_getter_value.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	io.add_sync_method("author_watchAccounts", move |params: Params| {
+		debug!("worker_api_direct rpc was called: author_watchAccounts");
+		let watch_hash = watch_accounts_inner(account_watches.as_ref(), params)?;
+		// The connection is kept open under the hash to push the transfer notifications.
+		let json_value = RpcReturnValue::new(
+			watch_hash.encode(),
+			true,
+			DirectRequestStatus::TrustedOperationStatus(TrustedOperationStatus::Submitted),
+		);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	let fee_shard_routes = shard_routes.clone();
+	io.add_sync_method("author_estimateFee", move |params: Params| {
+		debug!("worker_api_direct rpc was called: author_estimateFee");
+		let json_value = match estimate_fee_inner(fee_shard_routes.as_ref(), params)? {
+			Routed::Served(estimate) =>
+				RpcReturnValue::new(estimate.encode(), false, DirectRequestStatus::Ok).to_hex(),
+			Routed::Redirected(route) => compute_hex_encoded_redirect(&route),
 		};
 		Ok(json!(json_value))
 	});
 
+	io.add_sync_method("author_depositAddress", move |params: Params| {
+		debug!("worker_api_direct rpc was called: author_depositAddress");
+		let address = deposit_address_inner(params)?;
+		let json_value = RpcReturnValue::new(address.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	io.add_sync_method("state_executeTimeLockedGetter", move |params: Params| {
+		debug!("worker_api_direct rpc was called: state_executeTimeLockedGetter");
+		let time_locked_value =
+			execute_time_locked_getter_inner(time_locked_getter_executor.as_ref(), params)?;
+		let json_value =
+			RpcReturnValue::new(time_locked_value.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	io.add_sync_method("state_getTimeLockKey", move |params: Params| {
+		debug!("worker_api_direct rpc was called: state_getTimeLockKey");
+		let key = get_time_lock_key_inner(params)?;
+		let json_value = RpcReturnValue::new(key.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	io.add_sync_method("sla_getMetrics", move |_: Params| {
+		debug!("worker_api_direct rpc was called: sla_getMetrics");
+		let maybe_metrics = get_sla_metrics_inner()?;
+		let json_value =
+			RpcReturnValue::new(maybe_metrics.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
+	io.add_sync_method("author_getIsolatedShards", move |_: Params| {
+		debug!("worker_api_direct rpc was called: author_getIsolatedShards");
+		let isolated_shards = get_isolated_shards_inner()?;
+		let json_value =
+			RpcReturnValue::new(isolated_shards.encode(), false, DirectRequestStatus::Ok);
+		Ok(json!(json_value.to_hex()))
+	});
+
 	io.add_sync_method("attesteer_forwardDcapQuote", move |params: Params| {
 		debug!("worker_api_direct rpc was called: attesteer_forwardDcapQuote");
-		let json_value = match forward_dcap_quote_inner(params) {
-			Ok(val) => RpcReturnValue {
-				do_watch: false,
-				value: val.encode(),
-				status: DirectRequestStatus::Ok,
-			}
-			.to_hex(),
-			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+		let val = forward_dcap_quote_inner(params)?;
+		let json_value = RpcReturnValue {
+			do_watch: false,
+			value: val.encode(),
+			status: DirectRequestStatus::Ok,
 		};
-
-		Ok(json!(json_value))
+		Ok(json!(json_value.to_hex()))
 	});
 
 	io.add_sync_method("attesteer_forwardIasAttestationReport", move |params: Params| {
 		debug!("worker_api_direct rpc was called: attesteer_forwardIasAttestationReport");
-		let json_value = match attesteer_forward_ias_attestation_report_inner(params) {
-			Ok(val) => RpcReturnValue {
-				do_watch: false,
-				value: val.encode(),
-				status: DirectRequestStatus::Ok,
-			}
-			.to_hex(),
-			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+		let val = attesteer_forward_ias_attestation_report_inner(params)?;
+		let json_value = RpcReturnValue {
+			do_watch: false,
+			value: val.encode(),
+			status: DirectRequestStatus::Ok,
 		};
-
-		Ok(json!(json_value))
+		Ok(json!(json_value.to_hex()))
 	});
 
 	io.add_sync_method("system_health", |_: Params| {
@@ -245,74 +378,388 @@ where
 
 fn execute_getter_inner<GE: ExecuteGetter>(
 	getter_executor: &GE,
+	shard_routes: &ShardRoutes,
 	params: Params,
-) -> Result<Option<Vec<u8>>, String> {
-	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+) -> EnclaveResult<Routed<Option<Vec<u8>>>> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
 
-	let request =
-		Request::from_hex(&hex_encoded_params[0].clone()).map_err(|e| format!("{:?}", e))?;
+	let request = Request::from_hex(&hex_encoded_params[0].clone()).map_err(invalid_request)?;
 
 	let shard: ShardIdentifier = request.shard;
 	let encoded_trusted_getter: Vec<u8> = request.cyphertext;
+	if let Some(route) = shard_routes.route(&shard) {
+		return Ok(Routed::Redirected(route))
+	}
+
+	// Anonymous getters are the first to go when the worker is overloaded.
+	let load_shedder = GLOBAL_LOAD_SHEDDER_COMPONENT.get()?;
+	if load_shedder.sheds_anonymous_getters()
+		&& matches!(Getter::decode(&mut encoded_trusted_getter.as_slice()), Ok(Getter::public(_)))
+	{
+		return Err(EnclaveError::Unavailable(
+			"Worker is overloaded, public getters are not served for now".to_owned(),
+		))
+	}
+	let urgent = matches!(
+		Getter::decode(&mut encoded_trusted_getter.as_slice()),
+		Ok(Getter::trusted(signed_getter)) if is_urgent_getter(&signed_getter.getter)
+	);
+	admit_getter(&shard, urgent)?;
 
 	let getter_result = getter_executor
 		.execute_getter(&shard, encoded_trusted_getter)
-		.map_err(|e| format!("{:?}", e))?;
+		.context("executing the getter")?;
 
-	Ok(getter_result)
+	Ok(Routed::Served(getter_result))
 }
 
-fn forward_dcap_quote_inner(params: Params) -> Result<OpaqueExtrinsic, String> {
-	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+/// Nonce getters are needed to submit the calls for the next block, so they are never deferred.
+fn is_urgent_getter(getter: &TrustedGetter) -> bool {
+	match getter {
+		TrustedGetter::nonce(_) => true,
+		#[cfg(feature = "evm")]
+		TrustedGetter::evm_nonce(_) => true,
+		_ => false,
+	}
+}
 
-	if hex_encoded_params.len() != 1 {
-		return Err(format!(
-			"Wrong number of arguments for IAS attestation report forwarding: {}, expected: {}",
+/// The state of an isolated shard is not served until the worker is restarted.
+fn ensure_not_isolated(shard: &ShardIdentifier) -> EnclaveResult<()> {
+	if GLOBAL_SHARD_ISOLATION_COMPONENT.get()?.is_isolated(shard) {
+		return Err(EnclaveError::Unavailable(format!(
+			"Shard {:?} is isolated until the worker is restarted",
+			shard
+		)))
+	}
+	Ok(())
+}
+
+/// Rejects getters of isolated shards and defers non-urgent getters while a block is authored,
+/// with the time to retry after.
+fn admit_getter(shard: &ShardIdentifier, urgent: bool) -> EnclaveResult<()> {
+	ensure_not_isolated(shard)?;
+	GLOBAL_GETTER_ADMISSION_COMPONENT
+		.get()?
+		.admit(urgent, duration_now())
+		.map_err(|retry_after| {
+			EnclaveError::Unavailable(format!(
+				"Worker is authoring a block, retry the getter after {} ms",
+				retry_after.as_millis()
+			))
+		})
+}
+
+/// Reserves the next usable nonce of the account that signed the nonce reservation
+/// authorization of the request.
+///
+/// The nonce follows the account nonce in the state, the pending calls of the account and the
+/// nonces reserved by its other clients. Returns the nonce and the sidechain block its
+/// reservation expires at.
+fn reserve_nonce_inner<Author, GE>(
+	top_pool_author: &Author,
+	getter_executor: &GE,
+	nonce_reservations: &NonceReservations,
+	params: Params,
+) -> EnclaveResult<(Index, u64)>
+where
+	Author: AuthorApi<H256, H256, TrustedCallSigned, Getter>,
+	GE: ExecuteGetter,
+{
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	let signed_authorization =
+		SignedRpcAuthorization::from_hex(hex_encoded_params.get(0).ok_or_else(|| {
+			EnclaveError::InvalidRequest("Missing nonce reservation authorization".to_owned())
+		})?)
+		.map_err(invalid_request)?;
+	let shard = signed_authorization.authorization.shard;
+	let account = signed_authorization.authorization.account.clone();
+	ensure_not_isolated(&shard)?;
+
+	// The nonce is only returned once the reservation has checked the authorization.
+	let nonce_getter = Getter::trusted(TrustedGetterSigned::new(
+		TrustedGetter::nonce(account.clone()),
+		Signature::Ed25519(ed25519::Signature::from_raw([0u8; 64])),
+	));
+	let state_nonce = getter_executor
+		.execute_authorized_getter(&shard, nonce_getter.encode())
+		.context("getting the nonce of the account")?
+		.map(|encoded| Index::decode(&mut encoded.as_slice()))
+		.transpose()
+		.context("decoding the nonce of the account")?
+		.unwrap_or_default();
+
+	let next_free = top_pool_author
+		.get_pending_trusted_calls_for(shard, &account)
+		.iter()
+		.filter_map(|operation| operation.to_call())
+		.map(|call| call.nonce.saturating_add(1))
+		.fold(state_nonce, Index::max);
+
+	nonce_reservations
+		.reserve(&signed_authorization, next_free, current_sidechain_block(&shard)?)
+		.map_err(|e| EnclaveError::InvalidRequest(e.to_owned()))
+}
+
+/// Opens a getter session for the account that signed the session authorization of the request.
+fn open_getter_session_inner(
+	getter_sessions: &GetterSessions,
+	shard_routes: &ShardRoutes,
+	params: Params,
+) -> EnclaveResult<Routed<GetterSession>> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	let signed_authorization =
+		SignedRpcAuthorization::from_hex(hex_encoded_params.get(0).ok_or_else(|| {
+			EnclaveError::InvalidRequest("Missing getter session authorization".to_owned())
+		})?)
+		.map_err(invalid_request)?;
+	let shard = signed_authorization.authorization.shard;
+	if let Some(route) = shard_routes.route(&shard) {
+		return Ok(Routed::Redirected(route))
+	}
+	admit_getter(&shard, false)?;
+
+	let mut token = GetterSessionToken::default();
+	StdRng::new().context("drawing the session token")?.fill_bytes(&mut token);
+	let session = getter_sessions
+		.open(token, &signed_authorization, current_sidechain_block(&shard)?)
+		.map_err(|e| EnclaveError::InvalidRequest(e.to_owned()))?;
+	Ok(Routed::Served(session))
+}
+
+/// Executes the unsigned trusted getter of the request, authorized by the getter session token.
+fn execute_session_getter_inner<GE: ExecuteGetter>(
+	getter_executor: &GE,
+	getter_sessions: &GetterSessions,
+	params: Params,
+) -> EnclaveResult<Option<Vec<u8>>> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	if hex_encoded_params.len() != 2 {
+		return Err(EnclaveError::InvalidRequest(format!(
+			"Wrong number of arguments for session getter: {}, expected: {}",
 			hex_encoded_params.len(),
-			1
+			2
+		)))
+	}
+	let request = Request::from_hex(&hex_encoded_params[0]).map_err(invalid_request)?;
+	let token = GetterSessionToken::from_hex(&hex_encoded_params[1]).map_err(invalid_request)?;
+	let getter =
+		TrustedGetter::decode(&mut request.cyphertext.as_slice()).map_err(invalid_request)?;
+
+	if !getter_sessions.is_authorized(
+		&token,
+		&request.shard,
+		getter.sender_account(),
+		current_sidechain_block(&request.shard)?,
+	) {
+		return Err(EnclaveError::InvalidRequest(
+			"Invalid or expired getter session token".to_owned(),
 		))
 	}
+	admit_getter(&request.shard, is_urgent_getter(&getter))?;
+
+	let unsigned_getter = Getter::trusted(TrustedGetterSigned::new(
+		getter,
+		Signature::Ed25519(ed25519::Signature::from_raw([0u8; 64])),
+	));
+	getter_executor
+		.execute_authorized_getter(&request.shard, unsigned_getter.encode())
+		.context("executing the session getter")
+}
+
+/// Estimates the fee of the signed trusted call in the request against the current state of the
+/// shard, without executing it. The call has to be signed, because the estimate tells whether
+/// the sender can afford it.
+fn estimate_fee_inner(
+	shard_routes: &ShardRoutes,
+	params: Params,
+) -> EnclaveResult<Routed<FeeEstimate>> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	let request = Request::from_hex(hex_encoded_params.get(0).ok_or_else(|| {
+		EnclaveError::InvalidRequest("Missing fee estimation request".to_owned())
+	})?)
+	.map_err(invalid_request)?;
+	let shard = request.shard;
+	shard_routes.serve_or_redirect(&shard, || {
+		ensure_not_isolated(&shard)?;
+		let trusted_call = TrustedCallSigned::decode(&mut request.cyphertext.as_slice())
+			.map_err(invalid_request)?;
+		let mrenclave = GLOBAL_OCALL_API_COMPONENT
+			.get()?
+			.get_mrenclave_of_self()
+			.context("getting the mrenclave")?;
+		if !trusted_call.verify_signature(&mrenclave.m, &shard) {
+			return Err(EnclaveError::InvalidRequest(
+				"Invalid signature of the trusted call".to_owned(),
+			))
+		}
+
+		let (mut state, _) = GLOBAL_STATE_HANDLER_COMPONENT
+			.get()?
+			.load_cloned(&shard)
+			.context("loading the state to estimate the fee")?;
+		Ok(state.execute_with(|| fees::estimate(&trusted_call.call)))
+	})
+}
+
+/// Deposit address of the parent account with the given index. The address only accepts sweeps
+/// of the parent once the parent has derived it with `TrustedCall::deposit_address_derive`.
+fn deposit_address_inner(params: Params) -> EnclaveResult<AccountId> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	if hex_encoded_params.len() != 2 {
+		return Err(EnclaveError::InvalidRequest(format!(
+			"Wrong number of arguments for deposit address: {}, expected: {}",
+			hex_encoded_params.len(),
+			2
+		)))
+	}
+	let parent = AccountId::from_hex(&hex_encoded_params[0]).map_err(invalid_request)?;
+	let index = DepositIndex::from_hex(&hex_encoded_params[1]).map_err(invalid_request)?;
+	Ok(deposit_address::derive(&parent, index))
+}
 
-	let encoded_quote_to_forward: Vec<u8> =
-		itp_utils::hex::decode_hex(&hex_encoded_params[0]).map_err(|e| format!("{:?}", e))?;
+fn watch_accounts_inner(account_watches: &AccountWatches, params: Params) -> EnclaveResult<H256> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	let signed_request =
+		SignedAccountWatchRequest::from_hex(hex_encoded_params.get(0).ok_or_else(|| {
+			EnclaveError::InvalidRequest("Missing account watch request".to_owned())
+		})?)
+		.map_err(invalid_request)?;
+	account_watches
+		.register(&signed_request, now_as_millis())
+		.map_err(EnclaveError::InvalidRequest)
+}
+
+/// Number of the last sidechain block applied to the state of the shard, which getter sessions
+/// and nonce reservations expire with, because the time of the host can't be trusted.
+fn current_sidechain_block(shard: &ShardIdentifier) -> EnclaveResult<BlockNumber> {
+	GLOBAL_STATE_OBSERVER_COMPONENT
+		.get()?
+		.observe_state(shard, |state| state.get_block_number().unwrap_or_default())
+		.context("observing the sidechain block number")
+}
+
+/// Number of the last sidechain block applied to the state of the shard, and the state hash.
+fn get_state_hash_inner(shard: &ShardIdentifier) -> EnclaveResult<(Option<BlockNumber>, H256)> {
+	let (state, state_hash) = GLOBAL_STATE_HANDLER_COMPONENT
+		.get()?
+		.load_cloned(shard)
+		.context("loading the state to get its hash")?;
+	Ok((state.get_block_number(), state_hash))
+}
+
+/// Executes the getter and encrypts its result with the time lock key of the release block.
+fn execute_time_locked_getter_inner<GE: ExecuteGetter>(
+	getter_executor: &GE,
+	params: Params,
+) -> EnclaveResult<TimeLockedValue> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	if hex_encoded_params.len() != 2 {
+		return Err(EnclaveError::InvalidRequest(format!(
+			"Wrong number of arguments for time-locked getter: {}, expected: {}",
+			hex_encoded_params.len(),
+			2
+		)))
+	}
+	let request = Request::from_hex(&hex_encoded_params[0]).map_err(invalid_request)?;
+	let release_block = BlockNumber::from_hex(&hex_encoded_params[1]).map_err(invalid_request)?;
+	ensure_not_isolated(&request.shard)?;
+
+	let getter_result = getter_executor
+		.execute_getter(&request.shard, request.cyphertext)
+		.context("executing the time-locked getter")?;
+
+	let AeadCiphertext { nonce, ciphertext } = time_lock_key(&request.shard, release_block)?
+		.encrypt_with_random_nonce(
+			&getter_result.encode(),
+			&time_lock_key_context(&request.shard, release_block),
+		)
+		.context("encrypting the time-locked getter result")?;
+	Ok(TimeLockedValue { release_block, nonce, ciphertext })
+}
+
+/// Returns the time lock key of a sidechain block, once the shard's state has reached it.
+fn get_time_lock_key_inner(params: Params) -> EnclaveResult<AeadKey> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
+	if hex_encoded_params.len() != 2 {
+		return Err(EnclaveError::InvalidRequest(format!(
+			"Wrong number of arguments for time lock key: {}, expected: {}",
+			hex_encoded_params.len(),
+			2
+		)))
+	}
+	let shard = ShardIdentifier::from_hex(&hex_encoded_params[0]).map_err(invalid_request)?;
+	let release_block = BlockNumber::from_hex(&hex_encoded_params[1]).map_err(invalid_request)?;
+
+	let (maybe_block_number, _) = get_state_hash_inner(&shard)?;
+	if maybe_block_number.map_or(true, |block_number| block_number < release_block) {
+		return Err(EnclaveError::Unavailable(format!(
+			"Time lock key of block {} is not released yet, the shard is at block {:?}",
+			release_block, maybe_block_number
+		)))
+	}
+	time_lock_key(&shard, release_block)
+}
+
+/// Derived from the state key, so all validateers of the shard derive the same key.
+fn time_lock_key(shard: &ShardIdentifier, release_block: BlockNumber) -> EnclaveResult<AeadKey> {
+	let state_key = GLOBAL_STATE_KEY_REPOSITORY_COMPONENT
+		.get()?
+		.retrieve_key()
+		.context("retrieving the state key")?;
+	Ok(state_key.derive(&time_lock_key_context(shard, release_block)).into())
+}
+
+/// Signed digest of the last closed SLA period, none before the first period has been closed.
+fn get_sla_metrics_inner() -> EnclaveResult<Option<SignedSlaMetrics>> {
+	Ok(GLOBAL_SLA_METRICS_RECORDER_COMPONENT.get()?.latest())
+}
+
+/// Isolated shards with the error that caused their isolation.
+fn get_isolated_shards_inner() -> EnclaveResult<Vec<(ShardIdentifier, String)>> {
+	Ok(GLOBAL_SHARD_ISOLATION_COMPONENT.get()?.isolated_shards())
+}
+
+fn forward_dcap_quote_inner(params: Params) -> EnclaveResult<OpaqueExtrinsic> {
+	let encoded_quote_to_forward = single_hex_param(params, "DCAP quote forwarding")?;
 
 	let url = String::new();
 	let ext = generate_dcap_ra_extrinsic_from_quote_internal(url, &encoded_quote_to_forward)
-		.map_err(|e| format!("{:?}", e))?;
+		.context("creating the registration extrinsic of the DCAP quote")?;
 
-	let validator_access = get_validator_accessor_from_integritee_solo_or_parachain().unwrap();
-	validator_access
-		.execute_mut_on_validator(|v| v.send_extrinsics(vec![ext.clone()]))
-		.unwrap();
-
-	Ok(ext)
+	send_to_integritee_parentchain(ext)
 }
 
 fn attesteer_forward_ias_attestation_report_inner(
 	params: Params,
-) -> Result<OpaqueExtrinsic, String> {
-	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+) -> EnclaveResult<OpaqueExtrinsic> {
+	let ias_attestation_report = single_hex_param(params, "IAS attestation report forwarding")?;
+
+	let url = String::new();
+	let ext = generate_ias_ra_extrinsic_from_der_cert_internal(url, &ias_attestation_report)
+		.context("creating the registration extrinsic of the IAS attestation report")?;
+
+	send_to_integritee_parentchain(ext)
+}
+
+fn single_hex_param(params: Params, request: &str) -> EnclaveResult<Vec<u8>> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
 
 	if hex_encoded_params.len() != 1 {
-		return Err(format!(
-			"Wrong number of arguments for IAS attestation report forwarding: {}, expected: {}",
+		return Err(EnclaveError::InvalidRequest(format!(
+			"Wrong number of arguments for {}: {}, expected: {}",
+			request,
 			hex_encoded_params.len(),
 			1
-		))
+		)))
 	}
 
-	let ias_attestation_report =
-		itp_utils::hex::decode_hex(&hex_encoded_params[0]).map_err(|e| format!("{:?}", e))?;
-
-	let url = String::new();
-	let ext = generate_ias_ra_extrinsic_from_der_cert_internal(url, &ias_attestation_report)
-		.map_err(|e| format!("{:?}", e))?;
+	itp_utils::hex::decode_hex(&hex_encoded_params[0]).map_err(invalid_request)
+}
 
-	let validator_access = get_validator_accessor_from_integritee_solo_or_parachain().unwrap();
-	validator_access
+fn send_to_integritee_parentchain(ext: OpaqueExtrinsic) -> EnclaveResult<OpaqueExtrinsic> {
+	get_validator_accessor_from_integritee_solo_or_parachain()?
 		.execute_mut_on_validator(|v| v.send_extrinsics(vec![ext.clone()]))
-		.unwrap();
-
+		.context("sending the registration extrinsic to the Integritee parentchain")?;
 	Ok(ext)
 }
 
@@ -16,27 +16,32 @@
 
 use crate::{
 	error::{Error, Result as EnclaveResult},
+	initialization::global_components::GLOBAL_OCALL_API_COMPONENT,
 	utils::{
 		get_extrinsic_factory_from_integritee_solo_or_parachain,
 		get_stf_enclave_signer_from_solo_or_parachain,
+		get_validator_accessor_from_integritee_solo_or_parachain,
 	},
 };
 use codec::Encode;
 use enclave_bridge_primitives::ShardConfig;
-
+use itc_parentchain::light_client::{concurrent_access::ValidatorAccess, LightClientState};
+use itp_component_container::ComponentGetter;
 use itp_extrinsics_factory::CreateExtrinsics;
 use itp_node_api::metadata::{
 	pallet_enclave_bridge::EnclaveBridgeCallIndexes, provider::AccessNodeMetadata,
 };
 
 use itp_ocall_api::{EnclaveAttestationOCallApi, EnclaveOnChainOCallApi};
+use itp_pallet_storage::{EnclaveBridgeStorage, EnclaveBridgeStorageKeys};
 
 use itp_types::{
 	parentchain::{AccountId, BlockNumber, ParentchainId},
-	OpaqueCall, ShardIdentifier,
+	OpaqueCall, ShardIdentifier, UpgradableShardConfig,
 };
 use itp_utils::hex::hex_encode;
 use log::*;
+use std::vec::Vec;
 
 use teerex_primitives::EnclaveFingerprint;
 
@@ -67,3 +72,35 @@ pub(crate) fn init_shard_config(shard: ShardIdentifier) -> EnclaveResult<()> {
 		.map_err(|e| Error::Other(e.into()))?;
 	Ok(())
 }
+
+/// The owners of the shard: the authorities of its active config in the enclave bridge pallet,
+/// as of the latest Integritee parentchain block the light client has imported. A shard without
+/// authorities has no owner.
+pub(crate) fn shard_owners(shard: &ShardIdentifier) -> EnclaveResult<Vec<AccountId>> {
+	let header = get_validator_accessor_from_integritee_solo_or_parachain()?
+		.execute_on_validator(|v| v.latest_finalized_header())?;
+	let maybe_shard_config: Option<UpgradableShardConfig> = GLOBAL_OCALL_API_COMPONENT
+		.get()?
+		.get_storage_verified(
+			EnclaveBridgeStorage::shard_config_registry(shard),
+			&header,
+			&ParentchainId::Integritee,
+		)?
+		.value;
+	Ok(maybe_shard_config
+		.and_then(|shard_config| shard_config.active_config.authorities)
+		.unwrap_or_default())
+}
+
+/// Fails unless the account is one of the [`shard_owners`].
+pub(crate) fn ensure_shard_owner(
+	shard: &ShardIdentifier,
+	account: &AccountId,
+) -> EnclaveResult<()> {
+	if !shard_owners(shard)?.contains(account) {
+		return Err(Error::Other(
+			"account is not an owner of the shard on the Integritee parentchain".into(),
+		))
+	}
+	Ok(())
+}
@@ -0,0 +1,47 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Hashing of the effective shard configuration, mirrored on-chain with the block import
+//! confirmations. Not to be confused with the `ShardConfig` of the enclave bridge pallet.
+
+use crate::initialization::global_components::EnclaveStateHandler;
+use ita_stf::shard_configuration;
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_stf_state_handler::handle_state::HandleState;
+use itp_types::{ShardIdentifier, H256};
+use its_sidechain::consensus_common::{Error as ConsensusError, HashShardConfiguration};
+use std::sync::Arc;
+
+pub struct ShardConfigurationHasher {
+	state_handler: Arc<EnclaveStateHandler>,
+}
+
+impl ShardConfigurationHasher {
+	pub fn new(state_handler: Arc<EnclaveStateHandler>) -> Self {
+		ShardConfigurationHasher { state_handler }
+	}
+}
+
+impl HashShardConfiguration for ShardConfigurationHasher {
+	fn shard_configuration_hash(&self, shard: &ShardIdentifier) -> Result<H256, ConsensusError> {
+		let (mut state, _) = self
+			.state_handler
+			.load_cloned(shard)
+			.map_err(|e| ConsensusError::Other(e.into()))?;
+		Ok(state.execute_with(|| shard_configuration::current().hash()))
+	}
+}
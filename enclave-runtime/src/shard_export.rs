@@ -0,0 +1,108 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+//! Export of the state of a shard, re-encrypted to a key of its owner and streamed to a file of
+//! the worker service in bounded chunks.
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::{
+		GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
+	},
+	shard_config::ensure_shard_owner,
+	state_backup::BackupChunkWriter,
+};
+use codec::{Decode, Encode};
+use itp_component_container::ComponentGetter;
+use itp_settings::worker::STATE_BACKUP_CHUNK_SIZE;
+use itp_sgx_crypto::{
+	key_repository::AccessKey,
+	session_key::{EphemeralKeyGenerator, GenerateEphemeralKey, MessageDirection},
+	AesStream,
+};
+use itp_sgx_externalities::{SgxExternalitiesTrait, StateHash};
+use itp_stf_state_handler::handle_state::HandleState;
+use itp_time_utils::now_as_millis;
+use itp_types::shard_export::{
+	ShardExportHeader, SignedShardExportHeader, SignedShardExportRequest, SHARD_EXPORT_VERSION,
+};
+use itp_utils::stream_encoder::StreamEncoder;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, vec::Vec};
+
+#[no_mangle]
+pub unsafe extern "C" fn export_shard_state(
+	request: *const u8,
+	request_size: u32,
+	path: *const u8,
+	path_size: u32,
+) -> sgx_status_t {
+	let mut request_slice = slice::from_raw_parts(request, request_size as usize);
+	let request = match SignedShardExportRequest::decode(&mut request_slice) {
+		Ok(request) => request,
+		Err(e) => {
+			error!("Could not decode the shard export request: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+	let path = slice::from_raw_parts(path, path_size as usize).to_vec();
+
+	if let Err(e) = export_shard_state_internal(&request, path) {
+		warn!("Failed to export the state of shard {:?}: {:?}", request.request.shard, e);
+		return e.into()
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Streams the state of the shard, encrypted to the recipient key of the request, if the request
+/// is signed by an owner of the shard on the Integritee parentchain.
+fn export_shard_state_internal(
+	request: &SignedShardExportRequest,
+	path: Vec<u8>,
+) -> EnclaveResult<()> {
+	if !request.verify_signature() {
+		return Err(Error::Other("invalid signature of the shard export request".into()))
+	}
+	let shard = request.request.shard;
+	ensure_shard_owner(&shard, &request.signer)?;
+	let (state, _) = GLOBAL_STATE_HANDLER_COMPONENT.get()?.load_cloned(&shard)?;
+
+	let recipient = request.request.recipient;
+	let ephemeral_key = EphemeralKeyGenerator.generate()?;
+	let enclave_public = ephemeral_key.public();
+	let session_key = ephemeral_key.agree(&recipient, &recipient, &enclave_public)?;
+
+	let header = ShardExportHeader {
+		version: SHARD_EXPORT_VERSION,
+		shard,
+		owner: request.signer.clone(),
+		timestamp: now_as_millis(),
+		enclave_public,
+		state_hash: state.hash(),
+	};
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let mut writer = BackupChunkWriter::new(
+		path,
+		AesStream::new(&session_key.message_key(MessageDirection::Response, 0))?,
+	);
+	writer.write_plaintext(&SignedShardExportHeader::new(header, &signer).encode())?;
+
+	let bytes_written = StreamEncoder::encode(writer, STATE_BACKUP_CHUNK_SIZE, state.state())
+		.map_err(Error::Sgx)?;
+	info!("Exported {} bytes of the state of shard {:?} to its owner", bytes_written, shard);
+	Ok(())
+}
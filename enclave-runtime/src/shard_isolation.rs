@@ -0,0 +1,134 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+//! Isolation of the shards whose processing failed with an unrecoverable error, so the enclave
+//! keeps serving the other shards.
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::GLOBAL_SHARD_ISOLATION_COMPONENT,
+};
+use codec::Decode;
+use itp_component_container::ComponentGetter;
+use itp_stf_state_handler::error::Error as StateHandlerError;
+use itp_top_pool_author::call_validation::ValidateTrustedCall;
+use itp_types::{panic_policy::PanicPolicy, ShardIdentifier};
+use its_sidechain::consensus_common::ShardIsolation;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{
+	boxed::Box,
+	format,
+	panic::{self, AssertUnwindSafe},
+	slice,
+	string::{String, ToString},
+	sync::Arc,
+};
+
+#[no_mangle]
+pub unsafe extern "C" fn set_panic_policy(policy: *const u8, policy_size: u32) -> sgx_status_t {
+	let mut policy_slice = slice::from_raw_parts(policy, policy_size as usize);
+	let policy = match PanicPolicy::decode(&mut policy_slice) {
+		Ok(policy) => policy,
+		Err(e) => {
+			error!("Could not decode the panic policy: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	match GLOBAL_SHARD_ISOLATION_COMPONENT.get() {
+		Ok(isolation) => isolation.set_policy(policy),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Processes the shard, unless it is isolated. Returns `None` if the shard is skipped.
+///
+/// With [`PanicPolicy::IsolateShard`], a panic or an unrecoverable error isolates the shard
+/// instead of failing the enclave. Locks held outside of `process` are not poisoned by a panic,
+/// because they are not dropped while unwinding. The state lock taken inside of `process` is
+/// released before the panic reaches this function, see
+/// [`itp_stf_state_handler::handle_state::mutate_unpoisoned`]. Locks of data that is mutated in
+/// place, like the light client, stay poisoned, since their data may be inconsistent.
+pub(crate) fn process_unless_isolated<T>(
+	shard: &ShardIdentifier,
+	process: impl FnOnce() -> EnclaveResult<T>,
+) -> EnclaveResult<Option<T>> {
+	let isolation = GLOBAL_SHARD_ISOLATION_COMPONENT.get()?;
+	if isolation.is_isolated(shard) {
+		debug!("Skipping the isolated shard {:?}", shard);
+		return Ok(None)
+	}
+	if isolation.policy() == PanicPolicy::Halt {
+		return process().map(Some)
+	}
+
+	let reason = match panic::catch_unwind(AssertUnwindSafe(process)) {
+		Ok(Ok(result)) => return Ok(Some(result)),
+		Ok(Err(e)) if is_unrecoverable(&e) => format!("{:?}", e),
+		Ok(Err(e)) => return Err(e),
+		Err(panic) => format!("panicked: {}", panic_message(panic)),
+	};
+	if !isolation.isolate(shard, reason.clone()) {
+		return Err(Error::Other(reason.into()))
+	}
+	Ok(None)
+}
+
+/// Rejects the trusted calls to isolated shards before they enter the pool, and validates the
+/// others with the inner validator.
+pub struct RejectIsolatedShards<Validator> {
+	isolation: Arc<ShardIsolation>,
+	validator: Validator,
+}
+
+impl<Validator> RejectIsolatedShards<Validator> {
+	pub fn new(isolation: Arc<ShardIsolation>, validator: Validator) -> Self {
+		RejectIsolatedShards { isolation, validator }
+	}
+}
+
+impl<Validator, TCS> ValidateTrustedCall<TCS> for RejectIsolatedShards<Validator>
+where
+	Validator: ValidateTrustedCall<TCS>,
+{
+	fn validate(&self, shard: &ShardIdentifier, call: &TCS) -> Result<(), String> {
+		if self.isolation.is_isolated(shard) {
+			return Err(format!("Shard {:?} is isolated until the worker is restarted", shard))
+		}
+		self.validator.validate(shard, call)
+	}
+}
+
+/// Errors that recur every time the shard is processed, because its state is corrupt.
+fn is_unrecoverable(error: &Error) -> bool {
+	matches!(
+		error.root_cause(),
+		Error::Codec(_)
+			| Error::StfStateHandler(StateHandlerError::CryptoError(_))
+			| Error::StfStateHandler(StateHandlerError::Other(_))
+	)
+}
+
+fn panic_message(panic: Box<dyn core::any::Any + Send>) -> String {
+	match panic.downcast::<String>() {
+		Ok(message) => *message,
+		Err(panic) => panic
+			.downcast_ref::<&str>()
+			.map_or_else(|| "unknown cause".to_string(), |message| message.to_string()),
+	}
+}
@@ -0,0 +1,42 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{error::Error, initialization::global_components::GLOBAL_SHARD_ROUTES_COMPONENT};
+use codec::Decode;
+use itp_component_container::ComponentGetter;
+use itp_types::shard_routing::ShardRoute;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, vec::Vec};
+
+#[no_mangle]
+pub unsafe extern "C" fn set_shard_routes(routes: *const u8, routes_size: u32) -> sgx_status_t {
+	let mut routes_slice = slice::from_raw_parts(routes, routes_size as usize);
+	let routes = match Vec::<ShardRoute>::decode(&mut routes_slice) {
+		Ok(routes) => routes,
+		Err(e) => {
+			error!("Could not decode the shard routes: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	match GLOBAL_SHARD_ROUTES_COMPONENT.get() {
+		Ok(shard_routes) => shard_routes.set_routes(routes),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
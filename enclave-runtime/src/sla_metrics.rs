@@ -0,0 +1,48 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	error::Result as EnclaveResult,
+	initialization::global_components::{
+		GLOBAL_OCALL_API_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+		GLOBAL_SLA_METRICS_RECORDER_COMPONENT,
+	},
+};
+use itp_component_container::ComponentGetter;
+use itp_ocall_api::EnclaveAttestationOCallApi;
+use itp_sgx_crypto::key_repository::AccessKey;
+use itp_types::{sla_metrics::SignedSlaMetrics, ShardIdentifier};
+use its_sidechain::consensus_common::SlotOutcome;
+use log::*;
+
+/// Feeds the outcome of the slot into the SLA metrics and signs the digest at the end of a period.
+pub(crate) fn end_sla_metrics_slot(
+	shard: &ShardIdentifier,
+	outcome: SlotOutcome,
+) -> EnclaveResult<()> {
+	let mrenclave = GLOBAL_OCALL_API_COMPONENT.get()?.get_mrenclave_of_self()?.m;
+	let recorder = GLOBAL_SLA_METRICS_RECORDER_COMPONENT.get()?;
+	if let Some(metrics) = recorder.on_slot_end(mrenclave, shard, outcome) {
+		info!(
+			"SLA metrics #{}: {} blocks authored and {} missed in {} slots",
+			metrics.sequence, metrics.blocks_authored, metrics.missed_slots, metrics.slots
+		);
+		let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+		recorder.set_latest(SignedSlaMetrics::new(metrics, &signer));
+	}
+	Ok(())
+}
@@ -0,0 +1,242 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Golden-path smoke test an operator runs against a fresh deployment before registering it.
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	get_base_path,
+	initialization::global_components::{
+		EnclaveNodeMetadataRepository, EnclaveStateFileIo, EnclaveStateInitializer, EnclaveStf,
+		GLOBAL_OCALL_API_COMPONENT, GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT,
+		GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_FILE_IO_COMPONENT,
+	},
+};
+use codec::{Decode, Encode};
+use frame_support::traits::UnfilteredDispatchable;
+use ita_sgx_runtime::Runtime;
+use ita_stf::{Getter, TrustedCall, TrustedGetter};
+use itp_component_container::ComponentGetter;
+use itp_ocall_api::EnclaveAttestationOCallApi;
+use itp_sgx_crypto::key_repository::AccessKey;
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_stf_interface::{StateCallInterface, StateGetterInterface};
+use itp_stf_primitives::{
+	traits::{TrustedCallSigning, TrustedCallVerification},
+	types::{AccountId, KeyPair},
+};
+use itp_stf_state_handler::{
+	file_io::{StateDir, StateFileIo},
+	state_initializer::InitializeState,
+};
+use itp_time_utils::now_as_millis;
+use itp_types::{
+	smoke_test::{SignedSmokeTestReport, SmokeTestReport, SmokeTestStep},
+	Balance, ShardIdentifier,
+};
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use sgx_types::sgx_status_t;
+use sp_core::{blake2_256, ed25519, Pair};
+use sp_runtime::MultiAddress;
+use std::{boxed::Box, format, slice, string::String, sync::Arc, vec::Vec};
+
+/// Free balance of the sender before the transfer.
+const SMOKE_TEST_ENDOWMENT: Balance = 1_000_000_000_000;
+
+/// Amount transferred, above the existential deposit so the receiver account is created.
+const SMOKE_TEST_TRANSFER: Balance = 1_000_000;
+
+type StepOutcomes = Vec<(SmokeTestStep, Result<(), Vec<u8>>)>;
+
+#[no_mangle]
+pub unsafe extern "C" fn run_smoke_test(report: *mut u8, report_size: u32) -> sgx_status_t {
+	let signed_report = match run_smoke_test_internal() {
+		Ok(signed_report) => signed_report,
+		Err(e) => {
+			error!("Could not run the smoke test: {:?}", e);
+			return e.into()
+		},
+	};
+	if signed_report.report.passed() {
+		info!("Smoke test passed");
+	} else {
+		warn!("Smoke test failed");
+	}
+
+	let report_slice = slice::from_raw_parts_mut(report, report_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(report_slice, signed_report.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Errors of the components the test depends on abort it, failed steps are reported instead.
+fn run_smoke_test_internal() -> EnclaveResult<SignedSmokeTestReport> {
+	let mrenclave = GLOBAL_OCALL_API_COMPONENT.get()?.get_mrenclave_of_self()?.m;
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let timestamp = now_as_millis();
+
+	let smoke_test = SmokeTest {
+		state_file_io: GLOBAL_STATE_FILE_IO_COMPONENT.get()?,
+		state_initializer: EnclaveStateInitializer::new(
+			GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?,
+		),
+		state_dir: StateDir::new(get_base_path()?),
+		shard: blake2_256(&(b"smoke_test", timestamp).encode()).into(),
+		mrenclave,
+		timestamp,
+	};
+	info!("Running the smoke test against the throwaway shard {:?}", smoke_test.shard);
+	let outcomes = smoke_test.run();
+
+	Ok(SignedSmokeTestReport::new(SmokeTestReport { mrenclave, timestamp, outcomes }, &signer))
+}
+
+/// Works on the state files directly, so the state handler never caches the throwaway shard.
+struct SmokeTest {
+	state_file_io: Arc<EnclaveStateFileIo>,
+	state_initializer: EnclaveStateInitializer,
+	state_dir: StateDir,
+	shard: ShardIdentifier,
+	mrenclave: [u8; 32],
+	timestamp: u64,
+}
+
+impl SmokeTest {
+	fn run(&self) -> StepOutcomes {
+		let sender = ed25519::Pair::from_seed(&blake2_256(b"smoke_test_sender"));
+		let receiver = ed25519::Pair::from_seed(&blake2_256(b"smoke_test_receiver"));
+
+		let mut outcomes = Vec::new();
+		if let Some(state_id) =
+			record(&mut outcomes, SmokeTestStep::InitShard, self.init_shard(&sender))
+		{
+			if let Some(state_id) = record(
+				&mut outcomes,
+				SmokeTestStep::Transfer,
+				self.transfer(state_id, &sender, &receiver),
+			) {
+				record(&mut outcomes, SmokeTestStep::Getter, self.getter(state_id, &receiver));
+			}
+		}
+		// The shard is removed even if a step failed.
+		record(&mut outcomes, SmokeTestStep::PurgeShard, self.purge_shard());
+		outcomes
+	}
+
+	/// Seals a fresh state with an endowed sender to the shard.
+	fn init_shard(&self, sender: &ed25519::Pair) -> Result<u128, String> {
+		let mut state = self.state_initializer.initialize().map_err(|e| format!("{:?}", e))?;
+		let sender: AccountId = sender.public().into();
+		state.execute_with(|| {
+			ita_sgx_runtime::BalancesCall::<Runtime>::force_set_balance {
+				who: MultiAddress::Id(sender),
+				new_free: SMOKE_TEST_ENDOWMENT,
+			}
+			.dispatch_bypass_filter(ita_sgx_runtime::RuntimeOrigin::root())
+			.map_err(|e| format!("could not endow the sender: {:?}", e.error))
+		})?;
+		state.prune_state_diff();
+
+		let state_id = self.timestamp as u128;
+		self.state_file_io
+			.initialize_shard(&self.shard, state_id, &state)
+			.map_err(|e| format!("{:?}", e))?;
+		Ok(state_id)
+	}
+
+	/// Executes a signed transfer on the unsealed state and seals the result as a new state.
+	fn transfer(
+		&self,
+		state_id: u128,
+		sender: &ed25519::Pair,
+		receiver: &ed25519::Pair,
+	) -> Result<u128, String> {
+		let mut state =
+			self.state_file_io.load(&self.shard, state_id).map_err(|e| format!("{:?}", e))?;
+		let call = TrustedCall::balance_transfer(
+			sender.public().into(),
+			receiver.public().into(),
+			SMOKE_TEST_TRANSFER,
+		)
+		.sign(&KeyPair::Ed25519(Box::new(sender.clone())), 0, &self.mrenclave, &self.shard);
+		if !call.verify_signature(&self.mrenclave, &self.shard) {
+			return Err("invalid signature of the trusted call".into())
+		}
+
+		EnclaveStf::execute_call(
+			&mut state,
+			call,
+			&mut Vec::new(),
+			Arc::new(EnclaveNodeMetadataRepository::default()),
+		)
+		.map_err(|e| format!("{:?}", e))?;
+		state.prune_state_diff();
+
+		let next_state_id = state_id + 1;
+		self.state_file_io
+			.write(&self.shard, next_state_id, &state)
+			.map_err(|e| format!("{:?}", e))?;
+		Ok(next_state_id)
+	}
+
+	/// Queries the balance of the receiver on the unsealed state.
+	fn getter(&self, state_id: u128, receiver: &ed25519::Pair) -> Result<(), String> {
+		let mut state =
+			self.state_file_io.load(&self.shard, state_id).map_err(|e| format!("{:?}", e))?;
+		let getter = TrustedGetter::free_balance(receiver.public().into())
+			.sign(&KeyPair::Ed25519(Box::new(receiver.clone())));
+		if !getter.verify_signature() {
+			return Err("invalid signature of the trusted getter".into())
+		}
+
+		let balance = EnclaveStf::execute_getter(&mut state, Getter::trusted(getter))
+			.and_then(|encoded| Balance::decode(&mut encoded.as_slice()).ok());
+		match balance {
+			Some(SMOKE_TEST_TRANSFER) => Ok(()),
+			balance => Err(format!("unexpected balance of the receiver: {:?}", balance)),
+		}
+	}
+
+	fn purge_shard(&self) -> Result<(), String> {
+		self.state_dir.purge_shard_dir(&self.shard);
+		if self.state_dir.shard_exists(&self.shard) {
+			return Err("the shard directory still exists".into())
+		}
+		Ok(())
+	}
+}
+
+/// Records the outcome of the step, returns the value of a passed step.
+fn record<T>(
+	outcomes: &mut StepOutcomes,
+	step: SmokeTestStep,
+	result: Result<T, String>,
+) -> Option<T> {
+	match result {
+		Ok(value) => {
+			outcomes.push((step, Ok(())));
+			Some(value)
+		},
+		Err(e) => {
+			warn!("Smoke test step {:?} failed: {}", step, e);
+			outcomes.push((step, Err(e.into_bytes())));
+			None
+		},
+	}
+}
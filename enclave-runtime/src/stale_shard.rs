@@ -0,0 +1,106 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Stale shards are not authored for, the enclave tries to catch up with a peer instead.
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::{
+		GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT, GLOBAL_STALE_SHARD_DETECTOR_COMPONENT,
+		GLOBAL_STATE_HANDLER_COMPONENT,
+	},
+};
+use itp_component_container::ComponentGetter;
+use itp_enclave_metrics::EnclaveMetric;
+use itp_ocall_api::EnclaveMetricsOCallApi;
+use itp_stf_state_handler::handle_state::HandleState;
+use itp_types::{parentchain::SidechainBlockConfirmation, Header, ShardIdentifier};
+use its_sidechain::{consensus_common::SyncBlockFromPeer, state::SidechainSystemExt};
+use log::*;
+use sgx_types::sgx_status_t;
+
+#[no_mangle]
+pub unsafe extern "C" fn set_stale_shard_threshold(threshold: u64) -> sgx_status_t {
+	match GLOBAL_STALE_SHARD_DETECTOR_COMPONENT.get() {
+		Ok(detector) => detector.set_threshold(threshold),
+		Err(e) => return Error::ComponentContainer(e).into(),
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Fetches the blocks up to the last confirmed one from a peer if the shard is stale. Returns
+/// the latest imported parentchain header and whether the shard is still stale.
+pub(crate) fn catch_up_if_stale<OCallApi: EnclaveMetricsOCallApi>(
+	shard: &ShardIdentifier,
+	parentchain_header: Header,
+	maybe_latest_sidechain_block_confirmation: &Option<SidechainBlockConfirmation>,
+	ocall_api: &OCallApi,
+) -> EnclaveResult<(Header, bool)> {
+	let detector = GLOBAL_STALE_SHARD_DETECTOR_COMPONENT.get()?;
+	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+	let was_stale = detector.is_stale(shard);
+
+	let (block_number, last_block_hash) = state_handler.execute_on_current(shard, |state, _| {
+		(state.get_block_number(), state.get_last_block_hash())
+	})?;
+	let confirmation = match (
+		detector.update(shard, block_number, maybe_latest_sidechain_block_confirmation),
+		maybe_latest_sidechain_block_confirmation,
+	) {
+		(Some(lag), Some(confirmation)) => {
+			update_lag_metric(lag, ocall_api);
+			confirmation
+		},
+		_ => {
+			if was_stale {
+				update_lag_metric(0, ocall_api);
+			}
+			return Ok((parentchain_header, false))
+		},
+	};
+
+	info!("Catching up with a peer on stale shard {:?}", shard);
+	let parentchain_header = match GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT.get()?.sync_from_peer(
+		// The parent hash of the first block, if no block has been imported yet.
+		last_block_hash.unwrap_or_default(),
+		confirmation.block_header_hash,
+		&parentchain_header,
+		*shard,
+	) {
+		Ok(parentchain_header) => parentchain_header,
+		Err(e) => {
+			warn!("Failed to catch up with a peer on stale shard {:?}: {:?}", shard, e);
+			return Ok((parentchain_header, true))
+		},
+	};
+
+	let block_number =
+		state_handler.execute_on_current(shard, |state, _| state.get_block_number())?;
+	let is_stale = detector
+		.update(shard, block_number, maybe_latest_sidechain_block_confirmation)
+		.is_some();
+	if !is_stale {
+		update_lag_metric(0, ocall_api);
+	}
+	Ok((parentchain_header, is_stale))
+}
+
+fn update_lag_metric<OCallApi: EnclaveMetricsOCallApi>(lag: u64, ocall_api: &OCallApi) {
+	if let Err(e) = ocall_api.update_metric(EnclaveMetric::StaleShardLagSet(lag)) {
+		warn!("Failed to update metric for the stale shard lag: {:?}", e);
+	}
+}
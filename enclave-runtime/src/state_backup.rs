@@ -0,0 +1,230 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Backup of the state of a shard, streamed to a file of the worker service in bounded chunks,
+//! and its restore. The format is documented in [`itp_types::state_backup`].
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::{
+		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
+	},
+	ocall::OcallApi,
+};
+use codec::{Decode, Encode, Input};
+use ita_stf::State as StfState;
+use itp_component_container::ComponentGetter;
+use itp_settings::worker::STATE_BACKUP_CHUNK_SIZE;
+use itp_sgx_crypto::{
+	aead::{AeadCiphertext, AeadKey},
+	key_repository::AccessKey,
+	AesStream,
+};
+use itp_sgx_externalities::{SgxExternalitiesTrait, SgxExternalitiesType};
+use itp_stf_state_handler::handle_state::HandleState;
+use itp_types::{state_backup::StateBackupHeader, ShardIdentifier};
+use itp_utils::stream_encoder::{StreamEncoder, WriteChunk};
+use log::*;
+use sgx_rand::{Rng, StdRng};
+use sgx_types::{sgx_status_t, SgxResult};
+use std::{slice, vec::Vec};
+
+#[no_mangle]
+pub unsafe extern "C" fn export_state_backup(
+	shard: *const u8,
+	shard_size: u32,
+	path: *const u8,
+	path_size: u32,
+) -> sgx_status_t {
+	let mut shard_slice = slice::from_raw_parts(shard, shard_size as usize);
+	let shard = match ShardIdentifier::decode(&mut shard_slice) {
+		Ok(shard) => shard,
+		Err(e) => {
+			error!("Could not decode the shard of the state backup: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+	let path = slice::from_raw_parts(path, path_size as usize).to_vec();
+
+	if let Err(e) = export_state_backup_internal(shard, path) {
+		error!("Failed to export a backup of the state of shard {:?}: {:?}", shard, e);
+		return e.into()
+	}
+	sgx_status_t::SGX_SUCCESS
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn import_state_backup(path: *const u8, path_size: u32) -> sgx_status_t {
+	let path = slice::from_raw_parts(path, path_size as usize).to_vec();
+
+	match import_state_backup_internal(path) {
+		Ok(shard) => {
+			info!("Restored the state of shard {:?} from the backup", shard);
+			sgx_status_t::SGX_SUCCESS
+		},
+		Err(e) => {
+			error!("Failed to restore the state backup: {:?}", e);
+			e.into()
+		},
+	}
+}
+
+fn export_state_backup_internal(shard: ShardIdentifier, path: Vec<u8>) -> EnclaveResult<()> {
+	let (state, _) = GLOBAL_STATE_HANDLER_COMPONENT.get()?.load_cloned(&shard)?;
+	let state_key = GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+
+	let mut backup_id = [0u8; 32];
+	StdRng::new()?.fill_bytes(&mut backup_id);
+	let header = StateBackupHeader { shard, backup_id };
+	OcallApi.write_state_backup_chunk(&path, &header.encode())?;
+
+	let mut writer = AeadChunkWriter {
+		path,
+		key: state_key.derive(&header.key_context()).into(),
+		header,
+		index: 0,
+	};
+	let bytes_written = StreamEncoder::encode(&mut writer, STATE_BACKUP_CHUNK_SIZE, state.state())
+		.map_err(Error::Sgx)?;
+	writer.finish()?;
+	info!("Exported {} bytes of the state of shard {:?}", bytes_written, shard);
+	Ok(())
+}
+
+/// Decrypts the backup at `path` and replaces the state of its shard with it.
+///
+/// The decrypted state is only written once the whole backup has been authenticated. Returns
+/// the shard of the backup.
+fn import_state_backup_internal(path: Vec<u8>) -> EnclaveResult<ShardIdentifier> {
+	let state_key = GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let mut reader = BackupChunkReader { path, offset: 0, chunk: Vec::new(), position: 0 };
+	let header = StateBackupHeader::decode(&mut reader)?;
+	let key: AeadKey = state_key.derive(&header.key_context()).into();
+
+	let mut encoded_state = Vec::new();
+	let mut index = 0;
+	loop {
+		let (is_last, ciphertext) = <(bool, AeadCiphertext)>::decode(&mut reader)?;
+		let plaintext = key.decrypt(&ciphertext, &header.chunk_aad(index, is_last))?;
+		if is_last {
+			break
+		}
+		encoded_state.extend(plaintext);
+		index += 1;
+	}
+
+	let state = StfState::new(SgxExternalitiesType::decode(&mut encoded_state.as_slice())?);
+	GLOBAL_STATE_HANDLER_COMPONENT.get()?.reset(state, &header.shard)?;
+	Ok(header.shard)
+}
+
+/// Encrypts every chunk under a random nonce and has the worker service append it to the backup
+/// file. [`AeadChunkWriter::finish`] must be called once all chunks are written.
+struct AeadChunkWriter {
+	path: Vec<u8>,
+	key: AeadKey,
+	header: StateBackupHeader,
+	index: u64,
+}
+
+impl AeadChunkWriter {
+	fn write(&mut self, chunk: &[u8], is_last: bool) -> SgxResult<()> {
+		let ciphertext = self
+			.key
+			.encrypt_with_random_nonce(chunk, &self.header.chunk_aad(self.index, is_last))?;
+		self.index += 1;
+		OcallApi.write_state_backup_chunk(&self.path, &(is_last, ciphertext).encode())
+	}
+
+	/// Writes the empty last chunk, without it the backup is rejected as truncated.
+	fn finish(mut self) -> SgxResult<()> {
+		self.write(&[], true)
+	}
+}
+
+impl WriteChunk for &mut AeadChunkWriter {
+	type Error = sgx_status_t;
+
+	fn write_chunk(&mut self, chunk: &[u8]) -> SgxResult<()> {
+		self.write(chunk, false)
+	}
+}
+
+/// Reads the backup file through the worker service, at most one chunk at a time.
+struct BackupChunkReader {
+	path: Vec<u8>,
+	/// Offset in the file of the end of the current chunk.
+	offset: u64,
+	chunk: Vec<u8>,
+	/// Position of the next unread byte in the current chunk.
+	position: usize,
+}
+
+impl Input for BackupChunkReader {
+	fn remaining_len(&mut self) -> Result<Option<usize>, codec::Error> {
+		Ok(None)
+	}
+
+	fn read(&mut self, into: &mut [u8]) -> Result<(), codec::Error> {
+		let mut filled = 0;
+		while filled < into.len() {
+			if self.position == self.chunk.len() {
+				self.chunk = OcallApi
+					.read_state_backup_chunk(&self.path, self.offset, STATE_BACKUP_CHUNK_SIZE)
+					.map_err(|_| codec::Error::from("Could not read the state backup"))?;
+				if self.chunk.is_empty() {
+					return Err("Unexpected end of the state backup".into())
+				}
+				self.offset += self.chunk.len() as u64;
+				self.position = 0;
+			}
+			let length = (into.len() - filled).min(self.chunk.len() - self.position);
+			into[filled..filled + length]
+				.copy_from_slice(&self.chunk[self.position..self.position + length]);
+			filled += length;
+			self.position += length;
+		}
+		Ok(())
+	}
+}
+
+/// Encrypts the chunks with a continuous keystream and has the worker service append them to
+/// the file. Unlike the backups, the shard exports are verified with a signed state hash.
+pub(crate) struct BackupChunkWriter {
+	path: Vec<u8>,
+	cipher: AesStream,
+}
+
+impl BackupChunkWriter {
+	pub(crate) fn new(path: Vec<u8>, cipher: AesStream) -> Self {
+		BackupChunkWriter { path, cipher }
+	}
+
+	pub(crate) fn write_plaintext(&self, chunk: &[u8]) -> SgxResult<()> {
+		OcallApi.write_state_backup_chunk(&self.path, chunk)
+	}
+}
+
+impl WriteChunk for BackupChunkWriter {
+	type Error = sgx_status_t;
+
+	fn write_chunk(&mut self, chunk: &[u8]) -> SgxResult<()> {
+		let mut ciphertext = chunk.to_vec();
+		self.cipher.apply(&mut ciphertext);
+		self.write_plaintext(&ciphertext)
+	}
+}
@@ -0,0 +1,98 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Comparison of two state snapshots of a shard, revealing only key hashes and value sizes.
+
+use crate::{
+	error::{Error, Result as EnclaveResult},
+	initialization::global_components::GLOBAL_STATE_FILE_IO_COMPONENT,
+};
+use codec::{Decode, Encode};
+use itp_component_container::ComponentGetter;
+use itp_settings::worker::STATE_DIFF_MAX_CHANGES;
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_stf_state_handler::file_io::StateFileIo;
+use itp_types::state_diff::{KeyHashSalt, StateDiff, StateDiffRequest};
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use once_cell::sync::OnceCell;
+use sgx_rand::{Rng, StdRng};
+use sgx_types::sgx_status_t;
+use std::slice;
+
+/// Salt of the key hashes, drawn once per run of the enclave. The diffs of one run can be
+/// compared with each other, but the operator can't hash guessed keys to match them.
+static KEY_HASH_SALT: OnceCell<KeyHashSalt> = OnceCell::new();
+
+#[no_mangle]
+pub unsafe extern "C" fn diff_state_snapshots(
+	request: *const u8,
+	request_size: u32,
+	diff: *mut u8,
+	diff_size: u32,
+) -> sgx_status_t {
+	let mut request_slice = slice::from_raw_parts(request, request_size as usize);
+	let request = match StateDiffRequest::decode(&mut request_slice) {
+		Ok(request) => request,
+		Err(e) => {
+			error!("Could not decode the state diff request: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	let state_diff = match diff_state_snapshots_internal(&request) {
+		Ok(state_diff) => state_diff,
+		Err(e) => {
+			error!("Could not diff the state snapshots of shard {:?}: {:?}", request.shard, e);
+			return e.into()
+		},
+	};
+
+	let diff_slice = slice::from_raw_parts_mut(diff, diff_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(diff_slice, state_diff.encode()) {
+		return Error::BufferError(e).into()
+	};
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Loads both snapshots from their state files, bypassing the state handler cache.
+fn diff_state_snapshots_internal(request: &StateDiffRequest) -> EnclaveResult<StateDiff> {
+	let state_file_io = GLOBAL_STATE_FILE_IO_COMPONENT.get()?;
+	let from = state_file_io.load(&request.shard, request.from)?;
+	let to = state_file_io.load(&request.shard, request.to)?;
+
+	let state_diff =
+		StateDiff::between(from.state(), to.state(), STATE_DIFF_MAX_CHANGES, key_hash_salt()?);
+	info!(
+		"State of shard {:?} changed from snapshot {} to {}: {} keys added, {} removed, {} modified",
+		request.shard,
+		request.from,
+		request.to,
+		state_diff.added,
+		state_diff.removed,
+		state_diff.modified
+	);
+	Ok(state_diff)
+}
+
+fn key_hash_salt() -> EnclaveResult<&'static KeyHashSalt> {
+	KEY_HASH_SALT.get_or_try_init(|| {
+		let mut salt = KeyHashSalt::default();
+		StdRng::new()?.fill_bytes(&mut salt);
+		Ok(salt)
+	})
+}
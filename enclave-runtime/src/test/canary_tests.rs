@@ -0,0 +1,49 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	canary::run_canary_round_internal, get_base_path, initialization::restrict_file_access,
+};
+use itp_sgx_io::access_policy::replace_allowed_path_prefixes;
+use itp_types::ShardIdentifier;
+use log::*;
+use std::{path::PathBuf, vec::Vec};
+
+/// Reverts the file access policy when dropped, also if the test panics, so the policy doesn't
+/// leak into the tests running after it.
+struct RestoreFileAccessPolicy(Option<Vec<PathBuf>>);
+
+impl Drop for RestoreFileAccessPolicy {
+	fn drop(&mut self) {
+		if let Err(e) = replace_allowed_path_prefixes(self.0.take()) {
+			error!("Could not restore the file access policy: {:?}", e);
+		}
+	}
+}
+
+/// The canary works on its own state directory, which must be accessible under the file access
+/// policy of the enclave. Two rounds, so the state written by the first round is loaded again.
+pub fn canary_round_passes_with_the_file_access_policy_installed() {
+	let _restore = RestoreFileAccessPolicy(replace_allowed_path_prefixes(None).unwrap());
+	restrict_file_access(&get_base_path().unwrap()).unwrap();
+	let shard = ShardIdentifier::from([7u8; 32]);
+
+	for _ in 0..2 {
+		let report = run_canary_round_internal(shard).unwrap();
+		assert!(report.passed(), "canary round failed: {:?}", report.failure());
+	}
+}
@@ -17,7 +17,9 @@
 */
 use crate::test::mocks::attestation_ocall_mock::AttestationOCallMock;
 use hex::FromHexError;
-use itp_attestation_handler::cert::{verify_attn_report, verify_mra_cert};
+use itp_attestation_handler::cert::{
+	mrenclave_of_mra_cert, verify_attn_report, verify_mra_cert, PeerMeasurementPolicy,
+};
 use sgx_types::{sgx_measurement_t, sgx_status_t, SGX_HASH_SIZE};
 use std::vec::Vec;
 
@@ -35,16 +37,58 @@ pub fn test_verify_mra_cert_should_work() {
 	let mr_enclave = get_mr_enclave_from_hex_string(TEST4_MRENCLAVE).unwrap();
 	let attestation_ocall =
 		AttestationOCallMock::create_with_mr_enclave(sgx_measurement_t { m: mr_enclave });
-	let result = verify_mra_cert(TEST4_CERT, false, false, &attestation_ocall);
+	let result = verify_mra_cert(
+		TEST4_CERT,
+		false,
+		false,
+		PeerMeasurementPolicy::SameMrEnclave,
+		&attestation_ocall,
+	);
 
 	assert!(result.is_ok());
 }
 
+pub fn test_verify_mra_cert_accepts_announced_upgrade_mrenclave() {
+	let mr_enclave = get_mr_enclave_from_hex_string(TEST4_MRENCLAVE).unwrap();
+	let attestation_ocall =
+		AttestationOCallMock::create_with_mr_enclave(sgx_measurement_t { m: [0u8; SGX_HASH_SIZE] });
+
+	let same_result = verify_mra_cert(
+		TEST4_CERT,
+		false,
+		false,
+		PeerMeasurementPolicy::SameMrEnclave,
+		&attestation_ocall,
+	);
+	let upgrade_result = verify_mra_cert(
+		TEST4_CERT,
+		false,
+		false,
+		PeerMeasurementPolicy::SameOrUpgradeMrEnclave(mr_enclave),
+		&attestation_ocall,
+	);
+
+	assert!(same_result.is_err());
+	assert!(upgrade_result.is_ok());
+}
+
+pub fn test_mrenclave_of_mra_cert_is_the_one_of_the_attestation_report() {
+	let mr_enclave = get_mr_enclave_from_hex_string(TEST4_MRENCLAVE).unwrap();
+
+	assert_eq!(mrenclave_of_mra_cert(TEST4_CERT, false), Ok(mr_enclave));
+}
+
 pub fn test_verify_wrong_cert_is_err() {
 	let mr_enclave = get_mr_enclave_from_hex_string(TEST4_MRENCLAVE).unwrap();
 	let attestation_ocall =
 		AttestationOCallMock::create_with_mr_enclave(sgx_measurement_t { m: mr_enclave });
-	let result = verify_mra_cert(CERT_WRONG_PLATFORM_BLOB, false, false, &attestation_ocall);
+	let result = verify_mra_cert(
+		CERT_WRONG_PLATFORM_BLOB,
+		false,
+		false,
+		PeerMeasurementPolicy::SameMrEnclave,
+		&attestation_ocall,
+	);
 
 	assert!(result.is_err());
 	assert_eq!(result.unwrap_err(), sgx_status_t::SGX_ERROR_UNEXPECTED);
@@ -52,7 +96,12 @@ pub fn test_verify_wrong_cert_is_err() {
 
 pub fn test_given_wrong_platform_info_when_verifying_attestation_report_then_return_error() {
 	let attestation_ocall = AttestationOCallMock::new();
-	let result = verify_attn_report(CERT_WRONG_PLATFORM_BLOB, Vec::new(), &attestation_ocall);
+	let result = verify_attn_report(
+		CERT_WRONG_PLATFORM_BLOB,
+		Vec::new(),
+		PeerMeasurementPolicy::SameMrEnclave,
+		&attestation_ocall,
+	);
 
 	assert!(result.is_err());
 	assert_eq!(result.unwrap_err(), sgx_status_t::SGX_ERROR_UNEXPECTED);
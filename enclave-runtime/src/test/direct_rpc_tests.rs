@@ -16,7 +16,13 @@
 
 */
 
-use crate::{rpc::worker_api_direct::public_api_rpc_handler, Hash};
+use crate::{
+	rpc::{
+		account_watch_subscriptions::AccountWatchSubscriptions,
+		worker_api_direct::public_api_rpc_handler,
+	},
+	Hash,
+};
 use codec::{Decode, Encode};
 use ita_stf::{Getter, TrustedGetter, TrustedGetterSigned};
 use itc_direct_rpc_server::{
@@ -25,15 +31,18 @@ use itc_direct_rpc_server::{
 };
 use itc_tls_websocket_server::{ConnectionToken, WebSocketMessageHandler};
 use itp_rpc::{RpcRequest, RpcReturnValue};
-use itp_sgx_crypto::get_rsa3072_repository;
+use itp_sgx_crypto::{get_rsa3072_repository, session_key::EphemeralKeyGenerator};
 use itp_sgx_temp_dir::TempDir;
 use itp_stf_executor::{getter_executor::GetterExecutor, mocks::GetStateMock};
 use itp_stf_state_observer::mock::ObserveStateMock;
 use itp_top_pool_author::mocks::AuthorApiMock;
 use itp_types::{AccountId, DirectRequestStatus, Request, ShardIdentifier};
 use itp_utils::{FromHexPrefixed, ToHexPrefixed};
-use sp_core::ed25519::Signature;
-use sp_runtime::MultiSignature;
+use its_sidechain::rpc_handler::{account_watch::AccountWatches, shard_routing::ShardRoutes};
+use sp_core::{
+	ed25519::{self, Signature},
+	Pair,
+};
 use std::{string::ToString, sync::Arc, vec::Vec};
 
 pub fn get_state_request_works() {
@@ -50,14 +59,27 @@ pub fn get_state_request_works() {
 	let getter_executor =
 		Arc::new(GetterExecutor::<_, GetStateMock<TestState>, Getter>::new(state_observer));
 	let top_pool_author = Arc::new(AuthorApiMock::default());
-
-	let io_handler =
-		public_api_rpc_handler(top_pool_author, getter_executor, Arc::new(rsa_repository));
-	let rpc_handler = Arc::new(RpcWsHandler::new(io_handler, watch_extractor, connection_registry));
+	let account_watches = Arc::new(AccountWatches::default());
+
+	let io_handler = public_api_rpc_handler(
+		top_pool_author,
+		getter_executor,
+		Arc::new(rsa_repository),
+		Arc::new(ShardRoutes::default()),
+		account_watches.clone(),
+	);
+	let rpc_handler = Arc::new(RpcWsHandler::new(
+		io_handler,
+		watch_extractor,
+		connection_registry,
+		Arc::new(AccountWatchSubscriptions::new(account_watches)),
+		Arc::new(EphemeralKeyGenerator),
+		ed25519::Pair::from_seed(&[1u8; 32]),
+	));
 
 	let getter = Getter::trusted(TrustedGetterSigned::new(
 		TrustedGetter::nonce(AccountId::new([0u8; 32])),
-		MultiSignature::Ed25519(Signature::from_raw([0u8; 64])),
+		Signature::from_raw([0u8; 64]).into(),
 	));
 
 	let request = Request { shard: ShardIdentifier::default(), cyphertext: getter.encode() };
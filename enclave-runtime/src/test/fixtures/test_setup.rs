@@ -33,7 +33,11 @@ use itp_test::mock::{
 	shielding_crypto_mock::ShieldingCryptoMock,
 };
 use itp_top_pool::{basic_pool::BasicPool, pool::ExtrinsicHash};
-use itp_top_pool_author::{api::SidechainApi, author::Author, top_filter::AllowAllTopsFilter};
+use itp_top_pool_author::{
+	api::SidechainApi, author::Author, call_validation::AcceptAllCalls, load_shedding::LoadShedder,
+	persistence::PersistenceExclusions, quarantine::PayloadQuarantine,
+	top_filter::AllowAllTopsFilter,
+};
 use itp_types::{Block, MrEnclave};
 use sp_core::{crypto::Pair, ed25519 as spEd25519};
 use std::sync::Arc;
@@ -99,6 +103,10 @@ pub fn test_setup() -> (
 			state_handler.clone(),
 			shielding_key_repo,
 			Arc::new(MetricsOCallMock::default()),
+			Arc::new(PayloadQuarantine::default()),
+			Arc::new(LoadShedder::default()),
+			Arc::new(PersistenceExclusions::default()),
+			Arc::new(AcceptAllCalls),
 		)),
 		state,
 		shard,
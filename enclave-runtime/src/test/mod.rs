@@ -16,6 +16,7 @@
 
 */
 
+pub mod canary_tests;
 pub mod cert_tests;
 pub mod direct_rpc_tests;
 pub mod enclave_signer_tests;
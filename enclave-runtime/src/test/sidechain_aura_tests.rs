@@ -47,9 +47,15 @@ use itp_stf_interface::system_pallet::{SystemPalletAccountInterface, SystemPalle
 use itp_stf_primitives::types::{StatePayload, TrustedOperation};
 use itp_stf_state_handler::handle_state::HandleState;
 use itp_test::mock::{handle_state_mock::HandleStateMock, metrics_ocall_mock::MetricsOCallMock};
-use itp_time_utils::duration_now;
-use itp_top_pool_author::{top_filter::AllowAllTopsFilter, traits::AuthorApi};
-use itp_types::{AccountId, Block as ParentchainBlock, ShardIdentifier};
+use itp_time_utils::{duration_now, now_as_millis};
+use itp_top_pool_author::{
+	call_validation::AcceptAllCalls, load_shedding::LoadShedder, maintenance::MaintenanceSchedule,
+	persistence::PersistenceExclusions, quarantine::PayloadQuarantine,
+	top_filter::AllowAllTopsFilter, traits::AuthorApi,
+};
+use itp_types::{
+	maintenance_window::MaintenanceWindow, AccountId, Block as ParentchainBlock, ShardIdentifier,
+};
 use its_block_verification::slot::slot_from_timestamp_and_duration;
 use its_primitives::{traits::Block, types::SignedBlock as SignedSidechainBlock};
 use its_sidechain::{aura::proposer_factory::ProposerFactory, slots::SlotInfo};
@@ -95,7 +101,7 @@ pub fn produce_sidechain_block_and_import_it() {
 	let stf_executor = Arc::new(TestStfExecutor::new(
 		ocall_api.clone(),
 		state_handler.clone(),
-		node_metadata_repo,
+		node_metadata_repo.clone(),
 	));
 	let top_pool = create_top_pool();
 
@@ -105,6 +111,10 @@ pub fn produce_sidechain_block_and_import_it() {
 		state_handler.clone(),
 		shielding_key_repo,
 		Arc::new(MetricsOCallMock::default()),
+		Arc::new(PayloadQuarantine::default()),
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
 	));
 	let parentchain_block_import_trigger = Arc::new(TestParentchainBlockImportTrigger::default());
 	let block_importer = Arc::new(TestBlockImporter::new(
@@ -115,8 +125,14 @@ pub fn produce_sidechain_block_and_import_it() {
 		ocall_api.clone(),
 	));
 	let block_composer = Arc::new(TestBlockComposer::new(signer, state_key_repo));
-	let proposer_environment =
-		ProposerFactory::new(top_pool_author.clone(), stf_executor, block_composer);
+	let proposer_environment = ProposerFactory::new(
+		top_pool_author.clone(),
+		stf_executor,
+		block_composer,
+		node_metadata_repo,
+		Arc::new(MaintenanceSchedule::default()),
+		Arc::new(LoadShedder::default()),
+	);
 
 	info!("Create trusted operations..");
 	let sender = endowed_account();
@@ -225,6 +241,94 @@ pub fn produce_sidechain_block_and_import_it() {
 	assert!(!TestStf::get_events(&mut state).is_empty());
 }
 
+/// Trusted calls of a shard under maintenance stay in the TOP pool, and still count for the nonce
+/// of their sender, while the block is produced without them.
+pub fn trusted_calls_are_deferred_during_a_maintenance_window() {
+	if WorkerModeProvider::worker_mode() != WorkerMode::Sidechain {
+		info!("Ignoring sidechain maintenance window test: Not in sidechain mode");
+		return
+	}
+
+	let signer = TestSigner::from_seed(b"42315678901234567890123456789012");
+	let shielding_key = TestShieldingKey::new().unwrap();
+	let state_key_repo = Arc::new(TestStateKeyRepo::new(TestStateKey::new([3u8; 16], [1u8; 16])));
+	let parentchain_header = ParentchainHeaderBuilder::default().build();
+	let ocall_api = create_ocall_api(&parentchain_header, &signer);
+
+	let state_handler = Arc::new(TestStateHandler::default());
+	let enclave_call_signer = enclave_call_signer(&shielding_key);
+	let (_, shard_id) = init_state(state_handler.as_ref(), enclave_call_signer.public().into());
+
+	let node_metadata_repo = Arc::new(NodeMetadataRepository::new(NodeMetadataMock::new()));
+	let stf_executor = Arc::new(TestStfExecutor::new(
+		ocall_api.clone(),
+		state_handler.clone(),
+		node_metadata_repo.clone(),
+	));
+	let top_pool_author = Arc::new(TestTopPoolAuthor::new(
+		create_top_pool(),
+		AllowAllTopsFilter::<TrustedCallSigned, Getter>::new(),
+		state_handler.clone(),
+		Arc::new(TestShieldingKeyRepo::new(shielding_key)),
+		Arc::new(MetricsOCallMock::default()),
+		Arc::new(PayloadQuarantine::default()),
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
+	));
+	let maintenance_schedule = Arc::new(MaintenanceSchedule::default());
+	let now = now_as_millis();
+	maintenance_schedule
+		.set_windows(shard_id, vec![MaintenanceWindow { start: now - 1000, end: now + 60_000 }]);
+	let proposer_environment = ProposerFactory::new(
+		top_pool_author.clone(),
+		stf_executor,
+		Arc::new(TestBlockComposer::new(signer, state_key_repo)),
+		node_metadata_repo,
+		maintenance_schedule,
+		Arc::new(LoadShedder::default()),
+	);
+
+	let sender = endowed_account();
+	let sender_account: AccountId = sender.public().into();
+	let trusted_operation = encrypted_trusted_operation_transfer_balance(
+		ocall_api.as_ref(),
+		&shard_id,
+		&shielding_key,
+		sender,
+		unendowed_account().public().into(),
+		1000,
+	);
+	executor::block_on(top_pool_author.submit_top(trusted_operation, shard_id)).unwrap();
+
+	let timestamp = duration_now();
+	let slot_info = SlotInfo::new(
+		slot_from_timestamp_and_duration(timestamp, SLOT_DURATION),
+		timestamp,
+		SLOT_DURATION,
+		timestamp + SLOT_DURATION,
+		parentchain_header,
+		None,
+		None,
+	);
+	let (blocks, _) =
+		exec_aura_on_slot::<_, ParentchainBlock, SignedSidechainBlock, _, _, _, _, _>(
+			slot_info,
+			signer,
+			ocall_api,
+			Arc::new(TestParentchainBlockImportTrigger::default()),
+			None::<Arc<TestParentchainBlockImportTrigger>>,
+			None::<Arc<TestParentchainBlockImportTrigger>>,
+			proposer_environment,
+			vec![shard_id],
+		)
+		.unwrap();
+
+	assert_eq!(1, blocks.len());
+	assert_eq!(1, top_pool_author.get_pending_trusted_calls(shard_id).len());
+	assert_eq!(1, top_pool_author.get_pending_trusted_calls_for(shard_id, &sender_account).len());
+}
+
 fn encrypted_trusted_operation_transfer_balance<
 	AttestationApi: EnclaveAttestationOCallApi,
 	ShieldingKey: ShieldingCryptoEncrypt,
@@ -40,7 +40,11 @@ use itp_stf_interface::system_pallet::SystemPalletEventInterface;
 use itp_stf_state_handler::handle_state::HandleState;
 use itp_test::mock::metrics_ocall_mock::MetricsOCallMock;
 use itp_time_utils::duration_now;
-use itp_top_pool_author::top_filter::AllowAllTopsFilter;
+use itp_top_pool_author::{
+	call_validation::AcceptAllCalls, load_shedding::LoadShedder, maintenance::MaintenanceSchedule,
+	persistence::PersistenceExclusions, quarantine::PayloadQuarantine,
+	top_filter::AllowAllTopsFilter,
+};
 use itp_types::Block as ParentchainBlock;
 use its_block_verification::slot::slot_from_timestamp_and_duration;
 use its_primitives::types::SignedBlock as SignedSidechainBlock;
@@ -83,7 +87,7 @@ pub fn ensure_events_get_reset_upon_block_proposal() {
 	let stf_executor = Arc::new(TestStfExecutor::new(
 		ocall_api.clone(),
 		state_handler.clone(),
-		node_metadata_repo,
+		node_metadata_repo.clone(),
 	));
 	let top_pool = create_top_pool();
 
@@ -93,6 +97,10 @@ pub fn ensure_events_get_reset_upon_block_proposal() {
 		state_handler.clone(),
 		shielding_key_repo,
 		Arc::new(MetricsOCallMock::default()),
+		Arc::new(PayloadQuarantine::default()),
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
 	));
 	let parentchain_block_import_trigger = Arc::new(TestParentchainBlockImportTrigger::default());
 	let block_importer = Arc::new(TestBlockImporter::new(
@@ -103,7 +111,14 @@ pub fn ensure_events_get_reset_upon_block_proposal() {
 		ocall_api.clone(),
 	));
 	let block_composer = Arc::new(TestBlockComposer::new(signer, state_key_repo));
-	let proposer_environment = ProposerFactory::new(top_pool_author, stf_executor, block_composer);
+	let proposer_environment = ProposerFactory::new(
+		top_pool_author,
+		stf_executor,
+		block_composer,
+		node_metadata_repo,
+		Arc::new(MaintenanceSchedule::default()),
+		Arc::new(LoadShedder::default()),
+	);
 
 	// Add some events to the state.
 	let topic_hash = H256::from([7; 32]);
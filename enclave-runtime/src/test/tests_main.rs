@@ -18,9 +18,10 @@
 use crate::test::evm_pallet_tests;
 
 use crate::{
-	rpc,
+	key_ceremony, rpc,
 	sync::tests::{enclave_rw_lock_works, sidechain_rw_lock_works},
 	test::{
+		canary_tests,
 		cert_tests::*,
 		direct_rpc_tests, enclave_signer_tests,
 		fixtures::test_setup::{
@@ -81,6 +82,26 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		stf_sgx_tests::enclave_account_initialization_works,
 		stf_sgx_tests::shield_funds_increments_signer_account_nonce,
 		stf_sgx_tests::test_root_account_exists_after_initialization,
+		stf_sgx_tests::transfer_above_compliance_threshold_requires_attestation,
+		stf_sgx_tests::module_balance_movements_are_gated_on_compliance_when_validating,
+		stf_sgx_tests::disabled_feature_rejects_calls_from_its_activation_block,
+		stf_sgx_tests::scheduled_state_migration_runs_once_at_its_activation_block,
+		stf_sgx_tests::aggregate_queries_are_noisy_and_limited_by_the_privacy_budget,
+		stf_sgx_tests::state_is_hashed_with_the_algorithm_set_by_root,
+		stf_sgx_tests::shard_configuration_hash_changes_with_the_configuration,
+		stf_sgx_tests::order_book_matches_orders_at_block_initialization,
+		stf_sgx_tests::order_book_events_are_only_available_to_the_order_owner,
+		stf_sgx_tests::voting_publishes_result_to_parentchain_after_deadline,
+		stf_sgx_tests::auction_pays_highest_sealed_bid_to_seller_after_close,
+		stf_sgx_tests::ended_auction_is_settled_by_the_block_hook,
+		stf_sgx_tests::payment_channel_streams_rate_per_block_until_closed,
+		stf_sgx_tests::payment_channel_is_closed_if_the_reserved_deposit_runs_short,
+		stf_sgx_tests::recovery_moves_funds_to_rescuer_after_approvals_and_delay,
+		stf_sgx_tests::multisig_executes_proposal_once_threshold_is_reached,
+		stf_sgx_tests::proxy_can_only_send_calls_within_its_scope,
+		stf_sgx_tests::only_events_of_disclosing_calls_are_public,
+		stf_sgx_tests::events_are_never_attributed_to_more_than_one_call,
+		stf_sgx_tests::deposit_addresses_can_only_be_swept_by_their_parent,
 		itp_stf_state_handler::test::sgx_tests::test_write_and_load_state_works,
 		itp_stf_state_handler::test::sgx_tests::test_sgx_state_decode_encode_works,
 		itp_stf_state_handler::test::sgx_tests::test_encrypt_decrypt_state_type_works,
@@ -114,6 +135,8 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		test_retrieve_event_count,
 		test_reset_events,
 		rpc::worker_api_direct::tests::test_given_io_handler_methods_then_retrieve_all_names_as_string,
+		crate::error::tests::context_keeps_the_domain_and_the_sgx_status_of_the_error,
+		crate::error::tests::rpc_error_carries_the_code_of_the_domain,
 		handle_state_mock::tests::initialized_shards_list_is_empty,
 		handle_state_mock::tests::shard_exists_after_inserting,
 		handle_state_mock::tests::from_shard_works,
@@ -123,6 +146,8 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		handle_state_mock::tests::ensure_encode_and_encrypt_does_not_affect_state_hash,
 		// mra cert tests
 		test_verify_mra_cert_should_work,
+		test_verify_mra_cert_accepts_announced_upgrade_mrenclave,
+		test_mrenclave_of_mra_cert_is_the_one_of_the_attestation_report,
 		test_verify_wrong_cert_is_err,
 		test_given_wrong_platform_info_when_verifying_attestation_report_then_return_error,
 		// sync tests
@@ -137,8 +162,10 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		enclave_signer_tests::derive_key_is_deterministic,
 		enclave_signer_tests::nonce_is_computed_correctly,
 		state_getter_tests::state_getter_works,
+		canary_tests::canary_round_passes_with_the_file_access_policy_installed,
 		// sidechain integration tests
 		sidechain_aura_tests::produce_sidechain_block_and_import_it,
+		sidechain_aura_tests::trusted_calls_are_deferred_during_a_maintenance_window,
 		sidechain_event_tests::ensure_events_get_reset_upon_block_proposal,
 		top_pool_tests::process_indirect_call_in_top_pool,
 		top_pool_tests::submit_shielding_call_to_top_pool,
@@ -152,6 +179,17 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		tls_ra::seal_handler::test::seal_state_works,
 		tls_ra::seal_handler::test::seal_state_fails_for_invalid_state,
 		tls_ra::seal_handler::test::unseal_seal_state_works,
+		tls_ra::seal_handler::test::staged_state_is_kept_until_discarded,
+		key_ceremony::tests::committed_state_key_rotation_is_completed_on_recovery,
+		key_ceremony::tests::uncommitted_state_key_rotation_is_discarded_on_recovery,
+		tls_ra::compression::tests::compression_round_trip_works,
+		tls_ra::compression::tests::negotiation_picks_first_supported_offer,
+		tls_ra::compression::tests::unknown_offered_compressions_are_skipped,
+		tls_ra::compression::tests::decompressing_invalid_zstd_payload_fails,
+		tls_ra::compression::tests::decompressing_oversized_payload_fails,
+		tls_ra::state_chunks::tests::chunked_state_round_trip_works,
+		tls_ra::state_chunks::tests::applying_diff_against_snapshot_yields_new_state,
+		tls_ra::state_chunks::tests::applying_tampered_chunk_fails,
 		tls_ra::tests::test_tls_ra_server_client_networking,
 		tls_ra::tests::test_state_and_key_provisioning,
 		// RPC tests
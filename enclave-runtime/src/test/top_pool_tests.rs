@@ -54,7 +54,11 @@ use itp_stf_executor::enclave_signer::StfEnclaveSigner;
 use itp_stf_primitives::{traits::TrustedCallVerification, types::TrustedOperation};
 use itp_stf_state_observer::mock::ObserveStateMock;
 use itp_test::mock::metrics_ocall_mock::MetricsOCallMock;
-use itp_top_pool_author::{top_filter::AllowAllTopsFilter, traits::AuthorApi};
+use itp_top_pool_author::{
+	call_validation::AcceptAllCalls, load_shedding::LoadShedder,
+	persistence::PersistenceExclusions, quarantine::PayloadQuarantine,
+	top_filter::AllowAllTopsFilter, traits::AuthorApi,
+};
 use itp_types::{
 	parentchain::{Address, ParentchainId},
 	AccountId, Block, ShardIdentifier, ShieldFundsFn, H256,
@@ -87,6 +91,10 @@ pub fn process_indirect_call_in_top_pool() {
 		state_handler,
 		shielding_key_repo,
 		Arc::new(MetricsOCallMock::default()),
+		Arc::new(PayloadQuarantine::default()),
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
 	));
 
 	let encrypted_indirect_call =
@@ -120,6 +128,10 @@ pub fn submit_shielding_call_to_top_pool() {
 		state_handler,
 		shielding_key_repo.clone(),
 		Arc::new(MetricsOCallMock::default()),
+		Arc::new(PayloadQuarantine::default()),
+		Arc::new(LoadShedder::default()),
+		Arc::new(PersistenceExclusions::default()),
+		Arc::new(AcceptAllCalls),
 	));
 
 	let enclave_signer =
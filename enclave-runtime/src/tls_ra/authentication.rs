@@ -16,8 +16,10 @@
 */
 
 //! Remote attestation certificate authentication of server and client
-use itp_attestation_handler::cert;
+use itp_attestation_handler::cert::{self, PeerMeasurementPolicy};
+use itp_enclave_upgrade::GLOBAL_UPGRADE_COORDINATOR;
 use itp_ocall_api::EnclaveAttestationOCallApi;
+use itp_types::ShardIdentifier;
 use log::*;
 use sgx_types::*;
 use webpki::DNSName;
@@ -72,7 +74,16 @@ where
 		let is_dcap = true;
 		#[cfg(not(feature = "dcap"))]
 		let is_dcap = false;
-		match cert::verify_mra_cert(&certs[0].0, true, is_dcap, &self.attestation_ocall) {
+		// An enclave with the announced upgrade MRENCLAVE may be provisioned with our secrets.
+		let policy = match GLOBAL_UPGRADE_COORDINATOR.scheduled() {
+			Ok(Some(upgrade)) => PeerMeasurementPolicy::SameOrUpgradeMrEnclave(upgrade.mrenclave),
+			Ok(None) => PeerMeasurementPolicy::SameMrEnclave,
+			Err(e) => {
+				error!("Could not read the scheduled enclave upgrade: {:?}", e);
+				PeerMeasurementPolicy::SameMrEnclave
+			},
+		};
+		match cert::verify_mra_cert(&certs[0].0, true, is_dcap, policy, &self.attestation_ocall) {
 			Ok(()) => Ok(rustls::ClientCertVerified::assertion()),
 			Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) =>
 				if self.outdated_ok {
@@ -89,12 +100,19 @@ where
 pub struct ServerAuth<A> {
 	outdated_ok: bool,
 	skip_ra: bool,
+	/// Shard that is requested from the server.
+	shard: ShardIdentifier,
 	attestation_ocall: A,
 }
 
 impl<A> ServerAuth<A> {
-	pub fn new(outdated_ok: bool, skip_ra: bool, attestation_ocall: A) -> Self {
-		ServerAuth { outdated_ok, skip_ra, attestation_ocall }
+	pub fn new(
+		outdated_ok: bool,
+		skip_ra: bool,
+		shard: ShardIdentifier,
+		attestation_ocall: A,
+	) -> Self {
+		ServerAuth { outdated_ok, skip_ra, shard, attestation_ocall }
 	}
 }
 
@@ -130,8 +148,17 @@ where
 		let is_dcap = true;
 		#[cfg(not(feature = "dcap"))]
 		let is_dcap = false;
+		// The server may run the upgrade announced on chain for the requested shard.
+		let policy = match GLOBAL_UPGRADE_COORDINATOR.scheduled_for(&self.shard) {
+			Ok(Some(upgrade)) => PeerMeasurementPolicy::SameOrUpgradeMrEnclave(upgrade.mrenclave),
+			Ok(None) => PeerMeasurementPolicy::SameMrEnclave,
+			Err(e) => {
+				error!("Could not read the scheduled enclave upgrade: {:?}", e);
+				PeerMeasurementPolicy::SameMrEnclave
+			},
+		};
 		// This call will automatically verify cert is properly signed
-		match cert::verify_mra_cert(&certs[0].0, true, is_dcap, &self.attestation_ocall) {
+		match cert::verify_mra_cert(&certs[0].0, true, is_dcap, policy, &self.attestation_ocall) {
 			Ok(()) => Ok(rustls::ServerCertVerified::assertion()),
 			Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) =>
 				if self.outdated_ok {
@@ -0,0 +1,180 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Compression of the provisioning payloads.
+//!
+//! The client offers the algorithms it supports in order of preference, the server picks the
+//! first one it supports as well and announces it before sending any payload. All payloads
+//! after the announcement are compressed with the selected algorithm.
+
+use crate::error::{Error as EnclaveError, Result as EnclaveResult};
+use codec::{Decode, Encode};
+use ruzstd::{
+	decoding::StreamingDecoder,
+	encoding::{compress_to_vec, CompressionLevel},
+	io::Read,
+};
+use std::{vec, vec::Vec};
+
+/// Supported algorithms, in order of preference.
+pub const SUPPORTED_COMPRESSIONS: [Compression; 3] =
+	[Compression::Zstd, Compression::Lz4, Compression::None];
+
+/// Upper bound of a decompressed payload, to not allocate arbitrary amounts of memory.
+pub const MAX_DECOMPRESSED_SIZE: usize = 512 * 1024 * 1024;
+
+const SIZE_PREFIX_LENGTH: usize = std::mem::size_of::<u32>();
+
+/// Size of the chunks a zstd payload is decompressed in.
+const ZSTD_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Decode, Encode)]
+pub enum Compression {
+	#[default]
+	None,
+	/// LZ4 block format, prefixed with the little endian `u32` decompressed size.
+	Lz4,
+	/// zstd frame, compressed by the pure Rust `ruzstd` implementation.
+	Zstd,
+}
+
+impl Compression {
+	/// Picks the first offered algorithm that is supported, falls back to no compression.
+	pub fn negotiate(offered: &[Compression]) -> Compression {
+		offered
+			.iter()
+			.find(|c| SUPPORTED_COMPRESSIONS.contains(c))
+			.copied()
+			.unwrap_or_default()
+	}
+
+	/// Decodes an encoded offer, skipping the algorithms we don't know, e.g. ones a newer client
+	/// supports. Each algorithm is encoded as its single byte variant index.
+	pub fn decode_offer(encoded: &[u8]) -> EnclaveResult<Vec<Compression>> {
+		let indices = Vec::<u8>::decode(&mut &encoded[..])?;
+		Ok(indices
+			.into_iter()
+			.filter_map(|index| Compression::decode(&mut &[index][..]).ok())
+			.collect())
+	}
+
+	pub fn compress(self, bytes: &[u8]) -> Vec<u8> {
+		match self {
+			Compression::None => bytes.to_vec(),
+			Compression::Lz4 => lz4_flex::block::compress_prepend_size(bytes),
+			Compression::Zstd => compress_to_vec(bytes, CompressionLevel::Fastest),
+		}
+	}
+
+	pub fn decompress(self, bytes: Vec<u8>) -> EnclaveResult<Vec<u8>> {
+		match self {
+			Compression::None => Ok(bytes),
+			Compression::Lz4 => {
+				if bytes.len() < SIZE_PREFIX_LENGTH {
+					return Err(EnclaveError::Other("compressed payload is too short".into()))
+				}
+				let mut size = [0u8; SIZE_PREFIX_LENGTH];
+				size.copy_from_slice(&bytes[..SIZE_PREFIX_LENGTH]);
+				let size = u32::from_le_bytes(size) as usize;
+				ensure_decompressed_size(size)?;
+				lz4_flex::block::decompress(&bytes[SIZE_PREFIX_LENGTH..], size)
+					.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))
+			},
+			Compression::Zstd => zstd_decompress(&bytes),
+		}
+	}
+}
+
+/// Decompresses in chunks, so a frame that expands beyond the limit is rejected before it is
+/// fully decompressed.
+fn zstd_decompress(bytes: &[u8]) -> EnclaveResult<Vec<u8>> {
+	let mut source = bytes;
+	let mut decoder = StreamingDecoder::new(&mut source)
+		.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))?;
+	let mut decompressed = Vec::new();
+	let mut chunk = vec![0u8; ZSTD_CHUNK_SIZE];
+	loop {
+		let read = decoder
+			.read(&mut chunk)
+			.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))?;
+		if read == 0 {
+			return Ok(decompressed)
+		}
+		ensure_decompressed_size(decompressed.len() + read)?;
+		decompressed.extend_from_slice(&chunk[..read]);
+	}
+}
+
+fn ensure_decompressed_size(size: usize) -> EnclaveResult<()> {
+	if size > MAX_DECOMPRESSED_SIZE {
+		return Err(EnclaveError::Other(
+			format!("decompressed payload of {} bytes is too large", size).into(),
+		))
+	}
+	Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+
+	pub fn compression_round_trip_works() {
+		let payload = [[1u8; 1000].as_slice(), b"some state".as_slice()].concat();
+
+		for compression in SUPPORTED_COMPRESSIONS {
+			let compressed = compression.compress(&payload);
+			assert_eq!(payload, compression.decompress(compressed).unwrap());
+		}
+		assert!(Compression::Lz4.compress(&payload).len() < payload.len());
+		assert!(Compression::Zstd.compress(&payload).len() < payload.len());
+	}
+
+	pub fn negotiation_picks_first_supported_offer() {
+		assert_eq!(Compression::Zstd, Compression::negotiate(&SUPPORTED_COMPRESSIONS));
+		assert_eq!(
+			Compression::Lz4,
+			Compression::negotiate(&[Compression::Lz4, Compression::Zstd])
+		);
+		assert_eq!(Compression::None, Compression::negotiate(&[Compression::None]));
+		assert_eq!(Compression::None, Compression::negotiate(&[]));
+	}
+
+	pub fn unknown_offered_compressions_are_skipped() {
+		let mut offer = SUPPORTED_COMPRESSIONS.to_vec().encode();
+		assert_eq!(Compression::decode_offer(&offer).unwrap(), SUPPORTED_COMPRESSIONS.to_vec());
+
+		// A newer client prefers an algorithm we don't know.
+		offer = vec![42u8, 1, 0].encode();
+		assert_eq!(
+			Compression::decode_offer(&offer).unwrap(),
+			vec![Compression::Lz4, Compression::None]
+		);
+		assert!(Compression::decode_offer(&[0xff]).is_err());
+	}
+
+	pub fn decompressing_invalid_zstd_payload_fails() {
+		assert!(Compression::Zstd.decompress(b"not zstd".to_vec()).is_err());
+		assert!(Compression::Zstd.decompress(Vec::new()).is_err());
+	}
+
+	pub fn decompressing_oversized_payload_fails() {
+		let mut payload = ((MAX_DECOMPRESSED_SIZE + 1) as u32).to_le_bytes().to_vec();
+		payload.extend_from_slice(&[0u8; 16]);
+
+		assert!(Compression::Lz4.decompress(payload).is_err());
+	}
+}
@@ -29,6 +29,7 @@ pub struct SealHandlerMock {
 	pub state_key: Arc<RwLock<Vec<u8>>>,
 	pub state: Arc<RwLock<Vec<u8>>>,
 	pub light_client_state: Arc<RwLock<Vec<u8>>>,
+	pub staged_state: Arc<RwLock<Option<Vec<u8>>>>,
 }
 
 impl SealHandlerMock {
@@ -38,7 +39,13 @@ impl SealHandlerMock {
 		state: Arc<RwLock<Vec<u8>>>,
 		light_client_state: Arc<RwLock<Vec<u8>>>,
 	) -> Self {
-		Self { shielding_key, state_key, state, light_client_state }
+		Self {
+			shielding_key,
+			state_key,
+			state,
+			light_client_state,
+			staged_state: Default::default(),
+		}
 	}
 }
 
@@ -66,6 +73,20 @@ impl SealStateAndKeys for SealHandlerMock {
 		*self.light_client_state.write().unwrap() = bytes.to_vec();
 		Ok(())
 	}
+
+	fn seal_staged_state(&self, bytes: &[u8], _shard: &ShardIdentifier) -> EnclaveResult<()> {
+		*self.staged_state.write().unwrap() = Some(bytes.to_vec());
+		Ok(())
+	}
+
+	fn unseal_staged_state(&self, _shard: &ShardIdentifier) -> EnclaveResult<Option<Vec<u8>>> {
+		Ok(self.staged_state.read().unwrap().clone())
+	}
+
+	fn discard_staged_state(&self, _shard: &ShardIdentifier) -> EnclaveResult<()> {
+		*self.staged_state.write().unwrap() = None;
+		Ok(())
+	}
 }
 
 impl UnsealStateAndKeys for SealHandlerMock {
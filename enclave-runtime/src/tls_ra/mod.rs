@@ -18,11 +18,15 @@
 //! Contains all logic of the state provisioning mechanism
 //! including the remote attestation and tls / tcp connection part.
 
+use crate::error::Error as EnclaveError;
 use codec::{Decode, Encode, MaxEncodedLen};
 use itp_types::{AccountId, ShardIdentifier};
+use std::convert::TryFrom;
 
 mod authentication;
+pub mod compression;
 pub mod seal_handler;
+pub mod state_chunks;
 mod tls_ra_client;
 mod tls_ra_server;
 
@@ -53,17 +57,38 @@ pub enum Opcode {
 	StateKey,
 	State,
 	LightClient,
+	/// Hashes of the state chunks the client already has, sent by the client.
+	KnownStateChunks,
+	/// Hashes of all state chunks of the server.
+	StateManifest,
+	/// A state chunk that differs from the client's known state.
+	StateChunk,
+	/// Compression algorithms supported by the client, in order of preference.
+	CompressionOffer,
+	/// Compression algorithm selected by the server for all following payloads.
+	Compression,
+	/// Announced upgrade of the enclave, so the provisioned enclave knows when to take over.
+	UpgradeSchedule,
 }
 
-impl From<u8> for Opcode {
-	fn from(item: u8) -> Self {
-		match item {
+/// A peer sending an unknown opcode is disconnected.
+impl TryFrom<u8> for Opcode {
+	type Error = EnclaveError;
+
+	fn try_from(item: u8) -> Result<Self, Self::Error> {
+		Ok(match item {
 			0 => Opcode::ShieldingKey,
 			1 => Opcode::StateKey,
 			2 => Opcode::State,
 			3 => Opcode::LightClient,
-			_ => unimplemented!("Unsupported/unknown Opcode for MU-RA exchange"),
-		}
+			4 => Opcode::KnownStateChunks,
+			5 => Opcode::StateManifest,
+			6 => Opcode::StateChunk,
+			7 => Opcode::CompressionOffer,
+			8 => Opcode::Compression,
+			9 => Opcode::UpgradeSchedule,
+			_ => return Err(EnclaveError::Other(format!("unknown opcode {}", item).into())),
+		})
 	}
 }
 
@@ -27,11 +27,12 @@ use itp_sgx_crypto::{
 	Aes,
 };
 use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_sgx_io::{seal, unseal};
 use itp_stf_state_handler::handle_state::HandleState;
 use itp_types::ShardIdentifier;
 use log::*;
 use sgx_crypto_helper::rsa3072::Rsa3072KeyPair;
-use std::{sync::Arc, vec::Vec};
+use std::{fs, io::ErrorKind, path::PathBuf, sync::Arc, vec::Vec};
 
 /// Handles the sealing and unsealing of the shielding key, state key and the state.
 #[derive(Default)]
@@ -40,6 +41,8 @@ pub struct SealHandler<ShieldingKeyRepository, StateKeyRepository, StateHandler,
 	state_key_repository: Arc<StateKeyRepository>,
 	shielding_key_repository: Arc<ShieldingKeyRepository>,
 	light_client_seal: Arc<LightClientSeal>,
+	/// Directory of the partially provisioned states.
+	staging_dir: PathBuf,
 }
 
 impl<ShieldingKeyRepository, StateKeyRepository, StateHandler, LightClientSeal>
@@ -50,8 +53,19 @@ impl<ShieldingKeyRepository, StateKeyRepository, StateHandler, LightClientSeal>
 		state_key_repository: Arc<StateKeyRepository>,
 		shielding_key_repository: Arc<ShieldingKeyRepository>,
 		light_client_seal: Arc<LightClientSeal>,
+		staging_dir: PathBuf,
 	) -> Self {
-		Self { state_handler, state_key_repository, shielding_key_repository, light_client_seal }
+		Self {
+			state_handler,
+			state_key_repository,
+			shielding_key_repository,
+			light_client_seal,
+			staging_dir,
+		}
+	}
+
+	fn staged_state_path(&self, shard: &ShardIdentifier) -> PathBuf {
+		self.staging_dir.join(format!("{}.bin", hex::encode(shard.encode())))
 	}
 }
 
@@ -61,6 +75,11 @@ pub trait SealStateAndKeys {
 	fn seal_state(&self, bytes: &[u8], shard: &ShardIdentifier) -> EnclaveResult<()>;
 	fn seal_new_empty_state(&self, shard: &ShardIdentifier) -> EnclaveResult<()>;
 	fn seal_light_client_state(&self, bytes: &[u8]) -> EnclaveResult<()>;
+	/// Seals a partially provisioned state apart from the state of the shard, so a retried
+	/// provisioning continues from it without the shard running on an incomplete state.
+	fn seal_staged_state(&self, bytes: &[u8], shard: &ShardIdentifier) -> EnclaveResult<()>;
+	fn unseal_staged_state(&self, shard: &ShardIdentifier) -> EnclaveResult<Option<Vec<u8>>>;
+	fn discard_staged_state(&self, shard: &ShardIdentifier) -> EnclaveResult<()>;
 }
 
 pub trait UnsealStateAndKeys {
@@ -124,6 +143,28 @@ where
 		info!("Successfully reset state with new enclave account, for shard {:?}", shard);
 		Ok(())
 	}
+
+	fn seal_staged_state(&self, bytes: &[u8], shard: &ShardIdentifier) -> EnclaveResult<()> {
+		fs::create_dir_all(&self.staging_dir)?;
+		seal(bytes, self.staged_state_path(shard))?;
+		info!("Staged partially provisioned state of shard {:?}", shard);
+		Ok(())
+	}
+
+	fn unseal_staged_state(&self, shard: &ShardIdentifier) -> EnclaveResult<Option<Vec<u8>>> {
+		match unseal(self.staged_state_path(shard)) {
+			Ok(bytes) => Ok(Some(bytes)),
+			Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(e.into()),
+		}
+	}
+
+	fn discard_staged_state(&self, shard: &ShardIdentifier) -> EnclaveResult<()> {
+		match fs::remove_file(self.staged_state_path(shard)) {
+			Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+			_ => Ok(()),
+		}
+	}
 }
 
 impl<ShieldingKeyRepository, StateKeyRepository, StateHandler, LightClientSeal> UnsealStateAndKeys
@@ -164,6 +205,7 @@ pub mod test {
 	use super::*;
 	use itc_parentchain::light_client::mocks::validator_mock_seal::LightValidationStateSealMock;
 	use itp_sgx_crypto::mocks::KeyRepositoryMock;
+	use itp_sgx_temp_dir::TempDir;
 	use itp_test::mock::handle_state_mock::HandleStateMock;
 
 	type StateKeyRepositoryMock = KeyRepositoryMock<Aes>;
@@ -265,4 +307,24 @@ pub mod test {
 
 		assert!(result.is_ok());
 	}
+
+	pub fn staged_state_is_kept_until_discarded() {
+		let temp_dir = TempDir::with_prefix("staged_state_is_kept_until_discarded").unwrap();
+		let seal_handler = SealHandlerMock::new(
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			temp_dir.path().join("staged_states"),
+		);
+		let shard = ShardIdentifier::default();
+		assert_eq!(seal_handler.unseal_staged_state(&shard).unwrap(), None);
+
+		seal_handler.seal_staged_state(&[1, 2, 3], &shard).unwrap();
+		assert_eq!(seal_handler.unseal_staged_state(&shard).unwrap(), Some(vec![1, 2, 3]));
+
+		seal_handler.discard_staged_state(&shard).unwrap();
+		assert_eq!(seal_handler.unseal_staged_state(&shard).unwrap(), None);
+		seal_handler.discard_staged_state(&shard).unwrap();
+	}
 }
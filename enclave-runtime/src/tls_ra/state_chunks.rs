@@ -0,0 +1,171 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Chunking of the state for incremental state provisioning.
+//!
+//! The state is split into [`NUMBER_OF_CHUNKS`] chunks by the hash of each key, so the chunk
+//! boundaries only depend on the keys and stay stable while the state changes. The client
+//! sends the hashes of the chunks it already has, and the server only sends the chunks that
+//! differ. Every received chunk is verified against the server's manifest before it is applied.
+
+use crate::error::{Error as EnclaveError, Result as EnclaveResult};
+use codec::{Decode, Encode};
+use ita_stf::StateType as StfStateType;
+use itp_types::H256;
+use sp_core::hashing::blake2_256;
+use std::vec::Vec;
+
+pub const NUMBER_OF_CHUNKS: usize = 256;
+
+type Entries = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Hashes of all chunks of a state.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Decode, Encode)]
+pub struct StateManifest {
+	pub chunk_hashes: Vec<H256>,
+}
+
+impl StateManifest {
+	/// Root hash committing to the whole state.
+	pub fn root(&self) -> H256 {
+		self.chunk_hashes.using_encoded(blake2_256).into()
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Decode, Encode)]
+pub struct StateChunk {
+	pub index: u8,
+	pub entries: Entries,
+}
+
+/// A state split into chunks, with the entries of each chunk sorted by key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChunkedState {
+	chunks: Vec<Entries>,
+}
+
+impl ChunkedState {
+	pub fn new(mut state: StfStateType) -> Self {
+		let mut chunks = vec![Entries::new(); NUMBER_OF_CHUNKS];
+		// Iterating the sorted state keeps the entries within each chunk sorted as well.
+		for (key, value) in std::mem::take(&mut *state) {
+			chunks[chunk_index(&key) as usize].push((key, value));
+		}
+		Self { chunks }
+	}
+
+	pub fn manifest(&self) -> StateManifest {
+		StateManifest { chunk_hashes: self.chunks.iter().map(|c| chunk_hash(c)).collect() }
+	}
+
+	/// Chunks that differ from the given chunk hashes of a known snapshot.
+	pub fn diff(&self, known_chunk_hashes: &[H256]) -> Vec<StateChunk> {
+		self.chunks
+			.iter()
+			.enumerate()
+			.filter(|(index, entries)| known_chunk_hashes.get(*index) != Some(&chunk_hash(entries)))
+			.map(|(index, entries)| StateChunk { index: index as u8, entries: entries.clone() })
+			.collect()
+	}
+
+	/// Verifies a chunk against the manifest and replaces the corresponding local chunk.
+	pub fn apply(&mut self, chunk: StateChunk, manifest: &StateManifest) -> EnclaveResult<()> {
+		let expected_hash = manifest
+			.chunk_hashes
+			.get(chunk.index as usize)
+			.ok_or_else(|| EnclaveError::Other("state manifest is incomplete".into()))?;
+		if &chunk_hash(&chunk.entries) != expected_hash {
+			return Err(EnclaveError::Other(
+				format!("state chunk {} does not match the manifest", chunk.index).into(),
+			))
+		}
+		if chunk.entries.iter().any(|(key, _)| chunk_index(key) != chunk.index) {
+			return Err(EnclaveError::Other(
+				format!("state chunk {} contains foreign keys", chunk.index).into(),
+			))
+		}
+		self.chunks[chunk.index as usize] = chunk.entries;
+		Ok(())
+	}
+
+	pub fn matches(&self, manifest: &StateManifest) -> bool {
+		self.manifest() == *manifest
+	}
+
+	pub fn into_state(self) -> StfStateType {
+		let mut state = StfStateType::default();
+		state.extend(self.chunks.into_iter().flatten());
+		state
+	}
+}
+
+fn chunk_index(key: &[u8]) -> u8 {
+	blake2_256(key)[0]
+}
+
+fn chunk_hash(entries: &[(Vec<u8>, Vec<u8>)]) -> H256 {
+	entries.using_encoded(blake2_256).into()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+
+	fn state(entries: &[(&str, &str)]) -> StfStateType {
+		let mut state = StfStateType::default();
+		state.extend(entries.iter().map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec())));
+		state
+	}
+
+	pub fn chunked_state_round_trip_works() {
+		let state = state(&[("a", "1"), ("b", "2"), ("c", "3")]);
+
+		let chunked = ChunkedState::new(state.clone());
+
+		assert_eq!(NUMBER_OF_CHUNKS, chunked.manifest().chunk_hashes.len());
+		assert_eq!(state, chunked.into_state());
+	}
+
+	pub fn applying_diff_against_snapshot_yields_new_state() {
+		let snapshot = state(&[("a", "1"), ("b", "2"), ("c", "3")]);
+		let new_state = state(&[("a", "1"), ("b", "5"), ("d", "4")]);
+		let mut local = ChunkedState::new(snapshot);
+		let remote = ChunkedState::new(new_state.clone());
+		let manifest = remote.manifest();
+
+		let diff = remote.diff(&local.manifest().chunk_hashes);
+		// Only the chunks of the changed, removed and added key differ.
+		assert!(diff.len() <= 3);
+		for chunk in diff {
+			local.apply(chunk, &manifest).unwrap();
+		}
+
+		assert!(local.matches(&manifest));
+		assert_eq!(new_state, local.into_state());
+	}
+
+	pub fn applying_tampered_chunk_fails() {
+		let remote = ChunkedState::new(state(&[("a", "1")]));
+		let manifest = remote.manifest();
+		let mut chunk = remote.diff(&[]).into_iter().find(|c| !c.entries.is_empty()).unwrap();
+		chunk.entries[0].1 = b"2".to_vec();
+
+		let mut local = ChunkedState::new(StfStateType::default());
+
+		assert!(local.apply(chunk, &manifest).is_err());
+	}
+}
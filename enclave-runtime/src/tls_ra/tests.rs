@@ -25,10 +25,12 @@ use crate::{
 	initialization::global_components::EnclaveStf,
 	tls_ra::seal_handler::{SealHandler, SealStateAndKeys, UnsealStateAndKeys},
 };
-use ita_stf::State;
+use codec::Encode;
+use ita_stf::{State, StateType as StfStateType};
 use itc_parentchain::light_client::mocks::validator_mock_seal::LightValidationStateSealMock;
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
 use itp_sgx_crypto::{mocks::KeyRepositoryMock, Aes};
+use itp_sgx_temp_dir::TempDir;
 use itp_stf_interface::InitState;
 use itp_stf_primitives::types::AccountId;
 use itp_stf_state_handler::handle_state::HandleState;
@@ -39,6 +41,7 @@ use sgx_types::{sgx_quote_sign_type_t, sgx_target_info_t};
 use std::{
 	net::{TcpListener, TcpStream},
 	os::unix::io::AsRawFd,
+	path::PathBuf,
 	string::String,
 	sync::{Arc, SgxRwLock as RwLock},
 	thread,
@@ -75,7 +78,10 @@ pub fn test_tls_ra_server_client_networking() {
 	let client_account = AccountId::from([42; 32]);
 	let shielding_key_encoded = vec![1, 2, 3];
 	let state_key_encoded = vec![5, 2, 3, 7];
-	let state_encoded = Vec::from([1u8; 26000]); // Have a decently sized state, so read() must be called multiple times.
+	// Have a decently sized state, so read() must be called multiple times.
+	let mut state = StfStateType::default();
+	state.extend((0u32..1000).map(|i| (i.encode(), Vec::from([1u8; 26]))));
+	let state_encoded = state.encode();
 	let light_client_state_encoded = Vec::from([1u8; 10000]); // Have a decently sized state, so read() must be called multiple times.
 
 	let server_seal_handler = SealHandlerMock::new(
@@ -147,10 +153,22 @@ pub fn test_state_and_key_provisioning() {
 	let initialized_state = EnclaveStf::init_state(AccountId::new([1u8; 32]));
 	let shard = ShardIdentifier::from([1u8; 32]);
 
-	let server_seal_handler =
-		create_seal_handler(state_key, shielding_key, initialized_state, &shard);
-	let client_seal_handler =
-		create_seal_handler(Aes::default(), Rsa3072KeyPair::default(), State::default(), &shard);
+	let temp_dir = TempDir::with_prefix("test_state_and_key_provisioning").unwrap();
+
+	let server_seal_handler = create_seal_handler(
+		state_key,
+		shielding_key,
+		initialized_state,
+		&shard,
+		temp_dir.path().join("server"),
+	);
+	let client_seal_handler = create_seal_handler(
+		Aes::default(),
+		Rsa3072KeyPair::default(),
+		State::default(),
+		&shard,
+		temp_dir.path().join("client"),
+	);
 
 	let port: u16 = 3150;
 
@@ -185,6 +203,7 @@ fn create_seal_handler(
 	shielding_key: Rsa3072KeyPair,
 	state: State,
 	shard: &ShardIdentifier,
+	staging_dir: PathBuf,
 ) -> impl UnsealStateAndKeys + SealStateAndKeys {
 	let state_key_repository = Arc::new(KeyRepositoryMock::<Aes>::new(state_key));
 	let shielding_key_repository =
@@ -193,5 +212,11 @@ fn create_seal_handler(
 	state_handler.reset(state, shard).unwrap();
 	let seal = Arc::new(LightValidationStateSealMock::new());
 
-	SealHandler::new(state_handler, state_key_repository, shielding_key_repository, seal)
+	SealHandler::new(
+		state_handler,
+		state_key_repository,
+		shielding_key_repository,
+		seal,
+		staging_dir,
+	)
 }
@@ -17,25 +17,36 @@
 
 //! Implementation of the client part of the state provisioning.
 
-use super::{authentication::ServerAuth, Opcode, TcpHeader};
+use super::{
+	authentication::ServerAuth,
+	compression::{Compression, SUPPORTED_COMPRESSIONS},
+	state_chunks::{ChunkedState, StateChunk, StateManifest},
+	Opcode, TcpHeader,
+};
 use crate::{
 	attestation::create_ra_report_and_signature,
 	error::{Error as EnclaveError, Result as EnclaveResult},
+	get_base_path,
 	initialization::global_components::{
 		EnclaveSealHandler, GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL,
 		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
 	},
 	ocall::OcallApi,
 	shard_config::init_shard_config,
-	tls_ra::{seal_handler::SealStateAndKeys, ClientProvisioningRequest},
+	tls_ra::{
+		seal_handler::{SealStateAndKeys, UnsealStateAndKeys},
+		ClientProvisioningRequest,
+	},
 	GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
 };
-use codec::Encode;
-
+use codec::{Decode, Encode};
+use ita_stf::StateType as StfStateType;
 use itp_attestation_handler::{RemoteAttestationType, DEV_HOSTNAME};
 use itp_component_container::ComponentGetter;
+use itp_enclave_upgrade::{ScheduledUpgrade, GLOBAL_UPGRADE_COORDINATOR};
 
 use itp_ocall_api::EnclaveAttestationOCallApi;
+use itp_settings::files::STAGED_STATES_PATH;
 use itp_sgx_crypto::key_repository::AccessPubkey;
 use itp_types::{AccountId, ShardIdentifier};
 
@@ -55,23 +66,35 @@ use std::{
 /// Includes a seal handler, which handles the storage part of the received data.
 struct TlsClient<'a, StateAndKeySealer>
 where
-	StateAndKeySealer: SealStateAndKeys,
+	StateAndKeySealer: SealStateAndKeys + UnsealStateAndKeys,
 {
 	tls_stream: Stream<'a, ClientSession, TcpStream>,
 	seal_handler: StateAndKeySealer,
 	shard: ShardIdentifier,
+	/// Local state, which the received state chunks are applied to.
+	chunked_state: ChunkedState,
+	state_manifest: Option<StateManifest>,
+	/// Compression of the received payloads, as announced by the server.
+	compression: Compression,
 }
 
 impl<'a, StateAndKeySealer> TlsClient<'a, StateAndKeySealer>
 where
-	StateAndKeySealer: SealStateAndKeys,
+	StateAndKeySealer: SealStateAndKeys + UnsealStateAndKeys,
 {
 	fn new(
 		tls_stream: Stream<'a, ClientSession, TcpStream>,
 		seal_handler: StateAndKeySealer,
 		shard: ShardIdentifier,
 	) -> TlsClient<StateAndKeySealer> {
-		TlsClient { tls_stream, seal_handler, shard }
+		TlsClient {
+			tls_stream,
+			seal_handler,
+			shard,
+			chunked_state: ChunkedState::new(StfStateType::default()),
+			state_manifest: None,
+			compression: Compression::None,
+		}
 	}
 
 	/// Read all data sent by the server of the specific shard.
@@ -84,6 +107,8 @@ where
 		);
 		self.send_provisioning_request(account)?;
 		debug!("self.send_provisioning_request() succeeded.");
+		self.send_known_state_chunks()?;
+		self.write(Opcode::CompressionOffer, &SUPPORTED_COMPRESSIONS.to_vec().encode())?;
 		self.read_and_seal_all()
 	}
 
@@ -96,12 +121,52 @@ where
 		Ok(())
 	}
 
+	/// Send the chunk hashes of our local state, so the server only sends the chunks that differ.
+	///
+	/// The state staged by an aborted provisioning takes precedence over the state of the shard.
+	/// If there is no usable local state, we send no hashes and receive the full state.
+	fn send_known_state_chunks(&mut self) -> EnclaveResult<()> {
+		let local_state = self
+			.seal_handler
+			.unseal_staged_state(&self.shard)
+			.ok()
+			.flatten()
+			.or_else(|| self.seal_handler.unseal_state(&self.shard).ok())
+			.and_then(|bytes| StfStateType::decode(&mut bytes.as_slice()).ok());
+		let known_chunk_hashes = match local_state {
+			Some(state) => {
+				self.chunked_state = ChunkedState::new(state);
+				self.chunked_state.manifest().chunk_hashes
+			},
+			None => {
+				debug!("No local state of shard {:?}, requesting the full state", self.shard);
+				Vec::new()
+			},
+		};
+		self.write(Opcode::KnownStateChunks, &known_chunk_hashes.encode())
+	}
+
+	/// Send a header / payload pair to the server.
+	fn write(&mut self, opcode: Opcode, bytes: &[u8]) -> EnclaveResult<()> {
+		let mut message = opcode.to_bytes().to_vec();
+		message.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+		message.extend_from_slice(bytes);
+		self.tls_stream.write_all(&message)?;
+		Ok(())
+	}
+
 	/// Read and seal all relevant data sent by the server.
 	fn read_and_seal_all(&mut self) -> EnclaveResult<()> {
 		let mut received_payloads: Vec<Opcode> = Vec::new();
 
 		loop {
-			let maybe_opcode = self.read_and_seal()?;
+			let maybe_opcode = match self.read_and_seal() {
+				Ok(o) => o,
+				Err(e) => {
+					self.seal_partially_synced_state();
+					return Err(e)
+				},
+			};
 			match maybe_opcode {
 				None => break,
 				Some(o) => {
@@ -109,12 +174,14 @@ where
 				},
 			}
 		}
+		self.seal_synced_state()?;
 		info!("Successfully read and sealed all data sent by the state provisioning server.");
 
 		// In case we receive a shielding key, but no state, we need to reset our state
 		// to update the enclave account.
 		if received_payloads.contains(&Opcode::ShieldingKey)
 			&& !received_payloads.contains(&Opcode::State)
+			&& !received_payloads.contains(&Opcode::StateManifest)
 		{
 			self.seal_handler.seal_new_empty_state(&self.shard)?;
 		}
@@ -132,20 +199,86 @@ where
 		}
 		let header = self.read_header(start_byte[0])?;
 		let bytes = self.read_until(header.payload_length as usize)?;
+		if header.opcode == Opcode::Compression {
+			self.compression = Compression::decode(&mut bytes.as_slice())?;
+			debug!("Server selected compression {:?}", self.compression);
+			return Ok(Some(header.opcode))
+		}
+		let bytes = self.compression.decompress(bytes)?;
 		match header.opcode {
 			Opcode::ShieldingKey => self.seal_handler.seal_shielding_key(&bytes)?,
 			Opcode::StateKey => self.seal_handler.seal_state_key(&bytes)?,
 			Opcode::State => self.seal_handler.seal_state(&bytes, &self.shard)?,
 			Opcode::LightClient => self.seal_handler.seal_light_client_state(&bytes)?,
+			Opcode::StateManifest =>
+				self.state_manifest = Some(StateManifest::decode(&mut bytes.as_slice())?),
+			Opcode::StateChunk => {
+				let chunk = StateChunk::decode(&mut bytes.as_slice())?;
+				let manifest = self.state_manifest.as_ref().ok_or_else(|| {
+					EnclaveError::Other("received state chunk before the manifest".into())
+				})?;
+				self.chunked_state.apply(chunk, manifest)?;
+			},
+			Opcode::UpgradeSchedule => {
+				let upgrade = ScheduledUpgrade::decode(&mut bytes.as_slice())?;
+				if upgrade.shard != self.shard {
+					return Err(EnclaveError::Other(
+						"received enclave upgrade schedule of another shard".into(),
+					))
+				}
+				GLOBAL_UPGRADE_COORDINATOR
+					.schedule(upgrade)
+					.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))?
+			},
+			Opcode::KnownStateChunks | Opcode::CompressionOffer | Opcode::Compression =>
+				return Err(EnclaveError::Other("unexpected opcode from server".into())),
 		};
 		Ok(Some(header.opcode))
 	}
 
+	/// Seal the state assembled from the received chunks, once it matches the manifest.
+	fn seal_synced_state(&mut self) -> EnclaveResult<()> {
+		let is_complete = match self.state_manifest.as_ref() {
+			Some(manifest) => self.chunked_state.matches(manifest),
+			None => return Ok(()),
+		};
+		if !is_complete {
+			self.seal_partially_synced_state();
+			return Err(EnclaveError::Other(
+				"state provisioning ended before all chunks were received".into(),
+			))
+		}
+		if let Some(manifest) = self.state_manifest.take() {
+			info!("Received state with root {:?}", manifest.root());
+		}
+		self.seal_handler.seal_state(&self.take_chunked_state().encode(), &self.shard)?;
+		self.seal_handler.discard_staged_state(&self.shard)
+	}
+
+	/// Stage the chunks received so far, so a retried provisioning only needs the missing ones.
+	/// The state of the shard stays untouched until the provisioning is complete.
+	fn seal_partially_synced_state(&mut self) {
+		if self.state_manifest.take().is_none() {
+			return
+		}
+		if let Err(e) = self
+			.seal_handler
+			.seal_staged_state(&self.take_chunked_state().encode(), &self.shard)
+		{
+			error!("Failed to stage partially provisioned state: {:?}", e);
+		}
+	}
+
+	fn take_chunked_state(&mut self) -> StfStateType {
+		std::mem::replace(&mut self.chunked_state, ChunkedState::new(Default::default()))
+			.into_state()
+	}
+
 	/// Reads the payload header, indicating the sent payload length and type.
 	fn read_header(&mut self, start_byte: u8) -> EnclaveResult<TcpHeader> {
 		debug!("Read first byte: {:?}", start_byte);
 		// The first sent byte indicates the payload type.
-		let opcode: Opcode = start_byte.into();
+		let opcode = Opcode::try_from(start_byte)?;
 		debug!("Read header opcode: {:?}", opcode);
 		// The following bytes contain the payload length, which is a u64.
 		let mut payload_length_buffer = [0u8; std::mem::size_of::<u64>()];
@@ -209,11 +342,20 @@ pub unsafe extern "C" fn request_state_provisioning(
 		},
 	};
 
+	let staging_dir = match get_base_path() {
+		Ok(path) => path.join(STAGED_STATES_PATH),
+		Err(e) => {
+			error!("{:?}", e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+
 	let seal_handler = EnclaveSealHandler::new(
 		state_handler,
 		state_key_repository,
 		shielding_key_repository,
 		light_client_seal,
+		staging_dir,
 	);
 
 	let signing_key_repository = match GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get() {
@@ -254,7 +396,9 @@ pub unsafe extern "C" fn request_state_provisioning(
 /// Internal [`request_state_provisioning`] function to be able to use the handy `?` operator.
 // allowing clippy rant because this fn will be refactored with MU RA deprecation
 #[allow(clippy::too_many_arguments)]
-pub(crate) fn request_state_provisioning_internal<StateAndKeySealer: SealStateAndKeys>(
+pub(crate) fn request_state_provisioning_internal<
+	StateAndKeySealer: SealStateAndKeys + UnsealStateAndKeys,
+>(
 	socket_fd: c_int,
 	sign_type: sgx_quote_sign_type_t,
 	quoting_enclave_target_info: Option<&sgx_target_info_t>,
@@ -271,6 +415,7 @@ pub(crate) fn request_state_provisioning_internal<StateAndKeySealer: SealStateAn
 		quote_size,
 		OcallApi,
 		skip_ra == 1,
+		shard,
 	)?;
 	debug!("Client config retrieved");
 	let (mut client_session, mut tcp_stream) = tls_client_session_stream(socket_fd, client_config)?;
@@ -292,6 +437,7 @@ fn tls_client_config<A: EnclaveAttestationOCallApi + 'static>(
 	quote_size: Option<&u32>,
 	ocall_api: A,
 	skip_ra: bool,
+	shard: ShardIdentifier,
 ) -> EnclaveResult<ClientConfig> {
 	#[cfg(not(feature = "dcap"))]
 	let attestation_type = RemoteAttestationType::Epid;
@@ -315,7 +461,7 @@ fn tls_client_config<A: EnclaveAttestationOCallApi + 'static>(
 	cfg.set_single_client_cert(certs, privkey).unwrap();
 	// ServerAuth will perform MU RA as part of authentication process
 	cfg.dangerous()
-		.set_certificate_verifier(Arc::new(ServerAuth::new(true, skip_ra, ocall_api)));
+		.set_certificate_verifier(Arc::new(ServerAuth::new(true, skip_ra, shard, ocall_api)));
 	cfg.versions.clear();
 	cfg.versions.push(rustls::ProtocolVersion::TLSv1_2);
 	Ok(cfg)
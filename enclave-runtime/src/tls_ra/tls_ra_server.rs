@@ -17,10 +17,16 @@
 
 //! Implementation of the server part of the state provisioning.
 
-use super::{authentication::ClientAuth, ClientProvisioningRequest, Opcode, TcpHeader};
+use super::{
+	authentication::ClientAuth,
+	compression::Compression,
+	state_chunks::{ChunkedState, NUMBER_OF_CHUNKS},
+	ClientProvisioningRequest, Opcode, TcpHeader,
+};
 use crate::{
 	attestation::create_ra_report_and_signature,
 	error::{Error as EnclaveError, Result as EnclaveResult},
+	get_base_path,
 	initialization::global_components::{
 		EnclaveSealHandler, GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL,
 		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
@@ -30,20 +36,26 @@ use crate::{
 	tls_ra::seal_handler::UnsealStateAndKeys,
 	GLOBAL_STATE_HANDLER_COMPONENT,
 };
-use codec::Decode;
-use itp_attestation_handler::RemoteAttestationType;
+use codec::{Decode, Encode};
+use ita_stf::StateType as StfStateType;
+use itp_attestation_handler::{cert, RemoteAttestationType};
 use itp_component_container::ComponentGetter;
+use itp_enclave_upgrade::GLOBAL_UPGRADE_COORDINATOR;
 use itp_ocall_api::EnclaveAttestationOCallApi;
-use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
-use itp_types::ShardIdentifier;
+use itp_settings::{
+	files::STAGED_STATES_PATH,
+	worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider},
+};
+use itp_types::{ShardIdentifier, H256};
 use log::*;
-use rustls::{ServerConfig, ServerSession, StreamOwned};
+use rustls::{ServerConfig, ServerSession, Session, StreamOwned};
 use sgx_types::*;
 use std::{
 	backtrace::{self, PrintFormat},
 	io::{Read, Write},
 	net::TcpStream,
 	sync::Arc,
+	vec::Vec,
 };
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -69,6 +81,8 @@ struct TlsServer<StateAndKeyUnsealer> {
 	tls_stream: StreamOwned<ServerSession, TcpStream>,
 	seal_handler: StateAndKeyUnsealer,
 	provisioning_payload: ProvisioningPayload,
+	/// Compression of the payloads, once it has been negotiated with the client.
+	compression: Compression,
 }
 
 impl<StateAndKeyUnsealer> TlsServer<StateAndKeyUnsealer>
@@ -80,7 +94,7 @@ where
 		seal_handler: StateAndKeyUnsealer,
 		provisioning_payload: ProvisioningPayload,
 	) -> Self {
-		Self { tls_stream, seal_handler, provisioning_payload }
+		Self { tls_stream, seal_handler, provisioning_payload, compression: Compression::None }
 	}
 
 	/// Sends all relevant data of the specific shard to the client.
@@ -90,8 +104,11 @@ where
 		);
 		let request = self.await_shard_request_from_client()?;
 		println!("    [Enclave] (MU-RA-Server) handle_shard_request_from_client, await_shard_request_from_client() OK");
+		self.ensure_client_may_receive(&request.shard)?;
+		let known_chunk_hashes = self.await_known_state_chunks_from_client()?;
+		self.negotiate_compression()?;
 		println!("    [Enclave] (MU-RA-Server) handle_shard_request_from_client, write_all()");
-		self.write_provisioning_payloads(&request.shard)?;
+		self.write_provisioning_payloads(&request.shard, &known_chunk_hashes)?;
 
 		info!(
 			"will make client account 0x{} a proxy of vault for shard {:?}",
@@ -118,14 +135,88 @@ where
 			.map_err(|_| EnclaveError::Other("matching byte size can't fail to decode".into()))
 	}
 
+	/// An enclave with the announced upgrade MRENCLAVE is only provisioned with the shard the
+	/// upgrade has been announced for.
+	fn ensure_client_may_receive(&self, shard: &ShardIdentifier) -> EnclaveResult<()> {
+		let upgrade = match GLOBAL_UPGRADE_COORDINATOR
+			.scheduled()
+			.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))?
+		{
+			Some(upgrade) if upgrade.shard != *shard => upgrade,
+			_ => return Ok(()),
+		};
+		// Certificates without an EPID attestation report are rejected while an upgrade of
+		// another shard is scheduled, as the client might run the upgrade.
+		let client_cert = self
+			.tls_stream
+			.sess
+			.get_peer_certificates()
+			.and_then(|certs| certs.into_iter().next())
+			.ok_or_else(|| EnclaveError::Other("client presented no certificate".into()))?;
+		let client_mrenclave =
+			cert::mrenclave_of_mra_cert(&client_cert.0, true).map_err(EnclaveError::Sgx)?;
+		if client_mrenclave == upgrade.mrenclave {
+			return Err(EnclaveError::Other(
+				format!("upgrade enclave requested shard {:?}, not {:?}", shard, upgrade.shard)
+					.into(),
+			))
+		}
+		Ok(())
+	}
+
+	/// Read the hashes of the state chunks the client already has.
+	fn await_known_state_chunks_from_client(&mut self) -> EnclaveResult<Vec<H256>> {
+		// The compact length prefix takes at most 5 bytes.
+		let max_length = NUMBER_OF_CHUNKS * std::mem::size_of::<H256>() + 5;
+		let payload = self.read_client_message(Opcode::KnownStateChunks, max_length)?;
+		Ok(Vec::<H256>::decode(&mut payload.as_slice())?)
+	}
+
+	/// Select a compression out of the client's offer and announce it to the client.
+	fn negotiate_compression(&mut self) -> EnclaveResult<()> {
+		let payload = self.read_client_message(Opcode::CompressionOffer, 64)?;
+		let offered = Compression::decode_offer(&payload)?;
+		let compression = Compression::negotiate(&offered);
+		debug!("Client offered compressions {:?}, selected {:?}", offered, compression);
+		self.write(Opcode::Compression, &compression.encode())?;
+		self.compression = compression;
+		Ok(())
+	}
+
+	/// Read a message of the expected type, limiting its length before allocating.
+	fn read_client_message(
+		&mut self,
+		expected: Opcode,
+		max_length: usize,
+	) -> EnclaveResult<Vec<u8>> {
+		let mut opcode = [0u8; 1];
+		self.tls_stream.read_exact(&mut opcode)?;
+		let mut payload_length = [0u8; std::mem::size_of::<u64>()];
+		self.tls_stream.read_exact(&mut payload_length)?;
+		let payload_length = u64::from_be_bytes(payload_length) as usize;
+
+		if Opcode::try_from(opcode[0])? != expected || payload_length > max_length {
+			return Err(EnclaveError::Other(
+				format!("invalid {:?} message from client", expected).into(),
+			))
+		}
+		let mut payload = vec![0u8; payload_length];
+		self.tls_stream.read_exact(&mut payload)?;
+		Ok(payload)
+	}
+
 	/// Sends all relevant data to the client.
-	fn write_provisioning_payloads(&mut self, shard: &ShardIdentifier) -> EnclaveResult<()> {
+	fn write_provisioning_payloads(
+		&mut self,
+		shard: &ShardIdentifier,
+		known_chunk_hashes: &[H256],
+	) -> EnclaveResult<()> {
 		debug!("Provisioning is set to: {:?}", self.provisioning_payload);
 		match self.provisioning_payload {
 			ProvisioningPayload::Everything => {
 				self.write_shielding_key()?;
 				self.write_state_key()?;
-				self.write_state(shard)?;
+				self.write_state(shard, known_chunk_hashes)?;
 				self.write_light_client_state()?;
 			},
 			ProvisioningPayload::ShieldingKeyAndLightClient => {
@@ -133,6 +224,7 @@ where
 				self.write_light_client_state()?;
 			},
 		}
+		self.write_upgrade_schedule(shard)?;
 
 		debug!("Successfully provisioned all payloads to peer");
 		Ok(())
@@ -144,15 +236,39 @@ where
 		Ok(())
 	}
 
+	fn write_upgrade_schedule(&mut self, shard: &ShardIdentifier) -> EnclaveResult<()> {
+		let maybe_upgrade = GLOBAL_UPGRADE_COORDINATOR
+			.scheduled_for(shard)
+			.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))?;
+		if let Some(upgrade) = maybe_upgrade {
+			self.write(Opcode::UpgradeSchedule, &upgrade.encode())?;
+		}
+		Ok(())
+	}
+
 	fn write_state_key(&mut self) -> EnclaveResult<()> {
 		let state_key = self.seal_handler.unseal_state_key()?;
 		self.write(Opcode::StateKey, &state_key)?;
 		Ok(())
 	}
 
-	fn write_state(&mut self, shard: &ShardIdentifier) -> EnclaveResult<()> {
-		let state = self.seal_handler.unseal_state(shard)?;
-		self.write(Opcode::State, &state)?;
+	/// Sends the state manifest followed by all chunks that differ from the client's state.
+	fn write_state(
+		&mut self,
+		shard: &ShardIdentifier,
+		known_chunk_hashes: &[H256],
+	) -> EnclaveResult<()> {
+		let state = StfStateType::decode(&mut self.seal_handler.unseal_state(shard)?.as_slice())?;
+		let chunked_state = ChunkedState::new(state);
+		let manifest = chunked_state.manifest();
+		debug!("Provisioning state of shard {:?} with root {:?}", shard, manifest.root());
+		self.write(Opcode::StateManifest, &manifest.encode())?;
+
+		let chunks = chunked_state.diff(known_chunk_hashes);
+		info!("Sending {} of {} state chunks to client", chunks.len(), NUMBER_OF_CHUNKS);
+		for chunk in chunks {
+			self.write(Opcode::StateChunk, &chunk.encode())?;
+		}
 		Ok(())
 	}
 
@@ -162,12 +278,18 @@ where
 		Ok(())
 	}
 
-	/// Sends the header followed by the payload.
+	/// Sends the header followed by the payload, compressed with the negotiated compression.
 	fn write(&mut self, opcode: Opcode, bytes: &[u8]) -> EnclaveResult<()> {
-		let payload_length = bytes.len() as u64;
+		let payload = self.compression.compress(bytes);
+		let payload_length = payload.len() as u64;
 		self.write_header(TcpHeader::new(opcode, payload_length))?;
-		debug!("Write payload - opcode: {:?}, payload_length: {}", opcode, payload_length);
-		self.tls_stream.write_all(bytes)?;
+		debug!(
+			"Write payload - opcode: {:?}, payload_length: {}, uncompressed: {}",
+			opcode,
+			payload_length,
+			bytes.len()
+		);
+		self.tls_stream.write_all(&payload)?;
 		Ok(())
 	}
 
@@ -225,11 +347,20 @@ pub unsafe extern "C" fn run_state_provisioning_server(
 		},
 	};
 
+	let staging_dir = match get_base_path() {
+		Ok(path) => path.join(STAGED_STATES_PATH),
+		Err(e) => {
+			error!("{:?}", e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+
 	let seal_handler = EnclaveSealHandler::new(
 		state_handler,
 		state_key_repository,
 		shielding_key_repository,
 		light_client_seal,
+		staging_dir,
 	);
 
 	if let Err(e) = run_state_provisioning_server_internal::<_, WorkerModeProvider>(
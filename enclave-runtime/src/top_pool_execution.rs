@@ -16,18 +16,27 @@
 */
 
 use crate::{
+	account_watch::notify_incoming_transfers,
 	error::{Error, Result},
 	initialization::global_components::{
-		GLOBAL_OCALL_API_COMPONENT, GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT,
-		GLOBAL_SIDECHAIN_IMPORT_QUEUE_WORKER_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
-		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
+		GLOBAL_GETTER_ADMISSION_COMPONENT, GLOBAL_LOAD_SHEDDER_COMPONENT,
+		GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT, GLOBAL_SIDECHAIN_IMPORT_QUEUE_WORKER_COMPONENT,
+		GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
+		GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
 	},
+	load_shedding::end_load_shedding_slot,
+	rpc::rpc_resource_sweep::sweep_idle_rpc_resources,
+	shard_isolation::process_unless_isolated,
 	shard_vault::get_shard_vault_internal,
+	sla_metrics::end_sla_metrics_slot,
+	stale_shard::catch_up_if_stale,
 	sync::{EnclaveLock, EnclaveStateRWLock},
 	utils::{
 		get_extrinsic_factory_from_integritee_solo_or_parachain,
 		get_extrinsic_factory_from_target_a_solo_or_parachain,
 		get_extrinsic_factory_from_target_b_solo_or_parachain,
+		get_node_metadata_repository_from_integritee_solo_or_parachain,
 		get_stf_executor_from_integritee_solo_or_parachain,
 		get_stf_executor_from_target_a_solo_or_parachain,
 		get_stf_executor_from_target_b_solo_or_parachain,
@@ -48,8 +57,9 @@ use itc_parentchain::{
 	},
 };
 use itp_component_container::ComponentGetter;
+use itp_enclave_upgrade::GLOBAL_UPGRADE_COORDINATOR;
 use itp_extrinsics_factory::CreateExtrinsics;
-use itp_ocall_api::{EnclaveOnChainOCallApi, EnclaveSidechainOCallApi};
+use itp_ocall_api::{EnclaveAttestationOCallApi, EnclaveOnChainOCallApi, EnclaveSidechainOCallApi};
 use itp_pallet_storage::{SidechainPalletStorage, SidechainPalletStorageKeys};
 use itp_settings::sidechain::SLOT_DURATION;
 use itp_sgx_crypto::key_repository::AccessKey;
@@ -57,7 +67,7 @@ use itp_stf_state_handler::query_shard_state::QueryShardState;
 use itp_time_utils::duration_now;
 use itp_types::{
 	parentchain::{ParentchainCall, ParentchainId, SidechainBlockConfirmation},
-	Block, OpaqueCall, H256,
+	Block, OpaqueCall, ShardIdentifier, H256,
 };
 use its_primitives::{
 	traits::{
@@ -67,7 +77,9 @@ use its_primitives::{
 };
 use its_sidechain::{
 	aura::{proposer_factory::ProposerFactory, Aura, SlotClaimStrategy},
-	consensus_common::{Environment, Error as ConsensusError, ProcessBlockImportQueue},
+	consensus_common::{
+		Environment, Error as ConsensusError, ProcessBlockImportQueue, SlotOutcome,
+	},
 	slots::{yield_next_slot, LastSlot, PerShardSlotWorkerScheduler, SlotInfo},
 	validateer_fetch::ValidateerFetch,
 };
@@ -77,7 +89,11 @@ use sp_core::{crypto::UncheckedFrom, Pair};
 use sp_runtime::{
 	generic::SignedBlock as SignedParentchainBlock, traits::Block as BlockTrait, MultiSignature,
 };
-use std::{sync::Arc, time::Instant, vec::Vec};
+use std::{
+	sync::{Arc, SgxRwLockWriteGuard},
+	time::{Duration, Instant},
+	vec::Vec,
+};
 
 #[no_mangle]
 pub unsafe extern "C" fn execute_trusted_calls() -> sgx_status_t {
@@ -102,10 +118,49 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 
 	// We acquire lock explicitly (variable binding), since '_' will drop the lock after the statement.
 	// See https://medium.com/codechain/rust-underscore-does-not-bind-fec6a18115a8
-	let _enclave_write_lock = EnclaveLock::write_all()?;
+	let mut enclave_write_lock = Some(EnclaveLock::write_all()?);
 
 	let slot_beginning_timestamp = duration_now();
 
+	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+	let shards = state_handler.list_shards()?;
+	let shard = *shards.get(0).ok_or(Error::NoShardAssigned)?;
+	if shards.len() > 1 {
+		return Err(Error::TooManyShardsAssigned)
+	};
+	sweep_idle_rpc_resources();
+
+	// The outcome is recorded on every exit path, a slot that doesn't get to author counts as
+	// missed.
+	let mut slot_outcome = Some(SlotOutcome { missed: true, ..Default::default() });
+	// The lock is only borrowed, so a panic on the shard doesn't poison it.
+	let result = process_unless_isolated(&shard, || {
+		execute_trusted_calls_on_shard(
+			start_time,
+			slot_beginning_timestamp,
+			shard,
+			shards,
+			&mut enclave_write_lock,
+			&mut slot_outcome,
+		)
+	});
+	if let Some(outcome) = slot_outcome {
+		if let Err(e) = end_sla_metrics_slot(&shard, outcome) {
+			warn!("Failed to record the slot in the SLA metrics: {:?}", e);
+		}
+	}
+	result?;
+	Ok(())
+}
+
+fn execute_trusted_calls_on_shard(
+	start_time: Instant,
+	slot_beginning_timestamp: Duration,
+	shard: ShardIdentifier,
+	shards: Vec<ShardIdentifier>,
+	enclave_write_lock: &mut Option<SgxRwLockWriteGuard<'static, ()>>,
+	slot_outcome: &mut Option<SlotOutcome>,
+) -> Result<()> {
 	let integritee_parentchain_import_dispatcher =
 		get_triggered_dispatcher_from_integritee_solo_or_parachain()?;
 	let maybe_target_a_parentchain_import_dispatcher =
@@ -145,13 +200,7 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 			let latest_parentchain_header = v.latest_finalized_header()?;
 			Ok(latest_parentchain_header)
 		})?;
-
-	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
-	let shards = state_handler.list_shards()?;
-	let shard = *shards.get(0).ok_or(Error::NoShardAssigned)?;
-	if shards.len() > 1 {
-		return Err(Error::TooManyShardsAssigned)
-	};
+	set_parentchain_block(slot_outcome, current_integritee_parentchain_header.number);
 
 	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
 
@@ -176,13 +225,44 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 
 	let latest_integritee_parentchain_header = sidechain_block_import_queue_worker.process_queue(
 		&current_integritee_parentchain_header,
-		maybe_latest_sidechain_block_confirmation,
+		maybe_latest_sidechain_block_confirmation.as_ref().cloned(),
 	)?;
 
 	trace!(
 		"Elapsed time to process sidechain block import queue: {} ms",
 		start_time.elapsed().as_millis()
 	);
+	notify_account_watchers(&shard);
+
+	// Building on an outdated state would fork the sidechain.
+	let (latest_integritee_parentchain_header, is_stale) = catch_up_if_stale(
+		&shard,
+		latest_integritee_parentchain_header,
+		&maybe_latest_sidechain_block_confirmation,
+		ocall_api.as_ref(),
+	)?;
+	set_parentchain_block(slot_outcome, latest_integritee_parentchain_header.number);
+	if is_stale {
+		warn!("Not authoring for the stale shard {:?}", shard);
+		return Ok(())
+	}
+
+	// Authoring switches from the old to the new enclave at the activation block of an upgrade.
+	let own_mrenclave = ocall_api.get_mrenclave_of_self()?.m;
+	if !GLOBAL_UPGRADE_COORDINATOR
+		.may_author(&own_mrenclave, latest_integritee_parentchain_header.number)
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?
+	{
+		debug!(
+			"Not authoring on parentchain block {} due to the scheduled enclave upgrade",
+			latest_integritee_parentchain_header.number
+		);
+		// The other enclave of the upgrade authors in this slot.
+		if let Some(outcome) = slot_outcome.as_mut() {
+			outcome.missed = false;
+		}
+		return Ok(())
+	}
 
 	let (_, vault_target) = get_shard_vault_internal(shard)?;
 	trace!("using StfExecutor from {:?} parentchain", vault_target);
@@ -209,15 +289,22 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 		Some(slot) => {
 			if slot.duration_remaining().is_none() {
 				warn!("No time remaining in slot, skipping AURA execution");
-				return Ok(())
+				return end_load_shedding_slot(true, ocall_api.as_ref())
 			}
 
 			log_remaining_slot_duration(&slot, "Before AURA");
 
-			let env = ProposerFactory::<Block, _, _, _>::new(
+			// Getters are deferred until the block is produced, at the latest until the slot ends.
+			let getter_admission = GLOBAL_GETTER_ADMISSION_COMPONENT.get()?;
+			getter_admission.begin_authoring(slot.ends_at);
+
+			let env = ProposerFactory::<Block, _, _, _, _>::new(
 				top_pool_author,
 				stf_executor,
 				block_composer,
+				get_node_metadata_repository_from_integritee_solo_or_parachain()?,
+				GLOBAL_MAINTENANCE_SCHEDULE_COMPONENT.get()?,
+				GLOBAL_LOAD_SHEDDER_COMPONENT.get()?,
 			);
 
 			let (blocks, parentchain_calls) =
@@ -233,11 +320,19 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 				)?;
 
 			debug!("Aura executed successfully");
+			notify_account_watchers(&shard);
 
 			// Drop lock as soon as we don't need it anymore.
-			drop(_enclave_write_lock);
+			drop(enclave_write_lock.take());
+			getter_admission.end_authoring();
 
 			log_remaining_slot_duration(&slot, "After AURA");
+			let deadline_missed = slot.duration_remaining().is_none();
+			if let Some(outcome) = slot_outcome.as_mut() {
+				outcome.blocks_authored = blocks.len() as u64;
+				outcome.missed = deadline_missed;
+			}
+			end_load_shedding_slot(deadline_missed, ocall_api.as_ref())?;
 
 			send_blocks_and_extrinsics::<Block, _, _>(blocks, parentchain_calls, ocall_api)?;
 
@@ -245,6 +340,8 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 		},
 		None => {
 			debug!("No slot yielded. Skipping block production.");
+			// Not a new slot, so there is nothing to record.
+			*slot_outcome = None;
 			return Ok(())
 		},
 	};
@@ -253,6 +350,19 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 	Ok(())
 }
 
+fn set_parentchain_block(slot_outcome: &mut Option<SlotOutcome>, block: u32) {
+	if let Some(outcome) = slot_outcome.as_mut() {
+		outcome.parentchain_block = Some(block.into());
+	}
+}
+
+/// Failing notifications must not stop the block production.
+fn notify_account_watchers(shard: &H256) {
+	if let Err(e) = notify_incoming_transfers(shard) {
+		warn!("Failed to notify the account watchers of shard {:?}: {:?}", shard, e);
+	}
+}
+
 /// Executes aura for the given `slot`.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn exec_aura_on_slot<
@@ -0,0 +1,206 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Monitoring of the balance of the enclave account on the Integritee parentchain.
+//!
+//! The free balance is sampled periodically. The fees spent are the decreases of the balance
+//! between the samples of the last hour, top-ups are not counted. The runway is how long the free
+//! balance lasts at that rate. While the runway is below the configured minimum, non-critical
+//! extrinsics are paused, so the remaining funds are left for the block confirmations.
+
+use crate::account_funding::EnclaveAccountInfo;
+use itp_types::parentchain::Balance;
+use lazy_static::lazy_static;
+use log::*;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use serde::Serialize;
+use std::{
+	collections::VecDeque,
+	sync::Arc,
+	thread,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+lazy_static! {
+	static ref FEE_SPEND_PER_HOUR: IntGauge = register_int_gauge!(
+		"integritee_worker_enclave_account_fee_spend_per_hour",
+		"Fees spent by the enclave account per hour, over the balance samples of the last hour"
+	)
+	.unwrap();
+	static ref FUNDS_RUNWAY: IntGauge = register_int_gauge!(
+		"integritee_worker_enclave_account_runway_seconds",
+		"Seconds the free balance of the enclave account lasts at the recent fee spend, -1 if no fees have been spent"
+	)
+	.unwrap();
+	static ref PAUSED_EXTRINSICS: IntCounter = register_int_counter!(
+		"integritee_worker_non_critical_extrinsics_paused",
+		"Number of non-critical extrinsics not sent because the enclave account runs low on funds"
+	)
+	.unwrap();
+}
+
+const BALANCE_SAMPLING_INTERVAL: Duration = Duration::from_secs(60);
+const BALANCE_SAMPLING_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BalanceStatus {
+	pub free_balance: Balance,
+	pub fee_spend_per_hour: Balance,
+	/// Not set as long as no fees have been spent.
+	pub runway_secs: Option<u64>,
+	pub non_critical_extrinsics_paused: bool,
+}
+
+pub struct BalanceMonitor {
+	/// Non-critical extrinsics are paused below this runway, never if not set.
+	min_runway: Option<Duration>,
+	/// Free balance samples of the last hour, with the time since the unix epoch.
+	samples: Mutex<VecDeque<(Duration, Balance)>>,
+}
+
+impl BalanceMonitor {
+	pub fn new(min_runway: Option<Duration>) -> Self {
+		BalanceMonitor { min_runway, samples: Default::default() }
+	}
+
+	pub fn record(&self, now: Duration, free_balance: Balance) {
+		let mut samples = self.samples.lock();
+		samples.push_back((now, free_balance));
+		while samples.front().map_or(false, |(sampled_at, _)| {
+			now.saturating_sub(*sampled_at) > BALANCE_SAMPLING_WINDOW
+		}) {
+			samples.pop_front();
+		}
+		drop(samples);
+
+		if let Some(status) = self.status() {
+			FEE_SPEND_PER_HOUR.set(status.fee_spend_per_hour.try_into().unwrap_or(i64::MAX));
+			FUNDS_RUNWAY.set(status.runway_secs.map_or(-1, |r| r.try_into().unwrap_or(i64::MAX)));
+			if status.non_critical_extrinsics_paused {
+				warn!(
+					"Enclave account runs low on funds: {} left, {} spent per hour",
+					status.free_balance, status.fee_spend_per_hour
+				);
+			}
+		}
+	}
+
+	/// Status as of the last sample, none before the first sample.
+	pub fn status(&self) -> Option<BalanceStatus> {
+		let samples = self.samples.lock();
+		let (last_sampled_at, free_balance) = *samples.back()?;
+		let spent: Balance = samples
+			.iter()
+			.zip(samples.iter().skip(1))
+			.map(|((_, previous), (_, next))| previous.saturating_sub(*next))
+			.sum();
+		let elapsed_secs = last_sampled_at.saturating_sub(samples.front()?.0).as_secs();
+		let fee_spend_per_hour = if elapsed_secs == 0 {
+			0
+		} else {
+			spent.saturating_mul(3600) / Balance::from(elapsed_secs)
+		};
+		let runway_secs = (fee_spend_per_hour > 0).then(|| {
+			(free_balance.saturating_mul(3600) / fee_spend_per_hour)
+				.try_into()
+				.unwrap_or(u64::MAX)
+		});
+		let non_critical_extrinsics_paused = match (self.min_runway, runway_secs) {
+			(Some(min_runway), Some(runway_secs)) => runway_secs < min_runway.as_secs(),
+			_ => false,
+		};
+		Some(BalanceStatus {
+			free_balance,
+			fee_spend_per_hour,
+			runway_secs,
+			non_critical_extrinsics_paused,
+		})
+	}
+
+	/// Counts the extrinsic as paused if it is.
+	pub fn pauses_non_critical_extrinsic(&self) -> bool {
+		let paused = self.status().map_or(false, |status| status.non_critical_extrinsics_paused);
+		if paused {
+			PAUSED_EXTRINSICS.inc();
+		}
+		paused
+	}
+}
+
+pub fn start_balance_monitoring_thread<AccountInfo>(
+	monitor: Arc<BalanceMonitor>,
+	account_info: AccountInfo,
+) where
+	AccountInfo: EnclaveAccountInfo + Send + 'static,
+{
+	thread::Builder::new()
+		.name("balance_monitoring_thread".to_owned())
+		.spawn(move || loop {
+			match account_info.free_balance() {
+				Ok(free_balance) => monitor.record(
+					SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+					free_balance,
+				),
+				Err(e) => warn!("Could not fetch the free balance of the enclave account: {:?}", e),
+			}
+			thread::sleep(BALANCE_SAMPLING_INTERVAL);
+		})
+		.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at_minute(minute: u64) -> Duration {
+		Duration::from_secs(minute * 60)
+	}
+
+	#[test]
+	fn runway_follows_the_fees_spent_in_the_last_hour_without_top_ups() {
+		let monitor = BalanceMonitor::new(Some(Duration::from_secs(10 * 3600)));
+		assert!(monitor.status().is_none());
+
+		monitor.record(at_minute(0), 2_000);
+		monitor.record(at_minute(30), 1_900);
+		monitor.record(at_minute(40), 5_000);
+		monitor.record(at_minute(60), 4_900);
+
+		let status = monitor.status().unwrap();
+		assert_eq!(status.fee_spend_per_hour, 200);
+		assert_eq!(status.runway_secs, Some(4_900 * 3600 / 200));
+		assert!(!status.non_critical_extrinsics_paused);
+
+		monitor.record(at_minute(90), 1_400);
+
+		let status = monitor.status().unwrap();
+		assert_eq!(status.fee_spend_per_hour, 100 + 3_500);
+		assert!(status.non_critical_extrinsics_paused);
+		assert!(monitor.pauses_non_critical_extrinsic());
+	}
+
+	#[test]
+	fn nothing_is_paused_without_fees_spent() {
+		let monitor = BalanceMonitor::new(Some(Duration::from_secs(3600)));
+		monitor.record(at_minute(0), 10);
+		monitor.record(at_minute(1), 10);
+
+		assert_eq!(monitor.status().unwrap().runway_secs, None);
+		assert!(!monitor.pauses_non_critical_extrinsic());
+	}
+}
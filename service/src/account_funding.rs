@@ -15,13 +15,16 @@
 
 */
 
-use crate::error::{Error, ServiceResult};
-use codec::Encode;
+use crate::{
+	error::{Error, ServiceResult},
+	external_signer::{compose_signed_extrinsic, RemoteSigner, SignPayload},
+};
+use codec::{Compact, Encode};
 use itp_node_api::api_client::{AccountApi, ParentchainApi, TEEREX};
 use itp_settings::worker::REGISTERING_FEE_FACTOR_FOR_INIT_FUNDS;
 use itp_types::{
 	parentchain::{AccountId, Balance, ParentchainId},
-	Moment,
+	Moment, OpaqueCall,
 };
 use log::*;
 use sp_core::{
@@ -30,9 +33,11 @@ use sp_core::{
 };
 use sp_keyring::AccountKeyring;
 use sp_runtime::{MultiAddress, Saturating};
-use std::{thread, time::Duration};
+use std::{sync::Arc, thread, time::Duration};
 use substrate_api_client::{
-	ac_compose_macros::compose_extrinsic, ac_primitives::Bytes, extrinsic::BalancesExtrinsics,
+	ac_compose_macros::{compose_call, compose_extrinsic},
+	ac_primitives::Bytes,
+	extrinsic::BalancesExtrinsics,
 	GetBalance, GetStorage, GetTransactionPayment, SubmitAndWatch, XtStatus,
 };
 use teerex_primitives::SgxAttestationMethod;
@@ -61,14 +66,36 @@ impl EnclaveAccountInfoProvider {
 	}
 }
 
+/// Who pays for missing funds of the enclave account.
+#[derive(Clone)]
+pub enum FundingSource {
+	/// Alice pays, only for dev chains.
+	Alice,
+	/// The operator's funding account pays, signed by an external signer.
+	ExternalSigner(Arc<RemoteSigner>),
+	/// Wait for a manual transfer.
+	Manual,
+}
+
+impl FundingSource {
+	pub fn new(is_development_mode: bool, maybe_funding_signer: Option<RemoteSigner>) -> Self {
+		match (is_development_mode, maybe_funding_signer) {
+			(true, _) => FundingSource::Alice,
+			(false, Some(signer)) => FundingSource::ExternalSigner(Arc::new(signer)),
+			(false, None) => FundingSource::Manual,
+		}
+	}
+}
+
 /// evaluate if the enclave should have more funds and how much more
 /// in --dev mode: let Alice pay for missing funds
+/// with a funding signer: let the operator's funding account pay for missing funds
 /// in production mode: wait for manual transfer before continuing
 pub fn setup_reasonable_account_funding(
 	api: &ParentchainApi,
 	accountid: &AccountId32,
 	parentchain_id: ParentchainId,
-	is_development_mode: bool,
+	funding_source: &FundingSource,
 ) -> ServiceResult<()> {
 	loop {
 		let needed = estimate_funds_needed_to_run_for_a_while(api, accountid, parentchain_id)?;
@@ -79,15 +106,40 @@ pub fn setup_reasonable_account_funding(
 			return Ok(())
 		}
 
-		if is_development_mode {
-			info!("[{:?}] Alice will grant {:?} to {:?}", parentchain_id, missing_funds, accountid);
-			bootstrap_funds_from_alice(api, accountid, missing_funds)?;
-		} else {
-			error!(
-				"[{:?}] Enclave account needs funding. please send at least {:?} to {:?}",
-				parentchain_id, missing_funds, accountid
-			);
-			thread::sleep(Duration::from_secs(10));
+		match funding_source {
+			FundingSource::Alice => {
+				info!(
+					"[{:?}] Alice will grant {:?} to {:?}",
+					parentchain_id, missing_funds, accountid
+				);
+				bootstrap_funds_from_alice(api, accountid, missing_funds)?;
+			},
+			FundingSource::ExternalSigner(signer) => {
+				info!(
+					"[{:?}] Funding account {:?} will grant {:?} to {:?}",
+					parentchain_id,
+					signer.account_id(),
+					missing_funds,
+					accountid
+				);
+				// The signer may be unavailable for a while, e.g. while the HSM awaits approval.
+				if let Err(e) =
+					fund_from_external_signer(api, signer.as_ref(), accountid, missing_funds)
+				{
+					error!(
+						"[{:?}] Funding via the external signer failed: {:?}",
+						parentchain_id, e
+					);
+					thread::sleep(Duration::from_secs(10));
+				}
+			},
+			FundingSource::Manual => {
+				error!(
+					"[{:?}] Enclave account needs funding. please send at least {:?} to {:?}",
+					parentchain_id, missing_funds, accountid
+				);
+				thread::sleep(Duration::from_secs(10));
+			},
 		}
 	}
 }
@@ -196,6 +248,40 @@ fn bootstrap_funds_from_alice(
 	Ok(())
 }
 
+/// The operator's funding account sends some funds to the account, signed by the external signer.
+fn fund_from_external_signer<Signer: SignPayload>(
+	api: &ParentchainApi,
+	signer: &Signer,
+	accountid: &AccountId32,
+	funding_amount: Balance,
+) -> ServiceResult<()> {
+	let funding_free = api.get_free_balance(signer.account_id())?;
+	if funding_amount > funding_free {
+		return Err(Error::Custom(
+			format!("funding account has {} but needs to grant {}", funding_free, funding_amount)
+				.into(),
+		))
+	}
+
+	let call = OpaqueCall::from_tuple(&compose_call!(
+		api.metadata(),
+		"Balances",
+		"transfer_allow_death",
+		MultiAddress::<AccountId, ()>::Id(accountid.clone()),
+		Compact(funding_amount)
+	));
+	let xt = compose_signed_extrinsic(api, signer, call)?;
+
+	println!("[+] send extrinsic: funding Enclave from the funding account");
+	let xt_report =
+		api.submit_and_watch_opaque_extrinsic_until(&xt.encode().into(), XtStatus::InBlock)?;
+	info!(
+		"[<] L1 extrinsic success. extrinsic hash: {:?} / status: {:?}",
+		xt_report.extrinsic_hash, xt_report.status
+	);
+	Ok(())
+}
+
 /// precise estimation of necessary funds to register a hardcoded number of proxies
 pub fn shard_vault_initial_funds(api: &ParentchainApi) -> Result<Balance, Error> {
 	let proxy_deposit_base: Balance = api.get_constant("Proxy", "ProxyDepositBase")?;
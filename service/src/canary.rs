@@ -0,0 +1,214 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Continuous self-test of the worker on a designated canary shard.
+//!
+//! A round of the canary is run in the enclave periodically, see [`itp_types::canary`]. Every
+//! round is counted in the metrics, and the optional webhook is notified when the canary starts
+//! failing and when it recovers, not on every failed round.
+
+use crate::{
+	error::{Error, ServiceResult},
+	retry::{retry, CallClass},
+};
+use base58::ToBase58;
+use itc_rest_client::{
+	http_client::{DefaultSend, HttpClient},
+	rest_client::{RestClient, Url},
+	RestPath, RestPost,
+};
+use itp_enclave_api::enclave_base::EnclaveBase;
+use itp_types::{canary::CanaryReport, ShardIdentifier};
+use lazy_static::lazy_static;
+use log::*;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge};
+use serde::Serialize;
+use std::{sync::Arc, thread, time::Duration};
+
+lazy_static! {
+	static ref CANARY_ROUNDS: IntCounterVec = register_int_counter_vec!(
+		"integritee_worker_canary_rounds",
+		"Number of canary rounds by outcome: passed, failed or error if the round could not be run",
+		&["outcome"]
+	)
+	.unwrap();
+	static ref CANARY_STEP_FAILURES: IntCounterVec = register_int_counter_vec!(
+		"integritee_worker_canary_step_failures",
+		"Number of failed canary rounds by the failed step",
+		&["step"]
+	)
+	.unwrap();
+	static ref CANARY_PASSING: IntGauge = register_int_gauge!(
+		"integritee_worker_canary_passing",
+		"1 if the last canary round passed, 0 otherwise"
+	)
+	.unwrap();
+}
+
+const CANARY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of the `POST` to the webhook.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanaryAlert {
+	/// Base58 encoded canary shard.
+	pub shard: String,
+	pub passing: bool,
+	/// Round of the canary, none if the round could not be run.
+	pub round: Option<u64>,
+	/// Failed step, none if the round could not be run.
+	pub failed_step: Option<String>,
+	pub error: Option<String>,
+}
+
+impl RestPath<()> for CanaryAlert {
+	fn get_path(_: ()) -> Result<String, itc_rest_client::error::Error> {
+		Ok(String::new())
+	}
+}
+
+/// Tracks the outcomes of the canary rounds.
+#[derive(Default)]
+pub struct CanaryMonitor {
+	/// Outcome of the last round, none before the first round.
+	passing: Mutex<Option<bool>>,
+}
+
+impl CanaryMonitor {
+	/// Records the outcome of a round, returns an alert if the canary started failing or
+	/// recovered with it. A first passing round is no news.
+	pub fn record(
+		&self,
+		shard: &ShardIdentifier,
+		outcome: Result<CanaryReport, String>,
+	) -> Option<CanaryAlert> {
+		let mut alert = CanaryAlert {
+			shard: shard.as_bytes().to_base58(),
+			passing: false,
+			round: None,
+			failed_step: None,
+			error: None,
+		};
+		match outcome {
+			Ok(report) if report.passed() => {
+				CANARY_ROUNDS.with_label_values(&["passed"]).inc();
+				alert.passing = true;
+				alert.round = Some(report.round);
+			},
+			Ok(report) => {
+				CANARY_ROUNDS.with_label_values(&["failed"]).inc();
+				alert.round = Some(report.round);
+				if let Some((step, error)) = report.failure() {
+					CANARY_STEP_FAILURES.with_label_values(&[step.name()]).inc();
+					alert.failed_step = Some(step.name().to_string());
+					alert.error = Some(String::from_utf8_lossy(error).into_owned());
+				}
+				warn!("Canary round {} failed: {:?}", report.round, alert.error);
+			},
+			Err(e) => {
+				CANARY_ROUNDS.with_label_values(&["error"]).inc();
+				warn!("Could not run a canary round: {}", e);
+				alert.error = Some(e);
+			},
+		}
+		CANARY_PASSING.set(alert.passing as i64);
+
+		let previously_passing = self.passing.lock().replace(alert.passing);
+		match previously_passing {
+			Some(previously_passing) if previously_passing != alert.passing => Some(alert),
+			None if !alert.passing => Some(alert),
+			_ => None,
+		}
+	}
+}
+
+/// Webhook notified about alerts of the canary.
+pub struct CanaryWebhook {
+	url: Url,
+}
+
+impl CanaryWebhook {
+	pub fn new(url: &str) -> ServiceResult<Self> {
+		let url = Url::parse(url).map_err(|e| Error::Custom(e.into()))?;
+		Ok(Self { url })
+	}
+
+	pub fn notify(&self, alert: &CanaryAlert) -> ServiceResult<()> {
+		retry(CallClass::Webhook, || {
+			let http_client =
+				HttpClient::new(DefaultSend {}, true, Some(WEBHOOK_TIMEOUT), None, None);
+			let mut rest_client = RestClient::new(http_client, self.url.clone());
+			rest_client.post((), alert)
+		})
+		.map_err(|e| Error::Custom(format!("Canary webhook at {} failed: {}", self.url, e).into()))
+	}
+}
+
+pub fn start_canary_thread<E: EnclaveBase>(
+	enclave: Arc<E>,
+	shard: ShardIdentifier,
+	webhook: Option<CanaryWebhook>,
+) {
+	info!("Running the canary on shard {:?} every {:?}", shard, CANARY_INTERVAL);
+	let monitor = CanaryMonitor::default();
+	thread::Builder::new()
+		.name("canary_thread".to_owned())
+		.spawn(move || loop {
+			let outcome = enclave.run_canary_round(&shard).map_err(|e| format!("{:?}", e));
+			if let (Some(alert), Some(webhook)) = (monitor.record(&shard, outcome), &webhook) {
+				if let Err(e) = webhook.notify(&alert) {
+					warn!("Could not notify the canary alert: {:?}", e);
+				}
+			}
+			thread::sleep(CANARY_INTERVAL);
+		})
+		.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_types::canary::CanaryStep;
+
+	fn report(failed_step: Option<CanaryStep>) -> Result<CanaryReport, String> {
+		let outcomes = CanaryStep::ALL
+			.iter()
+			.map(|step| match Some(*step) == failed_step {
+				true => (*step, Err(b"unexpected balance".to_vec())),
+				false => (*step, Ok(())),
+			})
+			.collect();
+		Ok(CanaryReport { round: 7, timestamp: 1_000, outcomes })
+	}
+
+	#[test]
+	fn only_changes_of_the_outcome_raise_alerts() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let monitor = CanaryMonitor::default();
+
+		assert_eq!(monitor.record(&shard, report(None)), None);
+
+		let alert = monitor.record(&shard, report(Some(CanaryStep::Getter))).unwrap();
+		assert!(!alert.passing);
+		assert_eq!(alert.failed_step.as_deref(), Some("getter"));
+		assert_eq!(alert.error.as_deref(), Some("unexpected balance"));
+
+		assert_eq!(monitor.record(&shard, Err("enclave unavailable".into())), None);
+		assert!(monitor.record(&shard, report(None)).unwrap().passing);
+	}
+}
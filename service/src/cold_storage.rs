@@ -0,0 +1,183 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Tiering of the rarely accessed state snapshots to a slower, cheaper storage target.
+//!
+//! All but the newest snapshots of a shard are moved to the cold storage directory and replaced
+//! by a symbolic link, so the enclave keeps reading them transparently when a historical state is
+//! needed. The snapshots are encrypted by the enclave, so the cold storage directory may as well
+//! be a mounted remote file system. Pruned sidechain blocks are moved to the same target by the
+//! sidechain storage.
+
+use crate::error::{Error, ServiceResult};
+use itp_settings::files::SHARDS_PATH;
+use log::*;
+use std::{
+	fs, io,
+	os::unix::fs::symlink,
+	path::{Path, PathBuf},
+	thread,
+	time::Duration,
+};
+
+/// Interval in which the snapshots are tiered.
+const TIERING_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Suffix of the snapshot file names, the state id (a timestamp) is the prefix.
+const SNAPSHOT_FILE_SUFFIX: &str = "_state.bin";
+
+pub struct ColdStorage {
+	data_dir: PathBuf,
+	cold_dir: PathBuf,
+	hot_snapshots: usize,
+}
+
+impl ColdStorage {
+	pub fn new(data_dir: PathBuf, cold_dir: PathBuf, hot_snapshots: usize) -> Self {
+		ColdStorage { data_dir, cold_dir, hot_snapshots }
+	}
+
+	/// Moves the old snapshots of all shards to cold storage and removes the cold snapshots the
+	/// enclave has pruned since. Returns the number of moved snapshots.
+	pub fn tier_snapshots(&self) -> ServiceResult<usize> {
+		let shards_dir = self.data_dir.join(SHARDS_PATH);
+		if !shards_dir.exists() {
+			return Ok(0)
+		}
+		let mut moved = 0;
+		for shard in file_names(&shards_dir).map_err(|e| Error::Custom(e.into()))? {
+			moved += self.tier_shard(&shard).map_err(|e| Error::Custom(e.into()))?;
+		}
+		Ok(moved)
+	}
+
+	fn tier_shard(&self, shard: &str) -> io::Result<usize> {
+		let hot_dir = self.data_dir.join(SHARDS_PATH).join(shard);
+		let cold_dir = self.cold_dir.join(SHARDS_PATH).join(shard);
+		fs::create_dir_all(&cold_dir)?;
+
+		let mut snapshots: Vec<(u128, String)> = file_names(&hot_dir)?
+			.into_iter()
+			.filter_map(|name| Some((state_id(&name)?, name)))
+			.collect();
+		snapshots.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+		let mut moved = 0;
+		for (_, name) in snapshots.iter().skip(self.hot_snapshots) {
+			let hot_path = hot_dir.join(name);
+			if fs::symlink_metadata(&hot_path)?.file_type().is_symlink() {
+				continue
+			}
+			move_to_cold_storage(&hot_path, &cold_dir.join(name))?;
+			moved += 1;
+		}
+
+		// The enclave removes the link of a pruned snapshot, the cold copy is not needed anymore.
+		for name in file_names(&cold_dir)? {
+			if fs::symlink_metadata(hot_dir.join(&name)).is_err() {
+				debug!("Removing pruned snapshot {} of shard {} from cold storage", name, shard);
+				fs::remove_file(cold_dir.join(&name))?;
+			}
+		}
+		Ok(moved)
+	}
+}
+
+/// Periodically tiers the snapshots, see [`ColdStorage::tier_snapshots`].
+pub fn start_cold_storage_tiering_thread(cold_storage: ColdStorage) {
+	thread::Builder::new()
+		.name("cold_storage_tiering".to_owned())
+		.spawn(move || loop {
+			match cold_storage.tier_snapshots() {
+				Ok(0) => {},
+				Ok(moved) => info!("Moved {} state snapshots to cold storage", moved),
+				Err(e) => warn!("Could not move state snapshots to cold storage: {:?}", e),
+			}
+			thread::sleep(TIERING_INTERVAL);
+		})
+		.unwrap();
+}
+
+/// Copies the file to cold storage and atomically replaces it by a link to the copy.
+fn move_to_cold_storage(hot_path: &Path, cold_path: &Path) -> io::Result<()> {
+	let cold_tmp_path = cold_path.with_extension("tmp");
+	fs::copy(hot_path, &cold_tmp_path)?;
+	fs::rename(&cold_tmp_path, cold_path)?;
+
+	let link_tmp_path = hot_path.with_extension("link");
+	let _ = fs::remove_file(&link_tmp_path);
+	symlink(cold_path, &link_tmp_path)?;
+	fs::rename(&link_tmp_path, hot_path)
+}
+
+fn state_id(file_name: &str) -> Option<u128> {
+	file_name.strip_suffix(SNAPSHOT_FILE_SUFFIX)?.parse().ok()
+}
+
+fn file_names(directory: &Path) -> io::Result<Vec<String>> {
+	Ok(fs::read_dir(directory)?
+		.filter_map(|entry| entry.ok().and_then(|e| e.file_name().into_string().ok()))
+		.filter(|name| !name.ends_with(".tmp") && !name.ends_with(".link"))
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(name);
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn old_snapshots_are_moved_to_cold_storage_and_stay_readable() {
+		let data_dir = temp_dir("cold_storage_tiering_data");
+		let cold_dir = temp_dir("cold_storage_tiering_cold");
+		let shard_dir = data_dir.join(SHARDS_PATH).join("shard");
+		fs::create_dir_all(&shard_dir).unwrap();
+		for state_id in 1..=4u8 {
+			fs::write(shard_dir.join(format!("{}_state.bin", state_id)), [state_id]).unwrap();
+		}
+		let cold_storage = ColdStorage::new(data_dir.clone(), cold_dir.clone(), 2);
+
+		assert_eq!(cold_storage.tier_snapshots().unwrap(), 2);
+		assert_eq!(cold_storage.tier_snapshots().unwrap(), 0);
+
+		let cold_shard_dir = cold_dir.join(SHARDS_PATH).join("shard");
+		assert!(fs::symlink_metadata(shard_dir.join("1_state.bin"))
+			.unwrap()
+			.file_type()
+			.is_symlink());
+		assert_eq!(fs::read(shard_dir.join("1_state.bin")).unwrap(), vec![1]);
+		assert!(!fs::symlink_metadata(shard_dir.join("3_state.bin"))
+			.unwrap()
+			.file_type()
+			.is_symlink());
+		assert!(cold_shard_dir.join("2_state.bin").exists());
+
+		// The enclave prunes the oldest snapshot.
+		fs::remove_file(shard_dir.join("1_state.bin")).unwrap();
+		cold_storage.tier_snapshots().unwrap();
+
+		assert!(!cold_shard_dir.join("1_state.bin").exists());
+		fs::remove_dir_all(data_dir).unwrap();
+		fs::remove_dir_all(cold_dir).unwrap();
+	}
+}
@@ -15,10 +15,23 @@
 
 */
 
+use base58::FromBase58;
 use clap::ArgMatches;
 use itc_rest_client::rest_client::Url;
-use itp_settings::teeracle::{DEFAULT_MARKET_DATA_UPDATE_INTERVAL, ONE_DAY, THIRTY_MINUTES};
-use itp_types::parentchain::ParentchainId;
+use itp_settings::{
+	sidechain::{
+		LOAD_SHEDDING_EPC_PRESSURE_PERCENT, LOAD_SHEDDING_ESCALATION_SLOTS,
+		LOAD_SHEDDING_MAX_SUBMISSIONS_PER_SLOT, LOAD_SHEDDING_RECOVERY_SLOTS,
+	},
+	teeracle::{DEFAULT_MARKET_DATA_UPDATE_INTERVAL, ONE_DAY, THIRTY_MINUTES},
+};
+use itp_types::{
+	load_shedding::{LoadSheddingPolicy, SheddingAction},
+	maintenance_window::MaintenanceWindow,
+	panic_policy::PanicPolicy,
+	parentchain::ParentchainId,
+	ShardIdentifier,
+};
 use parse_duration::parse;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -34,6 +47,9 @@ static DEFAULT_UNTRUSTED_PORT: &str = "2001";
 static DEFAULT_MU_RA_PORT: &str = "3443";
 static DEFAULT_METRICS_PORT: &str = "8787";
 static DEFAULT_UNTRUSTED_HTTP_PORT: &str = "4545";
+const DEFAULT_MIN_FREE_DISK_SPACE_MB: u64 = 512;
+/// Number of the newest snapshots of a shard that are kept on the local disk, if tiered.
+pub const DEFAULT_HOT_STATE_SNAPSHOTS: usize = 2;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Config {
@@ -43,6 +59,10 @@ pub struct Config {
 	target_a_parentchain_rpc_port: Option<String>,
 	target_b_parentchain_rpc_url: Option<String>,
 	target_b_parentchain_rpc_port: Option<String>,
+	/// Endpoints (including ws:// and port) to fail over to, if the primary node is not available.
+	integritee_rpc_fallback_endpoints: Vec<String>,
+	target_a_parentchain_rpc_fallback_endpoints: Vec<String>,
+	target_b_parentchain_rpc_fallback_endpoints: Vec<String>,
 	worker_ip: String,
 	/// Trusted worker address that will be advertised on the parentchain.
 	trusted_external_worker_address: Option<String>,
@@ -64,6 +84,10 @@ pub struct Config {
 	untrusted_http_port: String,
 	/// Data directory used by all the services.
 	data_dir: PathBuf,
+	/// Writes to the data directory are refused below this amount of free disk space.
+	min_free_disk_space_mb: u64,
+	/// Slower storage target old state snapshots and pruned sidechain blocks are moved to.
+	cold_storage_dir: Option<PathBuf>,
 	/// Config of the 'run' subcommand
 	run_config: Option<RunConfig>,
 }
@@ -77,6 +101,9 @@ impl Config {
 		target_a_parentchain_rpc_port: Option<String>,
 		target_b_parentchain_rpc_url: Option<String>,
 		target_b_parentchain_rpc_port: Option<String>,
+		integritee_rpc_fallback_endpoints: Vec<String>,
+		target_a_parentchain_rpc_fallback_endpoints: Vec<String>,
+		target_b_parentchain_rpc_fallback_endpoints: Vec<String>,
 		worker_ip: String,
 		trusted_external_worker_address: Option<String>,
 		trusted_worker_port: String,
@@ -88,6 +115,8 @@ impl Config {
 		metrics_server_port: String,
 		untrusted_http_port: String,
 		data_dir: PathBuf,
+		min_free_disk_space_mb: u64,
+		cold_storage_dir: Option<PathBuf>,
 		run_config: Option<RunConfig>,
 	) -> Self {
 		Self {
@@ -97,6 +126,9 @@ impl Config {
 			target_a_parentchain_rpc_port,
 			target_b_parentchain_rpc_url,
 			target_b_parentchain_rpc_port,
+			integritee_rpc_fallback_endpoints,
+			target_a_parentchain_rpc_fallback_endpoints,
+			target_b_parentchain_rpc_fallback_endpoints,
 			worker_ip,
 			trusted_external_worker_address,
 			trusted_worker_port,
@@ -108,6 +140,8 @@ impl Config {
 			metrics_server_port,
 			untrusted_http_port,
 			data_dir,
+			min_free_disk_space_mb,
+			cold_storage_dir,
 			run_config,
 		}
 	}
@@ -147,6 +181,23 @@ impl Config {
 		None
 	}
 
+	/// Integritee RPC endpoint followed by its fallbacks, in order of preference.
+	pub fn integritee_rpc_endpoints(&self) -> Vec<String> {
+		with_fallbacks(self.integritee_rpc_endpoint(), &self.integritee_rpc_fallback_endpoints)
+	}
+
+	pub fn target_a_parentchain_rpc_endpoints(&self) -> Option<Vec<String>> {
+		self.target_a_parentchain_rpc_endpoint().map(|endpoint| {
+			with_fallbacks(endpoint, &self.target_a_parentchain_rpc_fallback_endpoints)
+		})
+	}
+
+	pub fn target_b_parentchain_rpc_endpoints(&self) -> Option<Vec<String>> {
+		self.target_b_parentchain_rpc_endpoint().map(|endpoint| {
+			with_fallbacks(endpoint, &self.target_b_parentchain_rpc_fallback_endpoints)
+		})
+	}
+
 	pub fn trusted_worker_url_internal(&self) -> String {
 		format!("{}:{}", self.worker_ip, self.trusted_worker_port)
 	}
@@ -187,6 +238,14 @@ impl Config {
 		self.data_dir.as_path()
 	}
 
+	pub fn min_free_disk_space_bytes(&self) -> u64 {
+		self.min_free_disk_space_mb.saturating_mul(1024 * 1024)
+	}
+
+	pub fn cold_storage_dir(&self) -> Option<&Path> {
+		self.cold_storage_dir.as_deref()
+	}
+
 	pub fn run_config(&self) -> &Option<RunConfig> {
 		&self.run_config
 	}
@@ -232,6 +291,20 @@ impl From<&ArgMatches<'_>> for Config {
 			},
 		};
 
+		let min_free_disk_space_mb = m
+			.value_of("min-free-disk-space")
+			.map(|s| {
+				s.parse()
+					.unwrap_or_else(|e| panic!("min-free-disk-space parsing error {:?}", e))
+			})
+			.unwrap_or(DEFAULT_MIN_FREE_DISK_SPACE_MB);
+
+		// The snapshots are replaced by links to the cold storage, which must not be relative.
+		let cold_storage_dir = m.value_of("cold-storage-dir").map(|d| {
+			fs::create_dir_all(d).unwrap();
+			fs::canonicalize(d).unwrap()
+		});
+
 		let run_config = m.subcommand_matches("run").map(RunConfig::from);
 
 		Self::new(
@@ -241,6 +314,9 @@ impl From<&ArgMatches<'_>> for Config {
 			m.value_of("target-a-parentchain-rpc-port").map(Into::into),
 			m.value_of("target-b-parentchain-rpc-url").map(Into::into),
 			m.value_of("target-b-parentchain-rpc-port").map(Into::into),
+			values_of(m, "integritee-rpc-fallback-endpoint"),
+			values_of(m, "target-a-parentchain-rpc-fallback-endpoint"),
+			values_of(m, "target-b-parentchain-rpc-fallback-endpoint"),
 			if m.is_present("ws-external") { "0.0.0.0".into() } else { "127.0.0.1".into() },
 			m.value_of("trusted-external-address")
 				.map(|url| add_port_if_necessary(url, trusted_port)),
@@ -255,6 +331,8 @@ impl From<&ArgMatches<'_>> for Config {
 			metrics_server_port.to_string(),
 			untrusted_http_port.to_string(),
 			data_dir,
+			min_free_disk_space_mb,
+			cold_storage_dir,
 			run_config,
 		)
 	}
@@ -276,6 +354,37 @@ pub struct RunConfig {
 	marblerun_base_url: Option<String>,
 	/// parentchain which should be used for shielding/unshielding the stf's native token
 	pub shielding_target: Option<ParentchainId>,
+	/// Trusted RPC url of the worker this worker shadows, if running in shadow mode.
+	shadow_of: Option<String>,
+	/// Untrusted urls of the shadow workers this worker broadcasts its sidechain blocks to.
+	shadow_peers: Vec<String>,
+	/// Url of the remote signer holding the key of the funding account.
+	funding_signer_url: Option<String>,
+	/// Account that tops up the enclave accounts, signed by the remote signer.
+	funding_account: Option<String>,
+	/// Size limit of the archive of rejected shielded payloads in bytes, disabled if not set.
+	payload_quarantine_size: Option<u64>,
+	/// Periods during which the execution of trusted calls of the shard is deferred.
+	maintenance_windows: Vec<MaintenanceWindow>,
+	/// Load shedding under sustained overload, disabled if not set.
+	load_shedding_policy: Option<LoadSheddingPolicy>,
+	/// Sidechain blocks a shard may lag the parentchain before it is stale, default if not set.
+	stale_shard_threshold: Option<u64>,
+	/// Whether an unrecoverable error on a shard fails the enclave or only isolates the shard.
+	panic_policy: PanicPolicy,
+	/// Non-critical extrinsics are paused while the enclave account runs out of funds within this
+	/// period at the recent fee spend, never if not set.
+	min_funds_runway: Option<Duration>,
+	/// Shard on which the canary continuously runs a transfer and a getter, no canary if not set.
+	canary_shard: Option<ShardIdentifier>,
+	/// Url notified when the canary starts failing and when it recovers.
+	canary_webhook: Option<String>,
+	/// Trusted call types whose contents never leave the enclave memory.
+	never_persist_calls: Vec<String>,
+	/// Shards served by other worker instances, with the trusted RPC url of the instance if static.
+	shard_routes: Vec<(ShardIdentifier, Option<String>)>,
+	/// Number of the newest snapshots of a shard kept out of cold storage, default if not set.
+	hot_state_snapshots: Option<usize>,
 }
 
 impl RunConfig {
@@ -310,6 +419,70 @@ impl RunConfig {
 		// https://github.com/edgelesssys/marblerun/blob/master/docs/docs/workflows/monitoring.md?plain=1#L26
 		self.marblerun_base_url.as_deref().unwrap_or("http://localhost:9944")
 	}
+
+	pub fn shadow_of(&self) -> Option<&str> {
+		self.shadow_of.as_deref()
+	}
+
+	pub fn is_shadow(&self) -> bool {
+		self.shadow_of.is_some()
+	}
+
+	pub fn shadow_peers(&self) -> &[String] {
+		&self.shadow_peers
+	}
+
+	pub fn funding_signer_url(&self) -> Option<&str> {
+		self.funding_signer_url.as_deref()
+	}
+
+	pub fn funding_account(&self) -> Option<&str> {
+		self.funding_account.as_deref()
+	}
+
+	pub fn payload_quarantine_size(&self) -> Option<u64> {
+		self.payload_quarantine_size
+	}
+
+	pub fn maintenance_windows(&self) -> &[MaintenanceWindow] {
+		&self.maintenance_windows
+	}
+
+	pub fn load_shedding_policy(&self) -> Option<&LoadSheddingPolicy> {
+		self.load_shedding_policy.as_ref()
+	}
+
+	pub fn stale_shard_threshold(&self) -> Option<u64> {
+		self.stale_shard_threshold
+	}
+
+	pub fn panic_policy(&self) -> PanicPolicy {
+		self.panic_policy
+	}
+
+	pub fn min_funds_runway(&self) -> Option<Duration> {
+		self.min_funds_runway
+	}
+
+	pub fn canary_shard(&self) -> Option<&ShardIdentifier> {
+		self.canary_shard.as_ref()
+	}
+
+	pub fn canary_webhook(&self) -> Option<&str> {
+		self.canary_webhook.as_deref()
+	}
+
+	pub fn never_persist_calls(&self) -> &[String] {
+		&self.never_persist_calls
+	}
+
+	pub fn shard_routes(&self) -> &[(ShardIdentifier, Option<String>)] {
+		&self.shard_routes
+	}
+
+	pub fn hot_state_snapshots(&self) -> usize {
+		self.hot_state_snapshots.unwrap_or(DEFAULT_HOT_STATE_SNAPSHOTS)
+	}
 }
 
 impl From<&ArgMatches<'_>> for RunConfig {
@@ -339,6 +512,82 @@ impl From<&ArgMatches<'_>> for RunConfig {
 				i
 			),
 		});
+		let shadow_of = m.value_of("shadow-of").map(|s| s.to_string());
+		let shadow_peers = values_of(m, "shadow-peer");
+		let funding_signer_url = m.value_of("funding-signer-url").map(|s| s.to_string());
+		let funding_account = m.value_of("funding-account").map(|s| s.to_string());
+		let payload_quarantine_size = m.value_of("payload-quarantine-size").map(|s| {
+			s.parse()
+				.unwrap_or_else(|e| panic!("payload-quarantine-size parsing error {:?}", e))
+		});
+		let maintenance_windows = values_of(m, "maintenance-window")
+			.iter()
+			.map(|w| {
+				parse_maintenance_window(w)
+					.unwrap_or_else(|| panic!("maintenance-window parsing error: {}", w))
+			})
+			.collect();
+		let load_shedding_policy = m.value_of("load-shedding").map(|actions| {
+			let max_submissions_per_slot = m
+				.value_of("max-submissions-per-slot")
+				.map(|s| {
+					s.parse().unwrap_or_else(|e| {
+						panic!("max-submissions-per-slot parsing error {:?}", e)
+					})
+				})
+				.unwrap_or(LOAD_SHEDDING_MAX_SUBMISSIONS_PER_SLOT);
+			LoadSheddingPolicy {
+				actions: parse_shedding_actions(actions)
+					.unwrap_or_else(|| panic!("load-shedding parsing error: {}", actions)),
+				escalate_after: LOAD_SHEDDING_ESCALATION_SLOTS,
+				recover_after: LOAD_SHEDDING_RECOVERY_SLOTS,
+				epc_pressure_percent: LOAD_SHEDDING_EPC_PRESSURE_PERCENT,
+				max_submissions_per_slot,
+				low_priority_shards: values_of(m, "low-priority-shard")
+					.iter()
+					.map(|shard| {
+						parse_shard(shard).unwrap_or_else(|| {
+							panic!("low-priority-shard parsing error: {}", shard)
+						})
+					})
+					.collect(),
+			}
+		});
+		let stale_shard_threshold = m.value_of("stale-shard-threshold").map(|s| {
+			s.parse()
+				.unwrap_or_else(|e| panic!("stale-shard-threshold parsing error {:?}", e))
+		});
+		let panic_policy = m.value_of("panic-policy").map_or(PanicPolicy::Halt, |p| match p {
+			"halt" => PanicPolicy::Halt,
+			"isolate-shard" => PanicPolicy::IsolateShard,
+			_ => panic!("failed to parse panic-policy: {} must be one of halt|isolate-shard", p),
+		});
+		let min_funds_runway = m.value_of("min-funds-runway").map(|hours| {
+			let hours: u64 = hours
+				.parse()
+				.unwrap_or_else(|e| panic!("min-funds-runway parsing error {:?}", e));
+			Duration::from_secs(hours * 60 * 60)
+		});
+		let canary_shard = m.value_of("canary-shard").map(|shard| {
+			parse_shard(shard).unwrap_or_else(|| panic!("canary-shard parsing error: {}", shard))
+		});
+		let canary_webhook = m.value_of("canary-webhook").map(|url| {
+			Url::parse(url)
+				.unwrap_or_else(|e| panic!("canary-webhook parsing error: {:?}", e))
+				.to_string()
+		});
+		let never_persist_calls = values_of(m, "never-persist-call");
+		let shard_routes = values_of(m, "shard-route")
+			.iter()
+			.map(|route| {
+				parse_shard_route(route)
+					.unwrap_or_else(|| panic!("shard-route parsing error: {}", route))
+			})
+			.collect();
+		let hot_state_snapshots = m.value_of("hot-state-snapshots").map(|s| match s.parse() {
+			Ok(snapshots) if snapshots > 0 => snapshots,
+			_ => panic!("hot-state-snapshots parsing error: {} must be at least 1", s),
+		});
 		Self {
 			skip_ra,
 			dev,
@@ -347,10 +596,70 @@ impl From<&ArgMatches<'_>> for RunConfig {
 			reregister_teeracle_interval,
 			marblerun_base_url,
 			shielding_target,
+			shadow_of,
+			shadow_peers,
+			funding_signer_url,
+			funding_account,
+			payload_quarantine_size,
+			maintenance_windows,
+			load_shedding_policy,
+			stale_shard_threshold,
+			panic_policy,
+			min_funds_runway,
+			canary_shard,
+			canary_webhook,
+			never_persist_calls,
+			shard_routes,
+			hot_state_snapshots,
 		}
 	}
 }
 
+/// Parses the comma separated actions, each may only be given once.
+fn parse_shedding_actions(actions: &str) -> Option<Vec<SheddingAction>> {
+	let mut parsed = Vec::new();
+	for action in actions.split(',') {
+		let action = match action.trim() {
+			"anonymous-getters" => SheddingAction::ShedAnonymousGetters,
+			"submissions" => SheddingAction::RateLimitSubmissions,
+			"low-priority-shards" => SheddingAction::PauseLowPriorityShards,
+			_ => return None,
+		};
+		if parsed.contains(&action) {
+			return None
+		}
+		parsed.push(action);
+	}
+	Some(parsed)
+}
+
+fn parse_shard(shard: &str) -> Option<ShardIdentifier> {
+	let shard = shard.from_base58().ok()?;
+	(shard.len() == 32).then(|| ShardIdentifier::from_slice(&shard))
+}
+
+/// Parses `<shard>[=<url>]` with the base58 encoded shard.
+fn parse_shard_route(route: &str) -> Option<(ShardIdentifier, Option<String>)> {
+	match route.split_once('=') {
+		Some((shard, url)) => {
+			let url = Url::parse(url.trim()).ok()?;
+			Some((parse_shard(shard.trim())?, Some(url.as_str().trim_end_matches('/').to_string())))
+		},
+		None => Some((parse_shard(route.trim())?, None)),
+	}
+}
+
+/// Parses `<start>..<end>` in unix seconds.
+fn parse_maintenance_window(window: &str) -> Option<MaintenanceWindow> {
+	let (start, end) = window.split_once("..")?;
+	let start: u64 = start.trim().parse().ok()?;
+	let end: u64 = end.trim().parse().ok()?;
+	if start >= end {
+		return None
+	}
+	Some(MaintenanceWindow { start: start * 1000, end: end * 1000 })
+}
+
 fn add_port_if_necessary(url: &str, port: &str) -> String {
 	// [Option("ws(s)"), ip, Option(port)]
 	match url.split(':').count() {
@@ -369,6 +678,16 @@ fn add_port_if_necessary(url: &str, port: &str) -> String {
 	}
 }
 
+fn with_fallbacks(endpoint: String, fallbacks: &[String]) -> Vec<String> {
+	std::iter::once(endpoint).chain(fallbacks.iter().cloned()).collect()
+}
+
+fn values_of(m: &ArgMatches<'_>, name: &str) -> Vec<String> {
+	m.values_of(name)
+		.map(|values| values.map(Into::into).collect())
+		.unwrap_or_default()
+}
+
 pub fn pwd() -> PathBuf {
 	std::env::current_dir().expect("works on all supported platforms; qed.")
 }
@@ -376,6 +695,7 @@ pub fn pwd() -> PathBuf {
 #[cfg(test)]
 mod test {
 	use super::*;
+	use base58::ToBase58;
 	use std::collections::HashMap;
 
 	#[test]
@@ -390,6 +710,9 @@ mod test {
 		assert_eq!(config.target_a_parentchain_rpc_port, None);
 		assert_eq!(config.target_b_parentchain_rpc_url, None);
 		assert_eq!(config.target_b_parentchain_rpc_port, None);
+		assert!(config.integritee_rpc_fallback_endpoints.is_empty());
+		assert!(config.target_a_parentchain_rpc_fallback_endpoints.is_empty());
+		assert!(config.target_b_parentchain_rpc_fallback_endpoints.is_empty());
 		assert_eq!(config.trusted_worker_port, DEFAULT_TRUSTED_PORT);
 		assert_eq!(config.untrusted_worker_port, DEFAULT_UNTRUSTED_PORT);
 		assert_eq!(config.mu_ra_port, DEFAULT_MU_RA_PORT);
@@ -400,6 +723,8 @@ mod test {
 		assert!(!config.enable_metrics_server);
 		assert_eq!(config.untrusted_http_port, DEFAULT_UNTRUSTED_HTTP_PORT);
 		assert_eq!(config.data_dir, pwd());
+		assert_eq!(config.min_free_disk_space_mb, DEFAULT_MIN_FREE_DISK_SPACE_MB);
+		assert!(config.cold_storage_dir.is_none());
 		assert!(config.run_config.is_none());
 	}
 
@@ -464,6 +789,25 @@ mod test {
 		assert_eq!(config.untrusted_http_port, untrusted_http_port.to_string());
 	}
 
+	#[test]
+	fn fallback_endpoints_follow_primary_endpoint() {
+		let fallbacks = ["ws://10.0.0.2:9944", "ws://10.0.0.3:9944"];
+
+		let mut args = ArgMatches::default();
+		args.args = HashMap::from([("integritee-rpc-fallback-endpoint", Default::default())]);
+		// Workaround because MatchedArg is private.
+		args.args.get_mut("integritee-rpc-fallback-endpoint").unwrap().vals =
+			fallbacks.iter().map(|f| (*f).into()).collect();
+
+		let config = Config::from(&args);
+
+		assert_eq!(
+			config.integritee_rpc_endpoints(),
+			vec![config.integritee_rpc_endpoint(), fallbacks[0].into(), fallbacks[1].into()]
+		);
+		assert!(config.target_a_parentchain_rpc_endpoints().is_none());
+	}
+
 	#[test]
 	fn default_run_config_is_correct() {
 		let empty_args = ArgMatches::default();
@@ -473,6 +817,20 @@ mod test {
 		assert_eq!(run_config.skip_ra, false);
 		assert!(run_config.shard.is_none());
 		assert!(run_config.teeracle_update_interval.is_none());
+		assert!(!run_config.is_shadow());
+		assert!(run_config.shadow_peers.is_empty());
+		assert!(run_config.funding_signer_url().is_none());
+		assert!(run_config.payload_quarantine_size().is_none());
+		assert!(run_config.maintenance_windows().is_empty());
+		assert!(run_config.load_shedding_policy().is_none());
+		assert!(run_config.stale_shard_threshold().is_none());
+		assert_eq!(run_config.panic_policy(), PanicPolicy::Halt);
+		assert!(run_config.min_funds_runway().is_none());
+		assert!(run_config.canary_shard().is_none());
+		assert!(run_config.canary_webhook().is_none());
+		assert!(run_config.never_persist_calls().is_empty());
+		assert!(run_config.shard_routes().is_empty());
+		assert_eq!(run_config.hot_state_snapshots(), DEFAULT_HOT_STATE_SNAPSHOTS);
 	}
 
 	#[test]
@@ -485,10 +843,12 @@ mod test {
 			("skip-ra", Default::default()),
 			("shard", Default::default()),
 			("teeracle-interval", Default::default()),
+			("hot-state-snapshots", Default::default()),
 		]);
 		// Workaround because MatchedArg is private.
 		args.args.get_mut("shard").unwrap().vals = vec![shard_identifier.into()];
 		args.args.get_mut("teeracle-interval").unwrap().vals = vec!["42s".into()];
+		args.args.get_mut("hot-state-snapshots").unwrap().vals = vec!["5".into()];
 
 		let run_config = RunConfig::from(&args);
 
@@ -496,6 +856,45 @@ mod test {
 		assert_eq!(run_config.skip_ra, true);
 		assert_eq!(run_config.shard.unwrap(), shard_identifier.to_string());
 		assert_eq!(run_config.teeracle_update_interval.unwrap(), Duration::from_secs(42));
+		assert_eq!(run_config.hot_state_snapshots(), 5);
+	}
+
+	#[test]
+	fn maintenance_window_parsing_works() {
+		assert_eq!(
+			parse_maintenance_window("1700000000..1700003600"),
+			Some(MaintenanceWindow { start: 1_700_000_000_000, end: 1_700_003_600_000 })
+		);
+		assert_eq!(parse_maintenance_window("1700003600..1700000000"), None);
+		assert_eq!(parse_maintenance_window("1700000000"), None);
+	}
+
+	#[test]
+	fn shard_route_parsing_works() {
+		let shard = ShardIdentifier::repeat_byte(7);
+		let shard_base58 = shard.as_bytes().to_base58();
+
+		assert_eq!(
+			parse_shard_route(&format!("{}=wss://worker-2:2000", shard_base58)),
+			Some((shard, Some("wss://worker-2:2000".to_string())))
+		);
+		assert_eq!(parse_shard_route(&shard_base58), Some((shard, None)));
+		assert_eq!(parse_shard_route(&format!("{}=not a url", shard_base58)), None);
+		assert_eq!(parse_shard_route("invalid-shard=wss://worker-2:2000"), None);
+	}
+
+	#[test]
+	fn shedding_actions_parsing_works() {
+		assert_eq!(
+			parse_shedding_actions("anonymous-getters, submissions,low-priority-shards"),
+			Some(vec![
+				SheddingAction::ShedAnonymousGetters,
+				SheddingAction::RateLimitSubmissions,
+				SheddingAction::PauseLowPriorityShards,
+			])
+		);
+		assert_eq!(parse_shedding_actions("submissions,submissions"), None);
+		assert_eq!(parse_shedding_actions("everything"), None);
 	}
 
 	#[test]
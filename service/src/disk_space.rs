@@ -0,0 +1,129 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Free disk space checks before state, snapshots or blocks are written.
+//!
+//! Running out of disk space in the middle of a write corrupts the written file, so writes are
+//! refused once the free space would drop below the configured threshold.
+
+use crate::error::{Error, ServiceResult};
+use lazy_static::lazy_static;
+use log::*;
+use prometheus::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use std::{
+	ffi::CString,
+	io,
+	mem::MaybeUninit,
+	os::unix::ffi::OsStrExt,
+	path::{Path, PathBuf},
+};
+
+lazy_static! {
+	static ref FREE_DISK_SPACE: IntGauge = register_int_gauge!(
+		"integritee_worker_free_disk_space_bytes",
+		"Free disk space available to the worker, as of the last check"
+	)
+	.unwrap();
+	static ref REFUSED_DISK_WRITES: IntCounter = register_int_counter!(
+		"integritee_worker_disk_writes_refused",
+		"Number of writes refused because of insufficient free disk space"
+	)
+	.unwrap();
+}
+
+pub struct DiskSpaceMonitor {
+	data_dir: PathBuf,
+	min_free_bytes: u64,
+}
+
+impl DiskSpaceMonitor {
+	pub fn new(data_dir: PathBuf, min_free_bytes: u64) -> Self {
+		DiskSpaceMonitor { data_dir, min_free_bytes }
+	}
+
+	/// Free space of the file system `path` is on, `path` does not need to exist yet.
+	pub fn free_bytes(&self, path: &Path) -> ServiceResult<u64> {
+		let free_bytes = available_bytes(&existing_ancestor(path)).map_err(|e| {
+			Error::Custom(format!("Could not query free disk space of {:?}: {}", path, e).into())
+		})?;
+		FREE_DISK_SPACE.set(free_bytes.try_into().unwrap_or(i64::MAX));
+		Ok(free_bytes)
+	}
+
+	/// Fails with [`Error::InsufficientDiskSpace`] if writing `bytes_to_write` to `path` would
+	/// leave less than the configured free space.
+	pub fn ensure_free_space(&self, path: &Path, bytes_to_write: u64) -> ServiceResult<()> {
+		let free_bytes = self.free_bytes(path)?;
+		if free_bytes.saturating_sub(bytes_to_write) < self.min_free_bytes {
+			REFUSED_DISK_WRITES.inc();
+			return Err(Error::InsufficientDiskSpace {
+				path: path.display().to_string(),
+				free_bytes,
+				min_free_bytes: self.min_free_bytes,
+			})
+		}
+		Ok(())
+	}
+
+	/// Same as [`Self::ensure_free_space`] for the data directory.
+	pub fn ensure_free_space_in_data_dir(&self, bytes_to_write: u64) -> ServiceResult<()> {
+		self.ensure_free_space(&self.data_dir, bytes_to_write)
+	}
+}
+
+/// Closest ancestor of `path` that exists, the current directory for relative paths.
+fn existing_ancestor(path: &Path) -> PathBuf {
+	path.ancestors()
+		.find(|p| !p.as_os_str().is_empty() && p.exists())
+		.map(Path::to_path_buf)
+		.unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn available_bytes(path: &Path) -> io::Result<u64> {
+	let c_path = CString::new(path.as_os_str().as_bytes())
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+	let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+	if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+		return Err(io::Error::last_os_error())
+	}
+	let stat = unsafe { stat.assume_init() };
+	Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn free_space_of_a_missing_file_is_the_one_of_its_directory() {
+		let monitor = DiskSpaceMonitor::new(std::env::temp_dir(), 0);
+		let missing_file = std::env::temp_dir().join("missing_dir").join("missing_file.bin");
+
+		assert!(monitor.free_bytes(&missing_file).unwrap() > 0);
+		assert!(monitor.ensure_free_space(&missing_file, 0).is_ok());
+	}
+
+	#[test]
+	fn writes_below_the_threshold_are_refused() {
+		let monitor = DiskSpaceMonitor::new(std::env::temp_dir(), u64::MAX);
+
+		assert!(matches!(
+			monitor.ensure_free_space_in_data_dir(1),
+			Err(Error::InsufficientDiskSpace { min_free_bytes: u64::MAX, .. })
+		));
+	}
+}
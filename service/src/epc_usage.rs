@@ -0,0 +1,66 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! EPC usage of the worker, as accounted by the misc controller of its cgroup.
+//!
+//! The kernel only accounts the EPC on the unified cgroup hierarchy (cgroup v2), without it the
+//! usage is unknown and never counts as overload.
+
+use std::{fs, path::Path};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const EPC_RESOURCE: &str = "sgx_epc";
+
+/// Used and available EPC in bytes.
+pub fn epc_usage() -> Option<(u64, u64)> {
+	let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+	let cgroup = cgroups.lines().find_map(|line| line.strip_prefix("0::"))?;
+	let dir = Path::new(CGROUP_ROOT).join(cgroup.trim_start_matches('/'));
+
+	let used = epc_value(&fs::read_to_string(dir.join("misc.current")).ok()?)?;
+	// The limit of the cgroup is `max` unless configured, then the capacity of the host applies.
+	let total = fs::read_to_string(dir.join("misc.max"))
+		.ok()
+		.and_then(|max| epc_value(&max))
+		.or_else(|| {
+			epc_value(&fs::read_to_string(Path::new(CGROUP_ROOT).join("misc.capacity")).ok()?)
+		})?;
+	Some((used, total))
+}
+
+/// Value of the EPC entry of a flat keyed cgroup file, `None` if it is `max`.
+fn epc_value(content: &str) -> Option<u64> {
+	content.lines().find_map(|line| {
+		let mut fields = line.split_whitespace();
+		if fields.next()? != EPC_RESOURCE {
+			return None
+		}
+		fields.next()?.parse().ok()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn epc_value_is_read_from_flat_keyed_file() {
+		assert_eq!(epc_value("res_a 12\nsgx_epc 94371840\n"), Some(94371840));
+		assert_eq!(epc_value("sgx_epc max\n"), None);
+		assert_eq!(epc_value("res_a 12\n"), None);
+	}
+}
@@ -54,6 +54,10 @@ pub enum Error {
 	UnknownBlockHeader(Hash),
 	#[error("Enclave has not enough funds to send extrinsic")]
 	LowEnclaveBalance,
+	#[error("Shards directory is locked by another worker process (PID {pid}), see {lock_file}")]
+	ShardsDirectoryLocked { lock_file: String, pid: String },
+	#[error("Insufficient disk space to write {path}: {free_bytes} bytes free, {min_free_bytes} bytes must remain free")]
+	InsufficientDiskSpace { path: String, free_bytes: u64, min_free_bytes: u64 },
 	#[error("{0}")]
 	Custom(Box<dyn std::error::Error + Sync + Send + 'static>),
 }
@@ -0,0 +1,207 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Signing of the service's own parentchain extrinsics by an external signer.
+//!
+//! The operator's funding account can be held by a remote signer, e.g. in front of an HSM, so
+//! its seed never has to be on the worker host. The signer is only ever asked to sign payloads
+//! of the service, the enclave keys are not involved. Its signatures are verified against the
+//! account before they are used, so a compromised signer can't get anything else submitted.
+
+use crate::error::{Error, ServiceResult};
+use codec::{Decode, Encode};
+use itc_rest_client::{
+	http_client::{DefaultSend, HttpClient},
+	rest_client::{RestClient, Url},
+	RestPath, RestPost,
+};
+use itp_node_api::api_client::{
+	AccountApi, Address, ExtrinsicParams, PairSignature, ParentchainApi, ParentchainSignedExtra,
+	UncheckedExtrinsicV4,
+};
+use itp_types::{parentchain::AccountId, OpaqueCall};
+use log::*;
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::Ss58Codec;
+use sp_runtime::traits::Verify;
+use std::time::Duration;
+use substrate_api_client::ac_primitives::SignedPayload;
+
+const SIGN_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub type SignedExtrinsic =
+	UncheckedExtrinsicV4<Address, OpaqueCall, PairSignature, ParentchainSignedExtra>;
+
+/// Signs payloads on behalf of an account whose key the service does not hold.
+pub trait SignPayload {
+	fn account_id(&self) -> &AccountId;
+
+	fn sign(&self, payload: &[u8]) -> ServiceResult<PairSignature>;
+}
+
+/// Body of `POST <signer url>/sign`.
+#[derive(Serialize, Debug)]
+struct SignRequest {
+	/// SS58 encoded account that shall sign.
+	account: String,
+	/// Hex encoded payload.
+	payload: String,
+}
+
+impl RestPath<()> for SignRequest {
+	fn get_path(_: ()) -> Result<String, itc_rest_client::error::Error> {
+		Ok("sign".to_string())
+	}
+}
+
+#[derive(Deserialize, Debug)]
+struct SignResponse {
+	/// Hex encoded SCALE encoding of the `MultiSignature`.
+	signature: String,
+}
+
+/// Client of a remote signer API.
+pub struct RemoteSigner {
+	url: Url,
+	account_id: AccountId,
+}
+
+impl RemoteSigner {
+	pub fn new(url: &str, account_id: AccountId) -> ServiceResult<Self> {
+		let url = Url::parse(url).map_err(|e| Error::Custom(e.into()))?;
+		Ok(Self { url, account_id })
+	}
+}
+
+impl SignPayload for RemoteSigner {
+	fn account_id(&self) -> &AccountId {
+		&self.account_id
+	}
+
+	fn sign(&self, payload: &[u8]) -> ServiceResult<PairSignature> {
+		let http_client =
+			HttpClient::new(DefaultSend {}, true, Some(SIGN_REQUEST_TIMEOUT), None, None);
+		let mut rest_client = RestClient::new(http_client, self.url.clone());
+		let request = SignRequest {
+			account: self.account_id.to_ss58check(),
+			payload: format!("0x{}", hex::encode(payload)),
+		};
+		let response: SignResponse = rest_client.post_capture((), &request).map_err(|e| {
+			Error::Custom(format!("Remote signer at {} failed to sign: {}", self.url, e).into())
+		})?;
+		decode_verified_signature(&response, payload, &self.account_id)
+	}
+}
+
+/// Decodes the signature of the response, which must be a valid signature of the payload by the
+/// account.
+fn decode_verified_signature(
+	response: &SignResponse,
+	payload: &[u8],
+	account_id: &AccountId,
+) -> ServiceResult<PairSignature> {
+	let signature = hex::decode(response.signature.trim_start_matches("0x"))
+		.map_err(|e| Error::Custom(e.into()))?;
+	let signature = PairSignature::decode(&mut signature.as_slice())?;
+	if !signature.verify(payload, account_id) {
+		return Err(Error::Custom(
+			format!(
+				"Remote signer returned an invalid signature for {}",
+				account_id.to_ss58check()
+			)
+			.into(),
+		))
+	}
+	Ok(signature)
+}
+
+/// Composes an extrinsic of the call, signed by the external signer with its next nonce.
+pub fn compose_signed_extrinsic<Signer: SignPayload>(
+	api: &ParentchainApi,
+	signer: &Signer,
+	call: OpaqueCall,
+) -> ServiceResult<SignedExtrinsic> {
+	let nonce = api.get_nonce_of(signer.account_id())?;
+	let extrinsic_params = api.extrinsic_params(nonce);
+	let payload = SignedPayload::from_raw(
+		call.clone(),
+		extrinsic_params.signed_extra(),
+		extrinsic_params.additional_signed(),
+	);
+	debug!("Requesting the external signature of {}", signer.account_id().to_ss58check());
+	let signature = payload.using_encoded(|payload| signer.sign(payload))?;
+	Ok(UncheckedExtrinsicV4::new_signed(
+		call,
+		Address::Id(signer.account_id().clone()),
+		signature,
+		extrinsic_params.signed_extra(),
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{sr25519, Pair};
+	use sp_runtime::MultiSignature;
+
+	fn response(signature: &MultiSignature) -> SignResponse {
+		SignResponse { signature: format!("0x{}", hex::encode(signature.encode())) }
+	}
+
+	#[test]
+	fn signature_of_the_account_over_the_payload_is_accepted() {
+		let pair = sr25519::Pair::from_seed(&[3u8; 32]);
+		let signature = MultiSignature::Sr25519(pair.sign(b"payload"));
+
+		let decoded =
+			decode_verified_signature(&response(&signature), b"payload", &pair.public().into())
+				.unwrap();
+
+		assert_eq!(decoded, signature);
+	}
+
+	#[test]
+	fn signature_of_another_account_is_rejected() {
+		let pair = sr25519::Pair::from_seed(&[3u8; 32]);
+		let other_pair = sr25519::Pair::from_seed(&[4u8; 32]);
+		let signature = MultiSignature::Sr25519(other_pair.sign(b"payload"));
+
+		let result =
+			decode_verified_signature(&response(&signature), b"payload", &pair.public().into());
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn signature_over_another_payload_is_rejected() {
+		let pair = sr25519::Pair::from_seed(&[3u8; 32]);
+		let signature = MultiSignature::Sr25519(pair.sign(b"other payload"));
+
+		let result =
+			decode_verified_signature(&response(&signature), b"payload", &pair.public().into());
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn undecodable_signature_is_rejected() {
+		let pair = sr25519::Pair::from_seed(&[3u8; 32]);
+		let response = SignResponse { signature: "0x0102".to_string() };
+
+		assert!(decode_verified_signature(&response, b"payload", &pair.public().into()).is_err());
+	}
+}
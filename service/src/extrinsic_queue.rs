@@ -0,0 +1,352 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Persistent queue of the extrinsics the enclave sends to the parentchains.
+//!
+//! Extrinsics are stored before they are submitted and are only removed once the node has
+//! accepted them, so neither a parentchain outage nor a restart of the worker drops them.
+//! Extrinsics that still fail after [`MAX_SUBMISSION_ATTEMPTS`] are moved to a dead-letter file,
+//! so an extrinsic the node rejects doesn't block the ones queued after it.
+
+use crate::error::{Error, ServiceResult};
+use codec::{Compact, Decode, Encode};
+use itp_node_api::api_client::{Address, PairSignature, ParentchainSignedExtra};
+use itp_types::parentchain::{AccountId, Index, ParentchainId};
+use log::*;
+use sp_runtime::{MultiAddress, OpaqueExtrinsic};
+use std::{
+	collections::VecDeque,
+	fs,
+	path::{Path, PathBuf},
+	sync::{Condvar, Mutex, MutexGuard},
+	time::Duration,
+};
+
+pub const OUTBOUND_EXTRINSICS_FILE: &str = "outbound_extrinsics.bin";
+pub const DEAD_LETTER_FILE: &str = "outbound_extrinsics_dead_letter.bin";
+
+/// Further extrinsics are rejected, so an unreachable parentchain doesn't fill the disk.
+pub const MAX_QUEUED_EXTRINSICS: usize = 10_000;
+
+/// Failed submissions after which a queued extrinsic is moved to the dead-letter file.
+pub const MAX_SUBMISSION_ATTEMPTS: u32 = 5;
+
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct QueuedExtrinsic {
+	pub parentchain_id: ParentchainId,
+	pub extrinsic: OpaqueExtrinsic,
+	/// The following extrinsics of the parentchain are only submitted once this one is in a block.
+	pub await_inclusion: bool,
+	pub failed_submissions: u32,
+}
+
+struct OpenQueue {
+	path: PathBuf,
+	dead_letter_path: PathBuf,
+	extrinsics: VecDeque<QueuedExtrinsic>,
+	/// Whether extrinsics have been pushed since the last [`ExtrinsicQueue::wait_for_push`].
+	pushed: bool,
+}
+
+/// The queue is closed until [`ExtrinsicQueue::open`] is called by the worker, which also
+/// replays it. Nothing can be queued while it is closed.
+#[derive(Default)]
+pub struct ExtrinsicQueue {
+	queue: Mutex<Option<OpenQueue>>,
+	pushed: Condvar,
+}
+
+impl ExtrinsicQueue {
+	/// Opened queue, see [`ExtrinsicQueue::open`].
+	pub fn load_or_create(data_dir: &Path) -> ServiceResult<Self> {
+		let queue = Self::default();
+		queue.open(data_dir)?;
+		Ok(queue)
+	}
+
+	/// Loads the extrinsics that have not been submitted before the last shutdown.
+	pub fn open(&self, data_dir: &Path) -> ServiceResult<()> {
+		let path = data_dir.join(OUTBOUND_EXTRINSICS_FILE);
+		let extrinsics: VecDeque<QueuedExtrinsic> = read_or_default(&path)?;
+		if !extrinsics.is_empty() {
+			info!(
+				"Loaded {} outbound extrinsics that have not been submitted yet",
+				extrinsics.len()
+			);
+		}
+		*self.lock()? = Some(OpenQueue {
+			path,
+			dead_letter_path: data_dir.join(DEAD_LETTER_FILE),
+			extrinsics,
+			pushed: false,
+		});
+		Ok(())
+	}
+
+	pub fn is_open(&self) -> ServiceResult<bool> {
+		Ok(self.lock()?.is_some())
+	}
+
+	pub fn push(
+		&self,
+		parentchain_id: ParentchainId,
+		extrinsics: Vec<OpaqueExtrinsic>,
+		await_inclusion: bool,
+	) -> ServiceResult<()> {
+		let mut guard = self.lock()?;
+		let queue = open_queue(&mut guard)?;
+		if queue.extrinsics.len() + extrinsics.len() > MAX_QUEUED_EXTRINSICS {
+			return Err(Error::Custom(
+				format!(
+					"Outbound extrinsic queue is full, rejecting {} extrinsics to {:?}",
+					extrinsics.len(),
+					parentchain_id
+				)
+				.into(),
+			))
+		}
+		queue.extrinsics.extend(extrinsics.into_iter().map(|extrinsic| QueuedExtrinsic {
+			parentchain_id,
+			extrinsic,
+			await_inclusion,
+			failed_submissions: 0,
+		}));
+		queue.persist()?;
+		queue.pushed = true;
+		self.pushed.notify_all();
+		Ok(())
+	}
+
+	/// Oldest queued extrinsic of the parentchain.
+	pub fn front(&self, parentchain_id: ParentchainId) -> ServiceResult<Option<QueuedExtrinsic>> {
+		let mut guard = self.lock()?;
+		let queue = open_queue(&mut guard)?;
+		Ok(queue.position(parentchain_id).map(|index| queue.extrinsics[index].clone()))
+	}
+
+	/// Removes the oldest queued extrinsic of the parentchain.
+	pub fn pop_front(&self, parentchain_id: ParentchainId) -> ServiceResult<()> {
+		let mut guard = self.lock()?;
+		let queue = open_queue(&mut guard)?;
+		if let Some(index) = queue.position(parentchain_id) {
+			queue.extrinsics.remove(index);
+			queue.persist()?;
+		}
+		Ok(())
+	}
+
+	/// Counts a failed submission of the oldest queued extrinsic of the parentchain. Once it
+	/// failed [`MAX_SUBMISSION_ATTEMPTS`] times, it is moved to the dead-letter file and `true`
+	/// is returned.
+	pub fn record_failed_submission(&self, parentchain_id: ParentchainId) -> ServiceResult<bool> {
+		let mut guard = self.lock()?;
+		let queue = open_queue(&mut guard)?;
+		let index = match queue.position(parentchain_id) {
+			Some(index) => index,
+			None => return Ok(false),
+		};
+		queue.extrinsics[index].failed_submissions += 1;
+		let dead_lettered = queue.extrinsics[index].failed_submissions >= MAX_SUBMISSION_ATTEMPTS;
+		if dead_lettered {
+			let extrinsic = queue.extrinsics.remove(index).expect("index has just been found");
+			let mut dead_letters: Vec<QueuedExtrinsic> = read_or_default(&queue.dead_letter_path)?;
+			dead_letters.push(extrinsic);
+			write_atomically(&queue.dead_letter_path, &dead_letters)?;
+		}
+		queue.persist()?;
+		Ok(dead_lettered)
+	}
+
+	pub fn len(&self, parentchain_id: ParentchainId) -> ServiceResult<usize> {
+		let mut guard = self.lock()?;
+		let queue = open_queue(&mut guard)?;
+		Ok(queue.extrinsics.iter().filter(|xt| xt.parentchain_id == parentchain_id).count())
+	}
+
+	/// Blocks until extrinsics are pushed or the timeout elapsed. Returns immediately if
+	/// extrinsics have been pushed since the last call.
+	pub fn wait_for_push(&self, timeout: Duration) -> ServiceResult<()> {
+		let mut guard = self.lock()?;
+		if !open_queue(&mut guard)?.pushed {
+			guard = self.pushed.wait_timeout(guard, timeout).map_err(|_| poisoned_lock())?.0;
+		}
+		open_queue(&mut guard)?.pushed = false;
+		Ok(())
+	}
+
+	fn lock(&self) -> ServiceResult<MutexGuard<Option<OpenQueue>>> {
+		self.queue.lock().map_err(|_| poisoned_lock())
+	}
+}
+
+impl OpenQueue {
+	fn position(&self, parentchain_id: ParentchainId) -> Option<usize> {
+		self.extrinsics.iter().position(|xt| xt.parentchain_id == parentchain_id)
+	}
+
+	fn persist(&self) -> ServiceResult<()> {
+		write_atomically(&self.path, &self.extrinsics)
+	}
+}
+
+fn open_queue<'a>(
+	guard: &'a mut MutexGuard<Option<OpenQueue>>,
+) -> ServiceResult<&'a mut OpenQueue> {
+	guard
+		.as_mut()
+		.ok_or_else(|| Error::Custom("Outbound extrinsic queue has not been opened".into()))
+}
+
+fn read_or_default<T: Decode + Default>(path: &Path) -> ServiceResult<T> {
+	match fs::read(path) {
+		Ok(bytes) => Ok(Decode::decode(&mut bytes.as_slice())?),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+		Err(e) => Err(Error::Custom(e.into())),
+	}
+}
+
+/// Writes to a temporary file first, so a crash doesn't leave a corrupted file behind.
+fn write_atomically<T: Encode>(path: &Path, value: &T) -> ServiceResult<()> {
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, value.encode())
+		.and_then(|_| fs::rename(&tmp_path, path))
+		.map_err(|e| Error::Custom(e.into()))
+}
+
+/// Signer and nonce of a signed extrinsic.
+pub fn signer_and_nonce(extrinsic: &OpaqueExtrinsic) -> Option<(AccountId, Index)> {
+	let encoded = extrinsic.encode();
+	let mut input = encoded.as_slice();
+	let _length = Compact::<u32>::decode(&mut input).ok()?;
+	let version = u8::decode(&mut input).ok()?;
+	// The highest bit of the version byte marks signed extrinsics.
+	if version & 0b1000_0000 == 0 {
+		return None
+	}
+	let (address, _signature, signed_extra) =
+		<(Address, PairSignature, ParentchainSignedExtra)>::decode(&mut input).ok()?;
+	match address {
+		MultiAddress::Id(account) => Some((account, signed_extra.nonce)),
+		_ => None,
+	}
+}
+
+fn poisoned_lock() -> Error {
+	Error::Custom("Outbound extrinsic queue lock is poisoned".into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(name);
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	fn extrinsic(byte: u8) -> OpaqueExtrinsic {
+		OpaqueExtrinsic::from_bytes(&[4u8, byte]).unwrap()
+	}
+
+	#[test]
+	fn queued_extrinsics_survive_a_restart_in_order() {
+		let dir = temp_dir("outbound_extrinsics_survive_restart");
+		let queue = ExtrinsicQueue::load_or_create(&dir).unwrap();
+		queue
+			.push(ParentchainId::Integritee, vec![extrinsic(1), extrinsic(2)], false)
+			.unwrap();
+		queue.push(ParentchainId::TargetA, vec![extrinsic(3)], true).unwrap();
+		queue.pop_front(ParentchainId::Integritee).unwrap();
+		drop(queue);
+
+		let queue = ExtrinsicQueue::load_or_create(&dir).unwrap();
+
+		assert_eq!(queue.len(ParentchainId::Integritee).unwrap(), 1);
+		assert_eq!(
+			queue.front(ParentchainId::Integritee).unwrap().map(|xt| xt.extrinsic),
+			Some(extrinsic(2))
+		);
+		let target_a = queue.front(ParentchainId::TargetA).unwrap().unwrap();
+		assert_eq!((target_a.extrinsic, target_a.await_inclusion), (extrinsic(3), true));
+		assert_eq!(queue.front(ParentchainId::TargetB).unwrap(), None);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn closed_queue_rejects_extrinsics() {
+		let queue = ExtrinsicQueue::default();
+
+		assert!(!queue.is_open().unwrap());
+		assert!(queue.push(ParentchainId::Integritee, vec![extrinsic(1)], false).is_err());
+	}
+
+	#[test]
+	fn full_queue_rejects_extrinsics() {
+		let dir = temp_dir("outbound_extrinsics_full");
+		let queue = ExtrinsicQueue::load_or_create(&dir).unwrap();
+		let extrinsics = (0..MAX_QUEUED_EXTRINSICS).map(|i| extrinsic(i as u8)).collect();
+		queue.push(ParentchainId::Integritee, extrinsics, false).unwrap();
+
+		assert!(queue.push(ParentchainId::TargetA, vec![extrinsic(1)], false).is_err());
+		assert_eq!(queue.len(ParentchainId::TargetA).unwrap(), 0);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn extrinsic_is_dead_lettered_after_max_failed_submissions() {
+		let dir = temp_dir("outbound_extrinsics_dead_letter");
+		let queue = ExtrinsicQueue::load_or_create(&dir).unwrap();
+		queue
+			.push(ParentchainId::Integritee, vec![extrinsic(1), extrinsic(2)], false)
+			.unwrap();
+
+		for _ in 1..MAX_SUBMISSION_ATTEMPTS {
+			assert!(!queue.record_failed_submission(ParentchainId::Integritee).unwrap());
+		}
+		assert!(queue.record_failed_submission(ParentchainId::Integritee).unwrap());
+
+		assert_eq!(
+			queue.front(ParentchainId::Integritee).unwrap().map(|xt| xt.extrinsic),
+			Some(extrinsic(2))
+		);
+		let dead_letters: Vec<QueuedExtrinsic> =
+			read_or_default(&dir.join(DEAD_LETTER_FILE)).unwrap();
+		assert_eq!(dead_letters.len(), 1);
+		assert_eq!(dead_letters[0].extrinsic, extrinsic(1));
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn wait_for_push_returns_immediately_after_a_push() {
+		let dir = temp_dir("outbound_extrinsics_wait_for_push");
+		let queue = ExtrinsicQueue::load_or_create(&dir).unwrap();
+		queue.push(ParentchainId::Integritee, vec![extrinsic(1)], false).unwrap();
+
+		let start = std::time::Instant::now();
+		queue.wait_for_push(Duration::from_secs(10)).unwrap();
+
+		assert!(start.elapsed() < Duration::from_secs(10));
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn unsigned_extrinsic_has_no_nonce() {
+		assert_eq!(signer_and_nonce(&extrinsic(1)), None);
+	}
+}
@@ -16,9 +16,10 @@
 */
 
 //! Service to determine if the integritee services is initialized and registered on the node,
-//! hosted on a http server.
+//! hosted on a http server. It also serves the progress of the startup over JSON-RPC and the
+//! balance status of the enclave account.
 
-use crate::error::ServiceResult;
+use crate::{account_balance::BalanceMonitor, error::ServiceResult, startup::StartupProgress};
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode};
 use log::*;
 use parking_lot::RwLock;
@@ -27,6 +28,8 @@ use warp::Filter;
 
 pub async fn start_is_initialized_server<Handler>(
 	initialization_handler: Arc<Handler>,
+	startup_progress: Arc<StartupProgress>,
+	balance_monitor: Arc<BalanceMonitor>,
 	port: u16,
 ) -> ServiceResult<()>
 where
@@ -43,10 +46,21 @@ where
 		}
 	});
 
+	let startup_status_route = warp::post().and(warp::path::end()).and(warp::body::json()).map(
+		move |request: serde_json::Value| {
+			warp::reply::json(&startup_progress.handle_rpc_request(&request))
+		},
+	);
+
+	let account_balance_route =
+		warp::path!("account_balance").map(move || warp::reply::json(&balance_monitor.status()));
+
 	let socket_addr: SocketAddr = ([0, 0, 0, 0], port).into();
 
 	info!("Running initialized server on: {:?}", socket_addr);
-	warp::serve(is_initialized_route).run(socket_addr).await;
+	warp::serve(is_initialized_route.or(account_balance_route).or(startup_status_route))
+		.run(socket_addr)
+		.await;
 
 	info!("Initialized server shut down");
 	Ok(())
@@ -18,17 +18,29 @@
 #![cfg_attr(test, feature(assert_matches))]
 #![allow(unused)]
 
+mod account_balance;
 mod account_funding;
+mod canary;
+mod cold_storage;
 mod config;
+mod disk_space;
 mod enclave;
+mod epc_usage;
 mod error;
+mod external_signer;
+mod extrinsic_queue;
 mod globals;
 mod initialized_service;
 mod ocall_bridge;
 mod parentchain_handler;
 mod prometheus_metrics;
+mod retry;
 mod setup;
+mod shadow_run;
+mod shard_routing;
+mod shards_lock;
 mod sidechain_setup;
+mod startup;
 mod sync_block_broadcaster;
 mod sync_state;
 #[cfg(feature = "teeracle")]
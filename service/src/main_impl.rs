@@ -4,24 +4,36 @@ use crate::teeracle::{schedule_periodic_reregistration_thread, start_periodic_ma
 #[cfg(not(feature = "dcap"))]
 use crate::utils::check_files;
 use crate::{
-	account_funding::{setup_reasonable_account_funding, EnclaveAccountInfoProvider},
-	config::Config,
+	account_balance::{start_balance_monitoring_thread, BalanceMonitor},
+	account_funding::{
+		setup_reasonable_account_funding, EnclaveAccountInfoProvider, FundingSource,
+	},
+	canary::{start_canary_thread, CanaryWebhook},
+	cold_storage::{start_cold_storage_tiering_thread, ColdStorage},
+	config::{Config, RunConfig, DEFAULT_HOT_STATE_SNAPSHOTS},
+	disk_space::DiskSpaceMonitor,
 	enclave::{
 		api::enclave_init,
 		tls_ra::{enclave_request_state_provisioning, enclave_run_state_provisioning_server},
 	},
 	error::Error,
+	external_signer::RemoteSigner,
+	extrinsic_queue::ExtrinsicQueue,
 	globals::tokio_handle::{GetTokioHandle, GlobalTokioHandle},
 	initialized_service::{
 		start_is_initialized_server, InitializationHandler, IsInitialized, TrackInitialization,
 	},
 	ocall_bridge::{
 		bridge_api::Bridge as OCallBridge, component_factory::OCallBridgeComponentFactory,
+		worker_command_ocall::WorkerCommandVerifier, worker_on_chain_ocall::WorkerOnChainOCall,
 	},
 	parentchain_handler::{HandleParentchain, ParentchainHandler},
 	prometheus_metrics::{start_metrics_server, EnclaveMetricsReceiver, MetricsHandler},
-	setup,
+	setup, shadow_run,
+	shard_routing::{start_shard_routes_refresh, ShardRouter},
+	shards_lock::ShardsLock,
 	sidechain_setup::{sidechain_init_block_production, sidechain_start_untrusted_rpc_server},
+	startup::{StartupProgress, StartupStage},
 	sync_block_broadcaster::SyncBlockBroadcaster,
 	sync_state, tests,
 	utils::extract_shard,
@@ -42,7 +54,7 @@ use itp_enclave_api::{
 use itp_node_api::{
 	api_client::{AccountApi, PalletTeerexApi, ParentchainApi},
 	metadata::NodeMetadata,
-	node_api_factory::{CreateNodeApi, NodeApiFactory},
+	node_api_factory::{CreateNodeApi, Failback, NodeApiFactory},
 };
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
 use its_peer_fetch::{
@@ -78,6 +90,11 @@ use substrate_api_client::ac_node_api::{EventRecord, Phase::ApplyExtrinsic};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Subcommands that only read the data dir, so they may run next to the worker that owns the
+/// shards. The `state diff` subcommand is read-only as well, see [`is_read_only_command`].
+/// Exports of sealed files the running worker appends to are not read-only.
+const READ_ONLY_SUBCOMMANDS: [&str; 3] = ["mrenclave", "export-state-backup", "export-shard-state"];
+
 #[cfg(feature = "link-binary")]
 pub type EnclaveWorker =
 	Worker<Config, NodeApiFactory, Enclave, InitializationHandler<WorkerModeProvider>>;
@@ -111,6 +128,19 @@ pub(crate) fn main() {
 		thread::sleep(std::time::Duration::from_secs(5));
 	}
 
+	let _shards_lock = if is_read_only_command(&matches) {
+		None
+	} else {
+		match ShardsLock::acquire(config.data_dir()) {
+			Ok(lock) => Some(lock),
+			Err(e) => {
+				error!("{}", e);
+				eprintln!("Error: {}", e);
+				std::process::exit(1);
+			},
+		}
+	};
+
 	let clean_reset = matches.is_present("clean-reset");
 	if clean_reset {
 		crate::setup::purge_files_from_dir(config.data_dir()).unwrap();
@@ -119,13 +149,29 @@ pub(crate) fn main() {
 	// build the entire dependency tree
 	let tokio_handle = Arc::new(GlobalTokioHandle {});
 	let sidechain_blockstorage = Arc::new(
-		SidechainStorageLock::<SignedSidechainBlock>::from_base_path(
-			config.data_dir().to_path_buf(),
-		)
+		match config.cold_storage_dir() {
+			Some(cold_storage_dir) => {
+				start_cold_storage_tiering_thread(ColdStorage::new(
+					config.data_dir().to_path_buf(),
+					cold_storage_dir.to_path_buf(),
+					config
+						.run_config()
+						.as_ref()
+						.map_or(DEFAULT_HOT_STATE_SNAPSHOTS, RunConfig::hot_state_snapshots),
+				));
+				SidechainStorageLock::<SignedSidechainBlock>::from_base_path_with_cold_archive(
+					config.data_dir().to_path_buf(),
+					cold_storage_dir.to_path_buf(),
+				)
+			},
+			None => SidechainStorageLock::<SignedSidechainBlock>::from_base_path(
+				config.data_dir().to_path_buf(),
+			),
+		}
 		.unwrap(),
 	);
-	let node_api_factory = Arc::new(NodeApiFactory::new(
-		config.integritee_rpc_endpoint(),
+	let node_api_factory = Arc::new(NodeApiFactory::new_with_fallbacks(
+		config.integritee_rpc_endpoints(),
 		AccountKeyring::Alice.pair(),
 	));
 	let enclave = Arc::new(enclave_init(&config).unwrap());
@@ -145,19 +191,37 @@ pub(crate) fn main() {
 		Arc::new(BlockFetcher::<SignedSidechainBlock, _>::new(untrusted_peer_fetcher));
 	let enclave_metrics_receiver = Arc::new(EnclaveMetricsReceiver {});
 
-	let maybe_target_a_parentchain_api_factory = config
-		.target_a_parentchain_rpc_endpoint()
-		.map(|url| Arc::new(NodeApiFactory::new(url, AccountKeyring::Alice.pair())));
+	let maybe_target_a_parentchain_api_factory =
+		config.target_a_parentchain_rpc_endpoints().map(|urls| {
+			Arc::new(NodeApiFactory::new_with_fallbacks(urls, AccountKeyring::Alice.pair()))
+		});
 
-	let maybe_target_b_parentchain_api_factory = config
-		.target_b_parentchain_rpc_endpoint()
-		.map(|url| Arc::new(NodeApiFactory::new(url, AccountKeyring::Alice.pair())));
+	let maybe_target_b_parentchain_api_factory =
+		config.target_b_parentchain_rpc_endpoints().map(|urls| {
+			Arc::new(NodeApiFactory::new_with_fallbacks(urls, AccountKeyring::Alice.pair()))
+		});
+
+	// Opened by the worker only, the other subcommands submit their extrinsics directly.
+	let extrinsic_queue = Arc::new(ExtrinsicQueue::default());
+	let extrinsic_replay = WorkerOnChainOCall::new(
+		node_api_factory.clone(),
+		maybe_target_a_parentchain_api_factory.clone(),
+		maybe_target_b_parentchain_api_factory.clone(),
+		extrinsic_queue.clone(),
+	);
 
 	// initialize o-call bridge with a concrete factory implementation
 	OCallBridge::initialize(Arc::new(OCallBridgeComponentFactory::new(
 		node_api_factory.clone(),
 		maybe_target_a_parentchain_api_factory,
 		maybe_target_b_parentchain_api_factory,
+		extrinsic_queue.clone(),
+		Arc::new(WorkerCommandVerifier::new(enclave.get_ecc_signing_pubkey().unwrap())),
+		config.run_config().as_ref().map_or(false, RunConfig::is_shadow),
+		Arc::new(DiskSpaceMonitor::new(
+			config.data_dir().to_path_buf(),
+			config.min_free_disk_space_bytes(),
+		)),
 		sync_block_broadcaster,
 		enclave.clone(),
 		sidechain_blockstorage.clone(),
@@ -200,10 +264,13 @@ pub(crate) fn main() {
 			enclave,
 			sidechain_blockstorage,
 			node_api,
+			node_api_factory,
 			tokio_handle,
 			initialization_handler,
 			quoting_enclave_target_info,
 			quote_size,
+			extrinsic_queue,
+			extrinsic_replay,
 		);
 	} else if let Some(smatches) = matches.subcommand_matches("request-state") {
 		println!("*** Requesting state from a registered worker \n");
@@ -233,6 +300,64 @@ pub(crate) fn main() {
 		}
 	} else if matches.is_present("mrenclave") {
 		println!("{}", enclave.get_fingerprint().unwrap().encode().to_base58());
+	} else if let Some(sub_matches) = matches.subcommand_matches("export-payload-quarantine") {
+		setup::export_payload_quarantine(
+			&enclave,
+			node_api_factory.create_api().expect("Failed to create parentchain node API"),
+			sub_matches.value_of("request").expect("request is a required argument"),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("key-ceremony") {
+		setup::conduct_key_ceremony(
+			enclave.as_ref(),
+			sub_matches.value_of("ceremony").expect("ceremony is a required argument"),
+		);
+	} else if matches.is_present("key-ceremony-nonce") {
+		setup::issue_key_ceremony_nonce(enclave.as_ref());
+	} else if matches.is_present("export-key-ceremony-trail") {
+		setup::export_key_ceremony_trail(enclave.as_ref());
+	} else if matches.is_present("smoke-test") {
+		setup::run_smoke_test(enclave.as_ref());
+	} else if let Some(sub_matches) = matches.subcommand_matches("export-state-backup") {
+		setup::export_state_backup(
+			enclave.as_ref(),
+			&extract_shard(sub_matches.value_of("shard"), enclave.as_ref()),
+			sub_matches.value_of("path").expect("path is a required argument"),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("export-block-archive") {
+		setup::export_block_archive(
+			sidechain_blockstorage.as_ref(),
+			&extract_shard(sub_matches.value_of("shard"), enclave.as_ref()),
+			sub_matches.value_of("path").expect("path is a required argument"),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("import-state-backup") {
+		setup::import_state_backup(
+			enclave.as_ref(),
+			sub_matches.value_of("path").expect("path is a required argument"),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("export-shard-state") {
+		setup::export_shard_state(
+			&enclave,
+			node_api_factory.create_api().expect("Failed to create parentchain node API"),
+			sub_matches.value_of("request").expect("request is a required argument"),
+			sub_matches.value_of("path").expect("path is a required argument"),
+		);
+	} else if let Some(sub_matches) =
+		matches.subcommand_matches("state").and_then(|m| m.subcommand_matches("diff"))
+	{
+		let snapshot = |name: &str| {
+			sub_matches
+				.value_of(name)
+				.and_then(|id| id.parse::<u128>().ok())
+				.unwrap_or_else(|| {
+					panic!("{} must be the id of a state snapshot, as in its file name", name)
+				})
+		};
+		setup::diff_state_snapshots(
+			enclave.as_ref(),
+			&extract_shard(sub_matches.value_of("shard"), enclave.as_ref()),
+			snapshot("from"),
+			snapshot("to"),
+		);
 	} else if let Some(sub_matches) = matches.subcommand_matches("init-shard") {
 		setup::init_shard(
 			enclave.as_ref(),
@@ -278,10 +403,13 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	enclave: Arc<E>,
 	sidechain_storage: Arc<D>,
 	integritee_rpc_api: ParentchainApi,
+	integritee_api_factory: Arc<NodeApiFactory>,
 	tokio_handle_getter: Arc<T>,
 	initialization_handler: Arc<InitializationHandler>,
 	quoting_enclave_target_info: Option<sgx_target_info_t>,
 	quote_size: Option<u32>,
+	extrinsic_queue: Arc<ExtrinsicQueue>,
+	extrinsic_replay: WorkerOnChainOCall<NodeApiFactory>,
 ) where
 	T: GetTokioHandle,
 	E: EnclaveBase
@@ -323,6 +451,10 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	println!("  EVM is disabled");
 
 	info!("starting worker on shard {}", shard.encode().to_base58());
+
+	// From now on, the extrinsics of the enclave are queued and replayed from another thread.
+	extrinsic_queue.open(config.data_dir()).unwrap();
+	start_outbound_extrinsic_replay_thread(extrinsic_replay, extrinsic_queue);
 	// ------------------------------------------------------------------------
 	// check for required files
 	if !skip_ra {
@@ -334,11 +466,49 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	let mrenclave = enclave.get_fingerprint().unwrap();
 	println!("MRENCLAVE={}", mrenclave.0.to_base58());
 	println!("MRENCLAVE in hex {:?}", hex::encode(mrenclave));
+	if let Some(max_bytes) = run_config.payload_quarantine_size() {
+		enclave
+			.enable_payload_quarantine(max_bytes)
+			.expect("Could not enable the payload quarantine");
+	}
+	if !run_config.maintenance_windows().is_empty() {
+		enclave
+			.set_maintenance_windows(shard, run_config.maintenance_windows())
+			.expect("Could not set the maintenance windows");
+	}
+	if let Some(policy) = run_config.load_shedding_policy() {
+		enclave
+			.set_load_shedding_policy(policy)
+			.expect("Could not set the load shedding policy");
+	}
+	if let Some(threshold) = run_config.stale_shard_threshold() {
+		enclave
+			.set_stale_shard_threshold(threshold)
+			.expect("Could not set the stale shard threshold");
+	}
+	enclave
+		.set_panic_policy(run_config.panic_policy())
+		.expect("Could not set the panic policy");
+	if !run_config.never_persist_calls().is_empty() {
+		enclave
+			.set_never_persist_calls(run_config.never_persist_calls())
+			.expect("Could not set the never persisted calls");
+	}
 
 	// ------------------------------------------------------------------------
 	// let new workers call us for key provisioning
 	println!("MU-RA server listening on {}", config.mu_ra_url());
-	let is_development_mode = run_config.dev();
+	let maybe_funding_signer = run_config.funding_signer_url().map(|url| {
+		let funding_account = run_config
+			.funding_account()
+			.map(|account| {
+				AccountId32::from_ss58check(account)
+					.expect("funding account must be a valid ss58 id")
+			})
+			.expect("a funding signer url requires a funding account");
+		RemoteSigner::new(url, funding_account).expect("funding signer url must be valid")
+	});
+	let funding_source = FundingSource::new(run_config.dev(), maybe_funding_signer);
 	let ra_url = config.mu_ra_url();
 	let enclave_api_key_prov = enclave.clone();
 	thread::spawn(move || {
@@ -356,25 +526,40 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	let tokio_handle = tokio_handle_getter.get_handle();
 
 	// ------------------------------------------------------------------------
-	// Get the public key of our TEE.
-	let tee_accountid = enclave_account(enclave.as_ref());
-	println!("Enclave account {:} ", &tee_accountid.to_ss58check());
-
-	// ------------------------------------------------------------------------
-	// Start `is_initialized` server.
+	// Start `is_initialized` server, which also reports the progress of the startup.
+	let startup_progress = Arc::new(StartupProgress::default());
+	let balance_monitor = Arc::new(BalanceMonitor::new(run_config.min_funds_runway()));
 	let untrusted_http_server_port = config
 		.try_parse_untrusted_http_server_port()
 		.expect("untrusted http server port to be a valid port number");
 	let initialization_handler_clone = initialization_handler.clone();
+	let startup_progress_clone = startup_progress.clone();
+	let balance_monitor_clone = balance_monitor.clone();
 	tokio_handle.spawn(async move {
-		if let Err(e) =
-			start_is_initialized_server(initialization_handler_clone, untrusted_http_server_port)
-				.await
+		if let Err(e) = start_is_initialized_server(
+			initialization_handler_clone,
+			startup_progress_clone,
+			balance_monitor_clone,
+			untrusted_http_server_port,
+		)
+		.await
 		{
 			error!("Unexpected error in `is_initialized` server: {:?}", e);
 		}
 	});
 
+	// ------------------------------------------------------------------------
+	// Get the public key of our TEE.
+	startup_progress.start(StartupStage::Keys);
+	let tee_accountid = enclave_account(enclave.as_ref());
+	println!("Enclave account {:} ", &tee_accountid.to_ss58check());
+	startup_progress.complete(StartupStage::Keys);
+
+	start_balance_monitoring_thread(
+		balance_monitor.clone(),
+		EnclaveAccountInfoProvider::new(integritee_rpc_api.clone(), tee_accountid.clone()),
+	);
+
 	// ------------------------------------------------------------------------
 	// Start prometheus metrics server.
 	if config.enable_metrics_server() {
@@ -393,37 +578,6 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		});
 	}
 
-	// ------------------------------------------------------------------------
-	// Start trusted worker rpc server
-	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain
-		|| WorkerModeProvider::worker_mode() == WorkerMode::OffChainWorker
-	{
-		let direct_invocation_server_addr = config.trusted_worker_url_internal();
-		let enclave_for_direct_invocation = enclave.clone();
-		thread::spawn(move || {
-			println!(
-				"[+] Trusted RPC direct invocation server listening on {}",
-				direct_invocation_server_addr
-			);
-			enclave_for_direct_invocation
-				.init_direct_invocation_server(direct_invocation_server_addr)
-				.unwrap();
-			println!("[+] RPC direct invocation server shut down");
-		});
-	}
-
-	// ------------------------------------------------------------------------
-	// Start untrusted worker rpc server.
-	// i.e move sidechain block importing to trusted worker.
-	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain {
-		sidechain_start_untrusted_rpc_server(
-			&config,
-			enclave.clone(),
-			sidechain_storage.clone(),
-			&tokio_handle,
-		);
-	}
-
 	// ------------------------------------------------------------------------
 	// Init parentchain specific stuff. Needed early for parentchain communication.
 	let (integritee_parentchain_handler, integritee_last_synced_header_at_last_run) =
@@ -436,13 +590,7 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		);
 
 	#[cfg(feature = "dcap")]
-	register_collateral(
-		&integritee_rpc_api,
-		&*enclave,
-		&tee_accountid,
-		is_development_mode,
-		skip_ra,
-	);
+	register_collateral(&integritee_rpc_api, &*enclave, &tee_accountid, &funding_source, skip_ra);
 
 	let trusted_url = config.trusted_worker_url_external();
 
@@ -451,9 +599,10 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		integritee_rpc_api.clone(),
 		enclave.clone(),
 		tee_accountid.clone(),
-		is_development_mode,
+		funding_source.clone(),
 		trusted_url.clone(),
 		run_config.marblerun_base_url().to_string(),
+		balance_monitor.clone(),
 	);
 
 	// ------------------------------------------------------------------------
@@ -476,98 +625,146 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	#[cfg(feature = "dcap")]
 	let register_xt = move || enclave2.generate_dcap_ra_extrinsic(&trusted_url, skip_ra).unwrap();
 
+	let maybe_shadowed_worker_url = run_config.shadow_of().map(|url| url.to_string());
+	let maybe_register_enclave_xt_header = if maybe_shadowed_worker_url.is_some() {
+		println!("[!] Running in shadow mode, the enclave is not registered.");
+		startup_progress.skip(StartupStage::Attestation);
+		startup_progress.skip(StartupStage::Registration);
+		None
+	} else {
+		startup_progress.start(StartupStage::Attestation);
+		let register_enclave_xt = register_xt();
+		startup_progress.complete(StartupStage::Attestation);
+
+		startup_progress.start(StartupStage::Registration);
+		println!("[+] Send register enclave extrinsic");
+		let register_enclave_block_hash = send_integritee_extrinsic(
+			register_enclave_xt,
+			&integritee_rpc_api,
+			&tee_accountid,
+			&funding_source,
+		)
+		.expect("enclave RA registration must be successful to continue");
+
+		let api_register_enclave_xt_header = integritee_rpc_api
+			.get_header(Some(register_enclave_block_hash))
+			.unwrap()
+			.unwrap();
+
+		// TODO: #1451: Fix api-client type hacks
+		let register_enclave_xt_header =
+			Header::decode(&mut api_register_enclave_xt_header.encode().as_slice())
+				.expect("Can decode previously encoded header; qed");
+
+		println!(
+			"[+] Enclave registered at block number: {:?}, hash: {:?}",
+			register_enclave_xt_header.number(),
+			register_enclave_xt_header.hash()
+		);
+		// double-check
+		let my_enclave = integritee_rpc_api
+			.enclave(&tee_accountid, None)
+			.unwrap()
+			.expect("our enclave should be registered at this point");
+		trace!("verified that our enclave is registered: {:?}", my_enclave);
+		startup_progress.complete(StartupStage::Registration);
+		Some(register_enclave_xt_header)
+	};
+
 	// clones because of the move
+	#[cfg(feature = "teeracle")]
 	let node_api2 = integritee_rpc_api.clone();
+	#[cfg(feature = "teeracle")]
 	let tee_accountid_clone = tee_accountid.clone();
+	#[cfg(feature = "teeracle")]
+	let funding_source_clone = funding_source.clone();
+	#[cfg(feature = "teeracle")]
 	let send_register_xt = move || {
 		println!("[+] Send register enclave extrinsic");
 		send_integritee_extrinsic(
 			register_xt(),
 			&node_api2,
 			&tee_accountid_clone,
-			is_development_mode,
+			&funding_source_clone,
 		)
 	};
 
-	let register_enclave_block_hash =
-		send_register_xt().expect("enclave RA registration must be successful to continue");
-
-	let api_register_enclave_xt_header = integritee_rpc_api
-		.get_header(Some(register_enclave_block_hash))
-		.unwrap()
-		.unwrap();
-
-	// TODO: #1451: Fix api-client type hacks
-	let register_enclave_xt_header =
-		Header::decode(&mut api_register_enclave_xt_header.encode().as_slice())
-			.expect("Can decode previously encoded header; qed");
-
-	println!(
-		"[+] Enclave registered at block number: {:?}, hash: {:?}",
-		register_enclave_xt_header.number(),
-		register_enclave_xt_header.hash()
-	);
-	// double-check
-	let my_enclave = integritee_rpc_api
-		.enclave(&tee_accountid, None)
-		.unwrap()
-		.expect("our enclave should be registered at this point");
-	trace!("verified that our enclave is registered: {:?}", my_enclave);
+	startup_progress.start(StartupStage::ShardLoad);
 
 	let (we_are_primary_validateer, re_init_parentchain_needed) =
-		match integritee_rpc_api.primary_worker_for_shard(shard, None).unwrap() {
-			Some(primary_enclave) => match primary_enclave.instance_signer() {
-				AnySigner::Known(MultiSigner::Ed25519(primary)) =>
-					if primary.encode() == tee_accountid.encode() {
-						println!("We are primary worker on this shard and we have been previously running.");
-						(true, false)
-					} else {
-						println!(
-							"We are NOT primary worker. The primary worker is {}.",
-							primary.to_ss58check(),
-						);
-						info!("The primary worker enclave is {:?}", primary_enclave);
-						if enclave
-							.get_shard_creation_info(shard)
-							.unwrap()
-							.for_parentchain(ParentchainId::Integritee)
-							.is_none()
-						{
-							//obtain provisioning from last active worker as this hasn't been done before
-							info!("my state doesn't know the creation header of the shard. will request provisioning");
-							sync_state::sync_state::<_, _, WorkerModeProvider>(
-								&integritee_rpc_api,
-								&shard,
-								enclave.as_ref(),
-								skip_ra,
+		if let Some(shadowed_worker_url) = &maybe_shadowed_worker_url {
+			println!("We are shadowing the worker at {}.", shadowed_worker_url);
+			if enclave
+				.get_shard_creation_info(shard)
+				.unwrap()
+				.for_parentchain(ParentchainId::Integritee)
+				.is_none()
+			{
+				sync_state::sync_state_from_worker(
+					shadowed_worker_url,
+					shard,
+					enclave.as_ref(),
+					skip_ra,
+				);
+			}
+			(false, true)
+		} else {
+			match integritee_rpc_api.primary_worker_for_shard(shard, None).unwrap() {
+				Some(primary_enclave) => match primary_enclave.instance_signer() {
+					AnySigner::Known(MultiSigner::Ed25519(primary)) =>
+						if primary.encode() == tee_accountid.encode() {
+							println!("We are primary worker on this shard and we have been previously running.");
+							(true, false)
+						} else {
+							println!(
+								"We are NOT primary worker. The primary worker is {}.",
+								primary.to_ss58check(),
 							);
-						}
-						(false, true)
+							info!("The primary worker enclave is {:?}", primary_enclave);
+							if enclave
+								.get_shard_creation_info(shard)
+								.unwrap()
+								.for_parentchain(ParentchainId::Integritee)
+								.is_none()
+							{
+								//obtain provisioning from last active worker as this hasn't been done before
+								info!("my state doesn't know the creation header of the shard. will request provisioning");
+								sync_state::sync_state::<_, _, WorkerModeProvider>(
+									&integritee_rpc_api,
+									&shard,
+									enclave.as_ref(),
+									skip_ra,
+								);
+							}
+							(false, true)
+						},
+					_ => {
+						panic!(
+							"the primary worker for shard {:?} has unknown signer type: {:?}",
+							shard, primary_enclave
+						);
 					},
-				_ => {
-					panic!(
-						"the primary worker for shard {:?} has unknown signer type: {:?}",
-						shard, primary_enclave
-					);
 				},
-			},
-			None => {
-				println!("We are the primary worker on this shard and the shard is untouched. Will initialize it");
-				enclave.init_shard(shard.encode()).unwrap();
-				if WorkerModeProvider::worker_mode() != WorkerMode::Teeracle {
-					enclave
-						.init_shard_creation_parentchain_header(
-							shard,
-							&ParentchainId::Integritee,
-							&register_enclave_xt_header,
-						)
-						.unwrap();
-					debug!("shard config should be initialized on integritee network now");
-					(true, true)
-				} else {
-					(true, false)
-				}
-			},
+				None => {
+					println!("We are the primary worker on this shard and the shard is untouched. Will initialize it");
+					enclave.init_shard(shard.encode()).unwrap();
+					if WorkerModeProvider::worker_mode() != WorkerMode::Teeracle {
+						enclave
+							.init_shard_creation_parentchain_header(
+								shard,
+								&ParentchainId::Integritee,
+								maybe_register_enclave_xt_header
+									.as_ref()
+									.expect("enclave is registered unless shadowing; qed"),
+							)
+							.unwrap();
+						debug!("shard config should be initialized on integritee network now");
+						(true, true)
+					} else {
+						(true, false)
+					}
+				},
+			}
 		};
 	debug!("getting shard creation: {:?}", enclave.get_shard_creation_info(shard));
 	initialization_handler.registered_on_parentchain();
@@ -620,6 +817,7 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 				integritee_parentchain_handler,
 				last_synced_header,
 				*shard,
+				integritee_api_factory.clone(),
 			);
 
 			info!("skipping shard vault check because not yet supported for offchain worker");
@@ -632,7 +830,9 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 				integritee_parentchain_handler
 					.await_sync_and_import_parentchain_until_at_least(
 						&integritee_last_synced_header_at_last_run,
-						&register_enclave_xt_header,
+						maybe_register_enclave_xt_header
+							.as_ref()
+							.expect("the primary worker is registered; qed"),
 						*shard,
 					)
 					.unwrap()
@@ -644,6 +844,7 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 				integritee_parentchain_handler,
 				last_synced_header,
 				*shard,
+				integritee_api_factory.clone(),
 			);
 
 			spawn_worker_for_shard_polling(
@@ -654,27 +855,27 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		},
 	}
 
-	let maybe_target_a_rpc_api = if let Some(url) = config.target_a_parentchain_rpc_endpoint() {
+	let maybe_target_a_rpc_api = if let Some(urls) = config.target_a_parentchain_rpc_endpoints() {
 		Some(init_target_parentchain(
 			&enclave,
 			&tee_accountid,
-			url,
+			urls,
 			shard,
 			ParentchainId::TargetA,
-			is_development_mode,
+			&funding_source,
 		))
 	} else {
 		None
 	};
 
-	let maybe_target_b_rpc_api = if let Some(url) = config.target_b_parentchain_rpc_endpoint() {
+	let maybe_target_b_rpc_api = if let Some(urls) = config.target_b_parentchain_rpc_endpoints() {
 		Some(init_target_parentchain(
 			&enclave,
 			&tee_accountid,
-			url,
+			urls,
 			shard,
 			ParentchainId::TargetB,
-			is_development_mode,
+			&funding_source,
 		))
 	} else {
 		None
@@ -690,10 +891,78 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		we_are_primary_validateer,
 	);
 
+	startup_progress.complete(StartupStage::ShardLoad);
+
 	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain {
+		startup_progress.start(StartupStage::PoolRestore);
 		println!("[Integritee:SCV] starting block production");
 		let last_synced_header =
-			sidechain_init_block_production(enclave.clone(), sidechain_storage).unwrap();
+			sidechain_init_block_production(enclave.clone(), sidechain_storage.clone()).unwrap();
+		startup_progress.complete(StartupStage::PoolRestore);
+
+		if let Some(shadowed_worker_url) = maybe_shadowed_worker_url {
+			shadow_run::start_state_hash_comparison(
+				shadowed_worker_url,
+				config.trusted_worker_url_external(),
+			)
+			.unwrap();
+		}
+	} else {
+		startup_progress.skip(StartupStage::PoolRestore);
+	}
+
+	// ------------------------------------------------------------------------
+	// Start trusted worker rpc server
+	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain
+		|| WorkerModeProvider::worker_mode() == WorkerMode::OffChainWorker
+	{
+		startup_progress.start(StartupStage::RpcOpen);
+		let direct_invocation_server_addr = config.trusted_worker_url_internal();
+		let enclave_for_direct_invocation = enclave.clone();
+		thread::spawn(move || {
+			println!(
+				"[+] Trusted RPC direct invocation server listening on {}",
+				direct_invocation_server_addr
+			);
+			enclave_for_direct_invocation
+				.init_direct_invocation_server(direct_invocation_server_addr)
+				.unwrap();
+			println!("[+] RPC direct invocation server shut down");
+		});
+
+		if !run_config.shard_routes().is_empty() {
+			let shard_router = ShardRouter::new(
+				integritee_rpc_api.clone(),
+				*shard,
+				run_config.shard_routes().to_vec(),
+			);
+			start_shard_routes_refresh(shard_router, enclave.clone());
+		}
+	}
+
+	// ------------------------------------------------------------------------
+	// Start untrusted worker rpc server.
+	// i.e move sidechain block importing to trusted worker.
+	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain {
+		sidechain_start_untrusted_rpc_server(
+			&config,
+			enclave.clone(),
+			sidechain_storage.clone(),
+			&tokio_handle,
+		);
+	}
+
+	match WorkerModeProvider::worker_mode() {
+		WorkerMode::Sidechain | WorkerMode::OffChainWorker =>
+			startup_progress.complete(StartupStage::RpcOpen),
+		WorkerMode::Teeracle => startup_progress.skip(StartupStage::RpcOpen),
+	}
+
+	if let Some(canary_shard) = run_config.canary_shard() {
+		let webhook = run_config
+			.canary_webhook()
+			.map(|url| CanaryWebhook::new(url).expect("canary webhook url has been validated"));
+		start_canary_thread(enclave.clone(), *canary_shard, webhook);
 	}
 
 	ita_parentchain_interface::event_subscriber::subscribe_to_parentchain_events(
@@ -754,28 +1023,25 @@ fn init_provided_shard_vault<E: EnclaveBase>(
 fn init_target_parentchain<E>(
 	enclave: &Arc<E>,
 	tee_account_id: &AccountId32,
-	url: String,
+	urls: Vec<String>,
 	shard: &ShardIdentifier,
 	parentchain_id: ParentchainId,
-	is_development_mode: bool,
+	funding_source: &FundingSource,
 ) -> ParentchainApi
 where
 	E: EnclaveBase + Sidechain,
 {
-	println!("Initializing parentchain {:?} with url: {}", parentchain_id, url);
-	let node_api = NodeApiFactory::new(url, AccountKeyring::Alice.pair())
+	println!("Initializing parentchain {:?} with urls: {:?}", parentchain_id, urls);
+	let node_api_factory =
+		Arc::new(NodeApiFactory::new_with_fallbacks(urls, AccountKeyring::Alice.pair()));
+	let node_api = node_api_factory
 		.create_api()
 		.unwrap_or_else(|_| panic!("[{:?}] Failed to create parentchain node API", parentchain_id));
 
-	setup_reasonable_account_funding(
-		&node_api,
-		tee_account_id,
-		parentchain_id,
-		is_development_mode,
-	)
-	.unwrap_or_else(|_| {
-		panic!("[{:?}] Could not fund parentchain enclave account", parentchain_id)
-	});
+	setup_reasonable_account_funding(&node_api, tee_account_id, parentchain_id, funding_source)
+		.unwrap_or_else(|_| {
+			panic!("[{:?}] Could not fund parentchain enclave account", parentchain_id)
+		});
 
 	// we attempt to set shard creation for this parentchain in case it hasn't been done before
 	let api_head = node_api.get_header(None).unwrap().unwrap();
@@ -803,6 +1069,7 @@ where
 			parentchain_handler.clone(),
 			last_synched_header,
 			*shard,
+			node_api_factory,
 		)
 	}
 
@@ -902,9 +1169,10 @@ fn fetch_marblerun_events_every_hour<E>(
 	api: ParentchainApi,
 	enclave: Arc<E>,
 	accountid: AccountId32,
-	is_development_mode: bool,
+	funding_source: FundingSource,
 	url: String,
 	marblerun_base_url: String,
+	balance_monitor: Arc<BalanceMonitor>,
 ) where
 	E: RemoteAttestation + Clone + Sync + Send + 'static,
 {
@@ -912,12 +1180,18 @@ fn fetch_marblerun_events_every_hour<E>(
 	let handle = thread::spawn(move || {
 		const POLL_INTERVAL_5_MINUTES_IN_SECS: u64 = 5 * 60;
 		loop {
+			// The quote registrations are the first to go when the funds run low.
+			if balance_monitor.pauses_non_critical_extrinsic() {
+				warn!("Enclave account runs low on funds, not registering marblerun quotes");
+				thread::sleep(Duration::from_secs(POLL_INTERVAL_5_MINUTES_IN_SECS));
+				continue
+			}
 			info!("Polling marblerun events for quotes to register");
 			register_quotes_from_marblerun(
 				&api,
 				enclave.clone(),
 				&accountid,
-				is_development_mode,
+				&funding_source,
 				url.clone(),
 				&marblerun_base_url,
 			);
@@ -933,7 +1207,7 @@ fn register_quotes_from_marblerun(
 	api: &ParentchainApi,
 	enclave: Arc<dyn RemoteAttestation>,
 	accountid: &AccountId32,
-	is_development_mode: bool,
+	funding_source: &FundingSource,
 	url: String,
 	marblerun_base_url: &str,
 ) {
@@ -949,7 +1223,7 @@ fn register_quotes_from_marblerun(
 	for quote in quotes {
 		match enclave.generate_dcap_ra_extrinsic_from_quote(url.clone(), &quote) {
 			Ok(xt) => {
-				send_integritee_extrinsic(xt, api, accountid, is_development_mode);
+				send_integritee_extrinsic(xt, api, accountid, funding_source);
 			},
 			Err(e) => {
 				error!("Extracting information from quote failed: {}", e)
@@ -962,7 +1236,7 @@ fn register_collateral(
 	api: &ParentchainApi,
 	enclave: &dyn RemoteAttestation,
 	accountid: &AccountId32,
-	is_development_mode: bool,
+	funding_source: &FundingSource,
 	skip_ra: bool,
 ) {
 	//TODO generate_dcap_ra_quote() does not really need skip_ra, rethink how many layers skip_ra should be passed along
@@ -971,11 +1245,11 @@ fn register_collateral(
 		let (fmspc, _tcb_info) = extract_tcb_info_from_raw_dcap_quote(&dcap_quote).unwrap();
 		println!("[>] DCAP setup: register QE collateral");
 		let uxt = enclave.generate_register_quoting_enclave_extrinsic(fmspc).unwrap();
-		send_integritee_extrinsic(uxt, api, accountid, is_development_mode);
+		send_integritee_extrinsic(uxt, api, accountid, funding_source);
 
 		println!("[>] DCAP setup: register TCB info");
 		let uxt = enclave.generate_register_tcb_info_extrinsic(fmspc).unwrap();
-		send_integritee_extrinsic(uxt, api, accountid, is_development_mode);
+		send_integritee_extrinsic(uxt, api, accountid, funding_source);
 	}
 }
 
@@ -983,7 +1257,7 @@ fn send_integritee_extrinsic(
 	extrinsic: Vec<u8>,
 	api: &ParentchainApi,
 	fee_payer: &AccountId32,
-	is_development_mode: bool,
+	funding_source: &FundingSource,
 ) -> ServiceResult<Hash> {
 	let fee = crate::account_funding::estimate_fee(api, extrinsic.clone())?;
 	let ed = api.get_existential_deposit()?;
@@ -998,12 +1272,7 @@ fn send_integritee_extrinsic(
 	);
 
 	if missing_funds > 0 {
-		setup_reasonable_account_funding(
-			api,
-			fee_payer,
-			ParentchainId::Integritee,
-			is_development_mode,
-		)?
+		setup_reasonable_account_funding(api, fee_payer, ParentchainId::Integritee, funding_source)?
 	}
 
 	match api.submit_and_watch_opaque_extrinsic_until(&extrinsic.into(), XtStatus::Finalized) {
@@ -1024,30 +1293,86 @@ fn start_parentchain_header_subscription_thread<E: EnclaveBase + Sidechain>(
 	parentchain_handler: Arc<ParentchainHandler<ParentchainApi, E>>,
 	last_synced_header: Header,
 	shard: ShardIdentifier,
+	node_api_factory: Arc<NodeApiFactory>,
 ) {
+	/// Delay before re-establishing a terminated subscription.
+	const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
 	let parentchain_id = *parentchain_handler.parentchain_id();
 	thread::Builder::new()
 		.name(format!("{:?}_parentchain_sync_loop", parentchain_id))
 		.spawn(move || {
-			if let Err(e) =
-				subscribe_to_parentchain_new_headers(parentchain_handler, last_synced_header, shard)
+			let mut parentchain_handler = parentchain_handler;
+			let mut last_synced_header = last_synced_header;
+			let mut failback = Failback::default();
+			loop {
+				if let Err(e) = subscribe_to_parentchain_new_headers(
+					&parentchain_handler,
+					&mut last_synced_header,
+					shard,
+					&node_api_factory,
+					&mut failback,
+				) {
+					error!(
+						"[{:?}] parentchain block syncing terminated with a failure: {:?}",
+						parentchain_id, e
+					);
+				}
+				thread::sleep(RESUBSCRIBE_DELAY);
+				// The factory fails over to another node if the current one is not available.
+				match node_api_factory.create_api_with_failback(&mut failback) {
+					Ok(api) =>
+						parentchain_handler =
+							Arc::new(parentchain_handler.with_parentchain_api(api)),
+					Err(e) => error!(
+						"[{:?}] could not reconnect to the parentchain, will retry: {:?}",
+						parentchain_id, e
+					),
+				}
+				println!("[!] [{:?}] re-establishing parentchain block syncing", parentchain_id);
+			}
+		})
+		.unwrap();
+}
+
+/// Periodically submits the outbound extrinsics that could not be sent to the parentchains, e.g.
+/// because of an outage or a restart of the worker.
+/// Newly queued extrinsics are submitted right away.
+fn start_outbound_extrinsic_replay_thread(
+	on_chain_ocall: WorkerOnChainOCall<NodeApiFactory>,
+	extrinsic_queue: Arc<ExtrinsicQueue>,
+) {
+	const REPLAY_INTERVAL: Duration = Duration::from_secs(30);
+
+	thread::Builder::new()
+		.name("outbound_extrinsic_replay".to_owned())
+		.spawn(move || loop {
+			for parentchain_id in
+				[ParentchainId::Integritee, ParentchainId::TargetA, ParentchainId::TargetB]
 			{
-				error!(
-					"[{:?}] parentchain block syncing terminated with a failure: {:?}",
-					parentchain_id, e
-				);
+				if let Err(e) = on_chain_ocall.submit_queued(parentchain_id) {
+					warn!("Could not replay queued extrinsics to {:?}: {:?}", parentchain_id, e);
+				}
+			}
+			if let Err(e) = extrinsic_queue.wait_for_push(REPLAY_INTERVAL) {
+				error!("Outbound extrinsic queue failed: {:?}", e);
+				thread::sleep(REPLAY_INTERVAL);
 			}
-			println!("[!] [{:?}] parentchain block syncing has terminated", parentchain_id);
 		})
 		.unwrap();
 }
 
 /// Subscribe to the node API finalized heads stream and trigger a parent chain sync
 /// upon receiving a new header.
+///
+/// Returns once the primary node has recovered while syncing from a fallback node, so the
+/// caller can reconnect to the primary.
 fn subscribe_to_parentchain_new_headers<E: EnclaveBase + Sidechain>(
-	parentchain_handler: Arc<ParentchainHandler<ParentchainApi, E>>,
-	mut last_synced_header: Header,
+	parentchain_handler: &ParentchainHandler<ParentchainApi, E>,
+	last_synced_header: &mut Header,
 	shard: ShardIdentifier,
+	node_api_factory: &NodeApiFactory,
+	failback: &mut Failback,
 ) -> Result<(), Error> {
 	// TODO: this should be implemented by parentchain_handler directly, and not via
 	// exposed parentchain_api
@@ -1067,14 +1392,24 @@ fn subscribe_to_parentchain_new_headers<E: EnclaveBase + Sidechain>(
 			parentchain_id, new_header.number
 		);
 
-		last_synced_header = parentchain_handler.sync_parentchain_until_latest_finalized(
-			last_synced_header,
+		*last_synced_header = parentchain_handler.sync_parentchain_until_latest_finalized(
+			last_synced_header.clone(),
 			shard,
 			false,
 		)?;
+
+		if node_api_factory.should_fail_back(failback) {
+			info!("[{:?}] switching back to the primary parentchain node", parentchain_id);
+			return Ok(())
+		}
 	}
 }
 
+fn is_read_only_command(matches: &ArgMatches) -> bool {
+	READ_ONLY_SUBCOMMANDS.iter().any(|name| matches.is_present(name))
+		|| matches.subcommand_matches("state").map_or(false, |m| m.is_present("diff"))
+}
+
 /// Get the public signing key of the TEE.
 fn enclave_account<E: EnclaveBase>(enclave_api: &E) -> AccountId32 {
 	let tee_public = enclave_api.get_ecc_signing_pubkey().unwrap();
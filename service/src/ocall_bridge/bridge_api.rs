@@ -80,6 +80,28 @@ impl Bridge {
 			.get_ipfs_api()
 	}
 
+	pub fn get_command_api() -> Arc<dyn WorkerCommandBridge> {
+		COMPONENT_FACTORY
+			.read()
+			.as_ref()
+			.expect("Component factory has not been set. Use `initialize()`")
+			.get_command_api()
+	}
+
+	/// Returns `None` until the bridge is initialized, the enclave already writes files while it
+	/// is being initialized.
+	pub fn get_disk_space_api() -> Option<Arc<dyn DiskSpaceBridge>> {
+		COMPONENT_FACTORY.read().as_ref().map(|factory| factory.get_disk_space_api())
+	}
+
+	pub fn get_state_backup_api() -> Arc<dyn StateBackupBridge> {
+		COMPONENT_FACTORY
+			.read()
+			.as_ref()
+			.expect("Component factory has not been set. Use `initialize()`")
+			.get_state_backup_api()
+	}
+
 	pub fn get_metrics_api() -> Arc<dyn MetricsBridge> {
 		COMPONENT_FACTORY
 			.read()
@@ -112,6 +134,15 @@ pub trait GetOCallBridgeComponents {
 
 	/// Metrics OCall API.
 	fn get_metrics_api(&self) -> Arc<dyn MetricsBridge>;
+
+	/// Signed worker command OCall API.
+	fn get_command_api(&self) -> Arc<dyn WorkerCommandBridge>;
+
+	/// Disk space OCall API.
+	fn get_disk_space_api(&self) -> Arc<dyn DiskSpaceBridge>;
+
+	/// State backup OCall API.
+	fn get_state_backup_api(&self) -> Arc<dyn StateBackupBridge>;
 }
 
 /// OCall bridge errors
@@ -135,6 +166,12 @@ pub enum OCallBridgeError {
 	SendExtrinsicsToParentchain(String),
 	#[error("IPFS Error: {0}")]
 	IpfsError(String),
+	#[error("Rejected worker command: {0}")]
+	RejectedWorkerCommand(String),
+	#[error("Disk space check failed: {0}")]
+	DiskSpace(String),
+	#[error("Accessing the state backup failed: {0}")]
+	StateBackup(String),
 	#[error("DirectInvocation Error: {0}")]
 	DirectInvocationError(String),
 	#[error(transparent)]
@@ -244,11 +281,41 @@ pub type Cid = [u8; 46];
 /// Trait for all the OCalls related to IPFS
 #[cfg_attr(test, automock)]
 pub trait IpfsBridge {
-	fn write_to_ipfs(&self, data: &'static [u8]) -> OCallBridgeResult<Cid>;
+	fn write_to_ipfs(&self, data: Vec<u8>) -> OCallBridgeResult<Cid>;
 
 	fn read_from_ipfs(&self, cid: Cid) -> OCallBridgeResult<()>;
 }
 
+/// Trait for the signed commands the enclave asks the worker service to execute.
+#[cfg_attr(test, automock)]
+pub trait WorkerCommandBridge {
+	/// Verifies and executes an encoded signed command, returns the encoded response.
+	fn execute_command(&self, signed_command_encoded: Vec<u8>) -> OCallBridgeResult<Vec<u8>>;
+}
+
+/// Trait for checking the free disk space before the enclave writes a file.
+#[cfg_attr(test, automock)]
+pub trait DiskSpaceBridge {
+	/// Returns false if writing `bytes_to_write` to `path` would leave less than the configured
+	/// free disk space.
+	fn has_free_disk_space(&self, path: Vec<u8>, bytes_to_write: u64) -> OCallBridgeResult<bool>;
+}
+
+/// Trait for the state backup files the enclave streams out and back in chunks.
+#[cfg_attr(test, automock)]
+pub trait StateBackupBridge {
+	/// Appends the chunk to the state backup file at `path`.
+	fn write_state_backup_chunk(&self, path: Vec<u8>, chunk: Vec<u8>) -> OCallBridgeResult<()>;
+
+	/// Reads at most `max_size` bytes of the state backup file at `path`, from `offset` on.
+	fn read_state_backup_chunk(
+		&self,
+		path: Vec<u8>,
+		offset: u64,
+		max_size: usize,
+	) -> OCallBridgeResult<Vec<u8>>;
+}
+
 /// Trait for the direct invocation OCalls
 #[cfg_attr(test, automock)]
 pub trait DirectInvocationBridge {
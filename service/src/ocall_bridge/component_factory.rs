@@ -17,16 +17,22 @@
 */
 
 use crate::{
+	disk_space::DiskSpaceMonitor,
+	extrinsic_queue::ExtrinsicQueue,
 	globals::tokio_handle::GetTokioHandle,
 	ocall_bridge::{
 		bridge_api::{
-			GetOCallBridgeComponents, IpfsBridge, MetricsBridge, RemoteAttestationBridge,
-			SidechainBridge, WorkerOnChainBridge,
+			DiskSpaceBridge, GetOCallBridgeComponents, IpfsBridge, MetricsBridge,
+			RemoteAttestationBridge, SidechainBridge, StateBackupBridge, WorkerCommandBridge,
+			WorkerOnChainBridge,
 		},
+		disk_space_ocall::DiskSpaceOCall,
 		ipfs_ocall::IpfsOCall,
 		metrics_ocall::MetricsOCall,
 		remote_attestation_ocall::RemoteAttestationOCall,
 		sidechain_ocall::SidechainOCall,
+		state_backup_ocall::StateBackupOCall,
+		worker_command_ocall::{WorkerCommandOCall, WorkerCommandVerifier},
 		worker_on_chain_ocall::WorkerOnChainOCall,
 	},
 	prometheus_metrics::ReceiveEnclaveMetrics,
@@ -56,6 +62,11 @@ pub struct OCallBridgeComponentFactory<
 	integritee_rpc_api_factory: Arc<NodeApi>,
 	target_a_parentchain_rpc_api_factory: Option<Arc<NodeApi>>,
 	target_b_parentchain_rpc_api_factory: Option<Arc<NodeApi>>,
+	extrinsic_queue: Arc<ExtrinsicQueue>,
+	command_verifier: Arc<WorkerCommandVerifier>,
+	/// Drop the enclave commands with external effects, see [`crate::shadow_run`].
+	shadow: bool,
+	disk_space_monitor: Arc<DiskSpaceMonitor>,
 	block_broadcaster: Arc<Broadcaster>,
 	enclave_api: Arc<EnclaveApi>,
 	block_storage: Arc<Storage>,
@@ -91,6 +102,10 @@ impl<
 		integritee_rpc_api_factory: Arc<NodeApi>,
 		target_a_parentchain_rpc_api_factory: Option<Arc<NodeApi>>,
 		target_b_parentchain_rpc_api_factory: Option<Arc<NodeApi>>,
+		extrinsic_queue: Arc<ExtrinsicQueue>,
+		command_verifier: Arc<WorkerCommandVerifier>,
+		shadow: bool,
+		disk_space_monitor: Arc<DiskSpaceMonitor>,
 		block_broadcaster: Arc<Broadcaster>,
 		enclave_api: Arc<EnclaveApi>,
 		block_storage: Arc<Storage>,
@@ -103,6 +118,10 @@ impl<
 			integritee_rpc_api_factory,
 			target_a_parentchain_rpc_api_factory,
 			target_b_parentchain_rpc_api_factory,
+			extrinsic_queue,
+			command_verifier,
+			shadow,
+			disk_space_monitor,
 			block_broadcaster,
 			enclave_api,
 			block_storage,
@@ -151,6 +170,7 @@ impl<
 		Arc::new(SidechainOCall::new(
 			self.block_broadcaster.clone(),
 			self.block_storage.clone(),
+			self.disk_space_monitor.clone(),
 			self.peer_updater.clone(),
 			self.peer_block_fetcher.clone(),
 			self.tokio_handle.clone(),
@@ -162,6 +182,7 @@ impl<
 			self.integritee_rpc_api_factory.clone(),
 			self.target_a_parentchain_rpc_api_factory.clone(),
 			self.target_b_parentchain_rpc_api_factory.clone(),
+			self.extrinsic_queue.clone(),
 		))
 	}
 
@@ -172,4 +193,22 @@ impl<
 	fn get_metrics_api(&self) -> Arc<dyn MetricsBridge> {
 		Arc::new(MetricsOCall::new(self.metrics_receiver.clone()))
 	}
+
+	fn get_command_api(&self) -> Arc<dyn WorkerCommandBridge> {
+		Arc::new(WorkerCommandOCall::new(
+			self.command_verifier.clone(),
+			self.shadow,
+			self.get_oc_api(),
+			self.get_sidechain_api(),
+			self.get_ipfs_api(),
+		))
+	}
+
+	fn get_disk_space_api(&self) -> Arc<dyn DiskSpaceBridge> {
+		Arc::new(DiskSpaceOCall::new(self.disk_space_monitor.clone()))
+	}
+
+	fn get_state_backup_api(&self) -> Arc<dyn StateBackupBridge> {
+		Arc::new(StateBackupOCall::new(self.disk_space_monitor.clone()))
+	}
 }
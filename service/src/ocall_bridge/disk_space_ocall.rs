@@ -0,0 +1,61 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	disk_space::DiskSpaceMonitor,
+	error::Error,
+	ocall_bridge::bridge_api::{DiskSpaceBridge, OCallBridgeError, OCallBridgeResult},
+};
+use log::*;
+use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::Path, sync::Arc};
+
+pub struct DiskSpaceOCall {
+	monitor: Arc<DiskSpaceMonitor>,
+}
+
+impl DiskSpaceOCall {
+	pub fn new(monitor: Arc<DiskSpaceMonitor>) -> Self {
+		DiskSpaceOCall { monitor }
+	}
+}
+
+impl DiskSpaceBridge for DiskSpaceOCall {
+	fn has_free_disk_space(&self, path: Vec<u8>, bytes_to_write: u64) -> OCallBridgeResult<bool> {
+		let path = Path::new(OsStr::from_bytes(&path));
+		match self.monitor.ensure_free_space(path, bytes_to_write) {
+			Ok(()) => Ok(true),
+			Err(e @ Error::InsufficientDiskSpace { .. }) => {
+				error!("Refusing enclave write: {}", e);
+				Ok(false)
+			},
+			Err(e) => Err(OCallBridgeError::DiskSpace(e.to_string())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn write_below_threshold_is_refused() {
+		let disk_space_ocall =
+			DiskSpaceOCall::new(Arc::new(DiskSpaceMonitor::new(std::env::temp_dir(), u64::MAX)));
+
+		assert!(!disk_space_ocall.has_free_disk_space(b"shards/state.bin".to_vec(), 1).unwrap());
+	}
+}
@@ -0,0 +1,63 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::ocall_bridge::bridge_api::{Bridge, DiskSpaceBridge};
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, sync::Arc};
+
+/// # Safety
+///
+/// FFI are always unsafe
+#[no_mangle]
+pub unsafe extern "C" fn ocall_check_disk_space(
+	path_ptr: *const u8,
+	path_size: u32,
+	bytes_to_write: u64,
+	has_free_space: *mut u8,
+) -> sgx_status_t {
+	match Bridge::get_disk_space_api() {
+		Some(disk_space_api) =>
+			check_disk_space(path_ptr, path_size, bytes_to_write, has_free_space, disk_space_api),
+		None => {
+			debug!("OCall bridge is not initialized yet, not checking disk space");
+			*has_free_space = 1;
+			sgx_status_t::SGX_SUCCESS
+		},
+	}
+}
+
+fn check_disk_space(
+	path_ptr: *const u8,
+	path_size: u32,
+	bytes_to_write: u64,
+	has_free_space: *mut u8,
+	disk_space_api: Arc<dyn DiskSpaceBridge>,
+) -> sgx_status_t {
+	let path: Vec<u8> = unsafe { Vec::from(slice::from_raw_parts(path_ptr, path_size as usize)) };
+
+	match disk_space_api.has_free_disk_space(path, bytes_to_write) {
+		Ok(result) => {
+			unsafe { *has_free_space = result as u8 };
+			sgx_status_t::SGX_SUCCESS
+		},
+		Err(e) => {
+			error!("check_disk_space o-call failed: {:?}", e);
+			sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	}
+}
@@ -0,0 +1,68 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::ocall_bridge::bridge_api::{Bridge, WorkerCommandBridge};
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, sync::Arc, vec::Vec};
+
+/// # Safety
+///
+/// FFI are always unsafe
+#[no_mangle]
+pub unsafe extern "C" fn ocall_execute_worker_command(
+	signed_command: *const u8,
+	signed_command_size: u32,
+	response: *mut u8,
+	response_size: u32,
+) -> sgx_status_t {
+	execute_worker_command(
+		signed_command,
+		signed_command_size,
+		response,
+		response_size,
+		Bridge::get_command_api(),
+	)
+}
+
+fn execute_worker_command(
+	signed_command: *const u8,
+	signed_command_size: u32,
+	response: *mut u8,
+	response_size: u32,
+	command_api: Arc<dyn WorkerCommandBridge>,
+) -> sgx_status_t {
+	let signed_command_vec: Vec<u8> =
+		unsafe { Vec::from(slice::from_raw_parts(signed_command, signed_command_size as usize)) };
+
+	match command_api.execute_command(signed_command_vec) {
+		Ok(r) => {
+			let response_slice =
+				unsafe { slice::from_raw_parts_mut(response, response_size as usize) };
+			if let Err(e) = write_slice_and_whitespace_pad(response_slice, r) {
+				error!("Failed to transfer worker command response to o-call buffer: {:?}", e);
+				return sgx_status_t::SGX_ERROR_UNEXPECTED
+			}
+			sgx_status_t::SGX_SUCCESS
+		},
+		Err(e) => {
+			error!("Worker command failed: {:?}", e);
+			sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	}
+}
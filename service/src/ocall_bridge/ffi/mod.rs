@@ -21,6 +21,8 @@
 //! These should just be wrappers that transform the C-API structures and call the
 //! actual implementation of the OCalls (using the traits defined in the bridge_api).
 
+pub mod check_disk_space;
+pub mod execute_worker_command;
 pub mod fetch_sidechain_blocks_from_peer;
 pub mod get_ias_socket;
 pub mod get_quote;
@@ -28,8 +30,8 @@ pub mod get_qve_report_on_quote;
 pub mod get_update_info;
 pub mod init_quote;
 pub mod ipfs;
-pub mod propose_sidechain_blocks;
-pub mod send_to_parentchain;
+pub mod read_state_backup_chunk;
 pub mod store_sidechain_blocks;
 pub mod update_metric;
 pub mod worker_request;
+pub mod write_state_backup_chunk;
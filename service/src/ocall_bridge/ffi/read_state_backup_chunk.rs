@@ -0,0 +1,75 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::ocall_bridge::bridge_api::{Bridge, StateBackupBridge};
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, sync::Arc};
+
+/// # Safety
+///
+/// FFI are always unsafe
+#[no_mangle]
+pub unsafe extern "C" fn ocall_read_state_backup_chunk(
+	path_ptr: *const u8,
+	path_size: u32,
+	offset: u64,
+	chunk_ptr: *mut u8,
+	chunk_size: u32,
+	bytes_read: *mut u32,
+) -> sgx_status_t {
+	read_state_backup_chunk(
+		path_ptr,
+		path_size,
+		offset,
+		chunk_ptr,
+		chunk_size,
+		bytes_read,
+		Bridge::get_state_backup_api(),
+	)
+}
+
+fn read_state_backup_chunk(
+	path_ptr: *const u8,
+	path_size: u32,
+	offset: u64,
+	chunk_ptr: *mut u8,
+	chunk_size: u32,
+	bytes_read: *mut u32,
+	state_backup_api: Arc<dyn StateBackupBridge>,
+) -> sgx_status_t {
+	let path: Vec<u8> = unsafe { Vec::from(slice::from_raw_parts(path_ptr, path_size as usize)) };
+
+	let chunk = match state_backup_api.read_state_backup_chunk(path, offset, chunk_size as usize) {
+		Ok(chunk) => chunk,
+		Err(e) => {
+			error!("read_state_backup_chunk o-call failed: {:?}", e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+	if chunk.len() > chunk_size as usize {
+		error!("Read more bytes of the state backup than fit the o-call buffer");
+		return sgx_status_t::SGX_ERROR_UNEXPECTED
+	}
+
+	let chunk_slice = unsafe { slice::from_raw_parts_mut(chunk_ptr, chunk.len()) };
+	chunk_slice.copy_from_slice(&chunk);
+	unsafe {
+		*bytes_read = chunk.len() as u32;
+	}
+	sgx_status_t::SGX_SUCCESS
+}
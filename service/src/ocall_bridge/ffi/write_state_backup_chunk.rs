@@ -0,0 +1,60 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::ocall_bridge::bridge_api::{Bridge, StateBackupBridge};
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{slice, sync::Arc};
+
+/// # Safety
+///
+/// FFI are always unsafe
+#[no_mangle]
+pub unsafe extern "C" fn ocall_write_state_backup_chunk(
+	path_ptr: *const u8,
+	path_size: u32,
+	chunk_ptr: *const u8,
+	chunk_size: u32,
+) -> sgx_status_t {
+	write_state_backup_chunk(
+		path_ptr,
+		path_size,
+		chunk_ptr,
+		chunk_size,
+		Bridge::get_state_backup_api(),
+	)
+}
+
+fn write_state_backup_chunk(
+	path_ptr: *const u8,
+	path_size: u32,
+	chunk_ptr: *const u8,
+	chunk_size: u32,
+	state_backup_api: Arc<dyn StateBackupBridge>,
+) -> sgx_status_t {
+	let path: Vec<u8> = unsafe { Vec::from(slice::from_raw_parts(path_ptr, path_size as usize)) };
+	let chunk: Vec<u8> =
+		unsafe { Vec::from(slice::from_raw_parts(chunk_ptr, chunk_size as usize)) };
+
+	match state_backup_api.write_state_backup_chunk(path, chunk) {
+		Ok(()) => sgx_status_t::SGX_SUCCESS,
+		Err(e) => {
+			error!("write_state_backup_chunk o-call failed: {:?}", e);
+			sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	}
+}
@@ -30,7 +30,7 @@ use std::{
 pub struct IpfsOCall;
 
 impl IpfsBridge for IpfsOCall {
-	fn write_to_ipfs(&self, data: &'static [u8]) -> OCallBridgeResult<Cid> {
+	fn write_to_ipfs(&self, data: Vec<u8>) -> OCallBridgeResult<Cid> {
 		debug!("    Entering ocall_write_ipfs");
 		Ok(write_to_ipfs(data))
 	}
@@ -59,7 +59,7 @@ fn create_file(filename: &str, result: &[u8]) -> Result<(), String> {
 }
 
 #[tokio::main]
-async fn write_to_ipfs(data: &'static [u8]) -> Cid {
+async fn write_to_ipfs(data: Vec<u8>) -> Cid {
 	// Creates an `IpfsClient` connected to the endpoint specified in ~/.ipfs/api.
 	// If not found, tries to connect to `localhost:5001`.
 	let client = IpfsClient::default();
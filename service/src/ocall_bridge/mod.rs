@@ -21,12 +21,15 @@
 pub mod bridge_api;
 pub mod component_factory;
 
+mod disk_space_ocall;
 mod ffi;
 mod ipfs_ocall;
 mod metrics_ocall;
 mod remote_attestation_ocall;
 mod sidechain_ocall;
-mod worker_on_chain_ocall;
+mod state_backup_ocall;
+pub mod worker_command_ocall;
+pub mod worker_on_chain_ocall;
 
 #[cfg(test)]
 pub mod test;
@@ -16,8 +16,9 @@
 
 */
 
-use crate::ocall_bridge::bridge_api::{
-	OCallBridgeError, OCallBridgeResult, RemoteAttestationBridge,
+use crate::{
+	ocall_bridge::bridge_api::{OCallBridgeError, OCallBridgeResult, RemoteAttestationBridge},
+	retry::{retry_without_delay, CallClass},
 };
 use itp_enclave_api::remote_attestation::{QveReport, RemoteAttestationCallBacks};
 use sgx_types::*;
@@ -52,10 +53,11 @@ where
 		let port = 443;
 		let hostname = "api.trustedservices.intel.com";
 
-		let addr = lookup_ipv4(hostname, port).map_err(OCallBridgeError::GetIasSocket)?;
-
-		let stream = TcpStream::connect(addr).map_err(|_| {
-			OCallBridgeError::GetIasSocket("[-] Connect tls server failed!".to_string())
+		let stream = retry_without_delay(CallClass::Attestation, || {
+			let addr = lookup_ipv4(hostname, port).map_err(OCallBridgeError::GetIasSocket)?;
+			TcpStream::connect(addr).map_err(|_| {
+				OCallBridgeError::GetIasSocket("[-] Connect tls server failed!".to_string())
+			})
 		})?;
 
 		Ok(stream.into_raw_fd())
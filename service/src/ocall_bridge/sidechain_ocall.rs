@@ -17,6 +17,7 @@
 */
 
 use crate::{
+	disk_space::DiskSpaceMonitor,
 	globals::tokio_handle::GetTokioHandle,
 	ocall_bridge::bridge_api::{OCallBridgeError, OCallBridgeResult, SidechainBridge},
 	sync_block_broadcaster::BroadcastBlocks,
@@ -36,6 +37,7 @@ use std::{collections::HashSet, sync::Arc};
 pub struct SidechainOCall<BlockBroadcaster, Storage, PeerUpdater, PeerBlockFetcher, TokioHandle> {
 	block_broadcaster: Arc<BlockBroadcaster>,
 	block_storage: Arc<Storage>,
+	disk_space_monitor: Arc<DiskSpaceMonitor>,
 	peer_updater: Arc<PeerUpdater>,
 	peer_block_fetcher: Arc<PeerBlockFetcher>,
 	tokio_handle: Arc<TokioHandle>,
@@ -47,6 +49,7 @@ impl<BlockBroadcaster, Storage, PeerUpdater, PeerBlockFetcher, TokioHandle>
 	pub fn new(
 		block_broadcaster: Arc<BlockBroadcaster>,
 		block_storage: Arc<Storage>,
+		disk_space_monitor: Arc<DiskSpaceMonitor>,
 		peer_updater: Arc<PeerUpdater>,
 		peer_block_fetcher: Arc<PeerBlockFetcher>,
 		tokio_handle: Arc<TokioHandle>,
@@ -54,6 +57,7 @@ impl<BlockBroadcaster, Storage, PeerUpdater, PeerBlockFetcher, TokioHandle>
 		SidechainOCall {
 			block_broadcaster,
 			block_storage,
+			disk_space_monitor,
 			peer_updater,
 			peer_block_fetcher,
 			tokio_handle,
@@ -148,6 +152,14 @@ where
 				},
 			};
 
+		if let Err(e) = self
+			.disk_space_monitor
+			.ensure_free_space_in_data_dir(signed_blocks_encoded.len() as u64)
+		{
+			error!("Refusing to store sidechain blocks: {}", e);
+			return Err(OCallBridgeError::DiskSpace(e.to_string()))
+		}
+
 		if let Err(e) = self.block_storage.store_blocks(signed_blocks) {
 			error!("Error storing blocks: {:?}", e);
 		}
@@ -279,6 +291,7 @@ mod tests {
 		SidechainOCall::new(
 			block_broadcaster_mock,
 			block_storage_mock,
+			Arc::new(DiskSpaceMonitor::new(std::env::temp_dir(), 0)),
 			peer_updater_mock,
 			peer_block_fetcher_mock,
 			scoped_tokio_handle,
@@ -0,0 +1,129 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	disk_space::DiskSpaceMonitor,
+	ocall_bridge::bridge_api::{OCallBridgeError, OCallBridgeResult, StateBackupBridge},
+};
+use std::{
+	ffi::OsStr,
+	fs::{File, OpenOptions},
+	io::{Read, Seek, SeekFrom, Write},
+	os::unix::ffi::OsStrExt,
+	path::Path,
+	sync::Arc,
+};
+
+pub struct StateBackupOCall {
+	disk_space_monitor: Arc<DiskSpaceMonitor>,
+}
+
+impl StateBackupOCall {
+	pub fn new(disk_space_monitor: Arc<DiskSpaceMonitor>) -> Self {
+		StateBackupOCall { disk_space_monitor }
+	}
+}
+
+impl StateBackupBridge for StateBackupOCall {
+	fn write_state_backup_chunk(&self, path: Vec<u8>, chunk: Vec<u8>) -> OCallBridgeResult<()> {
+		let path = Path::new(OsStr::from_bytes(&path));
+		self.disk_space_monitor
+			.ensure_free_space(path, chunk.len() as u64)
+			.map_err(|e| OCallBridgeError::StateBackup(e.to_string()))?;
+		// The file is created by the worker before the export is started, so a typo in the path
+		// does not leave a partial backup somewhere else.
+		OpenOptions::new()
+			.append(true)
+			.open(path)
+			.and_then(|mut file| file.write_all(&chunk))
+			.map_err(|e| OCallBridgeError::StateBackup(format!("{:?}: {}", path, e)))
+	}
+
+	fn read_state_backup_chunk(
+		&self,
+		path: Vec<u8>,
+		offset: u64,
+		max_size: usize,
+	) -> OCallBridgeResult<Vec<u8>> {
+		let path = Path::new(OsStr::from_bytes(&path));
+		let mut chunk = Vec::with_capacity(max_size);
+		File::open(path)
+			.and_then(|mut file| {
+				file.seek(SeekFrom::Start(offset))?;
+				file.take(max_size as u64).read_to_end(&mut chunk)
+			})
+			.map_err(|e| OCallBridgeError::StateBackup(format!("{:?}: {}", path, e)))?;
+		Ok(chunk)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	#[test]
+	fn chunks_are_appended_to_the_existing_file() {
+		let path = std::env::temp_dir().join("state_backup_chunks_are_appended.bin");
+		fs::write(&path, [1u8]).unwrap();
+		let state_backup_ocall =
+			StateBackupOCall::new(Arc::new(DiskSpaceMonitor::new(std::env::temp_dir(), 0)));
+		let path_bytes = path.as_os_str().as_bytes().to_vec();
+
+		state_backup_ocall
+			.write_state_backup_chunk(path_bytes.clone(), vec![2, 3])
+			.unwrap();
+		state_backup_ocall.write_state_backup_chunk(path_bytes, vec![4]).unwrap();
+
+		assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3, 4]);
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn missing_file_is_not_created() {
+		let path = std::env::temp_dir().join("state_backup_missing_file.bin");
+		let _ = fs::remove_file(&path);
+		let state_backup_ocall =
+			StateBackupOCall::new(Arc::new(DiskSpaceMonitor::new(std::env::temp_dir(), 0)));
+
+		let result = state_backup_ocall
+			.write_state_backup_chunk(path.as_os_str().as_bytes().to_vec(), vec![1]);
+
+		assert!(matches!(result, Err(OCallBridgeError::StateBackup(_))));
+		assert!(!path.exists());
+	}
+
+	#[test]
+	fn chunks_are_read_from_the_offset_until_the_end() {
+		let path = std::env::temp_dir().join("state_backup_chunks_are_read.bin");
+		fs::write(&path, [1u8, 2, 3, 4, 5]).unwrap();
+		let state_backup_ocall =
+			StateBackupOCall::new(Arc::new(DiskSpaceMonitor::new(std::env::temp_dir(), 0)));
+		let path_bytes = path.as_os_str().as_bytes().to_vec();
+
+		let read = |offset| {
+			state_backup_ocall
+				.read_state_backup_chunk(path_bytes.clone(), offset, 3)
+				.unwrap()
+		};
+
+		assert_eq!(read(0), vec![1, 2, 3]);
+		assert_eq!(read(3), vec![4, 5]);
+		assert!(read(5).is_empty());
+		fs::remove_file(path).unwrap();
+	}
+}
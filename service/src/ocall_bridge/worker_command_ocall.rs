@@ -0,0 +1,301 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Verification and execution of the signed commands issued by the enclave.
+
+use crate::ocall_bridge::bridge_api::{
+	IpfsBridge, OCallBridgeError, OCallBridgeResult, SidechainBridge, WorkerCommandBridge,
+	WorkerOnChainBridge,
+};
+use codec::{Decode, Encode};
+use itp_types::worker_command::{SignedWorkerCommand, WorkerCommand};
+use its_primitives::types::block::SignedBlock as SignedSidechainBlock;
+use lazy_static::lazy_static;
+use log::*;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter, register_int_counter_vec, IntCounter, IntCounterVec};
+use sp_core::ed25519;
+use std::sync::Arc;
+
+lazy_static! {
+	static ref ENCLAVE_COMMANDS: IntCounterVec = register_int_counter_vec!(
+		"integritee_worker_enclave_commands",
+		"Number of executed commands issued by the enclave",
+		&["command"]
+	)
+	.unwrap();
+	static ref REJECTED_ENCLAVE_COMMANDS: IntCounter = register_int_counter!(
+		"integritee_worker_enclave_commands_rejected",
+		"Number of enclave commands with an invalid signature or a replayed sequence number"
+	)
+	.unwrap();
+	static ref SUPPRESSED_ENCLAVE_COMMANDS: IntCounter = register_int_counter!(
+		"integritee_worker_enclave_commands_suppressed",
+		"Number of enclave commands with external effects dropped in shadow mode"
+	)
+	.unwrap();
+}
+
+/// Number of sequence numbers below the highest one seen that are still accepted. Commands are
+/// issued by several enclave threads and may arrive out of order.
+const REPLAY_WINDOW_SIZE: u64 = 64;
+
+/// Sliding window over the sequence numbers of the received commands.
+#[derive(Default)]
+struct ReplayWindow {
+	highest: Option<u64>,
+	/// Bit `i` is set if the sequence number `highest - i` has been received.
+	received: u64,
+}
+
+impl ReplayWindow {
+	/// Records the sequence number, returns false if it has been received before or is too old.
+	fn record(&mut self, sequence: u64) -> bool {
+		let highest = match self.highest {
+			Some(highest) => highest,
+			None => {
+				self.highest = Some(sequence);
+				self.received = 1;
+				return true
+			},
+		};
+		if sequence > highest {
+			let shift = sequence - highest;
+			self.received = if shift >= REPLAY_WINDOW_SIZE { 0 } else { self.received << shift };
+			self.received |= 1;
+			self.highest = Some(sequence);
+			return true
+		}
+		let offset = highest - sequence;
+		if offset >= REPLAY_WINDOW_SIZE || self.received & (1 << offset) != 0 {
+			return false
+		}
+		self.received |= 1 << offset;
+		true
+	}
+}
+
+/// Verifies that commands are signed by the enclave and have not been replayed.
+pub struct WorkerCommandVerifier {
+	enclave_signer: ed25519::Public,
+	replay_window: Mutex<ReplayWindow>,
+}
+
+impl WorkerCommandVerifier {
+	pub fn new(enclave_signer: ed25519::Public) -> Self {
+		WorkerCommandVerifier { enclave_signer, replay_window: Default::default() }
+	}
+
+	fn verify<B: Encode>(&self, signed_command: &SignedWorkerCommand<B>) -> OCallBridgeResult<()> {
+		if !signed_command.verify_signature(&self.enclave_signer) {
+			return Err(OCallBridgeError::RejectedWorkerCommand(format!(
+				"invalid signature of command #{}",
+				signed_command.sequence
+			)))
+		}
+		if !self.replay_window.lock().record(signed_command.sequence) {
+			return Err(OCallBridgeError::RejectedWorkerCommand(format!(
+				"command #{} has been replayed",
+				signed_command.sequence
+			)))
+		}
+		Ok(())
+	}
+}
+
+pub struct WorkerCommandOCall {
+	verifier: Arc<WorkerCommandVerifier>,
+	/// A shadow worker never submits extrinsics nor gossips blocks.
+	shadow: bool,
+	on_chain_api: Arc<dyn WorkerOnChainBridge>,
+	sidechain_api: Arc<dyn SidechainBridge>,
+	ipfs_api: Arc<dyn IpfsBridge>,
+}
+
+impl WorkerCommandOCall {
+	pub fn new(
+		verifier: Arc<WorkerCommandVerifier>,
+		shadow: bool,
+		on_chain_api: Arc<dyn WorkerOnChainBridge>,
+		sidechain_api: Arc<dyn SidechainBridge>,
+		ipfs_api: Arc<dyn IpfsBridge>,
+	) -> Self {
+		WorkerCommandOCall { verifier, shadow, on_chain_api, sidechain_api, ipfs_api }
+	}
+}
+
+impl WorkerCommandBridge for WorkerCommandOCall {
+	fn execute_command(&self, signed_command_encoded: Vec<u8>) -> OCallBridgeResult<Vec<u8>> {
+		let signed_command: SignedWorkerCommand<SignedSidechainBlock> =
+			Decode::decode(&mut signed_command_encoded.as_slice())?;
+		if let Err(e) = self.verifier.verify(&signed_command) {
+			REJECTED_ENCLAVE_COMMANDS.inc();
+			return Err(e)
+		}
+		let sequence = signed_command.sequence;
+		let command = signed_command.command;
+		ENCLAVE_COMMANDS.with_label_values(&[command.name()]).inc();
+
+		if self.shadow
+			&& matches!(
+				command,
+				WorkerCommand::SubmitExtrinsics { .. } | WorkerCommand::ProposeSidechainBlocks(_)
+			) {
+			debug!("Enclave command #{}: {} suppressed in shadow mode", sequence, command.name());
+			SUPPRESSED_ENCLAVE_COMMANDS.inc();
+			return Ok(Vec::new())
+		}
+
+		match command {
+			WorkerCommand::SubmitExtrinsics {
+				parentchain_id,
+				extrinsics,
+				await_each_inclusion,
+			} => {
+				debug!(
+					"Enclave command #{}: submit {} extrinsics to {:?}",
+					sequence,
+					extrinsics.len(),
+					parentchain_id
+				);
+				self.on_chain_api.send_to_parentchain(
+					extrinsics.encode(),
+					parentchain_id.encode(),
+					await_each_inclusion,
+				)?;
+				Ok(Vec::new())
+			},
+			WorkerCommand::ProposeSidechainBlocks(signed_blocks) => {
+				debug!(
+					"Enclave command #{}: propose sidechain blocks {:?}",
+					sequence,
+					signed_blocks.iter().map(|b| b.block.header.block_number).collect::<Vec<_>>()
+				);
+				self.sidechain_api.propose_sidechain_blocks(signed_blocks.encode())?;
+				Ok(Vec::new())
+			},
+			WorkerCommand::WriteIpfs(data) => {
+				debug!("Enclave command #{}: write {} bytes to IPFS", sequence, data.len());
+				self.ipfs_api.write_to_ipfs(data).map(|cid| cid.encode())
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ocall_bridge::bridge_api::{
+		MockIpfsBridge, MockSidechainBridge, MockWorkerOnChainBridge,
+	};
+	use itp_types::parentchain::ParentchainId;
+	use sp_core::Pair;
+	use sp_runtime::OpaqueExtrinsic;
+
+	fn enclave_signer() -> ed25519::Pair {
+		ed25519::Pair::from_seed(&[1u8; 32])
+	}
+
+	fn command_ocall(on_chain_api: MockWorkerOnChainBridge) -> WorkerCommandOCall {
+		WorkerCommandOCall::new(
+			Arc::new(WorkerCommandVerifier::new(enclave_signer().public())),
+			false,
+			Arc::new(on_chain_api),
+			Arc::new(MockSidechainBridge::new()),
+			Arc::new(MockIpfsBridge::new()),
+		)
+	}
+
+	fn submit_command(sequence: u64, signer: &ed25519::Pair) -> Vec<u8> {
+		let command = WorkerCommand::<SignedSidechainBlock>::SubmitExtrinsics {
+			parentchain_id: ParentchainId::Integritee,
+			extrinsics: vec![OpaqueExtrinsic::from_bytes(&[4u8, 1]).unwrap()],
+			await_each_inclusion: true,
+		};
+		SignedWorkerCommand::new(command, sequence, signer).encode()
+	}
+
+	#[test]
+	fn signed_command_is_forwarded_to_the_parentchain() {
+		let mut on_chain_api = MockWorkerOnChainBridge::new();
+		on_chain_api
+			.expect_send_to_parentchain()
+			.withf(|extrinsics, parentchain_id, await_each_inclusion| {
+				*parentchain_id == ParentchainId::Integritee.encode()
+					&& Vec::<OpaqueExtrinsic>::decode(&mut extrinsics.as_slice()).unwrap().len()
+						== 1 && *await_each_inclusion
+			})
+			.times(1)
+			.returning(|_, _, _| Ok(()));
+
+		let response = command_ocall(on_chain_api)
+			.execute_command(submit_command(0, &enclave_signer()))
+			.unwrap();
+
+		assert!(response.is_empty());
+	}
+
+	#[test]
+	fn shadow_worker_does_not_submit_extrinsics() {
+		let command_ocall = WorkerCommandOCall::new(
+			Arc::new(WorkerCommandVerifier::new(enclave_signer().public())),
+			true,
+			Arc::new(MockWorkerOnChainBridge::new()),
+			Arc::new(MockSidechainBridge::new()),
+			Arc::new(MockIpfsBridge::new()),
+		);
+
+		let response = command_ocall.execute_command(submit_command(0, &enclave_signer())).unwrap();
+
+		assert!(response.is_empty());
+	}
+
+	#[test]
+	fn command_with_foreign_signature_is_rejected() {
+		let foreign_signer = ed25519::Pair::from_seed(&[2u8; 32]);
+
+		let result = command_ocall(MockWorkerOnChainBridge::new())
+			.execute_command(submit_command(0, &foreign_signer));
+
+		assert!(matches!(result, Err(OCallBridgeError::RejectedWorkerCommand(_))));
+	}
+
+	#[test]
+	fn replayed_command_is_rejected() {
+		let mut on_chain_api = MockWorkerOnChainBridge::new();
+		on_chain_api.expect_send_to_parentchain().times(1).returning(|_, _, _| Ok(()));
+		let command_ocall = command_ocall(on_chain_api);
+		let command = submit_command(3, &enclave_signer());
+
+		command_ocall.execute_command(command.clone()).unwrap();
+		let result = command_ocall.execute_command(command);
+
+		assert!(matches!(result, Err(OCallBridgeError::RejectedWorkerCommand(_))));
+	}
+
+	#[test]
+	fn replay_window_accepts_commands_out_of_order() {
+		let mut window = ReplayWindow::default();
+
+		assert!(window.record(5));
+		assert!(window.record(7));
+		assert!(window.record(6));
+		assert!(!window.record(6));
+		assert!(window.record(7 + REPLAY_WINDOW_SIZE));
+		assert!(!window.record(7));
+	}
+}
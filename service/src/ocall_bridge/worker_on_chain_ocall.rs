@@ -16,11 +16,19 @@
 
 */
 
-use crate::ocall_bridge::bridge_api::{OCallBridgeError, OCallBridgeResult, WorkerOnChainBridge};
+use crate::{
+	error::Error as ServiceError,
+	extrinsic_queue::{signer_and_nonce, ExtrinsicQueue, MAX_SUBMISSION_ATTEMPTS},
+	ocall_bridge::bridge_api::{OCallBridgeError, OCallBridgeResult, WorkerOnChainBridge},
+	retry::{retry, CallClass},
+};
 use codec::{Decode, Encode};
-use itp_api_client_types::ParentchainApi;
-use itp_node_api::node_api_factory::CreateNodeApi;
-use itp_types::{parentchain::ParentchainId, WorkerRequest, WorkerResponse};
+use itp_api_client_types::{ApiClientError, ParentchainApi};
+use itp_node_api::{api_client::AccountApi, node_api_factory::CreateNodeApi};
+use itp_types::{
+	parentchain::{AccountId, ParentchainId},
+	WorkerRequest, WorkerResponse,
+};
 use log::*;
 use sp_runtime::OpaqueExtrinsic;
 use std::{sync::Arc, vec::Vec};
@@ -32,6 +40,7 @@ pub struct WorkerOnChainOCall<F> {
 	integritee_api_factory: Arc<F>,
 	target_a_parentchain_api_factory: Option<Arc<F>>,
 	target_b_parentchain_api_factory: Option<Arc<F>>,
+	extrinsic_queue: Arc<ExtrinsicQueue>,
 }
 
 impl<F> WorkerOnChainOCall<F> {
@@ -39,11 +48,13 @@ impl<F> WorkerOnChainOCall<F> {
 		integritee_api_factory: Arc<F>,
 		target_a_parentchain_api_factory: Option<Arc<F>>,
 		target_b_parentchain_api_factory: Option<Arc<F>>,
+		extrinsic_queue: Arc<ExtrinsicQueue>,
 	) -> Self {
 		WorkerOnChainOCall {
 			integritee_api_factory,
 			target_a_parentchain_api_factory,
 			target_b_parentchain_api_factory,
+			extrinsic_queue,
 		}
 	}
 }
@@ -64,6 +75,98 @@ impl<F: CreateNodeApi> WorkerOnChainOCall<F> {
 				.and_then(|f| f.create_api().map_err(Into::into))?,
 		})
 	}
+
+	/// Submits the queued extrinsics of the parentchain in order. Only called by the replay
+	/// thread, so the submissions don't block the enclave.
+	///
+	/// Extrinsics whose nonce is below the on-chain nonce of their signer have already been
+	/// included, e.g. before a restart, and are dropped. The submission stops at the first
+	/// extrinsic that cannot be sent, the remaining ones stay queued for the next replay. An
+	/// extrinsic that failed too often is moved to the dead-letter file instead.
+	pub fn submit_queued(&self, parentchain_id: ParentchainId) -> OCallBridgeResult<()> {
+		if self.extrinsic_queue.len(parentchain_id).map_err(queue_error)? == 0 {
+			return Ok(())
+		}
+		let api = retry(CallClass::ParentchainSubmission, || self.create_api(parentchain_id))?;
+		// Signers whose on-chain nonce has been checked already. As the queue is ordered, the
+		// following extrinsics of the same signer cannot be stale.
+		let mut reconciled_signers: Vec<AccountId> = Vec::new();
+
+		while let Some(queued) = self.extrinsic_queue.front(parentchain_id).map_err(queue_error)? {
+			let call = queued.extrinsic;
+			if let Some((signer, nonce)) = signer_and_nonce(&call) {
+				if !reconciled_signers.contains(&signer) {
+					let on_chain_nonce =
+						retry(CallClass::ParentchainSubmission, || api.get_nonce_of(&signer));
+					let on_chain_nonce = match on_chain_nonce {
+						Ok(nonce) => nonce,
+						Err(e) => {
+							warn!(
+								"Could not get the nonce of {} on {:?}: {:?}",
+								signer, parentchain_id, e
+							);
+							return Ok(())
+						},
+					};
+					if nonce < on_chain_nonce {
+						info!(
+							"Dropping queued extrinsic with nonce {} of {}, on-chain nonce on {:?} is {}",
+							nonce, signer, parentchain_id, on_chain_nonce
+						);
+						self.extrinsic_queue.pop_front(parentchain_id).map_err(queue_error)?;
+						continue
+					}
+					reconciled_signers.push(signer);
+				}
+			}
+
+			// The submission is not retried, as the node may have accepted the extrinsic even if
+			// an error is returned. The next replay reconciles the nonce before submitting again.
+			if let Err(e) = submit(&api, &call, queued.await_inclusion) {
+				if self
+					.extrinsic_queue
+					.record_failed_submission(parentchain_id)
+					.map_err(queue_error)?
+				{
+					error!(
+						"Moved extrinsic to {:?} to the dead-letter file after {} failed submissions: {:?}, error: {:?}",
+						parentchain_id,
+						MAX_SUBMISSION_ATTEMPTS,
+						serde_json::to_string(&call),
+						e
+					);
+					continue
+				}
+				error!(
+					"Could not send extrinsic to {:?}: {:?}, error: {:?}. Keeping {} extrinsics queued",
+					parentchain_id,
+					serde_json::to_string(&call),
+					e,
+					self.extrinsic_queue.len(parentchain_id).map_err(queue_error)?
+				);
+				return Ok(())
+			}
+			self.extrinsic_queue.pop_front(parentchain_id).map_err(queue_error)?;
+		}
+		Ok(())
+	}
+}
+
+fn submit(
+	api: &ParentchainApi,
+	call: &OpaqueExtrinsic,
+	await_inclusion: bool,
+) -> Result<(), ApiClientError> {
+	if await_inclusion {
+		api.submit_and_watch_opaque_extrinsic_until(&call.encode().into(), XtStatus::InBlock)
+			.map(|_| ())
+	} else {
+		api.submit_opaque_extrinsic(&call.encode().into()).map(|_| ())
+	}
+}
+
+fn queue_error(e: ServiceError) -> OCallBridgeError {
+	OCallBridgeError::SendExtrinsicsToParentchain(format!("Outbound extrinsic queue: {:?}", e))
 }
 
 impl<F> WorkerOnChainBridge for WorkerOnChainOCall<F>
@@ -132,27 +235,24 @@ where
 				extrinsics.len(),
 				parentchain_id, await_each_inlcusion
 			);
-			let api = self.create_api(parentchain_id)?;
-			for call in extrinsics.into_iter() {
-				if await_each_inlcusion {
-					if let Err(e) = api.submit_and_watch_opaque_extrinsic_until(
-						&call.encode().into(),
-						XtStatus::InBlock,
-					) {
+			if self.extrinsic_queue.is_open().map_err(queue_error)? {
+				// Only queued, the replay thread submits them in order, so the enclave doesn't
+				// wait for the parentchain.
+				self.extrinsic_queue
+					.push(parentchain_id, extrinsics, await_each_inlcusion)
+					.map_err(queue_error)?;
+			} else {
+				// No worker is running, e.g. in a subcommand, so nobody would replay a queue.
+				let api = self.create_api(parentchain_id)?;
+				for call in extrinsics.iter() {
+					if let Err(e) = submit(&api, call, await_each_inlcusion) {
 						error!(
 							"Could not send extrinsic to {:?}: {:?}, error: {:?}",
 							parentchain_id,
-							serde_json::to_string(&call),
+							serde_json::to_string(call),
 							e
 						);
 					}
-				} else if let Err(e) = api.submit_opaque_extrinsic(&call.encode().into()) {
-					error!(
-						"Could not send extrinsic to {:?}: {:?}, error: {:?}",
-						parentchain_id,
-						serde_json::to_string(&call),
-						e
-					);
 				}
 			}
 		}
@@ -181,7 +281,10 @@ mod tests {
 
 		let mock_node_api_factory = Arc::new(MockNodeApiFactory::new());
 
-		let on_chain_ocall = WorkerOnChainOCall::new(mock_node_api_factory, None, None);
+		let extrinsic_queue = Arc::new(ExtrinsicQueue::default());
+
+		let on_chain_ocall =
+			WorkerOnChainOCall::new(mock_node_api_factory, None, None, extrinsic_queue);
 
 		let response = on_chain_ocall
 			.worker_request(Vec::<u8>::new().encode(), ParentchainId::Integritee.encode())
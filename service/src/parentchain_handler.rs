@@ -86,6 +86,11 @@ where
 		Self { parentchain_api, enclave_api, parentchain_init_params }
 	}
 
+	/// Same handler, but talking to the parentchain through another api, e.g. after a failover.
+	pub fn with_parentchain_api(&self, parentchain_api: ParentchainApi) -> Self {
+		Self::new(parentchain_api, self.enclave_api.clone(), self.parentchain_init_params.clone())
+	}
+
 	// FIXME: Necessary in the future? Fix with #1080
 	pub fn new_with_automatic_light_client_allocation(
 		parentchain_api: ParentchainApi,
@@ -38,7 +38,9 @@ use itc_rest_client::{
 use itp_enclave_metrics::EnclaveMetric;
 use lazy_static::lazy_static;
 use log::*;
-use prometheus::{proto::MetricFamily, register_int_gauge, IntGauge};
+use prometheus::{
+	proto::MetricFamily, register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge,
+};
 use serde::{Deserialize, Serialize};
 use std::{net::SocketAddr, sync::Arc};
 use warp::{Filter, Rejection, Reply};
@@ -55,6 +57,15 @@ lazy_static! {
 	static ref ENCLAVE_SIDECHAIN_TOP_POOL_SIZE: IntGauge =
 		register_int_gauge!("integritee_worker_enclave_sidechain_top_pool_size", "Enclave sidechain top pool size")
 			.unwrap();
+	static ref ENCLAVE_LOAD_SHEDDING_LEVEL: IntGauge =
+		register_int_gauge!("integritee_worker_enclave_load_shedding_level", "Number of active load shedding actions")
+			.unwrap();
+	static ref ENCLAVE_STALE_SHARD_LAG: IntGauge =
+		register_int_gauge!("integritee_worker_enclave_stale_shard_lag", "Number of sidechain blocks a stale shard lags the parentchain, 0 if not stale")
+			.unwrap();
+	static ref ENCLAVE_LOAD_SHEDDING_TRANSITIONS: IntCounterVec =
+		register_int_counter_vec!("integritee_worker_enclave_load_shedding_transitions", "Number of load shedding level changes", &["direction"])
+			.unwrap();
 }
 
 pub async fn start_metrics_server<MetricsHandler>(
@@ -177,6 +188,18 @@ impl ReceiveEnclaveMetrics for EnclaveMetricsReceiver {
 			EnclaveMetric::ExchangeRateOracle(_) => {
 				error!("Received Teeracle metric, but Teeracle feature is not enabled, ignoring metric item.")
 			},
+			EnclaveMetric::LoadSheddingLevelSet(level) => {
+				let direction = if level as i64 > ENCLAVE_LOAD_SHEDDING_LEVEL.get() {
+					"escalate"
+				} else {
+					"recover"
+				};
+				ENCLAVE_LOAD_SHEDDING_TRANSITIONS.with_label_values(&[direction]).inc();
+				ENCLAVE_LOAD_SHEDDING_LEVEL.set(level as i64);
+			},
+			EnclaveMetric::StaleShardLagSet(lag) => {
+				ENCLAVE_STALE_SHARD_LAG.set(lag as i64);
+			},
 		}
 		Ok(())
 	}
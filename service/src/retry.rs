@@ -0,0 +1,263 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Retries of calls to untrusted services, with exponential backoff.
+//!
+//! Every call belongs to a [`CallClass`], which determines its [`RetryPolicy`] and the label
+//! of the retry metrics.
+//!
+//! Only calls that can safely be repeated are retried. Submitting an extrinsic is not, as the
+//! node may have accepted an extrinsic even if the submission returned an error.
+
+use lazy_static::lazy_static;
+use log::*;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use std::{cmp::min, fmt::Debug, future::Future, thread, time::Duration};
+
+lazy_static! {
+	static ref UNTRUSTED_CALL_RETRIES: IntCounterVec = register_int_counter_vec!(
+		"integritee_worker_untrusted_call_retries",
+		"Number of retried calls to untrusted services",
+		&["call_class"]
+	)
+	.unwrap();
+	static ref UNTRUSTED_CALL_FAILURES: IntCounterVec = register_int_counter_vec!(
+		"integritee_worker_untrusted_call_failures",
+		"Number of calls to untrusted services that failed after all retries",
+		&["call_class"]
+	)
+	.unwrap();
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CallClass {
+	/// Requests to the attestation services.
+	Attestation,
+	/// Preparing the submission of extrinsics to a parentchain, e.g. connecting to the node or
+	/// reading the nonce of the signer. The submission itself is not retried.
+	ParentchainSubmission,
+	/// Requests to other workers.
+	PeerRequest,
+	/// Notifications sent to webhooks.
+	Webhook,
+}
+
+impl CallClass {
+	pub fn policy(self) -> RetryPolicy {
+		match self {
+			CallClass::Attestation =>
+				RetryPolicy::new(5, Duration::from_secs(1), Duration::from_secs(30)),
+			CallClass::ParentchainSubmission =>
+				RetryPolicy::new(3, Duration::from_millis(500), Duration::from_secs(5)),
+			CallClass::PeerRequest =>
+				RetryPolicy::new(3, Duration::from_millis(200), Duration::from_secs(2)),
+			CallClass::Webhook =>
+				RetryPolicy::new(3, Duration::from_secs(1), Duration::from_secs(10)),
+		}
+	}
+
+	fn label(self) -> &'static str {
+		match self {
+			CallClass::Attestation => "attestation",
+			CallClass::ParentchainSubmission => "parentchain_submission",
+			CallClass::PeerRequest => "peer_request",
+			CallClass::Webhook => "webhook",
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+	/// Number of attempts, including the first one.
+	pub max_attempts: u32,
+	pub initial_delay: Duration,
+	pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+	pub const fn new(max_attempts: u32, initial_delay: Duration, max_delay: Duration) -> Self {
+		Self { max_attempts, initial_delay, max_delay }
+	}
+
+	/// The same number of attempts, without any delay between them.
+	pub const fn without_delay(self) -> Self {
+		Self::new(self.max_attempts, Duration::ZERO, Duration::ZERO)
+	}
+
+	/// Delay after the given failed attempt, doubling with every attempt.
+	pub fn delay(&self, failed_attempt: u32) -> Duration {
+		let factor = 2u32.saturating_pow(failed_attempt.saturating_sub(1));
+		min(self.initial_delay.saturating_mul(factor), self.max_delay)
+	}
+
+	/// Delay before the next attempt, or `None` if the call should not be retried any more.
+	fn next_delay<E: Debug>(
+		&self,
+		call_class: CallClass,
+		failed_attempt: u32,
+		error: &E,
+	) -> Option<Duration> {
+		if failed_attempt >= self.max_attempts {
+			error!("{:?} call failed after {} attempts: {:?}", call_class, failed_attempt, error);
+			UNTRUSTED_CALL_FAILURES.with_label_values(&[call_class.label()]).inc();
+			return None
+		}
+		let delay = self.delay(failed_attempt);
+		if delay.is_zero() {
+			warn!(
+				"{:?} call failed (attempt {}/{}), retrying immediately: {:?}",
+				call_class, failed_attempt, self.max_attempts, error
+			);
+		} else {
+			warn!(
+				"{:?} call failed (attempt {}/{}), retrying in {:?}: {:?}",
+				call_class, failed_attempt, self.max_attempts, delay, error
+			);
+		}
+		UNTRUSTED_CALL_RETRIES.with_label_values(&[call_class.label()]).inc();
+		Some(delay)
+	}
+}
+
+/// Calls `call` until it succeeds or the policy of the call class is exhausted.
+///
+/// Blocks the thread between the attempts, use [`retry_without_delay`] in OCALLs.
+pub fn retry<T, E, F>(call_class: CallClass, call: F) -> Result<T, E>
+where
+	E: Debug,
+	F: FnMut() -> Result<T, E>,
+{
+	retry_with_policy(call_class, call_class.policy(), call, thread::sleep)
+}
+
+/// Like [`retry`], but the attempts follow each other immediately. For OCALLs, where waiting
+/// would block the enclave thread.
+pub fn retry_without_delay<T, E, F>(call_class: CallClass, call: F) -> Result<T, E>
+where
+	E: Debug,
+	F: FnMut() -> Result<T, E>,
+{
+	retry_with_policy(call_class, call_class.policy().without_delay(), call, |_| ())
+}
+
+fn retry_with_policy<T, E, F, W>(
+	call_class: CallClass,
+	policy: RetryPolicy,
+	mut call: F,
+	wait: W,
+) -> Result<T, E>
+where
+	E: Debug,
+	F: FnMut() -> Result<T, E>,
+	W: Fn(Duration),
+{
+	let mut attempt = 1;
+	loop {
+		match call() {
+			Ok(value) => return Ok(value),
+			Err(e) => match policy.next_delay(call_class, attempt, &e) {
+				Some(delay) => wait(delay),
+				None => return Err(e),
+			},
+		}
+		attempt += 1;
+	}
+}
+
+/// Async version of [`retry`], which does not block the executor while waiting.
+pub async fn retry_async<T, E, F, Fut>(call_class: CallClass, mut call: F) -> Result<T, E>
+where
+	E: Debug,
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T, E>>,
+{
+	let policy = call_class.policy();
+	let mut attempt = 1;
+	loop {
+		match call().await {
+			Ok(value) => return Ok(value),
+			Err(e) => match policy.next_delay(call_class, attempt, &e) {
+				Some(delay) => tokio::time::sleep(delay).await,
+				None => return Err(e),
+			},
+		}
+		attempt += 1;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn delay_doubles_up_to_max_delay() {
+		let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_millis(500));
+
+		assert_eq!(policy.delay(1), Duration::from_millis(100));
+		assert_eq!(policy.delay(2), Duration::from_millis(200));
+		assert_eq!(policy.delay(3), Duration::from_millis(400));
+		assert_eq!(policy.delay(4), Duration::from_millis(500));
+		assert_eq!(policy.delay(u32::MAX), Duration::from_millis(500));
+	}
+
+	#[test]
+	fn policy_without_delay_keeps_the_attempts() {
+		let policy = CallClass::Attestation.policy().without_delay();
+
+		assert_eq!(policy.max_attempts, CallClass::Attestation.policy().max_attempts);
+		assert_eq!(policy.delay(1), Duration::ZERO);
+		assert_eq!(policy.delay(u32::MAX), Duration::ZERO);
+	}
+
+	#[test]
+	fn retry_returns_first_success() {
+		let mut attempts = 0;
+
+		let result: Result<u32, ()> = retry(CallClass::PeerRequest, || {
+			attempts += 1;
+			if attempts < 3 {
+				Err(())
+			} else {
+				Ok(attempts)
+			}
+		});
+
+		assert_eq!(result, Ok(3));
+	}
+
+	#[test]
+	fn retry_gives_up_after_max_attempts() {
+		let mut attempts = 0;
+
+		let result: Result<(), u32> = retry(CallClass::PeerRequest, || {
+			attempts += 1;
+			Err(attempts)
+		});
+
+		assert_eq!(result, Err(CallClass::PeerRequest.policy().max_attempts));
+	}
+
+	#[test]
+	fn retry_without_delay_does_not_wait() {
+		let start = std::time::Instant::now();
+
+		let result: Result<(), ()> = retry_without_delay(CallClass::Attestation, || Err(()));
+
+		assert_eq!(result, Err(()));
+		assert!(start.elapsed() < CallClass::Attestation.policy().initial_delay);
+	}
+}
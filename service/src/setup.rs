@@ -17,30 +17,54 @@
 */
 
 use crate::error::{Error, ServiceResult};
-use itp_settings::files::{
-	INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, SHARDS_PATH, SIDECHAIN_STORAGE_PATH,
-	TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+use itp_settings::{
+	files::{
+		INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, SHARDS_PATH, SIDECHAIN_STORAGE_PATH,
+		TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+	},
+	worker::BLOCK_ARCHIVE_CHUNK_SIZE,
+};
+use itp_types::ShardIdentifier;
+use itp_utils::stream_encoder::WriteChunk;
+use its_primitives::types::block::SignedBlock as SignedSidechainBlock;
+use its_storage::SidechainStorageLock;
+use log::*;
+use std::{
+	fs,
+	fs::File,
+	io::{self, Write},
+	path::Path,
 };
-use std::{fs, path::Path};
 
 #[cfg(feature = "link-binary")]
 pub(crate) use needs_enclave::{
-	generate_shielding_key_file, generate_signing_key_file, init_shard, initialize_shard_and_keys,
+	conduct_key_ceremony, diff_state_snapshots, export_key_ceremony_trail,
+	export_payload_quarantine, export_shard_state, export_state_backup,
+	generate_shielding_key_file, generate_signing_key_file, import_state_backup, init_shard,
+	initialize_shard_and_keys, issue_key_ceremony_nonce, run_smoke_test,
 };
 
 #[cfg(feature = "link-binary")]
 mod needs_enclave {
-	use crate::error::{Error, ServiceResult};
-	use codec::Encode;
+	use crate::{
+		error::{Error, ServiceResult},
+		parentchain_handler::{HandleParentchain, ParentchainHandler},
+	};
+	use codec::{Decode, Encode};
+	use itc_parentchain::primitives::ParentchainId;
 	use itp_enclave_api::{enclave_base::EnclaveBase, Enclave};
+	use itp_node_api::api_client::ParentchainApi;
 	use itp_settings::files::{
 		INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, SHARDS_PATH, SHIELDING_KEY_FILE,
 		SIDECHAIN_STORAGE_PATH, SIGNING_KEY_FILE, TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
 		TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
 	};
-	use itp_types::ShardIdentifier;
+	use itp_types::{
+		key_ceremony::ApprovedKeyCeremony, payload_quarantine::SignedQuarantineExportRequest,
+		shard_export::SignedShardExportRequest, ShardIdentifier,
+	};
 	use log::*;
-	use std::{fs, fs::File, path::Path};
+	use std::{fs, fs::File, path::Path, sync::Arc};
 
 	/// Initializes the shard and generates the key files.
 	pub(crate) fn initialize_shard_and_keys(
@@ -101,6 +125,210 @@ mod needs_enclave {
 			},
 		}
 	}
+
+	/// Inits the light client of the Integritee parentchain, which the enclave reads the shard
+	/// owners from.
+	fn init_integritee_light_client(
+		enclave: &Arc<Enclave>,
+		node_api: ParentchainApi,
+		shard: &ShardIdentifier,
+	) {
+		let parentchain_handler = ParentchainHandler::new_with_automatic_light_client_allocation(
+			node_api,
+			enclave.clone(),
+			ParentchainId::Integritee,
+			*shard,
+		)
+		.unwrap();
+		let last_synced_header = parentchain_handler.init_parentchain_components().unwrap();
+		debug!("Shard owners are read at parentchain block {}", last_synced_header.number);
+	}
+
+	/// Prints the encrypted export of the payload quarantine, hex encoded.
+	pub(crate) fn export_payload_quarantine(
+		enclave: &Arc<Enclave>,
+		node_api: ParentchainApi,
+		request_hex: &str,
+	) {
+		info!("*** Export the payload quarantine from the TEE\n");
+		let request = hex::decode(request_hex.trim_start_matches("0x"))
+			.ok()
+			.and_then(|bytes| SignedQuarantineExportRequest::decode(&mut bytes.as_slice()).ok())
+			.expect("request must be a hex encoded signed quarantine export request");
+		init_integritee_light_client(enclave, node_api, &request.request.shard);
+		let export = enclave.export_payload_quarantine(&request).unwrap();
+		println!("0x{}", hex::encode(export.encode()));
+	}
+
+	/// Conducts the key ceremony and prints the encrypted state key escrow, if any, hex encoded.
+	pub(crate) fn conduct_key_ceremony(enclave: &Enclave, ceremony_hex: &str) {
+		info!("*** Conduct a key ceremony in the TEE\n");
+		let ceremony = hex::decode(ceremony_hex.trim_start_matches("0x"))
+			.ok()
+			.and_then(|bytes| ApprovedKeyCeremony::decode(&mut bytes.as_slice()).ok())
+			.expect("ceremony must be a hex encoded approved key ceremony");
+		match enclave.conduct_key_ceremony(&ceremony).unwrap() {
+			Some(escrow) => println!("0x{}", hex::encode(escrow.encode())),
+			None => println!("Key ceremony #{} conducted", ceremony.request.ceremony_id),
+		}
+	}
+
+	/// Prints the enclave account and the nonce the next key ceremony has to be signed over.
+	pub(crate) fn issue_key_ceremony_nonce(enclave: &Enclave) {
+		info!("*** Issue a key ceremony nonce in the TEE\n");
+		let enclave_account = enclave.get_ecc_signing_pubkey().unwrap();
+		let nonce = enclave.issue_key_ceremony_nonce().unwrap();
+		println!("enclave: 0x{}", hex::encode(enclave_account.as_array_ref()));
+		println!("nonce: 0x{}", hex::encode(nonce));
+	}
+
+	/// Prints the operator quorums and the trail of the conducted key ceremonies, hex encoded.
+	pub(crate) fn export_key_ceremony_trail(enclave: &Enclave) {
+		info!("*** Export the key ceremony trail from the TEE\n");
+		let trail = enclave.export_key_ceremony_trail().unwrap();
+		println!("0x{}", hex::encode(trail.encode()));
+	}
+
+	/// Prints the outcome of every step and the signed report, hex encoded. Exits with an error if
+	/// the deployment did not pass.
+	pub(crate) fn run_smoke_test(enclave: &Enclave) {
+		info!("*** Run the smoke test in the TEE\n");
+		let signed_report = enclave.run_smoke_test().unwrap();
+		for (step, outcome) in &signed_report.report.outcomes {
+			match outcome {
+				Ok(()) => println!("{:?}: passed", step),
+				Err(e) => println!("{:?}: failed: {}", step, String::from_utf8_lossy(e)),
+			}
+		}
+		println!("0x{}", hex::encode(signed_report.encode()));
+		if !signed_report.report.passed() {
+			error!("Smoke test failed");
+			std::process::exit(1);
+		}
+	}
+
+	/// Creates the backup file and has the enclave stream the encrypted state of the shard to it.
+	pub(crate) fn export_state_backup(enclave: &Enclave, shard: &ShardIdentifier, path: &str) {
+		info!("*** Export a backup of the state of shard {:?} from the TEE\n", shard);
+		if let Err(e) = fs::OpenOptions::new().write(true).create_new(true).open(path) {
+			error!("Could not create the state backup file '{}': {}", path, e);
+			std::process::exit(1);
+		}
+		if let Err(e) = enclave.export_state_backup(shard, path) {
+			error!("Failed to export the state backup, removing '{}': {:?}", path, e);
+			let _ = fs::remove_file(path);
+			std::process::exit(1);
+		}
+		println!("[+] State backup written to '{}'", path);
+	}
+
+	/// Has the enclave restore the state of the shard of the backup at `path`.
+	pub(crate) fn import_state_backup(enclave: &Enclave, path: &str) {
+		info!("*** Restore the state backup '{}' in the TEE\n", path);
+		if let Err(e) = enclave.import_state_backup(path) {
+			error!("Failed to restore the state backup '{}': {:?}", path, e);
+			std::process::exit(1);
+		}
+		println!("[+] State restored from '{}'", path);
+	}
+
+	/// Creates the export file and has the enclave stream the state of the shard to it, encrypted
+	/// to the key of the owner.
+	pub(crate) fn export_shard_state(
+		enclave: &Arc<Enclave>,
+		node_api: ParentchainApi,
+		request_hex: &str,
+		path: &str,
+	) {
+		let request = hex::decode(request_hex.trim_start_matches("0x"))
+			.ok()
+			.and_then(|bytes| SignedShardExportRequest::decode(&mut bytes.as_slice()).ok())
+			.expect("request must be a hex encoded signed shard export request");
+		info!("*** Export the state of shard {:?} from the TEE\n", request.request.shard);
+		init_integritee_light_client(enclave, node_api, &request.request.shard);
+		if let Err(e) = fs::OpenOptions::new().write(true).create_new(true).open(path) {
+			error!("Could not create the shard export file '{}': {}", path, e);
+			std::process::exit(1);
+		}
+		if let Err(e) = enclave.export_shard_state(&request, path) {
+			error!("Failed to export the state of the shard, removing '{}': {:?}", path, e);
+			let _ = fs::remove_file(path);
+			std::process::exit(1);
+		}
+		println!("[+] Shard state export written to '{}'", path);
+	}
+
+	/// Prints the number of changed keys and, per key hash, the sizes of the value in both
+	/// snapshots. The keys and values themselves never leave the enclave.
+	pub(crate) fn diff_state_snapshots(
+		enclave: &Enclave,
+		shard: &ShardIdentifier,
+		from: u128,
+		to: u128,
+	) {
+		info!("*** Diff the state snapshots {} and {} of shard {:?} in the TEE\n", from, to, shard);
+		let diff = match enclave.diff_state_snapshots(shard, from, to) {
+			Ok(diff) => diff,
+			Err(e) => {
+				error!("Failed to diff the state snapshots: {:?}", e);
+				std::process::exit(1);
+			},
+		};
+		println!(
+			"{} keys added, {} removed, {} modified, {:+} bytes",
+			diff.added, diff.removed, diff.modified, diff.size_delta
+		);
+		for change in &diff.changes {
+			let size = |size: Option<u32>| size.map_or("-".to_string(), |size| size.to_string());
+			println!(
+				"{:?} {} -> {} ({:+})",
+				change.key_hash,
+				size(change.from_size),
+				size(change.to_size),
+				change.size_delta()
+			);
+		}
+		let total = diff.added + diff.removed + diff.modified;
+		if diff.changes.len() < total as usize {
+			println!("[+] {} more changed keys not listed", total as usize - diff.changes.len());
+		}
+	}
+}
+
+/// Writes the sidechain blocks of the shard, including the archived ones, to a new file at
+/// `path`, one SCALE encoded block after the other.
+pub(crate) fn export_block_archive(
+	sidechain_storage: &SidechainStorageLock<SignedSidechainBlock>,
+	shard: &ShardIdentifier,
+	path: &str,
+) {
+	info!("*** Export the sidechain blocks of shard {:?}\n", shard);
+	let file = match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+		Ok(file) => file,
+		Err(e) => {
+			error!("Could not create the block archive file '{}': {}", path, e);
+			std::process::exit(1);
+		},
+	};
+	match sidechain_storage.export_blocks(shard, FileChunkWriter(file), BLOCK_ARCHIVE_CHUNK_SIZE) {
+		Ok(exported_blocks) =>
+			println!("[+] {} sidechain blocks written to '{}'", exported_blocks, path),
+		Err(e) => {
+			error!("Failed to export the block archive, removing '{}': {:?}", path, e);
+			let _ = fs::remove_file(path);
+			std::process::exit(1);
+		},
+	}
+}
+
+struct FileChunkWriter(File);
+
+impl WriteChunk for FileChunkWriter {
+	type Error = io::Error;
+
+	fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+		self.0.write_all(chunk)
+	}
 }
 
 /// Purge all worker files from `dir`.
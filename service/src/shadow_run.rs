@@ -0,0 +1,199 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Shadow mode, in which the enclave of a new build runs next to the current one before an upgrade.
+//!
+//! A shadow worker imports the sidechain blocks of the worker it shadows, but never registers,
+//! authors or submits extrinsics. Its state hash is compared to the one of the shadowed worker
+//! for every sidechain block both of them have applied.
+
+use crate::error::{Error, ServiceResult};
+use itc_rpc_client::direct_client::{DirectApi, DirectClient as DirectWorkerApi};
+use itp_settings::sidechain::SLOT_DURATION;
+use itp_types::H256;
+use lazy_static::lazy_static;
+use log::*;
+use prometheus::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+use std::{collections::BTreeMap, thread};
+
+lazy_static! {
+	static ref SHADOW_STATE_MATCHES: IntCounter = register_int_counter!(
+		"integritee_worker_shadow_state_matches",
+		"Number of sidechain blocks after which the shadow enclave has the same state hash"
+	)
+	.unwrap();
+	static ref SHADOW_STATE_MISMATCHES: IntCounter = register_int_counter!(
+		"integritee_worker_shadow_state_mismatches",
+		"Number of sidechain blocks after which the shadow enclave has a different state hash"
+	)
+	.unwrap();
+	static ref SHADOW_LAST_COMPARED_BLOCK: IntGauge = register_int_gauge!(
+		"integritee_worker_shadow_last_compared_block",
+		"Number of the last sidechain block whose state hashes have been compared"
+	)
+	.unwrap();
+}
+
+/// Number of state hashes per worker that are kept until the other worker has applied the block.
+const STATE_HASH_HISTORY: usize = 64;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Comparison {
+	Match,
+	Mismatch { shadowed: H256, shadow: H256 },
+}
+
+/// State hashes of both workers by sidechain block number.
+#[derive(Default)]
+pub struct StateHashComparison {
+	shadowed: BTreeMap<u64, H256>,
+	shadow: BTreeMap<u64, H256>,
+}
+
+impl StateHashComparison {
+	/// Records the current state hashes of both workers and compares all blocks both of them
+	/// have applied by now.
+	pub fn record(
+		&mut self,
+		shadowed: (Option<u64>, H256),
+		shadow: (Option<u64>, H256),
+	) -> Vec<(u64, Comparison)> {
+		if let (Some(block_number), hash) = shadowed {
+			self.shadowed.insert(block_number, hash);
+		}
+		if let (Some(block_number), hash) = shadow {
+			self.shadow.insert(block_number, hash);
+		}
+
+		let compared_blocks: Vec<u64> =
+			self.shadow.keys().filter(|n| self.shadowed.contains_key(n)).copied().collect();
+		let comparisons = compared_blocks
+			.into_iter()
+			.map(|block_number| {
+				let shadowed = self.shadowed.remove(&block_number).expect("key exists; qed");
+				let shadow = self.shadow.remove(&block_number).expect("key exists; qed");
+				let comparison = if shadowed == shadow {
+					Comparison::Match
+				} else {
+					Comparison::Mismatch { shadowed, shadow }
+				};
+				(block_number, comparison)
+			})
+			.collect();
+
+		prune(&mut self.shadowed);
+		prune(&mut self.shadow);
+		comparisons
+	}
+}
+
+fn prune(hashes: &mut BTreeMap<u64, H256>) {
+	while hashes.len() > STATE_HASH_HISTORY {
+		let oldest = *hashes.keys().next().expect("map is not empty; qed");
+		hashes.remove(&oldest);
+	}
+}
+
+/// Compares the state hashes of the shadowed worker and our enclave once per slot.
+pub fn start_state_hash_comparison(
+	shadowed_worker_url: String,
+	own_worker_url: String,
+) -> ServiceResult<()> {
+	println!("[+] Comparing state hashes with the shadowed worker at {}", shadowed_worker_url);
+	thread::Builder::new()
+		.name("shadow_state_hash_comparison".to_owned())
+		.spawn(move || {
+			let shadowed_worker = DirectWorkerApi::new(shadowed_worker_url);
+			let own_worker = DirectWorkerApi::new(own_worker_url);
+			let mut comparison = StateHashComparison::default();
+			loop {
+				thread::sleep(SLOT_DURATION);
+				let state_hashes = shadowed_worker
+					.get_state_hash()
+					.and_then(|shadowed| own_worker.get_state_hash().map(|own| (shadowed, own)));
+				match state_hashes {
+					Ok((shadowed, own)) =>
+						for (block_number, result) in comparison.record(shadowed, own) {
+							report(block_number, result)
+						},
+					Err(e) => warn!("Could not fetch the state hashes to compare: {:?}", e),
+				}
+			}
+		})
+		.map_err(|e| Error::Custom(Box::new(e)))?;
+	Ok(())
+}
+
+fn report(block_number: u64, comparison: Comparison) {
+	SHADOW_LAST_COMPARED_BLOCK.set(block_number as i64);
+	match comparison {
+		Comparison::Match => {
+			debug!("State hash after sidechain block {} matches", block_number);
+			SHADOW_STATE_MATCHES.inc();
+		},
+		Comparison::Mismatch { shadowed, shadow } => {
+			error!(
+				"State hash after sidechain block {} differs: shadowed worker {:?}, shadow {:?}",
+				block_number, shadowed, shadow
+			);
+			SHADOW_STATE_MISMATCHES.inc();
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn blocks_are_compared_once_both_workers_applied_them() {
+		let mut comparison = StateHashComparison::default();
+
+		assert!(comparison
+			.record((Some(2), H256::repeat_byte(2)), (Some(1), H256::repeat_byte(1)))
+			.is_empty());
+		let results =
+			comparison.record((Some(3), H256::repeat_byte(3)), (Some(2), H256::repeat_byte(9)));
+
+		assert_eq!(
+			results,
+			vec![(
+				2,
+				Comparison::Mismatch {
+					shadowed: H256::repeat_byte(2),
+					shadow: H256::repeat_byte(9)
+				}
+			)]
+		);
+		assert_eq!(
+			comparison.record((Some(3), H256::repeat_byte(3)), (Some(3), H256::repeat_byte(3))),
+			vec![(3, Comparison::Match)]
+		);
+	}
+
+	#[test]
+	fn old_state_hashes_are_pruned() {
+		let mut comparison = StateHashComparison::default();
+
+		for block_number in 0..2 * STATE_HASH_HISTORY as u64 {
+			comparison.record((Some(block_number), H256::zero()), (None, H256::zero()));
+		}
+
+		assert_eq!(comparison.shadowed.len(), STATE_HASH_HISTORY);
+		assert!(comparison.record((None, H256::zero()), (Some(0), H256::zero())).is_empty());
+	}
+}
@@ -0,0 +1,138 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Static shard routing table of a multi-worker deployment.
+//!
+//! Every worker instance serves one shard. The table lists the shards served by the other
+//! instances, either with the trusted RPC url of the instance or without one, in which case the
+//! url of the primary worker of the shard is looked up in the on-chain registry. The resolved
+//! routes are pushed to the enclave, which redirects the requests for these shards.
+
+use crate::error::{Error, ServiceResult};
+use itp_enclave_api::enclave_base::EnclaveBase;
+use itp_node_api::api_client::PalletTeerexApi;
+use itp_types::{shard_routing::ShardRoute, ShardIdentifier};
+use log::*;
+use std::{sync::Arc, thread, time::Duration};
+
+/// Interval at which the routes resolved from the on-chain registry are refreshed.
+const SHARD_ROUTES_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct ShardRouter<NodeApi> {
+	node_api: NodeApi,
+	own_shard: ShardIdentifier,
+	/// Shards served by other instances, with the url of the instance if it is static.
+	routes: Vec<(ShardIdentifier, Option<String>)>,
+}
+
+impl<NodeApi: PalletTeerexApi> ShardRouter<NodeApi> {
+	pub fn new(
+		node_api: NodeApi,
+		own_shard: ShardIdentifier,
+		routes: Vec<(ShardIdentifier, Option<String>)>,
+	) -> Self {
+		let routes = routes.into_iter().filter(|(shard, _)| *shard != own_shard).collect();
+		ShardRouter { node_api, own_shard, routes }
+	}
+
+	/// Trusted RPC url of the instance serving the shard, none if this instance serves it.
+	pub fn endpoint_for(&self, shard: &ShardIdentifier) -> ServiceResult<Option<String>> {
+		if *shard == self.own_shard {
+			return Ok(None)
+		}
+		match self.routes.iter().find(|(routed_shard, _)| routed_shard == shard) {
+			Some((_, Some(url))) => Ok(Some(url.clone())),
+			Some((_, None)) => self.registered_endpoint(shard).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	/// Routes of all the shards in the table, the ones that can't be resolved are skipped.
+	pub fn resolve_routes(&self) -> Vec<ShardRoute> {
+		self.routes
+			.iter()
+			.filter_map(|(shard, _)| match self.endpoint_for(shard) {
+				Ok(url) => url.map(|url| ShardRoute { shard: *shard, url: url.into_bytes() }),
+				Err(e) => {
+					warn!("Could not resolve the route of shard {:?}: {:?}", shard, e);
+					None
+				},
+			})
+			.collect()
+	}
+
+	fn registered_endpoint(&self, shard: &ShardIdentifier) -> ServiceResult<String> {
+		let enclave = self
+			.node_api
+			.primary_worker_for_shard(shard, None)?
+			.ok_or(Error::NoWorkerForShardFound(*shard))?;
+		let url = enclave.instance_url().ok_or(Error::NoWorkerForShardFound(*shard))?;
+		String::from_utf8(url).map_err(|e| Error::Custom(e.into()))
+	}
+}
+
+/// Pushes the routes to the enclave and keeps them up to date with the on-chain registry.
+pub fn start_shard_routes_refresh<NodeApi, Enclave>(
+	shard_router: ShardRouter<NodeApi>,
+	enclave: Arc<Enclave>,
+) where
+	NodeApi: PalletTeerexApi + Send + 'static,
+	Enclave: EnclaveBase + 'static,
+{
+	println!(
+		"[+] Routing the requests for {} shards to other worker instances",
+		shard_router.routes.len()
+	);
+	thread::spawn(move || loop {
+		let routes = shard_router.resolve_routes();
+		if let Err(e) = enclave.set_shard_routes(&routes) {
+			error!("Could not set the shard routes: {:?}", e);
+		}
+		thread::sleep(SHARD_ROUTES_REFRESH_INTERVAL);
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::mock::{TestNodeApi, W2_URL};
+
+	#[test]
+	fn routes_are_resolved_statically_or_from_the_registry() {
+		let own_shard = ShardIdentifier::repeat_byte(1);
+		let static_shard = ShardIdentifier::repeat_byte(2);
+		let registered_shard = ShardIdentifier::repeat_byte(3);
+		let router = ShardRouter::new(
+			TestNodeApi,
+			own_shard,
+			vec![
+				(own_shard, Some("wss://localhost:2000".to_string())),
+				(static_shard, Some("wss://worker-2:2000".to_string())),
+				(registered_shard, None),
+			],
+		);
+
+		assert_eq!(router.endpoint_for(&own_shard).unwrap(), None);
+		assert_eq!(
+			router.endpoint_for(&static_shard).unwrap(),
+			Some("wss://worker-2:2000".to_string())
+		);
+		assert!(router.endpoint_for(&registered_shard).unwrap().unwrap().ends_with(W2_URL));
+		assert_eq!(router.endpoint_for(&ShardIdentifier::repeat_byte(4)).unwrap(), None);
+		assert_eq!(router.resolve_routes().len(), 2);
+	}
+}
@@ -0,0 +1,97 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Exclusive lock on the shards directory of a data directory.
+//!
+//! Two worker processes on the same data directory would seal over each other's shard state.
+//! The lock file lives next to the shards directory, so it survives a clean reset.
+
+use crate::error::{Error, ServiceResult};
+use itp_settings::files::SHARDS_PATH;
+use std::{
+	fs::{File, OpenOptions},
+	io::{Read, Seek, SeekFrom, Write},
+	os::unix::io::AsRawFd,
+	path::Path,
+};
+
+/// Holds the lock until it is dropped, i.e. for the lifetime of the worker process.
+pub struct ShardsLock {
+	_file: File,
+}
+
+impl ShardsLock {
+	/// Acquires the lock and records the id of this process in the lock file.
+	pub fn acquire(data_dir: &Path) -> ServiceResult<Self> {
+		let path = data_dir.join(format!("{}.lock", SHARDS_PATH));
+		let mut file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.open(&path)
+			.map_err(|e| Error::Custom(e.into()))?;
+
+		if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+			let error = std::io::Error::last_os_error();
+			if error.kind() != std::io::ErrorKind::WouldBlock {
+				return Err(Error::Custom(error.into()))
+			}
+			let mut pid = String::new();
+			let _ = file.read_to_string(&mut pid);
+			return Err(Error::ShardsDirectoryLocked {
+				lock_file: path.display().to_string(),
+				pid: pid.trim().to_string(),
+			})
+		}
+
+		file.set_len(0)
+			.and_then(|_| file.seek(SeekFrom::Start(0)))
+			.and_then(|_| write!(file, "{}", std::process::id()))
+			.map_err(|e| Error::Custom(e.into()))?;
+
+		Ok(ShardsLock { _file: file })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{fs, path::PathBuf};
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(name);
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn second_lock_fails_with_pid_of_holder_until_first_is_released() {
+		let dir = temp_dir("shards_lock_is_exclusive");
+		let lock = ShardsLock::acquire(&dir).unwrap();
+
+		match ShardsLock::acquire(&dir) {
+			Err(Error::ShardsDirectoryLocked { pid, .. }) =>
+				assert_eq!(pid, std::process::id().to_string()),
+			_ => panic!("expected the shards directory to be locked"),
+		}
+
+		drop(lock);
+		assert!(ShardsLock::acquire(&dir).is_ok());
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
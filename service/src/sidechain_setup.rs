@@ -17,6 +17,7 @@
 
 use crate::{
 	config::Config,
+	epc_usage::epc_usage,
 	error::{Error, ServiceResult},
 	parentchain_handler::HandleParentchain,
 };
@@ -103,6 +104,11 @@ where
 
 /// Execute trusted operations in the enclave.
 fn execute_trusted_calls<E: Sidechain>(enclave_api: &E) {
+	if let Some((used_bytes, total_bytes)) = epc_usage() {
+		if let Err(e) = enclave_api.report_epc_usage(used_bytes, total_bytes) {
+			warn!("Could not report the EPC usage: {:?}", e);
+		}
+	}
 	if let Err(e) = enclave_api.execute_trusted_calls() {
 		error!("{:?}", e);
 	};
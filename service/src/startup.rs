@@ -0,0 +1,283 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+//! Startup of the worker as an explicit dependency graph of stages.
+//!
+//! A stage may only start once all the stages it depends on have completed or were skipped. The
+//! progress of every stage is logged, exported as prometheus metrics and served by the
+//! `system_startupStatus` RPC of the untrusted http server.
+
+use lazy_static::lazy_static;
+use log::*;
+use parking_lot::Mutex;
+use prometheus::{register_int_gauge_vec, IntGaugeVec};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+pub const STARTUP_STATUS_RPC_METHOD: &str = "system_startupStatus";
+
+lazy_static! {
+	static ref STARTUP_STAGE_STATE: IntGaugeVec = register_int_gauge_vec!(
+		"integritee_worker_startup_stage_state",
+		"State of the startup stage: 0 pending, 1 running, 2 completed, 3 skipped",
+		&["stage"]
+	)
+	.unwrap();
+	static ref STARTUP_STAGE_DURATION: IntGaugeVec = register_int_gauge_vec!(
+		"integritee_worker_startup_stage_duration_ms",
+		"Duration of the completed startup stage in milliseconds",
+		&["stage"]
+	)
+	.unwrap();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupStage {
+	/// Retrieve the signing and shielding keys of the enclave.
+	Keys,
+	/// Create the remote attestation report of the enclave.
+	Attestation,
+	/// Register the enclave with its attestation on the Integritee parentchain.
+	Registration,
+	/// Initialize or provision the shard and sync the parentchains up to the shard's state.
+	ShardLoad,
+	/// Initialize the sidechain components and resume the block production from the TOP pool.
+	PoolRestore,
+	/// Open the trusted and untrusted RPC servers.
+	RpcOpen,
+}
+
+impl StartupStage {
+	pub const ALL: [StartupStage; 6] = [
+		StartupStage::Keys,
+		StartupStage::Attestation,
+		StartupStage::Registration,
+		StartupStage::ShardLoad,
+		StartupStage::PoolRestore,
+		StartupStage::RpcOpen,
+	];
+
+	pub fn dependencies(&self) -> &'static [StartupStage] {
+		match self {
+			StartupStage::Keys => &[],
+			StartupStage::Attestation => &[StartupStage::Keys],
+			StartupStage::Registration => &[StartupStage::Attestation],
+			StartupStage::ShardLoad => &[StartupStage::Registration],
+			StartupStage::PoolRestore => &[StartupStage::ShardLoad],
+			StartupStage::RpcOpen => &[StartupStage::PoolRestore],
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			StartupStage::Keys => "keys",
+			StartupStage::Attestation => "attestation",
+			StartupStage::Registration => "registration",
+			StartupStage::ShardLoad => "shard_load",
+			StartupStage::PoolRestore => "pool_restore",
+			StartupStage::RpcOpen => "rpc_open",
+		}
+	}
+
+	fn index(&self) -> usize {
+		*self as usize
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Progress {
+	Pending,
+	Running(Instant),
+	Completed(Duration),
+	Skipped,
+}
+
+impl Progress {
+	fn is_finished(&self) -> bool {
+		matches!(self, Progress::Completed(_) | Progress::Skipped)
+	}
+
+	fn name(&self) -> &'static str {
+		match self {
+			Progress::Pending => "pending",
+			Progress::Running(_) => "running",
+			Progress::Completed(_) => "completed",
+			Progress::Skipped => "skipped",
+		}
+	}
+
+	fn metric_value(&self) -> i64 {
+		match self {
+			Progress::Pending => 0,
+			Progress::Running(_) => 1,
+			Progress::Completed(_) => 2,
+			Progress::Skipped => 3,
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StageStatus {
+	pub stage: StartupStage,
+	pub state: &'static str,
+	pub depends_on: Vec<StartupStage>,
+	/// Time spent in the stage so far, or in total once it has completed.
+	pub elapsed_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StartupStatus {
+	/// Whether all stages have completed or were skipped.
+	pub ready: bool,
+	pub stages: Vec<StageStatus>,
+}
+
+pub struct StartupProgress {
+	stages: Mutex<[Progress; StartupStage::ALL.len()]>,
+}
+
+impl Default for StartupProgress {
+	fn default() -> Self {
+		for stage in StartupStage::ALL.iter() {
+			STARTUP_STAGE_STATE.with_label_values(&[stage.name()]).set(0);
+		}
+		StartupProgress { stages: Mutex::new([Progress::Pending; StartupStage::ALL.len()]) }
+	}
+}
+
+impl StartupProgress {
+	/// Panics if a stage the given one depends on has not finished, which is a bug in the order
+	/// of the startup.
+	pub fn start(&self, stage: StartupStage) {
+		info!("[Startup] {} started", stage.name());
+		self.transition(stage, Progress::Running(Instant::now()));
+	}
+
+	pub fn complete(&self, stage: StartupStage) {
+		let elapsed = match self.stages.lock()[stage.index()] {
+			Progress::Running(since) => since.elapsed(),
+			_ => Duration::default(),
+		};
+		info!("[Startup] {} completed in {} ms", stage.name(), elapsed.as_millis());
+		STARTUP_STAGE_DURATION
+			.with_label_values(&[stage.name()])
+			.set(elapsed.as_millis() as i64);
+		self.transition(stage, Progress::Completed(elapsed));
+	}
+
+	/// For the stages that don't apply to the worker mode or configuration.
+	pub fn skip(&self, stage: StartupStage) {
+		info!("[Startup] {} skipped", stage.name());
+		self.transition(stage, Progress::Skipped);
+	}
+
+	pub fn status(&self) -> StartupStatus {
+		let stages = self.stages.lock();
+		StartupStatus {
+			ready: stages.iter().all(Progress::is_finished),
+			stages: StartupStage::ALL
+				.iter()
+				.map(|stage| {
+					let progress = stages[stage.index()];
+					StageStatus {
+						stage: *stage,
+						state: progress.name(),
+						depends_on: stage.dependencies().to_vec(),
+						elapsed_ms: match progress {
+							Progress::Running(since) => Some(since.elapsed().as_millis() as u64),
+							Progress::Completed(duration) => Some(duration.as_millis() as u64),
+							Progress::Pending | Progress::Skipped => None,
+						},
+					}
+				})
+				.collect(),
+		}
+	}
+
+	/// Answers a JSON-RPC request for [`STARTUP_STATUS_RPC_METHOD`].
+	pub fn handle_rpc_request(&self, request: &Value) -> Value {
+		let id = request.get("id").cloned().unwrap_or(Value::Null);
+		match request.get("method").and_then(Value::as_str) {
+			Some(STARTUP_STATUS_RPC_METHOD) =>
+				json!({ "jsonrpc": "2.0", "result": self.status(), "id": id }),
+			_ => json!({
+				"jsonrpc": "2.0",
+				"error": { "code": -32601, "message": "Method not found" },
+				"id": id,
+			}),
+		}
+	}
+
+	fn transition(&self, stage: StartupStage, progress: Progress) {
+		let mut stages = self.stages.lock();
+		if let Some(dependency) = stage
+			.dependencies()
+			.iter()
+			.find(|dependency| !stages[dependency.index()].is_finished())
+		{
+			panic!(
+				"Startup stage {} is not allowed before {} has finished",
+				stage.name(),
+				dependency.name()
+			);
+		}
+		stages[stage.index()] = progress;
+		STARTUP_STAGE_STATE
+			.with_label_values(&[stage.name()])
+			.set(progress.metric_value());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn startup_is_ready_once_all_stages_have_finished() {
+		let progress = StartupProgress::default();
+		progress.start(StartupStage::Keys);
+		progress.complete(StartupStage::Keys);
+		progress.skip(StartupStage::Attestation);
+		progress.skip(StartupStage::Registration);
+		progress.start(StartupStage::ShardLoad);
+
+		let status = progress.status();
+		assert!(!status.ready);
+		assert_eq!(status.stages[3].state, "running");
+		assert_eq!(status.stages[4].state, "pending");
+
+		progress.complete(StartupStage::ShardLoad);
+		progress.skip(StartupStage::PoolRestore);
+		progress.start(StartupStage::RpcOpen);
+		progress.complete(StartupStage::RpcOpen);
+
+		let response = progress.handle_rpc_request(
+			&json!({ "jsonrpc": "2.0", "method": STARTUP_STATUS_RPC_METHOD, "id": 1 }),
+		);
+		assert_eq!(response["result"]["ready"], json!(true));
+		assert_eq!(response["result"]["stages"][0]["stage"], json!("keys"));
+	}
+
+	#[test]
+	#[should_panic]
+	fn stage_cannot_start_before_its_dependencies_finished() {
+		let progress = StartupProgress::default();
+		progress.start(StartupStage::Keys);
+		progress.start(StartupStage::Attestation);
+	}
+}
@@ -19,6 +19,7 @@
 use crate::{
 	enclave::tls_ra::enclave_request_state_provisioning,
 	error::{Error, ServiceResult as Result},
+	retry::{retry, CallClass},
 };
 use futures::executor;
 use itc_rpc_client::direct_client::{DirectApi, DirectClient as DirectWorkerApi};
@@ -54,15 +55,42 @@ pub(crate) fn sync_state<
 				.expect("Author of last finalized sidechain block could not be found"),
 	};
 
-	println!("Requesting state provisioning from worker at {}", &provider_url);
+	request_state_provisioning(&provider_url, shard, enclave_api, skip_ra);
+}
+
+/// Requests the state provisioning from the worker with the given trusted RPC url, which a shadow
+/// worker does from the worker it shadows instead of the last active one.
+pub(crate) fn sync_state_from_worker<E: TlsRemoteAttestation + EnclaveBase + RemoteAttestation>(
+	worker_url: &str,
+	shard: &ShardIdentifier,
+	enclave_api: &E,
+	skip_ra: bool,
+) {
+	let provider_url = retry(CallClass::PeerRequest, || {
+		DirectWorkerApi::new(worker_url.to_string()).get_mu_ra_url()
+	})
+	.expect("mu-ra url of the shadowed worker could not be retrieved");
+	request_state_provisioning(&provider_url, shard, enclave_api, skip_ra);
+}
+
+fn request_state_provisioning<E: TlsRemoteAttestation + RemoteAttestation>(
+	provider_url: &str,
+	shard: &ShardIdentifier,
+	enclave_api: &E,
+	skip_ra: bool,
+) {
+	println!("Requesting state provisioning from worker at {}", provider_url);
 
-	enclave_request_state_provisioning(
-		enclave_api,
-		sgx_quote_sign_type_t::SGX_UNLINKABLE_SIGNATURE,
-		&provider_url,
-		shard,
-		skip_ra,
-	)
+	// A retry only needs the state chunks that have not been received yet.
+	retry(CallClass::PeerRequest, || {
+		enclave_request_state_provisioning(
+			enclave_api,
+			sgx_quote_sign_type_t::SGX_UNLINKABLE_SIGNATURE,
+			provider_url,
+			shard,
+			skip_ra,
+		)
+	})
 	.unwrap();
 	println!("[+] State provisioning successfully performed.");
 }
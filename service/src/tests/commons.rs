@@ -44,6 +44,9 @@ pub fn local_worker_config(
 		Default::default(),
 		Default::default(),
 		Default::default(),
+		Vec::new(),
+		Vec::new(),
+		Vec::new(),
 		url.next().unwrap().into(),
 		None,
 		url.next().unwrap().into(),
@@ -55,6 +58,8 @@ pub fn local_worker_config(
 		"8787".to_string(),
 		"4545".to_string(),
 		crate::config::pwd(),
+		512,
+		None,
 		None,
 	)
 }
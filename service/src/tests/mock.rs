@@ -78,7 +78,7 @@ impl PalletTeerexApi for TestNodeApi {
 		_: &ShardIdentifier,
 		_at_block: Option<Hash>,
 	) -> ApiResult<Option<MultiEnclave<Vec<u8>>>> {
-		unreachable!()
+		Ok(Some(enclaves().remove(1)))
 	}
 
 	fn shard_status(
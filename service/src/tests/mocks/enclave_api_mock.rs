@@ -27,7 +27,17 @@ use itp_settings::worker::MR_ENCLAVE_SIZE;
 use itp_stf_interface::ShardCreationInfo;
 use itp_storage::StorageProof;
 use itp_types::{
+	canary::CanaryReport,
+	key_ceremony::{ApprovedKeyCeremony, EncryptedStateKeyEscrow, KeyCeremonyTrail},
+	load_shedding::LoadSheddingPolicy,
+	maintenance_window::MaintenanceWindow,
+	panic_policy::PanicPolicy,
 	parentchain::{Balance, Header},
+	payload_quarantine::{EncryptedQuarantineExport, SignedQuarantineExportRequest},
+	shard_export::SignedShardExportRequest,
+	shard_routing::ShardRoute,
+	smoke_test::SignedSmokeTestReport,
+	state_diff::StateDiff,
 	ShardIdentifier,
 };
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
@@ -110,6 +120,93 @@ impl EnclaveBase for EnclaveMock {
 	fn get_fingerprint(&self) -> EnclaveResult<EnclaveFingerprint> {
 		Ok([1u8; MR_ENCLAVE_SIZE].into())
 	}
+
+	fn enable_payload_quarantine(&self, _max_bytes: u64) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn export_payload_quarantine(
+		&self,
+		_request: &SignedQuarantineExportRequest,
+	) -> EnclaveResult<EncryptedQuarantineExport> {
+		unreachable!()
+	}
+
+	fn set_maintenance_windows(
+		&self,
+		_shard: &ShardIdentifier,
+		_windows: &[MaintenanceWindow],
+	) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn set_load_shedding_policy(&self, _policy: &LoadSheddingPolicy) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn set_stale_shard_threshold(&self, _threshold: u64) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn set_panic_policy(&self, _policy: PanicPolicy) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn set_never_persist_calls(&self, _call_names: &[String]) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn set_shard_routes(&self, _routes: &[ShardRoute]) -> EnclaveResult<()> {
+		Ok(())
+	}
+
+	fn conduct_key_ceremony(
+		&self,
+		_ceremony: &ApprovedKeyCeremony,
+	) -> EnclaveResult<Option<EncryptedStateKeyEscrow>> {
+		unimplemented!()
+	}
+
+	fn export_key_ceremony_trail(&self) -> EnclaveResult<KeyCeremonyTrail> {
+		Ok(KeyCeremonyTrail::default())
+	}
+
+	fn issue_key_ceremony_nonce(&self) -> EnclaveResult<[u8; 32]> {
+		unimplemented!()
+	}
+
+	fn run_smoke_test(&self) -> EnclaveResult<SignedSmokeTestReport> {
+		unimplemented!()
+	}
+
+	fn run_canary_round(&self, _shard: &ShardIdentifier) -> EnclaveResult<CanaryReport> {
+		unimplemented!()
+	}
+
+	fn export_state_backup(&self, _shard: &ShardIdentifier, _path: &str) -> EnclaveResult<()> {
+		unimplemented!()
+	}
+
+	fn import_state_backup(&self, _path: &str) -> EnclaveResult<()> {
+		unimplemented!()
+	}
+
+	fn export_shard_state(
+		&self,
+		_request: &SignedShardExportRequest,
+		_path: &str,
+	) -> EnclaveResult<()> {
+		unimplemented!()
+	}
+
+	fn diff_state_snapshots(
+		&self,
+		_shard: &ShardIdentifier,
+		_from: u128,
+		_to: u128,
+	) -> EnclaveResult<StateDiff> {
+		unimplemented!()
+	}
 }
 
 impl Sidechain for EnclaveMock {
@@ -127,4 +224,8 @@ impl Sidechain for EnclaveMock {
 	fn execute_trusted_calls(&self) -> EnclaveResult<()> {
 		todo!()
 	}
+
+	fn report_epc_usage(&self, _used_bytes: u64, _total_bytes: u64) -> EnclaveResult<()> {
+		Ok(())
+	}
 }
@@ -20,13 +20,20 @@
 /// This should serve as a proof of concept for a potential refactoring design. Ultimately, everything
 /// from the main.rs should be covered by the worker struct here - hidden and split across
 /// multiple traits.
-use crate::{config::Config, error::Error, initialized_service::TrackInitialization};
+use crate::{
+	config::Config,
+	error::Error,
+	initialized_service::TrackInitialization,
+	retry::{retry_async, CallClass},
+};
 use async_trait::async_trait;
 use itc_rpc_client::direct_client::{DirectApi, DirectClient as DirectWorkerApi};
+use itc_rpc_server::block_compression::compress_blocks;
 use itp_node_api::{api_client::PalletTeerexApi, node_api_factory::CreateNodeApi};
 use itp_types::ShardIdentifier;
+use itp_utils::hex::hex_encode;
 use its_primitives::types::SignedBlock as SignedSidechainBlock;
-use its_rpc_handler::constants::RPC_METHOD_NAME_IMPORT_BLOCKS;
+use its_rpc_handler::constants::RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS;
 use jsonrpsee::{
 	types::{to_json_value, traits::Client},
 	ws_client::WsClientBuilder,
@@ -39,7 +46,7 @@ use url::Url as UrlType;
 pub type WorkerResult<T> = Result<T, Error>;
 pub type Url = String;
 pub struct Worker<Config, NodeApiFactory, Enclave, InitializationHandler> {
-	_config: Config,
+	config: Config,
 	// unused yet, but will be used when more methods are migrated to the worker
 	_enclave_api: Arc<Enclave>,
 	node_api_factory: Arc<NodeApiFactory>,
@@ -58,7 +65,7 @@ impl<Config, NodeApiFactory, Enclave, InitializationHandler>
 		peers: Vec<Url>,
 	) -> Self {
 		Self {
-			_config: config,
+			config,
 			_enclave_api: enclave_api,
 			node_api_factory,
 			initialization_handler,
@@ -88,7 +95,8 @@ where
 		}
 		let nr_blocks = blocks.len();
 
-		let blocks_json = vec![to_json_value(blocks)?];
+		let compressed_blocks = compress_blocks(&blocks).map_err(|e| Error::Custom(e.into()))?;
+		let blocks_json = vec![to_json_value(hex_encode(&compressed_blocks))?];
 		let peers = self
 			.peers
 			.read()
@@ -106,21 +114,23 @@ where
 
 			tokio::spawn(async move {
 				debug!("Broadcasting block to peer with address: {:?}", url);
-				// FIXME: Websocket connection to a worker should stay, once established.
-				let client = match WsClientBuilder::default().build(&url).await {
-					Ok(c) => c,
-					Err(e) => {
-						error!("Failed to create websocket client for block broadcasting (target url: {}): {:?}", url, e);
-						return
-					},
-				};
+				let (url_ref, blocks) = (&url, &blocks);
+				let result = retry_async(CallClass::PeerRequest, || async move {
+					// FIXME: Websocket connection to a worker should stay, once established.
+					let client = WsClientBuilder::default().build(url_ref).await?;
+					client
+						.request::<Vec<u8>>(
+							RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS,
+							blocks.clone().into(),
+						)
+						.await
+				})
+				.await;
 
-				if let Err(e) =
-					client.request::<Vec<u8>>(RPC_METHOD_NAME_IMPORT_BLOCKS, blocks.into()).await
-				{
+				if let Err(e) = result {
 					error!(
 						"Broadcast block request ({}) to {} failed: {:?}",
-						RPC_METHOD_NAME_IMPORT_BLOCKS, url, e
+						RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS, url, e
 					);
 				}
 			});
@@ -182,6 +192,10 @@ where
 				},
 			}
 		}
+		if let Some(run_config) = self.config.run_config() {
+			// Shadow workers are not registered, they only receive the blocks we broadcast.
+			peer_urls.extend(run_config.shadow_peers().iter().cloned());
+		}
 		debug!("found {} peers in shard state for {:?}", peer_urls.len(), shard);
 		Ok(peer_urls)
 	}
@@ -206,7 +220,9 @@ mod tests {
 		worker::{AsyncBlockBroadcaster, Worker},
 	};
 	use frame_support::assert_ok;
+	use itc_rpc_server::block_compression::decompress_blocks;
 	use itp_node_api::node_api_factory::NodeApiFactory;
+	use itp_utils::hex::decode_hex;
 	use its_primitives::types::block::SignedBlock as SignedSidechainBlock;
 	use its_test::sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait};
 	use jsonrpsee::{ws_server::WsServerBuilder, RpcModule};
@@ -223,9 +239,11 @@ mod tests {
 		let mut server = WsServerBuilder::default().build(addr).await?;
 		let mut module = RpcModule::new(());
 
-		module.register_method(RPC_METHOD_NAME_IMPORT_BLOCKS, |params, _| {
-			debug!("{} params: {:?}", RPC_METHOD_NAME_IMPORT_BLOCKS, params);
-			let _blocks: Vec<SignedSidechainBlock> = params.one()?;
+		module.register_method(RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS, |params, _| {
+			debug!("{} params: {:?}", RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS, params);
+			let compressed = decode_hex(params.one::<String>()?).expect("hex encoded blocks");
+			let _blocks: Vec<SignedSidechainBlock> =
+				decompress_blocks(&compressed).expect("compressed blocks");
 			Ok("ok".as_bytes().to_vec())
 		})?;
 
@@ -28,7 +28,7 @@ use itp_settings::sidechain::SLOT_DURATION;
 use itp_sgx_crypto::{key_repository::AccessKey, StateCrypto};
 use itp_sgx_externalities::SgxExternalities;
 use itp_stf_primitives::{traits::TrustedCallVerification, types::TrustedOperationOrHash};
-use itp_stf_state_handler::handle_state::HandleState;
+use itp_stf_state_handler::handle_state::{mutate_unpoisoned, HandleState};
 use itp_top_pool_author::traits::{AuthorApi, OnBlockImported};
 use itp_types::H256;
 pub use its_consensus_common::BlockImport;
@@ -234,7 +234,9 @@ impl<
 
 		// We load a copy of the state and apply the update. In case the update fails, we don't write
 		// the state back to the state handler, and thus guaranteeing state integrity.
-		let updated_state = mutating_function(state)?;
+		let (write_lock, updated_state) =
+			mutate_unpoisoned(write_lock, || mutating_function(state));
+		let updated_state = updated_state?;
 
 		self.state_handler
 			.write_after_mutation(updated_state, write_lock, shard)
@@ -19,9 +19,12 @@ use crate::slot_proposer::{ExternalitiesFor, SlotProposer};
 use codec::Encode;
 use finality_grandpa::BlockNumberOps;
 use ita_stf::{Getter, TrustedCallSigned};
+use itp_node_api::metadata::{provider::AccessNodeMetadata, NodeMetadataTrait};
 use itp_sgx_externalities::{SgxExternalitiesTrait, StateHash};
 use itp_stf_executor::traits::StateUpdateProposer;
-use itp_top_pool_author::traits::AuthorApi;
+use itp_top_pool_author::{
+	load_shedding::LoadShedder, maintenance::MaintenanceSchedule, traits::AuthorApi,
+};
 use itp_types::H256;
 use its_block_composer::ComposeBlock;
 use its_consensus_common::{Environment, Error as ConsensusError};
@@ -38,25 +41,46 @@ use std::{marker::PhantomData, sync::Arc};
 
 ///! `ProposerFactory` instance containing all the data to create the `SlotProposer` for the
 /// next `Slot`.
-pub struct ProposerFactory<ParentchainBlock: Block, TopPoolAuthor, StfExecutor, BlockComposer> {
+pub struct ProposerFactory<
+	ParentchainBlock: Block,
+	TopPoolAuthor,
+	StfExecutor,
+	BlockComposer,
+	NodeMetadataRepository,
+> {
 	top_pool_author: Arc<TopPoolAuthor>,
 	stf_executor: Arc<StfExecutor>,
 	block_composer: Arc<BlockComposer>,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+	maintenance_schedule: Arc<MaintenanceSchedule>,
+	load_shedder: Arc<LoadShedder>,
 	_phantom: PhantomData<ParentchainBlock>,
 }
 
-impl<ParentchainBlock: Block, TopPoolAuthor, StfExecutor, BlockComposer>
-	ProposerFactory<ParentchainBlock, TopPoolAuthor, StfExecutor, BlockComposer>
+impl<
+		ParentchainBlock: Block,
+		TopPoolAuthor,
+		StfExecutor,
+		BlockComposer,
+		NodeMetadataRepository,
+	>
+	ProposerFactory<ParentchainBlock, TopPoolAuthor, StfExecutor, BlockComposer, NodeMetadataRepository>
 {
 	pub fn new(
 		top_pool_executor: Arc<TopPoolAuthor>,
 		stf_executor: Arc<StfExecutor>,
 		block_composer: Arc<BlockComposer>,
+		node_metadata_repo: Arc<NodeMetadataRepository>,
+		maintenance_schedule: Arc<MaintenanceSchedule>,
+		load_shedder: Arc<LoadShedder>,
 	) -> Self {
 		Self {
 			top_pool_author: top_pool_executor,
 			stf_executor,
 			block_composer,
+			node_metadata_repo,
+			maintenance_schedule,
+			load_shedder,
 			_phantom: Default::default(),
 		}
 	}
@@ -68,9 +92,15 @@ impl<
 		TopPoolAuthor,
 		StfExecutor,
 		BlockComposer,
+		NodeMetadataRepository,
 	> Environment<ParentchainBlock, SignedSidechainBlock>
-	for ProposerFactory<ParentchainBlock, TopPoolAuthor, StfExecutor, BlockComposer>
-where
+	for ProposerFactory<
+		ParentchainBlock,
+		TopPoolAuthor,
+		StfExecutor,
+		BlockComposer,
+		NodeMetadataRepository,
+	> where
 	NumberFor<ParentchainBlock>: BlockNumberOps,
 	SignedSidechainBlock: SignedSidechainBlockTrait<Public = sp_core::ed25519::Public, Signature = MultiSignature>
 		+ 'static,
@@ -90,6 +120,8 @@ where
 		> + Send
 		+ Sync
 		+ 'static,
+	NodeMetadataRepository: AccessNodeMetadata + Send + Sync + 'static,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
 {
 	type Proposer = SlotProposer<
 		ParentchainBlock,
@@ -97,6 +129,7 @@ where
 		TopPoolAuthor,
 		StfExecutor,
 		BlockComposer,
+		NodeMetadataRepository,
 	>;
 	type Error = ConsensusError;
 
@@ -109,6 +142,9 @@ where
 			top_pool_author: self.top_pool_author.clone(),
 			stf_executor: self.stf_executor.clone(),
 			block_composer: self.block_composer.clone(),
+			node_metadata_repo: self.node_metadata_repo.clone(),
+			maintenance_schedule: self.maintenance_schedule.clone(),
+			load_shedder: self.load_shedder.clone(),
 			parentchain_header: parent_header,
 			shard,
 			_phantom: PhantomData,
@@ -17,11 +17,14 @@
 
 use codec::Encode;
 use finality_grandpa::BlockNumberOps;
-use ita_stf::{Getter, TrustedCallSigned};
+use ita_stf::{block_hooks, state_migration, Getter, TrustedCallSigned};
+use itp_node_api::metadata::{provider::AccessNodeMetadata, NodeMetadataTrait};
 use itp_sgx_externalities::{SgxExternalitiesTrait, StateHash};
 use itp_stf_executor::traits::StateUpdateProposer;
 use itp_time_utils::now_as_millis;
-use itp_top_pool_author::traits::AuthorApi;
+use itp_top_pool_author::{
+	load_shedding::LoadShedder, maintenance::MaintenanceSchedule, traits::AuthorApi,
+};
 use itp_types::H256;
 use its_block_composer::ComposeBlock;
 use its_consensus_common::{Error as ConsensusError, Proposal, Proposer};
@@ -45,19 +48,35 @@ pub struct SlotProposer<
 	TopPoolAuthor,
 	StfExecutor,
 	BlockComposer,
+	NodeMetadataRepository,
 > {
 	pub(crate) top_pool_author: Arc<TopPoolAuthor>,
 	pub(crate) stf_executor: Arc<StfExecutor>,
 	pub(crate) block_composer: Arc<BlockComposer>,
+	pub(crate) node_metadata_repo: Arc<NodeMetadataRepository>,
+	pub(crate) maintenance_schedule: Arc<MaintenanceSchedule>,
+	pub(crate) load_shedder: Arc<LoadShedder>,
 	pub(crate) parentchain_header: ParentchainBlock::Header,
 	pub(crate) shard: ShardIdentifierFor<SignedSidechainBlock>,
 	pub(crate) _phantom: PhantomData<ParentchainBlock>,
 }
 
-impl<ParentchainBlock, SignedSidechainBlock, TopPoolAuthor, BlockComposer, StfExecutor>
-	Proposer<ParentchainBlock, SignedSidechainBlock>
-	for SlotProposer<ParentchainBlock, SignedSidechainBlock, TopPoolAuthor, StfExecutor, BlockComposer>
-where
+impl<
+		ParentchainBlock,
+		SignedSidechainBlock,
+		TopPoolAuthor,
+		BlockComposer,
+		StfExecutor,
+		NodeMetadataRepository,
+	> Proposer<ParentchainBlock, SignedSidechainBlock>
+	for SlotProposer<
+		ParentchainBlock,
+		SignedSidechainBlock,
+		TopPoolAuthor,
+		StfExecutor,
+		BlockComposer,
+		NodeMetadataRepository,
+	> where
 	ParentchainBlock: Block<Hash = H256>,
 	NumberFor<ParentchainBlock>: BlockNumberOps,
 	SignedSidechainBlock: SignedSidechainBlockTrait<Public = sp_core::ed25519::Public, Signature = MultiSignature>
@@ -78,11 +97,14 @@ where
 		> + Send
 		+ Sync
 		+ 'static,
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
 {
 	/// Proposes a new sidechain block.
 	///
 	/// This includes the following steps:
-	/// 1) Retrieve all trusted calls from the top pool.
+	/// 1) Retrieve all trusted calls from the top pool, unless the shard is under maintenance or
+	///    overloaded.
 	/// 2) Calculate a new state that will be proposed in the sidechain block.
 	/// 3) Compose the sidechain block and the parentchain confirmation.
 	fn propose(
@@ -92,13 +114,35 @@ where
 		let latest_parentchain_header = &self.parentchain_header;
 
 		// 1) Retrieve trusted calls from top pool.
-		let trusted_calls = self.top_pool_author.get_pending_trusted_calls(self.shard);
+		let mut trusted_calls = self.top_pool_author.get_pending_trusted_calls(self.shard);
+
+		// The calls stay in the pool until the maintenance window has ended, or the load has
+		// decreased. The block hooks are still executed.
+		if !trusted_calls.is_empty()
+			&& (self.maintenance_schedule.is_under_maintenance(&self.shard, now_as_millis())
+				|| self.load_shedder.is_paused(&self.shard))
+		{
+			debug!(
+				"Deferring {} trusted calls of shard {:?} under maintenance or overload",
+				trusted_calls.len(),
+				self.shard
+			);
+			let deferred: Vec<_> =
+				trusted_calls.iter().map(|call| self.top_pool_author.hash_of(call)).collect();
+			self.top_pool_author.on_calls_deferred(&deferred);
+			trusted_calls.clear();
+		}
 
 		if !trusted_calls.is_empty() {
-			debug!("Got following trusted calls from pool: {:?}", trusted_calls);
+			// Only the hashes are logged, calls may carry contents that must never leave the enclave.
+			debug!(
+				"Got trusted calls with the following hashes from pool: {:?}",
+				trusted_calls.iter().map(|call| call.hash()).collect::<Vec<_>>()
+			);
 		}
 
-		// 2) Execute trusted calls.
+		// 2) Execute the block hooks and trusted calls.
+		let mut block_hook_calls = Vec::new();
 		let batch_execution_result = self
 			.stf_executor
 			.propose_state_update(
@@ -111,12 +155,21 @@ where
 					sidechain_db
 						.set_block_number(&sidechain_db.get_block_number().map_or(1, |n| n + 1));
 					sidechain_db.set_timestamp(&now_as_millis());
+					// Migrations run first, so the block hooks already operate on the migrated state.
+					state_migration::run_due_migrations(&mut sidechain_db);
+					sidechain_db.execute_with(|| {
+						block_hooks::on_initialize(
+							&mut block_hook_calls,
+							self.node_metadata_repo.clone(),
+						)
+					});
 					sidechain_db
 				},
 			)
 			.map_err(|e| ConsensusError::Other(e.to_string().into()))?;
 
-		let parentchain_extrinsics = batch_execution_result.get_extrinsic_callbacks();
+		let mut parentchain_extrinsics = block_hook_calls;
+		parentchain_extrinsics.extend(batch_execution_result.get_extrinsic_callbacks());
 
 		let executed_operation_hashes: Vec<_> =
 			batch_execution_result.get_executed_operation_hashes().to_vec();
@@ -20,15 +20,21 @@ use itc_parentchain_light_client::{
 	concurrent_access::ValidatorAccess, BlockNumberOps, ExtrinsicSender, NumberFor,
 };
 use itp_extrinsics_factory::CreateExtrinsics;
-use itp_node_api_metadata::{pallet_sidechain::SidechainCallIndexes, NodeMetadataTrait};
+use itp_node_api_metadata::{
+	pallet_enclave_bridge::EnclaveBridgeCallIndexes, pallet_sidechain::SidechainCallIndexes,
+	NodeMetadataTrait,
+};
 use itp_node_api_metadata_provider::AccessNodeMetadata;
 use itp_settings::worker::BLOCK_NUMBER_FINALIZATION_DIFF;
-use itp_types::{parentchain::SidechainBlockConfirmation, OpaqueCall, ShardIdentifier};
+use itp_types::{parentchain::SidechainBlockConfirmation, OpaqueCall, ShardIdentifier, H256};
 use its_primitives::traits::Header as HeaderTrait;
 use log::*;
 use sp_runtime::traits::Block as ParentchainBlockTrait;
 use std::{marker::PhantomData, sync::Arc};
 
+/// Data of the hash published with a block import confirmation, the topic is the shard.
+pub const SHARD_CONFIGURATION_DATA: &[u8] = b"shard configuration";
+
 /// Trait to confirm a sidechain block import.
 pub trait ConfirmBlockImport<SidechainHeader> {
 	fn confirm_import(
@@ -39,17 +45,27 @@ pub trait ConfirmBlockImport<SidechainHeader> {
 	) -> Result<()>;
 }
 
+/// Trait to hash the effective configuration of a shard, as seen by this validateer.
+pub trait HashShardConfiguration {
+	fn shard_configuration_hash(&self, shard: &ShardIdentifier) -> Result<H256>;
+}
+
 /// Creates and sends a sidechain block import confirmation extrsinic to the parentchain.
+///
+/// The hash of the shard configuration is published along with each confirmation, so clients
+/// can detect validateers running with a divergent configuration.
 pub struct BlockImportConfirmationHandler<
 	ParentchainBlock,
 	SidechainHeader,
 	NodeMetadataRepository,
 	ExtrinsicsFactory,
 	ValidatorAccessor,
+	ConfigurationHasher,
 > {
 	metadata_repository: Arc<NodeMetadataRepository>,
 	extrinsics_factory: Arc<ExtrinsicsFactory>,
 	validator_accessor: Arc<ValidatorAccessor>,
+	configuration_hasher: Arc<ConfigurationHasher>,
 	_phantom: PhantomData<(ParentchainBlock, SidechainHeader)>,
 }
 
@@ -59,6 +75,7 @@ impl<
 		NodeMetadataRepository,
 		ExtrinsicsFactory,
 		ValidatorAccessor,
+		ConfigurationHasher,
 	>
 	BlockImportConfirmationHandler<
 		ParentchainBlock,
@@ -66,17 +83,20 @@ impl<
 		NodeMetadataRepository,
 		ExtrinsicsFactory,
 		ValidatorAccessor,
+		ConfigurationHasher,
 	>
 {
 	pub fn new(
 		metadata_repository: Arc<NodeMetadataRepository>,
 		extrinsics_factory: Arc<ExtrinsicsFactory>,
 		validator_accessor: Arc<ValidatorAccessor>,
+		configuration_hasher: Arc<ConfigurationHasher>,
 	) -> Self {
 		Self {
 			metadata_repository,
 			extrinsics_factory,
 			validator_accessor,
+			configuration_hasher,
 			_phantom: Default::default(),
 		}
 	}
@@ -88,6 +108,7 @@ impl<
 		NodeMetadataRepository,
 		ExtrinsicsFactory,
 		ValidatorAccessor,
+		ConfigurationHasher,
 	> ConfirmBlockImport<SidechainHeader>
 	for BlockImportConfirmationHandler<
 		ParentchainBlock,
@@ -95,6 +116,7 @@ impl<
 		NodeMetadataRepository,
 		ExtrinsicsFactory,
 		ValidatorAccessor,
+		ConfigurationHasher,
 	> where
 	ParentchainBlock: ParentchainBlockTrait,
 	NumberFor<ParentchainBlock>: BlockNumberOps,
@@ -103,6 +125,7 @@ impl<
 	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
 	ExtrinsicsFactory: CreateExtrinsics,
 	ValidatorAccessor: ValidatorAccess<ParentchainBlock> + Send + Sync + 'static,
+	ConfigurationHasher: HashShardConfiguration,
 {
 	fn confirm_import(
 		&self,
@@ -128,9 +151,30 @@ impl<
 				},
 			));
 
+			let mut calls = vec![opaque_call];
+			// The configuration hash is optional, it must never hold back the confirmation.
+			match self.configuration_hasher.shard_configuration_hash(shard) {
+				Ok(configuration_hash) => match self
+					.metadata_repository
+					.get_from_metadata(|m| m.publish_hash_call_indexes())
+				{
+					Ok(Ok(publish_hash_call)) => calls.push(OpaqueCall::from_tuple(&(
+						publish_hash_call,
+						configuration_hash,
+						vec![*shard],
+						SHARD_CONFIGURATION_DATA.to_vec(),
+					))),
+					e => warn!(
+						"Not publishing the configuration hash, publish_hash call is unavailable: {:?}",
+						e
+					),
+				},
+				Err(e) => warn!("Could not hash the configuration of shard {:?}: {:?}", shard, e),
+			}
+
 			let xts = self
 				.extrinsics_factory
-				.create_extrinsics(&[opaque_call], None)
+				.create_extrinsics(&calls, None)
 				.map_err(|e| Error::Other(e.into()))?;
 
 			debug!("Sending sidechain block import confirmation extrinsic..");
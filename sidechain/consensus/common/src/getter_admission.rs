@@ -0,0 +1,97 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Admission of getters while a block is authored.
+//!
+//! From the start of the block production of a slot until it is done, non-urgent getters are
+//! deferred with a hint when to retry, so the state locks and the CPU are left to the authoring.
+//! In between slots, all getters are admitted.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "sgx")]
+use std::sync::SgxMutex as Mutex;
+
+use core::time::Duration;
+use log::*;
+
+/// Lower bound of the retry hint, so clients don't retry right away at the end of a slot.
+pub const MIN_GETTER_RETRY_AFTER: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+pub struct GetterAdmission {
+	/// End of the slot that is currently authored.
+	authoring_until: Mutex<Option<Duration>>,
+}
+
+impl GetterAdmission {
+	/// Defers the non-urgent getters until `end_authoring` is called or the slot ends, whichever
+	/// happens first. The latter covers an authoring that fails half way.
+	pub fn begin_authoring(&self, slot_ends_at: Duration) {
+		match self.authoring_until.lock() {
+			Ok(mut authoring_until) => *authoring_until = Some(slot_ends_at),
+			Err(_) => error!("Getter admission lock is poisoned"),
+		}
+	}
+
+	pub fn end_authoring(&self) {
+		match self.authoring_until.lock() {
+			Ok(mut authoring_until) => *authoring_until = None,
+			Err(_) => error!("Getter admission lock is poisoned"),
+		}
+	}
+
+	/// Returns the time after which a deferred getter should be retried.
+	pub fn admit(&self, urgent: bool, now: Duration) -> Result<(), Duration> {
+		if urgent {
+			return Ok(())
+		}
+		let authoring_until = match self.authoring_until.lock() {
+			Ok(authoring_until) => *authoring_until,
+			Err(_) => return Ok(()),
+		};
+		match authoring_until {
+			Some(slot_ends_at) if slot_ends_at > now =>
+				Err((slot_ends_at - now).max(MIN_GETTER_RETRY_AFTER)),
+			_ => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_urgent_getters_are_admitted_while_authoring() {
+		let admission = GetterAdmission::default();
+		let now = Duration::from_millis(1_000);
+		assert_eq!(admission.admit(false, now), Ok(()));
+
+		admission.begin_authoring(Duration::from_millis(1_300));
+		assert_eq!(admission.admit(true, now), Ok(()));
+		assert_eq!(admission.admit(false, now), Err(Duration::from_millis(300)));
+		assert_eq!(
+			admission.admit(false, Duration::from_millis(1_280)),
+			Err(MIN_GETTER_RETRY_AFTER)
+		);
+		assert_eq!(admission.admit(false, Duration::from_millis(1_300)), Ok(()));
+
+		admission.end_authoring();
+		assert_eq!(admission.admit(false, now), Ok(()));
+	}
+}
@@ -36,8 +36,12 @@ mod block_import;
 mod block_import_confirmation_handler;
 mod block_import_queue_worker;
 mod error;
+mod getter_admission;
 mod header_db;
 mod peer_block_sync;
+mod shard_isolation;
+mod sla_metrics;
+mod stale_shard_detection;
 
 // The feature flag will be removed once we use the module outside of tests.
 #[cfg(test)]
@@ -50,8 +54,12 @@ pub use block_import::*;
 pub use block_import_confirmation_handler::*;
 pub use block_import_queue_worker::*;
 pub use error::*;
+pub use getter_admission::*;
 use itp_types::parentchain::ParentchainCall;
 pub use peer_block_sync::*;
+pub use shard_isolation::*;
+pub use sla_metrics::*;
+pub use stale_shard_detection::*;
 
 pub trait Verifier<ParentchainBlock, SignedSidechainBlock>: Send + Sync
 where
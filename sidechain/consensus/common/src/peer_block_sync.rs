@@ -53,6 +53,16 @@ where
 		sidechain_block: SignedSidechainBlock,
 		last_imported_parentchain_header: &ParentchainHeader,
 	) -> Result<ParentchainHeader>;
+
+	/// Fetch the blocks following `last_imported_sidechain_block_hash` up to and including
+	/// `import_until_block_hash` from a peer and import them.
+	fn sync_from_peer(
+		&self,
+		last_imported_sidechain_block_hash: BlockHash,
+		import_until_block_hash: BlockHash,
+		last_imported_parentchain_header: &ParentchainHeader,
+		shard_identifier: ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<ParentchainHeader>;
 }
 
 /// Sidechain peer block sync implementation.
@@ -236,6 +246,20 @@ where
 		self.importer.import_block(sidechain_block, current_parentchain_header)
 	}
 
+	fn sync_from_peer(
+		&self,
+		last_imported_sidechain_block_hash: BlockHash,
+		import_until_block_hash: BlockHash,
+		current_parentchain_header: &ParentchainBlock::Header,
+		shard_identifier: ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<ParentchainBlock::Header> {
+		self.fetch_and_import_blocks_from_peer(
+			last_imported_sidechain_block_hash,
+			import_until_block_hash,
+			current_parentchain_header,
+			shard_identifier,
+		)
+	}
 }
 
 #[cfg(test)]
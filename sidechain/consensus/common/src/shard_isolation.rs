@@ -0,0 +1,114 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+//! Shards disabled after an unrecoverable error, according to the [`PanicPolicy`].
+//!
+//! An isolated shard is neither imported nor authored for, and its getters and trusted calls are
+//! rejected, until the worker is restarted. The isolated shards are listed by the
+//! `author_getIsolatedShards` RPC. A corrupt state of one shard thereby doesn't take the other
+//! shards of the enclave down with it.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "sgx")]
+use std::sync::SgxMutex as Mutex;
+
+use itp_types::{panic_policy::PanicPolicy, ShardIdentifier};
+use log::*;
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+#[derive(Default)]
+struct Isolation {
+	policy: PanicPolicy,
+	/// Isolated shards with the error that caused the isolation.
+	isolated_shards: BTreeMap<ShardIdentifier, String>,
+}
+
+#[derive(Default)]
+pub struct ShardIsolation {
+	isolation: Mutex<Isolation>,
+}
+
+impl ShardIsolation {
+	pub fn set_policy(&self, policy: PanicPolicy) {
+		info!("Panic policy: {:?}", policy);
+		match self.isolation.lock() {
+			Ok(mut isolation) => isolation.policy = policy,
+			Err(_) => error!("Shard isolation lock is poisoned"),
+		}
+	}
+
+	pub fn policy(&self) -> PanicPolicy {
+		self.isolation.lock().map_or(PanicPolicy::Halt, |isolation| isolation.policy)
+	}
+
+	/// Isolates the shard if the policy allows it. Returns false if the enclave has to halt
+	/// instead.
+	pub fn isolate(&self, shard: &ShardIdentifier, reason: String) -> bool {
+		let mut isolation = match self.isolation.lock() {
+			Ok(isolation) => isolation,
+			Err(_) => {
+				error!("Shard isolation lock is poisoned");
+				return false
+			},
+		};
+		if isolation.policy == PanicPolicy::Halt {
+			return false
+		}
+		error!("Isolating shard {:?} until the worker is restarted: {}", shard, reason);
+		isolation.isolated_shards.insert(*shard, reason);
+		true
+	}
+
+	pub fn is_isolated(&self, shard: &ShardIdentifier) -> bool {
+		self.isolation
+			.lock()
+			.map_or(false, |isolation| isolation.isolated_shards.contains_key(shard))
+	}
+
+	pub fn isolated_shards(&self) -> Vec<(ShardIdentifier, String)> {
+		self.isolation.lock().map_or_else(
+			|_| Vec::new(),
+			|isolation| {
+				isolation
+					.isolated_shards
+					.iter()
+					.map(|(shard, reason)| (*shard, reason.clone()))
+					.collect()
+			},
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn shard_is_only_isolated_with_the_isolate_shard_policy() {
+		let isolation = ShardIsolation::default();
+		let shard = ShardIdentifier::repeat_byte(1);
+
+		assert!(!isolation.isolate(&shard, "undecodable state".into()));
+		assert!(!isolation.is_isolated(&shard));
+
+		isolation.set_policy(PanicPolicy::IsolateShard);
+		assert!(isolation.isolate(&shard, "undecodable state".into()));
+		assert!(isolation.is_isolated(&shard));
+		assert!(!isolation.is_isolated(&ShardIdentifier::repeat_byte(2)));
+		assert_eq!(isolation.isolated_shards(), vec![(shard, "undecodable state".into())]);
+	}
+}
@@ -0,0 +1,189 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Accounting of the SLA metrics of the enclave over fixed periods of parentchain blocks.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "sgx")]
+use std::sync::SgxMutex as Mutex;
+
+use itp_types::{
+	sla_metrics::{SignedSlaMetrics, SlaMetrics},
+	ShardIdentifier,
+};
+use log::*;
+
+struct Period {
+	start_block: u64,
+	slots: u64,
+	blocks_authored: u64,
+	missed_slots: u64,
+}
+
+impl Period {
+	fn new(start_block: u64) -> Self {
+		Period { start_block, slots: 0, blocks_authored: 0, missed_slots: 0 }
+	}
+}
+
+#[derive(Default)]
+struct Accounting {
+	started_at_block: Option<u64>,
+	/// Parentchain block of the last recorded slot.
+	last_block: Option<u64>,
+	sequence: u64,
+	period: Option<Period>,
+	latest: Option<SignedSlaMetrics>,
+}
+
+/// Outcome of a slot of the enclave.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotOutcome {
+	/// Latest imported parentchain block in the slot, none if the slot has failed before.
+	pub parentchain_block: Option<u64>,
+	pub blocks_authored: u64,
+	/// Whether the slot didn't produce the blocks it should have.
+	pub missed: bool,
+}
+
+pub struct SlaMetricsRecorder {
+	period_length_blocks: u64,
+	accounting: Mutex<Accounting>,
+}
+
+impl SlaMetricsRecorder {
+	pub fn new(period_length_blocks: u64) -> Self {
+		SlaMetricsRecorder { period_length_blocks, accounting: Default::default() }
+	}
+
+	/// Records the outcome of a slot. A slot without a parentchain block counts at the block of
+	/// the previous slot, and is dropped if there hasn't been any. Returns the metrics of the
+	/// period once it has lasted the period length, the next slot starts a new period.
+	pub fn on_slot_end(
+		&self,
+		mrenclave: [u8; 32],
+		shard: &ShardIdentifier,
+		outcome: SlotOutcome,
+	) -> Option<SlaMetrics> {
+		let mut accounting = match self.accounting.lock() {
+			Ok(accounting) => accounting,
+			Err(_) => {
+				error!("SLA metrics lock is poisoned");
+				return None
+			},
+		};
+		let block = match outcome.parentchain_block.or(accounting.last_block) {
+			Some(block) => block.max(accounting.last_block.unwrap_or_default()),
+			None => {
+				warn!("Dropping the outcome of a slot before any parentchain block is known");
+				return None
+			},
+		};
+		accounting.last_block = Some(block);
+		let started_at_block = *accounting.started_at_block.get_or_insert(block);
+		let period = accounting.period.get_or_insert_with(|| Period::new(block));
+		period.slots += 1;
+		period.blocks_authored += outcome.blocks_authored;
+		if outcome.missed {
+			period.missed_slots += 1;
+		}
+		if block.saturating_sub(period.start_block) < self.period_length_blocks {
+			return None
+		}
+
+		let period = accounting.period.take()?;
+		let sequence = accounting.sequence;
+		accounting.sequence += 1;
+		Some(SlaMetrics {
+			mrenclave,
+			shard: *shard,
+			sequence,
+			enclave_started_at_block: started_at_block,
+			period_start_block: period.start_block,
+			period_end_block: block,
+			slots: period.slots,
+			blocks_authored: period.blocks_authored,
+			missed_slots: period.missed_slots,
+		})
+	}
+
+	/// Replaces the signed digest of the last closed period.
+	pub fn set_latest(&self, signed_metrics: SignedSlaMetrics) {
+		match self.accounting.lock() {
+			Ok(mut accounting) => accounting.latest = Some(signed_metrics),
+			Err(_) => error!("SLA metrics lock is poisoned"),
+		}
+	}
+
+	pub fn latest(&self) -> Option<SignedSlaMetrics> {
+		self.accounting.lock().ok().and_then(|accounting| accounting.latest.clone())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn outcome(parentchain_block: Option<u64>, blocks_authored: u64, missed: bool) -> SlotOutcome {
+		SlotOutcome { parentchain_block, blocks_authored, missed }
+	}
+
+	#[test]
+	fn period_is_closed_once_it_has_lasted_the_period_length() {
+		let recorder = SlaMetricsRecorder::new(3);
+		let shard = ShardIdentifier::repeat_byte(1);
+
+		assert_eq!(recorder.on_slot_end([2u8; 32], &shard, outcome(Some(10), 1, false)), None);
+		assert_eq!(recorder.on_slot_end([2u8; 32], &shard, outcome(Some(11), 0, true)), None);
+		let metrics = recorder.on_slot_end([2u8; 32], &shard, outcome(Some(13), 1, false)).unwrap();
+
+		assert_eq!(metrics.sequence, 0);
+		assert_eq!((metrics.period_start_block, metrics.period_end_block), (10, 13));
+		assert_eq!((metrics.slots, metrics.blocks_authored, metrics.missed_slots), (3, 2, 1));
+	}
+
+	#[test]
+	fn next_period_starts_with_the_next_slot() {
+		let recorder = SlaMetricsRecorder::new(1);
+		let shard = ShardIdentifier::repeat_byte(1);
+		recorder.on_slot_end([2u8; 32], &shard, outcome(Some(10), 1, false));
+		recorder.on_slot_end([2u8; 32], &shard, outcome(Some(11), 1, false)).unwrap();
+
+		assert_eq!(recorder.on_slot_end([2u8; 32], &shard, outcome(Some(12), 0, true)), None);
+		let metrics = recorder.on_slot_end([2u8; 32], &shard, outcome(Some(13), 0, false)).unwrap();
+
+		assert_eq!(metrics.sequence, 1);
+		assert_eq!(metrics.enclave_started_at_block, 10);
+		assert_eq!(metrics.uptime_blocks(), 3);
+		assert_eq!((metrics.slots, metrics.blocks_authored, metrics.missed_slots), (2, 0, 1));
+	}
+
+	#[test]
+	fn failed_slot_counts_at_the_block_of_the_previous_slot() {
+		let recorder = SlaMetricsRecorder::new(2);
+		let shard = ShardIdentifier::repeat_byte(1);
+
+		assert_eq!(recorder.on_slot_end([2u8; 32], &shard, outcome(None, 0, true)), None);
+		recorder.on_slot_end([2u8; 32], &shard, outcome(Some(10), 1, false));
+		recorder.on_slot_end([2u8; 32], &shard, outcome(None, 0, true));
+		let metrics = recorder.on_slot_end([2u8; 32], &shard, outcome(Some(12), 1, false)).unwrap();
+
+		assert_eq!(metrics.period_start_block, 10);
+		assert_eq!((metrics.slots, metrics.blocks_authored, metrics.missed_slots), (3, 2, 1));
+	}
+}
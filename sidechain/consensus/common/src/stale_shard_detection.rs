@@ -0,0 +1,141 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Detection of shards whose state lags the sidechain blocks confirmed on the parentchain.
+//!
+//! A validateer that missed blocks, e.g. while it was offline, must not author blocks on top of
+//! its outdated state. A shard is stale while the last imported sidechain block lags the last
+//! confirmed block by more than the threshold.
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "sgx")]
+use std::sync::SgxMutex as Mutex;
+
+use itp_settings::{sidechain::STALE_SHARD_THRESHOLD, worker::BLOCK_NUMBER_FINALIZATION_DIFF};
+use itp_types::{parentchain::SidechainBlockConfirmation, ShardIdentifier};
+use log::*;
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+struct Staleness {
+	threshold: Option<u64>,
+	stale_shards: BTreeSet<ShardIdentifier>,
+}
+
+/// Uses [`STALE_SHARD_THRESHOLD`], until a threshold is set.
+#[derive(Default)]
+pub struct StaleShardDetector {
+	staleness: Mutex<Staleness>,
+}
+
+impl StaleShardDetector {
+	/// Threshold in sidechain blocks.
+	pub fn set_threshold(&self, threshold: u64) {
+		info!("Stale shard threshold: {} sidechain blocks", threshold);
+		match self.staleness.lock() {
+			Ok(mut staleness) => staleness.threshold = Some(threshold),
+			Err(_) => error!("Stale shard detection lock is poisoned"),
+		}
+	}
+
+	/// Compares the number of the last imported sidechain block with the last confirmed one and
+	/// updates the staleness of the shard. Returns the lag in blocks if the shard is stale.
+	pub fn update(
+		&self,
+		shard: &ShardIdentifier,
+		last_imported_block_number: Option<u64>,
+		maybe_latest_sidechain_block_confirmation: &Option<SidechainBlockConfirmation>,
+	) -> Option<u64> {
+		let lag = maybe_latest_sidechain_block_confirmation.as_ref().map_or(0, |confirmation| {
+			// The confirmation carries the number of the next block to be confirmed.
+			confirmation
+				.block_number
+				.saturating_sub(BLOCK_NUMBER_FINALIZATION_DIFF)
+				.saturating_sub(last_imported_block_number.unwrap_or(0))
+		});
+		let mut staleness = match self.staleness.lock() {
+			Ok(staleness) => staleness,
+			Err(_) => {
+				error!("Stale shard detection lock is poisoned");
+				return None
+			},
+		};
+		if lag > staleness.threshold.unwrap_or(STALE_SHARD_THRESHOLD) {
+			if staleness.stale_shards.insert(*shard) {
+				warn!("Shard {:?} is stale, it lags the parentchain by {} blocks", shard, lag);
+			}
+			return Some(lag)
+		}
+		if staleness.stale_shards.remove(shard) {
+			info!("Shard {:?} has caught up with the parentchain", shard);
+		}
+		None
+	}
+
+	pub fn is_stale(&self, shard: &ShardIdentifier) -> bool {
+		self.staleness
+			.lock()
+			.map_or(false, |staleness| staleness.stale_shards.contains(shard))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_types::H256;
+
+	fn confirmation(confirmed_block_number: u64) -> Option<SidechainBlockConfirmation> {
+		Some(SidechainBlockConfirmation {
+			block_number: confirmed_block_number + BLOCK_NUMBER_FINALIZATION_DIFF,
+			block_header_hash: H256::random(),
+		})
+	}
+
+	#[test]
+	fn shard_lagging_more_than_the_threshold_is_stale_until_it_caught_up() {
+		let detector = StaleShardDetector::default();
+		detector.set_threshold(10);
+		let shard = ShardIdentifier::repeat_byte(1);
+
+		assert_eq!(detector.update(&shard, Some(89), &confirmation(100)), Some(11));
+		assert!(detector.is_stale(&shard));
+
+		assert_eq!(detector.update(&shard, Some(90), &confirmation(100)), None);
+		assert!(!detector.is_stale(&shard));
+	}
+
+	#[test]
+	fn shard_without_confirmation_is_not_stale() {
+		let detector = StaleShardDetector::default();
+		let shard = ShardIdentifier::repeat_byte(1);
+
+		assert_eq!(detector.update(&shard, None, &None), None);
+		assert!(!detector.is_stale(&shard));
+	}
+
+	#[test]
+	fn shard_without_imported_blocks_lags_by_all_confirmed_blocks() {
+		let detector = StaleShardDetector::default();
+		let shard = ShardIdentifier::repeat_byte(1);
+
+		assert_eq!(
+			detector.update(&shard, None, &confirmation(STALE_SHARD_THRESHOLD + 1)),
+			Some(STALE_SHARD_THRESHOLD + 1)
+		);
+	}
+}
@@ -0,0 +1,212 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Watch-only registrations of the deposit accounts of exchanges, see
+//! [`itp_types::account_watch`].
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use codec::Encode;
+use core::sync::atomic::{AtomicU64, Ordering};
+use itp_types::{account_watch::SignedAccountWatchRequest, AccountId, ShardIdentifier, H256};
+use log::*;
+use sp_core::blake2_256;
+use std::{collections::BTreeMap, format, string::String, vec::Vec};
+
+/// Maximum number of accounts watched by one registration.
+pub const MAX_WATCHED_ACCOUNTS: usize = 1_000;
+
+/// Maximum number of registrations watched at the same time.
+pub const MAX_ACCOUNT_WATCHES: usize = 100;
+
+/// Maximum number of registrations watched on one connection at the same time, so a single
+/// client cannot take up all of the registrations.
+pub const MAX_ACCOUNT_WATCHES_PER_CONNECTION: usize = 4;
+
+#[derive(Default)]
+pub struct AccountWatches {
+	/// Shard and accounts of every registration, by the hash of its watching connection.
+	watches: RwLock<BTreeMap<H256, (ShardIdentifier, Vec<AccountId>)>>,
+	/// Last sidechain block of every shard whose incoming transfers have been notified.
+	last_notified_blocks: RwLock<BTreeMap<ShardIdentifier, u64>>,
+	/// Number of registrations so far, a repeated request gets a connection of its own.
+	registrations: AtomicU64,
+}
+
+impl AccountWatches {
+	/// Registers the accounts of the request, returns the hash to watch the connection under.
+	pub fn register(
+		&self,
+		signed_request: &SignedAccountWatchRequest,
+		now_millis: u64,
+	) -> Result<H256, String> {
+		let request = &signed_request.request;
+		if request.valid_until < now_millis {
+			return Err("Account watch request has expired".into())
+		}
+		if request.accounts.is_empty() || request.accounts.len() > MAX_WATCHED_ACCOUNTS {
+			return Err(format!(
+				"Account watch request must contain between 1 and {} accounts",
+				MAX_WATCHED_ACCOUNTS
+			))
+		}
+		if !signed_request.verify_ownership() {
+			return Err("Account watch request is not signed by all of its accounts".into())
+		}
+
+		let mut watches = self.watches.write().unwrap_or_else(|e| e.into_inner());
+		if watches.len() >= MAX_ACCOUNT_WATCHES {
+			return Err("Too many account watches registered".into())
+		}
+		let registration = self.registrations.fetch_add(1, Ordering::SeqCst);
+		let watch_hash = H256::from(blake2_256(&(request, registration).encode()));
+		info!(
+			"Watching {} accounts of shard {:?} under {:?}",
+			request.accounts.len(),
+			request.shard,
+			watch_hash
+		);
+		watches.insert(watch_hash, (request.shard, request.accounts.clone()));
+		Ok(watch_hash)
+	}
+
+	pub fn unregister(&self, watch_hash: &H256) {
+		let mut watches = self.watches.write().unwrap_or_else(|e| e.into_inner());
+		if watches.remove(watch_hash).is_some() {
+			debug!("Removed account watch {:?}", watch_hash);
+		}
+	}
+
+	pub fn is_registered(&self, watch_hash: &H256) -> bool {
+		self.watches.read().unwrap_or_else(|e| e.into_inner()).contains_key(watch_hash)
+	}
+
+	/// Removes the registrations for which `keep` doesn't hold, e.g. the ones whose connection
+	/// has been closed. Returns the number of removed registrations.
+	pub fn retain<F: Fn(&H256) -> bool>(&self, keep: F) -> usize {
+		let mut watches = self.watches.write().unwrap_or_else(|e| e.into_inner());
+		let registration_count = watches.len();
+		watches.retain(|watch_hash, _| keep(watch_hash));
+		registration_count - watches.len()
+	}
+
+	/// Hashes of the connections watching the account.
+	pub fn watchers_of(&self, shard: &ShardIdentifier, account: &AccountId) -> Vec<H256> {
+		let watches = self.watches.read().unwrap_or_else(|e| e.into_inner());
+		watches
+			.iter()
+			.filter(|(_, (watched_shard, accounts))| {
+				watched_shard == shard && accounts.contains(account)
+			})
+			.map(|(watch_hash, _)| *watch_hash)
+			.collect()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.watches.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+	}
+
+	/// Records the sidechain block as notified, returns false if it has been notified before.
+	pub fn mark_notified(&self, shard: ShardIdentifier, sidechain_block: u64) -> bool {
+		let mut last_notified_blocks =
+			self.last_notified_blocks.write().unwrap_or_else(|e| e.into_inner());
+		match last_notified_blocks.get(&shard) {
+			Some(last_notified) if *last_notified >= sidechain_block => false,
+			_ => {
+				last_notified_blocks.insert(shard, sidechain_block);
+				true
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_types::{account_watch::AccountWatchRequest, Signature};
+	use sp_core::{sr25519, Pair};
+
+	#[test]
+	fn only_owned_accounts_are_watched() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let bob = sr25519::Pair::from_seed(&[2u8; 32]);
+		let request = AccountWatchRequest {
+			shard,
+			accounts: vec![alice.public().into(), bob.public().into()],
+			valid_until: 10,
+		};
+		let alice_proof: Signature = alice.sign(&request.encode()).into();
+		let bob_proof: Signature = bob.sign(&request.encode()).into();
+		let watches = AccountWatches::default();
+
+		let forged_request = SignedAccountWatchRequest {
+			request: request.clone(),
+			proofs: vec![alice_proof.clone(), alice_proof.clone()],
+		};
+		assert!(watches.register(&forged_request, 0).is_err());
+
+		let signed_request =
+			SignedAccountWatchRequest { request, proofs: vec![alice_proof, bob_proof] };
+		assert!(watches.register(&signed_request, 11).is_err());
+		let watch_hash = watches.register(&signed_request, 0).unwrap();
+
+		assert_eq!(watches.watchers_of(&shard, &bob.public().into()), vec![watch_hash]);
+		assert!(watches
+			.watchers_of(&ShardIdentifier::repeat_byte(2), &bob.public().into())
+			.is_empty());
+
+		watches.unregister(&watch_hash);
+		assert!(watches.is_empty());
+	}
+
+	#[test]
+	fn registrations_without_connection_are_removed() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let request = AccountWatchRequest {
+			shard: ShardIdentifier::repeat_byte(1),
+			accounts: vec![alice.public().into()],
+			valid_until: 10,
+		};
+		let proof: Signature = alice.sign(&request.encode()).into();
+		let signed_request = SignedAccountWatchRequest { request, proofs: vec![proof] };
+		let watches = AccountWatches::default();
+		let watch_hash = watches.register(&signed_request, 0).unwrap();
+		let other_watch_hash = watches.register(&signed_request, 0).unwrap();
+
+		assert!(watches.is_registered(&watch_hash));
+		assert_eq!(watches.retain(|_| true), 0);
+		assert_eq!(watches.retain(|hash| *hash != watch_hash), 1);
+		assert!(!watches.is_registered(&watch_hash));
+		assert!(watches.is_registered(&other_watch_hash));
+	}
+
+	#[test]
+	fn sidechain_blocks_are_notified_once() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let watches = AccountWatches::default();
+
+		assert!(watches.mark_notified(shard, 3));
+		assert!(!watches.mark_notified(shard, 3));
+		assert!(!watches.mark_notified(shard, 2));
+		assert!(watches.mark_notified(shard, 4));
+	}
+}
@@ -19,4 +19,5 @@
 
 // RPC method names.
 pub const RPC_METHOD_NAME_IMPORT_BLOCKS: &str = "sidechain_importBlock";
+pub const RPC_METHOD_NAME_IMPORT_COMPRESSED_BLOCKS: &str = "sidechain_importCompressedBlocks";
 pub const RPC_METHOD_NAME_FETCH_BLOCKS_FROM_PEER: &str = "sidechain_fetchBlocksFromPeer";
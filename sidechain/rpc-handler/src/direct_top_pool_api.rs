@@ -25,20 +25,26 @@ use rust_base58::base58::FromBase58;
 #[cfg(feature = "sgx")]
 use base58::FromBase58;
 
+use crate::shard_routing::{compute_hex_encoded_redirect, Routed, ShardRoutes};
 use codec::{Decode, Encode};
-use itp_rpc::RpcReturnValue;
+use itp_rpc::{error_code, RpcReturnValue};
 use itp_stf_primitives::types::AccountId;
 use itp_top_pool_author::traits::AuthorApi;
 use itp_types::{DirectRequestStatus, Request, ShardIdentifier, TrustedOperationStatus};
 use itp_utils::{FromHexPrefixed, ToHexPrefixed};
-use jsonrpc_core::{futures::executor, serde_json::json, Error as RpcError, IoHandler, Params};
+use jsonrpc_core::{
+	futures::executor, serde_json::json, Error as RpcError, ErrorCode, IoHandler, Params,
+};
 use log::*;
 use std::{borrow::ToOwned, format, string::String, sync::Arc, vec, vec::Vec};
 
 type Hash = sp_core::H256;
 
+/// Like the RPC methods of the enclave, the methods fail with the stable error code of the failing
+/// domain, see [`error_code`].
 pub fn add_top_pool_direct_rpc_methods<R, TCS, G>(
 	top_pool_author: Arc<R>,
+	shard_routes: Arc<ShardRoutes>,
 	mut io_handler: IoHandler,
 ) -> IoHandler
 where
@@ -47,10 +53,15 @@ where
 	G: PartialEq + Encode + Decode + Debug + Send + Sync + 'static,
 {
 	let watch_author = top_pool_author.clone();
+	let watch_shard_routes = shard_routes.clone();
 	io_handler.add_sync_method("author_submitAndWatchExtrinsic", move |params: Params| {
 		debug!("worker_api_direct rpc was called: author_submitAndWatchExtrinsic");
-		let json_value = match author_submit_extrinsic_inner(watch_author.clone(), params) {
-			Ok(hash_value) => RpcReturnValue {
+		let json_value = match author_submit_extrinsic_inner(
+			watch_author.clone(),
+			watch_shard_routes.as_ref(),
+			params,
+		)? {
+			Routed::Served(hash_value) => RpcReturnValue {
 				do_watch: true,
 				value: hash_value.encode(),
 				status: DirectRequestStatus::TrustedOperationStatus(
@@ -58,7 +69,7 @@ where
 				),
 			}
 			.to_hex(),
-			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+			Routed::Redirected(route) => compute_hex_encoded_redirect(&route),
 		};
 		Ok(json!(json_value))
 	});
@@ -66,8 +77,12 @@ where
 	let submit_author = top_pool_author.clone();
 	io_handler.add_sync_method("author_submitExtrinsic", move |params: Params| {
 		debug!("worker_api_direct rpc was called: author_submitExtrinsic");
-		let json_value = match author_submit_extrinsic_inner(submit_author.clone(), params) {
-			Ok(hash_value) => RpcReturnValue {
+		let json_value = match author_submit_extrinsic_inner(
+			submit_author.clone(),
+			shard_routes.as_ref(),
+			params,
+		)? {
+			Routed::Served(hash_value) => RpcReturnValue {
 				do_watch: false,
 				value: hash_value.encode(),
 				status: DirectRequestStatus::TrustedOperationStatus(
@@ -75,7 +90,7 @@ where
 				),
 			}
 			.to_hex(),
-			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+			Routed::Redirected(route) => compute_hex_encoded_redirect(&route),
 		};
 		Ok(json!(json_value))
 	});
@@ -83,71 +98,36 @@ where
 	let pending_author = top_pool_author.clone();
 	io_handler.add_sync_method("author_pendingExtrinsics", move |params: Params| {
 		debug!("worker_api_direct rpc was called: author_pendingExtrinsics");
-		match params.parse::<Vec<String>>() {
-			Ok(shards) => {
-				let mut retrieved_operations = vec![];
-				for shard_base58 in shards.iter() {
-					let shard = match decode_shard_from_base58(shard_base58.as_str()) {
-						Ok(id) => id,
-						Err(msg) => {
-							let error_msg: String =
-								format!("Could not retrieve pending calls due to: {}", msg);
-							return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-						},
-					};
-					if let Ok(vec_of_operations) = pending_author.pending_tops(shard) {
-						retrieved_operations.push(vec_of_operations);
-					}
-				}
-				let json_value = RpcReturnValue {
-					do_watch: false,
-					value: retrieved_operations.encode(),
-					status: DirectRequestStatus::Ok,
-				};
-				Ok(json!(json_value.to_hex()))
-			},
-			Err(e) => {
-				let error_msg: String = format!("Could not retrieve pending calls due to: {}", e);
-				Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-			},
+		let shards = params.parse::<Vec<String>>().map_err(invalid_request)?;
+		let mut retrieved_operations = vec![];
+		for shard_base58 in shards.iter() {
+			let shard = decode_shard_from_base58(shard_base58.as_str()).map_err(invalid_request)?;
+			if let Ok(vec_of_operations) = pending_author.pending_tops(shard) {
+				retrieved_operations.push(vec_of_operations);
+			}
 		}
+		let json_value = RpcReturnValue {
+			do_watch: false,
+			value: retrieved_operations.encode(),
+			status: DirectRequestStatus::Ok,
+		};
+		Ok(json!(json_value.to_hex()))
 	});
 
 	let pending_author = top_pool_author;
 	io_handler.add_sync_method("author_pendingTrustedCallsFor", move |params: Params| {
 		debug!("worker_api_direct rpc was called: author_pendingTrustedCallsFor");
-		match params.parse::<(String, String)>() {
-			Ok((shard_base58, account_hex)) => {
-				let shard = match decode_shard_from_base58(shard_base58.as_str()) {
-					Ok(id) => id,
-					Err(msg) => {
-						let error_msg: String =
-							format!("Could not retrieve pending trusted calls due to: {}", msg);
-						return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-					},
-				};
-				let account = match AccountId::from_hex(account_hex.as_str()) {
-					Ok(acc) => acc,
-					Err(msg) => {
-						let error_msg: String =
-							format!("Could not retrieve pending trusted calls due to: {:?}", msg);
-						return Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-					},
-				};
-				let trusted_calls = pending_author.get_pending_trusted_calls_for(shard, &account);
-				let json_value = RpcReturnValue {
-					do_watch: false,
-					value: trusted_calls.encode(),
-					status: DirectRequestStatus::Ok,
-				};
-				Ok(json!(json_value.to_hex()))
-			},
-			Err(e) => {
-				let error_msg: String =
-					format!("Could not retrieve pending trusted calls due to: {}", e);
-				Ok(json!(compute_hex_encoded_return_error(error_msg.as_str())))
-			},
-		}
+		let (shard_base58, account_hex) =
+			params.parse::<(String, String)>().map_err(invalid_request)?;
+		let shard = decode_shard_from_base58(shard_base58.as_str()).map_err(invalid_request)?;
+		let account = AccountId::from_hex(account_hex.as_str()).map_err(invalid_request)?;
+		let trusted_calls = pending_author.get_pending_trusted_calls_for(shard, &account);
+		let json_value = RpcReturnValue {
+			do_watch: false,
+			value: trusted_calls.encode(),
+			status: DirectRequestStatus::Ok,
+		};
+		Ok(json!(json_value.to_hex()))
 	});
 
 	io_handler
@@ -166,11 +146,29 @@ fn decode_shard_from_base58(shard_base58: &str) -> Result<ShardIdentifier, Strin
 	Ok(shard)
 }
 
-fn compute_hex_encoded_return_error(error_msg: &str) -> String {
-	RpcReturnValue::from_error_message(error_msg).to_hex()
+/// Malformed parameters of a request.
+fn invalid_request<E: Debug>(error: E) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(error_code::RPC_ERROR),
+		message: "Rpc error".to_owned(),
+		data: Some(format!("{:?}", error).into()),
+	}
+}
+
+/// The pool rejected the trusted operation.
+fn pool_error(error: RpcError) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(error_code::POOL_ERROR),
+		message: "Pool error".to_owned(),
+		data: Some(format!("{:?}", error).into()),
+	}
 }
 
-fn author_submit_extrinsic_inner<R, TCS, G>(author: Arc<R>, params: Params) -> Result<Hash, String>
+fn author_submit_extrinsic_inner<R, TCS, G>(
+	author: Arc<R>,
+	shard_routes: &ShardRoutes,
+	params: Params,
+) -> Result<Routed<Hash>, RpcError>
 where
 	R: AuthorApi<Hash, Hash, TCS, G> + Send + Sync + 'static,
 	TCS: PartialEq + Encode + Decode + Debug + Send + Sync + 'static,
@@ -178,20 +176,21 @@ where
 {
 	debug!("Author submit and watch trusted operation..");
 
-	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(invalid_request)?;
 
-	let request =
-		Request::from_hex(&hex_encoded_params[0].clone()).map_err(|e| format!("{:?}", e))?;
+	let request = Request::from_hex(&hex_encoded_params[0].clone()).map_err(invalid_request)?;
 
 	let shard: ShardIdentifier = request.shard;
 	let encrypted_trusted_call: Vec<u8> = request.cyphertext;
-	let result = async { author.watch_top(encrypted_trusted_call, shard).await };
-	let response: Result<Hash, RpcError> = executor::block_on(result);
+	shard_routes.serve_or_redirect(&shard, || {
+		let result = async { author.watch_top(encrypted_trusted_call, shard).await };
+		let response: Result<Hash, RpcError> = executor::block_on(result);
 
-	match &response {
-		Ok(h) => debug!("Trusted operation submitted successfully ({:?})", h),
-		Err(e) => warn!("Submitting trusted operation failed: {:?}", e),
-	}
+		match &response {
+			Ok(h) => debug!("Trusted operation submitted successfully ({:?})", h),
+			Err(e) => warn!("Submitting trusted operation failed: {:?}", e),
+		}
 
-	response.map_err(|e| format!("{:?}", e))
+		response.map_err(pool_error)
+	})
 }
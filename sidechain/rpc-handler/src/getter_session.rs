@@ -0,0 +1,240 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Short-lived getter sessions of polling clients.
+//!
+//! A client opens a session with a signed authorization and presents the enclave-issued token
+//! with the subsequent getters of the same account, instead of signing each of them. A session
+//! expires with its authorization, at a sidechain block of the shard.
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::rpc_authorization::check_authorization;
+use codec::{Decode, Encode};
+use itp_types::{
+	rpc_authorization::{AuthorizedRpc, SignedRpcAuthorization},
+	AccountId, ShardIdentifier,
+};
+use std::collections::BTreeMap;
+
+/// Maximum number of sidechain blocks a getter session lasts.
+pub const MAX_GETTER_SESSION_BLOCKS: u64 = 900;
+
+/// Maximum number of open sessions of an account on a shard.
+pub const MAX_GETTER_SESSIONS_PER_ACCOUNT: usize = 8;
+
+/// Maximum number of open sessions, which bounds the memory of the sessions.
+pub const MAX_GETTER_SESSIONS: usize = 10_000;
+
+pub type GetterSessionToken = [u8; 32];
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct GetterSession {
+	pub token: GetterSessionToken,
+	/// Sidechain block of the shard at which the session expires.
+	pub expires_at_block: u64,
+}
+
+struct OpenSession {
+	shard: ShardIdentifier,
+	account: AccountId,
+	/// Nonce of the authorization the session was opened with.
+	authorization_nonce: [u8; 32],
+	expires_at_block: u64,
+}
+
+#[derive(Default)]
+pub struct GetterSessions {
+	sessions: RwLock<BTreeMap<GetterSessionToken, OpenSession>>,
+}
+
+impl GetterSessions {
+	/// Opens a session for the account of the authorization. The token must be random and
+	/// `current_block` the last sidechain block of the authorization's shard.
+	pub fn open(
+		&self,
+		token: GetterSessionToken,
+		signed_authorization: &SignedRpcAuthorization,
+		current_block: u64,
+	) -> Result<GetterSession, &'static str> {
+		let authorization = check_authorization(
+			signed_authorization,
+			AuthorizedRpc::OpenGetterSession,
+			current_block,
+			MAX_GETTER_SESSION_BLOCKS,
+		)?;
+
+		let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+		sessions.retain(|_, session| {
+			session.shard != authorization.shard || session.expires_at_block > current_block
+		});
+		// A session lasts as long as its authorization, so its nonce is known as long as the
+		// authorization could be replayed.
+		if sessions
+			.values()
+			.any(|session| session.authorization_nonce == authorization.nonce)
+		{
+			return Err("Authorization has already been used")
+		}
+		let sessions_of_account = sessions
+			.values()
+			.filter(|session| {
+				session.shard == authorization.shard && session.account == authorization.account
+			})
+			.count();
+		if sessions_of_account >= MAX_GETTER_SESSIONS_PER_ACCOUNT {
+			return Err("Too many open getter sessions of the account")
+		}
+		if sessions.len() >= MAX_GETTER_SESSIONS {
+			return Err("Too many open getter sessions")
+		}
+		sessions.insert(
+			token,
+			OpenSession {
+				shard: authorization.shard,
+				account: authorization.account.clone(),
+				authorization_nonce: authorization.nonce,
+				expires_at_block: authorization.expires_at_block,
+			},
+		);
+		Ok(GetterSession { token, expires_at_block: authorization.expires_at_block })
+	}
+
+	/// Whether the token belongs to an unexpired session of the account on the shard.
+	pub fn is_authorized(
+		&self,
+		token: &GetterSessionToken,
+		shard: &ShardIdentifier,
+		account: &AccountId,
+		current_block: u64,
+	) -> bool {
+		let sessions = self.sessions.read().unwrap_or_else(|e| e.into_inner());
+		sessions.get(token).map_or(false, |session| {
+			&session.shard == shard
+				&& &session.account == account
+				&& session.expires_at_block > current_block
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_types::rpc_authorization::RpcAuthorization;
+	use sp_core::{sr25519, Pair};
+
+	fn authorization(
+		signer: &sr25519::Pair,
+		rpc: AuthorizedRpc,
+		nonce: u8,
+		expires_at_block: u64,
+	) -> SignedRpcAuthorization {
+		let authorization = RpcAuthorization {
+			rpc,
+			shard: ShardIdentifier::repeat_byte(1),
+			account: signer.public().into(),
+			nonce: [nonce; 32],
+			expires_at_block,
+		};
+		let signature = signer.sign(&authorization.encode()).into();
+		SignedRpcAuthorization { authorization, signature }
+	}
+
+	#[test]
+	fn session_authorizes_only_its_account_until_it_expires() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let alice_account: AccountId = alice.public().into();
+		let bob = AccountId::from([2u8; 32]);
+		let sessions = GetterSessions::default();
+
+		let session = sessions
+			.open([7u8; 32], &authorization(&alice, AuthorizedRpc::OpenGetterSession, 1, 20), 10)
+			.unwrap();
+
+		assert_eq!(session.expires_at_block, 20);
+		assert!(sessions.is_authorized(&[7u8; 32], &shard, &alice_account, 19));
+		assert!(!sessions.is_authorized(&[7u8; 32], &shard, &bob, 19));
+		assert!(!sessions.is_authorized(&[8u8; 32], &shard, &alice_account, 19));
+		assert!(!sessions.is_authorized(
+			&[7u8; 32],
+			&ShardIdentifier::repeat_byte(2),
+			&alice_account,
+			19
+		));
+		assert!(!sessions.is_authorized(&[7u8; 32], &shard, &alice_account, 20));
+	}
+
+	#[test]
+	fn authorization_opens_only_one_session() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let sessions = GetterSessions::default();
+		let signed_authorization = authorization(&alice, AuthorizedRpc::OpenGetterSession, 1, 20);
+
+		assert!(sessions.open([7u8; 32], &signed_authorization, 10).is_ok());
+		assert!(sessions.open([8u8; 32], &signed_authorization, 11).is_err());
+	}
+
+	#[test]
+	fn invalid_authorizations_are_rejected() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let sessions = GetterSessions::default();
+		let mut forged = authorization(&alice, AuthorizedRpc::OpenGetterSession, 1, 20);
+		forged.authorization.account = AccountId::from([2u8; 32]);
+
+		for (signed_authorization, current_block) in [
+			(authorization(&alice, AuthorizedRpc::ReserveNonce, 1, 20), 10),
+			(authorization(&alice, AuthorizedRpc::OpenGetterSession, 2, 10), 10),
+			(
+				authorization(
+					&alice,
+					AuthorizedRpc::OpenGetterSession,
+					3,
+					11 + MAX_GETTER_SESSION_BLOCKS,
+				),
+				10,
+			),
+			(forged, 10),
+		] {
+			assert!(sessions.open([7u8; 32], &signed_authorization, current_block).is_err());
+		}
+	}
+
+	#[test]
+	fn sessions_are_capped_per_account() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let bob = sr25519::Pair::from_seed(&[2u8; 32]);
+		let sessions = GetterSessions::default();
+		for nonce in 0..MAX_GETTER_SESSIONS_PER_ACCOUNT as u8 {
+			let signed_authorization =
+				authorization(&alice, AuthorizedRpc::OpenGetterSession, nonce, 20);
+			assert!(sessions.open([nonce; 32], &signed_authorization, 10).is_ok());
+		}
+
+		let one_too_many = authorization(&alice, AuthorizedRpc::OpenGetterSession, 100, 20);
+		assert!(sessions.open([100; 32], &one_too_many, 10).is_err());
+		let of_bob = authorization(&bob, AuthorizedRpc::OpenGetterSession, 101, 20);
+		assert!(sessions.open([101; 32], &of_bob, 10).is_ok());
+		// The sessions of alice have expired.
+		let after_expiry = authorization(&alice, AuthorizedRpc::OpenGetterSession, 102, 30);
+		assert!(sessions.open([102; 32], &after_expiry, 20).is_ok());
+	}
+}
@@ -31,6 +31,11 @@ pub mod sgx_reexport_prelude {
 	pub use rust_base58_sgx as base58;
 }
 
+pub mod account_watch;
 pub mod constants;
 pub mod direct_top_pool_api;
+pub mod getter_session;
 pub mod import_block_api;
+pub mod nonce_reservation;
+pub mod rpc_authorization;
+pub mod shard_routing;
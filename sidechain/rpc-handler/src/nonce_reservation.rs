@@ -0,0 +1,166 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Short-lived nonce reservations of the clients submitting trusted calls.
+//!
+//! Concurrent processes of the same client each reserve a nonce with a signed authorization
+//! before they submit a call, so they don't pick the same one. A reservation expires with its
+//! authorization, at a sidechain block of the shard, if no call with its nonce shows up.
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::rpc_authorization::check_authorization;
+use itp_types::{
+	rpc_authorization::{AuthorizedRpc, SignedRpcAuthorization},
+	AccountId, Index, ShardIdentifier,
+};
+use std::{collections::BTreeMap, vec::Vec};
+
+/// Maximum number of sidechain blocks a nonce stays reserved.
+pub const MAX_NONCE_RESERVATION_BLOCKS: u64 = 30;
+
+/// Maximum number of nonces an account can have reserved on a shard at once.
+pub const MAX_NONCE_RESERVATIONS_PER_ACCOUNT: usize = 16;
+
+#[derive(Default)]
+struct Reservations {
+	/// Reserved nonces of every account, with the block they expire at.
+	reserved: BTreeMap<(ShardIdentifier, AccountId), Vec<(Index, u64)>>,
+	/// Nonces of the accepted authorizations, with their shard and the block they expire at.
+	/// A reservation can be released before its authorization expires, so they are kept apart.
+	used_authorizations: BTreeMap<[u8; 32], (ShardIdentifier, u64)>,
+}
+
+#[derive(Default)]
+pub struct NonceReservations {
+	reservations: RwLock<Reservations>,
+}
+
+impl NonceReservations {
+	/// Reserves the lowest nonce from `next_free` on that is not reserved yet, for the account
+	/// of the authorization. `current_block` is the last sidechain block of its shard.
+	///
+	/// `next_free` is the next nonce of the account that is neither in its state nor used by one
+	/// of its pending calls. Returns the nonce and the block its reservation expires at.
+	pub fn reserve(
+		&self,
+		signed_authorization: &SignedRpcAuthorization,
+		next_free: Index,
+		current_block: u64,
+	) -> Result<(Index, u64), &'static str> {
+		let authorization = check_authorization(
+			signed_authorization,
+			AuthorizedRpc::ReserveNonce,
+			current_block,
+			MAX_NONCE_RESERVATION_BLOCKS,
+		)?;
+		let shard = authorization.shard;
+
+		let mut reservations = self.reservations.write().unwrap_or_else(|e| e.into_inner());
+		let Reservations { reserved: table, used_authorizations } = &mut *reservations;
+		used_authorizations
+			.retain(|_, (used_shard, expiry)| *used_shard != shard || *expiry > current_block);
+		if used_authorizations.contains_key(&authorization.nonce) {
+			return Err("Authorization has already been used")
+		}
+		// Drop the expired reservations of the shard, so the table doesn't grow.
+		table.retain(|(reserved_shard, _), reserved| {
+			reserved.retain(|(_, expiry)| *reserved_shard != shard || *expiry > current_block);
+			!reserved.is_empty()
+		});
+
+		let reserved = table.entry((shard, authorization.account.clone())).or_default();
+		reserved.retain(|(nonce, _)| *nonce >= next_free);
+		if reserved.len() >= MAX_NONCE_RESERVATIONS_PER_ACCOUNT {
+			return Err("Too many reserved nonces of the account")
+		}
+		let mut nonce = next_free;
+		while reserved.iter().any(|(reserved_nonce, _)| *reserved_nonce == nonce) {
+			nonce = nonce.saturating_add(1);
+		}
+		let expiry = authorization.expires_at_block;
+		reserved.push((nonce, expiry));
+		used_authorizations.insert(authorization.nonce, (shard, expiry));
+		Ok((nonce, expiry))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use itp_types::rpc_authorization::RpcAuthorization;
+	use sp_core::{sr25519, Pair};
+
+	fn authorization(
+		signer: &sr25519::Pair,
+		nonce: u8,
+		expires_at_block: u64,
+	) -> SignedRpcAuthorization {
+		let authorization = RpcAuthorization {
+			rpc: AuthorizedRpc::ReserveNonce,
+			shard: ShardIdentifier::repeat_byte(1),
+			account: signer.public().into(),
+			nonce: [nonce; 32],
+			expires_at_block,
+		};
+		let signature = signer.sign(&authorization.encode()).into();
+		SignedRpcAuthorization { authorization, signature }
+	}
+
+	#[test]
+	fn concurrent_reservations_get_distinct_nonces_until_they_expire() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let bob = sr25519::Pair::from_seed(&[2u8; 32]);
+		let reservations = NonceReservations::default();
+
+		assert_eq!(reservations.reserve(&authorization(&alice, 1, 20), 3, 10), Ok((3, 20)));
+		assert_eq!(reservations.reserve(&authorization(&alice, 2, 20), 3, 11), Ok((4, 20)));
+		assert_eq!(reservations.reserve(&authorization(&bob, 3, 20), 3, 12), Ok((3, 20)));
+		// The call with nonce 3 has been submitted.
+		assert_eq!(reservations.reserve(&authorization(&alice, 4, 30), 4, 13), Ok((5, 30)));
+		assert_eq!(reservations.reserve(&authorization(&alice, 5, 40), 4, 20), Ok((4, 40)));
+	}
+
+	#[test]
+	fn authorization_reserves_only_one_nonce() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let reservations = NonceReservations::default();
+		let signed_authorization = authorization(&alice, 1, 20);
+
+		assert!(reservations.reserve(&signed_authorization, 3, 10).is_ok());
+		// Even once the reserved nonce has been used.
+		assert!(reservations.reserve(&signed_authorization, 4, 11).is_err());
+	}
+
+	#[test]
+	fn reservations_are_capped_per_account() {
+		let alice = sr25519::Pair::from_seed(&[1u8; 32]);
+		let reservations = NonceReservations::default();
+		for nonce in 0..MAX_NONCE_RESERVATIONS_PER_ACCOUNT as u8 {
+			assert!(reservations.reserve(&authorization(&alice, nonce, 20), 0, 10).is_ok());
+		}
+
+		assert!(reservations.reserve(&authorization(&alice, 100, 20), 0, 10).is_err());
+		// Submitted calls release their reservations.
+		assert!(reservations.reserve(&authorization(&alice, 101, 20), 1, 10).is_ok());
+	}
+}
@@ -0,0 +1,46 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Checks of the signed authorizations that getter sessions and nonce reservations are opened
+//! with. The replays are rejected by the registries, which keep the nonces of the
+//! authorizations they accepted until these expire.
+
+use itp_types::rpc_authorization::{AuthorizedRpc, RpcAuthorization, SignedRpcAuthorization};
+
+/// Returns the authorization if it is signed by its account, for the RPC and unexpired at the
+/// last sidechain block of its shard, with an expiry at most `max_blocks` ahead.
+pub fn check_authorization(
+	signed_authorization: &SignedRpcAuthorization,
+	rpc: AuthorizedRpc,
+	current_block: u64,
+	max_blocks: u64,
+) -> Result<&RpcAuthorization, &'static str> {
+	let authorization = &signed_authorization.authorization;
+	if authorization.rpc != rpc {
+		return Err("Authorization is for another RPC")
+	}
+	if authorization.expires_at_block <= current_block {
+		return Err("Authorization has expired")
+	}
+	if authorization.expires_at_block > current_block.saturating_add(max_blocks) {
+		return Err("Authorization expires too late")
+	}
+	if !signed_authorization.verify_signature() {
+		return Err("Invalid authorization signature")
+	}
+	Ok(authorization)
+}
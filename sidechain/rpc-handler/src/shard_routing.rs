@@ -0,0 +1,106 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Routing table of the shards served by other worker instances of a multi-worker deployment.
+//!
+//! Requests for such a shard are not served, but answered with a redirect to the trusted RPC
+//! url of the worker instance serving it.
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use codec::Encode;
+use itp_rpc::RpcReturnValue;
+use itp_types::{shard_routing::ShardRoute, DirectRequestStatus, ShardIdentifier};
+use itp_utils::ToHexPrefixed;
+use log::*;
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Outcome of a request for a shard that may be served by another worker instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Routed<T> {
+	Served(T),
+	Redirected(ShardRoute),
+}
+
+#[derive(Default)]
+pub struct ShardRoutes {
+	routes: RwLock<BTreeMap<ShardIdentifier, Vec<u8>>>,
+}
+
+impl ShardRoutes {
+	/// Replaces the whole routing table.
+	pub fn set_routes(&self, routes: Vec<ShardRoute>) {
+		info!("Routing {} shards to other worker instances", routes.len());
+		let mut table = self.routes.write().unwrap_or_else(|e| e.into_inner());
+		*table = routes.into_iter().map(|route| (route.shard, route.url)).collect();
+	}
+
+	/// Route to the worker instance serving the shard, none if it is served by this worker.
+	pub fn route(&self, shard: &ShardIdentifier) -> Option<ShardRoute> {
+		let table = self.routes.read().unwrap_or_else(|e| e.into_inner());
+		table.get(shard).map(|url| ShardRoute { shard: *shard, url: url.clone() })
+	}
+
+	/// Serves the request with `serve` unless the shard is routed to another worker instance.
+	pub fn serve_or_redirect<T, E>(
+		&self,
+		shard: &ShardIdentifier,
+		serve: impl FnOnce() -> Result<T, E>,
+	) -> Result<Routed<T>, E> {
+		match self.route(shard) {
+			Some(route) => {
+				debug!("Redirecting request for shard {:?}", shard);
+				Ok(Routed::Redirected(route))
+			},
+			None => serve().map(Routed::Served),
+		}
+	}
+}
+
+/// Hex encoded [`RpcReturnValue`] redirecting the client to another worker instance.
+pub fn compute_hex_encoded_redirect(route: &ShardRoute) -> String {
+	RpcReturnValue::new(route.encode(), false, DirectRequestStatus::Redirect).to_hex()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_routed_shards_are_redirected() {
+		let routed_shard = ShardIdentifier::repeat_byte(1);
+		let route = ShardRoute { shard: routed_shard, url: b"wss://worker-2:2000".to_vec() };
+		let shard_routes = ShardRoutes::default();
+		shard_routes.set_routes(vec![route.clone()]);
+
+		assert_eq!(
+			shard_routes.serve_or_redirect(&routed_shard, || Ok::<_, ()>(1)),
+			Ok(Routed::Redirected(route))
+		);
+		assert_eq!(
+			shard_routes.serve_or_redirect(&ShardIdentifier::repeat_byte(2), || Ok::<_, ()>(1)),
+			Ok(Routed::Served(1))
+		);
+
+		shard_routes.set_routes(Vec::new());
+		assert_eq!(shard_routes.route(&routed_shard), None);
+	}
+}
@@ -31,4 +31,6 @@ pub enum Error {
 	Decode(#[from] codec::Error),
 	#[error("Given block is not a successor of the last known block")]
 	HeaderAncestryMismatch,
+	#[error("Could not export the blocks: {0}")]
+	Export(String),
 }
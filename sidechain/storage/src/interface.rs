@@ -21,12 +21,13 @@ use mockall::predicate::*;
 use mockall::*;
 
 use super::{storage::SidechainStorage, Result};
+use itp_utils::stream_encoder::WriteChunk;
 use its_primitives::{
 	traits::{ShardIdentifierFor, SignedBlock as SignedBlockT},
 	types::{BlockHash, BlockNumber},
 };
 use parking_lot::RwLock;
-use std::path::PathBuf;
+use std::{fmt::Debug, path::PathBuf};
 
 /// Lock wrapper around sidechain storage
 pub struct SidechainStorageLock<SignedBlock: SignedBlockT> {
@@ -39,6 +40,29 @@ impl<SignedBlock: SignedBlockT> SidechainStorageLock<SignedBlock> {
 			storage: RwLock::new(SidechainStorage::<SignedBlock>::load_from_base_path(path)?),
 		})
 	}
+
+	/// Moves the pruned blocks to the cold storage path, see [`SidechainStorage::with_cold_archive`].
+	pub fn from_base_path_with_cold_archive(
+		path: PathBuf,
+		archive_path: PathBuf,
+	) -> Result<SidechainStorageLock<SignedBlock>> {
+		let storage = SidechainStorage::<SignedBlock>::load_from_base_path(path)?
+			.with_cold_archive(archive_path)?;
+		Ok(SidechainStorageLock { storage: RwLock::new(storage) })
+	}
+
+	/// See [`SidechainStorage::export_blocks`].
+	pub fn export_blocks<W: WriteChunk>(
+		&self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		writer: W,
+		chunk_size: usize,
+	) -> Result<u64>
+	where
+		W::Error: Debug,
+	{
+		self.storage.read().export_blocks(shard, writer, chunk_size)
+	}
 }
 
 /// Storage interface Trait
@@ -18,6 +18,7 @@
 use super::{db::SidechainDB, Error, Result};
 use codec::{Decode, Encode};
 use itp_settings::files::SIDECHAIN_STORAGE_PATH;
+use itp_utils::stream_encoder::{StreamEncoder, WriteChunk};
 use its_primitives::{
 	traits::{Block as BlockTrait, Header as HeaderTrait, SignedBlock as SignedBlockT},
 	types::{BlockHash, BlockNumber},
@@ -55,6 +56,8 @@ pub struct SidechainStorage<SignedBlock: SignedBlockT> {
 	shards: Vec<ShardIdentifierFor<SignedBlock>>,
 	/// map to last sidechain block of every shard
 	last_blocks: HashMap<ShardIdentifierFor<SignedBlock>, LastSidechainBlock>,
+	/// cold storage the pruned blocks are moved to, if any
+	archive: Option<SidechainDB>,
 }
 
 impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
@@ -64,7 +67,8 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 	pub fn load_from_base_path(base_path: PathBuf) -> Result<SidechainStorage<SignedBlock>> {
 		// load db
 		let db = SidechainDB::open_default(base_path.join(SIDECHAIN_STORAGE_PATH))?;
-		let mut storage = SidechainStorage { db, shards: vec![], last_blocks: HashMap::new() };
+		let mut storage =
+			SidechainStorage { db, shards: vec![], last_blocks: HashMap::new(), archive: None };
 		storage.shards = storage.load_shards_from_db()?;
 		// get last block of each shard
 		for shard in storage.shards.iter() {
@@ -78,6 +82,14 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 		Ok(storage)
 	}
 
+	/// Moves pruned blocks to the DB at the given cold storage path instead of dropping them.
+	///
+	/// Blocks that are no longer found in the local DB are looked up in the archive.
+	pub fn with_cold_archive(mut self, archive_path: PathBuf) -> Result<Self> {
+		self.archive = Some(SidechainDB::open_default(archive_path.join(SIDECHAIN_STORAGE_PATH))?);
+		Ok(self)
+	}
+
 	/// gets all shards of currently loaded sidechain db
 	pub fn shards(&self) -> &Vec<ShardIdentifierFor<SignedBlock>> {
 		&self.shards
@@ -103,7 +115,42 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 	/// gets the block of the given blockhash, if there is such a block
 	#[allow(unused)]
 	pub fn get_block(&self, block_hash: &BlockHash) -> Result<Option<SignedBlock>> {
-		self.db.get(block_hash)
+		match (self.db.get(block_hash)?, &self.archive) {
+			(None, Some(archive)) => archive.get(block_hash),
+			(block, _) => Ok(block),
+		}
+	}
+
+	/// Writes the stored blocks of the shard, from the local DB and the archive, to the chunk
+	/// writer in chunks of at most `chunk_size` bytes, oldest first. The blocks are SCALE encoded
+	/// one after the other and only one of them is held in memory at a time.
+	///
+	/// Returns the number of exported blocks.
+	pub fn export_blocks<W: WriteChunk>(
+		&self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		writer: W,
+		chunk_size: usize,
+	) -> Result<u64>
+	where
+		W::Error: Debug,
+	{
+		let last_block = self.get_last_block_of_shard(shard)?;
+		let mut encoder = StreamEncoder::new(writer, chunk_size);
+		let mut exported_blocks = 0;
+		for block_number in 0..=last_block.number {
+			let block_hash = match (self.get_block_hash(shard, block_number)?, &self.archive) {
+				(None, Some(archive)) => archive.get((*shard, block_number))?,
+				(block_hash, _) => block_hash,
+			};
+			if let Some(block) = block_hash.map(|hash| self.get_block(&hash)).transpose()?.flatten()
+			{
+				block.encode_to(&mut encoder);
+				exported_blocks += 1;
+			}
+		}
+		encoder.finish().map_err(|e| Error::Export(format!("{:?}", e)))?;
+		Ok(exported_blocks)
 	}
 
 	/// Get all blocks after (i.e. children of) a specified block.
@@ -217,6 +264,7 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 	) -> Result<()> {
 		let last_block = self.get_last_block_of_shard(shard)?;
 		trace!("pruning sidechain blocks older than {} for shard {:?}", block_number, shard);
+		self.archive_blocks_up_to(shard, block_number)?;
 		if last_block.number == block_number {
 			// given block number is last block of chain - purge whole shard
 			info!(
@@ -293,6 +341,31 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 		true
 	}
 
+	/// Copies the blocks of the shard up to the given block number to the archive, if there is one.
+	fn archive_blocks_up_to(
+		&mut self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+	) -> Result<()> {
+		let archive = match self.archive.as_mut() {
+			Some(archive) => archive,
+			None => return Ok(()),
+		};
+		let mut batch = WriteBatch::default();
+		let mut current_block_number = block_number;
+		while let Some(block_hash) = self.db.get::<_, BlockHash>((*shard, current_block_number))? {
+			if let Some(block) = self.db.get::<_, SignedBlock>(block_hash)? {
+				SidechainDB::add_to_batch(&mut batch, block_hash, block);
+				SidechainDB::add_to_batch(&mut batch, (*shard, current_block_number), block_hash);
+			}
+			if current_block_number == 0 {
+				break
+			}
+			current_block_number -= 1;
+		}
+		archive.write(batch)
+	}
+
 	/// Implementations of helper functions, not meant for pub use
 	/// gets the previous block of given shard and block number, if there is one.
 	fn get_previous_block(
@@ -1090,6 +1163,66 @@ mod test {
 		}
 	}
 
+	#[test]
+	fn pruned_blocks_are_fetched_from_the_cold_archive() {
+		let temp_dir = create_temp_dir();
+		let archive_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=4).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf())
+			.with_cold_archive(archive_dir.path().to_path_buf())
+			.unwrap();
+		for block in blocks.iter() {
+			sidechain_db.store_blocks(vec![block.clone()]).unwrap();
+		}
+
+		sidechain_db.prune_shards(1);
+
+		assert!(sidechain_db.get_block_hash(&shard, 2).unwrap().is_none());
+		assert_eq!(sidechain_db.get_block(&blocks[1].hash()).unwrap().unwrap(), blocks[1]);
+		assert_eq!(sidechain_db.get_blocks_after(&blocks[0].hash(), &shard).unwrap(), blocks[1..]);
+	}
+
+	#[derive(Default)]
+	struct ChunkCollector {
+		chunks: Vec<Vec<u8>>,
+	}
+
+	impl WriteChunk for &mut ChunkCollector {
+		type Error = ();
+
+		fn write_chunk(&mut self, chunk: &[u8]) -> std::result::Result<(), ()> {
+			self.chunks.push(chunk.to_vec());
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn exported_blocks_include_the_archived_blocks_in_bounded_chunks() {
+		let temp_dir = create_temp_dir();
+		let archive_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=4).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf())
+			.with_cold_archive(archive_dir.path().to_path_buf())
+			.unwrap();
+		for block in blocks.iter() {
+			sidechain_db.store_blocks(vec![block.clone()]).unwrap();
+		}
+		sidechain_db.prune_shards(1);
+		let mut collector = ChunkCollector::default();
+
+		assert_eq!(sidechain_db.export_blocks(&shard, &mut collector, 100).unwrap(), 4);
+
+		assert!(collector.chunks.iter().all(|chunk| chunk.len() <= 100));
+		let exported = collector.chunks.concat();
+		let mut input = exported.as_slice();
+		let exported_blocks: Vec<SignedBlock> =
+			(0..4).map(|_| SignedBlock::decode(&mut input).unwrap()).collect();
+		assert_eq!(exported_blocks, blocks);
+		assert!(input.is_empty());
+	}
+
 	#[test]
 	fn prune_shards_works_for_multiple_shards() {
 		let temp_dir = create_temp_dir();